@@ -6,7 +6,7 @@ use fake::{
   Fake,
 };
 use rust_decimal::Decimal;
-use sqlx::{postgres::Postgres, Decode, FromRow, Pool};
+use sqlx::{postgres::Postgres, FromRow, Pool};
 
 #[derive(Debug, FromRow)]
 struct Attraction {
@@ -38,6 +38,7 @@ impl Attraction {
   }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, FromRow)]
 struct AttractionWithType {
   attraction_id: i32,
@@ -50,6 +51,7 @@ struct AttractionWithType {
   type_description: String,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, FromRow)]
 struct AttractionRating {
   id: i32,
@@ -84,8 +86,8 @@ async fn generate_attractions(pool: &Pool<Postgres>) {
     )
     .bind(rnd_attraction.description.to_string())
     .bind(rnd_attraction.city_id)
-    .bind(rnd_attraction.latitude.to_string())
-    .bind(rnd_attraction.longitude.to_string())
+    .bind(rnd_attraction.latitude)
+    .bind(rnd_attraction.longitude)
     .bind(rnd_attraction.attraction_type_id)
     .fetch_one(pool)
     .await
@@ -95,9 +97,9 @@ async fn generate_attractions(pool: &Pool<Postgres>) {
         //  "Inserted attraction with id: {}",
         //  inserted_attraction.id
         //);
-        generate_rating(&inserted_attraction, &pool).await
+        generate_rating(&inserted_attraction, pool).await
       },
-      Err(e) => println!("Error inserting an attraction: {}", e.to_string()),
+      Err(e) => println!("Error inserting an attraction: {}", e),
     }
   }
 }
@@ -113,7 +115,7 @@ fn random_ratings(an_attraction: &Attraction) -> AttractionRating {
 
 async fn generate_rating(an_attraction: &Attraction, pool: &Pool<Postgres>) {
   for _n in 1..50 {
-    let rnd_rating = random_ratings(&an_attraction);
+    let rnd_rating = random_ratings(an_attraction);
     match sqlx::query_as::<_, AttractionRating>(
       "INSERT INTO attraction_rating (at, attraction_id, rate) VALUES ($1, \
        $2, $3) RETURNING *",
@@ -124,14 +126,15 @@ async fn generate_rating(an_attraction: &Attraction, pool: &Pool<Postgres>) {
     .fetch_one(pool)
     .await
     {
-      Ok(inserted_rating) => {
+      Ok(_inserted_rating) => {
         // println!("Inserted rating with id: {}", inserted_rating.id)
       },
-      Err(e) => println!("Error inserting a rating: {}", e.to_string()),
+      Err(e) => println!("Error inserting a rating: {}", e),
     }
   }
 }
 
+#[allow(dead_code)]
 async fn testing_connection(pool: &Pool<Postgres>) {
   match sqlx::query_as::<_, AttractionWithType>(
     r#"
@@ -145,7 +148,7 @@ async fn testing_connection(pool: &Pool<Postgres>) {
     Ok(attr_type) => {
       dbg!(attr_type);
     }
-    Err(e) => {println!("Error: {}", e.to_string())}
+    Err(e) => {println!("Error: {}", e)}
   }
 }
 
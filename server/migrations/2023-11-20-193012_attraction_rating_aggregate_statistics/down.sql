@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+alter table attraction_rating_aggregate
+    drop column rating_count,
+    drop column min_rate,
+    drop column max_rate,
+    drop column median,
+    drop column standard_deviation,
+    drop column histogram;
@@ -0,0 +1,39 @@
+alter table attraction_rating_aggregate
+    add rating_count       integer not null default 0,
+    add min_rate           decimal not null default 0,
+    add max_rate           decimal not null default 0,
+    add median             decimal not null default 0,
+    add standard_deviation decimal not null default 0,
+    add histogram          integer[] not null default '{}';
+
+-- Backfill the already generated daily aggregates. The histogram uses the same
+-- ten fixed buckets of width 0.1 over [0, 1] that the application uses.
+update attraction_rating_aggregate ara
+set rating_count       = stats.rating_count,
+    min_rate           = stats.min_rate,
+    max_rate           = stats.max_rate,
+    median             = stats.median,
+    standard_deviation = stats.standard_deviation,
+    histogram          = array(
+        select count(ar.id)::integer
+        from generate_series(0, 9) as bucket(idx)
+        left join attraction_rating ar
+            on ar.attraction_id = ara.attraction_id
+            and date_trunc('day', ar.at) = date_trunc('day', ara.at)
+            and least(greatest(floor(ar.rate * 10), 0), 9) = bucket.idx
+        group by bucket.idx
+        order by bucket.idx
+    )
+from (
+    select attraction_id,
+           date_trunc('day', at) as at,
+           count(*)::integer as rating_count,
+           min(rate) as min_rate,
+           max(rate) as max_rate,
+           percentile_cont(0.5) within group (order by rate) as median,
+           stddev_pop(rate) as standard_deviation
+    from attraction_rating
+    group by attraction_id, date_trunc('day', at)
+) stats
+where ara.attraction_id = stats.attraction_id
+  and date_trunc('day', ara.at) = stats.at;
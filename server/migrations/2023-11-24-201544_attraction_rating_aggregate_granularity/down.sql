@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+drop index attraction_rating_aggregate_granularity_idx;
+
+alter table attraction_rating_aggregate
+    drop column granularity;
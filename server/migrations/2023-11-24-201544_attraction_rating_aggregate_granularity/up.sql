@@ -0,0 +1,5 @@
+alter table attraction_rating_aggregate
+    add granularity varchar not null default 'DAY';
+
+create index attraction_rating_aggregate_granularity_idx
+    on attraction_rating_aggregate (attraction_id, granularity, at);
@@ -0,0 +1,16 @@
+-- This file should undo anything in `up.sql`
+alter table attraction_rating_aggregate
+    drop constraint attraction_rating_aggregate_uk;
+
+create index attraction_rating_aggregate_granularity_idx
+    on attraction_rating_aggregate (attraction_id, granularity, at);
+
+alter table attraction_rating_aggregate
+    drop column watermark;
+
+drop trigger attraction_rating_updated_at on attraction_rating;
+
+drop function attraction_rating_touch_updated_at();
+
+alter table attraction_rating
+    drop column updated_at;
@@ -0,0 +1,38 @@
+-- Track when every rating was last written, so a late or corrected rating
+-- moves the watermark of the aggregates that include it.
+alter table attraction_rating
+    add updated_at timestamp not null default clock_timestamp();
+
+create or replace function attraction_rating_touch_updated_at() returns trigger as
+$$
+begin
+    new.updated_at = clock_timestamp();
+    return new;
+end;
+$$ language plpgsql;
+
+create trigger attraction_rating_updated_at
+    before update
+    on attraction_rating
+    for each row
+execute function attraction_rating_touch_updated_at();
+
+-- The latest updated_at of the ratings included in the aggregate. The
+-- aggregates generated before this migration don't have it, so they are
+-- recomputed the next time.
+alter table attraction_rating_aggregate
+    add watermark timestamp;
+
+delete
+from attraction_rating_aggregate duplicated
+    using attraction_rating_aggregate kept
+where duplicated.attraction_id = kept.attraction_id
+  and duplicated.granularity = kept.granularity
+  and duplicated.at = kept.at
+  and duplicated.id < kept.id;
+
+drop index attraction_rating_aggregate_granularity_idx;
+
+alter table attraction_rating_aggregate
+    add constraint attraction_rating_aggregate_uk
+        unique (attraction_id, granularity, at);
@@ -0,0 +1,22 @@
+-- The mergeable summary of the ratings of an attraction in a day. The state
+-- columns record the ratings already included, so the new ones are added
+-- incrementally.
+create table attraction_rating_sketch
+(
+    id             serial
+        constraint attraction_rating_sketch_pk
+            primary key,
+    attraction_id  integer   not null
+        constraint attraction_rating_sketch_attraction_id_fk
+            references attraction,
+    at             timestamp not null,
+    sketch         bytea     not null,
+    rating_count   integer   not null,
+    last_rating_id integer   not null,
+    watermark      timestamp,
+    constraint attraction_rating_sketch_uk
+        unique (attraction_id, at)
+);
+
+alter table attraction_rating_sketch
+    owner to postgres;
@@ -0,0 +1,26 @@
+-- The days where the average or the volume of the ratings of an attraction
+-- deviates strongly from its history.
+create table attraction_rating_anomaly
+(
+    id            serial
+        constraint attraction_rating_anomaly_pk
+            primary key,
+    attraction_id integer   not null
+        constraint attraction_rating_anomaly_attraction_id_fk
+            references attraction,
+    at            timestamp not null,
+    metric        varchar   not null,
+    method        varchar   not null,
+    value         numeric   not null,
+    baseline      numeric   not null,
+    score         numeric   not null,
+    detected_at   timestamp not null default now(),
+    constraint attraction_rating_anomaly_uk
+        unique (attraction_id, at, metric)
+);
+
+alter table attraction_rating_anomaly
+    owner to postgres;
+
+create index attraction_rating_anomaly_at_index
+    on attraction_rating_anomaly (at);
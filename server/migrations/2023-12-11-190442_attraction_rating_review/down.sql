@@ -0,0 +1,12 @@
+-- This file should undo anything in `up.sql`
+drop index attraction_rating_status_index;
+drop index attraction_rating_fingerprint_at_index;
+drop index attraction_rating_submitter_at_index;
+
+alter table attraction_rating
+    drop column fraud_reasons,
+    drop column fraud_score,
+    drop column status,
+    drop column fingerprint,
+    drop column submitter,
+    drop column source;
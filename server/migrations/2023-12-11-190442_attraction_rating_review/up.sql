@@ -0,0 +1,19 @@
+-- Who and from where every rating was submitted, and the result of the
+-- review by the fraud rules. Only the accepted ratings are aggregated.
+alter table attraction_rating
+    add source        varchar   not null default 'UNKNOWN',
+    add submitter     varchar,
+    add fingerprint   varchar,
+    add status        varchar   not null default 'ACCEPTED',
+    add fraud_score   numeric   not null default 0,
+    add fraud_reasons varchar[] not null default '{}';
+
+create index attraction_rating_submitter_at_index
+    on attraction_rating (submitter, at);
+
+create index attraction_rating_fingerprint_at_index
+    on attraction_rating (fingerprint, at);
+
+create index attraction_rating_status_index
+    on attraction_rating (status)
+    where status <> 'ACCEPTED';
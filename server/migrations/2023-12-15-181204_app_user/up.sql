@@ -0,0 +1,37 @@
+-- The registered users, identified by their unique username. Only the
+-- argon2 hash of the password is kept.
+create table app_user
+(
+    id            serial
+        constraint app_user_pk
+            primary key,
+    username      varchar   not null
+        constraint app_user_username_uk
+            unique,
+    password_hash varchar   not null,
+    created_at    timestamp not null default now()
+);
+
+alter table app_user
+    owner to postgres;
+
+-- The cookie sessions opened by the login, the token is the value of the
+-- cookie.
+create table user_session
+(
+    token      varchar   not null
+        constraint user_session_pk
+            primary key,
+    user_id    integer   not null
+        constraint user_session_user_id_fk
+            references app_user
+            on delete cascade,
+    created_at timestamp not null default now(),
+    expires_at timestamp not null
+);
+
+alter table user_session
+    owner to postgres;
+
+create index user_session_user_id_index
+    on user_session (user_id);
@@ -0,0 +1,4 @@
+-- The role of every user, one of VIEWER, EDITOR or ADMIN. Every role has the
+-- permissions of the previous ones.
+alter table app_user
+    add role varchar not null default 'VIEWER';
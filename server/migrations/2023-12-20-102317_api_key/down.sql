@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+drop table api_key_usage;
+drop table api_key;
@@ -0,0 +1,48 @@
+-- The machine credentials of the partners, owned by a user. Only the sha256
+-- hash of the key is kept, the prefix identifies the key to its owner.
+create table api_key
+(
+    id              serial
+        constraint api_key_pk
+            primary key,
+    owner_id        integer   not null
+        constraint api_key_owner_id_fk
+            references app_user
+            on delete cascade,
+    name            varchar   not null,
+    prefix          varchar   not null,
+    key_hash        varchar   not null
+        constraint api_key_key_hash_uk
+            unique,
+    scopes          varchar[] not null,
+    rate_per_minute integer   not null,
+    burst           integer   not null,
+    created_at      timestamp not null default now(),
+    rotated_at      timestamp,
+    revoked_at      timestamp,
+    last_used_at    timestamp
+);
+
+alter table api_key
+    owner to postgres;
+
+create index api_key_owner_id_index
+    on api_key (owner_id);
+
+-- The requests made with every key per day, the throttled ones exceeded the
+-- rate limit of the key and were rejected.
+create table api_key_usage
+(
+    api_key_id      integer not null
+        constraint api_key_usage_api_key_id_fk
+            references api_key
+            on delete cascade,
+    day             date    not null,
+    request_count   bigint  not null default 0,
+    throttled_count bigint  not null default 0,
+    constraint api_key_usage_pk
+        primary key (api_key_id, day)
+);
+
+alter table api_key_usage
+    owner to postgres;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+drop index attraction_rating_user_day_uk;
+alter table attraction_rating drop column user_id;
@@ -0,0 +1,11 @@
+-- The user that rated, none for the anonymous ratings and the ones pushed by
+-- the partners with an api key. A user rates an attraction once per day.
+alter table attraction_rating
+    add user_id integer
+        constraint attraction_rating_user_id_fk
+            references app_user
+            on delete set null;
+
+create unique index attraction_rating_user_day_uk
+    on attraction_rating (user_id, attraction_id, date_trunc('day', at))
+    where user_id is not null;
@@ -0,0 +1,22 @@
+-- The item-item collaborative filtering model trained from the ratings of
+-- the users, the attractions with the strongest similarity, positive or
+-- negative, to every attraction. Every pair is stored in both directions.
+create table attraction_neighbour
+(
+    attraction_id    integer   not null
+        constraint attraction_neighbour_attraction_id_fk
+            references attraction
+            on delete cascade,
+    to_attraction_id integer   not null
+        constraint attraction_neighbour_to_attraction_id_fk
+            references attraction
+            on delete cascade,
+    similarity       numeric   not null,
+    co_rating_count  integer   not null,
+    trained_at       timestamp not null,
+    constraint attraction_neighbour_pk
+        primary key (attraction_id, to_attraction_id)
+);
+
+alter table attraction_neighbour
+    owner to postgres;
@@ -0,0 +1,35 @@
+-- The opening hours of the attractions in the OSM opening_hours syntax, in
+-- the local time of every attraction.
+create table attraction_opening_hours
+(
+    attraction_id integer   not null
+        constraint attraction_opening_hours_pk
+            primary key
+        constraint attraction_opening_hours_attraction_id_fk
+            references attraction
+            on delete cascade,
+    opening_hours varchar   not null,
+    updated_at    timestamp not null default now()
+);
+
+alter table attraction_opening_hours
+    owner to postgres;
+
+-- The public holidays of every country, the PH of the opening hours.
+create table holiday
+(
+    id          serial
+        constraint holiday_pk
+            primary key,
+    country_id  integer not null
+        constraint holiday_country_id_fk
+            references country
+            on delete cascade,
+    day         date    not null,
+    description varchar not null,
+    constraint holiday_country_id_day_uk
+        unique (country_id, day)
+);
+
+alter table holiday
+    owner to postgres;
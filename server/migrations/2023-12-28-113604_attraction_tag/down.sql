@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+drop table attraction_attribute;
+drop table attraction_tag;
+drop table tag;
@@ -0,0 +1,56 @@
+-- The tags of the attractions, like family-friendly, free or indoor. An
+-- attraction can have many tags, unlike its type.
+create table tag
+(
+    id          serial
+        constraint tag_pk
+            primary key,
+    code        varchar not null
+        constraint tag_code_uk
+            unique,
+    description varchar not null
+);
+
+alter table tag
+    owner to postgres;
+
+create table attraction_tag
+(
+    attraction_id integer not null
+        constraint attraction_tag_attraction_id_fk
+            references attraction
+            on delete cascade,
+    tag_id        integer not null
+        constraint attraction_tag_tag_id_fk
+            references tag
+            on delete cascade,
+    constraint attraction_tag_pk
+        primary key (attraction_id, tag_id)
+);
+
+alter table attraction_tag
+    owner to postgres;
+
+create index attraction_tag_tag_id_index
+    on attraction_tag (tag_id);
+
+-- The typed attributes of the attractions, all of them optional.
+create table attraction_attribute
+(
+    attraction_id integer   not null
+        constraint attraction_attribute_pk
+            primary key
+        constraint attraction_attribute_attraction_id_fk
+            references attraction
+            on delete cascade,
+    price_range   varchar
+        constraint attraction_attribute_price_range_check
+            check (price_range in ('FREE', 'LOW', 'MEDIUM', 'HIGH')),
+    visit_minutes integer
+        constraint attraction_attribute_visit_minutes_check
+            check (visit_minutes > 0),
+    updated_at    timestamp not null default now()
+);
+
+alter table attraction_attribute
+    owner to postgres;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+drop index attraction_description_trgm_index;
+drop function search_text(text);
@@ -0,0 +1,23 @@
+-- The search of the attractions by their description, tolerant to typos and
+-- accents.
+create extension if not exists pg_trgm;
+
+create extension if not exists unaccent;
+
+-- The unaccent function isn't immutable, it can't be indexed without this
+-- wrapper of its dictionary.
+create or replace function search_text(value text) returns text
+    language sql
+    immutable
+    parallel safe
+    strict
+as
+$$
+select lower(public.unaccent('public.unaccent'::regdictionary, value))
+$$;
+
+alter function search_text(text)
+    owner to postgres;
+
+create index attraction_description_trgm_index
+    on attraction using gin (search_text(description) gin_trgm_ops);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+drop table city_translation;
+drop table country_translation;
+drop table attraction_type_translation;
+drop table attraction_translation;
@@ -0,0 +1,66 @@
+-- The descriptions of the attractions and of their reference data in other
+-- languages, by locale like "es" or "pt-BR". The description of the entity
+-- itself is the last fallback when there isn't any translation.
+create table attraction_translation
+(
+    attraction_id integer   not null
+        constraint attraction_translation_attraction_id_fk
+            references attraction
+            on delete cascade,
+    locale        varchar   not null,
+    description   varchar   not null,
+    updated_at    timestamp not null default now(),
+    constraint attraction_translation_pk
+        primary key (attraction_id, locale)
+);
+
+alter table attraction_translation
+    owner to postgres;
+
+create table attraction_type_translation
+(
+    attraction_type_id integer   not null
+        constraint attraction_type_translation_attraction_type_id_fk
+            references attraction_type
+            on delete cascade,
+    locale             varchar   not null,
+    description        varchar   not null,
+    updated_at         timestamp not null default now(),
+    constraint attraction_type_translation_pk
+        primary key (attraction_type_id, locale)
+);
+
+alter table attraction_type_translation
+    owner to postgres;
+
+create table country_translation
+(
+    country_id  integer   not null
+        constraint country_translation_country_id_fk
+            references country
+            on delete cascade,
+    locale      varchar   not null,
+    description varchar   not null,
+    updated_at  timestamp not null default now(),
+    constraint country_translation_pk
+        primary key (country_id, locale)
+);
+
+alter table country_translation
+    owner to postgres;
+
+create table city_translation
+(
+    city_id     integer   not null
+        constraint city_translation_city_id_fk
+            references city
+            on delete cascade,
+    locale      varchar   not null,
+    description varchar   not null,
+    updated_at  timestamp not null default now(),
+    constraint city_translation_pk
+        primary key (city_id, locale)
+);
+
+alter table city_translation
+    owner to postgres;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+drop table attraction_duplicate_dismissal;
+drop table attraction_redirect;
@@ -0,0 +1,42 @@
+-- The attractions merged into another one, the duplicates. Their ratings,
+-- aggregates and similarities were moved to the surviving attraction and
+-- their ids redirect to it.
+create table attraction_redirect
+(
+    attraction_id    integer   not null
+        constraint attraction_redirect_pk
+            primary key,
+    to_attraction_id integer   not null
+        constraint attraction_redirect_to_attraction_id_fk
+            references attraction
+            on delete cascade,
+    merged_at        timestamp not null default now()
+);
+
+alter table attraction_redirect
+    owner to postgres;
+
+create index attraction_redirect_to_attraction_id_index
+    on attraction_redirect (to_attraction_id);
+
+-- The pairs of attractions reviewed as not being duplicates, they aren't
+-- reported again. The lowest id goes first.
+create table attraction_duplicate_dismissal
+(
+    attraction_id       integer   not null
+        constraint attraction_duplicate_dismissal_attraction_id_fk
+            references attraction
+            on delete cascade,
+    other_attraction_id integer   not null
+        constraint attraction_duplicate_dismissal_other_attraction_id_fk
+            references attraction
+            on delete cascade,
+    dismissed_at        timestamp not null default now(),
+    constraint attraction_duplicate_dismissal_pk
+        primary key (attraction_id, other_attraction_id),
+    constraint attraction_duplicate_dismissal_order_check
+        check (attraction_id < other_attraction_id)
+);
+
+alter table attraction_duplicate_dismissal
+    owner to postgres;
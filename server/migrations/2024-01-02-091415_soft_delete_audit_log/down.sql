@@ -0,0 +1,75 @@
+-- This file should undo anything in `up.sql`
+drop trigger tag_audit on tag;
+
+drop trigger city_audit on city;
+
+drop trigger country_audit on country;
+
+drop trigger attraction_type_audit on attraction_type;
+
+drop trigger attraction_audit on attraction;
+
+drop function audit_log_record();
+
+drop table audit_log;
+
+drop function audit_log_reject_change();
+
+drop trigger attraction_type_live_references on attraction_type;
+
+drop function attraction_type_check_live_references();
+
+drop trigger country_live_references on country;
+
+drop function country_check_live_references();
+
+drop trigger city_live_references on city;
+
+drop function city_check_live_references();
+
+drop trigger attraction_live_references on attraction;
+
+drop function attraction_check_live_references();
+
+-- The deleted rows come back, but the deleted tags and countries that
+-- clash with the codes of others.
+delete
+from tag deleted
+where deleted.deleted_at is not null
+  and exists(select 1
+             from tag live
+             where live.code = deleted.code and live.id <> deleted.id);
+
+drop index tag_code_uk;
+
+alter table tag
+    add constraint tag_code_uk
+        unique (code);
+
+delete
+from country deleted
+where deleted.deleted_at is not null
+  and exists(select 1
+             from country live
+             where live.iso_code = deleted.iso_code and live.id <> deleted.id);
+
+drop index country_iso_code_uk;
+
+alter table country
+    add constraint country_pk2
+        unique (iso_code);
+
+alter table tag
+    drop column deleted_at;
+
+alter table city
+    drop column deleted_at;
+
+alter table country
+    drop column deleted_at;
+
+alter table attraction_type
+    drop column deleted_at;
+
+alter table attraction
+    drop column deleted_at;
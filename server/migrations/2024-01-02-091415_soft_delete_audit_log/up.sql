@@ -0,0 +1,253 @@
+-- The attractions and the reference data are deleted by setting when, so
+-- they can be restored. The deleted rows are left out of every query.
+alter table attraction
+    add deleted_at timestamp;
+
+alter table attraction_type
+    add deleted_at timestamp;
+
+alter table country
+    add deleted_at timestamp;
+
+alter table city
+    add deleted_at timestamp;
+
+alter table tag
+    add deleted_at timestamp;
+
+-- The codes only have to be unique among the rows that aren't deleted, a
+-- deleted one can be created again.
+alter table country
+    drop constraint country_pk2;
+
+create unique index country_iso_code_uk
+    on country (iso_code)
+    where deleted_at is null;
+
+alter table tag
+    drop constraint tag_code_uk;
+
+create unique index tag_code_uk
+    on tag (code)
+    where deleted_at is null;
+
+-- The foreign keys don't know about the deleted rows, so a row can't
+-- reference a deleted one, and a row can't be deleted while others still
+-- reference it. Both fail as a violation of a foreign key.
+create or replace function attraction_check_live_references() returns trigger as
+$$
+begin
+    if new.deleted_at is null
+        and exists(select 1
+                   from city
+                   where id = new.city_id and deleted_at is not null
+                   union all
+                   select 1
+                   from attraction_type
+                   where id = new.attraction_type_id and deleted_at is not null)
+    then
+        raise foreign_key_violation
+            using message = 'The city or the type of the attraction is deleted';
+    end if;
+    return new;
+end;
+$$ language plpgsql;
+
+create trigger attraction_live_references
+    before insert or update
+    on attraction
+    for each row
+execute function attraction_check_live_references();
+
+create or replace function city_check_live_references() returns trigger as
+$$
+begin
+    if new.deleted_at is null
+        and exists(select 1
+                   from country
+                   where id = new.country_id and deleted_at is not null)
+    then
+        raise foreign_key_violation
+            using message = 'The country of the city is deleted';
+    end if;
+    if new.deleted_at is not null
+        and exists(select 1
+                   from attraction
+                   where city_id = new.id and deleted_at is null)
+    then
+        raise foreign_key_violation
+            using message = 'The city has attractions';
+    end if;
+    return new;
+end;
+$$ language plpgsql;
+
+create trigger city_live_references
+    before insert or update
+    on city
+    for each row
+execute function city_check_live_references();
+
+create or replace function country_check_live_references() returns trigger as
+$$
+begin
+    if new.deleted_at is not null
+        and exists(select 1
+                   from city
+                   where country_id = new.id and deleted_at is null)
+    then
+        raise foreign_key_violation
+            using message = 'The country has cities';
+    end if;
+    return new;
+end;
+$$ language plpgsql;
+
+create trigger country_live_references
+    before update
+    on country
+    for each row
+execute function country_check_live_references();
+
+create or replace function attraction_type_check_live_references() returns trigger as
+$$
+begin
+    if new.deleted_at is not null
+        and exists(select 1
+                   from attraction
+                   where attraction_type_id = new.id and deleted_at is null)
+    then
+        raise foreign_key_violation
+            using message = 'The type has attractions';
+    end if;
+    return new;
+end;
+$$ language plpgsql;
+
+create trigger attraction_type_live_references
+    before update
+    on attraction_type
+    for each row
+execute function attraction_type_check_live_references();
+
+-- Every write of the catalogue, with the user that made it and the row
+-- before and after it. The user is taken from the settings cala.actor_id
+-- and cala.actor of the transaction, the writes made outside the
+-- application don't have one.
+create table audit_log
+(
+    id          serial
+        constraint audit_log_pk
+            primary key,
+    entity      varchar   not null,
+    entity_id   integer   not null,
+    action      varchar   not null,
+    actor_id    integer,
+    actor       varchar,
+    before      jsonb,
+    after       jsonb,
+    changes     jsonb     not null,
+    recorded_at timestamp not null default clock_timestamp()
+);
+
+alter table audit_log
+    owner to postgres;
+
+create index audit_log_entity_index
+    on audit_log (entity, entity_id);
+
+create index audit_log_actor_id_index
+    on audit_log (actor_id);
+
+-- The log is append-only.
+create or replace function audit_log_reject_change() returns trigger as
+$$
+begin
+    raise exception 'The audit log is append-only';
+end;
+$$ language plpgsql;
+
+create trigger audit_log_append_only
+    before update or delete or truncate
+    on audit_log
+    for each statement
+execute function audit_log_reject_change();
+
+-- Record the write of a row. The action is CREATE, UPDATE, DELETE when the
+-- row is deleted, RESTORE when it's restored, or PURGE when it's removed
+-- for good. The changes are the columns with a different value, with the
+-- value before and after the write, a missing row counts as all nulls.
+create or replace function audit_log_record() returns trigger as
+$$
+declare
+    before_row jsonb := case when tg_op = 'INSERT' then null else to_jsonb(old) end;
+    after_row  jsonb := case when tg_op = 'DELETE' then null else to_jsonb(new) end;
+    changes    jsonb;
+    action     varchar;
+begin
+    select coalesce(jsonb_object_agg(
+                        coalesce(a.key, b.key),
+                        jsonb_build_object('before', b.value, 'after', a.value)),
+                    '{}')
+    into changes
+    from jsonb_each(after_row) a
+             full join jsonb_each(before_row) b on a.key = b.key
+    where coalesce(a.value, 'null') is distinct from coalesce(b.value, 'null');
+
+    if tg_op = 'UPDATE' and changes = '{}' then
+        return null;
+    end if;
+
+    action := case
+                  when tg_op = 'INSERT' then 'CREATE'
+                  when tg_op = 'DELETE' then 'PURGE'
+                  when old.deleted_at is null and new.deleted_at is not null
+                      then 'DELETE'
+                  when old.deleted_at is not null and new.deleted_at is null
+                      then 'RESTORE'
+                  else 'UPDATE'
+        end;
+
+    insert into audit_log
+        (entity, entity_id, action, actor_id, actor, before, after, changes)
+    values (tg_table_name,
+            (coalesce(after_row, before_row) ->> 'id')::integer,
+            action,
+            nullif(current_setting('cala.actor_id', true), '')::integer,
+            nullif(current_setting('cala.actor', true), ''),
+            before_row,
+            after_row,
+            changes);
+    return null;
+end;
+$$ language plpgsql;
+
+create trigger attraction_audit
+    after insert or update or delete
+    on attraction
+    for each row
+execute function audit_log_record();
+
+create trigger attraction_type_audit
+    after insert or update or delete
+    on attraction_type
+    for each row
+execute function audit_log_record();
+
+create trigger country_audit
+    after insert or update or delete
+    on country
+    for each row
+execute function audit_log_record();
+
+create trigger city_audit
+    after insert or update or delete
+    on city
+    for each row
+execute function audit_log_record();
+
+create trigger tag_audit
+    after insert or update or delete
+    on tag
+    for each row
+execute function audit_log_record();
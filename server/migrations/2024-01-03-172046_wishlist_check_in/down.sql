@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+drop table check_in;
+drop table wishlist_attraction;
+drop table wishlist;
@@ -0,0 +1,78 @@
+-- The named lists of attractions of the users, like the places to see in a
+-- trip. A list with a share token can be read by anyone with its link.
+create table wishlist
+(
+    id          serial
+        constraint wishlist_pk
+            primary key,
+    user_id     integer   not null
+        constraint wishlist_user_id_fk
+            references app_user
+            on delete cascade,
+    name        varchar   not null,
+    share_token varchar
+        constraint wishlist_share_token_uk
+            unique,
+    created_at  timestamp not null default now(),
+    updated_at  timestamp not null default now(),
+    constraint wishlist_user_id_name_uk
+        unique (user_id, name)
+);
+
+alter table wishlist
+    owner to postgres;
+
+-- The attractions of a list, in the order given by the user.
+create table wishlist_attraction
+(
+    wishlist_id   integer   not null
+        constraint wishlist_attraction_wishlist_id_fk
+            references wishlist
+            on delete cascade,
+    attraction_id integer   not null
+        constraint wishlist_attraction_attraction_id_fk
+            references attraction
+            on delete cascade,
+    position      integer   not null,
+    added_at      timestamp not null default now(),
+    constraint wishlist_attraction_pk
+        primary key (wishlist_id, attraction_id)
+);
+
+alter table wishlist_attraction
+    owner to postgres;
+
+create index wishlist_attraction_attraction_id_index
+    on wishlist_attraction (attraction_id);
+
+-- The visits of the users to the attractions. The rate given in a visit is
+-- registered as a rating of the user, the visits without one are an
+-- implicit signal of what the user likes.
+create table check_in
+(
+    id            serial
+        constraint check_in_pk
+            primary key,
+    user_id       integer   not null
+        constraint check_in_user_id_fk
+            references app_user
+            on delete cascade,
+    attraction_id integer   not null
+        constraint check_in_attraction_id_fk
+            references attraction,
+    at            timestamp not null,
+    rating_id     integer
+        constraint check_in_rating_id_fk
+            references attraction_rating
+            on delete set null,
+    created_at    timestamp not null default now()
+);
+
+alter table check_in
+    owner to postgres;
+
+create index check_in_user_id_index
+    on check_in (user_id, at);
+
+create index check_in_attraction_id_index
+    on check_in (attraction_id, at);
@@ -0,0 +1,61 @@
+-- This file should undo anything in `up.sql`
+drop trigger holiday_audit on holiday;
+
+drop trigger city_translation_audit on city_translation;
+
+drop trigger country_translation_audit on country_translation;
+
+drop trigger attraction_type_translation_audit on attraction_type_translation;
+
+drop trigger attraction_translation_audit on attraction_translation;
+
+drop trigger attraction_opening_hours_audit on attraction_opening_hours;
+
+drop trigger attraction_attribute_audit on attraction_attribute;
+
+drop trigger attraction_tag_audit on attraction_tag;
+
+create or replace function audit_log_record() returns trigger as
+$$
+declare
+    before_row jsonb := case when tg_op = 'INSERT' then null else to_jsonb(old) end;
+    after_row  jsonb := case when tg_op = 'DELETE' then null else to_jsonb(new) end;
+    changes    jsonb;
+    action     varchar;
+begin
+    select coalesce(jsonb_object_agg(
+                        coalesce(a.key, b.key),
+                        jsonb_build_object('before', b.value, 'after', a.value)),
+                    '{}')
+    into changes
+    from jsonb_each(after_row) a
+             full join jsonb_each(before_row) b on a.key = b.key
+    where coalesce(a.value, 'null') is distinct from coalesce(b.value, 'null');
+
+    if tg_op = 'UPDATE' and changes = '{}' then
+        return null;
+    end if;
+
+    action := case
+                  when tg_op = 'INSERT' then 'CREATE'
+                  when tg_op = 'DELETE' then 'PURGE'
+                  when old.deleted_at is null and new.deleted_at is not null
+                      then 'DELETE'
+                  when old.deleted_at is not null and new.deleted_at is null
+                      then 'RESTORE'
+                  else 'UPDATE'
+        end;
+
+    insert into audit_log
+        (entity, entity_id, action, actor_id, actor, before, after, changes)
+    values (tg_table_name,
+            (coalesce(after_row, before_row) ->> 'id')::integer,
+            action,
+            nullif(current_setting('cala.actor_id', true), '')::integer,
+            nullif(current_setting('cala.actor', true), ''),
+            before_row,
+            after_row,
+            changes);
+    return null;
+end;
+$$ language plpgsql;
@@ -0,0 +1,101 @@
+-- Audit the rows that belong to another one, like the tags or the
+-- translations of an attraction, logged under the id of their owner. The
+-- name of the column with the id is the argument of the trigger, the id by
+-- default.
+create or replace function audit_log_record() returns trigger as
+$$
+declare
+    before_row jsonb := case when tg_op = 'INSERT' then null else to_jsonb(old) end;
+    after_row  jsonb := case when tg_op = 'DELETE' then null else to_jsonb(new) end;
+    id_column  varchar := coalesce(tg_argv[0], 'id');
+    changes    jsonb;
+    action     varchar;
+begin
+    select coalesce(jsonb_object_agg(
+                        coalesce(a.key, b.key),
+                        jsonb_build_object('before', b.value, 'after', a.value)),
+                    '{}')
+    into changes
+    from jsonb_each(after_row) a
+             full join jsonb_each(before_row) b on a.key = b.key
+    where coalesce(a.value, 'null') is distinct from coalesce(b.value, 'null');
+
+    if tg_op = 'UPDATE' and changes = '{}' then
+        return null;
+    end if;
+
+    -- Not every table is deleted softly, so the deleted_at is read from the
+    -- rows.
+    action := case
+                  when tg_op = 'INSERT' then 'CREATE'
+                  when tg_op = 'DELETE' then 'PURGE'
+                  when before_row ->> 'deleted_at' is null
+                      and after_row ->> 'deleted_at' is not null
+                      then 'DELETE'
+                  when before_row ->> 'deleted_at' is not null
+                      and after_row ->> 'deleted_at' is null
+                      then 'RESTORE'
+                  else 'UPDATE'
+        end;
+
+    insert into audit_log
+        (entity, entity_id, action, actor_id, actor, before, after, changes)
+    values (tg_table_name,
+            (coalesce(after_row, before_row) ->> id_column)::integer,
+            action,
+            nullif(current_setting('cala.actor_id', true), '')::integer,
+            nullif(current_setting('cala.actor', true), ''),
+            before_row,
+            after_row,
+            changes);
+    return null;
+end;
+$$ language plpgsql;
+
+create trigger attraction_tag_audit
+    after insert or update or delete
+    on attraction_tag
+    for each row
+execute function audit_log_record('attraction_id');
+
+create trigger attraction_attribute_audit
+    after insert or update or delete
+    on attraction_attribute
+    for each row
+execute function audit_log_record('attraction_id');
+
+create trigger attraction_opening_hours_audit
+    after insert or update or delete
+    on attraction_opening_hours
+    for each row
+execute function audit_log_record('attraction_id');
+
+create trigger attraction_translation_audit
+    after insert or update or delete
+    on attraction_translation
+    for each row
+execute function audit_log_record('attraction_id');
+
+create trigger attraction_type_translation_audit
+    after insert or update or delete
+    on attraction_type_translation
+    for each row
+execute function audit_log_record('attraction_type_id');
+
+create trigger country_translation_audit
+    after insert or update or delete
+    on country_translation
+    for each row
+execute function audit_log_record('country_id');
+
+create trigger city_translation_audit
+    after insert or update or delete
+    on city_translation
+    for each row
+execute function audit_log_record('city_id');
+
+create trigger holiday_audit
+    after insert or update or delete
+    on holiday
+    for each row
+execute function audit_log_record();
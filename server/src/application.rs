@@ -1,3 +1,18 @@
+pub mod admin_api;
+pub mod api_key_api;
 pub mod app;
 pub mod attraction_api;
+pub mod audit_api;
+pub mod auth_api;
+pub mod data_quality_api;
+pub mod duplicate_api;
+pub mod itinerary_api;
+pub mod mw_auth;
+pub mod ranking_api;
+pub mod recommendation_api;
+pub mod reference_api;
+pub mod region_api;
+pub mod search_api;
 pub mod similarity_api;
+pub mod translation_api;
+pub mod visit_api;
@@ -0,0 +1,225 @@
+use crate::{
+  application::{
+    auth_api::UserDto,
+    mw_auth::{Authorized, CanAdmin},
+  },
+  model::{auth_controller::AuthController, role::Role},
+  Error, Result,
+};
+use axum::{
+  extract::{Path, State},
+  routing::{get, put},
+  Json, Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+struct RoleParam {
+  role: Role,
+}
+
+/// Defines the endpoints that handles the roles of the users.
+pub fn routes(auth_controller: Arc<dyn AuthController>) -> Router {
+  Router::new()
+    .route("/admin/users", get(list_users))
+    .route("/admin/users/:id/role", put(assign_role))
+    .with_state(auth_controller)
+}
+
+/// List the users with their roles.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, it must be an admin.
+/// * auth_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of users sorted by id.
+/// * Err with 403 status code if the user isn't an admin.
+async fn list_users(
+  _authorized: Authorized<CanAdmin>,
+  State(auth_controller): State<Arc<dyn AuthController>>,
+) -> Result<Json<Vec<UserDto>>> {
+  println!("->> USERS\n");
+  let users = auth_controller.list_users().await.unwrap_or_default();
+  Ok(Json(users.iter().map(UserDto::new).collect()))
+}
+
+/// Assign a role to a user, it takes effect in the next request of the user.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an admin.
+/// * id: the id of the user.
+/// * auth_controller: the controller responsible of the actions.
+/// * role_param: the new role, VIEWER, EDITOR or ADMIN.
+///
+/// # Return:
+/// * Ok with the user and its new role.
+/// * Err with 403 status code if the user isn't an admin.
+/// * Err with 404 status code if the user doesn't exist.
+/// * Err with 409 status code if the user is the last admin and the role
+///   isn't ADMIN.
+async fn assign_role(
+  authorized: Authorized<CanAdmin>,
+  Path(id): Path<i32>,
+  State(auth_controller): State<Arc<dyn AuthController>>,
+  Json(role_param): Json<RoleParam>,
+) -> Result<Json<UserDto>> {
+  println!(
+    "->> ASSIGN ROLE {} TO {} BY {}\n",
+    role_param.role,
+    id,
+    authorized.ctx.username()
+  );
+  match auth_controller.assign_role(id, role_param.role).await {
+    Ok(Some(a_user)) => Ok(Json(UserDto::new(&a_user))),
+    Ok(None) => Err(Error::UserNotFound {
+      id,
+    }),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidRoleAssignment {
+        reason: e,
+      })
+    },
+  }
+}
+
+/// Make a user an admin from the command line, registering it if it doesn't
+/// exist with the password of the ADMIN_PASSWORD variable. The registration
+/// always creates viewers, so this is how the first admin is created.
+///
+/// # Arguments:
+/// * auth_controller: the controller responsible of the actions.
+/// * username: the username of the admin.
+///
+/// # Return:
+/// * 0 if the user is an admin.
+/// * 1 if the user can't be made an admin.
+pub async fn run_cli(
+  auth_controller: Arc<dyn AuthController>,
+  username: Option<String>,
+) -> i32 {
+  let Some(username) = username else {
+    eprintln!("xx->> Usage: create-admin <username>");
+    return 1;
+  };
+  let password = std::env::var("ADMIN_PASSWORD").ok();
+  match auth_controller.create_admin(username, password).await {
+    Ok(an_admin) => {
+      println!(
+        "->> {} (id {}) is an admin",
+        an_admin.get_username(),
+        an_admin.get_id()
+      );
+      0
+    },
+    Err(e) => {
+      eprintln!("xx->> {}", e);
+      1
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::{
+    auth::{AuthSettings, Claims},
+    auth_controller::LoginSession,
+    user::User,
+  };
+  use async_trait::async_trait;
+  use chrono::NaiveDateTime;
+
+  /// Only the existing users can be made admins.
+  struct ExistingUsers(Vec<&'static str>);
+
+  #[async_trait]
+  impl AuthController for ExistingUsers {
+    async fn register(
+      &self,
+      _: String,
+      _: String,
+    ) -> std::result::Result<User, String> {
+      todo!()
+    }
+
+    async fn login(&self, _: String, _: String) -> Option<LoginSession> {
+      todo!()
+    }
+
+    async fn logout(&self, _: String) -> Option<()> {
+      todo!()
+    }
+
+    async fn user_of_session(&self, _: String) -> Option<User> {
+      todo!()
+    }
+
+    async fn user_by_id(&self, _: i32) -> Option<User> {
+      todo!()
+    }
+
+    async fn list_users(&self) -> Option<Vec<User>> {
+      todo!()
+    }
+
+    async fn assign_role(
+      &self,
+      _: i32,
+      _: Role,
+    ) -> std::result::Result<Option<User>, String> {
+      todo!()
+    }
+
+    async fn create_admin(
+      &self,
+      username: String,
+      _: Option<String>,
+    ) -> std::result::Result<User, String> {
+      let ExistingUsers(usernames) = self;
+      if !usernames.contains(&username.as_str()) {
+        return Err(format!("The user {username} doesn't exist"));
+      }
+      Ok(User {
+        id: 1,
+        username,
+        password_hash: String::new(),
+        created_at: NaiveDateTime::default(),
+        role: Role::Admin.to_string(),
+      })
+    }
+
+    fn claims_of(&self, _: &str) -> std::result::Result<Claims, String> {
+      todo!()
+    }
+
+    fn settings(&self) -> Arc<AuthSettings> {
+      todo!()
+    }
+  }
+
+  async fn exit_code_of(username: Option<&str>) -> i32 {
+    run_cli(
+      Arc::new(ExistingUsers(vec!["root"])),
+      username.map(String::from),
+    )
+    .await
+  }
+
+  #[tokio::test]
+  async fn the_cli_needs_a_username() {
+    assert_eq!(exit_code_of(None).await, 1);
+  }
+
+  #[tokio::test]
+  async fn the_cli_succeeds_when_the_user_is_made_an_admin() {
+    assert_eq!(exit_code_of(Some("root")).await, 0);
+  }
+
+  #[tokio::test]
+  async fn the_cli_fails_when_the_user_cant_be_made_an_admin() {
+    assert_eq!(exit_code_of(Some("stranger")).await, 1);
+  }
+}
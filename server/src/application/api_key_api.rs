@@ -0,0 +1,288 @@
+use crate::{
+  application::mw_auth::{Authorized, CanManageKeys},
+  ctx::Ctx,
+  model::{
+    api_key::{ApiKey, ApiKeyUsage, ApiScope},
+    api_key_controller::{ApiKeyController, IssuedApiKey},
+    role::Role,
+  },
+  Error, Result,
+};
+use axum::{
+  extract::{Path, Query, State},
+  http::StatusCode,
+  routing::{delete, get, post},
+  Json, Router,
+};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// The days of usage listed by default.
+const DEFAULT_USAGE_DAYS: i64 = 30;
+/// The most days of usage that can be listed.
+const MAX_USAGE_DAYS: i64 = 366;
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct ApiKeyDto {
+  pub id: i32,
+  pub owner_id: i32,
+  pub name: String,
+  pub prefix: String,
+  pub scopes: Vec<String>,
+  pub rate_per_minute: i32,
+  pub burst: i32,
+  pub created_at: NaiveDateTime,
+  pub rotated_at: Option<NaiveDateTime>,
+  pub revoked_at: Option<NaiveDateTime>,
+  pub last_used_at: Option<NaiveDateTime>,
+}
+
+impl ApiKeyDto {
+  fn new(an_api_key: &ApiKey) -> Self {
+    ApiKeyDto {
+      id: an_api_key.get_id(),
+      owner_id: an_api_key.get_owner_id(),
+      name: an_api_key.get_name(),
+      prefix: an_api_key.get_prefix(),
+      scopes: an_api_key
+        .get_scopes()
+        .iter()
+        .map(|scope| scope.to_string())
+        .collect(),
+      rate_per_minute: an_api_key.get_rate_per_minute(),
+      burst: an_api_key.get_burst(),
+      created_at: an_api_key.get_created_at(),
+      rotated_at: an_api_key.get_rotated_at(),
+      revoked_at: an_api_key.get_revoked_at(),
+      last_used_at: an_api_key.get_last_used_at(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct IssuedApiKeyDto {
+  /// The key, it can't be retrieved again.
+  pub key: String,
+  pub api_key: ApiKeyDto,
+}
+
+impl IssuedApiKeyDto {
+  fn new(an_issued_key: &IssuedApiKey) -> Self {
+    IssuedApiKeyDto {
+      key: an_issued_key.key.clone(),
+      api_key: ApiKeyDto::new(&an_issued_key.api_key),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct ApiKeyUsageDto {
+  pub day: NaiveDate,
+  pub request_count: i64,
+  pub throttled_count: i64,
+}
+
+impl ApiKeyUsageDto {
+  fn new(a_usage: &ApiKeyUsage) -> Self {
+    ApiKeyUsageDto {
+      day: a_usage.day,
+      request_count: a_usage.request_count,
+      throttled_count: a_usage.throttled_count,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct ApiKeyParam {
+  name: String,
+  scopes: Vec<ApiScope>,
+  rate_per_minute: Option<i32>,
+  burst: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct UsageParam {
+  days: Option<i64>,
+}
+
+/// Defines the endpoints that handles the api keys of the partners.
+pub fn routes(api_key_controller: Arc<dyn ApiKeyController>) -> Router {
+  Router::new()
+    .route("/api-keys", get(list_api_keys).post(issue_api_key))
+    .route("/api-keys/:id", delete(revoke_api_key))
+    .route("/api-keys/:id/rotate", post(rotate_api_key))
+    .route("/api-keys/:id/usage", get(api_key_usage))
+    .with_state(api_key_controller)
+}
+
+/// The owner whose keys the user can manage, None for the admins that can
+/// manage any key.
+fn owner_filter(ctx: &Ctx) -> Option<i32> {
+  (!ctx.role().grants(Role::Admin)).then_some(ctx.user_id())
+}
+
+/// Issue a new api key for the user.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the owner of the key.
+/// * api_key_controller: the controller responsible of the actions.
+/// * api_key_param: the name, the scopes granted by the role of the user
+///   (attractions:read, attractions:write, ratings:write or
+///   ratings:backfill) and the optional
+///   rate per minute and burst of the key.
+///
+/// # Return:
+/// * Ok with 201 status code, the key and its description. The key is only
+///   returned once.
+/// * Err with 400 status code if the values are invalid.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+async fn issue_api_key(
+  authorized: Authorized<CanManageKeys>,
+  State(api_key_controller): State<Arc<dyn ApiKeyController>>,
+  Json(api_key_param): Json<ApiKeyParam>,
+) -> Result<(StatusCode, Json<IssuedApiKeyDto>)> {
+  println!("->> ISSUE API KEY\n");
+  match api_key_controller
+    .issue(
+      authorized.ctx.user_id(),
+      authorized.ctx.role(),
+      api_key_param.name,
+      api_key_param.scopes,
+      api_key_param.rate_per_minute,
+      api_key_param.burst,
+    )
+    .await
+  {
+    Ok(an_issued_key) => Ok((
+      StatusCode::CREATED,
+      Json(IssuedApiKeyDto::new(&an_issued_key)),
+    )),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidApiKey {
+        reason: e,
+      })
+    },
+  }
+}
+
+/// List the api keys of the user, the revoked ones too.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the owner of the keys.
+/// * api_key_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of keys, without the keys themselves.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+async fn list_api_keys(
+  authorized: Authorized<CanManageKeys>,
+  State(api_key_controller): State<Arc<dyn ApiKeyController>>,
+) -> Result<Json<Vec<ApiKeyDto>>> {
+  println!("->> API KEYS\n");
+  let api_keys = api_key_controller
+    .api_keys_of(authorized.ctx.user_id())
+    .await
+    .unwrap_or_default();
+  Ok(Json(api_keys.iter().map(ApiKeyDto::new).collect()))
+}
+
+/// Replace an api key, the previous key stops working at once.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the owner of the key or an
+///   admin.
+/// * id: the id of the key.
+/// * api_key_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the new key and its description.
+/// * Err with 400 status code if the key is revoked.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+/// * Err with 404 status code if the key doesn't exist or belongs to another
+///   user.
+async fn rotate_api_key(
+  authorized: Authorized<CanManageKeys>,
+  Path(id): Path<i32>,
+  State(api_key_controller): State<Arc<dyn ApiKeyController>>,
+) -> Result<Json<IssuedApiKeyDto>> {
+  println!("->> ROTATE API KEY\n");
+  match api_key_controller
+    .rotate(id, owner_filter(&authorized.ctx))
+    .await
+  {
+    Ok(Some(an_issued_key)) => Ok(Json(IssuedApiKeyDto::new(&an_issued_key))),
+    Ok(None) => Err(Error::ApiKeyNotFound {
+      id,
+    }),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidApiKey {
+        reason: e,
+      })
+    },
+  }
+}
+
+/// Revoke an api key, it can't be used or rotated anymore.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the owner of the key or an
+///   admin.
+/// * id: the id of the key.
+/// * api_key_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the revoked key.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+/// * Err with 404 status code if the key doesn't exist or belongs to another
+///   user.
+async fn revoke_api_key(
+  authorized: Authorized<CanManageKeys>,
+  Path(id): Path<i32>,
+  State(api_key_controller): State<Arc<dyn ApiKeyController>>,
+) -> Result<Json<ApiKeyDto>> {
+  println!("->> REVOKE API KEY\n");
+  api_key_controller
+    .revoke(id, owner_filter(&authorized.ctx))
+    .await
+    .map(|an_api_key| Json(ApiKeyDto::new(&an_api_key)))
+    .ok_or(Error::ApiKeyNotFound {
+      id,
+    })
+}
+
+/// The requests made with an api key per day.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the owner of the key or an
+///   admin.
+/// * id: the id of the key.
+/// * usage_param: the days until today, 30 by default and 366 at most.
+/// * api_key_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of the days with requests, the earliest first.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+/// * Err with 404 status code if the key doesn't exist or belongs to another
+///   user.
+async fn api_key_usage(
+  authorized: Authorized<CanManageKeys>,
+  Path(id): Path<i32>,
+  Query(usage_param): Query<UsageParam>,
+  State(api_key_controller): State<Arc<dyn ApiKeyController>>,
+) -> Result<Json<Vec<ApiKeyUsageDto>>> {
+  println!("->> API KEY USAGE\n");
+  let days = usage_param
+    .days
+    .unwrap_or(DEFAULT_USAGE_DAYS)
+    .clamp(1, MAX_USAGE_DAYS);
+  api_key_controller
+    .usage(id, owner_filter(&authorized.ctx), days)
+    .await
+    .map(|usage| Json(usage.iter().map(ApiKeyUsageDto::new).collect()))
+    .ok_or(Error::ApiKeyNotFound {
+      id,
+    })
+}
@@ -1,10 +1,49 @@
 use crate::{
   db,
   model::{
+    aggregation_settings::AggregationSettings,
+    api_key::ApiKeySettings,
+    api_key_controller::{ApiKeyController, ApiKeyControllerImpl},
+    api_key_repository::{DummyApiKeyRepo, PgApiKeyRepository},
     attraction_controller::{AttractionController, AttractionControllerImpl},
     attraction_repository::{DummyAttractionRepo, PgAttractionRepository},
+    audit_controller::{AuditController, AuditControllerImpl},
+    audit_repository::{DummyAuditRepo, PgAuditRepository},
+    auth::AuthSettings,
+    auth_controller::{AuthController, AuthControllerImpl},
+    data_quality::DataQualitySettings,
+    data_quality_controller::{
+      DataQualityController, DataQualityControllerImpl,
+    },
+    data_quality_repository::{DummyDataQualityRepo, PgDataQualityRepository},
+    duplicate::DuplicateSettings,
+    duplicate_controller::{DuplicateController, DuplicateControllerImpl},
+    itinerary::ItinerarySettings,
+    itinerary_controller::{ItineraryController, ItineraryControllerImpl},
+    ranking_controller::{RankingController, RankingControllerImpl},
+    rating_rules::RatingRulesSettings,
+    recommendation::RecommendationSettings,
+    recommendation_controller::{
+      RecommendationController, RecommendationControllerImpl,
+    },
+    recommendation_repository::{
+      DummyRecommendationRepo, PgRecommendationRepository,
+    },
+    reference_controller::{ReferenceController, ReferenceControllerImpl},
+    reference_repository::{DummyReferenceRepo, PgReferenceRepository},
+    region_controller::{RegionController, RegionControllerImpl},
+    region_repository::{DummyRegionRepo, PgRegionRepository},
+    search::SearchSettings,
+    search_controller::{SearchController, SearchControllerImpl},
     similarity_controller::{SimilarityController, SimilarityControllerImpl},
     similarity_repository::{DummySimilarityRepo, PgSimilarityRepository},
+    translation_controller::{
+      TranslationController, TranslationControllerImpl,
+    },
+    translation_repository::{DummyTranslationRepo, PgTranslationRepository},
+    user_repository::{DummyUserRepo, PgUserRepository},
+    visit_controller::{VisitController, VisitControllerImpl},
+    visit_repository::{DummyVisitRepo, PgVisitRepository},
   },
 };
 use dotenv::dotenv;
@@ -13,6 +52,19 @@ use std::sync::Arc;
 pub struct Application {
   pub attraction: Arc<dyn AttractionController>,
   pub similarity: Arc<dyn SimilarityController>,
+  pub ranking: Arc<dyn RankingController>,
+  pub region: Arc<dyn RegionController>,
+  pub auth: Arc<dyn AuthController>,
+  pub reference: Arc<dyn ReferenceController>,
+  pub api_key: Arc<dyn ApiKeyController>,
+  pub recommendation: Arc<dyn RecommendationController>,
+  pub itinerary: Arc<dyn ItineraryController>,
+  pub search: Arc<dyn SearchController>,
+  pub translation: Arc<dyn TranslationController>,
+  pub duplicate: Arc<dyn DuplicateController>,
+  pub data_quality: Arc<dyn DataQualityController>,
+  pub audit: Arc<dyn AuditController>,
+  pub visit: Arc<dyn VisitController>,
 }
 
 impl Application {
@@ -24,19 +76,99 @@ impl Application {
     // ---- Repositories initialization ---- //
     let attraction_repo = PgAttractionRepository::new(db.clone());
     let similarity_repo = PgSimilarityRepository::new(db.clone());
+    let region_repo = PgRegionRepository::new(db.clone());
+    let user_repo = PgUserRepository::new(db.clone());
+    let reference_repo = PgReferenceRepository::new(db.clone());
+    let api_key_repo = PgApiKeyRepository::new(db.clone());
+    let recommendation_repo = PgRecommendationRepository::new(db.clone());
+    let translation_repo = PgTranslationRepository::new(db.clone());
+    let data_quality_repo = PgDataQualityRepository::new(db.clone());
+    let audit_repo = PgAuditRepository::new(db.clone());
+    let visit_repo = PgVisitRepository::new(db.clone());
 
     // ---- Controllers initialization ---- //
-    let attraction_controller =
-      AttractionControllerImpl::new(attraction_repo.clone());
+    let attraction_controller = AttractionControllerImpl::new(
+      attraction_repo.clone(),
+      RatingRulesSettings::from_env(),
+    );
 
     let similarity_controller = SimilarityControllerImpl::new(
       attraction_repo.clone(),
       similarity_repo.clone(),
+      AggregationSettings::from_env(),
+    );
+
+    let ranking_controller =
+      RankingControllerImpl::new(attraction_repo.clone());
+
+    let region_controller =
+      RegionControllerImpl::new(attraction_repo.clone(), region_repo.clone());
+
+    let auth_controller =
+      AuthControllerImpl::new(user_repo.clone(), AuthSettings::from_env());
+
+    let reference_controller =
+      ReferenceControllerImpl::new(reference_repo.clone());
+
+    let api_key_controller = ApiKeyControllerImpl::new(
+      api_key_repo.clone(),
+      user_repo.clone(),
+      ApiKeySettings::from_env(),
+    );
+
+    let recommendation_controller = RecommendationControllerImpl::new(
+      recommendation_repo.clone(),
+      RecommendationSettings::from_env(),
+    );
+
+    let itinerary_controller = ItineraryControllerImpl::new(
+      attraction_repo.clone(),
+      ItinerarySettings::from_env(),
+    );
+
+    let search_controller = SearchControllerImpl::new(
+      attraction_repo.clone(),
+      SearchSettings::from_env(),
+    );
+
+    let translation_controller =
+      TranslationControllerImpl::new(translation_repo.clone());
+
+    let duplicate_controller = DuplicateControllerImpl::new(
+      attraction_repo.clone(),
+      DuplicateSettings::from_env(),
+    );
+
+    let data_quality_controller = DataQualityControllerImpl::new(
+      attraction_repo.clone(),
+      data_quality_repo.clone(),
+      DataQualitySettings::from_env(),
+    );
+
+    let audit_controller = AuditControllerImpl::new(audit_repo.clone());
+
+    let visit_controller = VisitControllerImpl::new(
+      visit_repo.clone(),
+      attraction_repo.clone(),
+      RatingRulesSettings::from_env(),
     );
 
     Application {
       attraction: Arc::new(attraction_controller),
       similarity: Arc::new(similarity_controller),
+      ranking: Arc::new(ranking_controller),
+      region: Arc::new(region_controller),
+      auth: Arc::new(auth_controller),
+      reference: Arc::new(reference_controller),
+      api_key: Arc::new(api_key_controller),
+      recommendation: Arc::new(recommendation_controller),
+      itinerary: Arc::new(itinerary_controller),
+      search: Arc::new(search_controller),
+      translation: Arc::new(translation_controller),
+      duplicate: Arc::new(duplicate_controller),
+      data_quality: Arc::new(data_quality_controller),
+      audit: Arc::new(audit_controller),
+      visit: Arc::new(visit_controller),
     }
   }
 
@@ -46,41 +178,201 @@ impl Application {
     let _ = db::database::DbConnection::new(db_uri).await;
 
     // ---- Repositories initialization ---- //
-    let attraction_repo = DummyAttractionRepo::default();
-    let similarity_repo = DummySimilarityRepo::default();
+    let attraction_repo = DummyAttractionRepo;
+    let similarity_repo = DummySimilarityRepo;
+    let region_repo = DummyRegionRepo;
+    let user_repo = DummyUserRepo;
+    let reference_repo = DummyReferenceRepo;
+    let api_key_repo = DummyApiKeyRepo;
+    let recommendation_repo = DummyRecommendationRepo;
+    let translation_repo = DummyTranslationRepo;
+    let data_quality_repo = DummyDataQualityRepo;
+    let audit_repo = DummyAuditRepo;
+    let visit_repo = DummyVisitRepo;
 
     // ---- Controllers initialization ---- //
-    let attraction_controller =
-      AttractionControllerImpl::new(attraction_repo.clone());
+    let attraction_controller = AttractionControllerImpl::new(
+      attraction_repo.clone(),
+      RatingRulesSettings::from_env(),
+    );
 
     let similarity_controller = SimilarityControllerImpl::new(
       attraction_repo.clone(),
       similarity_repo.clone(),
+      AggregationSettings::from_env(),
+    );
+
+    let ranking_controller =
+      RankingControllerImpl::new(attraction_repo.clone());
+
+    let region_controller =
+      RegionControllerImpl::new(attraction_repo.clone(), region_repo.clone());
+
+    let auth_controller =
+      AuthControllerImpl::new(user_repo.clone(), AuthSettings::from_env());
+
+    let reference_controller =
+      ReferenceControllerImpl::new(reference_repo.clone());
+
+    let api_key_controller = ApiKeyControllerImpl::new(
+      api_key_repo.clone(),
+      user_repo.clone(),
+      ApiKeySettings::from_env(),
+    );
+
+    let recommendation_controller = RecommendationControllerImpl::new(
+      recommendation_repo.clone(),
+      RecommendationSettings::from_env(),
+    );
+
+    let itinerary_controller = ItineraryControllerImpl::new(
+      attraction_repo.clone(),
+      ItinerarySettings::from_env(),
+    );
+
+    let search_controller = SearchControllerImpl::new(
+      attraction_repo.clone(),
+      SearchSettings::from_env(),
+    );
+
+    let translation_controller =
+      TranslationControllerImpl::new(translation_repo.clone());
+
+    let duplicate_controller = DuplicateControllerImpl::new(
+      attraction_repo.clone(),
+      DuplicateSettings::from_env(),
+    );
+
+    let data_quality_controller = DataQualityControllerImpl::new(
+      attraction_repo.clone(),
+      data_quality_repo.clone(),
+      DataQualitySettings::from_env(),
+    );
+
+    let audit_controller = AuditControllerImpl::new(audit_repo.clone());
+
+    let visit_controller = VisitControllerImpl::new(
+      visit_repo.clone(),
+      attraction_repo.clone(),
+      RatingRulesSettings::from_env(),
     );
 
     Application {
       attraction: Arc::new(attraction_controller),
       similarity: Arc::new(similarity_controller),
+      ranking: Arc::new(ranking_controller),
+      region: Arc::new(region_controller),
+      auth: Arc::new(auth_controller),
+      reference: Arc::new(reference_controller),
+      api_key: Arc::new(api_key_controller),
+      recommendation: Arc::new(recommendation_controller),
+      itinerary: Arc::new(itinerary_controller),
+      search: Arc::new(search_controller),
+      translation: Arc::new(translation_controller),
+      duplicate: Arc::new(duplicate_controller),
+      data_quality: Arc::new(data_quality_controller),
+      audit: Arc::new(audit_controller),
+      visit: Arc::new(visit_controller),
     }
   }
 
   async fn new_dummy_app() -> Self {
     // ---- Repositories initialization ---- //
-    let attraction_repo = DummyAttractionRepo::default();
-    let similarity_repo = DummySimilarityRepo::default();
+    let attraction_repo = DummyAttractionRepo;
+    let similarity_repo = DummySimilarityRepo;
+    let region_repo = DummyRegionRepo;
+    let user_repo = DummyUserRepo;
+    let reference_repo = DummyReferenceRepo;
+    let api_key_repo = DummyApiKeyRepo;
+    let recommendation_repo = DummyRecommendationRepo;
+    let translation_repo = DummyTranslationRepo;
+    let data_quality_repo = DummyDataQualityRepo;
+    let audit_repo = DummyAuditRepo;
+    let visit_repo = DummyVisitRepo;
 
     // ---- Controllers initialization ---- //
-    let attraction_controller =
-      AttractionControllerImpl::new(attraction_repo.clone());
+    let attraction_controller = AttractionControllerImpl::new(
+      attraction_repo.clone(),
+      RatingRulesSettings::from_env(),
+    );
 
     let similarity_controller = SimilarityControllerImpl::new(
       attraction_repo.clone(),
       similarity_repo.clone(),
+      AggregationSettings::from_env(),
+    );
+
+    let ranking_controller =
+      RankingControllerImpl::new(attraction_repo.clone());
+
+    let region_controller =
+      RegionControllerImpl::new(attraction_repo.clone(), region_repo.clone());
+
+    let auth_controller =
+      AuthControllerImpl::new(user_repo.clone(), AuthSettings::from_env());
+
+    let reference_controller =
+      ReferenceControllerImpl::new(reference_repo.clone());
+
+    let api_key_controller = ApiKeyControllerImpl::new(
+      api_key_repo.clone(),
+      user_repo.clone(),
+      ApiKeySettings::from_env(),
+    );
+
+    let recommendation_controller = RecommendationControllerImpl::new(
+      recommendation_repo.clone(),
+      RecommendationSettings::from_env(),
+    );
+
+    let itinerary_controller = ItineraryControllerImpl::new(
+      attraction_repo.clone(),
+      ItinerarySettings::from_env(),
+    );
+
+    let search_controller = SearchControllerImpl::new(
+      attraction_repo.clone(),
+      SearchSettings::from_env(),
+    );
+
+    let translation_controller =
+      TranslationControllerImpl::new(translation_repo.clone());
+
+    let duplicate_controller = DuplicateControllerImpl::new(
+      attraction_repo.clone(),
+      DuplicateSettings::from_env(),
+    );
+
+    let data_quality_controller = DataQualityControllerImpl::new(
+      attraction_repo.clone(),
+      data_quality_repo.clone(),
+      DataQualitySettings::from_env(),
+    );
+
+    let audit_controller = AuditControllerImpl::new(audit_repo.clone());
+
+    let visit_controller = VisitControllerImpl::new(
+      visit_repo.clone(),
+      attraction_repo.clone(),
+      RatingRulesSettings::from_env(),
     );
 
     Application {
       attraction: Arc::new(attraction_controller),
       similarity: Arc::new(similarity_controller),
+      ranking: Arc::new(ranking_controller),
+      region: Arc::new(region_controller),
+      auth: Arc::new(auth_controller),
+      reference: Arc::new(reference_controller),
+      api_key: Arc::new(api_key_controller),
+      recommendation: Arc::new(recommendation_controller),
+      itinerary: Arc::new(itinerary_controller),
+      search: Arc::new(search_controller),
+      translation: Arc::new(translation_controller),
+      duplicate: Arc::new(duplicate_controller),
+      data_quality: Arc::new(data_quality_controller),
+      audit: Arc::new(audit_controller),
+      visit: Arc::new(visit_controller),
     }
   }
 }
@@ -88,12 +380,12 @@ impl Application {
 pub async fn start_application() -> Application {
   dotenv().ok();
   let scope = std::env::var("SCOPE").unwrap_or(String::from("TEST"));
-  return match scope.as_str() {
+  match scope.as_str() {
     "DEV" | "PROD" => Application::new().await,
     "TEST" => Application::new_test_app().await,
     _ => {
       println!("xx->> UNKNOWN SCOPE: {scope}, FALLBACK INTO DUMMY\n");
       Application::new_dummy_app().await
     },
-  };
+  }
 }
@@ -1,19 +1,44 @@
 use std::sync::Arc;
 
 use crate::{
+  application::{
+    mw_auth::{Authorized, CanAdmin, CanEdit, CanRate, CanRead},
+    reference_api::TagDto,
+    translation_api::Locales,
+  },
+  ctx::Ctx,
   model::{
-    attraction::{Attraction, AttractionRating, FullAttraction},
-    attraction_controller::AttractionController,
+    attraction::{
+      Attraction, AttractionAttributes, AttractionRating, FacetCount,
+      FullAttraction, NewAttraction, PriceRange,
+    },
+    attraction_controller::{
+      AttractionController, AttractionFilter, AttractionSchedule,
+      NearbyAttraction, RatingFail,
+    },
+    rating_rules::{IncomingRating, RatingStatus},
+    rating_statistics::{decimal_of, MAX_RATE, MIN_RATE},
+    translation::Translation,
   },
   Error, Result,
 };
 use axum::{
-  extract::{Path, State},
-  routing::get,
+  extract::{Path, Query, State},
+  http::StatusCode,
+  response::{IntoResponse, Redirect, Response},
+  routing::{get, post, put},
   Json, Router,
 };
-use chrono::NaiveDateTime;
-use serde::Serialize;
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use geoutils::Location;
+use serde::{Deserialize, Serialize};
+
+/// The radius of the nearby attractions when it isn't given.
+const DEFAULT_RADIUS_METERS: f64 = 1000.0;
+const MAX_RADIUS_METERS: f64 = 50000.0;
+const DEFAULT_NEARBY_LIMIT: usize = 20;
+const MAX_NEARBY_LIMIT: usize = 100;
 
 #[derive(Clone, Debug, Serialize, Default)]
 pub struct AttractionDto {
@@ -25,6 +50,8 @@ pub struct AttractionDto {
   pub attraction_type_id: Option<i32>,
   pub latitude: Option<String>,
   pub longitude: Option<String>,
+  /// The locale of the description, none when it isn't translated.
+  pub locale: Option<String>,
 }
 
 impl AttractionDto {
@@ -38,6 +65,7 @@ impl AttractionDto {
       attraction_type_id: None,
       latitude: None,
       longitude: None,
+      locale: full_attraction.get_locale(),
     }
   }
 
@@ -51,6 +79,152 @@ impl AttractionDto {
       attraction_type_id: Some(an_attraction.get_attraction_type_id()),
       latitude: an_attraction.get_latitude(),
       longitude: an_attraction.get_longitude(),
+      locale: None,
+    }
+  }
+
+  fn translated(self, a_translation: Option<&Translation>) -> Self {
+    match a_translation {
+      Some(a_translation) => AttractionDto {
+        description: a_translation.description.to_string(),
+        locale: Some(a_translation.locale.to_string()),
+        ..self
+      },
+      None => self,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct NearbyAttractionDto {
+  pub id: i32,
+  pub description: String,
+  pub city_id: i32,
+  pub attraction_type_id: i32,
+  pub latitude: Option<String>,
+  pub longitude: Option<String>,
+  pub distance_meters: Option<BigDecimal>,
+  /// The locale of the description, none when it isn't translated.
+  pub locale: Option<String>,
+}
+
+impl NearbyAttractionDto {
+  fn new(
+    a_nearby: &NearbyAttraction,
+    a_translation: Option<&Translation>,
+  ) -> Self {
+    let an_attraction = &a_nearby.attraction;
+    NearbyAttractionDto {
+      id: an_attraction.get_id(),
+      description: a_translation
+        .map_or(an_attraction.get_description(), |a_translation| {
+          a_translation.description.to_string()
+        }),
+      city_id: an_attraction.get_city_id(),
+      attraction_type_id: an_attraction.get_attraction_type_id(),
+      latitude: an_attraction.get_latitude(),
+      longitude: an_attraction.get_longitude(),
+      distance_meters: decimal_of(a_nearby.distance_meters.round()),
+      locale: a_translation
+        .map(|a_translation| a_translation.locale.to_string()),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct AttributesDto {
+  pub attraction_id: i32,
+  pub price_range: Option<String>,
+  pub visit_minutes: Option<i32>,
+  pub updated_at: Option<NaiveDateTime>,
+}
+
+impl AttributesDto {
+  fn new(attributes: &AttractionAttributes) -> Self {
+    AttributesDto {
+      attraction_id: attributes.get_attraction_id(),
+      price_range: attributes
+        .get_price_range()
+        .map(|a_range| a_range.to_string()),
+      visit_minutes: attributes.get_visit_minutes(),
+      updated_at: Some(attributes.get_updated_at()),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct FacetValueDto {
+  pub value: String,
+  pub description: Option<String>,
+  pub count: i64,
+}
+
+/// The number of attractions by every value of the facets, to filter them.
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct FacetsDto {
+  pub total: i64,
+  pub tags: Vec<FacetValueDto>,
+  pub attraction_types: Vec<FacetValueDto>,
+  pub price_ranges: Vec<FacetValueDto>,
+  pub visit_durations: Vec<FacetValueDto>,
+}
+
+impl FacetsDto {
+  fn new(facets: &[FacetCount]) -> Self {
+    let values_of = |facet: &str| {
+      facets
+        .iter()
+        .filter(|a_count| a_count.facet == facet)
+        .map(|a_count| FacetValueDto {
+          value: a_count.value.clone(),
+          description: a_count.description.clone(),
+          count: a_count.attraction_count,
+        })
+        .collect::<Vec<FacetValueDto>>()
+    };
+    let attraction_types = values_of("ATTRACTION_TYPE");
+    FacetsDto {
+      // Every attraction has a type.
+      total: attraction_types.iter().map(|a_value| a_value.count).sum(),
+      tags: values_of("TAG"),
+      attraction_types,
+      price_ranges: values_of("PRICE_RANGE"),
+      visit_durations: values_of("VISIT_DURATION"),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct OpeningDayDto {
+  pub day: NaiveDate,
+  /// The periods like "09:00-18:00", empty when it's closed.
+  pub hours: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct OpeningHoursDto {
+  pub attraction_id: i32,
+  pub opening_hours: String,
+  pub updated_at: Option<NaiveDateTime>,
+  pub is_open: Option<bool>,
+  pub week: Vec<OpeningDayDto>,
+}
+
+impl OpeningHoursDto {
+  fn new(a_schedule: &AttractionSchedule) -> Self {
+    OpeningHoursDto {
+      attraction_id: a_schedule.opening_hours.get_attraction_id(),
+      opening_hours: a_schedule.opening_hours.get_opening_hours(),
+      updated_at: Some(a_schedule.opening_hours.get_updated_at()),
+      is_open: a_schedule.is_open,
+      week: a_schedule
+        .week
+        .iter()
+        .map(|(day, spans)| OpeningDayDto {
+          day: *day,
+          hours: spans.iter().map(|a_span| a_span.to_string()).collect(),
+        })
+        .collect(),
     }
   }
 }
@@ -61,6 +235,12 @@ pub struct RatingDto {
   pub attraction_id: i32,
   pub at: NaiveDateTime,
   pub rate: bigdecimal::BigDecimal,
+  pub source: String,
+  pub submitter: Option<String>,
+  pub status: String,
+  pub fraud_score: bigdecimal::BigDecimal,
+  pub fraud_reasons: Vec<String>,
+  pub user_id: Option<i32>,
 }
 
 impl RatingDto {
@@ -70,55 +250,331 @@ impl RatingDto {
       attraction_id: a_rating.get_attraction_id(),
       at: a_rating.get_at(),
       rate: a_rating.get_rate(),
+      source: a_rating.get_source(),
+      submitter: a_rating.get_submitter(),
+      status: a_rating.get_status(),
+      fraud_score: a_rating.get_fraud_score(),
+      fraud_reasons: a_rating.get_fraud_reasons(),
+      user_id: a_rating.get_user_id(),
     }
   }
 }
 
+#[derive(Deserialize)]
+struct RateParam {
+  rate: BigDecimal,
+  at: Option<NaiveDateTime>,
+  source: Option<String>,
+  fingerprint: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UserRateParam {
+  rate: BigDecimal,
+}
+
+#[derive(Deserialize)]
+struct UserRatingsParam {
+  attraction_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct AttractionParam {
+  description: String,
+  city_id: i32,
+  attraction_type_id: i32,
+  latitude: Option<String>,
+  longitude: Option<String>,
+}
+
+impl AttractionParam {
+  fn into_new_attraction(self) -> NewAttraction {
+    NewAttraction {
+      description: self.description,
+      city_id: self.city_id,
+      attraction_type_id: self.attraction_type_id,
+      latitude: self.latitude,
+      longitude: self.longitude,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct ReviewParam {
+  status: RatingStatus,
+}
+
+/// The tags separated by commas, like "free,indoor".
+fn tags_of(tags: Option<String>) -> Vec<String> {
+  tags
+    .map(|tags| tags.split(',').map(str::to_string).collect())
+    .unwrap_or_default()
+}
+
+#[derive(Deserialize)]
+struct ListParam {
+  open_at: Option<NaiveDateTime>,
+  tags: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NearbyParam {
+  latitude: f64,
+  longitude: f64,
+  radius_meters: Option<f64>,
+  open_at: Option<NaiveDateTime>,
+  tags: Option<String>,
+  limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct FacetParam {
+  city_id: Option<i32>,
+  tags: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TagsParam {
+  tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct AttributesParam {
+  price_range: Option<PriceRange>,
+  visit_minutes: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct OpeningHoursAtParam {
+  at: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize)]
+struct OpeningHoursParam {
+  opening_hours: String,
+}
+
 /// Defines the endpoints that handles the interaction with the attractions.
 pub fn routes(attraction_controller: Arc<dyn AttractionController>) -> Router {
   Router::new()
+    .route("/attraction", post(create_attraction))
     .route("/attraction/all", get(list))
-    .route("/attraction/:id", get(get_attraction))
-    .route("/attraction/:id/rating", get(rating))
+    .route("/attraction/nearby", get(nearby))
+    .route("/attraction/facets", get(facets))
+    .route(
+      "/attraction/:id",
+      get(get_attraction)
+        .put(update_attraction)
+        .delete(delete_attraction),
+    )
+    .route("/attraction/:id/rating", get(rating).post(rate))
+    .route(
+      "/attraction/:id/opening-hours",
+      get(opening_hours)
+        .put(set_opening_hours)
+        .delete(delete_opening_hours),
+    )
+    .route("/attraction/:id/tags", get(tags).put(set_tags))
+    .route(
+      "/attraction/:id/attributes",
+      get(attributes).put(set_attributes),
+    )
+    .route("/rating/quarantine", get(quarantined_ratings))
+    .route("/rating/:id/status", put(review_rating))
+    .route("/me/ratings", get(user_ratings))
+    .route(
+      "/me/ratings/:id",
+      put(update_user_rating).delete(delete_user_rating),
+    )
     .with_state(attraction_controller)
 }
 
 /// List all the registered attractions.
 ///
 /// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * locales: the locales of the Accept-Language header, to describe the
+///   attractions in the first one with a translation.
+/// * list_param: the optional moment, in the local time of the attractions,
+///   to list only the ones open at it, and the optional tags separated by
+///   commas to list only the ones with all of them.
 /// * attraction_controller: the controller responsible of the actions.
 ///
 /// # Return:
 /// * Ok with a vector of attractions.
 /// * Err with the error.
 async fn list(
+  _authorized: Authorized<CanRead>,
+  Locales(locales): Locales,
+  Query(list_param): Query<ListParam>,
   State(attraction_controller): State<Arc<dyn AttractionController>>,
 ) -> Result<Json<Vec<AttractionDto>>> {
   println!("->> ATTRACTIONS\n");
-  let attractions = attraction_controller.list().await.unwrap_or_default();
+  let filter = AttractionFilter {
+    open_at: list_param.open_at,
+    tags: tags_of(list_param.tags),
+  };
+  let attractions =
+    attraction_controller.list(filter).await.unwrap_or_default();
+  let translations = attraction_controller
+    .translations_of(
+      attractions
+        .iter()
+        .map(|an_attraction| an_attraction.get_id())
+        .collect(),
+      locales,
+    )
+    .await
+    .unwrap_or_default();
   let dtos = attractions
     .iter()
-    .map(AttractionDto::from_entity)
+    .map(|an_attraction| {
+      AttractionDto::from_entity(an_attraction)
+        .translated(translations.get(&an_attraction.get_id()))
+    })
     .collect::<Vec<AttractionDto>>();
   Ok(Json(dtos))
 }
 
+/// List the attractions around a point, the closest first.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * locales: the locales of the Accept-Language header, to describe the
+///   attractions in the first one with a translation.
+/// * nearby_param: the point, the optional radius in meters (1000 by
+///   default), the optional moment in the local time of the attractions to
+///   list only the ones open at it, the optional tags separated by commas
+///   that all of them have, and the most attractions (20 by default).
+/// * attraction_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of attractions with their distance to the point.
+/// * Err with 400 status code if the point, the radius or the limit are out
+///   of range.
+async fn nearby(
+  _authorized: Authorized<CanRead>,
+  Locales(locales): Locales,
+  Query(nearby_param): Query<NearbyParam>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+) -> Result<Json<Vec<NearbyAttractionDto>>> {
+  println!("->> NEARBY ATTRACTIONS\n");
+  if !(-90.0..=90.0).contains(&nearby_param.latitude)
+    || !(-180.0..=180.0).contains(&nearby_param.longitude)
+  {
+    return Err(Error::InvalidLocation {
+      reason: String::from(
+        "The latitude must be between -90 and 90 and the longitude between \
+         -180 and 180",
+      ),
+    });
+  }
+  let radius_meters =
+    nearby_param.radius_meters.unwrap_or(DEFAULT_RADIUS_METERS);
+  if !(radius_meters > 0.0 && radius_meters <= MAX_RADIUS_METERS) {
+    return Err(Error::InvalidLocation {
+      reason: format!(
+        "The radius must be greater than 0 and {MAX_RADIUS_METERS} meters at \
+         most"
+      ),
+    });
+  }
+  let limit = nearby_param.limit.unwrap_or(DEFAULT_NEARBY_LIMIT);
+  if !(1..=MAX_NEARBY_LIMIT).contains(&limit) {
+    return Err(Error::InvalidLocation {
+      reason: format!("The limit must be between 1 and {MAX_NEARBY_LIMIT}"),
+    });
+  }
+  let nearby = attraction_controller
+    .nearby(
+      Location::new(nearby_param.latitude, nearby_param.longitude),
+      radius_meters,
+      AttractionFilter {
+        open_at: nearby_param.open_at,
+        tags: tags_of(nearby_param.tags),
+      },
+      limit,
+    )
+    .await
+    .unwrap_or_default();
+  let translations = attraction_controller
+    .translations_of(
+      nearby
+        .iter()
+        .map(|a_nearby| a_nearby.attraction.get_id())
+        .collect(),
+      locales,
+    )
+    .await
+    .unwrap_or_default();
+  Ok(Json(
+    nearby
+      .iter()
+      .map(|a_nearby| {
+        NearbyAttractionDto::new(
+          a_nearby,
+          translations.get(&a_nearby.attraction.get_id()),
+        )
+      })
+      .collect(),
+  ))
+}
+
+/// Count the attractions by every tag, type, price range and duration of the
+/// visit, to build the filters of the attractions.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * facet_param: the optional city of the attractions, all of them by
+///   default, and the optional tags separated by commas that they have.
+/// * attraction_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the number of attractions and their count by every value of
+///   every facet, the largest first.
+/// * Err with the error.
+async fn facets(
+  _authorized: Authorized<CanRead>,
+  Query(facet_param): Query<FacetParam>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+) -> Result<Json<FacetsDto>> {
+  println!("->> FACETS\n");
+  let facets = attraction_controller
+    .facets(facet_param.city_id, tags_of(facet_param.tags))
+    .await
+    .unwrap_or_default();
+  Ok(Json(FacetsDto::new(&facets)))
+}
+
 /// Retrieve a specific attraction.
 ///
 /// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * locales: the locales of the Accept-Language header, to describe the
+///   attraction, its city and its type in the first one with a translation.
 /// * id: the id of the attraction to be retrieved.
 /// * attraction_controller: the controller responsible of the actions.
 ///
 /// # Return:
 /// * Ok with the attraction that matches the id.
+/// * Ok with 308 status code to the attraction that it was merged into.
 /// * Err with 404 status code.
 async fn get_attraction(
+  _authorized: Authorized<CanRead>,
+  Locales(locales): Locales,
   Path(id): Path<i32>,
   State(attraction_controller): State<Arc<dyn AttractionController>>,
-) -> Result<Json<AttractionDto>> {
+) -> Result<Response> {
   println!("->> ATTRACTION\n");
-  match attraction_controller.get_attraction(id).await {
-    Some(an_attraction) => Ok(Json(AttractionDto::from_full(&an_attraction))),
+  if let Some(an_attraction) =
+    attraction_controller.get_attraction(id, locales).await
+  {
+    return Ok(Json(AttractionDto::from_full(&an_attraction)).into_response());
+  }
+  match attraction_controller.redirect_of(id).await {
+    Some(to_id) => {
+      Ok(Redirect::permanent(&format!("/attraction/{to_id}")).into_response())
+    },
     None => Err(Error::AttractionNotFound {
       id,
     }),
@@ -128,6 +584,7 @@ async fn get_attraction(
 /// List the ratings from particular attraction.
 ///
 /// # Arguments:
+/// * _authorized: the context of the request, any role can read.
 /// * id: the id of the attraction looking for the ratings.
 /// * attraction_controller: the controller responsible of the actions.
 ///
@@ -135,6 +592,7 @@ async fn get_attraction(
 /// * Ok with a vector of ratings from the attraction.
 /// * Err with the error.
 async fn rating(
+  _authorized: Authorized<CanRead>,
   Path(id): Path<i32>,
   State(attraction_controller): State<Arc<dyn AttractionController>>,
 ) -> Result<Json<Vec<RatingDto>>> {
@@ -150,3 +608,1196 @@ async fn rating(
     .collect::<Vec<RatingDto>>();
   Ok(Json(dtos))
 }
+
+/// Validates that the rate is in the range of the ratings.
+pub fn valid_rate(rate: &BigDecimal) -> Result<()> {
+  let in_range = rate
+    .to_f64()
+    .is_some_and(|rate| (MIN_RATE..=MAX_RATE).contains(&rate));
+  if !in_range {
+    return Err(Error::InvalidRating {
+      reason: format!("The rate must be between {MIN_RATE} and {MAX_RATE}"),
+    });
+  }
+  Ok(())
+}
+
+/// The moment of a rating, now unless the request can backfill.
+fn rating_moment(
+  ctx: &Ctx,
+  at: Option<NaiveDateTime>,
+) -> Result<NaiveDateTime> {
+  let now = Utc::now().naive_utc();
+  match at {
+    Some(at) if ctx.can_backfill() && at > now => Err(Error::InvalidRating {
+      reason: format!("The moment {at} is in the future"),
+    }),
+    Some(at) if ctx.can_backfill() => Ok(at),
+    _ => Ok(now),
+  }
+}
+
+/// Register a new rating for an attraction. The rating is reviewed by the
+/// fraud rules and quarantined if it looks suspicious, in that case it is not
+/// aggregated until it is accepted.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the user that rates.
+/// * id: the id of the rated attraction.
+/// * attraction_controller: the controller responsible of the actions.
+/// * rate_param: the rate, the optional moment, the source (API by default)
+///   and the optional device fingerprint. The submitter is always the
+///   authenticated user. The moment is always now, only the api keys with
+///   the ratings:backfill scope can give a past one.
+///
+/// # Return:
+/// * Ok with the registered rating and its status.
+/// * Err with 400 status code if the rate is out of range or the moment is
+///   in the future.
+/// * Err with 403 status code if the request isn't authenticated.
+/// * Err with 404 status code if the attraction doesn't exist.
+/// * Err with 409 status code if the user already rated the attraction that
+///   day.
+/// * Err with 500 status code if the rating can't be saved.
+async fn rate(
+  authorized: Authorized<CanRate>,
+  Path(id): Path<i32>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+  Json(rate_param): Json<RateParam>,
+) -> Result<Json<RatingDto>> {
+  println!("->> RATE\n");
+  valid_rate(&rate_param.rate)?;
+  let incoming_rating = IncomingRating {
+    attraction_id: id,
+    rate: rate_param.rate.normalized(),
+    at: rating_moment(&authorized.ctx, rate_param.at)?,
+    source: rate_param.source.unwrap_or(String::from("API")),
+    submitter: Some(authorized.ctx.username()),
+    fingerprint: rate_param.fingerprint,
+    user_id: authorized.ctx.rater_id(),
+  };
+  match attraction_controller.rate(incoming_rating).await {
+    Ok(Some(a_rating)) => Ok(Json(RatingDto::from_entity(&a_rating))),
+    Ok(None) => Err(Error::AttractionNotFound {
+      id,
+    }),
+    Err(RatingFail::Duplicate(reason)) => {
+      println!("xx->> {}", reason);
+      Err(Error::DuplicateRating {
+        reason,
+      })
+    },
+    Err(RatingFail::Service(e)) => {
+      println!("xx->> {}", e);
+      Err(Error::RateFail)
+    },
+  }
+}
+
+/// List the ratings waiting for a manual review, the latest first.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, it must be an editor.
+/// * attraction_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of quarantined ratings.
+/// * Err with 403 status code if the user isn't an editor.
+async fn quarantined_ratings(
+  _authorized: Authorized<CanEdit>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+) -> Result<Json<Vec<RatingDto>>> {
+  println!("->> QUARANTINED RATINGS\n");
+  let ratings = attraction_controller
+    .ratings_with_status(RatingStatus::Quarantined)
+    .await
+    .unwrap_or_default();
+  let dtos = ratings
+    .iter()
+    .map(RatingDto::from_entity)
+    .collect::<Vec<RatingDto>>();
+  Ok(Json(dtos))
+}
+
+/// Accept or reject a rating after a manual review.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, it must be an editor.
+/// * id: the id of the reviewed rating.
+/// * attraction_controller: the controller responsible of the actions.
+/// * review_param: the new status of the rating.
+///
+/// # Return:
+/// * Ok with the reviewed rating.
+/// * Err with 403 status code if the user isn't an editor.
+/// * Err with 404 status code if the rating doesn't exist.
+async fn review_rating(
+  _authorized: Authorized<CanEdit>,
+  Path(id): Path<i32>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+  Json(review_param): Json<ReviewParam>,
+) -> Result<Json<RatingDto>> {
+  println!("->> REVIEW RATING\n");
+  match attraction_controller
+    .review_rating(id, review_param.status)
+    .await
+  {
+    Some(a_rating) => Ok(Json(RatingDto::from_entity(&a_rating))),
+    None => Err(Error::RatingNotFound {
+      id,
+    }),
+  }
+}
+
+/// Register a new attraction.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * attraction_controller: the controller responsible of the actions.
+/// * attraction_param: the description, the city, the type and the optional
+///   latitude and longitude, given together.
+///
+/// # Return:
+/// * Ok with 201 status code and the new attraction.
+/// * Err with 400 status code if the values are invalid or the city or the
+///   type don't exist.
+/// * Err with 403 status code if the user isn't an editor.
+async fn create_attraction(
+  authorized: Authorized<CanEdit>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+  Json(attraction_param): Json<AttractionParam>,
+) -> Result<(StatusCode, Json<AttractionDto>)> {
+  println!("->> CREATE ATTRACTION\n");
+  match attraction_controller
+    .create(
+      attraction_param.into_new_attraction(),
+      authorized.ctx.actor(),
+    )
+    .await
+  {
+    Ok(an_attraction) => Ok((
+      StatusCode::CREATED,
+      Json(AttractionDto::from_entity(&an_attraction)),
+    )),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidAttraction {
+        reason: e,
+      })
+    },
+  }
+}
+
+/// Replace the values of an attraction.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * id: the id of the attraction.
+/// * attraction_controller: the controller responsible of the actions.
+/// * attraction_param: the new values of the attraction.
+///
+/// # Return:
+/// * Ok with the updated attraction.
+/// * Err with 400 status code if the values are invalid or the city or the
+///   type don't exist.
+/// * Err with 403 status code if the user isn't an editor.
+/// * Err with 404 status code if the attraction doesn't exist.
+async fn update_attraction(
+  authorized: Authorized<CanEdit>,
+  Path(id): Path<i32>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+  Json(attraction_param): Json<AttractionParam>,
+) -> Result<Json<AttractionDto>> {
+  println!("->> UPDATE ATTRACTION\n");
+  match attraction_controller
+    .update(
+      id,
+      attraction_param.into_new_attraction(),
+      authorized.ctx.actor(),
+    )
+    .await
+  {
+    Ok(Some(an_attraction)) => {
+      Ok(Json(AttractionDto::from_entity(&an_attraction)))
+    },
+    Ok(None) => Err(Error::AttractionNotFound {
+      id,
+    }),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidAttraction {
+        reason: e,
+      })
+    },
+  }
+}
+
+/// Delete an attraction softly. It's hidden with its ratings until an admin
+/// restores it.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an admin.
+/// * id: the id of the attraction.
+/// * attraction_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with 204 status code.
+/// * Err with 403 status code if the user isn't an admin.
+/// * Err with 404 status code if the attraction doesn't exist or is already
+///   deleted.
+async fn delete_attraction(
+  authorized: Authorized<CanAdmin>,
+  Path(id): Path<i32>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+) -> Result<StatusCode> {
+  println!("->> DELETE ATTRACTION\n");
+  match attraction_controller
+    .delete(id, authorized.ctx.actor())
+    .await
+  {
+    Ok(true) => Ok(StatusCode::NO_CONTENT),
+    Ok(false) => Err(Error::AttractionNotFound {
+      id,
+    }),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::DeleteAttractionFail {
+        id,
+      })
+    },
+  }
+}
+
+/// List the ratings of the user, the latest first.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the user that rated.
+/// * user_ratings_param: the optional attraction of the ratings.
+/// * attraction_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of the ratings of the user.
+/// * Err with 403 status code if the request isn't authenticated.
+async fn user_ratings(
+  authorized: Authorized<CanRead>,
+  Query(user_ratings_param): Query<UserRatingsParam>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+) -> Result<Json<Vec<RatingDto>>> {
+  println!("->> USER RATINGS\n");
+  let Some(user_id) = authorized.ctx.rater_id() else {
+    return Ok(Json(Vec::new()));
+  };
+  let ratings = attraction_controller
+    .ratings_of_user(user_id, user_ratings_param.attraction_id)
+    .await
+    .unwrap_or_default();
+  Ok(Json(ratings.iter().map(RatingDto::from_entity).collect()))
+}
+
+/// Change the rate of a rating of the user. The statistics of its period are
+/// calculated again.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the user that rated.
+/// * id: the id of the rating.
+/// * attraction_controller: the controller responsible of the actions.
+/// * user_rate_param: the new rate.
+///
+/// # Return:
+/// * Ok with the updated rating.
+/// * Err with 400 status code if the rate is out of range.
+/// * Err with 403 status code if the request isn't authenticated.
+/// * Err with 404 status code if the rating doesn't exist or belongs to
+///   another user.
+async fn update_user_rating(
+  authorized: Authorized<CanRate>,
+  Path(id): Path<i32>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+  Json(user_rate_param): Json<UserRateParam>,
+) -> Result<Json<RatingDto>> {
+  println!("->> UPDATE USER RATING\n");
+  valid_rate(&user_rate_param.rate)?;
+  let Some(user_id) = authorized.ctx.rater_id() else {
+    return Err(Error::RatingNotFound {
+      id,
+    });
+  };
+  attraction_controller
+    .update_user_rating(id, user_id, user_rate_param.rate.normalized())
+    .await
+    .map(|a_rating| Json(RatingDto::from_entity(&a_rating)))
+    .ok_or(Error::RatingNotFound {
+      id,
+    })
+}
+
+/// Delete a rating of the user. The statistics of its period are calculated
+/// again.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the user that rated.
+/// * id: the id of the rating.
+/// * attraction_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with 204 status code.
+/// * Err with 403 status code if the request isn't authenticated.
+/// * Err with 404 status code if the rating doesn't exist or belongs to
+///   another user.
+async fn delete_user_rating(
+  authorized: Authorized<CanRate>,
+  Path(id): Path<i32>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+) -> Result<StatusCode> {
+  println!("->> DELETE USER RATING\n");
+  let deleted = match authorized.ctx.rater_id() {
+    Some(user_id) => attraction_controller
+      .delete_user_rating(id, user_id)
+      .await
+      .unwrap_or_default(),
+    None => false,
+  };
+  if deleted {
+    Ok(StatusCode::NO_CONTENT)
+  } else {
+    Err(Error::RatingNotFound {
+      id,
+    })
+  }
+}
+
+/// Retrieve the opening hours of an attraction, with its hours in the seven
+/// days from the moment.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * id: the id of the attraction.
+/// * at_param: the optional moment in the local time of the attraction, to
+///   know whether it's open at it. Today by default, without checking it.
+/// * attraction_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the opening hours and whether it's open at the moment.
+/// * Err with 404 status code if the attraction doesn't exist or has no
+///   opening hours.
+async fn opening_hours(
+  _authorized: Authorized<CanRead>,
+  Path(id): Path<i32>,
+  Query(at_param): Query<OpeningHoursAtParam>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+) -> Result<Json<OpeningHoursDto>> {
+  println!("->> OPENING HOURS\n");
+  attraction_controller
+    .opening_hours(id, at_param.at)
+    .await
+    .map(|a_schedule| Json(OpeningHoursDto::new(&a_schedule)))
+    .ok_or(Error::OpeningHoursNotFound {
+      id,
+    })
+}
+
+/// Set the opening hours of an attraction in the OSM opening_hours syntax,
+/// like "Mo-Fr 09:00-18:00; Sa 10:00-14:00; PH off". The public holidays are
+/// the ones of the country of the attraction.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * id: the id of the attraction.
+/// * attraction_controller: the controller responsible of the actions.
+/// * opening_hours_param: the opening hours.
+///
+/// # Return:
+/// * Ok with the opening hours.
+/// * Err with 400 status code if the opening hours can't be parsed.
+/// * Err with 403 status code if the user isn't an editor.
+/// * Err with 404 status code if the attraction doesn't exist.
+async fn set_opening_hours(
+  authorized: Authorized<CanEdit>,
+  Path(id): Path<i32>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+  Json(opening_hours_param): Json<OpeningHoursParam>,
+) -> Result<Json<OpeningHoursDto>> {
+  println!("->> SET OPENING HOURS\n");
+  match attraction_controller
+    .set_opening_hours(
+      id,
+      opening_hours_param.opening_hours,
+      authorized.ctx.actor(),
+    )
+    .await
+  {
+    Ok(Some(saved)) => Ok(Json(OpeningHoursDto {
+      attraction_id: saved.get_attraction_id(),
+      opening_hours: saved.get_opening_hours(),
+      updated_at: Some(saved.get_updated_at()),
+      ..Default::default()
+    })),
+    Ok(None) => Err(Error::AttractionNotFound {
+      id,
+    }),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidOpeningHours {
+        reason: e,
+      })
+    },
+  }
+}
+
+/// Remove the opening hours of an attraction.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * id: the id of the attraction.
+/// * attraction_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with 204 status code.
+/// * Err with 403 status code if the user isn't an editor.
+/// * Err with 404 status code if the attraction has no opening hours.
+async fn delete_opening_hours(
+  authorized: Authorized<CanEdit>,
+  Path(id): Path<i32>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+) -> Result<StatusCode> {
+  println!("->> DELETE OPENING HOURS\n");
+  if attraction_controller
+    .delete_opening_hours(id, authorized.ctx.actor())
+    .await
+    .unwrap_or_default()
+  {
+    Ok(StatusCode::NO_CONTENT)
+  } else {
+    Err(Error::OpeningHoursNotFound {
+      id,
+    })
+  }
+}
+
+/// List the tags of an attraction.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * id: the id of the attraction.
+/// * attraction_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of tags sorted by code.
+/// * Err with 404 status code if the attraction doesn't exist.
+async fn tags(
+  _authorized: Authorized<CanRead>,
+  Path(id): Path<i32>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+) -> Result<Json<Vec<TagDto>>> {
+  println!("->> ATTRACTION TAGS\n");
+  attraction_controller
+    .tags_of(id)
+    .await
+    .map(|tags| Json(tags.iter().map(TagDto::new).collect()))
+    .ok_or(Error::AttractionNotFound {
+      id,
+    })
+}
+
+/// Replace the tags of an attraction.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * id: the id of the attraction.
+/// * attraction_controller: the controller responsible of the actions.
+/// * tags_param: the codes of the tags, empty to remove all of them.
+///
+/// # Return:
+/// * Ok with the new tags of the attraction.
+/// * Err with 400 status code if a tag doesn't exist.
+/// * Err with 403 status code if the user isn't an editor.
+/// * Err with 404 status code if the attraction doesn't exist.
+async fn set_tags(
+  authorized: Authorized<CanEdit>,
+  Path(id): Path<i32>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+  Json(tags_param): Json<TagsParam>,
+) -> Result<Json<Vec<TagDto>>> {
+  println!("->> SET ATTRACTION TAGS\n");
+  match attraction_controller
+    .set_tags(id, tags_param.tags, authorized.ctx.actor())
+    .await
+  {
+    Ok(Some(tags)) => Ok(Json(tags.iter().map(TagDto::new).collect())),
+    Ok(None) => Err(Error::AttractionNotFound {
+      id,
+    }),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidAttraction {
+        reason: e,
+      })
+    },
+  }
+}
+
+/// Retrieve the attributes of an attraction.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * id: the id of the attraction.
+/// * attraction_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the price range and the typical minutes of a visit.
+/// * Err with 404 status code if the attraction doesn't exist or has no
+///   attributes.
+async fn attributes(
+  _authorized: Authorized<CanRead>,
+  Path(id): Path<i32>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+) -> Result<Json<AttributesDto>> {
+  println!("->> ATTRACTION ATTRIBUTES\n");
+  attraction_controller
+    .attributes_of(id)
+    .await
+    .map(|attributes| Json(AttributesDto::new(&attributes)))
+    .ok_or(Error::AttributesNotFound {
+      id,
+    })
+}
+
+/// Replace the attributes of an attraction.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * id: the id of the attraction.
+/// * attraction_controller: the controller responsible of the actions.
+/// * attributes_param: the optional price range (FREE, LOW, MEDIUM or HIGH)
+///   and typical minutes of a visit.
+///
+/// # Return:
+/// * Ok with the new attributes.
+/// * Err with 400 status code if the minutes aren't positive.
+/// * Err with 403 status code if the user isn't an editor.
+/// * Err with 404 status code if the attraction doesn't exist.
+async fn set_attributes(
+  authorized: Authorized<CanEdit>,
+  Path(id): Path<i32>,
+  State(attraction_controller): State<Arc<dyn AttractionController>>,
+  Json(attributes_param): Json<AttributesParam>,
+) -> Result<Json<AttributesDto>> {
+  println!("->> SET ATTRACTION ATTRIBUTES\n");
+  match attraction_controller
+    .set_attributes(
+      id,
+      attributes_param.price_range,
+      attributes_param.visit_minutes,
+      authorized.ctx.actor(),
+    )
+    .await
+  {
+    Ok(Some(attributes)) => Ok(Json(AttributesDto::new(&attributes))),
+    Ok(None) => Err(Error::AttractionNotFound {
+      id,
+    }),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidAttraction {
+        reason: e,
+      })
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    application::mw_auth::Permission,
+    model::{
+      api_key::ApiScope,
+      attraction::{AttractionOpeningHours, Tag},
+      audit::Actor,
+      role::Role,
+    },
+  };
+  use async_trait::async_trait;
+  use axum::{
+    extract::FromRequestParts,
+    http::{header::LOCATION, Request},
+  };
+  use chrono::Duration;
+  use std::{collections::HashMap, str::FromStr, sync::Mutex};
+
+  /// The attraction 2 was merged into the 1, no attraction is found by its
+  /// id.
+  struct MergedAttractions;
+
+  #[async_trait]
+  impl AttractionController for MergedAttractions {
+    async fn list(&self, _: AttractionFilter) -> Option<Vec<Attraction>> {
+      todo!()
+    }
+
+    async fn nearby(
+      &self,
+      _: Location,
+      _: f64,
+      _: AttractionFilter,
+      _: usize,
+    ) -> Option<Vec<NearbyAttraction>> {
+      todo!()
+    }
+
+    async fn get_attraction(
+      &self,
+      _: i32,
+      _: Vec<String>,
+    ) -> Option<FullAttraction> {
+      None
+    }
+
+    async fn translations_of(
+      &self,
+      _: Vec<i32>,
+      _: Vec<String>,
+    ) -> Option<HashMap<i32, Translation>> {
+      todo!()
+    }
+
+    async fn redirect_of(&self, id: i32) -> Option<i32> {
+      (id == 2).then_some(1)
+    }
+
+    async fn ratings_for(&self, _: i32) -> Option<Vec<AttractionRating>> {
+      todo!()
+    }
+
+    async fn rate(
+      &self,
+      _: IncomingRating,
+    ) -> std::result::Result<Option<AttractionRating>, RatingFail> {
+      todo!()
+    }
+
+    async fn ratings_with_status(
+      &self,
+      _: RatingStatus,
+    ) -> Option<Vec<AttractionRating>> {
+      todo!()
+    }
+
+    async fn review_rating(
+      &self,
+      _: i32,
+      _: RatingStatus,
+    ) -> Option<AttractionRating> {
+      todo!()
+    }
+
+    async fn create(
+      &self,
+      _: NewAttraction,
+      _: Actor,
+    ) -> std::result::Result<Attraction, String> {
+      todo!()
+    }
+
+    async fn update(
+      &self,
+      _: i32,
+      _: NewAttraction,
+      _: Actor,
+    ) -> std::result::Result<Option<Attraction>, String> {
+      todo!()
+    }
+
+    async fn delete(
+      &self,
+      _: i32,
+      _: Actor,
+    ) -> std::result::Result<bool, String> {
+      todo!()
+    }
+
+    async fn ratings_of_user(
+      &self,
+      _: i32,
+      _: Option<i32>,
+    ) -> Option<Vec<AttractionRating>> {
+      todo!()
+    }
+
+    async fn update_user_rating(
+      &self,
+      _: i32,
+      _: i32,
+      _: BigDecimal,
+    ) -> Option<AttractionRating> {
+      todo!()
+    }
+
+    async fn delete_user_rating(&self, _: i32, _: i32) -> Option<bool> {
+      todo!()
+    }
+
+    async fn opening_hours(
+      &self,
+      _: i32,
+      _: Option<NaiveDateTime>,
+    ) -> Option<AttractionSchedule> {
+      todo!()
+    }
+
+    async fn set_opening_hours(
+      &self,
+      _: i32,
+      _: String,
+      _: Actor,
+    ) -> std::result::Result<Option<AttractionOpeningHours>, String> {
+      todo!()
+    }
+
+    async fn delete_opening_hours(&self, _: i32, _: Actor) -> Option<bool> {
+      todo!()
+    }
+
+    async fn tags_of(&self, _: i32) -> Option<Vec<Tag>> {
+      todo!()
+    }
+
+    async fn set_tags(
+      &self,
+      _: i32,
+      _: Vec<String>,
+      _: Actor,
+    ) -> std::result::Result<Option<Vec<Tag>>, String> {
+      todo!()
+    }
+
+    async fn attributes_of(&self, _: i32) -> Option<AttractionAttributes> {
+      todo!()
+    }
+
+    async fn set_attributes(
+      &self,
+      _: i32,
+      _: Option<PriceRange>,
+      _: Option<i32>,
+      _: Actor,
+    ) -> std::result::Result<Option<AttractionAttributes>, String> {
+      todo!()
+    }
+
+    async fn facets(
+      &self,
+      _: Option<i32>,
+      _: Vec<String>,
+    ) -> Option<Vec<FacetCount>> {
+      todo!()
+    }
+  }
+
+  /// The ratings in memory. A user, or a partner through its keys, can rate
+  /// an attraction once a day.
+  #[derive(Default)]
+  struct RatedAttractions {
+    ratings: Mutex<Vec<AttractionRating>>,
+  }
+
+  #[async_trait]
+  impl AttractionController for RatedAttractions {
+    async fn list(&self, _: AttractionFilter) -> Option<Vec<Attraction>> {
+      todo!()
+    }
+
+    async fn nearby(
+      &self,
+      _: Location,
+      _: f64,
+      _: AttractionFilter,
+      _: usize,
+    ) -> Option<Vec<NearbyAttraction>> {
+      todo!()
+    }
+
+    async fn get_attraction(
+      &self,
+      _: i32,
+      _: Vec<String>,
+    ) -> Option<FullAttraction> {
+      todo!()
+    }
+
+    async fn translations_of(
+      &self,
+      _: Vec<i32>,
+      _: Vec<String>,
+    ) -> Option<HashMap<i32, Translation>> {
+      todo!()
+    }
+
+    async fn redirect_of(&self, _: i32) -> Option<i32> {
+      todo!()
+    }
+
+    async fn ratings_for(&self, _: i32) -> Option<Vec<AttractionRating>> {
+      todo!()
+    }
+
+    async fn rate(
+      &self,
+      rating: IncomingRating,
+    ) -> std::result::Result<Option<AttractionRating>, RatingFail> {
+      let mut ratings = self.ratings.lock().unwrap();
+      let rated_that_day = ratings.iter().any(|a_rating| {
+        a_rating.attraction_id == rating.attraction_id
+          && a_rating.submitter == rating.submitter
+          && a_rating.at.date() == rating.at.date()
+      });
+      if rated_that_day {
+        return Err(RatingFail::Duplicate(String::from("Rated that day")));
+      }
+      let registered = AttractionRating {
+        id: ratings.len() as i32 + 1,
+        at: rating.at,
+        attraction_id: rating.attraction_id,
+        rate: rating.rate,
+        updated_at: rating.at,
+        source: rating.source,
+        submitter: rating.submitter,
+        fingerprint: rating.fingerprint,
+        status: RatingStatus::Accepted.to_string(),
+        fraud_score: BigDecimal::default(),
+        fraud_reasons: vec![],
+        user_id: rating.user_id,
+      };
+      ratings.push(registered.clone());
+      Ok(Some(registered))
+    }
+
+    async fn ratings_with_status(
+      &self,
+      _: RatingStatus,
+    ) -> Option<Vec<AttractionRating>> {
+      todo!()
+    }
+
+    async fn review_rating(
+      &self,
+      _: i32,
+      _: RatingStatus,
+    ) -> Option<AttractionRating> {
+      todo!()
+    }
+
+    async fn create(
+      &self,
+      _: NewAttraction,
+      _: Actor,
+    ) -> std::result::Result<Attraction, String> {
+      todo!()
+    }
+
+    async fn update(
+      &self,
+      _: i32,
+      _: NewAttraction,
+      _: Actor,
+    ) -> std::result::Result<Option<Attraction>, String> {
+      todo!()
+    }
+
+    async fn delete(
+      &self,
+      _: i32,
+      _: Actor,
+    ) -> std::result::Result<bool, String> {
+      todo!()
+    }
+
+    async fn ratings_of_user(
+      &self,
+      user_id: i32,
+      attraction_id: Option<i32>,
+    ) -> Option<Vec<AttractionRating>> {
+      let ratings = self.ratings.lock().unwrap();
+      Some(
+        ratings
+          .iter()
+          .filter(|a_rating| a_rating.user_id == Some(user_id))
+          .filter(|a_rating| {
+            attraction_id.is_none_or(|id| a_rating.attraction_id == id)
+          })
+          .cloned()
+          .collect(),
+      )
+    }
+
+    async fn update_user_rating(
+      &self,
+      id: i32,
+      user_id: i32,
+      rate: BigDecimal,
+    ) -> Option<AttractionRating> {
+      let mut ratings = self.ratings.lock().unwrap();
+      let a_rating = ratings.iter_mut().find(|a_rating| {
+        a_rating.id == id && a_rating.user_id == Some(user_id)
+      })?;
+      a_rating.rate = rate;
+      Some(a_rating.clone())
+    }
+
+    async fn delete_user_rating(&self, id: i32, user_id: i32) -> Option<bool> {
+      let mut ratings = self.ratings.lock().unwrap();
+      let before = ratings.len();
+      ratings.retain(|a_rating| {
+        a_rating.id != id || a_rating.user_id != Some(user_id)
+      });
+      Some(ratings.len() < before)
+    }
+
+    async fn opening_hours(
+      &self,
+      _: i32,
+      _: Option<NaiveDateTime>,
+    ) -> Option<AttractionSchedule> {
+      todo!()
+    }
+
+    async fn set_opening_hours(
+      &self,
+      _: i32,
+      _: String,
+      _: Actor,
+    ) -> std::result::Result<Option<AttractionOpeningHours>, String> {
+      todo!()
+    }
+
+    async fn delete_opening_hours(&self, _: i32, _: Actor) -> Option<bool> {
+      todo!()
+    }
+
+    async fn tags_of(&self, _: i32) -> Option<Vec<Tag>> {
+      todo!()
+    }
+
+    async fn set_tags(
+      &self,
+      _: i32,
+      _: Vec<String>,
+      _: Actor,
+    ) -> std::result::Result<Option<Vec<Tag>>, String> {
+      todo!()
+    }
+
+    async fn attributes_of(&self, _: i32) -> Option<AttractionAttributes> {
+      todo!()
+    }
+
+    async fn set_attributes(
+      &self,
+      _: i32,
+      _: Option<PriceRange>,
+      _: Option<i32>,
+      _: Actor,
+    ) -> std::result::Result<Option<AttractionAttributes>, String> {
+      todo!()
+    }
+
+    async fn facets(
+      &self,
+      _: Option<i32>,
+      _: Vec<String>,
+    ) -> Option<Vec<FacetCount>> {
+      todo!()
+    }
+  }
+
+  async fn authorized<P: Permission>(ctx: Ctx) -> Authorized<P> {
+    let (mut parts, _) = Request::new(()).into_parts();
+    let ctx: Result<Ctx> = Ok(ctx);
+    parts.extensions.insert(ctx);
+    Authorized::<P>::from_request_parts(&mut parts, &())
+      .await
+      .unwrap()
+  }
+
+  async fn get(id: i32) -> Result<Response> {
+    get_attraction(
+      authorized(Ctx::new(1, String::from("ana"), Role::Viewer)).await,
+      Locales(Vec::new()),
+      Path(id),
+      State(Arc::new(MergedAttractions)),
+    )
+    .await
+  }
+
+  #[tokio::test]
+  async fn a_merged_attraction_redirects_to_the_surviving_one() {
+    let response = get(2).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(response.headers()[LOCATION], "/attraction/1");
+  }
+
+  #[tokio::test]
+  async fn an_attraction_that_wasnt_merged_isnt_found() {
+    assert!(matches!(
+      get(3).await,
+      Err(Error::AttractionNotFound {
+        id: 3
+      })
+    ));
+  }
+
+  fn ana() -> Ctx {
+    Ctx::new(1, String::from("ana"), Role::Viewer)
+  }
+
+  fn bob() -> Ctx {
+    Ctx::new(2, String::from("bob"), Role::Viewer)
+  }
+
+  /// A key of the partner owned by ana.
+  fn a_partner_key(scopes: Vec<ApiScope>) -> Ctx {
+    ana().with_api_key(7, scopes)
+  }
+
+  fn a_rate(rate: &str, at: Option<NaiveDateTime>) -> Json<RateParam> {
+    Json(RateParam {
+      rate: BigDecimal::from_str(rate).unwrap(),
+      at,
+      source: None,
+      fingerprint: None,
+    })
+  }
+
+  async fn rate_as(
+    ctx: Ctx,
+    controller: &Arc<RatedAttractions>,
+    rate_param: Json<RateParam>,
+  ) -> Result<Json<RatingDto>> {
+    rate(
+      authorized(ctx).await,
+      Path(1),
+      State(controller.clone() as Arc<dyn AttractionController>),
+      rate_param,
+    )
+    .await
+  }
+
+  #[tokio::test]
+  async fn a_second_rating_on_the_same_day_is_a_conflict() {
+    let controller = Arc::new(RatedAttractions::default());
+    assert!(rate_as(ana(), &controller, a_rate("0.8", None))
+      .await
+      .is_ok());
+
+    let Err(e) = rate_as(ana(), &controller, a_rate("0.4", None)).await else {
+      panic!("The second rating of the day was registered");
+    };
+    assert!(matches!(e, Error::DuplicateRating { .. }));
+    assert_eq!(StatusCode::CONFLICT, e.client_status_and_error().0);
+  }
+
+  #[test]
+  fn only_a_key_with_the_backfill_scope_rates_in_the_past() {
+    let past = Utc::now().naive_utc() - Duration::days(30);
+    let backfill =
+      a_partner_key(vec![ApiScope::RatingsWrite, ApiScope::RatingsBackfill]);
+    assert_eq!(past, rating_moment(&backfill, Some(past)).unwrap());
+
+    let without_backfill = a_partner_key(vec![ApiScope::RatingsWrite]);
+    for ctx in [ana(), without_backfill] {
+      assert!(rating_moment(&ctx, Some(past)).unwrap() > past);
+    }
+  }
+
+  #[test]
+  fn a_rating_cant_be_backfilled_in_the_future() {
+    let future = Utc::now().naive_utc() + Duration::days(1);
+    let backfill =
+      a_partner_key(vec![ApiScope::RatingsWrite, ApiScope::RatingsBackfill]);
+    assert!(matches!(
+      rating_moment(&backfill, Some(future)),
+      Err(Error::InvalidRating { .. })
+    ));
+  }
+
+  #[tokio::test]
+  async fn the_ratings_of_a_key_have_no_user() {
+    let controller = Arc::new(RatedAttractions::default());
+    let of_key = rate_as(
+      a_partner_key(vec![ApiScope::RatingsWrite]),
+      &controller,
+      a_rate("0.8", None),
+    )
+    .await
+    .unwrap();
+    assert_eq!(None, of_key.user_id);
+
+    let of_user = rate_as(bob(), &controller, a_rate("0.8", None))
+      .await
+      .unwrap();
+    assert_eq!(Some(2), of_user.user_id);
+
+    let Json(listed) = user_ratings(
+      authorized(a_partner_key(vec![ApiScope::AttractionsRead])).await,
+      Query(UserRatingsParam {
+        attraction_id: None,
+      }),
+      State(controller.clone() as Arc<dyn AttractionController>),
+    )
+    .await
+    .unwrap();
+    assert!(listed.is_empty());
+  }
+
+  #[tokio::test]
+  async fn a_user_only_changes_its_own_ratings() {
+    let controller = Arc::new(RatedAttractions::default());
+    let Json(of_bob) = rate_as(bob(), &controller, a_rate("0.8", None))
+      .await
+      .unwrap();
+    let state = || State(controller.clone() as Arc<dyn AttractionController>);
+    let new_rate = || {
+      Json(UserRateParam {
+        rate: BigDecimal::from_str("0.2").unwrap(),
+      })
+    };
+
+    let updated = update_user_rating(
+      authorized(ana()).await,
+      Path(of_bob.id),
+      state(),
+      new_rate(),
+    )
+    .await;
+    assert!(matches!(updated, Err(Error::RatingNotFound { .. })));
+    let deleted =
+      delete_user_rating(authorized(ana()).await, Path(of_bob.id), state())
+        .await;
+    assert!(matches!(deleted, Err(Error::RatingNotFound { .. })));
+    let Json(listed) = user_ratings(
+      authorized(ana()).await,
+      Query(UserRatingsParam {
+        attraction_id: None,
+      }),
+      state(),
+    )
+    .await
+    .unwrap();
+    assert!(listed.is_empty());
+
+    let Json(updated) = update_user_rating(
+      authorized(bob()).await,
+      Path(of_bob.id),
+      state(),
+      new_rate(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(BigDecimal::from_str("0.2").unwrap(), updated.rate);
+    let deleted =
+      delete_user_rating(authorized(bob()).await, Path(of_bob.id), state())
+        .await
+        .unwrap();
+    assert_eq!(StatusCode::NO_CONTENT, deleted);
+  }
+}
@@ -0,0 +1,245 @@
+use crate::{
+  application::mw_auth::{Authorized, CanAdmin},
+  model::{
+    audit::{AuditAction, AuditEntry, AuditFilter, AuditedEntity},
+    audit_controller::{AuditController, DEFAULT_AUDIT_LIMIT},
+  },
+  Error, Result,
+};
+use axum::{
+  extract::{Path, Query, State},
+  http::StatusCode,
+  routing::{get, post},
+  Json, Router,
+};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct AuditEntryDto {
+  pub id: i32,
+  pub entity: String,
+  pub entity_id: i32,
+  pub action: String,
+  pub actor_id: Option<i32>,
+  pub actor: Option<String>,
+  pub before: Option<Value>,
+  pub after: Option<Value>,
+  pub changes: Value,
+  pub recorded_at: NaiveDateTime,
+}
+
+impl AuditEntryDto {
+  fn new(an_entry: &AuditEntry) -> Self {
+    AuditEntryDto {
+      id: an_entry.id,
+      entity: an_entry.entity.clone(),
+      entity_id: an_entry.entity_id,
+      action: an_entry.action.clone(),
+      actor_id: an_entry.actor_id,
+      actor: an_entry.actor.clone(),
+      before: an_entry.before.clone(),
+      after: an_entry.after.clone(),
+      changes: an_entry.changes.clone(),
+      recorded_at: an_entry.recorded_at,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct AuditParam {
+  entity: Option<AuditedEntity>,
+  entity_id: Option<i32>,
+  actor_id: Option<i32>,
+  action: Option<AuditAction>,
+  before_id: Option<i32>,
+  limit: Option<usize>,
+}
+
+impl AuditParam {
+  fn into_filter(self) -> AuditFilter {
+    AuditFilter {
+      entity: self.entity,
+      entity_id: self.entity_id,
+      actor_id: self.actor_id,
+      action: self.action,
+      before_id: self.before_id,
+      limit: self.limit.unwrap_or(DEFAULT_AUDIT_LIMIT),
+    }
+  }
+}
+
+/// Defines the endpoints that handles the history of the catalogue and the
+/// restore of its deleted rows.
+pub fn routes(audit_controller: Arc<dyn AuditController>) -> Router {
+  Router::new()
+    .route("/admin/audit", get(history))
+    .route("/admin/restore/:entity/:id", post(restore))
+    .with_state(audit_controller)
+}
+
+/// Browse the writes of the catalogue, the latest first. The writes of the
+/// tags, attributes, opening hours and translations of a row are under its
+/// id, like the ones of the attraction_tag entity.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, it must be an admin.
+/// * audit_param: the optional entity, like "attraction-type", the id of
+///   the row, the user that wrote, the action, the id to page before and the
+///   limit of entries, 50 by default.
+/// * audit_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of the entries, with the rows before and after the
+///   write and the changed columns.
+/// * Err with 400 status code if the limit is out of range.
+/// * Err with 403 status code if the user isn't an admin.
+async fn history(
+  _authorized: Authorized<CanAdmin>,
+  Query(audit_param): Query<AuditParam>,
+  State(audit_controller): State<Arc<dyn AuditController>>,
+) -> Result<Json<Vec<AuditEntryDto>>> {
+  println!("->> AUDIT\n");
+  match audit_controller.history(audit_param.into_filter()).await {
+    Ok(entries) => Ok(Json(entries.iter().map(AuditEntryDto::new).collect())),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidAudit {
+        reason: e,
+      })
+    },
+  }
+}
+
+/// Restore a deleted row of the catalogue.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an admin.
+/// * entity: the entity of the row, like "attraction" or "attraction-type".
+/// * id: the id of the row.
+/// * audit_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with 204 status code.
+/// * Err with 400 status code if the rows of the entity aren't deleted
+///   softly.
+/// * Err with 403 status code if the user isn't an admin.
+/// * Err with 404 status code if the row doesn't exist or isn't deleted.
+/// * Err with 409 status code if the row references a deleted one, or a row
+///   with the same code was created after deleting it.
+async fn restore(
+  authorized: Authorized<CanAdmin>,
+  Path((entity, id)): Path<(AuditedEntity, i32)>,
+  State(audit_controller): State<Arc<dyn AuditController>>,
+) -> Result<StatusCode> {
+  println!("->> RESTORE\n");
+  if !entity.deletes_softly() {
+    return Err(Error::InvalidAudit {
+      reason: format!("The rows of {entity} can't be restored"),
+    });
+  }
+  match audit_controller
+    .restore(entity, id, authorized.ctx.actor())
+    .await
+  {
+    Ok(true) => Ok(StatusCode::NO_CONTENT),
+    Ok(false) => Err(Error::DeletedNotFound {
+      entity: entity.to_string(),
+      id,
+    }),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::RestoreConflict {
+        reason: e,
+      })
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    ctx::Ctx,
+    model::{audit::Actor, role::Role},
+  };
+  use async_trait::async_trait;
+  use axum::{extract::FromRequestParts, http::Request};
+  use std::sync::Mutex;
+
+  /// The tag 7 is deleted, the restores are recorded with their actor.
+  #[derive(Default)]
+  struct DeletedTag {
+    restores: Mutex<Vec<(AuditedEntity, i32, Actor)>>,
+  }
+
+  #[async_trait]
+  impl AuditController for DeletedTag {
+    async fn history(
+      &self,
+      _: AuditFilter,
+    ) -> std::result::Result<Vec<AuditEntry>, String> {
+      todo!()
+    }
+
+    async fn restore(
+      &self,
+      entity: AuditedEntity,
+      id: i32,
+      actor: Actor,
+    ) -> std::result::Result<bool, String> {
+      self.restores.lock().unwrap().push((entity, id, actor));
+      Ok(entity == AuditedEntity::Tag && id == 7)
+    }
+  }
+
+  async fn restore_as_admin(
+    controller: Arc<DeletedTag>,
+    entity: AuditedEntity,
+    id: i32,
+  ) -> Result<StatusCode> {
+    let (mut parts, _) = Request::new(()).into_parts();
+    let ctx: Result<Ctx> = Ok(Ctx::new(1, String::from("admin"), Role::Admin));
+    parts.extensions.insert(ctx);
+    let authorized =
+      Authorized::<CanAdmin>::from_request_parts(&mut parts, &())
+        .await
+        .unwrap();
+    restore(authorized, Path((entity, id)), State(controller)).await
+  }
+
+  #[tokio::test]
+  async fn a_deleted_row_is_restored_on_behalf_of_the_admin() {
+    let controller = Arc::new(DeletedTag::default());
+    let restored =
+      restore_as_admin(controller.clone(), AuditedEntity::Tag, 7).await;
+    assert_eq!(restored.ok(), Some(StatusCode::NO_CONTENT));
+    let restores = controller.restores.lock().unwrap();
+    assert_eq!(restores.len(), 1);
+    assert_eq!(restores[0].2.username, "admin");
+  }
+
+  #[tokio::test]
+  async fn a_row_that_isnt_deleted_isnt_found() {
+    let controller = Arc::new(DeletedTag::default());
+    assert!(matches!(
+      restore_as_admin(controller, AuditedEntity::Tag, 8).await,
+      Err(Error::DeletedNotFound {
+        id: 8,
+        ..
+      })
+    ));
+  }
+
+  #[tokio::test]
+  async fn the_rows_removed_for_good_cant_be_restored() {
+    let controller = Arc::new(DeletedTag::default());
+    assert!(matches!(
+      restore_as_admin(controller.clone(), AuditedEntity::Holiday, 7).await,
+      Err(Error::InvalidAudit { .. })
+    ));
+    assert!(controller.restores.lock().unwrap().is_empty());
+  }
+}
@@ -0,0 +1,204 @@
+use crate::{
+  application::mw_auth::AUTH_TOKEN,
+  ctx::Ctx,
+  model::{
+    auth_controller::{AuthController, LoginSession},
+    user::User,
+  },
+  Error, Result,
+};
+use axum::{
+  extract::State,
+  http::StatusCode,
+  routing::{get, post},
+  Json, Router,
+};
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tower_cookies::{
+  cookie::{time, SameSite},
+  Cookie, Cookies,
+};
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct UserDto {
+  pub id: i32,
+  pub username: String,
+  pub role: String,
+  pub created_at: NaiveDateTime,
+}
+
+impl UserDto {
+  pub fn new(a_user: &User) -> Self {
+    UserDto {
+      id: a_user.get_id(),
+      username: a_user.get_username(),
+      role: a_user.get_role().to_string(),
+      created_at: a_user.get_created_at(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct LoginDto {
+  pub user_id: i32,
+  pub username: String,
+  pub role: String,
+  pub token: String,
+  pub token_type: String,
+  pub expires_at: NaiveDateTime,
+  pub session_expires_at: NaiveDateTime,
+}
+
+impl LoginDto {
+  fn new(a_login: &LoginSession) -> Self {
+    LoginDto {
+      user_id: a_login.user.get_id(),
+      username: a_login.user.get_username(),
+      role: a_login.user.get_role().to_string(),
+      token: a_login.jwt.clone(),
+      token_type: String::from("Bearer"),
+      expires_at: a_login.jwt_expires_at,
+      session_expires_at: a_login.session.get_expires_at(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct CtxDto {
+  pub user_id: i32,
+  pub username: String,
+  pub role: String,
+  pub api_key_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct CredentialsParam {
+  username: String,
+  password: String,
+}
+
+/// Defines the endpoints that handles the accounts and the sessions of the
+/// users.
+pub fn routes(auth_controller: Arc<dyn AuthController>) -> Router {
+  Router::new()
+    .route("/auth/register", post(register))
+    .route("/auth/login", post(login))
+    .route("/auth/logout", post(logout))
+    .route("/auth/me", get(me))
+    .with_state(auth_controller)
+}
+
+/// Register a new user. Every user is a viewer until an admin assigns them
+/// another role, the first admin is created from the command line.
+///
+/// # Arguments:
+/// * auth_controller: the controller responsible of the actions.
+/// * credentials_param: the username, between 3 and 50 letters, digits, '_',
+///   '-' or '.', and the password, of at least 8 characters.
+///
+/// # Return:
+/// * Ok with 201 status code and the new user.
+/// * Err with 400 status code if the credentials are invalid or the username
+///   is taken.
+async fn register(
+  State(auth_controller): State<Arc<dyn AuthController>>,
+  Json(credentials_param): Json<CredentialsParam>,
+) -> Result<(StatusCode, Json<UserDto>)> {
+  println!("->> REGISTER {}\n", credentials_param.username);
+  match auth_controller
+    .register(credentials_param.username, credentials_param.password)
+    .await
+  {
+    Ok(a_user) => Ok((StatusCode::CREATED, Json(UserDto::new(&a_user)))),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidRegistration {
+        reason: e,
+      })
+    },
+  }
+}
+
+/// Log in a user, opening a session kept in the auth-token cookie and
+/// signing a bearer token for the Authorization header.
+///
+/// # Arguments:
+/// * auth_controller: the controller responsible of the actions.
+/// * cookies: the cookies of the response.
+/// * credentials_param: the username and the password.
+///
+/// # Return:
+/// * Ok with the bearer token and the expiration of the token and the
+///   session.
+/// * Err with 403 status code if the username or the password are wrong.
+async fn login(
+  State(auth_controller): State<Arc<dyn AuthController>>,
+  cookies: Cookies,
+  Json(credentials_param): Json<CredentialsParam>,
+) -> Result<Json<LoginDto>> {
+  println!("->> LOGIN {}\n", credentials_param.username);
+  let a_login = auth_controller
+    .login(credentials_param.username, credentials_param.password)
+    .await
+    .ok_or(Error::LoginFail)?;
+
+  let settings = auth_controller.settings();
+  let max_age = (a_login.session.get_expires_at() - Utc::now().naive_utc())
+    .num_seconds()
+    .max(0);
+  let cookie = Cookie::build(AUTH_TOKEN, a_login.session.get_token())
+    .path("/")
+    .http_only(true)
+    .secure(settings.secure_cookie)
+    .same_site(SameSite::Lax)
+    .max_age(time::Duration::seconds(max_age))
+    .finish();
+  cookies.add(cookie);
+  Ok(Json(LoginDto::new(&a_login)))
+}
+
+/// Close the session of the auth-token cookie, if any, and remove the
+/// cookie. The bearer tokens remain valid until they expire.
+///
+/// # Arguments:
+/// * auth_controller: the controller responsible of the actions.
+/// * cookies: the cookies of the request.
+///
+/// # Return:
+/// * Ok if the session was closed or there wasn't one.
+/// * Err with the error.
+async fn logout(
+  State(auth_controller): State<Arc<dyn AuthController>>,
+  cookies: Cookies,
+) -> Result<()> {
+  println!("->> LOGOUT\n");
+  if let Some(cookie) = cookies.get(AUTH_TOKEN) {
+    auth_controller
+      .logout(cookie.value().to_string())
+      .await
+      .ok_or(Error::LogoutFail)?;
+    cookies.remove(Cookie::build(AUTH_TOKEN, "").path("/").finish());
+  }
+  Ok(())
+}
+
+/// The user that sends the request.
+///
+/// # Arguments:
+/// * ctx: the context of the request.
+///
+/// # Return:
+/// * Ok with the id, the username and the role of the user, and the id of
+///   the api key when the request is authenticated with one.
+/// * Err with 403 status code if the request isn't authenticated.
+async fn me(ctx: Ctx) -> Result<Json<CtxDto>> {
+  println!("->> ME {}\n", ctx.user_id());
+  Ok(Json(CtxDto {
+    user_id: ctx.user_id(),
+    username: ctx.username(),
+    role: ctx.role().to_string(),
+    api_key_id: ctx.api_key_id(),
+  }))
+}
@@ -0,0 +1,193 @@
+use crate::{
+  application::mw_auth::{Authorized, CanAdmin},
+  model::{
+    data_quality::{DataQualityCheck, DataQualityReport},
+    data_quality_controller::DataQualityController,
+  },
+  Error, Result,
+};
+use axum::{extract::State, routing::get, Json, Router};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// The exit code of the CLI when an error check finds offending rows.
+const EXIT_FAILED: i32 = 1;
+/// The exit code of the CLI when the report can't be run.
+const EXIT_NOT_RUN: i32 = 2;
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct DataQualityCheckDto {
+  pub code: String,
+  pub entity: String,
+  pub severity: String,
+  pub description: String,
+  pub count: i64,
+  pub sample_ids: Vec<i32>,
+  pub failed: bool,
+}
+
+impl DataQualityCheckDto {
+  fn new(a_check: &DataQualityCheck) -> Self {
+    DataQualityCheckDto {
+      code: a_check.code.to_string(),
+      entity: a_check.entity.to_string(),
+      severity: a_check.severity.as_str().to_string(),
+      description: a_check.description.to_string(),
+      count: a_check.count,
+      sample_ids: a_check.sample_ids.clone(),
+      failed: a_check.failed(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct DataQualityReportDto {
+  pub checked_at: NaiveDateTime,
+  pub failed: bool,
+  pub checks: Vec<DataQualityCheckDto>,
+}
+
+impl DataQualityReportDto {
+  fn new(a_report: &DataQualityReport) -> Self {
+    DataQualityReportDto {
+      checked_at: a_report.checked_at,
+      failed: a_report.failed(),
+      checks: a_report
+        .checks
+        .iter()
+        .map(DataQualityCheckDto::new)
+        .collect(),
+    }
+  }
+}
+
+/// Defines the endpoints that handles the quality of the data of the
+/// catalogue.
+pub fn routes(
+  data_quality_controller: Arc<dyn DataQualityController>,
+) -> Router {
+  Router::new()
+    .route("/admin/data-quality", get(data_quality))
+    .with_state(data_quality_controller)
+}
+
+/// Check the quality of the data of the attractions, the cities and their
+/// ratings and aggregates.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, it must be an admin.
+/// * data_quality_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the count and a sample of the offending ids of every check, it
+///   failed if any check with ERROR severity found offending rows.
+/// * Err with 403 status code if the user isn't an admin.
+/// * Err with 500 status code if the checks can't be run.
+async fn data_quality(
+  _authorized: Authorized<CanAdmin>,
+  State(data_quality_controller): State<Arc<dyn DataQualityController>>,
+) -> Result<Json<DataQualityReportDto>> {
+  println!("->> DATA QUALITY\n");
+  match data_quality_controller.report().await {
+    Ok(a_report) => Ok(Json(DataQualityReportDto::new(&a_report))),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::DataQualityFail)
+    },
+  }
+}
+
+/// Run the checks from the command line and print the report as json.
+///
+/// # Arguments:
+/// * data_quality_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * 0 if no check failed.
+/// * 1 if any check with ERROR severity found offending rows.
+/// * 2 if the checks can't be run.
+pub async fn run_cli(
+  data_quality_controller: Arc<dyn DataQualityController>,
+) -> i32 {
+  match data_quality_controller.report().await {
+    Ok(a_report) => {
+      let report_dto = DataQualityReportDto::new(&a_report);
+      match serde_json::to_string_pretty(&report_dto) {
+        Ok(json) => println!("{json}"),
+        Err(e) => {
+          eprintln!("xx->> {}", e);
+          return EXIT_NOT_RUN;
+        },
+      }
+      if report_dto.failed {
+        EXIT_FAILED
+      } else {
+        0
+      }
+    },
+    Err(e) => {
+      eprintln!("xx->> {}", e);
+      EXIT_NOT_RUN
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::data_quality::{CheckCount, Severity};
+  use async_trait::async_trait;
+
+  /// A report with a check of the severity that found the rows, or that
+  /// can't be run without a severity.
+  struct ReportWith(Option<Severity>, i64);
+
+  #[async_trait]
+  impl DataQualityController for ReportWith {
+    async fn report(&self) -> std::result::Result<DataQualityReport, String> {
+      let ReportWith(Some(severity), count) = self else {
+        return Err(String::from("The database is down"));
+      };
+      Ok(DataQualityReport {
+        checked_at: NaiveDateTime::default(),
+        checks: vec![DataQualityCheck::new(
+          "CHECK",
+          "attraction",
+          *severity,
+          "A check",
+          CheckCount {
+            count: *count,
+            sample_ids: (1..=*count as i32).collect(),
+          },
+        )],
+      })
+    }
+  }
+
+  async fn exit_code_of(controller: ReportWith) -> i32 {
+    run_cli(Arc::new(controller)).await
+  }
+
+  #[tokio::test]
+  async fn the_cli_succeeds_when_no_error_check_finds_rows() {
+    assert_eq!(exit_code_of(ReportWith(Some(Severity::Error), 0)).await, 0);
+    assert_eq!(
+      exit_code_of(ReportWith(Some(Severity::Warning), 3)).await,
+      0
+    );
+  }
+
+  #[tokio::test]
+  async fn the_cli_fails_when_an_error_check_finds_rows() {
+    assert_eq!(
+      exit_code_of(ReportWith(Some(Severity::Error), 2)).await,
+      EXIT_FAILED
+    );
+  }
+
+  #[tokio::test]
+  async fn the_cli_tells_when_the_checks_cant_be_run() {
+    assert_eq!(exit_code_of(ReportWith(None, 0)).await, EXIT_NOT_RUN);
+  }
+}
@@ -0,0 +1,207 @@
+use crate::{
+  application::mw_auth::{Authorized, CanAdmin, CanEdit},
+  model::{
+    duplicate::{AttractionMerge, DuplicateCandidate},
+    duplicate_controller::DuplicateController,
+    rating_statistics::decimal_of,
+  },
+  Error, Result,
+};
+use axum::{
+  extract::{Path, Query, State},
+  http::StatusCode,
+  routing::{get, post},
+  Json, Router,
+};
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 100;
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct DuplicateDto {
+  pub attraction_id: i32,
+  pub description: String,
+  pub other_attraction_id: i32,
+  pub other_description: String,
+  pub name_similarity: Option<BigDecimal>,
+  pub distance_meters: Option<BigDecimal>,
+  pub score: Option<BigDecimal>,
+}
+
+impl DuplicateDto {
+  fn new(a_candidate: &DuplicateCandidate) -> Self {
+    DuplicateDto {
+      attraction_id: a_candidate.attraction_id,
+      description: a_candidate.description.to_string(),
+      other_attraction_id: a_candidate.other_attraction_id,
+      other_description: a_candidate.other_description.to_string(),
+      name_similarity: decimal_of(a_candidate.name_similarity),
+      distance_meters: a_candidate
+        .distance_meters
+        .and_then(|meters| decimal_of(meters.round())),
+      score: decimal_of(a_candidate.score),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct MergeDto {
+  pub attraction_id: i32,
+  pub merged_attraction_id: i32,
+  pub moved_ratings: u64,
+  pub dropped_ratings: u64,
+  pub moved_aggregates: u64,
+  pub moved_similarities: u64,
+}
+
+impl MergeDto {
+  fn new(a_merge: &AttractionMerge) -> Self {
+    MergeDto {
+      attraction_id: a_merge.attraction_id,
+      merged_attraction_id: a_merge.merged_attraction_id,
+      moved_ratings: a_merge.moved_ratings,
+      dropped_ratings: a_merge.dropped_ratings,
+      moved_aggregates: a_merge.moved_aggregates,
+      moved_similarities: a_merge.moved_similarities,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct DuplicateFilterParam {
+  city_id: Option<i32>,
+  limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct DismissParam {
+  attraction_id: i32,
+  other_attraction_id: i32,
+}
+
+#[derive(Deserialize)]
+struct MergeParam {
+  merged_attraction_id: i32,
+}
+
+/// Defines the endpoints that handles the review of the duplicated
+/// attractions and their merge.
+pub fn routes(duplicate_controller: Arc<dyn DuplicateController>) -> Router {
+  Router::new()
+    .route("/attraction/duplicates", get(list_duplicates))
+    .route("/attraction/duplicates/dismiss", post(dismiss_duplicate))
+    .route("/attraction/:id/merge", post(merge))
+    .with_state(duplicate_controller)
+}
+
+fn invalid(reason: String) -> Error {
+  println!("xx->> {}", reason);
+  Error::InvalidDuplicate {
+    reason,
+  }
+}
+
+/// List the pairs of attractions that seem to be the same one, by the
+/// similarity of their names and their proximity, in the same city and of
+/// the same type.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, it must be an editor.
+/// * duplicate_controller: the controller responsible of the actions.
+/// * duplicate_filter_param: the optional city, every city by default, and
+///   the most pairs to list, 20 by default.
+///
+/// # Return:
+/// * Ok with the pairs that weren't dismissed, the most likely first.
+/// * Err with 400 status code if the limit is out of range.
+/// * Err with 403 status code if the user isn't an editor.
+async fn list_duplicates(
+  _authorized: Authorized<CanEdit>,
+  State(duplicate_controller): State<Arc<dyn DuplicateController>>,
+  Query(duplicate_filter_param): Query<DuplicateFilterParam>,
+) -> Result<Json<Vec<DuplicateDto>>> {
+  println!("->> DUPLICATES\n");
+  let limit = duplicate_filter_param.limit.unwrap_or(DEFAULT_LIMIT);
+  if !(1..=MAX_LIMIT).contains(&limit) {
+    return Err(invalid(format!(
+      "The limit must be between 1 and {MAX_LIMIT}"
+    )));
+  }
+  let duplicates = duplicate_controller
+    .duplicates(duplicate_filter_param.city_id, limit)
+    .await
+    .unwrap_or_default();
+  Ok(Json(duplicates.iter().map(DuplicateDto::new).collect()))
+}
+
+/// Dismiss a pair of attractions reviewed as not being the same one, it
+/// isn't listed again.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, it must be an editor.
+/// * duplicate_controller: the controller responsible of the actions.
+/// * dismiss_param: the ids of both attractions.
+///
+/// # Return:
+/// * Ok with 204 status code.
+/// * Err with 400 status code if both ids are the same.
+/// * Err with 403 status code if the user isn't an editor.
+/// * Err with 404 status code if any of the attractions doesn't exist.
+async fn dismiss_duplicate(
+  _authorized: Authorized<CanEdit>,
+  State(duplicate_controller): State<Arc<dyn DuplicateController>>,
+  Json(dismiss_param): Json<DismissParam>,
+) -> Result<StatusCode> {
+  println!("->> DISMISS DUPLICATE\n");
+  match duplicate_controller
+    .dismiss(
+      dismiss_param.attraction_id,
+      dismiss_param.other_attraction_id,
+    )
+    .await
+    .map_err(invalid)?
+  {
+    true => Ok(StatusCode::NO_CONTENT),
+    false => Err(Error::DuplicateNotFound {
+      id: dismiss_param.attraction_id,
+      other_id: dismiss_param.other_attraction_id,
+    }),
+  }
+}
+
+/// Merge a duplicate into an attraction. The ratings, aggregates and
+/// similarities of the duplicate move to the attraction, and the duplicate
+/// is deleted leaving a redirect from its id to the attraction.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an admin.
+/// * id: the id of the attraction that survives.
+/// * duplicate_controller: the controller responsible of the actions.
+/// * merge_param: the id of the duplicate.
+///
+/// # Return:
+/// * Ok with what was moved and the ratings dropped because their users
+///   had already rated the attraction the same day.
+/// * Err with 400 status code if both ids are the same.
+/// * Err with 403 status code if the user isn't an admin.
+/// * Err with 404 status code if any of the attractions doesn't exist.
+async fn merge(
+  authorized: Authorized<CanAdmin>,
+  Path(id): Path<i32>,
+  State(duplicate_controller): State<Arc<dyn DuplicateController>>,
+  Json(merge_param): Json<MergeParam>,
+) -> Result<Json<MergeDto>> {
+  println!("->> MERGE ATTRACTIONS\n");
+  duplicate_controller
+    .merge(id, merge_param.merged_attraction_id, authorized.ctx.actor())
+    .await
+    .map_err(invalid)?
+    .map(|a_merge| Json(MergeDto::new(&a_merge)))
+    .ok_or(Error::DuplicateNotFound {
+      id,
+      other_id: merge_param.merged_attraction_id,
+    })
+}
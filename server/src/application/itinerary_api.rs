@@ -0,0 +1,144 @@
+use crate::{
+  application::mw_auth::{Authorized, CanRead},
+  model::{
+    itinerary::{DayRoute, Itinerary, RouteStop},
+    itinerary_controller::{ItineraryController, ItineraryRequest},
+    rating_statistics::decimal_of,
+  },
+  Error, Result,
+};
+use axum::{extract::State, routing::post, Json, Router};
+use bigdecimal::BigDecimal;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct RouteStopDto {
+  pub order: usize,
+  pub attraction_id: i32,
+  pub walking_meters: Option<BigDecimal>,
+  pub arrival_minutes: Option<BigDecimal>,
+  pub open_on_arrival: Option<bool>,
+}
+
+impl RouteStopDto {
+  fn new(order: usize, a_stop: &RouteStop) -> Self {
+    RouteStopDto {
+      order,
+      attraction_id: a_stop.attraction_id,
+      walking_meters: decimal_of(a_stop.walking_meters.round()),
+      arrival_minutes: decimal_of(a_stop.arrival_minutes.round()),
+      open_on_arrival: a_stop.open_on_arrival,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct DayRouteDto {
+  pub day: usize,
+  pub stops: Vec<RouteStopDto>,
+  pub walking_meters: Option<BigDecimal>,
+  pub walking_minutes: Option<BigDecimal>,
+  pub visit_minutes: Option<BigDecimal>,
+  pub total_minutes: Option<BigDecimal>,
+}
+
+impl DayRouteDto {
+  fn new(a_day: &DayRoute) -> Self {
+    DayRouteDto {
+      day: a_day.day,
+      stops: a_day
+        .stops
+        .iter()
+        .enumerate()
+        .map(|(index, a_stop)| RouteStopDto::new(index + 1, a_stop))
+        .collect(),
+      walking_meters: decimal_of(a_day.walking_meters.round()),
+      walking_minutes: decimal_of(a_day.walking_minutes.round()),
+      visit_minutes: decimal_of(a_day.visit_minutes.round()),
+      total_minutes: decimal_of(a_day.total_minutes().round()),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct ItineraryDto {
+  pub days: Vec<DayRouteDto>,
+  pub unscheduled: Vec<i32>,
+  pub without_location: Vec<i32>,
+}
+
+impl ItineraryDto {
+  fn new(an_itinerary: &Itinerary) -> Self {
+    ItineraryDto {
+      days: an_itinerary.days.iter().map(DayRouteDto::new).collect(),
+      unscheduled: an_itinerary.unscheduled.clone(),
+      without_location: an_itinerary.without_location.clone(),
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct StartParam {
+  latitude: f64,
+  longitude: f64,
+}
+
+#[derive(Deserialize)]
+struct ItineraryParam {
+  attraction_ids: Vec<i32>,
+  start: StartParam,
+  days: usize,
+  max_hours_per_day: f64,
+  start_at: Option<NaiveDateTime>,
+}
+
+/// Defines the endpoints that handles the planning of the visits.
+pub fn routes(itinerary_controller: Arc<dyn ItineraryController>) -> Router {
+  Router::new()
+    .route("/itinerary", post(plan))
+    .with_state(itinerary_controller)
+}
+
+/// Plan the visit of the attractions in days. Every day is a walk from the
+/// start point through its attractions and back, as short as found.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * itinerary_controller: the controller responsible of the actions.
+/// * itinerary_param: the attractions, the start point, the number of days,
+///   the most hours of every day and the optional moment when the first day
+///   starts, in the local time of the attractions.
+///
+/// # Return:
+/// * Ok with the route of every day with attractions, the attractions that
+///   don't fit in the hours and the ones without coordinates. With the start
+///   moment, every stop tells whether the attraction is open on arrival.
+/// * Err with 400 status code if the values are invalid or an attraction
+///   doesn't exist.
+/// * Err with 403 status code if the request isn't authenticated.
+async fn plan(
+  _authorized: Authorized<CanRead>,
+  State(itinerary_controller): State<Arc<dyn ItineraryController>>,
+  Json(itinerary_param): Json<ItineraryParam>,
+) -> Result<Json<ItineraryDto>> {
+  println!("->> ITINERARY\n");
+  let request = ItineraryRequest {
+    attraction_ids: itinerary_param.attraction_ids,
+    start_latitude: itinerary_param.start.latitude,
+    start_longitude: itinerary_param.start.longitude,
+    days: itinerary_param.days,
+    max_hours_per_day: itinerary_param.max_hours_per_day,
+    start_at: itinerary_param.start_at,
+  };
+  match itinerary_controller.plan(request).await {
+    Ok(an_itinerary) => Ok(Json(ItineraryDto::new(&an_itinerary))),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidItinerary {
+        reason: e,
+      })
+    },
+  }
+}
@@ -0,0 +1,341 @@
+use crate::{
+  ctx::Ctx,
+  model::{
+    api_key::ApiScope, api_key_controller::ApiKeyController,
+    auth_controller::AuthController, rate_limit::RateDecision, role::Role,
+  },
+  Error, Result,
+};
+use async_trait::async_trait;
+use axum::{
+  extract::{FromRequestParts, State},
+  http::{
+    header::AUTHORIZATION, request::Parts, HeaderMap, HeaderName, HeaderValue,
+    Request,
+  },
+  middleware::Next,
+  response::{IntoResponse, Response},
+};
+use std::{marker::PhantomData, sync::Arc};
+use tower_cookies::{Cookie, Cookies};
+
+/// The name of the cookie with the session token.
+pub const AUTH_TOKEN: &str = "auth-token";
+/// The header with the api key of the partners.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Resolve the context of every request and keep the result in the request
+/// extensions, the handlers that need a user extract it with Ctx.
+///
+/// A bearer token in the Authorization header takes precedence over the
+/// session cookie. An invalid cookie is removed. The role is always the
+/// current one of the user, not the one when the token was issued.
+pub async fn mw_ctx_resolver<B>(
+  State(auth_controller): State<Arc<dyn AuthController>>,
+  cookies: Cookies,
+  mut req: Request<B>,
+  next: Next<B>,
+) -> Result<Response> {
+  println!("->> {:<12} - mw_ctx_resolver", "MIDDLEWARE");
+  let result_ctx =
+    resolve_ctx(auth_controller.as_ref(), &cookies, req.headers()).await;
+  if result_ctx.is_err() && cookies.get(AUTH_TOKEN).is_some() {
+    cookies.remove(Cookie::build(AUTH_TOKEN, "").path("/").finish());
+  }
+
+  req.extensions_mut().insert(result_ctx);
+  Ok(next.run(req).await)
+}
+
+/// Authenticate the requests with an api key, replacing the context resolved
+/// from the user session, and apply the rate limit of the key. Every
+/// response to a key has the RateLimit-Limit, RateLimit-Remaining and
+/// RateLimit-Reset headers, and the throttled ones have Retry-After too.
+///
+/// An unknown or revoked key is rejected, instead of falling back into the
+/// session.
+pub async fn mw_api_key<B>(
+  State(api_key_controller): State<Arc<dyn ApiKeyController>>,
+  mut req: Request<B>,
+  next: Next<B>,
+) -> Result<Response> {
+  println!("->> {:<12} - mw_api_key", "MIDDLEWARE");
+  let Some(key) = req.headers().get(API_KEY_HEADER) else {
+    return Ok(next.run(req).await);
+  };
+  let key = key.to_str().map_err(|_| Error::AuthFailTokenWrongFormat)?;
+  let (api_key, owner) = api_key_controller
+    .authenticate(key)
+    .await
+    .ok_or(Error::AuthFailInvalidApiKey)?;
+
+  let decision = api_key_controller.throttle(&api_key);
+  api_key_controller
+    .record_usage(api_key.get_id(), !decision.allowed)
+    .await;
+  let mut response = if decision.allowed {
+    let ctx = Ctx::new(owner.get_id(), owner.get_username(), owner.get_role())
+      .with_api_key(api_key.get_id(), api_key.get_scopes());
+    req.extensions_mut().insert(Ok::<Ctx, Error>(ctx));
+    next.run(req).await
+  } else {
+    Error::RateLimitExceeded {
+      api_key_id: api_key.get_id(),
+    }
+    .into_response()
+  };
+  insert_rate_limit_headers(response.headers_mut(), &decision);
+  Ok(response)
+}
+
+fn insert_rate_limit_headers(headers: &mut HeaderMap, decision: &RateDecision) {
+  let mut insert = |name: &'static str, value: u64| {
+    headers.insert(HeaderName::from_static(name), HeaderValue::from(value));
+  };
+  insert("ratelimit-limit", decision.limit as u64);
+  insert("ratelimit-remaining", decision.remaining as u64);
+  insert("ratelimit-reset", decision.reset_seconds);
+  if let Some(retry_after) = decision.retry_after_seconds {
+    insert("retry-after", retry_after);
+  }
+}
+
+async fn resolve_ctx(
+  auth_controller: &dyn AuthController,
+  cookies: &Cookies,
+  headers: &HeaderMap,
+) -> Result<Ctx> {
+  if let Some(authorization) = headers.get(AUTHORIZATION) {
+    let jwt = authorization
+      .to_str()
+      .ok()
+      .and_then(|value| value.strip_prefix("Bearer "))
+      .map(str::trim)
+      .ok_or(Error::AuthFailTokenWrongFormat)?;
+    let claims = auth_controller.claims_of(jwt).map_err(|e| {
+      println!("xx->> {}", e);
+      Error::AuthFailInvalidToken
+    })?;
+    let user = auth_controller
+      .user_by_id(claims.sub)
+      .await
+      .ok_or(Error::AuthFailInvalidToken)?;
+    return Ok(Ctx::new(
+      user.get_id(),
+      user.get_username(),
+      user.get_role(),
+    ));
+  }
+
+  let session_token = cookies
+    .get(AUTH_TOKEN)
+    .map(|cookie| cookie.value().to_string())
+    .ok_or(Error::AuthFailNoAuthTokenCookie)?;
+  if session_token.len() != 64
+    || !session_token.chars().all(|c| c.is_ascii_hexdigit())
+  {
+    return Err(Error::AuthFailTokenWrongFormat);
+  }
+  let user = auth_controller
+    .user_of_session(session_token)
+    .await
+    .ok_or(Error::AuthFailInvalidToken)?;
+  Ok(Ctx::new(
+    user.get_id(),
+    user.get_username(),
+    user.get_role(),
+  ))
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Ctx {
+  type Rejection = Error;
+
+  async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self> {
+    println!("->> {:<12} - Ctx", "EXTRACTOR");
+    parts
+      .extensions
+      .get::<Result<Ctx>>()
+      .ok_or(Error::AuthFailCtxNotInRequestExt)?
+      .clone()
+  }
+}
+
+/// A permission required by a handler, granted to a role and the ones above
+/// it. The requests authenticated with an api key also need the scope, and
+/// without one the permission is never granted to them.
+pub trait Permission {
+  const REQUIRED: Role;
+  const SCOPE: Option<ApiScope>;
+}
+
+/// Read the attractions, the ratings and their statistics.
+pub struct CanRead;
+/// Rate the attractions.
+pub struct CanRate;
+/// Edit the attractions and the reference data, and review the ratings.
+pub struct CanEdit;
+/// Calculate the similarities, delete and manage the roles of the users.
+pub struct CanAdmin;
+/// Manage the api keys, only for the users and not for the keys themselves.
+pub struct CanManageKeys;
+/// Keep the wishlists and the check-ins of the user, only for the users and
+/// not for the keys.
+pub struct CanVisit;
+
+impl Permission for CanRead {
+  const REQUIRED: Role = Role::Viewer;
+  const SCOPE: Option<ApiScope> = Some(ApiScope::AttractionsRead);
+}
+
+impl Permission for CanRate {
+  const REQUIRED: Role = Role::Viewer;
+  const SCOPE: Option<ApiScope> = Some(ApiScope::RatingsWrite);
+}
+
+impl Permission for CanEdit {
+  const REQUIRED: Role = Role::Editor;
+  const SCOPE: Option<ApiScope> = Some(ApiScope::AttractionsWrite);
+}
+
+impl Permission for CanAdmin {
+  const REQUIRED: Role = Role::Admin;
+  const SCOPE: Option<ApiScope> = None;
+}
+
+impl Permission for CanManageKeys {
+  const REQUIRED: Role = Role::Viewer;
+  const SCOPE: Option<ApiScope> = None;
+}
+
+impl Permission for CanVisit {
+  const REQUIRED: Role = Role::Viewer;
+  const SCOPE: Option<ApiScope> = None;
+}
+
+/// The context of a request whose user has the permission P, e.g. a handler
+/// with an `Authorized<CanEdit>` argument is only run for editors and admins.
+pub struct Authorized<P> {
+  pub ctx: Ctx,
+  permission: PhantomData<P>,
+}
+
+#[async_trait]
+impl<S, P> FromRequestParts<S> for Authorized<P>
+where
+  S: Send + Sync,
+  P: Permission,
+{
+  type Rejection = Error;
+
+  async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+    let ctx = Ctx::from_request_parts(parts, state).await?;
+    if !ctx.role().grants(P::REQUIRED) {
+      return Err(Error::AccessDenied {
+        required: P::REQUIRED.to_string(),
+      });
+    }
+    if !ctx.allows(P::SCOPE) {
+      return Err(Error::ScopeDenied {
+        required: P::SCOPE.map(|scope| scope.to_string()),
+      });
+    }
+    Ok(Authorized {
+      ctx,
+      permission: PhantomData,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  async fn authorize<P: Permission>(ctx: Result<Ctx>) -> Result<Authorized<P>> {
+    let (mut parts, _) = Request::new(()).into_parts();
+    parts.extensions.insert(ctx);
+    Authorized::<P>::from_request_parts(&mut parts, &()).await
+  }
+
+  fn user(role: Role) -> Ctx {
+    Ctx::new(1, String::from("ana"), role)
+  }
+
+  #[tokio::test]
+  async fn a_viewer_is_denied_the_permissions_of_an_editor() {
+    let denied = authorize::<CanEdit>(Ok(user(Role::Viewer))).await;
+    assert!(matches!(
+      denied,
+      Err(Error::AccessDenied { required }) if required == "EDITOR"
+    ));
+    assert!(authorize::<CanRate>(Ok(user(Role::Viewer))).await.is_ok());
+  }
+
+  #[tokio::test]
+  async fn an_editor_is_denied_the_permissions_of_an_admin() {
+    assert!(authorize::<CanEdit>(Ok(user(Role::Editor))).await.is_ok());
+    let denied = authorize::<CanAdmin>(Ok(user(Role::Editor))).await;
+    assert!(matches!(
+      denied,
+      Err(Error::AccessDenied { required }) if required == "ADMIN"
+    ));
+  }
+
+  #[tokio::test]
+  async fn an_admin_has_every_permission() {
+    let authorized = authorize::<CanAdmin>(Ok(user(Role::Admin))).await;
+    assert_eq!(authorized.map(|it| it.ctx.user_id()).ok(), Some(1));
+    assert!(authorize::<CanEdit>(Ok(user(Role::Admin))).await.is_ok());
+    assert!(authorize::<CanRead>(Ok(user(Role::Admin))).await.is_ok());
+  }
+
+  fn api_key(role: Role, scopes: Vec<ApiScope>) -> Ctx {
+    user(role).with_api_key(7, scopes)
+  }
+
+  #[tokio::test]
+  async fn a_key_is_denied_what_its_scopes_dont_allow() {
+    let reader = api_key(Role::Admin, vec![ApiScope::AttractionsRead]);
+    assert!(authorize::<CanRead>(Ok(reader.clone())).await.is_ok());
+    let denied = authorize::<CanEdit>(Ok(reader)).await;
+    assert!(matches!(
+      denied,
+      Err(Error::ScopeDenied { required: Some(scope) })
+        if scope == "attractions:write"
+    ));
+  }
+
+  #[tokio::test]
+  async fn a_key_is_denied_what_its_owner_cant_do() {
+    let writer = api_key(Role::Viewer, vec![ApiScope::AttractionsWrite]);
+    let denied = authorize::<CanEdit>(Ok(writer)).await;
+    assert!(matches!(denied, Err(Error::AccessDenied { .. })));
+  }
+
+  #[tokio::test]
+  async fn a_key_never_administers_nor_manages_the_keys() {
+    let every_scope = vec![
+      ApiScope::AttractionsRead,
+      ApiScope::AttractionsWrite,
+      ApiScope::RatingsWrite,
+      ApiScope::RatingsBackfill,
+    ];
+    let a_key = api_key(Role::Admin, every_scope);
+    let denied = authorize::<CanAdmin>(Ok(a_key.clone())).await;
+    assert!(matches!(
+      denied,
+      Err(Error::ScopeDenied {
+        required: None
+      })
+    ));
+    assert!(authorize::<CanManageKeys>(Ok(a_key.clone())).await.is_err());
+    assert!(authorize::<CanVisit>(Ok(a_key)).await.is_err());
+  }
+
+  #[tokio::test]
+  async fn an_unauthenticated_request_has_no_permission() {
+    let denied =
+      authorize::<CanRead>(Err(Error::AuthFailNoAuthTokenCookie)).await;
+    assert!(matches!(denied, Err(Error::AuthFailNoAuthTokenCookie)));
+  }
+}
@@ -0,0 +1,119 @@
+use crate::{
+  application::mw_auth::{Authorized, CanRead},
+  model::{
+    ranking::{PriorGroup, RankedAttraction, RankingOrder, RankingSettings},
+    ranking_controller::RankingController,
+    rating_statistics::decimal_of,
+  },
+  Result,
+};
+use axum::{
+  extract::{Query, State},
+  routing::get,
+  Json, Router,
+};
+use bigdecimal::BigDecimal;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct RankedAttractionDto {
+  pub rank: usize,
+  pub attraction_id: i32,
+  pub description: String,
+  pub city_id: i32,
+  pub attraction_type_id: i32,
+  pub rating_count: i64,
+  pub average: Option<BigDecimal>,
+  pub prior_mean: Option<BigDecimal>,
+  pub bayesian_average: Option<BigDecimal>,
+  pub recency_score: Option<BigDecimal>,
+  pub wilson_score: Option<BigDecimal>,
+  pub last_rated_at: Option<NaiveDateTime>,
+  pub check_in_count: i64,
+  pub popularity_score: Option<BigDecimal>,
+}
+
+impl RankedAttractionDto {
+  pub fn new(a_ranked: &RankedAttraction) -> Self {
+    RankedAttractionDto {
+      rank: a_ranked.rank,
+      attraction_id: a_ranked.attraction_id,
+      description: a_ranked.description.clone(),
+      city_id: a_ranked.city_id,
+      attraction_type_id: a_ranked.attraction_type_id,
+      rating_count: a_ranked.rating_count,
+      average: a_ranked.average.and_then(decimal_of),
+      prior_mean: decimal_of(a_ranked.prior_mean),
+      bayesian_average: decimal_of(a_ranked.bayesian_average),
+      recency_score: decimal_of(a_ranked.recency_score),
+      wilson_score: decimal_of(a_ranked.wilson_score),
+      last_rated_at: a_ranked.last_rated_at,
+      check_in_count: a_ranked.check_in_count,
+      popularity_score: decimal_of(a_ranked.popularity_score),
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct RankingParam {
+  city_id: Option<i32>,
+  type_id: Option<i32>,
+  window: Option<i64>,
+  #[serde(default)]
+  prior: PriorGroup,
+  #[serde(default)]
+  order: RankingOrder,
+  limit: Option<usize>,
+}
+
+/// Defines the endpoints that handles the rankings of the attractions.
+pub fn routes(ranking_controller: Arc<dyn RankingController>) -> Router {
+  Router::new()
+    .route("/ranking", get(ranking))
+    .with_state(ranking_controller)
+}
+
+/// Rank the attractions by their ratings, adjusted by the number of ratings
+/// so a few high rates don't beat thousands of them.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * ranking_param: the optional city and type of the attractions, the
+///   window in days of the ratings (all of them by default), the group whose
+///   mean is the prior (CITY by default), the score used to sort (BAYESIAN,
+///   RECENCY, WILSON or POPULARITY, that also counts the visits without a
+///   rating) and the optional maximum number of attractions.
+/// * ranking_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of ranked attractions, the best first.
+/// * Err with the error.
+async fn ranking(
+  _authorized: Authorized<CanRead>,
+  Query(ranking_param): Query<RankingParam>,
+  State(ranking_controller): State<Arc<dyn RankingController>>,
+) -> Result<Json<Vec<RankedAttractionDto>>> {
+  println!("->> RANKING by {}\n", ranking_param.order);
+  let settings = RankingSettings {
+    prior: ranking_param.prior,
+    order: ranking_param.order,
+    ..Default::default()
+  };
+  let ranked = ranking_controller
+    .ranking(
+      ranking_param.city_id,
+      ranking_param.type_id,
+      ranking_param.window,
+      settings,
+    )
+    .await
+    .unwrap_or_default();
+  let dtos = ranked
+    .iter()
+    .take(ranking_param.limit.unwrap_or(usize::MAX))
+    .map(RankedAttractionDto::new)
+    .collect::<Vec<RankedAttractionDto>>();
+  Ok(Json(dtos))
+}
@@ -0,0 +1,138 @@
+use crate::{
+  application::mw_auth::{Authorized, CanAdmin, CanRead},
+  model::{
+    rating_statistics::decimal_of,
+    recommendation::Recommendation,
+    recommendation_controller::{RecommendationController, TrainedModel},
+  },
+  Error, Result,
+};
+use axum::{
+  extract::{Query, State},
+  routing::{get, post},
+  Json, Router,
+};
+use bigdecimal::BigDecimal;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct RecommendationDto {
+  pub attraction_id: i32,
+  pub score: Option<BigDecimal>,
+  pub predicted_rate: Option<BigDecimal>,
+  pub collaborative_score: Option<BigDecimal>,
+  pub content_score: Option<BigDecimal>,
+  pub because_of: Vec<i32>,
+}
+
+impl RecommendationDto {
+  fn new(a_recommendation: &Recommendation) -> Self {
+    RecommendationDto {
+      attraction_id: a_recommendation.attraction_id,
+      score: decimal_of(a_recommendation.score),
+      predicted_rate: decimal_of(a_recommendation.predicted_rate),
+      collaborative_score: a_recommendation
+        .collaborative_score
+        .and_then(decimal_of),
+      content_score: a_recommendation.content_score.and_then(decimal_of),
+      because_of: a_recommendation.because_of.clone(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct TrainedModelDto {
+  pub users: usize,
+  pub rated_attractions: usize,
+  pub neighbours: usize,
+  pub trained_at: NaiveDateTime,
+}
+
+impl TrainedModelDto {
+  fn new(a_model: &TrainedModel) -> Self {
+    TrainedModelDto {
+      users: a_model.users,
+      rated_attractions: a_model.rated_attractions,
+      neighbours: a_model.neighbours,
+      trained_at: a_model.trained_at,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct RecommendationParam {
+  city_id: Option<i32>,
+  limit: Option<usize>,
+}
+
+/// Defines the endpoints that handles the personalized recommendations.
+pub fn routes(
+  recommendation_controller: Arc<dyn RecommendationController>,
+) -> Router {
+  Router::new()
+    .route("/me/recommendations", get(recommendations))
+    .route("/recommendation/train", post(train))
+    .with_state(recommendation_controller)
+}
+
+/// Recommend to the user the attractions liked by the users that liked the
+/// same ones, and for the attractions with few ratings, the ones similar to
+/// what the user liked.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the user of the ratings.
+/// * recommendation_param: the optional city of the attractions and maximum
+///   number of recommendations.
+/// * recommendation_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of recommendations, the best first. It is empty for
+///   the users without ratings and the requests of an api key.
+/// * Err with 403 status code if the request isn't authenticated.
+async fn recommendations(
+  authorized: Authorized<CanRead>,
+  Query(recommendation_param): Query<RecommendationParam>,
+  State(recommendation_controller): State<Arc<dyn RecommendationController>>,
+) -> Result<Json<Vec<RecommendationDto>>> {
+  println!("->> RECOMMENDATIONS\n");
+  let Some(user_id) = authorized.ctx.rater_id() else {
+    return Ok(Json(Vec::new()));
+  };
+  let recommendations = recommendation_controller
+    .recommendations(user_id, recommendation_param.city_id)
+    .await
+    .unwrap_or_default();
+  let dtos = recommendations
+    .iter()
+    .take(recommendation_param.limit.unwrap_or(usize::MAX))
+    .map(RecommendationDto::new)
+    .collect::<Vec<RecommendationDto>>();
+  Ok(Json(dtos))
+}
+
+/// Train the collaborative filtering model from the ratings of the users,
+/// replacing the previous one.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, it must be an admin.
+/// * recommendation_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the size of the trained model.
+/// * Err with 403 status code if the user isn't an admin.
+/// * Err with 500 status code.
+async fn train(
+  _authorized: Authorized<CanAdmin>,
+  State(recommendation_controller): State<Arc<dyn RecommendationController>>,
+) -> Result<Json<TrainedModelDto>> {
+  println!("->> TRAIN RECOMMENDATION\n");
+  match recommendation_controller.train().await {
+    Ok(a_model) => Ok(Json(TrainedModelDto::new(&a_model))),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::TrainRecommendationFail)
+    },
+  }
+}
@@ -0,0 +1,743 @@
+use crate::{
+  application::mw_auth::{Authorized, CanAdmin, CanEdit, CanRead},
+  model::{
+    attraction::{AttractionType, City, Country, Holiday, Tag},
+    reference_controller::ReferenceController,
+  },
+  Error, Result,
+};
+use axum::{
+  extract::{Path, Query, State},
+  http::StatusCode,
+  routing::{delete, get, put},
+  Json, Router,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const ATTRACTION_TYPE: &str = "ATTRACTION_TYPE";
+const COUNTRY: &str = "COUNTRY";
+const CITY: &str = "CITY";
+const HOLIDAY: &str = "HOLIDAY";
+const TAG: &str = "TAG";
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct AttractionTypeDto {
+  pub id: i32,
+  pub code: String,
+  pub description: String,
+}
+
+impl AttractionTypeDto {
+  fn new(an_attraction_type: &AttractionType) -> Self {
+    AttractionTypeDto {
+      id: an_attraction_type.get_id(),
+      code: an_attraction_type.get_code(),
+      description: an_attraction_type.get_description(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct CountryDto {
+  pub id: i32,
+  pub iso_code: String,
+  pub description: String,
+}
+
+impl CountryDto {
+  fn new(a_country: &Country) -> Self {
+    CountryDto {
+      id: a_country.get_id(),
+      iso_code: a_country.get_iso_code(),
+      description: a_country.get_description(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct CityDto {
+  pub id: i32,
+  pub description: String,
+  pub country_id: i32,
+}
+
+impl CityDto {
+  fn new(a_city: &City) -> Self {
+    CityDto {
+      id: a_city.get_id(),
+      description: a_city.get_description(),
+      country_id: a_city.get_country_id(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct TagDto {
+  pub id: i32,
+  pub code: String,
+  pub description: String,
+}
+
+impl TagDto {
+  pub fn new(a_tag: &Tag) -> Self {
+    TagDto {
+      id: a_tag.get_id(),
+      code: a_tag.get_code(),
+      description: a_tag.get_description(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct HolidayDto {
+  pub id: i32,
+  pub country_id: i32,
+  pub day: Option<NaiveDate>,
+  pub description: String,
+}
+
+impl HolidayDto {
+  fn new(a_holiday: &Holiday) -> Self {
+    HolidayDto {
+      id: a_holiday.get_id(),
+      country_id: a_holiday.get_country_id(),
+      day: Some(a_holiday.get_day()),
+      description: a_holiday.get_description(),
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct AttractionTypeParam {
+  code: String,
+  description: String,
+}
+
+#[derive(Deserialize)]
+struct TagParam {
+  code: String,
+  description: String,
+}
+
+#[derive(Deserialize)]
+struct NewCountryParam {
+  iso_code: String,
+  description: String,
+}
+
+#[derive(Deserialize)]
+struct CountryParam {
+  description: String,
+}
+
+#[derive(Deserialize)]
+struct CityParam {
+  description: String,
+  country_id: i32,
+}
+
+#[derive(Deserialize)]
+struct CityFilterParam {
+  country_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct HolidayParam {
+  day: NaiveDate,
+  description: String,
+}
+
+#[derive(Deserialize)]
+struct HolidayFilterParam {
+  year: Option<i32>,
+}
+
+/// Defines the endpoints that handles the reference data of the attractions:
+/// their types and tags, the countries with their holidays and the cities.
+pub fn routes(reference_controller: Arc<dyn ReferenceController>) -> Router {
+  Router::new()
+    .route(
+      "/attraction-type",
+      get(list_attraction_types).post(create_attraction_type),
+    )
+    .route(
+      "/attraction-type/:id",
+      put(update_attraction_type).delete(delete_attraction_type),
+    )
+    .route("/tag", get(list_tags).post(create_tag))
+    .route("/tag/:id", put(update_tag).delete(delete_tag))
+    .route("/country", get(list_countries).post(create_country))
+    .route("/country/:iso", put(update_country).delete(delete_country))
+    .route(
+      "/country/:iso/holidays",
+      get(list_holidays).post(create_holiday),
+    )
+    .route("/country/:iso/holidays/:day", delete(delete_holiday))
+    .route("/city", get(list_cities).post(create_city))
+    .route("/city/:id", put(update_city).delete(delete_city))
+    .with_state(reference_controller)
+}
+
+fn invalid(reason: String) -> Error {
+  println!("xx->> {}", reason);
+  Error::InvalidReference {
+    reason,
+  }
+}
+
+fn not_found(kind: &str, key: impl ToString) -> Error {
+  Error::ReferenceNotFound {
+    kind: kind.to_string(),
+    key: key.to_string(),
+  }
+}
+
+fn in_use(kind: &str, key: impl ToString, reason: String) -> Error {
+  println!("xx->> {}", reason);
+  Error::ReferenceInUse {
+    kind: kind.to_string(),
+    key: key.to_string(),
+  }
+}
+
+/// List the types of attractions.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * reference_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of types sorted by code.
+/// * Err with the error.
+async fn list_attraction_types(
+  _authorized: Authorized<CanRead>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+) -> Result<Json<Vec<AttractionTypeDto>>> {
+  println!("->> ATTRACTION TYPES\n");
+  let attraction_types = reference_controller
+    .attraction_types()
+    .await
+    .unwrap_or_default();
+  Ok(Json(
+    attraction_types
+      .iter()
+      .map(AttractionTypeDto::new)
+      .collect(),
+  ))
+}
+
+/// Register a new type of attraction.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * reference_controller: the controller responsible of the actions.
+/// * attraction_type_param: the code and the description of the type.
+///
+/// # Return:
+/// * Ok with 201 status code and the new type.
+/// * Err with 400 status code if the code or the description are empty.
+/// * Err with 403 status code if the user isn't an editor.
+async fn create_attraction_type(
+  authorized: Authorized<CanEdit>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+  Json(attraction_type_param): Json<AttractionTypeParam>,
+) -> Result<(StatusCode, Json<AttractionTypeDto>)> {
+  println!("->> CREATE ATTRACTION TYPE\n");
+  let an_attraction_type = reference_controller
+    .create_attraction_type(
+      attraction_type_param.code,
+      attraction_type_param.description,
+      authorized.ctx.actor(),
+    )
+    .await
+    .map_err(invalid)?;
+  Ok((
+    StatusCode::CREATED,
+    Json(AttractionTypeDto::new(&an_attraction_type)),
+  ))
+}
+
+/// Replace the code and the description of a type of attraction.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * id: the id of the type.
+/// * reference_controller: the controller responsible of the actions.
+/// * attraction_type_param: the new code and description of the type.
+///
+/// # Return:
+/// * Ok with the updated type.
+/// * Err with 400 status code if the code or the description are empty.
+/// * Err with 403 status code if the user isn't an editor.
+/// * Err with 404 status code if the type doesn't exist.
+async fn update_attraction_type(
+  authorized: Authorized<CanEdit>,
+  Path(id): Path<i32>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+  Json(attraction_type_param): Json<AttractionTypeParam>,
+) -> Result<Json<AttractionTypeDto>> {
+  println!("->> UPDATE ATTRACTION TYPE\n");
+  reference_controller
+    .update_attraction_type(
+      id,
+      attraction_type_param.code,
+      attraction_type_param.description,
+      authorized.ctx.actor(),
+    )
+    .await
+    .map_err(invalid)?
+    .map(|an_attraction_type| Json(AttractionTypeDto::new(&an_attraction_type)))
+    .ok_or(not_found(ATTRACTION_TYPE, id))
+}
+
+/// Delete softly a type of attraction that no attraction has, an admin can
+/// restore it.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an admin.
+/// * id: the id of the type.
+/// * reference_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with 204 status code.
+/// * Err with 403 status code if the user isn't an admin.
+/// * Err with 404 status code if the type doesn't exist.
+/// * Err with 409 status code if an attraction has the type.
+async fn delete_attraction_type(
+  authorized: Authorized<CanAdmin>,
+  Path(id): Path<i32>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+) -> Result<StatusCode> {
+  println!("->> DELETE ATTRACTION TYPE\n");
+  match reference_controller
+    .delete_attraction_type(id, authorized.ctx.actor())
+    .await
+  {
+    Ok(true) => Ok(StatusCode::NO_CONTENT),
+    Ok(false) => Err(not_found(ATTRACTION_TYPE, id)),
+    Err(e) => Err(in_use(ATTRACTION_TYPE, id, e)),
+  }
+}
+
+/// List the tags of the attractions.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * reference_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of tags sorted by code.
+/// * Err with the error.
+async fn list_tags(
+  _authorized: Authorized<CanRead>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+) -> Result<Json<Vec<TagDto>>> {
+  println!("->> TAGS\n");
+  let tags = reference_controller.tags().await.unwrap_or_default();
+  Ok(Json(tags.iter().map(TagDto::new).collect()))
+}
+
+/// Register a new tag.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * reference_controller: the controller responsible of the actions.
+/// * tag_param: the code, of letters, digits and dashes, and the
+///   description of the tag.
+///
+/// # Return:
+/// * Ok with 201 status code and the new tag.
+/// * Err with 400 status code if the values are invalid or the code is
+///   taken.
+/// * Err with 403 status code if the user isn't an editor.
+async fn create_tag(
+  authorized: Authorized<CanEdit>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+  Json(tag_param): Json<TagParam>,
+) -> Result<(StatusCode, Json<TagDto>)> {
+  println!("->> CREATE TAG\n");
+  let a_tag = reference_controller
+    .create_tag(
+      tag_param.code,
+      tag_param.description,
+      authorized.ctx.actor(),
+    )
+    .await
+    .map_err(invalid)?;
+  Ok((StatusCode::CREATED, Json(TagDto::new(&a_tag))))
+}
+
+/// Replace the code and the description of a tag.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * id: the id of the tag.
+/// * reference_controller: the controller responsible of the actions.
+/// * tag_param: the new code and description of the tag.
+///
+/// # Return:
+/// * Ok with the updated tag.
+/// * Err with 400 status code if the values are invalid or the code is
+///   taken.
+/// * Err with 403 status code if the user isn't an editor.
+/// * Err with 404 status code if the tag doesn't exist.
+async fn update_tag(
+  authorized: Authorized<CanEdit>,
+  Path(id): Path<i32>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+  Json(tag_param): Json<TagParam>,
+) -> Result<Json<TagDto>> {
+  println!("->> UPDATE TAG\n");
+  reference_controller
+    .update_tag(
+      id,
+      tag_param.code,
+      tag_param.description,
+      authorized.ctx.actor(),
+    )
+    .await
+    .map_err(invalid)?
+    .map(|a_tag| Json(TagDto::new(&a_tag)))
+    .ok_or(not_found(TAG, id))
+}
+
+/// Delete softly a tag, the attractions that have it lose it until an admin
+/// restores it.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an admin.
+/// * id: the id of the tag.
+/// * reference_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with 204 status code.
+/// * Err with 403 status code if the user isn't an admin.
+/// * Err with 404 status code if the tag doesn't exist.
+async fn delete_tag(
+  authorized: Authorized<CanAdmin>,
+  Path(id): Path<i32>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+) -> Result<StatusCode> {
+  println!("->> DELETE TAG\n");
+  match reference_controller
+    .delete_tag(id, authorized.ctx.actor())
+    .await
+  {
+    Ok(true) => Ok(StatusCode::NO_CONTENT),
+    Ok(false) => Err(not_found(TAG, id)),
+    Err(e) => Err(invalid(e)),
+  }
+}
+
+/// List the countries.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * reference_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of countries sorted by iso code.
+/// * Err with the error.
+async fn list_countries(
+  _authorized: Authorized<CanRead>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+) -> Result<Json<Vec<CountryDto>>> {
+  println!("->> COUNTRIES\n");
+  let countries = reference_controller.countries().await.unwrap_or_default();
+  Ok(Json(countries.iter().map(CountryDto::new).collect()))
+}
+
+/// Register a new country.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * reference_controller: the controller responsible of the actions.
+/// * country_param: the iso code, of 2 or 3 letters, and the description.
+///
+/// # Return:
+/// * Ok with 201 status code and the new country.
+/// * Err with 400 status code if the values are invalid or the iso code is
+///   taken.
+/// * Err with 403 status code if the user isn't an editor.
+async fn create_country(
+  authorized: Authorized<CanEdit>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+  Json(country_param): Json<NewCountryParam>,
+) -> Result<(StatusCode, Json<CountryDto>)> {
+  println!("->> CREATE COUNTRY\n");
+  let a_country = reference_controller
+    .create_country(
+      country_param.iso_code,
+      country_param.description,
+      authorized.ctx.actor(),
+    )
+    .await
+    .map_err(invalid)?;
+  Ok((StatusCode::CREATED, Json(CountryDto::new(&a_country))))
+}
+
+/// Replace the description of a country.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * iso: the iso code of the country.
+/// * reference_controller: the controller responsible of the actions.
+/// * country_param: the new description of the country.
+///
+/// # Return:
+/// * Ok with the updated country.
+/// * Err with 400 status code if the description is empty.
+/// * Err with 403 status code if the user isn't an editor.
+/// * Err with 404 status code if the country doesn't exist.
+async fn update_country(
+  authorized: Authorized<CanEdit>,
+  Path(iso): Path<String>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+  Json(country_param): Json<CountryParam>,
+) -> Result<Json<CountryDto>> {
+  println!("->> UPDATE COUNTRY\n");
+  reference_controller
+    .update_country(
+      iso.clone(),
+      country_param.description,
+      authorized.ctx.actor(),
+    )
+    .await
+    .map_err(invalid)?
+    .map(|a_country| Json(CountryDto::new(&a_country)))
+    .ok_or(not_found(COUNTRY, iso))
+}
+
+/// Delete softly a country without cities, an admin can restore it.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an admin.
+/// * iso: the iso code of the country.
+/// * reference_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with 204 status code.
+/// * Err with 403 status code if the user isn't an admin.
+/// * Err with 404 status code if the country doesn't exist.
+/// * Err with 409 status code if the country has cities.
+async fn delete_country(
+  authorized: Authorized<CanAdmin>,
+  Path(iso): Path<String>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+) -> Result<StatusCode> {
+  println!("->> DELETE COUNTRY\n");
+  match reference_controller
+    .delete_country(iso.clone(), authorized.ctx.actor())
+    .await
+  {
+    Ok(true) => Ok(StatusCode::NO_CONTENT),
+    Ok(false) => Err(not_found(COUNTRY, iso)),
+    Err(e) => Err(in_use(COUNTRY, iso, e)),
+  }
+}
+
+/// List the cities.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * city_filter_param: the optional country of the cities, all of them by
+///   default.
+/// * reference_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of cities sorted by description.
+/// * Err with the error.
+async fn list_cities(
+  _authorized: Authorized<CanRead>,
+  Query(city_filter_param): Query<CityFilterParam>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+) -> Result<Json<Vec<CityDto>>> {
+  println!("->> CITIES\n");
+  let cities = reference_controller
+    .cities(city_filter_param.country_id)
+    .await
+    .unwrap_or_default();
+  Ok(Json(cities.iter().map(CityDto::new).collect()))
+}
+
+/// Register a new city.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * reference_controller: the controller responsible of the actions.
+/// * city_param: the description and the country of the city.
+///
+/// # Return:
+/// * Ok with 201 status code and the new city.
+/// * Err with 400 status code if the description is empty or the country
+///   doesn't exist.
+/// * Err with 403 status code if the user isn't an editor.
+async fn create_city(
+  authorized: Authorized<CanEdit>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+  Json(city_param): Json<CityParam>,
+) -> Result<(StatusCode, Json<CityDto>)> {
+  println!("->> CREATE CITY\n");
+  let a_city = reference_controller
+    .create_city(
+      city_param.description,
+      city_param.country_id,
+      authorized.ctx.actor(),
+    )
+    .await
+    .map_err(invalid)?;
+  Ok((StatusCode::CREATED, Json(CityDto::new(&a_city))))
+}
+
+/// Replace the description and the country of a city.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * id: the id of the city.
+/// * reference_controller: the controller responsible of the actions.
+/// * city_param: the new description and country of the city.
+///
+/// # Return:
+/// * Ok with the updated city.
+/// * Err with 400 status code if the description is empty or the country
+///   doesn't exist.
+/// * Err with 403 status code if the user isn't an editor.
+/// * Err with 404 status code if the city doesn't exist.
+async fn update_city(
+  authorized: Authorized<CanEdit>,
+  Path(id): Path<i32>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+  Json(city_param): Json<CityParam>,
+) -> Result<Json<CityDto>> {
+  println!("->> UPDATE CITY\n");
+  reference_controller
+    .update_city(
+      id,
+      city_param.description,
+      city_param.country_id,
+      authorized.ctx.actor(),
+    )
+    .await
+    .map_err(invalid)?
+    .map(|a_city| Json(CityDto::new(&a_city)))
+    .ok_or(not_found(CITY, id))
+}
+
+/// Delete softly a city without attractions, an admin can restore it.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an admin.
+/// * id: the id of the city.
+/// * reference_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with 204 status code.
+/// * Err with 403 status code if the user isn't an admin.
+/// * Err with 404 status code if the city doesn't exist.
+/// * Err with 409 status code if the city has attractions.
+async fn delete_city(
+  authorized: Authorized<CanAdmin>,
+  Path(id): Path<i32>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+) -> Result<StatusCode> {
+  println!("->> DELETE CITY\n");
+  match reference_controller
+    .delete_city(id, authorized.ctx.actor())
+    .await
+  {
+    Ok(true) => Ok(StatusCode::NO_CONTENT),
+    Ok(false) => Err(not_found(CITY, id)),
+    Err(e) => Err(in_use(CITY, id, e)),
+  }
+}
+
+/// List the public holidays of a country, the PH of the opening hours of its
+/// attractions.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * iso: the iso code of the country.
+/// * holiday_filter_param: the optional year of the holidays, all of them by
+///   default.
+/// * reference_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of holidays sorted by day.
+/// * Err with the error.
+async fn list_holidays(
+  _authorized: Authorized<CanRead>,
+  Path(iso): Path<String>,
+  Query(holiday_filter_param): Query<HolidayFilterParam>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+) -> Result<Json<Vec<HolidayDto>>> {
+  println!("->> HOLIDAYS\n");
+  let holidays = reference_controller
+    .holidays(iso, holiday_filter_param.year)
+    .await
+    .unwrap_or_default();
+  Ok(Json(holidays.iter().map(HolidayDto::new).collect()))
+}
+
+/// Register a public holiday of a country.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * iso: the iso code of the country.
+/// * reference_controller: the controller responsible of the actions.
+/// * holiday_param: the day and the description of the holiday.
+///
+/// # Return:
+/// * Ok with 201 status code and the new holiday.
+/// * Err with 400 status code if the description is empty or the country
+///   already has a holiday that day.
+/// * Err with 403 status code if the user isn't an editor.
+/// * Err with 404 status code if the country doesn't exist.
+async fn create_holiday(
+  authorized: Authorized<CanEdit>,
+  Path(iso): Path<String>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+  Json(holiday_param): Json<HolidayParam>,
+) -> Result<(StatusCode, Json<HolidayDto>)> {
+  println!("->> CREATE HOLIDAY\n");
+  reference_controller
+    .create_holiday(
+      iso.clone(),
+      holiday_param.day,
+      holiday_param.description,
+      authorized.ctx.actor(),
+    )
+    .await
+    .map_err(invalid)?
+    .map(|a_holiday| (StatusCode::CREATED, Json(HolidayDto::new(&a_holiday))))
+    .ok_or(not_found(COUNTRY, iso))
+}
+
+/// Delete a public holiday of a country.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * iso: the iso code of the country.
+/// * day: the day of the holiday.
+/// * reference_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with 204 status code.
+/// * Err with 403 status code if the user isn't an editor.
+/// * Err with 404 status code if the country doesn't have the holiday.
+async fn delete_holiday(
+  authorized: Authorized<CanEdit>,
+  Path((iso, day)): Path<(String, NaiveDate)>,
+  State(reference_controller): State<Arc<dyn ReferenceController>>,
+) -> Result<StatusCode> {
+  println!("->> DELETE HOLIDAY\n");
+  match reference_controller
+    .delete_holiday(iso.clone(), day, authorized.ctx.actor())
+    .await
+  {
+    Ok(true) => Ok(StatusCode::NO_CONTENT),
+    Ok(false) => Err(not_found(HOLIDAY, format!("{iso} {day}"))),
+    Err(e) => Err(invalid(e)),
+  }
+}
@@ -0,0 +1,279 @@
+use crate::{
+  application::{
+    mw_auth::{Authorized, CanRead},
+    ranking_api::RankedAttractionDto,
+  },
+  model::{
+    attraction::RegionRatingBucket,
+    granularity::Granularity,
+    rating_statistics::decimal_of,
+    region_controller::{RegionController, RegionStats},
+    region_stats::{Geography, Improvement, Region, TypeCount},
+  },
+  Error, Result,
+};
+use axum::{
+  extract::{Path, Query, State},
+  routing::get,
+  Json, Router,
+};
+use bigdecimal::BigDecimal;
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// The attractions listed as top rated and most improved by default.
+const DEFAULT_TOP: usize = 10;
+/// The days compared for the most improved by default.
+const DEFAULT_IMPROVEMENT_DAYS: i32 = 30;
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct RegionDto {
+  pub city_id: Option<i32>,
+  pub name: String,
+  pub iso_code: String,
+  pub country: String,
+  pub city_count: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct TypeCountDto {
+  pub attraction_type_id: i32,
+  pub code: String,
+  pub description: String,
+  pub count: usize,
+}
+
+impl TypeCountDto {
+  fn new(a_type_count: &TypeCount) -> Self {
+    TypeCountDto {
+      attraction_type_id: a_type_count.attraction_type_id,
+      code: a_type_count.code.clone(),
+      description: a_type_count.description.clone(),
+      count: a_type_count.count,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct RegionBucketDto {
+  pub at: NaiveDateTime,
+  pub rating_count: i64,
+  pub average: BigDecimal,
+}
+
+impl RegionBucketDto {
+  fn new(a_bucket: &RegionRatingBucket) -> Self {
+    RegionBucketDto {
+      at: a_bucket.at,
+      rating_count: a_bucket.rating_count,
+      average: a_bucket.average.with_scale(10).normalized(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct ImprovementDto {
+  pub attraction_id: i32,
+  pub description: String,
+  pub previous_count: i64,
+  pub previous_average: Option<BigDecimal>,
+  pub recent_count: i64,
+  pub recent_average: Option<BigDecimal>,
+  pub difference: Option<BigDecimal>,
+}
+
+impl ImprovementDto {
+  fn new(an_improvement: &Improvement) -> Self {
+    let change = &an_improvement.change;
+    let rounded = |value: &Option<BigDecimal>| {
+      value
+        .as_ref()
+        .map(|value| value.with_scale(10).normalized())
+    };
+    ImprovementDto {
+      attraction_id: change.attraction_id,
+      description: change.description.clone(),
+      previous_count: change.previous_count,
+      previous_average: rounded(&change.previous_average),
+      recent_count: change.recent_count,
+      recent_average: rounded(&change.recent_average),
+      difference: decimal_of(an_improvement.difference),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct GeographyDto {
+  pub centroid_latitude: f64,
+  pub centroid_longitude: f64,
+  pub min_latitude: f64,
+  pub min_longitude: f64,
+  pub max_latitude: f64,
+  pub max_longitude: f64,
+  pub located_attractions: usize,
+}
+
+impl GeographyDto {
+  fn new(a_geography: &Geography) -> Self {
+    GeographyDto {
+      centroid_latitude: a_geography.centroid_latitude,
+      centroid_longitude: a_geography.centroid_longitude,
+      min_latitude: a_geography.min_latitude,
+      min_longitude: a_geography.min_longitude,
+      max_latitude: a_geography.max_latitude,
+      max_longitude: a_geography.max_longitude,
+      located_attractions: a_geography.located_attractions,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct RegionStatsDto {
+  pub region: RegionDto,
+  pub attraction_count: usize,
+  pub attractions_by_type: Vec<TypeCountDto>,
+  pub rating_count: i64,
+  pub average: Option<BigDecimal>,
+  pub over_time: Vec<RegionBucketDto>,
+  pub top_rated: Vec<RankedAttractionDto>,
+  pub improvement_days: i32,
+  pub most_improved: Vec<ImprovementDto>,
+  pub geography: Option<GeographyDto>,
+}
+
+impl RegionStatsDto {
+  fn new(region_stats: &RegionStats) -> Self {
+    RegionStatsDto {
+      region: RegionDto {
+        city_id: region_stats.region.city_id(),
+        name: region_stats.info.name.clone(),
+        iso_code: region_stats.info.iso_code.clone(),
+        country: region_stats.info.country.clone(),
+        city_count: region_stats.info.city_count,
+      },
+      attraction_count: region_stats.attraction_count,
+      attractions_by_type: region_stats
+        .attractions_by_type
+        .iter()
+        .map(TypeCountDto::new)
+        .collect(),
+      rating_count: region_stats.rating_count,
+      average: region_stats.average.and_then(decimal_of),
+      over_time: region_stats
+        .over_time
+        .iter()
+        .map(RegionBucketDto::new)
+        .collect(),
+      top_rated: region_stats
+        .top_rated
+        .iter()
+        .map(RankedAttractionDto::new)
+        .collect(),
+      improvement_days: region_stats.improvement_days,
+      most_improved: region_stats
+        .most_improved
+        .iter()
+        .map(ImprovementDto::new)
+        .collect(),
+      geography: region_stats.geography.as_ref().map(GeographyDto::new),
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct RegionStatsParam {
+  #[serde(default)]
+  granularity: Granularity,
+  from: Option<NaiveDate>,
+  to: Option<NaiveDate>,
+  top: Option<usize>,
+  improvement_days: Option<i32>,
+}
+
+/// Defines the endpoints that handles the statistics of the cities and the
+/// countries.
+pub fn routes(region_controller: Arc<dyn RegionController>) -> Router {
+  Router::new()
+    .route("/city/:id/stats", get(city_stats))
+    .route("/country/:iso/stats", get(country_stats))
+    .with_state(region_controller)
+}
+
+/// The statistics of the attractions of a city.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * city_id: the id of the city.
+/// * region_stats_param: the granularity of the ratings over time (DAY by
+///   default), the optional inclusive from and to dates, the number of top
+///   rated and most improved attractions (10 by default) and the days of the
+///   periods compared for the most improved (30 by default).
+/// * region_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the statistics of the city.
+/// * Err with the error if the city doesn't exist.
+async fn city_stats(
+  _authorized: Authorized<CanRead>,
+  Path(city_id): Path<i32>,
+  Query(region_stats_param): Query<RegionStatsParam>,
+  State(region_controller): State<Arc<dyn RegionController>>,
+) -> Result<Json<RegionStatsDto>> {
+  println!("->> STATS for city: {city_id}\n");
+  region_stats(Region::City(city_id), region_stats_param, region_controller)
+    .await
+}
+
+/// The statistics of the attractions of a country.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * iso_code: the ISO code of the country, case insensitive.
+/// * region_stats_param: the same params of the statistics of a city.
+/// * region_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the statistics of the country.
+/// * Err with the error if the country doesn't exist.
+async fn country_stats(
+  _authorized: Authorized<CanRead>,
+  Path(iso_code): Path<String>,
+  Query(region_stats_param): Query<RegionStatsParam>,
+  State(region_controller): State<Arc<dyn RegionController>>,
+) -> Result<Json<RegionStatsDto>> {
+  println!("->> STATS for country: {iso_code}\n");
+  region_stats(
+    Region::Country(iso_code),
+    region_stats_param,
+    region_controller,
+  )
+  .await
+}
+
+async fn region_stats(
+  region: Region,
+  region_stats_param: RegionStatsParam,
+  region_controller: Arc<dyn RegionController>,
+) -> Result<Json<RegionStatsDto>> {
+  let start_of = |date: NaiveDate| date.and_time(NaiveTime::default());
+  let region_stats = region_controller
+    .stats(
+      region.clone(),
+      region_stats_param.granularity,
+      region_stats_param.from.map(start_of),
+      region_stats_param
+        .to
+        .map(|date| start_of(date) + Duration::days(1)),
+      region_stats_param.top.unwrap_or(DEFAULT_TOP),
+      region_stats_param
+        .improvement_days
+        .unwrap_or(DEFAULT_IMPROVEMENT_DAYS)
+        .max(1),
+    )
+    .await
+    .ok_or(Error::RegionNotFound {
+      region: format!("{region:?}"),
+    })?;
+  Ok(Json(RegionStatsDto::new(&region_stats)))
+}
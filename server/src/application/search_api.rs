@@ -0,0 +1,102 @@
+use crate::{
+  application::mw_auth::{Authorized, CanRead},
+  model::{
+    rating_statistics::decimal_of,
+    search::SearchHit,
+    search_controller::{SearchController, SearchQuery},
+  },
+  Error, Result,
+};
+use axum::{
+  extract::{Query, State},
+  routing::get,
+  Json, Router,
+};
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const DEFAULT_LIMIT: usize = 10;
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct SearchHitDto {
+  pub attraction_id: i32,
+  pub description: String,
+  pub city_id: i32,
+  pub attraction_type_id: i32,
+  pub score: Option<BigDecimal>,
+  pub similarity: Option<BigDecimal>,
+  pub prefix_match: bool,
+  pub rating_count: i64,
+  pub average_rate: Option<BigDecimal>,
+}
+
+impl SearchHitDto {
+  fn new(a_hit: &SearchHit) -> Self {
+    let a_candidate = &a_hit.candidate;
+    SearchHitDto {
+      attraction_id: a_candidate.attraction_id,
+      description: a_candidate.description.clone(),
+      city_id: a_candidate.city_id,
+      attraction_type_id: a_candidate.attraction_type_id,
+      score: decimal_of(a_hit.score),
+      similarity: decimal_of(a_candidate.similarity),
+      prefix_match: a_candidate.prefix_match,
+      rating_count: a_candidate.rating_count,
+      average_rate: a_candidate.average_rate.and_then(decimal_of),
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct SearchParam {
+  q: String,
+  city_id: Option<i32>,
+  attraction_type_id: Option<i32>,
+  limit: Option<usize>,
+}
+
+/// Defines the endpoints that handles the search of the attractions.
+pub fn routes(search_controller: Arc<dyn SearchController>) -> Router {
+  Router::new()
+    .route("/attraction/search", get(search))
+    .with_state(search_controller)
+}
+
+/// Search the attractions by their description, tolerating typos, accents
+/// and the last word being typed.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * search_controller: the controller responsible of the actions.
+/// * search_param: the query, the optional city and attraction type to search
+///   in and the most matches to return, 10 by default.
+///
+/// # Return:
+/// * Ok with the matches, the best first. The score is the similarity of the
+///   description boosted by the prefix match and the popularity.
+/// * Err with 400 status code if the query has no words or the limit is out
+///   of range.
+/// * Err with 403 status code if the request isn't authenticated.
+async fn search(
+  _authorized: Authorized<CanRead>,
+  State(search_controller): State<Arc<dyn SearchController>>,
+  Query(search_param): Query<SearchParam>,
+) -> Result<Json<Vec<SearchHitDto>>> {
+  println!("->> SEARCH\n");
+  let query = SearchQuery {
+    text: search_param.q,
+    city_id: search_param.city_id,
+    attraction_type_id: search_param.attraction_type_id,
+    limit: search_param.limit.unwrap_or(DEFAULT_LIMIT),
+  };
+  match search_controller.search(query).await {
+    Ok(hits) => Ok(Json(hits.iter().map(SearchHitDto::new).collect())),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidSearch {
+        reason: e,
+      })
+    },
+  }
+}
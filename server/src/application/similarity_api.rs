@@ -1,7 +1,17 @@
 use crate::{
+  application::mw_auth::{Authorized, CanAdmin, CanRead},
   model::{
-    attraction::AttractionRatingAggregate,
+    attraction::{AttractionRatingAggregate, AttractionRatingAnomaly},
+    forecast::{
+      Backtest, Forecast, ForecastMethod, ForecastPoint, ForecastSettings,
+    },
+    granularity::Granularity,
+    rating_statistics::decimal_of,
     similarity_controller::SimilarityController,
+    time_series::{
+      GapFill, TimeSeries, TimeSeriesOptions, TimeSeriesPoint, Trend,
+      MAX_POINTS,
+    },
   },
   Error, Result,
 };
@@ -11,7 +21,7 @@ use axum::{
   Json, Router,
 };
 use bigdecimal::BigDecimal;
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -22,6 +32,13 @@ pub struct RatingAggregateDto {
   pub average: BigDecimal,
   pub percentile_95: BigDecimal,
   pub percentile_99: BigDecimal,
+  pub count: i32,
+  pub min: BigDecimal,
+  pub max: BigDecimal,
+  pub median: BigDecimal,
+  pub standard_deviation: BigDecimal,
+  pub histogram: Vec<i32>,
+  pub granularity: String,
 }
 
 impl RatingAggregateDto {
@@ -32,47 +49,273 @@ impl RatingAggregateDto {
       average: a_rating_aggregate.get_average(),
       percentile_95: a_rating_aggregate.get_95_percentile(),
       percentile_99: a_rating_aggregate.get_99_percentile(),
+      count: a_rating_aggregate.get_rating_count(),
+      min: a_rating_aggregate.get_min_rate(),
+      max: a_rating_aggregate.get_max_rate(),
+      median: a_rating_aggregate.get_median(),
+      standard_deviation: a_rating_aggregate.get_standard_deviation(),
+      histogram: a_rating_aggregate.get_histogram(),
+      granularity: a_rating_aggregate.get_granularity(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct TimeSeriesPointDto {
+  pub at: NaiveDateTime,
+  pub count: i32,
+  pub average: Option<BigDecimal>,
+  pub median: Option<BigDecimal>,
+  pub percentile_95: Option<BigDecimal>,
+  pub percentile_99: Option<BigDecimal>,
+  pub moving_average: Option<BigDecimal>,
+  pub filled: bool,
+}
+
+impl TimeSeriesPointDto {
+  fn new(a_point: &TimeSeriesPoint) -> Self {
+    TimeSeriesPointDto {
+      at: a_point.at,
+      count: a_point.count,
+      average: a_point.average.clone(),
+      median: a_point.median.clone(),
+      percentile_95: a_point.percentile_95.clone(),
+      percentile_99: a_point.percentile_99.clone(),
+      moving_average: a_point.moving_average.clone(),
+      filled: a_point.filled,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct TrendDto {
+  pub days: i64,
+  pub slope_per_day: Option<BigDecimal>,
+  pub last_week_average: Option<BigDecimal>,
+  pub previous_week_average: Option<BigDecimal>,
+  pub week_over_week_change: Option<BigDecimal>,
+}
+
+impl TrendDto {
+  fn new(a_trend: &Trend) -> Self {
+    TrendDto {
+      days: a_trend.days,
+      slope_per_day: a_trend.slope_per_day.clone(),
+      last_week_average: a_trend.last_week_average.clone(),
+      previous_week_average: a_trend.previous_week_average.clone(),
+      week_over_week_change: a_trend.week_over_week_change.clone(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct TimeSeriesDto {
+  pub attraction_id: i32,
+  pub granularity: String,
+  pub points: Vec<TimeSeriesPointDto>,
+  pub trend: TrendDto,
+}
+
+impl TimeSeriesDto {
+  fn new(a_time_series: &TimeSeries) -> Self {
+    TimeSeriesDto {
+      attraction_id: a_time_series.attraction_id,
+      granularity: a_time_series.granularity.to_string(),
+      points: a_time_series
+        .points
+        .iter()
+        .map(TimeSeriesPointDto::new)
+        .collect(),
+      trend: TrendDto::new(&a_time_series.trend),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct AnomalyDto {
+  pub attraction_id: i32,
+  pub at: NaiveDateTime,
+  pub metric: String,
+  pub method: String,
+  pub value: BigDecimal,
+  pub baseline: BigDecimal,
+  pub score: BigDecimal,
+  pub detected_at: NaiveDateTime,
+}
+
+impl AnomalyDto {
+  fn new(an_anomaly: &AttractionRatingAnomaly) -> Self {
+    AnomalyDto {
+      attraction_id: an_anomaly.get_attraction_id(),
+      at: an_anomaly.get_at(),
+      metric: an_anomaly.get_metric(),
+      method: an_anomaly.get_method(),
+      value: an_anomaly.get_value(),
+      baseline: an_anomaly.get_baseline(),
+      score: an_anomaly.get_score(),
+      detected_at: an_anomaly.get_detected_at(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct ForecastPointDto {
+  pub at: NaiveDateTime,
+  pub value: Option<BigDecimal>,
+  pub lower: Option<BigDecimal>,
+  pub upper: Option<BigDecimal>,
+}
+
+impl ForecastPointDto {
+  fn new(a_point: &ForecastPoint) -> Self {
+    ForecastPointDto {
+      at: a_point.at,
+      value: decimal_of(a_point.value),
+      lower: decimal_of(a_point.lower),
+      upper: decimal_of(a_point.upper),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct BacktestDto {
+  pub days: usize,
+  pub evaluated_days: usize,
+  pub mae: Option<BigDecimal>,
+  pub mape: Option<BigDecimal>,
+}
+
+impl BacktestDto {
+  fn new(a_backtest: &Backtest) -> Self {
+    BacktestDto {
+      days: a_backtest.days,
+      evaluated_days: a_backtest.evaluated_days,
+      mae: decimal_of(a_backtest.mae),
+      mape: a_backtest.mape.and_then(decimal_of),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct ForecastDto {
+  pub attraction_id: i32,
+  pub method: String,
+  pub alpha: Option<BigDecimal>,
+  pub beta: Option<BigDecimal>,
+  pub gamma: Option<BigDecimal>,
+  pub history_days: usize,
+  pub residual_sd: Option<BigDecimal>,
+  pub trend_per_day: Option<BigDecimal>,
+  pub confidence_z: Option<BigDecimal>,
+  pub points: Vec<ForecastPointDto>,
+  pub backtest: Option<BacktestDto>,
+}
+
+impl ForecastDto {
+  fn new(
+    attraction_id: i32,
+    a_forecast: &Forecast,
+    settings: &ForecastSettings,
+  ) -> Self {
+    ForecastDto {
+      attraction_id,
+      method: a_forecast.method.to_string(),
+      alpha: decimal_of(a_forecast.smoothing.alpha),
+      beta: a_forecast.smoothing.beta.and_then(decimal_of),
+      gamma: a_forecast.smoothing.gamma.and_then(decimal_of),
+      history_days: a_forecast.history_days,
+      residual_sd: decimal_of(a_forecast.residual_sd),
+      trend_per_day: a_forecast.trend_per_day.and_then(decimal_of),
+      confidence_z: decimal_of(settings.confidence_z),
+      points: a_forecast
+        .points
+        .iter()
+        .map(ForecastPointDto::new)
+        .collect(),
+      backtest: a_forecast.backtest.as_ref().map(BacktestDto::new),
     }
   }
 }
 
 #[derive(Deserialize)]
-struct AttractionParam {
+struct ForecastParam {
   attraction_id: i32,
+  #[serde(default)]
+  method: ForecastMethod,
+  horizon: Option<usize>,
+  history_days: Option<i64>,
+  backtest_days: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct AnomalyParam {
+  attraction_id: Option<i32>,
+  from: Option<NaiveDate>,
+  to: Option<NaiveDate>,
+}
+
+#[derive(Deserialize)]
+struct TimeSeriesParam {
+  attraction_id: i32,
+  #[serde(default)]
+  granularity: Granularity,
+  from: Option<NaiveDate>,
+  to: Option<NaiveDate>,
+  #[serde(default)]
+  fill: GapFill,
+  window: Option<usize>,
+  trend_days: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct AggregateParam {
+  attraction_id: i32,
+  #[serde(default)]
+  granularity: Granularity,
+  from: Option<NaiveDate>,
+  to: Option<NaiveDate>,
 }
 
 /// Defines the endpoints that handles the interaction with the similarity and
 /// the attractions.
 pub fn routes(similarity_controller: Arc<dyn SimilarityController>) -> Router {
   Router::new()
-    .route(
-      "/similarity/aggregate",
-      get(list_ratings_aggregate),
-    )
+    .route("/similarity/aggregate", get(list_ratings_aggregate))
+    .route("/similarity/timeseries", get(rating_time_series))
+    .route("/similarity/anomalies", get(list_anomalies))
+    .route("/similarity/forecast", get(forecast))
     .route("/similarity/calculate", post(calculate))
     .with_state(similarity_controller)
 }
 
-/// List all the aggregates ratings from all the attractions.
+/// List the aggregates ratings from an attraction, sorted by date.
 ///
 /// # Arguments:
-/// * attraction_param: the attraction query param necessary to retrieve the
-/// aggregates.
+/// * _authorized: the context of the request, any role can read.
+/// * aggregate_param: the query params necessary to retrieve the aggregates,
+///   the attraction, the granularity (DAY by default) and the optional
+///   inclusive from and to dates.
 /// * similarity_controller: the controller responsible of the actions.
 ///
 /// # Return:
 /// * Ok with a vector of rating aggregates.
 /// * Err with the error.
 async fn list_ratings_aggregate(
-  Query(attraction_param): Query<AttractionParam>,
+  _authorized: Authorized<CanRead>,
+  Query(aggregate_param): Query<AggregateParam>,
   State(similarity_controller): State<Arc<dyn SimilarityController>>,
 ) -> Result<Json<Vec<RatingAggregateDto>>> {
   println!(
-    "->> AGGREGATES for attraction: {}\n",
-    attraction_param.attraction_id
+    "->> AGGREGATES for attraction: {} by {}\n",
+    aggregate_param.attraction_id, aggregate_param.granularity
   );
   let all_aggregate_ratings = similarity_controller
-    .list_rating_aggregate(attraction_param.attraction_id)
+    .list_rating_aggregate(
+      aggregate_param.attraction_id,
+      aggregate_param.granularity,
+      aggregate_param.from,
+      aggregate_param.to,
+    )
     .await
     .unwrap_or_default();
   let dtos = all_aggregate_ratings
@@ -82,14 +325,162 @@ async fn list_ratings_aggregate(
   Ok(Json(dtos))
 }
 
+/// The evolution of the ratings of an attraction, one point per bucket of the
+/// granularity, with a moving average and the trend indicators.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * time_series_param: the query params of the series, the attraction, the
+///   granularity (DAY by default), the optional inclusive from and to dates,
+///   how the gaps are filled (NULL by default), the buckets of the moving
+///   average (7 by default) and the days of the trend (30 by default).
+/// * similarity_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the time series.
+/// * Err with the error if the range is invalid or too long.
+async fn rating_time_series(
+  _authorized: Authorized<CanRead>,
+  Query(time_series_param): Query<TimeSeriesParam>,
+  State(similarity_controller): State<Arc<dyn SimilarityController>>,
+) -> Result<Json<TimeSeriesDto>> {
+  println!(
+    "->> TIME SERIES for attraction: {} by {}\n",
+    time_series_param.attraction_id, time_series_param.granularity
+  );
+  let defaults = TimeSeriesOptions::default();
+  let options = TimeSeriesOptions {
+    fill: time_series_param.fill,
+    moving_average_window: time_series_param
+      .window
+      .unwrap_or(defaults.moving_average_window),
+    trend_days: time_series_param.trend_days.unwrap_or(defaults.trend_days),
+  };
+  match similarity_controller
+    .rating_time_series(
+      time_series_param.attraction_id,
+      time_series_param.granularity,
+      time_series_param.from,
+      time_series_param.to,
+      options,
+    )
+    .await
+  {
+    Ok(time_series) => Ok(Json(TimeSeriesDto::new(&time_series))),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidTimeSeries {
+        reason: e,
+      })
+    },
+  }
+}
+
+/// List the anomalies detected in the daily ratings, the latest first.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * anomaly_param: the optional attraction, all of them by default, and the
+///   optional inclusive from and to dates.
+/// * similarity_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of anomalies.
+/// * Err with the error.
+async fn list_anomalies(
+  _authorized: Authorized<CanRead>,
+  Query(anomaly_param): Query<AnomalyParam>,
+  State(similarity_controller): State<Arc<dyn SimilarityController>>,
+) -> Result<Json<Vec<AnomalyDto>>> {
+  println!(
+    "->> ANOMALIES for attraction: {:?}\n",
+    anomaly_param.attraction_id
+  );
+  let anomalies = similarity_controller
+    .list_anomalies(
+      anomaly_param.attraction_id,
+      anomaly_param.from,
+      anomaly_param.to,
+    )
+    .await
+    .unwrap_or_default();
+  let dtos = anomalies
+    .iter()
+    .map(AnomalyDto::new)
+    .collect::<Vec<AnomalyDto>>();
+  Ok(Json(dtos))
+}
+
+/// Forecast the daily average rating of an attraction with an exponential
+/// smoothing model, with confidence intervals and the error of the model on
+/// the last days.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * forecast_param: the attraction, the model (HOLT_WINTERS by default,
+///   with a weekly season), the days to forecast (30 by default), the days of
+///   history used to fit it (365 by default) and the last days held out for
+///   the backtest (28 by default, 0 to skip it).
+/// * similarity_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the forecast.
+/// * Err with the error if there isn't enough history for the model.
+async fn forecast(
+  _authorized: Authorized<CanRead>,
+  Query(forecast_param): Query<ForecastParam>,
+  State(similarity_controller): State<Arc<dyn SimilarityController>>,
+) -> Result<Json<ForecastDto>> {
+  println!(
+    "->> FORECAST for attraction: {} by {}\n",
+    forecast_param.attraction_id, forecast_param.method
+  );
+  let defaults = ForecastSettings::default();
+  let settings = ForecastSettings {
+    method: forecast_param.method,
+    horizon_days: forecast_param
+      .horizon
+      .unwrap_or(defaults.horizon_days)
+      .min(MAX_POINTS),
+    history_days: forecast_param
+      .history_days
+      .unwrap_or(defaults.history_days)
+      .clamp(1, MAX_POINTS as i64),
+    backtest_days: forecast_param
+      .backtest_days
+      .unwrap_or(defaults.backtest_days),
+    ..defaults
+  };
+  match similarity_controller
+    .forecast(forecast_param.attraction_id, settings)
+    .await
+  {
+    Ok(forecast) => Ok(Json(ForecastDto::new(
+      forecast_param.attraction_id,
+      &forecast,
+      &settings,
+    ))),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidForecast {
+        reason: e,
+      })
+    },
+  }
+}
+
 /// Calculate the similarity between all the attractions.
 ///
 /// # Arguments:
+/// * _authorized: the context of the request, it must be an admin.
 /// * similarity_controller: the controller responsible of the actions.
 ///
 /// # Return:
+/// * Err with 403 status code if the request isn't authenticated or the user
+///   isn't an admin.
 /// * Err with 500 status code.
 async fn calculate(
+  _authorized: Authorized<CanAdmin>,
   State(similarity_controller): State<Arc<dyn SimilarityController>>,
 ) -> Result<()> {
   println!("->> CALCULATE AGGREGATE\n");
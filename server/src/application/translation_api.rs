@@ -0,0 +1,257 @@
+use crate::{
+  application::mw_auth::{Authorized, CanEdit, CanRead},
+  model::{
+    translation::{locales_of, Translation, TranslationKey},
+    translation_controller::TranslationController,
+  },
+  Error, Result,
+};
+use async_trait::async_trait;
+use axum::{
+  extract::{FromRequestParts, Path, State},
+  http::{header::ACCEPT_LANGUAGE, request::Parts, StatusCode},
+  routing::{get, put},
+  Json, Router,
+};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// The locales of the Accept-Language header of the request, the preferred
+/// first and followed by their fallbacks. Without the header, or without a
+/// valid locale in it, there isn't any and the descriptions aren't
+/// translated.
+pub struct Locales(pub Vec<String>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Locales {
+  type Rejection = Error;
+
+  async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self> {
+    let locales = parts
+      .headers
+      .get(ACCEPT_LANGUAGE)
+      .and_then(|a_header| a_header.to_str().ok())
+      .map(locales_of)
+      .unwrap_or_default();
+    Ok(Locales(locales))
+  }
+}
+
+/// An entity with translated descriptions, that tells how it's identified in
+/// the path.
+pub trait Translated {
+  fn key_of(key: String) -> Option<TranslationKey>;
+}
+
+pub struct OfAttraction;
+pub struct OfAttractionType;
+pub struct OfCountry;
+pub struct OfCity;
+
+impl Translated for OfAttraction {
+  fn key_of(key: String) -> Option<TranslationKey> {
+    key.parse().ok().map(TranslationKey::Attraction)
+  }
+}
+
+impl Translated for OfAttractionType {
+  fn key_of(key: String) -> Option<TranslationKey> {
+    key.parse().ok().map(TranslationKey::AttractionType)
+  }
+}
+
+impl Translated for OfCountry {
+  fn key_of(key: String) -> Option<TranslationKey> {
+    Some(TranslationKey::Country(key))
+  }
+}
+
+impl Translated for OfCity {
+  fn key_of(key: String) -> Option<TranslationKey> {
+    key.parse().ok().map(TranslationKey::City)
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct TranslationDto {
+  pub locale: String,
+  pub description: String,
+  pub updated_at: Option<NaiveDateTime>,
+}
+
+impl TranslationDto {
+  fn new(a_translation: &Translation) -> Self {
+    TranslationDto {
+      locale: a_translation.locale.to_string(),
+      description: a_translation.description.to_string(),
+      updated_at: Some(a_translation.updated_at),
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct TranslationParam {
+  description: String,
+}
+
+/// Defines the endpoints that handles the translations of the descriptions
+/// of the attractions, their types, the countries and the cities.
+pub fn routes(
+  translation_controller: Arc<dyn TranslationController>,
+) -> Router {
+  Router::new()
+    .route(
+      "/attraction/:id/translations",
+      get(list_translations::<OfAttraction>),
+    )
+    .route(
+      "/attraction/:id/translations/:locale",
+      put(set_translation::<OfAttraction>)
+        .delete(delete_translation::<OfAttraction>),
+    )
+    .route(
+      "/attraction-type/:id/translations",
+      get(list_translations::<OfAttractionType>),
+    )
+    .route(
+      "/attraction-type/:id/translations/:locale",
+      put(set_translation::<OfAttractionType>)
+        .delete(delete_translation::<OfAttractionType>),
+    )
+    .route(
+      "/country/:iso/translations",
+      get(list_translations::<OfCountry>),
+    )
+    .route(
+      "/country/:iso/translations/:locale",
+      put(set_translation::<OfCountry>).delete(delete_translation::<OfCountry>),
+    )
+    .route("/city/:id/translations", get(list_translations::<OfCity>))
+    .route(
+      "/city/:id/translations/:locale",
+      put(set_translation::<OfCity>).delete(delete_translation::<OfCity>),
+    )
+    .with_state(translation_controller)
+}
+
+fn key_of<T: Translated>(key: String) -> Result<TranslationKey> {
+  T::key_of(key.clone()).ok_or(Error::InvalidTranslation {
+    reason: format!("The key {key} isn't valid"),
+  })
+}
+
+fn not_found(key: &TranslationKey) -> Error {
+  match key {
+    TranslationKey::Attraction(id) => Error::AttractionNotFound {
+      id: *id,
+    },
+    _ => Error::ReferenceNotFound {
+      kind: key.kind().to_string(),
+      key: key.key(),
+    },
+  }
+}
+
+/// List the translations of the description of an entity.
+///
+/// # Arguments:
+/// * _authorized: the context of the request, any role can read.
+/// * key: the id of the entity, or the iso code of the country.
+/// * translation_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the translations by locale, none if the entity doesn't exist.
+/// * Err with 400 status code if the key isn't valid.
+async fn list_translations<T: Translated>(
+  _authorized: Authorized<CanRead>,
+  Path(key): Path<String>,
+  State(translation_controller): State<Arc<dyn TranslationController>>,
+) -> Result<Json<Vec<TranslationDto>>> {
+  println!("->> TRANSLATIONS\n");
+  let key = key_of::<T>(key)?;
+  let translations = translation_controller
+    .translations(key)
+    .await
+    .unwrap_or_default();
+  Ok(Json(translations.iter().map(TranslationDto::new).collect()))
+}
+
+/// Create or replace the translation of the description of an entity.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * key: the id of the entity, or the iso code of the country.
+/// * locale: the locale of the translation, like "es" or "pt-BR".
+/// * translation_controller: the controller responsible of the actions.
+/// * translation_param: the translated description.
+///
+/// # Return:
+/// * Ok with the translation, with its locale in the canonical form.
+/// * Err with 400 status code if the key, the locale or the description
+///   aren't valid.
+/// * Err with 403 status code if the user isn't an editor.
+/// * Err with 404 status code if the entity doesn't exist.
+async fn set_translation<T: Translated>(
+  authorized: Authorized<CanEdit>,
+  Path((key, locale)): Path<(String, String)>,
+  State(translation_controller): State<Arc<dyn TranslationController>>,
+  Json(translation_param): Json<TranslationParam>,
+) -> Result<Json<TranslationDto>> {
+  println!("->> SET TRANSLATION\n");
+  let key = key_of::<T>(key)?;
+  translation_controller
+    .set_translation(
+      key.clone(),
+      locale,
+      translation_param.description,
+      authorized.ctx.actor(),
+    )
+    .await
+    .map_err(|e| {
+      println!("xx->> {}", e);
+      Error::InvalidTranslation {
+        reason: e,
+      }
+    })?
+    .map(|a_translation| Json(TranslationDto::new(&a_translation)))
+    .ok_or(not_found(&key))
+}
+
+/// Delete the translation of the description of an entity.
+///
+/// # Arguments:
+/// * authorized: the context of the request, it must be an editor.
+/// * key: the id of the entity, or the iso code of the country.
+/// * locale: the locale of the translation.
+/// * translation_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with 204 status code.
+/// * Err with 400 status code if the key or the locale aren't valid.
+/// * Err with 403 status code if the user isn't an editor.
+/// * Err with 404 status code if the entity doesn't have the translation.
+async fn delete_translation<T: Translated>(
+  authorized: Authorized<CanEdit>,
+  Path((key, locale)): Path<(String, String)>,
+  State(translation_controller): State<Arc<dyn TranslationController>>,
+) -> Result<StatusCode> {
+  println!("->> DELETE TRANSLATION\n");
+  let key = key_of::<T>(key)?;
+  match translation_controller
+    .delete_translation(key.clone(), locale.clone(), authorized.ctx.actor())
+    .await
+  {
+    Ok(true) => Ok(StatusCode::NO_CONTENT),
+    Ok(false) => Err(Error::TranslationNotFound {
+      kind: key.kind().to_string(),
+      key: format!("{}/{locale}", key.key()),
+    }),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidTranslation {
+        reason: e,
+      })
+    },
+  }
+}
@@ -0,0 +1,668 @@
+use crate::{
+  application::{
+    attraction_api::valid_rate,
+    mw_auth::{Authorized, CanVisit},
+  },
+  model::{
+    attraction_controller::RatingFail,
+    visit::{CheckIn, NewCheckIn, Wishlist, WishlistAttraction},
+    visit_controller::VisitController,
+  },
+  Error, Result,
+};
+use axum::{
+  extract::{Path, Query, State},
+  http::StatusCode,
+  routing::{delete, get, post},
+  Json, Router,
+};
+use bigdecimal::BigDecimal;
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct WishlistDto {
+  pub id: i32,
+  pub user_id: i32,
+  pub name: String,
+  /// The path to read the wishlist without logging in, none if it isn't
+  /// shared.
+  pub share_path: Option<String>,
+  pub created_at: NaiveDateTime,
+  pub updated_at: NaiveDateTime,
+}
+
+impl WishlistDto {
+  fn new(a_wishlist: &Wishlist) -> Self {
+    WishlistDto {
+      id: a_wishlist.id,
+      user_id: a_wishlist.user_id,
+      name: a_wishlist.name.clone(),
+      share_path: a_wishlist
+        .share_token
+        .as_ref()
+        .map(|token| format!("/shared-wishlist/{token}")),
+      created_at: a_wishlist.created_at,
+      updated_at: a_wishlist.updated_at,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct WishlistAttractionDto {
+  pub attraction_id: i32,
+  pub description: String,
+  pub city_id: i32,
+  pub position: i32,
+  pub added_at: NaiveDateTime,
+}
+
+impl WishlistAttractionDto {
+  fn new(an_attraction: &WishlistAttraction) -> Self {
+    WishlistAttractionDto {
+      attraction_id: an_attraction.attraction_id,
+      description: an_attraction.description.clone(),
+      city_id: an_attraction.city_id,
+      position: an_attraction.position,
+      added_at: an_attraction.added_at,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct WishlistDetailDto {
+  pub wishlist: WishlistDto,
+  pub attractions: Vec<WishlistAttractionDto>,
+}
+
+impl WishlistDetailDto {
+  fn new(
+    (a_wishlist, attractions): &(Wishlist, Vec<WishlistAttraction>),
+  ) -> Self {
+    WishlistDetailDto {
+      wishlist: WishlistDto::new(a_wishlist),
+      attractions: attractions.iter().map(WishlistAttractionDto::new).collect(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct CheckInDto {
+  pub id: i32,
+  pub user_id: i32,
+  pub attraction_id: i32,
+  pub at: NaiveDateTime,
+  pub rating_id: Option<i32>,
+  pub rate: Option<BigDecimal>,
+  /// The status of the rating, it's quarantined if it looks suspicious.
+  pub rating_status: Option<String>,
+  pub created_at: NaiveDateTime,
+}
+
+impl CheckInDto {
+  fn new(a_check_in: &CheckIn) -> Self {
+    CheckInDto {
+      id: a_check_in.id,
+      user_id: a_check_in.user_id,
+      attraction_id: a_check_in.attraction_id,
+      at: a_check_in.at,
+      rating_id: a_check_in.rating_id,
+      rate: a_check_in.rate.clone(),
+      rating_status: a_check_in.rating_status.clone(),
+      created_at: a_check_in.created_at,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct WishlistParam {
+  name: String,
+}
+
+#[derive(Deserialize)]
+struct WishlistAttractionsParam {
+  attraction_ids: Vec<i32>,
+}
+
+#[derive(Deserialize)]
+struct WishlistAttractionParam {
+  attraction_id: i32,
+}
+
+#[derive(Deserialize)]
+struct CheckInParam {
+  attraction_id: i32,
+  at: Option<NaiveDateTime>,
+  rate: Option<BigDecimal>,
+}
+
+#[derive(Deserialize)]
+struct CheckInFilterParam {
+  attraction_id: Option<i32>,
+}
+
+/// Defines the endpoints that handles the wishlists and the check-ins of the
+/// users.
+pub fn routes(visit_controller: Arc<dyn VisitController>) -> Router {
+  Router::new()
+    .route("/wishlist", get(wishlists).post(create_wishlist))
+    .route(
+      "/wishlist/:id",
+      get(wishlist).put(rename_wishlist).delete(delete_wishlist),
+    )
+    .route(
+      "/wishlist/:id/attractions",
+      post(add_wishlist_attraction).put(set_wishlist_attractions),
+    )
+    .route(
+      "/wishlist/:id/attractions/:attraction_id",
+      delete(remove_wishlist_attraction),
+    )
+    .route(
+      "/wishlist/:id/share",
+      post(share_wishlist).delete(unshare_wishlist),
+    )
+    .route("/shared-wishlist/:token", get(shared_wishlist))
+    .route("/check-in", get(check_ins).post(check_in))
+    .route("/check-in/:id", get(check_in_by_id).delete(delete_check_in))
+    .with_state(visit_controller)
+}
+
+fn wishlist_not_found(id: i32) -> Error {
+  Error::VisitNotFound {
+    kind: String::from("wishlist"),
+    key: id.to_string(),
+  }
+}
+
+/// List the wishlists of the user.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the owner of the wishlists.
+/// * visit_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of wishlists, without their attractions.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+async fn wishlists(
+  authorized: Authorized<CanVisit>,
+  State(visit_controller): State<Arc<dyn VisitController>>,
+) -> Result<Json<Vec<WishlistDto>>> {
+  println!("->> WISHLISTS\n");
+  let wishlists = visit_controller
+    .wishlists(authorized.ctx.user_id())
+    .await
+    .unwrap_or_default();
+  Ok(Json(wishlists.iter().map(WishlistDto::new).collect()))
+}
+
+/// Create an empty wishlist for the user.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the owner of the wishlist.
+/// * visit_controller: the controller responsible of the actions.
+/// * wishlist_param: the name of the wishlist, unique for the user.
+///
+/// # Return:
+/// * Ok with 201 status code and the wishlist.
+/// * Err with 400 status code if the name is invalid or already taken.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+async fn create_wishlist(
+  authorized: Authorized<CanVisit>,
+  State(visit_controller): State<Arc<dyn VisitController>>,
+  Json(wishlist_param): Json<WishlistParam>,
+) -> Result<(StatusCode, Json<WishlistDto>)> {
+  println!("->> CREATE WISHLIST\n");
+  match visit_controller
+    .create_wishlist(authorized.ctx.user_id(), wishlist_param.name)
+    .await
+  {
+    Ok(a_wishlist) => {
+      Ok((StatusCode::CREATED, Json(WishlistDto::new(&a_wishlist))))
+    },
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidVisit {
+        reason: e,
+      })
+    },
+  }
+}
+
+/// Get a wishlist of the user with its attractions.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the owner of the wishlist.
+/// * id: the id of the wishlist.
+/// * visit_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the wishlist and its attractions, in order.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+/// * Err with 404 status code if the wishlist doesn't exist or belongs to
+///   another user.
+async fn wishlist(
+  authorized: Authorized<CanVisit>,
+  Path(id): Path<i32>,
+  State(visit_controller): State<Arc<dyn VisitController>>,
+) -> Result<Json<WishlistDetailDto>> {
+  println!("->> WISHLIST\n");
+  visit_controller
+    .wishlist(id, authorized.ctx.user_id())
+    .await
+    .map(|a_detail| Json(WishlistDetailDto::new(&a_detail)))
+    .ok_or(wishlist_not_found(id))
+}
+
+/// Rename a wishlist of the user.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the owner of the wishlist.
+/// * id: the id of the wishlist.
+/// * visit_controller: the controller responsible of the actions.
+/// * wishlist_param: the new name of the wishlist.
+///
+/// # Return:
+/// * Ok with the renamed wishlist.
+/// * Err with 400 status code if the name is invalid or already taken.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+/// * Err with 404 status code if the wishlist doesn't exist or belongs to
+///   another user.
+async fn rename_wishlist(
+  authorized: Authorized<CanVisit>,
+  Path(id): Path<i32>,
+  State(visit_controller): State<Arc<dyn VisitController>>,
+  Json(wishlist_param): Json<WishlistParam>,
+) -> Result<Json<WishlistDto>> {
+  println!("->> RENAME WISHLIST\n");
+  match visit_controller
+    .rename_wishlist(id, authorized.ctx.user_id(), wishlist_param.name)
+    .await
+  {
+    Ok(Some(a_wishlist)) => Ok(Json(WishlistDto::new(&a_wishlist))),
+    Ok(None) => Err(wishlist_not_found(id)),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidVisit {
+        reason: e,
+      })
+    },
+  }
+}
+
+/// Delete a wishlist of the user, the shared link stops working.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the owner of the wishlist.
+/// * id: the id of the wishlist.
+/// * visit_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with 204 status code.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+/// * Err with 404 status code if the wishlist doesn't exist or belongs to
+///   another user.
+async fn delete_wishlist(
+  authorized: Authorized<CanVisit>,
+  Path(id): Path<i32>,
+  State(visit_controller): State<Arc<dyn VisitController>>,
+) -> Result<StatusCode> {
+  println!("->> DELETE WISHLIST\n");
+  match visit_controller
+    .delete_wishlist(id, authorized.ctx.user_id())
+    .await
+  {
+    Some(true) => Ok(StatusCode::NO_CONTENT),
+    _ => Err(wishlist_not_found(id)),
+  }
+}
+
+/// Replace the attractions of a wishlist, the order given is the order of
+/// the list.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the owner of the wishlist.
+/// * id: the id of the wishlist.
+/// * visit_controller: the controller responsible of the actions.
+/// * wishlist_attractions_param: the ids of the attractions, the repeated
+///   ones are listed once.
+///
+/// # Return:
+/// * Ok with the attractions of the wishlist, in order.
+/// * Err with 400 status code if any attraction doesn't exist or there are
+///   too many.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+/// * Err with 404 status code if the wishlist doesn't exist or belongs to
+///   another user.
+async fn set_wishlist_attractions(
+  authorized: Authorized<CanVisit>,
+  Path(id): Path<i32>,
+  State(visit_controller): State<Arc<dyn VisitController>>,
+  Json(wishlist_attractions_param): Json<WishlistAttractionsParam>,
+) -> Result<Json<Vec<WishlistAttractionDto>>> {
+  println!("->> SET WISHLIST ATTRACTIONS\n");
+  match visit_controller
+    .set_wishlist_attractions(
+      id,
+      authorized.ctx.user_id(),
+      wishlist_attractions_param.attraction_ids,
+    )
+    .await
+  {
+    Ok(Some(attractions)) => Ok(Json(
+      attractions.iter().map(WishlistAttractionDto::new).collect(),
+    )),
+    Ok(None) => Err(wishlist_not_found(id)),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidVisit {
+        reason: e,
+      })
+    },
+  }
+}
+
+/// Add an attraction at the end of a wishlist, nothing changes if the list
+/// already has it.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the owner of the wishlist.
+/// * id: the id of the wishlist.
+/// * visit_controller: the controller responsible of the actions.
+/// * wishlist_attraction_param: the id of the attraction.
+///
+/// # Return:
+/// * Ok with the attractions of the wishlist, in order.
+/// * Err with 400 status code if the attraction doesn't exist or the list is
+///   full.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+/// * Err with 404 status code if the wishlist doesn't exist or belongs to
+///   another user.
+async fn add_wishlist_attraction(
+  authorized: Authorized<CanVisit>,
+  Path(id): Path<i32>,
+  State(visit_controller): State<Arc<dyn VisitController>>,
+  Json(wishlist_attraction_param): Json<WishlistAttractionParam>,
+) -> Result<Json<Vec<WishlistAttractionDto>>> {
+  println!("->> ADD WISHLIST ATTRACTION\n");
+  match visit_controller
+    .add_wishlist_attraction(
+      id,
+      authorized.ctx.user_id(),
+      wishlist_attraction_param.attraction_id,
+    )
+    .await
+  {
+    Ok(Some(attractions)) => Ok(Json(
+      attractions.iter().map(WishlistAttractionDto::new).collect(),
+    )),
+    Ok(None) => Err(wishlist_not_found(id)),
+    Err(e) => {
+      println!("xx->> {}", e);
+      Err(Error::InvalidVisit {
+        reason: e,
+      })
+    },
+  }
+}
+
+/// Remove an attraction from a wishlist.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the owner of the wishlist.
+/// * id: the id of the wishlist.
+/// * attraction_id: the id of the attraction.
+/// * visit_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with 204 status code.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+/// * Err with 404 status code if the wishlist doesn't exist, belongs to
+///   another user or doesn't have the attraction.
+async fn remove_wishlist_attraction(
+  authorized: Authorized<CanVisit>,
+  Path((id, attraction_id)): Path<(i32, i32)>,
+  State(visit_controller): State<Arc<dyn VisitController>>,
+) -> Result<StatusCode> {
+  println!("->> REMOVE WISHLIST ATTRACTION\n");
+  match visit_controller
+    .remove_wishlist_attraction(id, authorized.ctx.user_id(), attraction_id)
+    .await
+  {
+    Some(true) => Ok(StatusCode::NO_CONTENT),
+    _ => Err(Error::VisitNotFound {
+      kind: String::from("wishlist attraction"),
+      key: format!("{id}/{attraction_id}"),
+    }),
+  }
+}
+
+/// Share a wishlist, anyone with its link can read it. Sharing it again
+/// keeps the same link.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the owner of the wishlist.
+/// * id: the id of the wishlist.
+/// * visit_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the wishlist and the path of its link.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+/// * Err with 404 status code if the wishlist doesn't exist or belongs to
+///   another user.
+async fn share_wishlist(
+  authorized: Authorized<CanVisit>,
+  Path(id): Path<i32>,
+  State(visit_controller): State<Arc<dyn VisitController>>,
+) -> Result<Json<WishlistDto>> {
+  println!("->> SHARE WISHLIST\n");
+  visit_controller
+    .share_wishlist(id, authorized.ctx.user_id(), true)
+    .await
+    .map(|a_wishlist| Json(WishlistDto::new(&a_wishlist)))
+    .ok_or(wishlist_not_found(id))
+}
+
+/// Stop sharing a wishlist, the link given stops working.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the owner of the wishlist.
+/// * id: the id of the wishlist.
+/// * visit_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the wishlist without a link.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+/// * Err with 404 status code if the wishlist doesn't exist or belongs to
+///   another user.
+async fn unshare_wishlist(
+  authorized: Authorized<CanVisit>,
+  Path(id): Path<i32>,
+  State(visit_controller): State<Arc<dyn VisitController>>,
+) -> Result<Json<WishlistDto>> {
+  println!("->> UNSHARE WISHLIST\n");
+  visit_controller
+    .share_wishlist(id, authorized.ctx.user_id(), false)
+    .await
+    .map(|a_wishlist| Json(WishlistDto::new(&a_wishlist)))
+    .ok_or(wishlist_not_found(id))
+}
+
+/// Read a shared wishlist, it doesn't need to log in.
+///
+/// # Arguments:
+/// * token: the token of the link of the wishlist.
+/// * visit_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the wishlist and its attractions, in order.
+/// * Err with 404 status code if no wishlist is shared with the token.
+async fn shared_wishlist(
+  Path(token): Path<String>,
+  State(visit_controller): State<Arc<dyn VisitController>>,
+) -> Result<Json<WishlistDetailDto>> {
+  println!("->> SHARED WISHLIST\n");
+  visit_controller
+    .shared_wishlist(token.clone())
+    .await
+    .map(|a_detail| Json(WishlistDetailDto::new(&a_detail)))
+    .ok_or(Error::VisitNotFound {
+      kind: String::from("shared wishlist"),
+      key: token,
+    })
+}
+
+/// List the check-ins of the user, the latest first.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the user that visited.
+/// * check_in_filter_param: the optional attraction visited.
+/// * visit_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with a vector of check-ins, with the rates given in them.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+async fn check_ins(
+  authorized: Authorized<CanVisit>,
+  Query(check_in_filter_param): Query<CheckInFilterParam>,
+  State(visit_controller): State<Arc<dyn VisitController>>,
+) -> Result<Json<Vec<CheckInDto>>> {
+  println!("->> CHECK-INS\n");
+  let check_ins = visit_controller
+    .check_ins(
+      authorized.ctx.user_id(),
+      check_in_filter_param.attraction_id,
+    )
+    .await
+    .unwrap_or_default();
+  Ok(Json(check_ins.iter().map(CheckInDto::new).collect()))
+}
+
+/// Register a visit of the user to an attraction. The optional rate is
+/// registered as a rating of the user, reviewed by the fraud rules like any
+/// other. The visits without a rate count as an interest of the user in the
+/// recommendations and in the popularity of the attraction.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the user that visited.
+/// * visit_controller: the controller responsible of the actions.
+/// * check_in_param: the id of the attraction, the moment of the visit (now
+///   by default) and the optional rate between 0 and 1. The rate is always
+///   dated now, whatever the moment of the visit.
+///
+/// # Return:
+/// * Ok with 201 status code and the check-in.
+/// * Err with 400 status code if the rate is out of range or the visit is
+///   in the future.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+/// * Err with 404 status code if the attraction doesn't exist.
+/// * Err with 409 status code if the visit has a rate and the user already
+///   rated the attraction that day.
+/// * Err with 500 status code if the check-in can't be saved.
+async fn check_in(
+  authorized: Authorized<CanVisit>,
+  State(visit_controller): State<Arc<dyn VisitController>>,
+  Json(check_in_param): Json<CheckInParam>,
+) -> Result<(StatusCode, Json<CheckInDto>)> {
+  println!("->> CHECK-IN\n");
+  if let Some(rate) = &check_in_param.rate {
+    valid_rate(rate)?;
+  }
+  let now = Utc::now().naive_utc();
+  let at = check_in_param.at.unwrap_or(now);
+  if at > now {
+    return Err(Error::InvalidVisit {
+      reason: format!("The visit at {at} is in the future"),
+    });
+  }
+  let attraction_id = check_in_param.attraction_id;
+  let new_check_in = NewCheckIn {
+    attraction_id,
+    at,
+    rate: check_in_param.rate.map(|rate| rate.normalized()),
+  };
+  match visit_controller
+    .create_check_in(
+      authorized.ctx.user_id(),
+      authorized.ctx.username(),
+      new_check_in,
+    )
+    .await
+  {
+    Ok(Some(a_check_in)) => {
+      Ok((StatusCode::CREATED, Json(CheckInDto::new(&a_check_in))))
+    },
+    Ok(None) => Err(Error::AttractionNotFound {
+      id: attraction_id,
+    }),
+    Err(RatingFail::Duplicate(reason)) => {
+      println!("xx->> {}", reason);
+      Err(Error::DuplicateRating {
+        reason,
+      })
+    },
+    Err(RatingFail::Service(e)) => {
+      println!("xx->> {}", e);
+      Err(Error::RateFail)
+    },
+  }
+}
+
+/// Get a check-in of the user.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the user that visited.
+/// * id: the id of the check-in.
+/// * visit_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with the check-in.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+/// * Err with 404 status code if the check-in doesn't exist or belongs to
+///   another user.
+async fn check_in_by_id(
+  authorized: Authorized<CanVisit>,
+  Path(id): Path<i32>,
+  State(visit_controller): State<Arc<dyn VisitController>>,
+) -> Result<Json<CheckInDto>> {
+  println!("->> CHECK-IN BY ID\n");
+  visit_controller
+    .check_in(id, authorized.ctx.user_id())
+    .await
+    .map(|a_check_in| Json(CheckInDto::new(&a_check_in)))
+    .ok_or(Error::VisitNotFound {
+      kind: String::from("check-in"),
+      key: id.to_string(),
+    })
+}
+
+/// Delete a check-in of the user and the rating given in it.
+///
+/// # Arguments:
+/// * authorized: the context of the request, the user that visited.
+/// * id: the id of the check-in.
+/// * visit_controller: the controller responsible of the actions.
+///
+/// # Return:
+/// * Ok with 204 status code.
+/// * Err with 403 status code if the request isn't authenticated by a user.
+/// * Err with 404 status code if the check-in doesn't exist or belongs to
+///   another user.
+async fn delete_check_in(
+  authorized: Authorized<CanVisit>,
+  Path(id): Path<i32>,
+  State(visit_controller): State<Arc<dyn VisitController>>,
+) -> Result<StatusCode> {
+  println!("->> DELETE CHECK-IN\n");
+  match visit_controller
+    .delete_check_in(id, authorized.ctx.user_id())
+    .await
+  {
+    Some(true) => Ok(StatusCode::NO_CONTENT),
+    _ => Err(Error::VisitNotFound {
+      kind: String::from("check-in"),
+      key: id.to_string(),
+    }),
+  }
+}
@@ -0,0 +1,126 @@
+use crate::model::{api_key::ApiScope, audit::Actor, role::Role};
+
+/// The context of a request, the authenticated user that sends it. When the
+/// request is authenticated with an api key, the user is the owner of the
+/// key and the request is also limited by the scopes of the key.
+#[derive(Clone, Debug)]
+pub struct Ctx {
+  user_id: i32,
+  username: String,
+  role: Role,
+  api_key_id: Option<i32>,
+  scopes: Vec<ApiScope>,
+}
+
+// Constructor.
+impl Ctx {
+  pub fn new(user_id: i32, username: String, role: Role) -> Self {
+    Ctx {
+      user_id,
+      username,
+      role,
+      api_key_id: None,
+      scopes: Vec::new(),
+    }
+  }
+
+  pub fn with_api_key(self, api_key_id: i32, scopes: Vec<ApiScope>) -> Self {
+    Ctx {
+      api_key_id: Some(api_key_id),
+      scopes,
+      ..self
+    }
+  }
+}
+
+// Property Accessors.
+impl Ctx {
+  pub fn user_id(&self) -> i32 {
+    self.user_id
+  }
+
+  pub fn username(&self) -> String {
+    self.username.to_string()
+  }
+
+  pub fn role(&self) -> Role {
+    self.role
+  }
+
+  pub fn api_key_id(&self) -> Option<i32> {
+    self.api_key_id
+  }
+
+  /// The user the writes of the catalogue are recorded for in the audit log.
+  pub fn actor(&self) -> Actor {
+    Actor {
+      id: self.user_id,
+      username: self.username(),
+    }
+  }
+
+  /// The user the ratings of the request belong to. The requests of an api
+  /// key act for the partner, not for the owner of the key.
+  pub fn rater_id(&self) -> Option<i32> {
+    self.api_key_id.is_none().then_some(self.user_id)
+  }
+
+  /// Whether the request can rate at a past moment, only the api keys with
+  /// the scope to backfill. The ratings of the users are always now.
+  pub fn can_backfill(&self) -> bool {
+    self.api_key_id.is_some()
+      && self.scopes.contains(&ApiScope::RatingsBackfill)
+  }
+
+  /// Whether the request can do what needs the scope. The requests of a
+  /// user can do anything, the ones of a key only what its scopes allow and
+  /// nothing without a scope.
+  pub fn allows(&self, scope: Option<ApiScope>) -> bool {
+    match (self.api_key_id, scope) {
+      (None, _) => true,
+      (Some(_), Some(scope)) => self.scopes.contains(&scope),
+      (Some(_), None) => false,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn a_user() -> Ctx {
+    Ctx::new(1, String::from("ana"), Role::Editor)
+  }
+
+  fn a_key(scopes: Vec<ApiScope>) -> Ctx {
+    a_user().with_api_key(7, scopes)
+  }
+
+  #[test]
+  fn the_ratings_of_a_key_belong_to_nobody() {
+    assert_eq!(Some(1), a_user().rater_id());
+    assert_eq!(None, a_key(vec![ApiScope::RatingsWrite]).rater_id());
+  }
+
+  #[test]
+  fn only_a_key_with_the_scope_can_backfill() {
+    assert!(!a_user().can_backfill());
+    assert!(!a_key(vec![ApiScope::RatingsWrite]).can_backfill());
+    assert!(
+      a_key(vec![ApiScope::RatingsWrite, ApiScope::RatingsBackfill])
+        .can_backfill()
+    );
+  }
+
+  #[test]
+  fn a_key_only_allows_its_scopes() {
+    let a_user = a_user();
+    assert!(a_user.allows(None));
+    assert!(a_user.allows(Some(ApiScope::AttractionsWrite)));
+
+    let a_key = a_key(vec![ApiScope::AttractionsRead]);
+    assert!(a_key.allows(Some(ApiScope::AttractionsRead)));
+    assert!(!a_key.allows(Some(ApiScope::AttractionsWrite)));
+    assert!(!a_key.allows(None));
+  }
+}
@@ -23,3 +23,18 @@ impl DbConnection {
     &self.pool
   }
 }
+
+/// Whether the error is the violation of a foreign key, a row that references
+/// a missing one or that is still referenced by others.
+pub fn is_foreign_key_violation(error: &sqlx::Error) -> bool {
+  error
+    .as_database_error()
+    .is_some_and(|e| e.is_foreign_key_violation())
+}
+
+/// Whether the error is the violation of a unique constraint.
+pub fn is_unique_violation(error: &sqlx::Error) -> bool {
+  error
+    .as_database_error()
+    .is_some_and(|e| e.is_unique_violation())
+}
@@ -17,10 +17,50 @@ pub enum Error {
   AuthFailNoAuthTokenCookie,
   AuthFailTokenWrongFormat,
   AuthFailCtxNotInRequestExt,
+  AuthFailInvalidToken,
+  AuthFailInvalidApiKey,
+  AccessDenied { required: String },
+  ScopeDenied { required: Option<String> },
+  RateLimitExceeded { api_key_id: i32 },
+  InvalidRegistration { reason: String },
+  LogoutFail,
+  InvalidApiKey { reason: String },
+  ApiKeyNotFound { id: i32 },
   // -- Model errors.
   AttractionNotFound { id: i32 },
+  RatingNotFound { id: i32 },
+  RegionNotFound { region: String },
+  InvalidRating { reason: String },
+  DuplicateRating { reason: String },
+  RateFail,
+  InvalidAttraction { reason: String },
+  DeleteAttractionFail { id: i32 },
+  ReferenceNotFound { kind: String, key: String },
+  InvalidReference { reason: String },
+  ReferenceInUse { kind: String, key: String },
+  UserNotFound { id: i32 },
+  InvalidRoleAssignment { reason: String },
+  OpeningHoursNotFound { id: i32 },
+  AttributesNotFound { id: i32 },
+  InvalidOpeningHours { reason: String },
+  InvalidLocation { reason: String },
+  TranslationNotFound { kind: String, key: String },
+  InvalidTranslation { reason: String },
+  DuplicateNotFound { id: i32, other_id: i32 },
+  InvalidDuplicate { reason: String },
+  DeletedNotFound { entity: String, id: i32 },
+  InvalidAudit { reason: String },
+  RestoreConflict { reason: String },
+  VisitNotFound { kind: String, key: String },
+  InvalidVisit { reason: String },
   // -- Similarity errors.
   GenerateSimilarityFail,
+  TrainRecommendationFail,
+  DataQualityFail,
+  InvalidTimeSeries { reason: String },
+  InvalidForecast { reason: String },
+  InvalidItinerary { reason: String },
+  InvalidSearch { reason: String },
 }
 
 impl core::fmt::Display for Error {
@@ -52,20 +92,127 @@ impl Error {
       // -- Auth.
       Self::AuthFailNoAuthTokenCookie
       | Self::AuthFailTokenWrongFormat
-      | Self::AuthFailCtxNotInRequestExt => {
+      | Self::AuthFailCtxNotInRequestExt
+      | Self::AuthFailInvalidToken
+      | Self::AuthFailInvalidApiKey => {
         (StatusCode::FORBIDDEN, ClientError::NO_AUTH)
       },
+      Self::AccessDenied {
+        ..
+      }
+      | Self::ScopeDenied {
+        ..
+      } => (StatusCode::FORBIDDEN, ClientError::NO_PERMISSION),
+      Self::RateLimitExceeded {
+        ..
+      } => (StatusCode::TOO_MANY_REQUESTS, ClientError::RATE_LIMITED),
+      Self::InvalidRegistration {
+        ..
+      }
+      | Self::InvalidApiKey {
+        ..
+      } => (StatusCode::BAD_REQUEST, ClientError::INVALID_PARAMS),
+      Self::ApiKeyNotFound {
+        ..
+      } => (StatusCode::NOT_FOUND, ClientError::INVALID_PARAMS),
 
       // -- Model.
       Self::AttractionNotFound {
         ..
+      }
+      | Self::RatingNotFound {
+        ..
+      }
+      | Self::RegionNotFound {
+        ..
+      }
+      | Self::ReferenceNotFound {
+        ..
+      }
+      | Self::UserNotFound {
+        ..
+      }
+      | Self::OpeningHoursNotFound {
+        ..
+      }
+      | Self::AttributesNotFound {
+        ..
+      }
+      | Self::TranslationNotFound {
+        ..
+      }
+      | Self::DuplicateNotFound {
+        ..
+      }
+      | Self::DeletedNotFound {
+        ..
+      }
+      | Self::VisitNotFound {
+        ..
       } => (StatusCode::NOT_FOUND, ClientError::INVALID_PARAMS),
+      Self::InvalidRating {
+        ..
+      }
+      | Self::InvalidAttraction {
+        ..
+      }
+      | Self::InvalidReference {
+        ..
+      }
+      | Self::InvalidOpeningHours {
+        ..
+      }
+      | Self::InvalidLocation {
+        ..
+      }
+      | Self::InvalidTranslation {
+        ..
+      }
+      | Self::InvalidDuplicate {
+        ..
+      }
+      | Self::InvalidAudit {
+        ..
+      }
+      | Self::InvalidVisit {
+        ..
+      } => (StatusCode::BAD_REQUEST, ClientError::INVALID_PARAMS),
+      Self::DuplicateRating {
+        ..
+      }
+      | Self::ReferenceInUse {
+        ..
+      }
+      | Self::InvalidRoleAssignment {
+        ..
+      }
+      | Self::RestoreConflict {
+        ..
+      } => (StatusCode::CONFLICT, ClientError::INVALID_PARAMS),
 
       // -- Similarity errors.
-      Self::GenerateSimilarityFail => (
+      Self::GenerateSimilarityFail
+      | Self::TrainRecommendationFail
+      | Self::DataQualityFail
+      | Self::RateFail
+      | Self::DeleteAttractionFail {
+        ..
+      } => (
         StatusCode::INTERNAL_SERVER_ERROR,
         ClientError::SERVICE_ERROR,
       ),
+      Self::InvalidTimeSeries {
+        ..
+      }
+      | Self::InvalidForecast {
+        ..
+      }
+      | Self::InvalidItinerary {
+        ..
+      }
+      | Self::InvalidSearch {
+        ..
+      } => (StatusCode::BAD_REQUEST, ClientError::INVALID_PARAMS),
       // -- Fallback.
       _ => (
         StatusCode::INTERNAL_SERVER_ERROR,
@@ -80,6 +227,8 @@ impl Error {
 pub enum ClientError {
   LOGIN_FAIL,
   NO_AUTH,
+  NO_PERMISSION,
+  RATE_LIMITED,
   INVALID_PARAMS,
   SERVICE_ERROR,
 }
@@ -3,27 +3,113 @@ use std::net::SocketAddr;
 pub use self::errors::{Error, Result};
 
 mod application;
+mod ctx;
 mod db;
 mod errors;
 mod model;
 
-use crate::application::app::start_application;
-use application::{attraction_api, similarity_api};
-use axum::{routing::get, Router};
+use crate::application::{
+  app::start_application,
+  mw_auth::{mw_api_key, mw_ctx_resolver},
+};
+use application::{
+  admin_api, api_key_api, attraction_api, audit_api, auth_api,
+  data_quality_api, duplicate_api, itinerary_api, ranking_api,
+  recommendation_api, reference_api, region_api, search_api, similarity_api,
+  translation_api, visit_api,
+};
+use axum::{
+  http::header::CONTENT_LENGTH,
+  middleware,
+  response::{IntoResponse, Response},
+  routing::get,
+  Json, Router,
+};
+use serde_json::json;
+use tower_cookies::CookieManagerLayer;
+use uuid::Uuid;
 
 #[tokio::main]
 async fn main() {
   let application = start_application().await;
 
+  // ---- Data quality checks from the command line ---- //
+  if std::env::args().nth(1).as_deref() == Some("data-quality") {
+    let exit_code =
+      data_quality_api::run_cli(application.data_quality.clone()).await;
+    std::process::exit(exit_code);
+  }
+
+  // ---- Admins from the command line ---- //
+  if std::env::args().nth(1).as_deref() == Some("create-admin") {
+    let exit_code =
+      admin_api::run_cli(application.auth.clone(), std::env::args().nth(2))
+        .await;
+    std::process::exit(exit_code);
+  }
+
   // ---- Routes initialization ---- //
   let attractions_api = attraction_api::routes(application.attraction.clone());
 
   let similarity_api = similarity_api::routes(application.similarity.clone());
 
+  let ranking_api = ranking_api::routes(application.ranking.clone());
+
+  let region_api = region_api::routes(application.region.clone());
+
+  let auth_api = auth_api::routes(application.auth.clone());
+
+  let admin_api = admin_api::routes(application.auth.clone());
+
+  let reference_api = reference_api::routes(application.reference.clone());
+
+  let api_key_api = api_key_api::routes(application.api_key.clone());
+
+  let recommendation_api =
+    recommendation_api::routes(application.recommendation.clone());
+
+  let itinerary_api = itinerary_api::routes(application.itinerary.clone());
+
+  let search_api = search_api::routes(application.search.clone());
+
+  let duplicate_api = duplicate_api::routes(application.duplicate.clone());
+
+  let translation_api =
+    translation_api::routes(application.translation.clone());
+
+  let data_quality_api =
+    data_quality_api::routes(application.data_quality.clone());
+  let audit_api = audit_api::routes(application.audit.clone());
+  let visit_api = visit_api::routes(application.visit.clone());
+
   let router = Router::new()
     .route("/hello", get(hello))
     .merge(attractions_api)
-    .merge(similarity_api);
+    .merge(similarity_api)
+    .merge(ranking_api)
+    .merge(region_api)
+    .merge(auth_api)
+    .merge(admin_api)
+    .merge(reference_api)
+    .merge(api_key_api)
+    .merge(recommendation_api)
+    .merge(itinerary_api)
+    .merge(search_api)
+    .merge(translation_api)
+    .merge(duplicate_api)
+    .merge(data_quality_api)
+    .merge(audit_api)
+    .merge(visit_api)
+    .layer(middleware::from_fn_with_state(
+      application.api_key.clone(),
+      mw_api_key,
+    ))
+    .layer(middleware::map_response(main_response_mapper))
+    .layer(middleware::from_fn_with_state(
+      application.auth.clone(),
+      mw_ctx_resolver,
+    ))
+    .layer(CookieManagerLayer::new());
 
   // ---- run it with hyper on localhost:8080 ---- //
   let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
@@ -34,6 +120,42 @@ async fn main() {
     .unwrap();
 }
 
+/// Turn the errors of the handlers into the response sent to the client,
+/// with the status and the type of the error and an id of the request.
+async fn main_response_mapper(res: Response) -> Response {
+  println!("->> {:<12} - main_response_mapper", "RES_MAPPER");
+  let uuid = Uuid::new_v4();
+
+  let service_error = res.extensions().get::<Error>();
+  let client_status_error =
+    service_error.map(|se| se.client_status_and_error());
+  let error_response =
+    client_status_error
+      .as_ref()
+      .map(|(status_code, client_error)| {
+        let client_error_body = json!({
+          "error": {
+            "type": client_error.as_ref(),
+            "req_uuid": uuid.to_string(),
+          }
+        });
+        println!("    ->> client_error_body: {client_error_body}");
+        let mut response =
+          (*status_code, Json(client_error_body)).into_response();
+        // Keep the headers of the error, like the rate limit ones, but not
+        // the ones that describe the replaced body.
+        for (name, value) in res.headers() {
+          if name != CONTENT_LENGTH && !response.headers().contains_key(name) {
+            response.headers_mut().insert(name, value.clone());
+          }
+        }
+        response
+      });
+
+  println!();
+  error_response.unwrap_or(res)
+}
+
 // basic handler that responds with a static string
 async fn hello() -> &'static str {
   "Hello, World!"
@@ -1,7 +1,55 @@
+pub mod aggregation_settings;
+pub mod anomaly;
+pub mod api_key;
+pub mod api_key_controller;
+pub mod api_key_repository;
 pub mod attraction;
 pub mod attraction_controller;
 pub mod attraction_repository;
 pub mod attraction_similarity;
+pub mod audit;
+pub mod audit_controller;
+pub mod audit_repository;
+pub mod auth;
+pub mod auth_controller;
+pub mod data_quality;
+pub mod data_quality_controller;
+pub mod data_quality_repository;
+pub mod duplicate;
+pub mod duplicate_controller;
+pub mod forecast;
+pub mod granularity;
+pub mod itinerary;
+pub mod itinerary_controller;
+pub mod opening_hours;
+pub mod percentile;
+pub mod ranking;
+pub mod ranking_controller;
+pub mod rate_limit;
+pub mod rating_rules;
+pub mod rating_sketch;
+pub mod rating_statistics;
+pub mod recommendation;
+pub mod recommendation_controller;
+pub mod recommendation_repository;
+pub mod reference_controller;
+pub mod reference_repository;
+pub mod region_controller;
+pub mod region_repository;
+pub mod region_stats;
+pub mod role;
+pub mod search;
+pub mod search_controller;
 pub mod similarity_controller;
 pub mod similarity_generator;
 pub mod similarity_repository;
+pub mod tdigest;
+pub mod time_series;
+pub mod translation;
+pub mod translation_controller;
+pub mod translation_repository;
+pub mod user;
+pub mod user_repository;
+pub mod visit;
+pub mod visit_controller;
+pub mod visit_repository;
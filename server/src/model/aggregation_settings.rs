@@ -0,0 +1,161 @@
+use crate::model::{
+  anomaly::AnomalyMethod, granularity::Granularity,
+  percentile::PercentileMethod,
+};
+use std::{fmt::Display, str::FromStr};
+
+/// How the ratings of the attractions are aggregated.
+#[derive(Debug, Clone)]
+pub struct AggregationSettings {
+  /// The method used to estimate the median and the percentiles.
+  pub percentile_method: PercentileMethod,
+  /// The periods for which the aggregates are generated.
+  pub granularities: Vec<Granularity>,
+  /// Derive the day based aggregates from the daily t-digest sketches instead
+  /// of sorting every rating of the period. The hourly aggregates are always
+  /// exact.
+  pub use_sketches: bool,
+  /// The periods with up to this number of ratings are aggregated exactly,
+  /// even when the sketches are enabled. The t-digest estimates the median
+  /// and the percentiles on its own, ignoring the percentile method, so a
+  /// larger threshold keeps more aggregates faithful to the method at the
+  /// cost of sorting more ratings.
+  pub exact_up_to: u64,
+  /// How the anomalies are detected after the aggregation.
+  pub anomaly: AnomalySettings,
+}
+
+/// How the anomalies of the daily aggregates are detected.
+#[derive(Debug, Clone)]
+pub struct AnomalySettings {
+  pub enabled: bool,
+  pub method: AnomalyMethod,
+  /// The days before a day used as its baseline.
+  pub baseline_days: i64,
+  /// The minimum number of days with ratings in the baseline to evaluate a
+  /// day.
+  pub min_baseline_days: usize,
+  /// The score, in standard deviations, from which a day is an anomaly.
+  pub threshold: f64,
+  /// The weight of the last day in the EWMA, between 0 and 1.
+  pub ewma_lambda: f64,
+  /// Skip the anomalous days when the average rating of an attraction is
+  /// taken for the similarity.
+  pub exclude_from_similarity: bool,
+}
+
+impl Default for AnomalySettings {
+  fn default() -> Self {
+    AnomalySettings {
+      enabled: true,
+      method: AnomalyMethod::default(),
+      baseline_days: 28,
+      min_baseline_days: 7,
+      threshold: 3.0,
+      ewma_lambda: 0.3,
+      exclude_from_similarity: false,
+    }
+  }
+}
+
+impl AnomalySettings {
+  /// Read the settings from the environment, falling back into the defaults
+  /// for the missing or invalid values.
+  /// * ANOMALY_DETECTION: true or false.
+  /// * ANOMALY_METHOD: Z_SCORE or EWMA.
+  /// * ANOMALY_BASELINE_DAYS, ANOMALY_MIN_BASELINE_DAYS, ANOMALY_THRESHOLD and
+  ///   ANOMALY_EWMA_LAMBDA: numbers.
+  /// * ANOMALY_EXCLUDE_FROM_SIMILARITY: true or false.
+  pub fn from_env() -> Self {
+    let defaults = AnomalySettings::default();
+    AnomalySettings {
+      enabled: from_env_var("ANOMALY_DETECTION").unwrap_or(defaults.enabled),
+      method: from_env_var("ANOMALY_METHOD").unwrap_or(defaults.method),
+      baseline_days: from_env_var("ANOMALY_BASELINE_DAYS")
+        .unwrap_or(defaults.baseline_days),
+      min_baseline_days: from_env_var("ANOMALY_MIN_BASELINE_DAYS")
+        .unwrap_or(defaults.min_baseline_days),
+      threshold: from_env_var("ANOMALY_THRESHOLD")
+        .unwrap_or(defaults.threshold),
+      ewma_lambda: from_env_var("ANOMALY_EWMA_LAMBDA")
+        .unwrap_or(defaults.ewma_lambda),
+      exclude_from_similarity: from_env_var("ANOMALY_EXCLUDE_FROM_SIMILARITY")
+        .unwrap_or(defaults.exclude_from_similarity),
+    }
+  }
+}
+
+impl Default for AggregationSettings {
+  fn default() -> Self {
+    AggregationSettings {
+      percentile_method: PercentileMethod::default(),
+      granularities: vec![Granularity::Day],
+      use_sketches: true,
+      exact_up_to: 1000,
+      anomaly: AnomalySettings::default(),
+    }
+  }
+}
+
+impl AggregationSettings {
+  /// Read the settings from the environment, falling back into the defaults
+  /// for the missing or invalid values.
+  /// * PERCENTILE_METHOD: one of the PercentileMethod, e.g. HYNDMAN_FAN_7.
+  /// * AGGREGATE_GRANULARITIES: a comma separated list of granularities, e.g.
+  ///   DAY,WEEK,MONTH,ROLLING_7_DAYS.
+  /// * AGGREGATE_WITH_SKETCHES: true or false.
+  /// * AGGREGATE_EXACT_UP_TO: the number of ratings of a period up to which it
+  ///   is aggregated exactly.
+  /// * The anomaly detection settings, see AnomalySettings.
+  pub fn from_env() -> Self {
+    let defaults = AggregationSettings::default();
+    let percentile_method =
+      from_env_var("PERCENTILE_METHOD").unwrap_or(defaults.percentile_method);
+    let granularities = match std::env::var("AGGREGATE_GRANULARITIES") {
+      Ok(values) => values
+        .split(',')
+        .filter(|value| !value.trim().is_empty())
+        .filter_map(parse_or_warn)
+        .collect::<Vec<Granularity>>(),
+      Err(_) => defaults.granularities.clone(),
+    };
+    let use_sketches =
+      from_env_var("AGGREGATE_WITH_SKETCHES").unwrap_or(defaults.use_sketches);
+    let exact_up_to =
+      from_env_var("AGGREGATE_EXACT_UP_TO").unwrap_or(defaults.exact_up_to);
+
+    AggregationSettings {
+      percentile_method,
+      granularities: if granularities.is_empty() {
+        defaults.granularities
+      } else {
+        granularities
+      },
+      use_sketches,
+      exact_up_to,
+      anomaly: AnomalySettings::from_env(),
+    }
+  }
+}
+
+pub(crate) fn from_env_var<T>(name: &str) -> Option<T>
+where
+  T: FromStr,
+  T::Err: Display,
+{
+  parse_or_warn(&std::env::var(name).ok()?)
+}
+
+fn parse_or_warn<T>(value: &str) -> Option<T>
+where
+  T: FromStr,
+  T::Err: Display,
+{
+  match value.trim().parse() {
+    Ok(parsed) => Some(parsed),
+    Err(e) => {
+      println!("xx->> {e}, IGNORING THE VALUE\n");
+      None
+    },
+  }
+}
@@ -0,0 +1,355 @@
+use crate::model::aggregation_settings::AnomalySettings;
+use chrono::{Duration, NaiveDateTime};
+use std::{fmt, str::FromStr};
+
+/// How a day is compared with the baseline of its history.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnomalyMethod {
+  /// The number of standard deviations between the value of the day and the
+  /// mean of the baseline.
+  #[default]
+  ZScore,
+  /// An exponentially weighted moving average control chart, more sensitive
+  /// to small shifts sustained over several days.
+  Ewma,
+}
+
+impl AnomalyMethod {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      AnomalyMethod::ZScore => "Z_SCORE",
+      AnomalyMethod::Ewma => "EWMA",
+    }
+  }
+}
+
+impl fmt::Display for AnomalyMethod {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+impl FromStr for AnomalyMethod {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_uppercase().as_str() {
+      "Z_SCORE" => Ok(AnomalyMethod::ZScore),
+      "EWMA" => Ok(AnomalyMethod::Ewma),
+      _ => Err(format!("Unknown anomaly method: {}", s)),
+    }
+  }
+}
+
+/// What is observed of the ratings of a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnomalyMetric {
+  /// The average rate.
+  Average,
+  /// The number of ratings.
+  Volume,
+}
+
+impl AnomalyMetric {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      AnomalyMetric::Average => "AVERAGE",
+      AnomalyMetric::Volume => "VOLUME",
+    }
+  }
+
+  fn value_of(&self, a_day: &DailyRating) -> f64 {
+    match self {
+      AnomalyMetric::Average => a_day.average,
+      AnomalyMetric::Volume => a_day.count,
+    }
+  }
+
+  /// The lowest standard deviation considered for the baseline, so a
+  /// perfectly stable history doesn't flag every tiny change.
+  fn min_deviation(&self) -> f64 {
+    match self {
+      AnomalyMetric::Average => 0.01,
+      AnomalyMetric::Volume => 1.0,
+    }
+  }
+}
+
+impl fmt::Display for AnomalyMetric {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+/// The ratings of an attraction in a day.
+#[derive(Debug, Clone)]
+pub struct DailyRating {
+  pub at: NaiveDateTime,
+  pub average: f64,
+  pub count: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anomaly {
+  pub at: NaiveDateTime,
+  pub metric: AnomalyMetric,
+  pub method: AnomalyMethod,
+  pub value: f64,
+  /// The mean of the baseline.
+  pub baseline: f64,
+  /// The deviation from the baseline, in standard deviations.
+  pub score: f64,
+}
+
+/// Find the days whose average or volume deviates from their history.
+///
+/// The baseline of a day are the previous days of the baseline window. The
+/// flagged days are kept capped at the threshold, so a review bombing
+/// campaign of several days doesn't become its own baseline at once, but
+/// neither narrows it, that would flag more days after every flag. That way
+/// a sustained change slowly ends up being the new normal. When there
+/// aren't enough days, the day is not evaluated.
+///
+/// # Arguments:
+/// * days: the ratings per day, sorted by date.
+/// * settings: the method and the thresholds of the detection.
+///
+/// # Return:
+/// * The anomalies, sorted by metric and date.
+pub fn detect(
+  days: &[DailyRating],
+  settings: &AnomalySettings,
+) -> Vec<Anomaly> {
+  [AnomalyMetric::Average, AnomalyMetric::Volume]
+    .iter()
+    .flat_map(|metric| detect_metric(days, *metric, settings))
+    .collect()
+}
+
+fn detect_metric(
+  days: &[DailyRating],
+  metric: AnomalyMetric,
+  settings: &AnomalySettings,
+) -> Vec<Anomaly> {
+  let lambda = settings.ewma_lambda.clamp(0.01, 1.0);
+  let mut anomalies = Vec::new();
+  let mut previous_days: Vec<(NaiveDateTime, f64)> = Vec::new();
+  let mut ewma: Option<f64> = None;
+
+  for a_day in days {
+    let value = metric.value_of(a_day);
+    let since = a_day.at - Duration::days(settings.baseline_days);
+    let baseline = previous_days
+      .iter()
+      .filter(|(at, _)| *at >= since)
+      .map(|(_, value)| *value)
+      .collect::<Vec<f64>>();
+    let statistic = lambda * value + (1.0 - lambda) * ewma.unwrap_or(value);
+    ewma = Some(statistic);
+
+    let deviation = (baseline.len() >= settings.min_baseline_days.max(2))
+      .then(|| mean_and_deviation(&baseline))
+      .map(|(mean, deviation)| (mean, deviation.max(metric.min_deviation())));
+    let Some((mean, deviation)) = deviation else {
+      previous_days.push((a_day.at, value));
+      continue;
+    };
+    let score = match settings.method {
+      AnomalyMethod::ZScore => (value - mean) / deviation,
+      AnomalyMethod::Ewma => {
+        (statistic - mean) / (deviation * (lambda / (2.0 - lambda)).sqrt())
+      },
+    };
+    let limit = settings.threshold * deviation;
+    previous_days.push((a_day.at, value.clamp(mean - limit, mean + limit)));
+    if score.abs() > settings.threshold {
+      anomalies.push(Anomaly {
+        at: a_day.at,
+        metric,
+        method: settings.method,
+        value,
+        baseline: mean,
+        score,
+      });
+    }
+  }
+  anomalies
+}
+
+/// The mean and the sample standard deviation.
+fn mean_and_deviation(values: &[f64]) -> (f64, f64) {
+  let n = values.len() as f64;
+  let mean = values.iter().sum::<f64>() / n;
+  let variance = values
+    .iter()
+    .map(|value| (value - mean).powi(2))
+    .sum::<f64>()
+    / (n - 1.0);
+  (mean, variance.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::NaiveDate;
+
+  fn history(averages: &[f64], counts: &[f64]) -> Vec<DailyRating> {
+    let start = NaiveDate::from_ymd_opt(2023, 10, 1)
+      .unwrap()
+      .and_hms_opt(0, 0, 0)
+      .unwrap();
+    averages
+      .iter()
+      .zip(counts.iter())
+      .enumerate()
+      .map(|(day, (average, count))| DailyRating {
+        at: start + Duration::days(day as i64),
+        average: *average,
+        count: *count,
+      })
+      .collect()
+  }
+
+  fn stable(days: usize) -> (Vec<f64>, Vec<f64>) {
+    let averages = (0..days)
+      .map(|day| 0.8 + if day % 2 == 0 { 0.02 } else { -0.02 })
+      .collect();
+    let counts = (0..days)
+      .map(|day| 10.0 + if day % 2 == 0 { 1.0 } else { -1.0 })
+      .collect();
+    (averages, counts)
+  }
+
+  #[test]
+  fn flags_a_review_bombing_day() {
+    let (mut averages, mut counts) = stable(20);
+    averages.push(0.1);
+    counts.push(60.0);
+    let days = history(&averages, &counts);
+    let anomalies = detect(&days, &AnomalySettings::default());
+
+    assert_eq!(anomalies.len(), 2);
+    assert_eq!(anomalies[0].metric, AnomalyMetric::Average);
+    assert_eq!(anomalies[0].at, days[20].at);
+    assert!(anomalies[0].score < -3.0);
+    assert_eq!(anomalies[1].metric, AnomalyMetric::Volume);
+    assert!(anomalies[1].score > 3.0);
+  }
+
+  #[test]
+  fn stable_history_has_no_anomalies() {
+    let (averages, counts) = stable(60);
+    for method in [AnomalyMethod::ZScore, AnomalyMethod::Ewma] {
+      let settings = AnomalySettings {
+        method,
+        ..Default::default()
+      };
+      assert!(detect(&history(&averages, &counts), &settings).is_empty());
+    }
+  }
+
+  /// A generator of the same pseudo random values in every run, a xorshift.
+  struct SeededRandom(u64);
+
+  impl SeededRandom {
+    fn next(&mut self) -> f64 {
+      self.0 ^= self.0 << 13;
+      self.0 ^= self.0 >> 7;
+      self.0 ^= self.0 << 17;
+      (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+  }
+
+  /// The averages of a few uniformly random rates, without anything to
+  /// flag. The small baselines have heavier tails than the normal, so more
+  /// than the 0.27% beyond 3 deviations are flagged.
+  #[test]
+  fn random_ratings_are_rarely_flagged() {
+    let mut random = SeededRandom(0x5eed);
+    for method in [AnomalyMethod::ZScore, AnomalyMethod::Ewma] {
+      let settings = AnomalySettings {
+        method,
+        ..Default::default()
+      };
+      let (mut flagged, mut evaluated) = (0, 0);
+      for _attraction in 0..100 {
+        let (averages, counts): (Vec<f64>, Vec<f64>) = (0..40)
+          .map(|_day| {
+            let count = 5 + (random.next() * 11.0) as usize;
+            let sum = (0..count).map(|_rating| random.next()).sum::<f64>();
+            (sum / count as f64, count as f64)
+          })
+          .unzip();
+        flagged += detect(&history(&averages, &counts), &settings).len();
+        evaluated += 2 * (40 - settings.min_baseline_days);
+      }
+      let rate = flagged as f64 / evaluated as f64;
+      assert!(
+        rate < 0.01,
+        "{method} flagged {flagged} of {evaluated} days"
+      );
+    }
+  }
+
+  #[test]
+  fn a_campaign_doesnt_become_its_baseline_at_once() {
+    let (mut averages, mut counts) = stable(20);
+    averages.extend([0.1; 5]);
+    counts.extend([10.0; 5]);
+    let anomalies =
+      detect(&history(&averages, &counts), &AnomalySettings::default());
+    assert_eq!(anomalies.len(), 5);
+    assert!(anomalies
+      .iter()
+      .all(|an_anomaly| an_anomaly.metric == AnomalyMetric::Average));
+  }
+
+  #[test]
+  fn needs_a_minimum_baseline() {
+    let (mut averages, mut counts) = stable(3);
+    averages.push(0.1);
+    counts.push(60.0);
+    let days = history(&averages, &counts);
+    assert!(detect(&days, &AnomalySettings::default()).is_empty());
+  }
+
+  #[test]
+  fn ewma_detects_a_small_sustained_shift() {
+    let (mut averages, counts) = stable(40);
+    for average in averages.iter_mut().skip(20) {
+      *average -= 0.04;
+    }
+    let days = history(&averages, &counts);
+    let z_score = detect(&days, &AnomalySettings::default());
+    let ewma = detect(
+      &days,
+      &AnomalySettings {
+        method: AnomalyMethod::Ewma,
+        ..Default::default()
+      },
+    );
+    assert!(z_score.is_empty());
+    assert!(ewma
+      .iter()
+      .any(|an_anomaly| an_anomaly.metric == AnomalyMetric::Average));
+  }
+
+  /// The flagged days enter the baseline capped at the threshold, so after a
+  /// sustained level change the baseline moves a bit every day until the new
+  /// level is no longer anomalous. The larger the change, the longer it
+  /// takes.
+  #[test]
+  fn a_sustained_change_becomes_the_new_normal() {
+    for (level, flagged_days) in [(0.6, 8), (0.4, 13), (0.1, 17)] {
+      let (mut averages, mut counts) = stable(28);
+      averages.extend([level; 90]);
+      counts.extend([10.0; 90]);
+      let days = history(&averages, &counts);
+      let flagged = detect(&days, &AnomalySettings::default())
+        .iter()
+        .map(|an_anomaly| (an_anomaly.at - days[28].at).num_days())
+        .collect::<Vec<i64>>();
+      assert_eq!((0..flagged_days).collect::<Vec<i64>>(), flagged);
+    }
+  }
+}
@@ -0,0 +1,272 @@
+use crate::model::{
+  aggregation_settings::from_env_var, auth::random_token, role::Role,
+};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use std::{fmt, str::FromStr};
+
+/// The start of every key, tells them apart from the session tokens.
+pub const KEY_PREFIX: &str = "ct_";
+/// The characters of the key shown to identify it after the issuance.
+const VISIBLE_LENGTH: usize = 11;
+
+/// The limits given to the keys.
+#[derive(Debug, Clone, Copy)]
+pub struct ApiKeySettings {
+  pub default_rate_per_minute: i32,
+  pub default_burst: i32,
+  /// The highest rate or burst that a key can have.
+  pub max_rate_per_minute: i32,
+}
+
+impl ApiKeySettings {
+  /// Read the settings from the environment, falling back into the defaults
+  /// for the missing or invalid values.
+  /// * API_KEY_RATE_PER_MINUTE: 60 by default.
+  /// * API_KEY_BURST: 60 by default.
+  /// * API_KEY_MAX_RATE_PER_MINUTE: 600 by default.
+  pub fn from_env() -> Self {
+    ApiKeySettings {
+      default_rate_per_minute: from_env_var("API_KEY_RATE_PER_MINUTE")
+        .unwrap_or(60),
+      default_burst: from_env_var("API_KEY_BURST").unwrap_or(60),
+      max_rate_per_minute: from_env_var("API_KEY_MAX_RATE_PER_MINUTE")
+        .unwrap_or(600),
+    }
+  }
+}
+
+/// What a request authenticated with an api key is allowed to do, besides
+/// the role of the owner of the key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub enum ApiScope {
+  /// Read the attractions, the ratings and their statistics.
+  AttractionsRead,
+  /// Edit the attractions and the reference data.
+  AttractionsWrite,
+  /// Rate the attractions.
+  RatingsWrite,
+  /// Rate the attractions at past moments, importing the history of a
+  /// partner into periods already aggregated.
+  RatingsBackfill,
+}
+
+impl ApiScope {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ApiScope::AttractionsRead => "attractions:read",
+      ApiScope::AttractionsWrite => "attractions:write",
+      ApiScope::RatingsWrite => "ratings:write",
+      ApiScope::RatingsBackfill => "ratings:backfill",
+    }
+  }
+
+  /// The role the owner of a key needs to give it the scope.
+  pub fn required_role(&self) -> Role {
+    match self {
+      ApiScope::AttractionsRead | ApiScope::RatingsWrite => Role::Viewer,
+      ApiScope::AttractionsWrite | ApiScope::RatingsBackfill => Role::Editor,
+    }
+  }
+}
+
+impl fmt::Display for ApiScope {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+impl FromStr for ApiScope {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_lowercase().as_str() {
+      "attractions:read" => Ok(ApiScope::AttractionsRead),
+      "attractions:write" => Ok(ApiScope::AttractionsWrite),
+      "ratings:write" => Ok(ApiScope::RatingsWrite),
+      "ratings:backfill" => Ok(ApiScope::RatingsBackfill),
+      _ => Err(format!("Unknown scope: {}", s)),
+    }
+  }
+}
+
+impl TryFrom<String> for ApiScope {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+#[allow(dead_code)]
+#[derive(FromRow, Debug, Clone)]
+pub struct ApiKey {
+  pub id: i32,
+  pub owner_id: i32,
+  pub name: String,
+  pub prefix: String,
+  pub key_hash: String,
+  pub scopes: Vec<String>,
+  pub rate_per_minute: i32,
+  pub burst: i32,
+  pub created_at: NaiveDateTime,
+  pub rotated_at: Option<NaiveDateTime>,
+  pub revoked_at: Option<NaiveDateTime>,
+  pub last_used_at: Option<NaiveDateTime>,
+}
+
+impl ApiKey {
+  pub fn get_id(&self) -> i32 {
+    self.id
+  }
+
+  pub fn get_owner_id(&self) -> i32 {
+    self.owner_id
+  }
+
+  pub fn get_name(&self) -> String {
+    self.name.to_string()
+  }
+
+  pub fn get_prefix(&self) -> String {
+    self.prefix.to_string()
+  }
+
+  /// The known scopes of the key, the unknown ones are ignored.
+  pub fn get_scopes(&self) -> Vec<ApiScope> {
+    self
+      .scopes
+      .iter()
+      .filter_map(|scope| scope.parse().ok())
+      .collect()
+  }
+
+  pub fn get_rate_per_minute(&self) -> i32 {
+    self.rate_per_minute
+  }
+
+  pub fn get_burst(&self) -> i32 {
+    self.burst
+  }
+
+  pub fn get_created_at(&self) -> NaiveDateTime {
+    self.created_at
+  }
+
+  pub fn get_rotated_at(&self) -> Option<NaiveDateTime> {
+    self.rotated_at
+  }
+
+  pub fn get_revoked_at(&self) -> Option<NaiveDateTime> {
+    self.revoked_at
+  }
+
+  pub fn get_last_used_at(&self) -> Option<NaiveDateTime> {
+    self.last_used_at
+  }
+}
+
+/// The requests made with a key in a day.
+#[derive(FromRow, Debug, Clone)]
+pub struct ApiKeyUsage {
+  pub day: NaiveDate,
+  pub request_count: i64,
+  pub throttled_count: i64,
+}
+
+/// A new random key, only given once to its owner.
+///
+/// # Return:
+/// * The key, the prefix that identifies it and its hash.
+pub fn generate_key() -> (String, String, String) {
+  let key = format!("{KEY_PREFIX}{}", random_token());
+  let prefix = key[..VISIBLE_LENGTH].to_string();
+  let key_hash = hash_key(&key);
+  (key, prefix, key_hash)
+}
+
+/// The hex sha256 of the key. The keys are random and long, so unlike the
+/// passwords they don't need a slow and salted hash, and they can be found
+/// by it.
+pub fn hash_key(key: &str) -> String {
+  format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn api_key_with_scopes(scopes: &[&str]) -> ApiKey {
+    ApiKey {
+      id: 1,
+      owner_id: 1,
+      name: String::from("partner"),
+      prefix: String::from("cala_ab12"),
+      key_hash: String::new(),
+      scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+      rate_per_minute: 60,
+      burst: 60,
+      created_at: NaiveDateTime::default(),
+      rotated_at: None,
+      revoked_at: None,
+      last_used_at: None,
+    }
+  }
+
+  #[test]
+  fn the_generated_keys_start_with_their_prefix() {
+    let (key, prefix, _) = generate_key();
+    assert!(key.starts_with(KEY_PREFIX));
+    assert!(key.starts_with(&prefix));
+    assert_eq!(prefix.len(), VISIBLE_LENGTH);
+  }
+
+  #[test]
+  fn the_keys_are_found_by_their_sha256() {
+    let (key, _, key_hash) = generate_key();
+    assert_eq!(key_hash, hash_key(&key));
+    assert_eq!(key_hash.len(), 64);
+    assert_eq!(
+      hash_key("abc"),
+      "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+  }
+
+  #[test]
+  fn every_generated_key_is_different() {
+    let (key, _, key_hash) = generate_key();
+    let (other_key, _, other_hash) = generate_key();
+    assert_ne!(key, other_key);
+    assert_ne!(key_hash, other_hash);
+  }
+
+  #[test]
+  fn the_scopes_are_parsed_in_any_case() {
+    assert_eq!(
+      "Ratings:Write".parse::<ApiScope>(),
+      Ok(ApiScope::RatingsWrite)
+    );
+    assert_eq!(
+      " ratings:backfill ".parse::<ApiScope>(),
+      Ok(ApiScope::RatingsBackfill)
+    );
+    assert!("ratings:delete".parse::<ApiScope>().is_err());
+  }
+
+  #[test]
+  fn the_unknown_scopes_of_a_key_are_ignored() {
+    let api_key = api_key_with_scopes(&["attractions:read", "admin:all"]);
+    assert_eq!(api_key.get_scopes(), vec![ApiScope::AttractionsRead]);
+  }
+
+  #[test]
+  fn the_scopes_to_write_the_catalogue_need_an_editor() {
+    assert_eq!(ApiScope::AttractionsRead.required_role(), Role::Viewer);
+    assert_eq!(ApiScope::RatingsWrite.required_role(), Role::Viewer);
+    assert_eq!(ApiScope::AttractionsWrite.required_role(), Role::Editor);
+    assert_eq!(ApiScope::RatingsBackfill.required_role(), Role::Editor);
+  }
+}
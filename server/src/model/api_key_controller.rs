@@ -0,0 +1,505 @@
+use crate::model::{
+  api_key::{
+    generate_key, hash_key, ApiKey, ApiKeySettings, ApiKeyUsage, ApiScope,
+    KEY_PREFIX,
+  },
+  api_key_repository::{ApiKeyRepository, NewApiKey},
+  rate_limit::{RateDecision, RateLimit, RateLimiter},
+  role::Role,
+  user::User,
+  user_repository::UserRepository,
+};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use std::{sync::Arc, time::Instant};
+
+/// A key just issued or rotated, the only moment when the key is known.
+pub struct IssuedApiKey {
+  pub api_key: ApiKey,
+  pub key: String,
+}
+
+#[async_trait]
+pub trait ApiKeyController: Send + Sync + 'static {
+  async fn issue(
+    &self,
+    owner_id: i32,
+    owner_role: Role,
+    name: String,
+    scopes: Vec<ApiScope>,
+    rate_per_minute: Option<i32>,
+    burst: Option<i32>,
+  ) -> Result<IssuedApiKey, String>;
+  async fn api_keys_of(&self, owner_id: i32) -> Option<Vec<ApiKey>>;
+  async fn rotate(
+    &self,
+    id: i32,
+    owner_id: Option<i32>,
+  ) -> Result<Option<IssuedApiKey>, String>;
+  async fn revoke(&self, id: i32, owner_id: Option<i32>) -> Option<ApiKey>;
+  async fn usage(
+    &self,
+    id: i32,
+    owner_id: Option<i32>,
+    days: i64,
+  ) -> Option<Vec<ApiKeyUsage>>;
+  async fn authenticate(&self, key: &str) -> Option<(ApiKey, User)>;
+  fn throttle(&self, api_key: &ApiKey) -> RateDecision;
+  async fn record_usage(&self, id: i32, throttled: bool);
+}
+
+#[derive(Clone)]
+pub struct ApiKeyControllerImpl<ApiKeyRepo, UserRepo> {
+  api_key_repository: ApiKeyRepo,
+  user_repository: UserRepo,
+  settings: ApiKeySettings,
+  rate_limiter: Arc<RateLimiter>,
+}
+
+impl<ApiKeyRepo, UserRepo> ApiKeyControllerImpl<ApiKeyRepo, UserRepo>
+where
+  ApiKeyRepo: ApiKeyRepository,
+  UserRepo: UserRepository,
+{
+  pub fn new(
+    api_key_repository: ApiKeyRepo,
+    user_repository: UserRepo,
+    settings: ApiKeySettings,
+  ) -> Self {
+    ApiKeyControllerImpl {
+      api_key_repository,
+      user_repository,
+      settings,
+      rate_limiter: Arc::new(RateLimiter::new()),
+    }
+  }
+
+  /// The key with the id, if it belongs to the owner. Without an owner any
+  /// key is returned.
+  async fn owned_api_key(
+    &self,
+    id: i32,
+    owner_id: Option<i32>,
+  ) -> Option<ApiKey> {
+    let api_key = self.api_key_repository.api_key_by_id(id).await.ok()??;
+    match owner_id {
+      Some(owner_id) if owner_id != api_key.get_owner_id() => None,
+      _ => Some(api_key),
+    }
+  }
+}
+
+#[async_trait]
+impl<ApiKeyRepo, UserRepo> ApiKeyController
+  for ApiKeyControllerImpl<ApiKeyRepo, UserRepo>
+where
+  ApiKeyRepo: ApiKeyRepository + Send + Sync + 'static,
+  UserRepo: UserRepository + Send + Sync + 'static,
+{
+  /// Issue a new key for the owner. The scopes must be granted by the role
+  /// of the owner, and the limits default to the ones of the settings.
+  async fn issue(
+    &self,
+    owner_id: i32,
+    owner_role: Role,
+    name: String,
+    scopes: Vec<ApiScope>,
+    rate_per_minute: Option<i32>,
+    burst: Option<i32>,
+  ) -> Result<IssuedApiKey, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+      return Err(String::from("The name can't be empty"));
+    }
+    let scopes = scopes.into_iter().fold(Vec::new(), |mut unique, scope| {
+      if !unique.contains(&scope) {
+        unique.push(scope);
+      }
+      unique
+    });
+    if scopes.is_empty() {
+      return Err(String::from("The key needs at least one scope"));
+    }
+    if let Some(scope) = scopes
+      .iter()
+      .find(|scope| !owner_role.grants(scope.required_role()))
+    {
+      return Err(format!(
+        "The scope {} needs the role {}",
+        scope,
+        scope.required_role()
+      ));
+    }
+    let rate_per_minute =
+      rate_per_minute.unwrap_or(self.settings.default_rate_per_minute);
+    let burst = burst.unwrap_or(self.settings.default_burst);
+    let max = self.settings.max_rate_per_minute;
+    if !(1..=max).contains(&rate_per_minute) || !(1..=max).contains(&burst) {
+      return Err(format!(
+        "The rate per minute and the burst must be between 1 and {max}"
+      ));
+    }
+
+    let (key, prefix, key_hash) = generate_key();
+    let api_key = self
+      .api_key_repository
+      .create_api_key(NewApiKey {
+        owner_id,
+        name,
+        prefix,
+        key_hash,
+        scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+        rate_per_minute,
+        burst,
+      })
+      .await
+      .map_err(|e| e.to_string())?;
+    Ok(IssuedApiKey {
+      api_key,
+      key,
+    })
+  }
+
+  async fn api_keys_of(&self, owner_id: i32) -> Option<Vec<ApiKey>> {
+    self.api_key_repository.api_keys_of(owner_id).await.ok()
+  }
+
+  /// Replace the key, the previous one stops working at once. The id, the
+  /// scopes, the limits and the usage are kept.
+  ///
+  /// # Return:
+  /// * Ok with None if the key doesn't exist or belongs to another owner.
+  /// * Err if the key is revoked.
+  async fn rotate(
+    &self,
+    id: i32,
+    owner_id: Option<i32>,
+  ) -> Result<Option<IssuedApiKey>, String> {
+    let Some(api_key) = self.owned_api_key(id, owner_id).await else {
+      return Ok(None);
+    };
+    if api_key.get_revoked_at().is_some() {
+      return Err(format!("The key {id} is revoked"));
+    }
+    let (key, prefix, key_hash) = generate_key();
+    let api_key = self
+      .api_key_repository
+      .rotate_api_key(id, prefix, key_hash, Utc::now().naive_utc())
+      .await
+      .map_err(|e| e.to_string())?;
+    Ok(Some(IssuedApiKey {
+      api_key,
+      key,
+    }))
+  }
+
+  /// Returns None if the key doesn't exist or belongs to another owner.
+  async fn revoke(&self, id: i32, owner_id: Option<i32>) -> Option<ApiKey> {
+    self.owned_api_key(id, owner_id).await?;
+    let api_key = self
+      .api_key_repository
+      .revoke_api_key(id, Utc::now().naive_utc())
+      .await
+      .ok()?;
+    self.rate_limiter.forget(id);
+    Some(api_key)
+  }
+
+  /// The usage of the key per day in the last days, None if the key doesn't
+  /// exist or belongs to another owner.
+  async fn usage(
+    &self,
+    id: i32,
+    owner_id: Option<i32>,
+    days: i64,
+  ) -> Option<Vec<ApiKeyUsage>> {
+    self.owned_api_key(id, owner_id).await?;
+    let from = Utc::now().date_naive() - Duration::days(days.max(1) - 1);
+    self.api_key_repository.usage_of(id, from).await.ok()
+  }
+
+  /// The active key and its owner.
+  async fn authenticate(&self, key: &str) -> Option<(ApiKey, User)> {
+    let key = key.trim();
+    if !key.starts_with(KEY_PREFIX) {
+      return None;
+    }
+    let api_key = self
+      .api_key_repository
+      .active_api_key_by_hash(hash_key(key))
+      .await
+      .ok()??;
+    let owner = self
+      .user_repository
+      .user_by_id(api_key.get_owner_id())
+      .await
+      .ok()??;
+    Some((api_key, owner))
+  }
+
+  fn throttle(&self, api_key: &ApiKey) -> RateDecision {
+    let limit = RateLimit {
+      rate_per_minute: api_key.get_rate_per_minute().max(1) as u32,
+      burst: api_key.get_burst().max(1) as u32,
+    };
+    self
+      .rate_limiter
+      .check(api_key.get_id(), limit, Instant::now())
+  }
+
+  async fn record_usage(&self, id: i32, throttled: bool) {
+    if let Err(e) = self
+      .api_key_repository
+      .record_usage(id, throttled, Utc::now().naive_utc())
+      .await
+    {
+      println!("xx->> CANNOT RECORD THE USAGE OF THE KEY {id}: {e}");
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::user::UserSession;
+  use chrono::{NaiveDate, NaiveDateTime};
+  use std::sync::Mutex;
+
+  /// The keys in memory, only what the authentication needs.
+  #[derive(Default)]
+  struct InMemoryApiKeyRepo {
+    api_keys: Mutex<Vec<ApiKey>>,
+  }
+
+  #[async_trait]
+  impl ApiKeyRepository for InMemoryApiKeyRepo {
+    async fn create_api_key(&self, api_key: NewApiKey) -> sqlx::Result<ApiKey> {
+      let mut api_keys = self.api_keys.lock().unwrap();
+      let created = ApiKey {
+        id: api_keys.len() as i32 + 1,
+        owner_id: api_key.owner_id,
+        name: api_key.name,
+        prefix: api_key.prefix,
+        key_hash: api_key.key_hash,
+        scopes: api_key.scopes,
+        rate_per_minute: api_key.rate_per_minute,
+        burst: api_key.burst,
+        created_at: Utc::now().naive_utc(),
+        rotated_at: None,
+        revoked_at: None,
+        last_used_at: None,
+      };
+      api_keys.push(created.clone());
+      Ok(created)
+    }
+
+    async fn api_key_by_id(&self, id: i32) -> sqlx::Result<Option<ApiKey>> {
+      let api_keys = self.api_keys.lock().unwrap();
+      Ok(api_keys.iter().find(|api_key| api_key.id == id).cloned())
+    }
+
+    async fn active_api_key_by_hash(
+      &self,
+      key_hash: String,
+    ) -> sqlx::Result<Option<ApiKey>> {
+      let api_keys = self.api_keys.lock().unwrap();
+      Ok(
+        api_keys
+          .iter()
+          .find(|api_key| {
+            api_key.key_hash == key_hash && api_key.revoked_at.is_none()
+          })
+          .cloned(),
+      )
+    }
+
+    async fn api_keys_of(&self, _: i32) -> sqlx::Result<Vec<ApiKey>> {
+      todo!()
+    }
+
+    async fn rotate_api_key(
+      &self,
+      _: i32,
+      _: String,
+      _: String,
+      _: NaiveDateTime,
+    ) -> sqlx::Result<ApiKey> {
+      todo!()
+    }
+
+    async fn revoke_api_key(
+      &self,
+      id: i32,
+      now: NaiveDateTime,
+    ) -> sqlx::Result<ApiKey> {
+      let mut api_keys = self.api_keys.lock().unwrap();
+      let api_key = api_keys
+        .iter_mut()
+        .find(|api_key| api_key.id == id)
+        .ok_or(sqlx::Error::RowNotFound)?;
+      api_key.revoked_at = Some(now);
+      Ok(api_key.clone())
+    }
+
+    async fn record_usage(
+      &self,
+      _: i32,
+      _: bool,
+      _: NaiveDateTime,
+    ) -> sqlx::Result<()> {
+      Ok(())
+    }
+
+    async fn usage_of(
+      &self,
+      _: i32,
+      _: NaiveDate,
+    ) -> sqlx::Result<Vec<ApiKeyUsage>> {
+      todo!()
+    }
+  }
+
+  /// A single user, the owner of every key.
+  struct OwnerRepo;
+
+  #[async_trait]
+  impl UserRepository for OwnerRepo {
+    async fn create_user(&self, _: String, _: String) -> sqlx::Result<User> {
+      todo!()
+    }
+
+    async fn user_by_username(&self, _: String) -> sqlx::Result<Option<User>> {
+      todo!()
+    }
+
+    async fn user_by_id(&self, id: i32) -> sqlx::Result<Option<User>> {
+      Ok((id == 1).then(|| User {
+        id,
+        username: String::from("partner"),
+        password_hash: String::new(),
+        created_at: NaiveDateTime::default(),
+        role: Role::Editor.to_string(),
+      }))
+    }
+
+    async fn list_users(&self) -> sqlx::Result<Vec<User>> {
+      todo!()
+    }
+
+    async fn count_with_role(&self, _: Role) -> sqlx::Result<i64> {
+      todo!()
+    }
+
+    async fn update_role(&self, _: i32, _: Role) -> sqlx::Result<Option<User>> {
+      todo!()
+    }
+
+    async fn create_session(
+      &self,
+      _: String,
+      _: i32,
+      _: NaiveDateTime,
+    ) -> sqlx::Result<UserSession> {
+      todo!()
+    }
+
+    async fn user_of_session(
+      &self,
+      _: String,
+      _: NaiveDateTime,
+    ) -> sqlx::Result<Option<User>> {
+      todo!()
+    }
+
+    async fn delete_session(&self, _: String) -> sqlx::Result<()> {
+      todo!()
+    }
+  }
+
+  fn controller() -> ApiKeyControllerImpl<InMemoryApiKeyRepo, OwnerRepo> {
+    ApiKeyControllerImpl::new(
+      InMemoryApiKeyRepo::default(),
+      OwnerRepo,
+      ApiKeySettings {
+        default_rate_per_minute: 60,
+        default_burst: 60,
+        max_rate_per_minute: 600,
+      },
+    )
+  }
+
+  async fn issue(
+    controller: &ApiKeyControllerImpl<InMemoryApiKeyRepo, OwnerRepo>,
+    burst: Option<i32>,
+  ) -> IssuedApiKey {
+    controller
+      .issue(
+        1,
+        Role::Editor,
+        String::from("partner"),
+        vec![ApiScope::AttractionsRead, ApiScope::AttractionsRead],
+        None,
+        burst,
+      )
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn a_key_is_denied_the_scopes_its_owner_cant_use() {
+    let denied = controller()
+      .issue(
+        2,
+        Role::Viewer,
+        String::from("partner"),
+        vec![ApiScope::AttractionsRead, ApiScope::AttractionsWrite],
+        None,
+        None,
+      )
+      .await;
+    assert_eq!(
+      denied.err(),
+      Some(String::from(
+        "The scope attractions:write needs the role EDITOR"
+      ))
+    );
+  }
+
+  #[tokio::test]
+  async fn the_issued_key_authenticates_as_its_owner() {
+    let controller = controller();
+    let issued = issue(&controller, None).await;
+    assert_eq!(issued.api_key.get_scopes(), vec![ApiScope::AttractionsRead]);
+
+    let (api_key, owner) = controller.authenticate(&issued.key).await.unwrap();
+    assert_eq!(api_key.get_id(), issued.api_key.get_id());
+    assert_eq!(owner.get_username(), "partner");
+  }
+
+  #[tokio::test]
+  async fn an_unknown_or_revoked_key_doesnt_authenticate() {
+    let controller = controller();
+    let issued = issue(&controller, None).await;
+    assert!(controller.authenticate("cala_unknown").await.is_none());
+    assert!(controller.authenticate("not a key").await.is_none());
+
+    controller.revoke(issued.api_key.get_id(), Some(1)).await;
+    assert!(controller.authenticate(&issued.key).await.is_none());
+  }
+
+  #[tokio::test]
+  async fn the_requests_of_a_key_are_throttled_after_its_burst() {
+    let controller = controller();
+    let issued = issue(&controller, Some(2)).await;
+    let decisions = (0..3)
+      .map(|_| controller.throttle(&issued.api_key))
+      .collect::<Vec<RateDecision>>();
+    assert!(decisions[0].allowed && decisions[1].allowed);
+    assert!(!decisions[2].allowed);
+    assert_eq!(decisions[2].limit, 2);
+
+    // A new key isn't throttled by the other one.
+    let other = issue(&controller, Some(2)).await;
+    assert!(controller.throttle(&other.api_key).allowed);
+  }
+}
@@ -0,0 +1,292 @@
+use crate::{
+  db::database::DbConnection,
+  model::api_key::{ApiKey, ApiKeyUsage},
+};
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime};
+
+/// The values of a key to be issued.
+#[derive(Clone, Debug)]
+pub struct NewApiKey {
+  pub owner_id: i32,
+  pub name: String,
+  pub prefix: String,
+  pub key_hash: String,
+  pub scopes: Vec<String>,
+  pub rate_per_minute: i32,
+  pub burst: i32,
+}
+
+#[async_trait]
+pub trait ApiKeyRepository {
+  async fn create_api_key(&self, api_key: NewApiKey) -> sqlx::Result<ApiKey>;
+  async fn api_key_by_id(&self, id: i32) -> sqlx::Result<Option<ApiKey>>;
+  async fn active_api_key_by_hash(
+    &self,
+    key_hash: String,
+  ) -> sqlx::Result<Option<ApiKey>>;
+  async fn api_keys_of(&self, owner_id: i32) -> sqlx::Result<Vec<ApiKey>>;
+  async fn rotate_api_key(
+    &self,
+    id: i32,
+    prefix: String,
+    key_hash: String,
+    now: NaiveDateTime,
+  ) -> sqlx::Result<ApiKey>;
+  async fn revoke_api_key(
+    &self,
+    id: i32,
+    now: NaiveDateTime,
+  ) -> sqlx::Result<ApiKey>;
+  async fn record_usage(
+    &self,
+    id: i32,
+    throttled: bool,
+    now: NaiveDateTime,
+  ) -> sqlx::Result<()>;
+  async fn usage_of(
+    &self,
+    id: i32,
+    from: NaiveDate,
+  ) -> sqlx::Result<Vec<ApiKeyUsage>>;
+}
+
+#[derive(Clone, Default)]
+pub struct DummyApiKeyRepo;
+
+#[async_trait]
+impl ApiKeyRepository for DummyApiKeyRepo {
+  async fn create_api_key(&self, _: NewApiKey) -> sqlx::Result<ApiKey> {
+    todo!()
+  }
+
+  async fn api_key_by_id(&self, _: i32) -> sqlx::Result<Option<ApiKey>> {
+    todo!()
+  }
+
+  async fn active_api_key_by_hash(
+    &self,
+    _: String,
+  ) -> sqlx::Result<Option<ApiKey>> {
+    todo!()
+  }
+
+  async fn api_keys_of(&self, _: i32) -> sqlx::Result<Vec<ApiKey>> {
+    todo!()
+  }
+
+  async fn rotate_api_key(
+    &self,
+    _: i32,
+    _: String,
+    _: String,
+    _: NaiveDateTime,
+  ) -> sqlx::Result<ApiKey> {
+    todo!()
+  }
+
+  async fn revoke_api_key(
+    &self,
+    _: i32,
+    _: NaiveDateTime,
+  ) -> sqlx::Result<ApiKey> {
+    todo!()
+  }
+
+  async fn record_usage(
+    &self,
+    _: i32,
+    _: bool,
+    _: NaiveDateTime,
+  ) -> sqlx::Result<()> {
+    todo!()
+  }
+
+  async fn usage_of(
+    &self,
+    _: i32,
+    _: NaiveDate,
+  ) -> sqlx::Result<Vec<ApiKeyUsage>> {
+    todo!()
+  }
+}
+
+#[derive(Clone)]
+pub struct PgApiKeyRepository {
+  connection: DbConnection,
+}
+
+impl PgApiKeyRepository {
+  pub fn new(connection: DbConnection) -> Self {
+    PgApiKeyRepository {
+      connection,
+    }
+  }
+}
+
+#[async_trait]
+impl ApiKeyRepository for PgApiKeyRepository {
+  async fn create_api_key(&self, api_key: NewApiKey) -> sqlx::Result<ApiKey> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      ApiKey,
+      r#"
+      INSERT INTO api_key
+        (owner_id, name, prefix, key_hash, scopes, rate_per_minute, burst)
+      VALUES ($1, $2, $3, $4, $5, $6, $7)
+      returning *
+      "#,
+      api_key.owner_id,
+      api_key.name,
+      api_key.prefix,
+      api_key.key_hash,
+      &api_key.scopes,
+      api_key.rate_per_minute,
+      api_key.burst
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  async fn api_key_by_id(&self, id: i32) -> sqlx::Result<Option<ApiKey>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      ApiKey,
+      r#"
+      SELECT * FROM api_key WHERE id = $1
+      "#,
+      id
+    )
+    .fetch_optional(conn)
+    .await
+  }
+
+  /// Returns the key with the hash, unless it is revoked.
+  async fn active_api_key_by_hash(
+    &self,
+    key_hash: String,
+  ) -> sqlx::Result<Option<ApiKey>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      ApiKey,
+      r#"
+      SELECT * FROM api_key
+      WHERE key_hash = $1 AND revoked_at IS NULL
+      "#,
+      key_hash
+    )
+    .fetch_optional(conn)
+    .await
+  }
+
+  async fn api_keys_of(&self, owner_id: i32) -> sqlx::Result<Vec<ApiKey>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      ApiKey,
+      r#"
+      SELECT * FROM api_key WHERE owner_id = $1
+      ORDER BY id
+      "#,
+      owner_id
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// Replace the hash of an active key, the previous key stops working.
+  async fn rotate_api_key(
+    &self,
+    id: i32,
+    prefix: String,
+    key_hash: String,
+    now: NaiveDateTime,
+  ) -> sqlx::Result<ApiKey> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      ApiKey,
+      r#"
+      UPDATE api_key SET prefix = $2, key_hash = $3, rotated_at = $4
+      WHERE id = $1 AND revoked_at IS NULL
+      returning *
+      "#,
+      id,
+      prefix,
+      key_hash,
+      now
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  /// Revoke the key, keeping the moment of the first revocation.
+  async fn revoke_api_key(
+    &self,
+    id: i32,
+    now: NaiveDateTime,
+  ) -> sqlx::Result<ApiKey> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      ApiKey,
+      r#"
+      UPDATE api_key SET revoked_at = coalesce(revoked_at, $2)
+      WHERE id = $1
+      returning *
+      "#,
+      id,
+      now
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  /// Count a request in the usage of the day, and remember when the key was
+  /// last used.
+  async fn record_usage(
+    &self,
+    id: i32,
+    throttled: bool,
+    now: NaiveDateTime,
+  ) -> sqlx::Result<()> {
+    let conn = self.connection.get();
+    sqlx::query!(
+      r#"
+      WITH used AS (
+        UPDATE api_key SET last_used_at = $3 WHERE id = $1
+      )
+      INSERT INTO api_key_usage
+        (api_key_id, day, request_count, throttled_count)
+      VALUES ($1, $3::timestamp::date, 1, CASE WHEN $2 THEN 1 ELSE 0 END)
+      ON CONFLICT ON CONSTRAINT api_key_usage_pk DO UPDATE SET
+      request_count = api_key_usage.request_count + 1,
+      throttled_count = api_key_usage.throttled_count
+        + EXCLUDED.throttled_count
+      "#,
+      id,
+      throttled,
+      now
+    )
+    .execute(conn)
+    .await
+    .map(|_| ())
+  }
+
+  async fn usage_of(
+    &self,
+    id: i32,
+    from: NaiveDate,
+  ) -> sqlx::Result<Vec<ApiKeyUsage>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      ApiKeyUsage,
+      r#"
+      SELECT day, request_count, throttled_count FROM api_key_usage
+      WHERE api_key_id = $1 AND day >= $2
+      ORDER BY day
+      "#,
+      id,
+      from
+    )
+    .fetch_all(conn)
+    .await
+  }
+}
@@ -1,6 +1,10 @@
+use crate::model::rating_rules::location_of;
 use bigdecimal::{self, BigDecimal};
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
+use geoutils::Location;
+use serde::Deserialize;
 use sqlx::FromRow;
+use std::{fmt, str::FromStr};
 
 #[derive(FromRow)]
 pub struct Attraction {
@@ -10,6 +14,19 @@ pub struct Attraction {
   pub latitude: Option<String>,
   pub longitude: Option<String>,
   pub attraction_type_id: i32,
+  /// Always none, the queries leave out the deleted rows.
+  #[allow(dead_code)]
+  pub deleted_at: Option<NaiveDateTime>,
+}
+
+/// The values of an attraction to be created or updated.
+#[derive(Clone, Debug)]
+pub struct NewAttraction {
+  pub description: String,
+  pub city_id: i32,
+  pub attraction_type_id: i32,
+  pub latitude: Option<String>,
+  pub longitude: Option<String>,
 }
 
 impl Attraction {
@@ -17,6 +34,10 @@ impl Attraction {
     self.id
   }
 
+  pub fn location(&self) -> Option<Location> {
+    location_of(&self.latitude, &self.longitude)
+  }
+
   pub fn get_description(&self) -> String {
     self.description.to_string()
   }
@@ -38,18 +59,245 @@ impl Attraction {
   }
 }
 
-#[derive(FromRow)]
+#[derive(FromRow, Debug, Clone)]
 pub struct AttractionType {
   pub id: i32,
   pub code: String,
   pub description: String,
+  /// Always none, the queries leave out the deleted rows.
+  #[allow(dead_code)]
+  pub deleted_at: Option<NaiveDateTime>,
 }
 
-#[derive(FromRow)]
+impl AttractionType {
+  pub fn get_id(&self) -> i32 {
+    self.id
+  }
+
+  pub fn get_code(&self) -> String {
+    self.code.to_string()
+  }
+
+  pub fn get_description(&self) -> String {
+    self.description.to_string()
+  }
+}
+
+#[derive(FromRow, Debug, Clone)]
+pub struct Tag {
+  pub id: i32,
+  pub code: String,
+  pub description: String,
+  /// Always none, the queries leave out the deleted rows.
+  #[allow(dead_code)]
+  pub deleted_at: Option<NaiveDateTime>,
+}
+
+impl Tag {
+  pub fn get_id(&self) -> i32 {
+    self.id
+  }
+
+  pub fn get_code(&self) -> String {
+    self.code.to_string()
+  }
+
+  pub fn get_description(&self) -> String {
+    self.description.to_string()
+  }
+}
+
+/// How much it costs to visit an attraction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum PriceRange {
+  Free,
+  Low,
+  Medium,
+  High,
+}
+
+impl PriceRange {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      PriceRange::Free => "FREE",
+      PriceRange::Low => "LOW",
+      PriceRange::Medium => "MEDIUM",
+      PriceRange::High => "HIGH",
+    }
+  }
+}
+
+impl fmt::Display for PriceRange {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+impl FromStr for PriceRange {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_uppercase().as_str() {
+      "FREE" => Ok(PriceRange::Free),
+      "LOW" => Ok(PriceRange::Low),
+      "MEDIUM" => Ok(PriceRange::Medium),
+      "HIGH" => Ok(PriceRange::High),
+      _ => Err(format!("Unknown price range: {}", s)),
+    }
+  }
+}
+
+impl TryFrom<String> for PriceRange {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+/// The typed attributes of an attraction.
+#[derive(FromRow, Debug, Clone)]
+pub struct AttractionAttributes {
+  pub attraction_id: i32,
+  pub price_range: Option<String>,
+  /// The typical duration of a visit.
+  pub visit_minutes: Option<i32>,
+  pub updated_at: NaiveDateTime,
+}
+
+impl AttractionAttributes {
+  pub fn get_attraction_id(&self) -> i32 {
+    self.attraction_id
+  }
+
+  pub fn get_price_range(&self) -> Option<PriceRange> {
+    self
+      .price_range
+      .as_ref()
+      .and_then(|value| value.parse().ok())
+  }
+
+  pub fn get_visit_minutes(&self) -> Option<i32> {
+    self.visit_minutes
+  }
+
+  pub fn get_updated_at(&self) -> NaiveDateTime {
+    self.updated_at
+  }
+}
+
+/// The number of attractions with a value of a facet: a tag, a price range
+/// or a type.
+#[derive(FromRow, Debug, Clone)]
+pub struct FacetCount {
+  pub facet: String,
+  pub value: String,
+  pub description: Option<String>,
+  pub attraction_count: i64,
+}
+
+#[derive(FromRow, Debug, Clone)]
+pub struct Country {
+  pub id: i32,
+  pub iso_code: String,
+  pub description: String,
+  /// Always none, the queries leave out the deleted rows.
+  #[allow(dead_code)]
+  pub deleted_at: Option<NaiveDateTime>,
+}
+
+impl Country {
+  pub fn get_id(&self) -> i32 {
+    self.id
+  }
+
+  pub fn get_iso_code(&self) -> String {
+    self.iso_code.to_string()
+  }
+
+  pub fn get_description(&self) -> String {
+    self.description.to_string()
+  }
+}
+
+#[derive(FromRow, Debug, Clone)]
 pub struct City {
   pub id: i32,
   pub description: String,
   pub country_id: i32,
+  /// Always none, the queries leave out the deleted rows.
+  #[allow(dead_code)]
+  pub deleted_at: Option<NaiveDateTime>,
+}
+
+impl City {
+  pub fn get_id(&self) -> i32 {
+    self.id
+  }
+
+  pub fn get_description(&self) -> String {
+    self.description.to_string()
+  }
+
+  pub fn get_country_id(&self) -> i32 {
+    self.country_id
+  }
+}
+
+/// The opening hours of an attraction in the OSM opening_hours syntax.
+#[derive(FromRow, Debug, Clone)]
+pub struct AttractionOpeningHours {
+  pub attraction_id: i32,
+  pub opening_hours: String,
+  pub updated_at: NaiveDateTime,
+}
+
+impl AttractionOpeningHours {
+  pub fn get_attraction_id(&self) -> i32 {
+    self.attraction_id
+  }
+
+  pub fn get_opening_hours(&self) -> String {
+    self.opening_hours.to_string()
+  }
+
+  pub fn get_updated_at(&self) -> NaiveDateTime {
+    self.updated_at
+  }
+}
+
+#[derive(FromRow, Debug, Clone)]
+pub struct Holiday {
+  pub id: i32,
+  pub country_id: i32,
+  pub day: NaiveDate,
+  pub description: String,
+}
+
+impl Holiday {
+  pub fn get_id(&self) -> i32 {
+    self.id
+  }
+
+  pub fn get_country_id(&self) -> i32 {
+    self.country_id
+  }
+
+  pub fn get_day(&self) -> NaiveDate {
+    self.day
+  }
+
+  pub fn get_description(&self) -> String {
+    self.description.to_string()
+  }
+}
+
+/// A holiday of the country where an attraction is.
+#[derive(FromRow, Debug, Clone)]
+pub struct AttractionHoliday {
+  pub attraction_id: i32,
+  pub day: NaiveDate,
 }
 
 #[derive(FromRow, Debug, Clone)]
@@ -58,6 +306,14 @@ pub struct AttractionRating {
   pub at: NaiveDateTime,
   pub attraction_id: i32,
   pub rate: BigDecimal,
+  pub updated_at: NaiveDateTime,
+  pub source: String,
+  pub submitter: Option<String>,
+  pub fingerprint: Option<String>,
+  pub status: String,
+  pub fraud_score: BigDecimal,
+  pub fraud_reasons: Vec<String>,
+  pub user_id: Option<i32>,
 }
 
 impl AttractionRating {
@@ -76,9 +332,57 @@ impl AttractionRating {
   pub fn get_attraction_id(&self) -> i32 {
     self.attraction_id
   }
+
+  pub fn get_updated_at(&self) -> NaiveDateTime {
+    self.updated_at
+  }
+
+  pub fn get_source(&self) -> String {
+    self.source.clone()
+  }
+
+  pub fn get_submitter(&self) -> Option<String> {
+    self.submitter.clone()
+  }
+
+  pub fn get_fingerprint(&self) -> Option<String> {
+    self.fingerprint.clone()
+  }
+
+  pub fn get_status(&self) -> String {
+    self.status.clone()
+  }
+
+  pub fn get_fraud_score(&self) -> BigDecimal {
+    self.fraud_score.clone()
+  }
+
+  pub fn get_fraud_reasons(&self) -> Vec<String> {
+    self.fraud_reasons.clone()
+  }
+
+  pub fn get_user_id(&self) -> Option<i32> {
+    self.user_id
+  }
 }
 
-#[derive(FromRow)]
+/// A rating registered around the moment of an incoming one, with the
+/// location of its attraction, used to review the incoming rating.
+#[allow(dead_code)]
+#[derive(FromRow, Debug, Clone)]
+pub struct RecentRating {
+  pub id: i32,
+  pub attraction_id: i32,
+  pub rate: BigDecimal,
+  pub at: NaiveDateTime,
+  pub submitter: Option<String>,
+  pub fingerprint: Option<String>,
+  pub latitude: Option<String>,
+  pub longitude: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(FromRow, Clone)]
 pub struct AttractionRatingAggregate {
   pub id: i32,
   pub attraction_id: i32,
@@ -86,6 +390,14 @@ pub struct AttractionRatingAggregate {
   pub average: BigDecimal,
   pub ninety_five_percentile: BigDecimal,
   pub ninety_nine_percentile: BigDecimal,
+  pub rating_count: i32,
+  pub min_rate: BigDecimal,
+  pub max_rate: BigDecimal,
+  pub median: BigDecimal,
+  pub standard_deviation: BigDecimal,
+  pub histogram: Vec<i32>,
+  pub granularity: String,
+  pub watermark: Option<NaiveDateTime>,
 }
 
 impl AttractionRatingAggregate {
@@ -108,6 +420,182 @@ impl AttractionRatingAggregate {
   pub fn get_99_percentile(&self) -> BigDecimal {
     self.ninety_nine_percentile.clone()
   }
+
+  pub fn get_rating_count(&self) -> i32 {
+    self.rating_count
+  }
+
+  pub fn get_min_rate(&self) -> BigDecimal {
+    self.min_rate.clone()
+  }
+
+  pub fn get_max_rate(&self) -> BigDecimal {
+    self.max_rate.clone()
+  }
+
+  pub fn get_median(&self) -> BigDecimal {
+    self.median.clone()
+  }
+
+  pub fn get_standard_deviation(&self) -> BigDecimal {
+    self.standard_deviation.clone()
+  }
+
+  pub fn get_histogram(&self) -> Vec<i32> {
+    self.histogram.clone()
+  }
+
+  pub fn get_granularity(&self) -> String {
+    self.granularity.to_string()
+  }
+
+  pub fn get_watermark(&self) -> Option<NaiveDateTime> {
+    self.watermark
+  }
+}
+
+/// The persisted sketch of the ratings of an attraction in a day, with the
+/// state of the ratings already included on it.
+#[allow(dead_code)]
+#[derive(FromRow, Clone)]
+pub struct AttractionRatingSketch {
+  pub id: i32,
+  pub attraction_id: i32,
+  pub at: NaiveDateTime,
+  pub sketch: Vec<u8>,
+  pub rating_count: i32,
+  pub last_rating_id: i32,
+  pub watermark: Option<NaiveDateTime>,
+}
+
+impl AttractionRatingSketch {
+  pub fn get_attraction_id(&self) -> i32 {
+    self.attraction_id
+  }
+
+  pub fn get_at(&self) -> NaiveDateTime {
+    self.at
+  }
+
+  pub fn get_rating_count(&self) -> i32 {
+    self.rating_count
+  }
+
+  pub fn get_last_rating_id(&self) -> i32 {
+    self.last_rating_id
+  }
+
+  pub fn get_watermark(&self) -> Option<NaiveDateTime> {
+    self.watermark
+  }
+}
+
+/// A day where the ratings of an attraction deviate from its history.
+#[allow(dead_code)]
+#[derive(FromRow)]
+pub struct AttractionRatingAnomaly {
+  pub id: i32,
+  pub attraction_id: i32,
+  pub at: NaiveDateTime,
+  pub metric: String,
+  pub method: String,
+  pub value: BigDecimal,
+  pub baseline: BigDecimal,
+  pub score: BigDecimal,
+  pub detected_at: NaiveDateTime,
+}
+
+impl AttractionRatingAnomaly {
+  pub fn get_attraction_id(&self) -> i32 {
+    self.attraction_id
+  }
+
+  pub fn get_at(&self) -> NaiveDateTime {
+    self.at
+  }
+
+  pub fn get_metric(&self) -> String {
+    self.metric.clone()
+  }
+
+  pub fn get_method(&self) -> String {
+    self.method.clone()
+  }
+
+  pub fn get_value(&self) -> BigDecimal {
+    self.value.clone()
+  }
+
+  pub fn get_baseline(&self) -> BigDecimal {
+    self.baseline.clone()
+  }
+
+  pub fn get_score(&self) -> BigDecimal {
+    self.score.clone()
+  }
+
+  pub fn get_detected_at(&self) -> NaiveDateTime {
+    self.detected_at
+  }
+}
+
+/// The accepted ratings and the check-ins of an attraction in a period, with
+/// their sums weighted by recency.
+#[derive(FromRow, Debug, Clone)]
+pub struct AttractionRatingSummary {
+  pub attraction_id: i32,
+  pub description: String,
+  pub city_id: i32,
+  pub attraction_type_id: i32,
+  pub rating_count: i64,
+  pub rate_sum: BigDecimal,
+  pub weighted_count: f64,
+  pub weighted_sum: f64,
+  pub last_rated_at: Option<NaiveDateTime>,
+  pub check_in_count: i64,
+  /// The check-ins without a rating weighted by recency, the rated ones are
+  /// already in the ratings.
+  pub weighted_check_ins: f64,
+}
+
+/// A city or a country, with the country it belongs to.
+#[derive(FromRow, Debug, Clone)]
+pub struct RegionInfo {
+  pub name: String,
+  pub iso_code: String,
+  pub country: String,
+  pub city_count: i64,
+}
+
+/// An attraction of a region, with its type.
+#[derive(FromRow, Debug, Clone)]
+pub struct RegionAttraction {
+  pub attraction_id: i32,
+  pub latitude: Option<String>,
+  pub longitude: Option<String>,
+  pub attraction_type_id: i32,
+  pub attraction_type_code: String,
+  pub attraction_type: String,
+}
+
+/// The aggregates of all the attractions of a region in a bucket.
+#[derive(FromRow, Debug, Clone)]
+pub struct RegionRatingBucket {
+  pub at: NaiveDateTime,
+  pub rating_count: i64,
+  pub average: BigDecimal,
+}
+
+/// The average rating of an attraction in the last days and in the same
+/// number of days before them.
+#[derive(FromRow, Debug, Clone)]
+pub struct AttractionRatingChange {
+  pub attraction_id: i32,
+  pub description: String,
+  pub previous_count: i64,
+  pub previous_average: Option<BigDecimal>,
+  pub recent_count: i64,
+  pub recent_average: Option<BigDecimal>,
 }
 
 #[derive(FromRow)]
@@ -116,6 +604,8 @@ pub struct FullAttraction {
   pub description: String,
   pub city: String,
   pub attraction_type: String,
+  /// The locale of the description, none when it isn't translated.
+  pub locale: Option<String>,
 }
 
 impl FullAttraction {
@@ -134,10 +624,20 @@ impl FullAttraction {
   pub fn get_attraction_type(&self) -> String {
     self.attraction_type.to_string()
   }
+
+  pub fn get_locale(&self) -> Option<String> {
+    self.locale.clone()
+  }
 }
 
+/// The state of the ratings of an attraction in a period. When the state of
+/// the ratings differs from the one recorded in the aggregate of the period,
+/// the aggregate is stale.
 #[derive(FromRow, Hash, Eq, PartialEq, Clone, Debug)]
 pub struct AttractionByDate {
   pub attraction_id: i32,
   pub at: Option<NaiveDateTime>,
+  pub rating_count: Option<i64>,
+  /// The latest moment when a rating of the period was written.
+  pub watermark: Option<NaiveDateTime>,
 }
@@ -1,30 +1,367 @@
 use super::attraction_repository::AttractionRepository;
-use crate::model::attraction::{Attraction, AttractionRating, FullAttraction};
+use crate::{
+  db::database::{is_foreign_key_violation, is_unique_violation},
+  model::{
+    attraction::{
+      Attraction, AttractionAttributes, AttractionOpeningHours,
+      AttractionRating, FacetCount, FullAttraction, NewAttraction, PriceRange,
+      Tag,
+    },
+    audit::Actor,
+    opening_hours::{OpeningHours, Schedules, TimeSpan},
+    rating_rules::{
+      location_of, IncomingRating, RatingContext, RatingRulesEngine,
+      RatingRulesSettings, RatingStatus,
+    },
+    rating_statistics::decimal_of,
+    reference_controller::tag_code_of,
+    translation::{best_of, Translation},
+  },
+};
 use async_trait::async_trait;
+use bigdecimal::BigDecimal;
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use geoutils::Location;
+use std::{
+  collections::{HashMap, HashSet},
+  fmt,
+  sync::Arc,
+};
+
+/// The most attractions listed at once.
+const LIST_LIMIT: usize = 20;
+
+/// What the listed attractions must satisfy.
+#[derive(Debug, Clone, Default)]
+pub struct AttractionFilter {
+  /// The moment when they are open, in their local time.
+  pub open_at: Option<NaiveDateTime>,
+  /// The codes of the tags that all of them have.
+  pub tags: Vec<String>,
+}
+
+impl AttractionFilter {
+  fn is_empty(&self) -> bool {
+    self.open_at.is_none() && self.tags.is_empty()
+  }
+}
+
+/// An attraction close to a point.
+pub struct NearbyAttraction {
+  pub attraction: Attraction,
+  pub distance_meters: f64,
+}
+
+/// The opening hours of an attraction and when it opens in the next days.
+pub struct AttractionSchedule {
+  pub opening_hours: AttractionOpeningHours,
+  /// Whether it's open at the asked moment, if any.
+  pub is_open: Option<bool>,
+  /// The periods when it's open in the seven days from the asked moment, or
+  /// from today.
+  pub week: Vec<(NaiveDate, Vec<TimeSpan>)>,
+}
+
+/// The opening hours of the attractions with the holidays of their countries
+/// between both days. The holidays of the day before the first one are
+/// included, for the hours that go past the midnight.
+pub async fn schedules_of<AttractionRepo>(
+  attraction_repository: &AttractionRepo,
+  attraction_ids: Vec<i32>,
+  from: NaiveDate,
+  to: NaiveDate,
+) -> sqlx::Result<Schedules>
+where
+  AttractionRepo: AttractionRepository,
+{
+  let opening_hours = attraction_repository
+    .opening_hours_of(attraction_ids.clone())
+    .await?;
+  let holidays = attraction_repository
+    .holidays_of(attraction_ids, from - Duration::days(1), to)
+    .await?;
+  Ok(Schedules::new(&opening_hours, &holidays))
+}
+
+/// Why a rating can't be registered.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RatingFail {
+  /// The user already rated the attraction that day.
+  Duplicate(String),
+  /// The rating couldn't be reviewed or saved.
+  Service(String),
+}
+
+impl fmt::Display for RatingFail {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      RatingFail::Duplicate(reason) | RatingFail::Service(reason) => {
+        write!(f, "{reason}")
+      },
+    }
+  }
+}
+
+/// Review a rating with the rules engine and register it, accepted or
+/// quarantined. Returns None if the attraction doesn't exist.
+pub async fn register_rating<AttractionRepo>(
+  attraction_repository: &AttractionRepo,
+  rules_engine: &RatingRulesEngine,
+  rating: IncomingRating,
+) -> Result<Option<AttractionRating>, RatingFail>
+where
+  AttractionRepo: AttractionRepository,
+{
+  let Some(mut attraction_rating) =
+    reviewed_rating(attraction_repository, rules_engine, rating).await?
+  else {
+    return Ok(None);
+  };
+  let id = attraction_repository
+    .save_rating(attraction_rating.clone())
+    .await
+    .map_err(|e| rating_fail(&e, &attraction_rating))?;
+  attraction_rating.id = id.id;
+  Ok(Some(attraction_rating))
+}
+
+/// Review a rating with the rules engine, the rating isn't saved. Returns
+/// None if the attraction doesn't exist.
+pub async fn reviewed_rating<AttractionRepo>(
+  attraction_repository: &AttractionRepo,
+  rules_engine: &RatingRulesEngine,
+  rating: IncomingRating,
+) -> Result<Option<AttractionRating>, RatingFail>
+where
+  AttractionRepo: AttractionRepository,
+{
+  let attraction = match attraction_repository
+    .attraction_by_id(rating.attraction_id)
+    .await
+  {
+    Ok(an_attraction) => an_attraction,
+    Err(sqlx::Error::RowNotFound) => return Ok(None),
+    Err(e) => return Err(RatingFail::Service(e.to_string())),
+  };
+  let recent = attraction_repository
+    .recent_ratings(
+      rating.attraction_id,
+      rating.submitter.clone(),
+      rating.fingerprint.clone(),
+      rating.at - rules_engine.lookback(),
+      rating.at + Duration::seconds(1),
+    )
+    .await
+    .map_err(|e| RatingFail::Service(e.to_string()))?;
+  let review = rules_engine.review(&RatingContext {
+    rating: &rating,
+    location: location_of(
+      &attraction.get_latitude(),
+      &attraction.get_longitude(),
+    ),
+    recent: &recent,
+  });
+  if review.status != RatingStatus::Accepted {
+    println!(
+      "->> RATING {} for attraction {}: {:?}\n",
+      review.status, rating.attraction_id, review.reasons
+    );
+  }
+
+  Ok(Some(AttractionRating {
+    id: 0,
+    at: rating.at,
+    attraction_id: rating.attraction_id,
+    rate: rating.rate,
+    updated_at: Utc::now().naive_utc(),
+    source: rating.source,
+    submitter: rating.submitter,
+    fingerprint: rating.fingerprint,
+    status: review.status.to_string(),
+    fraud_score: decimal_of(review.score).ok_or(RatingFail::Service(
+      format!("Invalid fraud score: {}", review.score),
+    ))?,
+    fraud_reasons: review.reasons,
+    user_id: rating.user_id,
+  }))
+}
+
+/// A duplicate if the user already rated the attraction that day.
+pub fn rating_fail(e: &sqlx::Error, a_rating: &AttractionRating) -> RatingFail {
+  if is_unique_violation(e) {
+    RatingFail::Duplicate(format!(
+      "The user already rated the attraction {} on {}",
+      a_rating.attraction_id,
+      a_rating.at.date()
+    ))
+  } else {
+    RatingFail::Service(e.to_string())
+  }
+}
 
 #[async_trait]
 pub trait AttractionController: Send + Sync + 'static {
-  async fn list(&self) -> Option<Vec<Attraction>>;
-  async fn get_attraction(&self, id: i32) -> Option<FullAttraction>;
+  async fn list(&self, filter: AttractionFilter) -> Option<Vec<Attraction>>;
+  async fn nearby(
+    &self,
+    location: Location,
+    radius_meters: f64,
+    filter: AttractionFilter,
+    limit: usize,
+  ) -> Option<Vec<NearbyAttraction>>;
+  async fn get_attraction(
+    &self,
+    id: i32,
+    locales: Vec<String>,
+  ) -> Option<FullAttraction>;
+  async fn translations_of(
+    &self,
+    ids: Vec<i32>,
+    locales: Vec<String>,
+  ) -> Option<HashMap<i32, Translation>>;
+  async fn redirect_of(&self, id: i32) -> Option<i32>;
   async fn ratings_for(
     &self,
     attraction_id: i32,
   ) -> Option<Vec<AttractionRating>>;
+  async fn rate(
+    &self,
+    rating: IncomingRating,
+  ) -> Result<Option<AttractionRating>, RatingFail>;
+  async fn ratings_with_status(
+    &self,
+    status: RatingStatus,
+  ) -> Option<Vec<AttractionRating>>;
+  async fn review_rating(
+    &self,
+    rating_id: i32,
+    status: RatingStatus,
+  ) -> Option<AttractionRating>;
+  async fn create(
+    &self,
+    attraction: NewAttraction,
+    actor: Actor,
+  ) -> Result<Attraction, String>;
+  async fn update(
+    &self,
+    id: i32,
+    attraction: NewAttraction,
+    actor: Actor,
+  ) -> Result<Option<Attraction>, String>;
+  async fn delete(&self, id: i32, actor: Actor) -> Result<bool, String>;
+  async fn ratings_of_user(
+    &self,
+    user_id: i32,
+    attraction_id: Option<i32>,
+  ) -> Option<Vec<AttractionRating>>;
+  async fn update_user_rating(
+    &self,
+    id: i32,
+    user_id: i32,
+    rate: BigDecimal,
+  ) -> Option<AttractionRating>;
+  async fn delete_user_rating(&self, id: i32, user_id: i32) -> Option<bool>;
+  async fn opening_hours(
+    &self,
+    id: i32,
+    at: Option<NaiveDateTime>,
+  ) -> Option<AttractionSchedule>;
+  async fn set_opening_hours(
+    &self,
+    id: i32,
+    opening_hours: String,
+    actor: Actor,
+  ) -> Result<Option<AttractionOpeningHours>, String>;
+  async fn delete_opening_hours(&self, id: i32, actor: Actor) -> Option<bool>;
+  async fn tags_of(&self, id: i32) -> Option<Vec<Tag>>;
+  async fn set_tags(
+    &self,
+    id: i32,
+    codes: Vec<String>,
+    actor: Actor,
+  ) -> Result<Option<Vec<Tag>>, String>;
+  async fn attributes_of(&self, id: i32) -> Option<AttractionAttributes>;
+  async fn set_attributes(
+    &self,
+    id: i32,
+    price_range: Option<PriceRange>,
+    visit_minutes: Option<i32>,
+    actor: Actor,
+  ) -> Result<Option<AttractionAttributes>, String>;
+  async fn facets(
+    &self,
+    city_id: Option<i32>,
+    tags: Vec<String>,
+  ) -> Option<Vec<FacetCount>>;
 }
 
 #[derive(Clone)]
 pub struct AttractionControllerImpl<AttractionRepo> {
   attraction_repository: AttractionRepo,
+  rules_engine: Arc<RatingRulesEngine>,
 }
 
 impl<AttractionRepo> AttractionControllerImpl<AttractionRepo>
 where
   AttractionRepo: AttractionRepository,
 {
-  pub fn new(attraction_repository: AttractionRepo) -> Self {
+  pub fn new(
+    attraction_repository: AttractionRepo,
+    rules_settings: RatingRulesSettings,
+  ) -> Self {
     AttractionControllerImpl {
       attraction_repository,
+      rules_engine: Arc::new(RatingRulesEngine::from_settings(&rules_settings)),
+    }
+  }
+
+  /// Keep the attractions that satisfy the filter. The ones without opening
+  /// hours are left out when filtering by the moment.
+  async fn filtered(
+    &self,
+    attractions: Vec<Attraction>,
+    filter: &AttractionFilter,
+  ) -> sqlx::Result<Vec<Attraction>> {
+    let mut attractions = attractions;
+    if !filter.tags.is_empty() {
+      let tagged = self
+        .attraction_repository
+        .attractions_tagged(codes_of(&filter.tags))
+        .await?
+        .iter()
+        .map(|an_attraction| an_attraction.get_id())
+        .collect::<HashSet<i32>>();
+      attractions
+        .retain(|an_attraction| tagged.contains(&an_attraction.get_id()));
     }
+    match filter.open_at {
+      Some(at) => self.open_at(attractions, at).await,
+      None => Ok(attractions),
+    }
+  }
+
+  /// Keep the attractions that are open at the moment, the ones without
+  /// opening hours are left out.
+  async fn open_at(
+    &self,
+    attractions: Vec<Attraction>,
+    at: NaiveDateTime,
+  ) -> sqlx::Result<Vec<Attraction>> {
+    let ids = attractions
+      .iter()
+      .map(|an_attraction| an_attraction.get_id())
+      .collect::<Vec<i32>>();
+    let schedules =
+      schedules_of(&self.attraction_repository, ids, at.date(), at.date())
+        .await?;
+    Ok(
+      attractions
+        .into_iter()
+        .filter(|an_attraction| {
+          schedules.is_open(an_attraction.get_id(), at) == Some(true)
+        })
+        .collect(),
+    )
   }
 }
 
@@ -34,30 +371,1148 @@ impl<AttractionRepo> AttractionController
 where
   AttractionRepo: AttractionRepository + Send + Sync + 'static,
 {
-  async fn list(&self) -> Option<Vec<Attraction>> {
-    match self.attraction_repository.list().await {
-      Ok(attractions) => Some(attractions),
-      Err(_) => None,
+  /// List the attractions, only the ones that satisfy the filter when it
+  /// isn't empty.
+  async fn list(&self, filter: AttractionFilter) -> Option<Vec<Attraction>> {
+    if filter.is_empty() {
+      return self.attraction_repository.list().await.ok();
     }
+    let attractions = if filter.tags.is_empty() {
+      self
+        .attraction_repository
+        .attractions_with_opening_hours()
+        .await
+    } else {
+      self
+        .attraction_repository
+        .attractions_tagged(codes_of(&filter.tags))
+        .await
+    }
+    .ok()?;
+    let mut listed = self.filtered(attractions, &filter).await.ok()?;
+    listed.truncate(LIST_LIMIT);
+    Some(listed)
+  }
+
+  /// The attractions within the radius of the location that satisfy the
+  /// filter, the closest first.
+  async fn nearby(
+    &self,
+    location: Location,
+    radius_meters: f64,
+    filter: AttractionFilter,
+    limit: usize,
+  ) -> Option<Vec<NearbyAttraction>> {
+    let attractions = self
+      .attraction_repository
+      .attractions_with_location()
+      .await
+      .ok()?
+      .into_iter()
+      .filter(|an_attraction| {
+        an_attraction.location().is_some_and(|other| {
+          location.haversine_distance_to(&other).meters() <= radius_meters
+        })
+      })
+      .collect::<Vec<Attraction>>();
+    let mut nearby = self
+      .filtered(attractions, &filter)
+      .await
+      .ok()?
+      .into_iter()
+      .filter_map(|an_attraction| {
+        let distance_meters = location
+          .haversine_distance_to(&an_attraction.location()?)
+          .meters();
+        Some(NearbyAttraction {
+          attraction: an_attraction,
+          distance_meters,
+        })
+      })
+      .collect::<Vec<NearbyAttraction>>();
+    nearby.sort_by(|one, other| {
+      one.distance_meters.total_cmp(&other.distance_meters)
+    });
+    nearby.truncate(limit);
+    Some(nearby)
   }
 
-  async fn get_attraction(&self, id: i32) -> Option<FullAttraction> {
-    match self.attraction_repository.get_attraction(id).await {
-      Ok(an_attraction) => Some(an_attraction),
-      Err(_) => None,
+  /// The attraction with the descriptions in the first of the locales with
+  /// a translation.
+  async fn get_attraction(
+    &self,
+    id: i32,
+    locales: Vec<String>,
+  ) -> Option<FullAttraction> {
+    self
+      .attraction_repository
+      .get_attraction(id, locales)
+      .await
+      .ok()
+  }
+
+  /// The description of every attraction in the first of the locales with a
+  /// translation. The attractions without any aren't in the map.
+  async fn translations_of(
+    &self,
+    ids: Vec<i32>,
+    locales: Vec<String>,
+  ) -> Option<HashMap<i32, Translation>> {
+    if ids.is_empty() || locales.is_empty() {
+      return Some(HashMap::new());
     }
+    let translations = self
+      .attraction_repository
+      .attraction_translations(ids.clone(), locales.clone())
+      .await
+      .ok()?;
+    Some(
+      ids
+        .into_iter()
+        .filter_map(|an_id| {
+          let of_attraction = translations
+            .iter()
+            .filter(|a_translation| a_translation.entity_id == an_id)
+            .cloned()
+            .collect::<Vec<Translation>>();
+          let best = best_of(&of_attraction, &locales)?.clone();
+          Some((an_id, best))
+        })
+        .collect(),
+    )
+  }
+
+  /// The attraction that the merged one became, none if it wasn't merged.
+  async fn redirect_of(&self, id: i32) -> Option<i32> {
+    self
+      .attraction_repository
+      .redirect_of(id)
+      .await
+      .ok()
+      .flatten()
+      .map(|an_id| an_id.id)
   }
 
   async fn ratings_for(
     &self,
     attraction_id: i32,
   ) -> Option<Vec<AttractionRating>> {
-    let Some(_) = self.get_attraction(attraction_id).await else {
-      return None;
+    self.get_attraction(attraction_id, vec![]).await?;
+    self
+      .attraction_repository
+      .ratings_for(attraction_id)
+      .await
+      .ok()
+  }
+
+  /// Review the rating with the rules engine and register it, accepted or
+  /// quarantined.
+  ///
+  /// # Return:
+  /// * Ok with None if the attraction doesn't exist.
+  /// * Err with a duplicate if the user already rated the attraction that
+  ///   day, or the failure if it can't be saved.
+  async fn rate(
+    &self,
+    rating: IncomingRating,
+  ) -> Result<Option<AttractionRating>, RatingFail> {
+    register_rating(&self.attraction_repository, &self.rules_engine, rating)
+      .await
+  }
+
+  async fn ratings_with_status(
+    &self,
+    status: RatingStatus,
+  ) -> Option<Vec<AttractionRating>> {
+    self
+      .attraction_repository
+      .ratings_with_status(status)
+      .await
+      .ok()
+  }
+
+  /// Change the status of a rating after a manual review, the aggregates
+  /// that include it are recomputed the next time.
+  async fn review_rating(
+    &self,
+    rating_id: i32,
+    status: RatingStatus,
+  ) -> Option<AttractionRating> {
+    self
+      .attraction_repository
+      .update_rating_status(rating_id, status)
+      .await
+      .ok()
+  }
+
+  async fn create(
+    &self,
+    attraction: NewAttraction,
+    actor: Actor,
+  ) -> Result<Attraction, String> {
+    let attraction = validate_attraction(attraction)?;
+    self
+      .attraction_repository
+      .create_attraction(attraction, &actor)
+      .await
+      .map_err(|e| reference_error(&e))
+  }
+
+  /// Returns None if the attraction doesn't exist or is deleted.
+  async fn update(
+    &self,
+    id: i32,
+    attraction: NewAttraction,
+    actor: Actor,
+  ) -> Result<Option<Attraction>, String> {
+    let attraction = validate_attraction(attraction)?;
+    match self
+      .attraction_repository
+      .update_attraction(id, attraction, &actor)
+      .await
+    {
+      Ok(an_attraction) => Ok(Some(an_attraction)),
+      Err(sqlx::Error::RowNotFound) => Ok(None),
+      Err(e) => Err(reference_error(&e)),
+    }
+  }
+
+  /// Returns false if the attraction doesn't exist or is already deleted.
+  async fn delete(&self, id: i32, actor: Actor) -> Result<bool, String> {
+    self
+      .attraction_repository
+      .delete_attraction(id, &actor)
+      .await
+      .map(|deleted| deleted > 0)
+      .map_err(|e| e.to_string())
+  }
+
+  async fn ratings_of_user(
+    &self,
+    user_id: i32,
+    attraction_id: Option<i32>,
+  ) -> Option<Vec<AttractionRating>> {
+    self
+      .attraction_repository
+      .ratings_of_user(user_id, attraction_id)
+      .await
+      .ok()
+  }
+
+  /// Change the rate of a rating of the user, keeping its status. Returns
+  /// None if the rating doesn't exist or belongs to another user.
+  async fn update_user_rating(
+    &self,
+    id: i32,
+    user_id: i32,
+    rate: BigDecimal,
+  ) -> Option<AttractionRating> {
+    self
+      .attraction_repository
+      .update_user_rating(id, user_id, rate, Utc::now().naive_utc())
+      .await
+      .ok()?
+  }
+
+  /// Returns false if the rating doesn't exist or belongs to another user.
+  async fn delete_user_rating(&self, id: i32, user_id: i32) -> Option<bool> {
+    self
+      .attraction_repository
+      .delete_user_rating(id, user_id)
+      .await
+      .ok()
+      .map(|deleted| deleted > 0)
+  }
+
+  /// Returns None if the attraction doesn't exist or has no opening hours.
+  async fn opening_hours(
+    &self,
+    id: i32,
+    at: Option<NaiveDateTime>,
+  ) -> Option<AttractionSchedule> {
+    self.attraction_repository.attraction_by_id(id).await.ok()?;
+    let opening_hours = self
+      .attraction_repository
+      .opening_hours_of(vec![id])
+      .await
+      .ok()?
+      .pop()?;
+    let hours = opening_hours
+      .get_opening_hours()
+      .parse::<OpeningHours>()
+      .ok()?;
+    let from = at.unwrap_or_else(|| Utc::now().naive_utc()).date();
+    let days = (0..7)
+      .map(|day| from + Duration::days(day))
+      .collect::<Vec<NaiveDate>>();
+    let holidays = self
+      .attraction_repository
+      .holidays_of(vec![id], from - Duration::days(1), days[days.len() - 1])
+      .await
+      .ok()?
+      .iter()
+      .map(|a_holiday| a_holiday.day)
+      .collect::<HashSet<NaiveDate>>();
+    Some(AttractionSchedule {
+      is_open: at.map(|at| hours.is_open(at, &holidays)),
+      week: days
+        .into_iter()
+        .map(|day| (day, hours.spans_on(day, holidays.contains(&day))))
+        .collect(),
+      opening_hours,
+    })
+  }
+
+  /// Set the opening hours of the attraction in the OSM syntax.
+  ///
+  /// # Return:
+  /// * Ok with None if the attraction doesn't exist.
+  /// * Err with the reason if the opening hours can't be parsed.
+  async fn set_opening_hours(
+    &self,
+    id: i32,
+    opening_hours: String,
+    actor: Actor,
+  ) -> Result<Option<AttractionOpeningHours>, String> {
+    let opening_hours = opening_hours.trim().to_string();
+    opening_hours.parse::<OpeningHours>()?;
+    if self
+      .attraction_repository
+      .attraction_by_id(id)
+      .await
+      .is_err()
+    {
+      return Ok(None);
+    }
+    match self
+      .attraction_repository
+      .save_opening_hours(id, opening_hours, Utc::now().naive_utc(), &actor)
+      .await
+    {
+      Ok(saved) => Ok(Some(saved)),
+      Err(e) if is_foreign_key_violation(&e) => Ok(None),
+      Err(e) => Err(e.to_string()),
+    }
+  }
+
+  /// Returns false if the attraction has no opening hours.
+  async fn delete_opening_hours(&self, id: i32, actor: Actor) -> Option<bool> {
+    self
+      .attraction_repository
+      .delete_opening_hours(id, &actor)
+      .await
+      .ok()
+      .map(|deleted| deleted > 0)
+  }
+
+  /// Returns None if the attraction doesn't exist.
+  async fn tags_of(&self, id: i32) -> Option<Vec<Tag>> {
+    self.attraction_repository.attraction_by_id(id).await.ok()?;
+    self.attraction_repository.tags_of(id).await.ok()
+  }
+
+  /// Replace the tags of the attraction.
+  ///
+  /// # Return:
+  /// * Ok with the new tags, or None if the attraction doesn't exist.
+  /// * Err with the reason if a tag doesn't exist.
+  async fn set_tags(
+    &self,
+    id: i32,
+    codes: Vec<String>,
+    actor: Actor,
+  ) -> Result<Option<Vec<Tag>>, String> {
+    if self
+      .attraction_repository
+      .attraction_by_id(id)
+      .await
+      .is_err()
+    {
+      return Ok(None);
+    }
+    let codes = codes
+      .into_iter()
+      .map(tag_code_of)
+      .collect::<Result<HashSet<String>, String>>()?
+      .into_iter()
+      .collect::<Vec<String>>();
+    let tags = self
+      .attraction_repository
+      .tags_by_codes(codes.clone())
+      .await
+      .map_err(|e| e.to_string())?;
+    let mut missing = codes
+      .iter()
+      .filter(|code| !tags.iter().any(|a_tag| a_tag.get_code() == **code))
+      .cloned()
+      .collect::<Vec<String>>();
+    if !missing.is_empty() {
+      missing.sort();
+      return Err(format!("The tags {} don't exist", missing.join(", ")));
+    }
+    match self
+      .attraction_repository
+      .replace_tags(
+        id,
+        tags.iter().map(|a_tag| a_tag.get_id()).collect(),
+        &actor,
+      )
+      .await
+    {
+      Ok(()) => Ok(Some(tags)),
+      Err(e) if is_foreign_key_violation(&e) => Ok(None),
+      Err(e) => Err(e.to_string()),
+    }
+  }
+
+  /// Returns None if the attraction doesn't exist or has no attributes.
+  async fn attributes_of(&self, id: i32) -> Option<AttractionAttributes> {
+    self.attraction_repository.attraction_by_id(id).await.ok()?;
+    self.attraction_repository.attributes_of(id).await.ok()?
+  }
+
+  /// Replace the attributes of the attraction.
+  ///
+  /// # Return:
+  /// * Ok with the new attributes, or None if the attraction doesn't exist.
+  /// * Err with the reason if the duration of the visit isn't positive.
+  async fn set_attributes(
+    &self,
+    id: i32,
+    price_range: Option<PriceRange>,
+    visit_minutes: Option<i32>,
+    actor: Actor,
+  ) -> Result<Option<AttractionAttributes>, String> {
+    if visit_minutes.is_some_and(|minutes| minutes <= 0) {
+      return Err(String::from("The minutes of the visit must be positive"));
+    }
+    if self
+      .attraction_repository
+      .attraction_by_id(id)
+      .await
+      .is_err()
+    {
+      return Ok(None);
+    }
+    let attributes = AttractionAttributes {
+      attraction_id: id,
+      price_range: price_range.map(|a_range| a_range.to_string()),
+      visit_minutes,
+      updated_at: Utc::now().naive_utc(),
     };
-    match self.attraction_repository.ratings_for(attraction_id).await {
-      Ok(ratings) => Some(ratings),
-      Err(_) => None,
+    match self
+      .attraction_repository
+      .save_attributes(attributes, &actor)
+      .await
+    {
+      Ok(saved) => Ok(Some(saved)),
+      Err(e) if is_foreign_key_violation(&e) => Ok(None),
+      Err(e) => Err(e.to_string()),
+    }
+  }
+
+  /// Count the attractions by every value of the facets, among the ones of
+  /// the city, or of every city, with all the tags.
+  async fn facets(
+    &self,
+    city_id: Option<i32>,
+    tags: Vec<String>,
+  ) -> Option<Vec<FacetCount>> {
+    self
+      .attraction_repository
+      .facets(city_id, codes_of(&tags))
+      .await
+      .ok()
+  }
+}
+
+/// The distinct codes of the tags, in lower case.
+fn codes_of(tags: &[String]) -> Vec<String> {
+  let mut codes = tags
+    .iter()
+    .map(|a_tag| a_tag.trim().to_lowercase())
+    .filter(|a_tag| !a_tag.is_empty())
+    .collect::<Vec<String>>();
+  codes.sort();
+  codes.dedup();
+  codes
+}
+
+fn reference_error(e: &sqlx::Error) -> String {
+  if is_foreign_key_violation(e) {
+    String::from("The city or the type of the attraction doesn't exist")
+  } else {
+    e.to_string()
+  }
+}
+
+/// Check the values of an attraction, trimming them.
+///
+/// # Return:
+/// * Ok with the trimmed values, the blank coordinates are None.
+/// * Err with a string with the reason if the description is empty or the
+///   coordinates are incomplete or out of range.
+fn validate_attraction(
+  attraction: NewAttraction,
+) -> Result<NewAttraction, String> {
+  let blank_as_none = |value: Option<String>| {
+    value
+      .map(|value| value.trim().to_string())
+      .filter(|value| !value.is_empty())
+  };
+  let attraction = NewAttraction {
+    description: attraction.description.trim().to_string(),
+    latitude: blank_as_none(attraction.latitude),
+    longitude: blank_as_none(attraction.longitude),
+    ..attraction
+  };
+  if attraction.description.is_empty() {
+    return Err(String::from("The description can't be empty"));
+  }
+  match (&attraction.latitude, &attraction.longitude) {
+    (None, None) => {},
+    (Some(_), Some(_)) => {
+      let in_range = location_of(&attraction.latitude, &attraction.longitude)
+        .is_some_and(|location| {
+          (-90.0..=90.0).contains(&location.latitude())
+            && (-180.0..=180.0).contains(&location.longitude())
+        });
+      if !in_range {
+        return Err(String::from(
+          "The latitude must be between -90 and 90 and the longitude between \
+           -180 and 180",
+        ));
+      }
+    },
+    _ => {
+      return Err(String::from(
+        "The latitude and the longitude must be given together",
+      ))
+    },
+  }
+  Ok(attraction)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::{
+    attraction::{
+      AttractionByDate, AttractionHoliday, AttractionRatingSummary,
+      RecentRating,
+    },
+    attraction_repository::EntityId,
+    duplicate::{AttractionMerge, DuplicateDismissal},
+    granularity::Granularity,
+    search::SearchCandidate,
+  };
+  use sqlx::error::{DatabaseError, ErrorKind};
+  use std::{error::Error, sync::Mutex};
+
+  /// The error of the database when a constraint is violated.
+  #[derive(Debug)]
+  struct Violation(ErrorKind);
+
+  impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      write!(f, "{:?}", self.0)
+    }
+  }
+
+  impl Error for Violation {}
+
+  impl DatabaseError for Violation {
+    fn message(&self) -> &str {
+      "violation"
+    }
+
+    fn as_error(&self) -> &(dyn Error + Send + Sync + 'static) {
+      self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn Error + Send + Sync + 'static) {
+      self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static> {
+      self
+    }
+
+    fn kind(&self) -> ErrorKind {
+      match self.0 {
+        ErrorKind::UniqueViolation => ErrorKind::UniqueViolation,
+        _ => ErrorKind::Other,
+      }
+    }
+  }
+
+  fn a_rating() -> AttractionRating {
+    AttractionRating {
+      id: 0,
+      at: NaiveDate::from_ymd_opt(2024, 3, 1)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap(),
+      attraction_id: 1,
+      rate: BigDecimal::from(1),
+      updated_at: NaiveDateTime::default(),
+      source: String::from("API"),
+      submitter: Some(String::from("ana")),
+      fingerprint: None,
+      status: RatingStatus::Accepted.to_string(),
+      fraud_score: BigDecimal::default(),
+      fraud_reasons: vec![],
+      user_id: Some(1),
+    }
+  }
+
+  #[test]
+  fn a_second_rating_on_the_same_day_is_a_duplicate() {
+    let e =
+      sqlx::Error::Database(Box::new(Violation(ErrorKind::UniqueViolation)));
+    assert!(matches!(
+      rating_fail(&e, &a_rating()),
+      RatingFail::Duplicate(_)
+    ));
+  }
+
+  #[test]
+  fn any_other_error_is_a_failure_of_the_service() {
+    for e in [
+      sqlx::Error::Database(Box::new(Violation(ErrorKind::CheckViolation))),
+      sqlx::Error::PoolTimedOut,
+    ] {
+      assert!(matches!(
+        rating_fail(&e, &a_rating()),
+        RatingFail::Service(_)
+      ));
+    }
+  }
+
+  /// The attractions 1, 2 and 3 with their tags and attributes in memory,
+  /// among the tags of the catalogue. It records the codes the facets are
+  /// counted for.
+  struct Catalogue {
+    tags: Vec<Tag>,
+    attraction_tags: Mutex<Vec<(i32, i32)>>,
+    attributes: Mutex<Vec<AttractionAttributes>>,
+    facets_asked: Mutex<Vec<Vec<String>>>,
+  }
+
+  impl Default for Catalogue {
+    fn default() -> Self {
+      let tags = ["free", "indoor", "family-friendly"]
+        .iter()
+        .enumerate()
+        .map(|(index, code)| Tag {
+          id: index as i32 + 1,
+          code: code.to_string(),
+          description: code.to_string(),
+          deleted_at: None,
+        })
+        .collect();
+      Catalogue {
+        tags,
+        attraction_tags: Mutex::new(Vec::new()),
+        attributes: Mutex::new(Vec::new()),
+        facets_asked: Mutex::new(Vec::new()),
+      }
+    }
+  }
+
+  const ATTRACTION_IDS: [i32; 3] = [1, 2, 3];
+
+  fn an_attraction(id: i32) -> Attraction {
+    Attraction {
+      id,
+      description: format!("Attraction {id}"),
+      city_id: 1,
+      latitude: None,
+      longitude: None,
+      attraction_type_id: 1,
+      deleted_at: None,
+    }
+  }
+
+  #[async_trait]
+  impl AttractionRepository for Catalogue {
+    async fn list(&self) -> sqlx::Result<Vec<Attraction>> {
+      Ok(ATTRACTION_IDS.into_iter().map(an_attraction).collect())
+    }
+
+    async fn get_attraction(
+      &self,
+      _: i32,
+      _: Vec<String>,
+    ) -> sqlx::Result<FullAttraction> {
+      todo!()
+    }
+
+    async fn attraction_by_id(&self, id: i32) -> sqlx::Result<Attraction> {
+      match ATTRACTION_IDS.contains(&id) {
+        true => Ok(an_attraction(id)),
+        false => Err(sqlx::Error::RowNotFound),
+      }
+    }
+
+    async fn attractions_by_ids(
+      &self,
+      _: Vec<i32>,
+    ) -> sqlx::Result<Vec<Attraction>> {
+      todo!()
+    }
+
+    async fn ratings_for(&self, _: i32) -> sqlx::Result<Vec<AttractionRating>> {
+      todo!()
+    }
+
+    async fn all_attractions_ids(&self) -> sqlx::Result<Vec<EntityId>> {
+      todo!()
+    }
+
+    async fn sorted_ratings_between(
+      &self,
+      _: i32,
+      _: NaiveDateTime,
+      _: NaiveDateTime,
+    ) -> sqlx::Result<Vec<AttractionRating>> {
+      todo!()
+    }
+
+    async fn ratings_changed_since(
+      &self,
+      _: i32,
+      _: NaiveDateTime,
+      _: NaiveDateTime,
+      _: i32,
+      _: Option<NaiveDateTime>,
+    ) -> sqlx::Result<Vec<AttractionRating>> {
+      todo!()
+    }
+
+    async fn group_ratings_by(
+      &self,
+      _: i32,
+      _: Granularity,
+    ) -> sqlx::Result<Vec<AttractionByDate>> {
+      todo!()
+    }
+
+    async fn recent_ratings(
+      &self,
+      _: i32,
+      _: Option<String>,
+      _: Option<String>,
+      _: NaiveDateTime,
+      _: NaiveDateTime,
+    ) -> sqlx::Result<Vec<RecentRating>> {
+      todo!()
+    }
+
+    async fn save_rating(&self, _: AttractionRating) -> sqlx::Result<EntityId> {
+      todo!()
+    }
+
+    async fn ratings_with_status(
+      &self,
+      _: RatingStatus,
+    ) -> sqlx::Result<Vec<AttractionRating>> {
+      todo!()
+    }
+
+    async fn update_rating_status(
+      &self,
+      _: i32,
+      _: RatingStatus,
+    ) -> sqlx::Result<AttractionRating> {
+      todo!()
+    }
+
+    async fn rating_summaries(
+      &self,
+      _: Option<NaiveDateTime>,
+      _: NaiveDateTime,
+      _: f64,
+    ) -> sqlx::Result<Vec<AttractionRatingSummary>> {
+      todo!()
+    }
+
+    async fn create_attraction(
+      &self,
+      _: NewAttraction,
+      _: &Actor,
+    ) -> sqlx::Result<Attraction> {
+      todo!()
+    }
+
+    async fn update_attraction(
+      &self,
+      _: i32,
+      _: NewAttraction,
+      _: &Actor,
+    ) -> sqlx::Result<Attraction> {
+      todo!()
+    }
+
+    async fn delete_attraction(&self, _: i32, _: &Actor) -> sqlx::Result<u64> {
+      todo!()
+    }
+
+    async fn ratings_of_user(
+      &self,
+      _: i32,
+      _: Option<i32>,
+    ) -> sqlx::Result<Vec<AttractionRating>> {
+      todo!()
+    }
+
+    async fn update_user_rating(
+      &self,
+      _: i32,
+      _: i32,
+      _: BigDecimal,
+      _: NaiveDateTime,
+    ) -> sqlx::Result<Option<AttractionRating>> {
+      todo!()
+    }
+
+    async fn delete_user_rating(&self, _: i32, _: i32) -> sqlx::Result<u64> {
+      todo!()
+    }
+
+    async fn attractions_with_location(&self) -> sqlx::Result<Vec<Attraction>> {
+      todo!()
+    }
+
+    async fn attractions_with_opening_hours(
+      &self,
+    ) -> sqlx::Result<Vec<Attraction>> {
+      todo!()
+    }
+
+    async fn opening_hours_of(
+      &self,
+      _: Vec<i32>,
+    ) -> sqlx::Result<Vec<AttractionOpeningHours>> {
+      todo!()
+    }
+
+    async fn save_opening_hours(
+      &self,
+      _: i32,
+      _: String,
+      _: NaiveDateTime,
+      _: &Actor,
+    ) -> sqlx::Result<AttractionOpeningHours> {
+      todo!()
+    }
+
+    async fn delete_opening_hours(
+      &self,
+      _: i32,
+      _: &Actor,
+    ) -> sqlx::Result<u64> {
+      todo!()
+    }
+
+    async fn holidays_of(
+      &self,
+      _: Vec<i32>,
+      _: NaiveDate,
+      _: NaiveDate,
+    ) -> sqlx::Result<Vec<AttractionHoliday>> {
+      todo!()
+    }
+
+    async fn tags_of(&self, attraction_id: i32) -> sqlx::Result<Vec<Tag>> {
+      let attraction_tags = self.attraction_tags.lock().unwrap();
+      Ok(
+        self
+          .tags
+          .iter()
+          .filter(|a_tag| attraction_tags.contains(&(attraction_id, a_tag.id)))
+          .cloned()
+          .collect(),
+      )
+    }
+
+    async fn tags_by_codes(
+      &self,
+      codes: Vec<String>,
+    ) -> sqlx::Result<Vec<Tag>> {
+      Ok(
+        self
+          .tags
+          .iter()
+          .filter(|a_tag| codes.contains(&a_tag.code))
+          .cloned()
+          .collect(),
+      )
+    }
+
+    async fn replace_tags(
+      &self,
+      attraction_id: i32,
+      tag_ids: Vec<i32>,
+      _: &Actor,
+    ) -> sqlx::Result<()> {
+      let mut attraction_tags = self.attraction_tags.lock().unwrap();
+      attraction_tags.retain(|(id, _)| *id != attraction_id);
+      attraction_tags
+        .extend(tag_ids.into_iter().map(|tag_id| (attraction_id, tag_id)));
+      Ok(())
+    }
+
+    async fn attractions_tagged(
+      &self,
+      codes: Vec<String>,
+    ) -> sqlx::Result<Vec<Attraction>> {
+      let mut tagged = vec![];
+      for id in ATTRACTION_IDS {
+        let tags = self.tags_of(id).await?;
+        if codes
+          .iter()
+          .all(|code| tags.iter().any(|a_tag| a_tag.code == *code))
+        {
+          tagged.push(an_attraction(id));
+        }
+      }
+      Ok(tagged)
+    }
+
+    async fn attributes_of(
+      &self,
+      attraction_id: i32,
+    ) -> sqlx::Result<Option<AttractionAttributes>> {
+      let attributes = self.attributes.lock().unwrap();
+      Ok(
+        attributes
+          .iter()
+          .find(|some_attributes| {
+            some_attributes.attraction_id == attraction_id
+          })
+          .cloned(),
+      )
+    }
+
+    async fn save_attributes(
+      &self,
+      attributes: AttractionAttributes,
+      _: &Actor,
+    ) -> sqlx::Result<AttractionAttributes> {
+      let mut saved = self.attributes.lock().unwrap();
+      saved.retain(|some_attributes| {
+        some_attributes.attraction_id != attributes.attraction_id
+      });
+      saved.push(attributes.clone());
+      Ok(attributes)
+    }
+
+    async fn facets(
+      &self,
+      _: Option<i32>,
+      codes: Vec<String>,
+    ) -> sqlx::Result<Vec<FacetCount>> {
+      self.facets_asked.lock().unwrap().push(codes);
+      Ok(vec![])
+    }
+
+    async fn search_candidates(
+      &self,
+      _: Vec<String>,
+      _: Option<i32>,
+      _: Option<i32>,
+      _: i64,
+    ) -> sqlx::Result<Vec<SearchCandidate>> {
+      todo!()
+    }
+
+    async fn attraction_translations(
+      &self,
+      _: Vec<i32>,
+      _: Vec<String>,
+    ) -> sqlx::Result<Vec<Translation>> {
+      todo!()
     }
+
+    async fn attractions_in(
+      &self,
+      _: Option<i32>,
+    ) -> sqlx::Result<Vec<Attraction>> {
+      todo!()
+    }
+
+    async fn dismissed_duplicates(
+      &self,
+    ) -> sqlx::Result<Vec<DuplicateDismissal>> {
+      todo!()
+    }
+
+    async fn dismiss_duplicate(&self, _: i32, _: i32) -> sqlx::Result<u64> {
+      todo!()
+    }
+
+    async fn merge_attractions(
+      &self,
+      _: i32,
+      _: i32,
+      _: &Actor,
+    ) -> sqlx::Result<AttractionMerge> {
+      todo!()
+    }
+
+    async fn redirect_of(&self, _: i32) -> sqlx::Result<Option<EntityId>> {
+      todo!()
+    }
+  }
+
+  fn controller() -> AttractionControllerImpl<Catalogue> {
+    AttractionControllerImpl::new(
+      Catalogue::default(),
+      RatingRulesSettings::default(),
+    )
+  }
+
+  fn an_editor() -> Actor {
+    Actor {
+      id: 1,
+      username: String::from("ana"),
+    }
+  }
+
+  fn codes(tags: &[&str]) -> Vec<String> {
+    tags.iter().map(|a_tag| a_tag.to_string()).collect()
+  }
+
+  fn codes_of_tags(tags: Vec<Tag>) -> Vec<String> {
+    let mut codes = tags
+      .iter()
+      .map(|a_tag| a_tag.get_code())
+      .collect::<Vec<String>>();
+    codes.sort();
+    codes
+  }
+
+  #[tokio::test]
+  async fn the_tags_of_an_attraction_are_replaced() {
+    let controller = controller();
+    let tagged = controller
+      .set_tags(1, codes(&[" Free ", "free", "INDOOR"]), an_editor())
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(codes(&["free", "indoor"]), codes_of_tags(tagged));
+
+    controller
+      .set_tags(1, codes(&["family-friendly"]), an_editor())
+      .await
+      .unwrap();
+    let tags = controller.tags_of(1).await.unwrap();
+    assert_eq!(codes(&["family-friendly"]), codes_of_tags(tags));
+  }
+
+  #[tokio::test]
+  async fn only_the_existing_tags_are_set() {
+    let controller = controller();
+    let unknown = controller
+      .set_tags(1, codes(&["free", "nightlife", "beach"]), an_editor())
+      .await;
+    assert_eq!(
+      Err(String::from("The tags beach, nightlife don't exist")),
+      unknown.map(|_| ())
+    );
+    assert!(controller
+      .set_tags(1, codes(&["no spaces"]), an_editor())
+      .await
+      .is_err());
+    assert!(controller
+      .set_tags(4, codes(&["free"]), an_editor())
+      .await
+      .unwrap()
+      .is_none());
+    assert!(controller.tags_of(1).await.unwrap().is_empty());
+  }
+
+  #[tokio::test]
+  async fn the_attributes_of_an_attraction_are_replaced() {
+    let controller = controller();
+    let saved = controller
+      .set_attributes(1, Some(PriceRange::Low), Some(90), an_editor())
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(Some(PriceRange::Low), saved.get_price_range());
+
+    controller
+      .set_attributes(1, None, Some(30), an_editor())
+      .await
+      .unwrap();
+    let attributes = controller.attributes_of(1).await.unwrap();
+    assert_eq!(None, attributes.get_price_range());
+    assert_eq!(Some(30), attributes.visit_minutes);
+  }
+
+  #[tokio::test]
+  async fn the_visit_must_last_and_the_attraction_exist() {
+    let controller = controller();
+    assert!(controller
+      .set_attributes(1, None, Some(0), an_editor())
+      .await
+      .is_err());
+    assert!(controller
+      .set_attributes(4, Some(PriceRange::Free), None, an_editor())
+      .await
+      .unwrap()
+      .is_none());
+    assert!(controller.attributes_of(1).await.is_none());
+  }
+
+  #[tokio::test]
+  async fn the_facets_are_counted_for_the_distinct_codes() {
+    let catalogue = Catalogue::default();
+    let controller =
+      AttractionControllerImpl::new(catalogue, RatingRulesSettings::default());
+    controller
+      .facets(None, codes(&[" Free", "free", "", "INDOOR"]))
+      .await
+      .unwrap();
+    let asked = controller
+      .attraction_repository
+      .facets_asked
+      .lock()
+      .unwrap();
+    assert_eq!(vec![codes(&["free", "indoor"])], *asked);
+  }
+
+  #[tokio::test]
+  async fn the_listed_attractions_have_all_the_tags() {
+    let controller = controller();
+    controller
+      .set_tags(1, codes(&["free", "indoor"]), an_editor())
+      .await
+      .unwrap();
+    controller
+      .set_tags(2, codes(&["free"]), an_editor())
+      .await
+      .unwrap();
+    let listed_with = |tags: &[&str]| {
+      let filter = AttractionFilter {
+        open_at: None,
+        tags: codes(tags),
+      };
+      async {
+        controller
+          .list(filter)
+          .await
+          .unwrap()
+          .iter()
+          .map(|an_attraction| an_attraction.get_id())
+          .collect::<Vec<i32>>()
+      }
+    };
+    assert_eq!(vec![1, 2], listed_with(&["free"]).await);
+    assert_eq!(vec![1], listed_with(&["FREE", "indoor"]).await);
+    assert_eq!(vec![1, 2, 3], listed_with(&[]).await);
+    assert!(listed_with(&["family-friendly"]).await.is_empty());
   }
 }
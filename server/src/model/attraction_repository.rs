@@ -1,10 +1,24 @@
-use super::attraction::{Attraction, AttractionRating};
+use super::attraction::{Attraction, AttractionRating, Tag};
 use crate::{
   db::database::DbConnection,
-  model::attraction::{AttractionByDate, FullAttraction},
+  model::{
+    attraction::{
+      AttractionAttributes, AttractionByDate, AttractionHoliday,
+      AttractionOpeningHours, AttractionRatingSummary, FacetCount,
+      FullAttraction, NewAttraction, RecentRating,
+    },
+    audit::Actor,
+    audit_repository::act_as,
+    duplicate::{AttractionMerge, DuplicateDismissal},
+    granularity::Granularity,
+    rating_rules::RatingStatus,
+    search::SearchCandidate,
+    translation::{languages_of, Translation},
+  },
 };
 use async_trait::async_trait;
-use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use bigdecimal::BigDecimal;
+use chrono::{NaiveDate, NaiveDateTime};
 use std::{fmt, fmt::Formatter};
 
 #[derive(Debug, Clone, Copy)]
@@ -21,21 +35,185 @@ impl fmt::Display for EntityId {
 #[async_trait]
 pub trait AttractionRepository {
   async fn list(&self) -> sqlx::Result<Vec<Attraction>>;
-  async fn get_attraction(&self, id: i32) -> sqlx::Result<FullAttraction>;
+  async fn get_attraction(
+    &self,
+    id: i32,
+    locales: Vec<String>,
+  ) -> sqlx::Result<FullAttraction>;
+  async fn attraction_by_id(&self, id: i32) -> sqlx::Result<Attraction>;
+  async fn attractions_by_ids(
+    &self,
+    ids: Vec<i32>,
+  ) -> sqlx::Result<Vec<Attraction>>;
   async fn ratings_for(
     &self,
     attraction_id: i32,
   ) -> sqlx::Result<Vec<AttractionRating>>;
   async fn all_attractions_ids(&self) -> sqlx::Result<Vec<EntityId>>;
-  async fn sorted_ratings_for(
+  async fn sorted_ratings_between(
     &self,
     attraction_id: i32,
-    from: NaiveDate,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
   ) -> sqlx::Result<Vec<AttractionRating>>;
-  async fn group_ratings_by_date(
+  async fn ratings_changed_since(
     &self,
     attraction_id: i32,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    last_rating_id: i32,
+    watermark: Option<NaiveDateTime>,
+  ) -> sqlx::Result<Vec<AttractionRating>>;
+  async fn group_ratings_by(
+    &self,
+    attraction_id: i32,
+    granularity: Granularity,
   ) -> sqlx::Result<Vec<AttractionByDate>>;
+  async fn recent_ratings(
+    &self,
+    attraction_id: i32,
+    submitter: Option<String>,
+    fingerprint: Option<String>,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+  ) -> sqlx::Result<Vec<RecentRating>>;
+  async fn save_rating(
+    &self,
+    rating: AttractionRating,
+  ) -> sqlx::Result<EntityId>;
+  async fn ratings_with_status(
+    &self,
+    status: RatingStatus,
+  ) -> sqlx::Result<Vec<AttractionRating>>;
+  async fn update_rating_status(
+    &self,
+    rating_id: i32,
+    status: RatingStatus,
+  ) -> sqlx::Result<AttractionRating>;
+  async fn rating_summaries(
+    &self,
+    since: Option<NaiveDateTime>,
+    now: NaiveDateTime,
+    half_life_days: f64,
+  ) -> sqlx::Result<Vec<AttractionRatingSummary>>;
+  async fn create_attraction(
+    &self,
+    attraction: NewAttraction,
+    actor: &Actor,
+  ) -> sqlx::Result<Attraction>;
+  async fn update_attraction(
+    &self,
+    id: i32,
+    attraction: NewAttraction,
+    actor: &Actor,
+  ) -> sqlx::Result<Attraction>;
+  async fn delete_attraction(
+    &self,
+    id: i32,
+    actor: &Actor,
+  ) -> sqlx::Result<u64>;
+  async fn ratings_of_user(
+    &self,
+    user_id: i32,
+    attraction_id: Option<i32>,
+  ) -> sqlx::Result<Vec<AttractionRating>>;
+  async fn update_user_rating(
+    &self,
+    id: i32,
+    user_id: i32,
+    rate: BigDecimal,
+    now: NaiveDateTime,
+  ) -> sqlx::Result<Option<AttractionRating>>;
+  async fn delete_user_rating(
+    &self,
+    id: i32,
+    user_id: i32,
+  ) -> sqlx::Result<u64>;
+  async fn attractions_with_location(&self) -> sqlx::Result<Vec<Attraction>>;
+  async fn attractions_with_opening_hours(
+    &self,
+  ) -> sqlx::Result<Vec<Attraction>>;
+  async fn opening_hours_of(
+    &self,
+    attraction_ids: Vec<i32>,
+  ) -> sqlx::Result<Vec<AttractionOpeningHours>>;
+  async fn save_opening_hours(
+    &self,
+    attraction_id: i32,
+    opening_hours: String,
+    now: NaiveDateTime,
+    actor: &Actor,
+  ) -> sqlx::Result<AttractionOpeningHours>;
+  async fn delete_opening_hours(
+    &self,
+    attraction_id: i32,
+    actor: &Actor,
+  ) -> sqlx::Result<u64>;
+  async fn holidays_of(
+    &self,
+    attraction_ids: Vec<i32>,
+    from: NaiveDate,
+    to: NaiveDate,
+  ) -> sqlx::Result<Vec<AttractionHoliday>>;
+  async fn tags_of(&self, attraction_id: i32) -> sqlx::Result<Vec<Tag>>;
+  async fn tags_by_codes(&self, codes: Vec<String>) -> sqlx::Result<Vec<Tag>>;
+  async fn replace_tags(
+    &self,
+    attraction_id: i32,
+    tag_ids: Vec<i32>,
+    actor: &Actor,
+  ) -> sqlx::Result<()>;
+  async fn attractions_tagged(
+    &self,
+    codes: Vec<String>,
+  ) -> sqlx::Result<Vec<Attraction>>;
+  async fn attributes_of(
+    &self,
+    attraction_id: i32,
+  ) -> sqlx::Result<Option<AttractionAttributes>>;
+  async fn save_attributes(
+    &self,
+    attributes: AttractionAttributes,
+    actor: &Actor,
+  ) -> sqlx::Result<AttractionAttributes>;
+  async fn facets(
+    &self,
+    city_id: Option<i32>,
+    codes: Vec<String>,
+  ) -> sqlx::Result<Vec<FacetCount>>;
+  async fn search_candidates(
+    &self,
+    words: Vec<String>,
+    city_id: Option<i32>,
+    attraction_type_id: Option<i32>,
+    limit: i64,
+  ) -> sqlx::Result<Vec<SearchCandidate>>;
+  async fn attraction_translations(
+    &self,
+    attraction_ids: Vec<i32>,
+    locales: Vec<String>,
+  ) -> sqlx::Result<Vec<Translation>>;
+  async fn attractions_in(
+    &self,
+    city_id: Option<i32>,
+  ) -> sqlx::Result<Vec<Attraction>>;
+  async fn dismissed_duplicates(&self)
+    -> sqlx::Result<Vec<DuplicateDismissal>>;
+  async fn dismiss_duplicate(
+    &self,
+    attraction_id: i32,
+    other_attraction_id: i32,
+  ) -> sqlx::Result<u64>;
+  async fn merge_attractions(
+    &self,
+    attraction_id: i32,
+    merged_attraction_id: i32,
+    actor: &Actor,
+  ) -> sqlx::Result<AttractionMerge>;
+  async fn redirect_of(
+    &self,
+    attraction_id: i32,
+  ) -> sqlx::Result<Option<EntityId>>;
 }
 
 #[derive(Clone, Default)]
@@ -47,7 +225,22 @@ impl AttractionRepository for DummyAttractionRepo {
     todo!()
   }
 
-  async fn get_attraction(&self, _: i32) -> sqlx::Result<FullAttraction> {
+  async fn get_attraction(
+    &self,
+    _: i32,
+    _: Vec<String>,
+  ) -> sqlx::Result<FullAttraction> {
+    todo!()
+  }
+
+  async fn attraction_by_id(&self, _: i32) -> sqlx::Result<Attraction> {
+    todo!()
+  }
+
+  async fn attractions_by_ids(
+    &self,
+    _: Vec<i32>,
+  ) -> sqlx::Result<Vec<Attraction>> {
     todo!()
   }
 
@@ -59,20 +252,250 @@ impl AttractionRepository for DummyAttractionRepo {
     todo!()
   }
 
-  async fn sorted_ratings_for(
+  async fn sorted_ratings_between(
     &self,
     _: i32,
-    _: NaiveDate,
+    _: NaiveDateTime,
+    _: NaiveDateTime,
+  ) -> sqlx::Result<Vec<AttractionRating>> {
+    todo!()
+  }
+
+  async fn ratings_changed_since(
+    &self,
+    _: i32,
+    _: NaiveDateTime,
+    _: NaiveDateTime,
+    _: i32,
+    _: Option<NaiveDateTime>,
   ) -> sqlx::Result<Vec<AttractionRating>> {
     todo!()
   }
 
-  async fn group_ratings_by_date(
+  async fn group_ratings_by(
     &self,
     _: i32,
+    _: Granularity,
   ) -> sqlx::Result<Vec<AttractionByDate>> {
     todo!()
   }
+
+  async fn recent_ratings(
+    &self,
+    _: i32,
+    _: Option<String>,
+    _: Option<String>,
+    _: NaiveDateTime,
+    _: NaiveDateTime,
+  ) -> sqlx::Result<Vec<RecentRating>> {
+    todo!()
+  }
+
+  async fn save_rating(&self, _: AttractionRating) -> sqlx::Result<EntityId> {
+    todo!()
+  }
+
+  async fn ratings_with_status(
+    &self,
+    _: RatingStatus,
+  ) -> sqlx::Result<Vec<AttractionRating>> {
+    todo!()
+  }
+
+  async fn update_rating_status(
+    &self,
+    _: i32,
+    _: RatingStatus,
+  ) -> sqlx::Result<AttractionRating> {
+    todo!()
+  }
+
+  async fn rating_summaries(
+    &self,
+    _: Option<NaiveDateTime>,
+    _: NaiveDateTime,
+    _: f64,
+  ) -> sqlx::Result<Vec<AttractionRatingSummary>> {
+    todo!()
+  }
+
+  async fn create_attraction(
+    &self,
+    _: NewAttraction,
+    _: &Actor,
+  ) -> sqlx::Result<Attraction> {
+    todo!()
+  }
+
+  async fn update_attraction(
+    &self,
+    _: i32,
+    _: NewAttraction,
+    _: &Actor,
+  ) -> sqlx::Result<Attraction> {
+    todo!()
+  }
+
+  async fn delete_attraction(&self, _: i32, _: &Actor) -> sqlx::Result<u64> {
+    todo!()
+  }
+
+  async fn ratings_of_user(
+    &self,
+    _: i32,
+    _: Option<i32>,
+  ) -> sqlx::Result<Vec<AttractionRating>> {
+    todo!()
+  }
+
+  async fn update_user_rating(
+    &self,
+    _: i32,
+    _: i32,
+    _: BigDecimal,
+    _: NaiveDateTime,
+  ) -> sqlx::Result<Option<AttractionRating>> {
+    todo!()
+  }
+
+  async fn delete_user_rating(&self, _: i32, _: i32) -> sqlx::Result<u64> {
+    todo!()
+  }
+
+  async fn attractions_with_location(&self) -> sqlx::Result<Vec<Attraction>> {
+    todo!()
+  }
+
+  async fn attractions_with_opening_hours(
+    &self,
+  ) -> sqlx::Result<Vec<Attraction>> {
+    todo!()
+  }
+
+  async fn opening_hours_of(
+    &self,
+    _: Vec<i32>,
+  ) -> sqlx::Result<Vec<AttractionOpeningHours>> {
+    todo!()
+  }
+
+  async fn save_opening_hours(
+    &self,
+    _: i32,
+    _: String,
+    _: NaiveDateTime,
+    _: &Actor,
+  ) -> sqlx::Result<AttractionOpeningHours> {
+    todo!()
+  }
+
+  async fn delete_opening_hours(&self, _: i32, _: &Actor) -> sqlx::Result<u64> {
+    todo!()
+  }
+
+  async fn holidays_of(
+    &self,
+    _: Vec<i32>,
+    _: NaiveDate,
+    _: NaiveDate,
+  ) -> sqlx::Result<Vec<AttractionHoliday>> {
+    todo!()
+  }
+
+  async fn tags_of(&self, _: i32) -> sqlx::Result<Vec<Tag>> {
+    todo!()
+  }
+
+  async fn tags_by_codes(&self, _: Vec<String>) -> sqlx::Result<Vec<Tag>> {
+    todo!()
+  }
+
+  async fn replace_tags(
+    &self,
+    _: i32,
+    _: Vec<i32>,
+    _: &Actor,
+  ) -> sqlx::Result<()> {
+    todo!()
+  }
+
+  async fn attractions_tagged(
+    &self,
+    _: Vec<String>,
+  ) -> sqlx::Result<Vec<Attraction>> {
+    todo!()
+  }
+
+  async fn attributes_of(
+    &self,
+    _: i32,
+  ) -> sqlx::Result<Option<AttractionAttributes>> {
+    todo!()
+  }
+
+  async fn save_attributes(
+    &self,
+    _: AttractionAttributes,
+    _: &Actor,
+  ) -> sqlx::Result<AttractionAttributes> {
+    todo!()
+  }
+
+  async fn facets(
+    &self,
+    _: Option<i32>,
+    _: Vec<String>,
+  ) -> sqlx::Result<Vec<FacetCount>> {
+    todo!()
+  }
+
+  async fn search_candidates(
+    &self,
+    _: Vec<String>,
+    _: Option<i32>,
+    _: Option<i32>,
+    _: i64,
+  ) -> sqlx::Result<Vec<SearchCandidate>> {
+    todo!()
+  }
+
+  async fn attraction_translations(
+    &self,
+    _: Vec<i32>,
+    _: Vec<String>,
+  ) -> sqlx::Result<Vec<Translation>> {
+    todo!()
+  }
+
+  async fn attractions_in(
+    &self,
+    _: Option<i32>,
+  ) -> sqlx::Result<Vec<Attraction>> {
+    todo!()
+  }
+
+  async fn dismissed_duplicates(
+    &self,
+  ) -> sqlx::Result<Vec<DuplicateDismissal>> {
+    todo!()
+  }
+
+  async fn dismiss_duplicate(&self, _: i32, _: i32) -> sqlx::Result<u64> {
+    todo!()
+  }
+
+  async fn merge_attractions(
+    &self,
+    _: i32,
+    _: i32,
+    _: &Actor,
+  ) -> sqlx::Result<AttractionMerge> {
+    todo!()
+  }
+
+  async fn redirect_of(&self, _: i32) -> sqlx::Result<Option<EntityId>> {
+    todo!()
+  }
 }
 
 #[derive(Clone)]
@@ -96,6 +519,7 @@ impl AttractionRepository for PgAttractionRepository {
       Attraction,
       r#"
       SELECT * FROM attraction
+      WHERE deleted_at IS NULL
       LIMIT 20
       "#
     )
@@ -103,22 +527,81 @@ impl AttractionRepository for PgAttractionRepository {
     .await
   }
 
+  /// The descriptions of the attraction, its city and its type are the ones
+  /// chosen as in best_of, or their own without any translation.
   async fn get_attraction(
     &self,
     the_attraction_id: i32,
+    locales: Vec<String>,
   ) -> sqlx::Result<FullAttraction> {
     let conn = self.connection.get();
     sqlx::query_as!(
       FullAttraction,
       r#"
-      SELECT a.id as attraction_id, a.description, c.description as city,
-      at.description as attraction_type
+      SELECT a.id as attraction_id,
+      coalesce(ta.description, a.description) as "description!",
+      coalesce(tc.description, c.description) as "city!",
+      coalesce(tat.description, at.description) as "attraction_type!",
+      ta.locale as "locale?"
       FROM attraction a
       INNER JOIN attraction_type at ON a.attraction_type_id = at.id
       INNER JOIN city c ON a.city_id = c.id
-      WHERE a.id = $1
+      LEFT JOIN LATERAL (
+        SELECT t.description, t.locale FROM attraction_translation t
+        WHERE t.attraction_id = a.id AND split_part(t.locale, '-', 1) = any($3)
+        ORDER BY array_position($3, split_part(t.locale, '-', 1)),
+        array_position($2, t.locale) NULLS LAST, t.locale
+        LIMIT 1
+      ) ta ON true
+      LEFT JOIN LATERAL (
+        SELECT t.description FROM city_translation t
+        WHERE t.city_id = c.id AND split_part(t.locale, '-', 1) = any($3)
+        ORDER BY array_position($3, split_part(t.locale, '-', 1)),
+        array_position($2, t.locale) NULLS LAST, t.locale
+        LIMIT 1
+      ) tc ON true
+      LEFT JOIN LATERAL (
+        SELECT t.description FROM attraction_type_translation t
+        WHERE t.attraction_type_id = at.id AND split_part(t.locale, '-', 1) = any($3)
+        ORDER BY array_position($3, split_part(t.locale, '-', 1)),
+        array_position($2, t.locale) NULLS LAST, t.locale
+        LIMIT 1
+      ) tat ON true
+      WHERE a.id = $1 AND a.deleted_at IS NULL
+      "#,
+      the_attraction_id,
+      &locales,
+      &languages_of(&locales)
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  async fn attractions_by_ids(
+    &self,
+    ids: Vec<i32>,
+  ) -> sqlx::Result<Vec<Attraction>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Attraction,
+      r#"
+      SELECT * FROM attraction WHERE id = ANY($1) AND deleted_at IS NULL
+      ORDER BY id
+      "#,
+      &ids
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn attraction_by_id(&self, id: i32) -> sqlx::Result<Attraction> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Attraction,
+      r#"
+      SELECT * FROM attraction WHERE id = $1 AND deleted_at IS NULL
       "#,
-      the_attraction_id
+      id
     )
     .fetch_one(conn)
     .await
@@ -148,6 +631,7 @@ impl AttractionRepository for PgAttractionRepository {
       EntityId,
       r#"
       SELECT id FROM attraction
+      WHERE deleted_at IS NULL
       order by id asc
       "#
     )
@@ -155,45 +639,1165 @@ impl AttractionRepository for PgAttractionRepository {
     .await
   }
 
-  /// Returns the elements sorted by the rate.
-  async fn sorted_ratings_for(
+  /// Returns the elements in the [from, to) period sorted by the rate.
+  async fn sorted_ratings_between(
     &self,
     attraction_id: i32,
-    from: NaiveDate,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
   ) -> sqlx::Result<Vec<AttractionRating>> {
     let conn = self.connection.get();
     sqlx::query_as!(
       AttractionRating,
       r#"
       SELECT * FROM attraction_rating
-      WHERE attraction_id = $1
-      AND DATE_TRUNC('day', at) = DATE_TRUNC('day', $2::timestamp)
+      WHERE attraction_id = $1 AND status = 'ACCEPTED'
+      AND at >= $2 AND at < $3
       ORDER BY rate asc
       "#,
       attraction_id,
-      NaiveDateTime::new(from, NaiveTime::default())
+      from,
+      to
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// Returns the elements in the [from, to) period that were inserted after
+  /// the last rating id or written after the watermark.
+  async fn ratings_changed_since(
+    &self,
+    attraction_id: i32,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    last_rating_id: i32,
+    watermark: Option<NaiveDateTime>,
+  ) -> sqlx::Result<Vec<AttractionRating>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      AttractionRating,
+      r#"
+      SELECT * FROM attraction_rating
+      WHERE attraction_id = $1 AND status = 'ACCEPTED'
+      AND at >= $2 AND at < $3
+      AND (id > $4 OR updated_at > COALESCE($5, '-infinity'::timestamp))
+      ORDER BY id asc
+      "#,
+      attraction_id,
+      from,
+      to,
+      last_rating_id,
+      watermark
     )
     .fetch_all(conn)
     .await
   }
 
-  async fn group_ratings_by_date(
+  /// Returns the state of every bucket of the granularity that has accepted
+  /// ratings.
+  /// A rolling window is evaluated every day between the first and the last
+  /// rated days, even if nobody rated the attraction that day.
+  async fn group_ratings_by(
     &self,
     attraction_id: i32,
+    granularity: Granularity,
   ) -> sqlx::Result<Vec<AttractionByDate>> {
     let conn = self.connection.get();
-    let rows = sqlx::query_as!(
+    if let Some(window_days) = granularity.rolling_days() {
+      return sqlx::query_as!(
+        AttractionByDate,
+        r#"
+        SELECT $1::integer as "attraction_id!", days.at,
+        COUNT(ar.id) as rating_count, MAX(ar.updated_at) as watermark
+        FROM GENERATE_SERIES(
+          (SELECT DATE_TRUNC('day', MIN(at)) FROM attraction_rating
+          WHERE attraction_id = $1),
+          (SELECT DATE_TRUNC('day', MAX(at)) FROM attraction_rating
+          WHERE attraction_id = $1),
+          INTERVAL '1 day'
+        ) as days(at)
+        INNER JOIN attraction_rating ar ON ar.attraction_id = $1
+        AND ar.status = 'ACCEPTED'
+        AND ar.at >= days.at - MAKE_INTERVAL(days => $2::integer - 1)
+        AND ar.at < days.at + INTERVAL '1 day'
+        GROUP BY days.at
+        "#,
+        attraction_id,
+        window_days as i32
+      )
+      .fetch_all(conn)
+      .await;
+    }
+    sqlx::query_as!(
       AttractionByDate,
       r#"
-      SELECT attraction_id, DATE_TRUNC('day', at) as at
+      SELECT attraction_id, DATE_TRUNC($2, at) as at,
+      COUNT(id) as rating_count, MAX(updated_at) as watermark
       FROM attraction_rating
-      WHERE attraction_id = $1
-      GROUP BY attraction_id, DATE_TRUNC('day', at)
+      WHERE attraction_id = $1 AND status = 'ACCEPTED'
+      GROUP BY attraction_id, DATE_TRUNC($2, at)
       "#,
-      attraction_id
+      attraction_id,
+      granularity.trunc_unit()
     )
     .fetch_all(conn)
-    .await?;
-    Ok(rows)
+    .await
+  }
+
+  /// Returns the ratings in the [from, to] period of the attraction, the
+  /// submitter or the fingerprint, whatever their status.
+  async fn recent_ratings(
+    &self,
+    attraction_id: i32,
+    submitter: Option<String>,
+    fingerprint: Option<String>,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+  ) -> sqlx::Result<Vec<RecentRating>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      RecentRating,
+      r#"
+      SELECT ar.id, ar.attraction_id, ar.rate, ar.at, ar.submitter,
+      ar.fingerprint, a.latitude, a.longitude
+      FROM attraction_rating ar
+      INNER JOIN attraction a ON a.id = ar.attraction_id
+      WHERE ar.at >= $4 AND ar.at <= $5
+      AND (ar.attraction_id = $1 OR ar.submitter = $2 OR ar.fingerprint = $3)
+      "#,
+      attraction_id,
+      submitter,
+      fingerprint,
+      from,
+      to
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn save_rating(
+    &self,
+    rating: AttractionRating,
+  ) -> sqlx::Result<EntityId> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      EntityId,
+      r#"
+      INSERT INTO attraction_rating
+      (at, attraction_id, rate, source, submitter, fingerprint, status,
+      fraud_score, fraud_reasons, user_id)
+      VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+      returning id
+      "#,
+      rating.get_at(),
+      rating.get_attraction_id(),
+      rating.get_rate(),
+      rating.get_source(),
+      rating.get_submitter(),
+      rating.get_fingerprint(),
+      rating.get_status(),
+      rating.get_fraud_score(),
+      &rating.get_fraud_reasons(),
+      rating.get_user_id()
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  async fn ratings_with_status(
+    &self,
+    status: RatingStatus,
+  ) -> sqlx::Result<Vec<AttractionRating>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      AttractionRating,
+      r#"
+      SELECT * FROM attraction_rating
+      WHERE status = $1
+      ORDER BY at desc
+      "#,
+      status.as_str()
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn update_rating_status(
+    &self,
+    rating_id: i32,
+    status: RatingStatus,
+  ) -> sqlx::Result<AttractionRating> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      AttractionRating,
+      r#"
+      UPDATE attraction_rating SET status = $2
+      WHERE id = $1
+      returning *
+      "#,
+      rating_id,
+      status.as_str()
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  /// Returns the accepted ratings and the check-ins since the given moment
+  /// of every attraction, rated or not. The weight of a rating or a check-in
+  /// halves every half_life_days of age at the moment now.
+  async fn rating_summaries(
+    &self,
+    since: Option<NaiveDateTime>,
+    now: NaiveDateTime,
+    half_life_days: f64,
+  ) -> sqlx::Result<Vec<AttractionRatingSummary>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      AttractionRatingSummary,
+      r#"
+      SELECT a.id as attraction_id, a.description, a.city_id,
+      a.attraction_type_id, COUNT(ar.id) as "rating_count!",
+      COALESCE(SUM(ar.rate), 0) as "rate_sum!",
+      COALESCE(SUM(weight.value), 0) as "weighted_count!",
+      COALESCE(SUM(ar.rate::float8 * weight.value), 0) as "weighted_sum!",
+      MAX(ar.at) as last_rated_at,
+      visits.count as "check_in_count!",
+      visits.weighted as "weighted_check_ins!"
+      FROM attraction a
+      LEFT JOIN LATERAL (
+        SELECT COUNT(ci.id) as count,
+        COALESCE(SUM(POWER(
+          0.5::float8,
+          GREATEST(EXTRACT(EPOCH FROM ($2 - ci.at))::float8, 0)
+          / 86400.0 / $3::float8
+        )) FILTER (WHERE ci.rating_id IS NULL), 0) as weighted
+        FROM check_in ci
+        WHERE ci.attraction_id = a.id
+        AND ($1::timestamp IS NULL OR ci.at >= $1)
+      ) visits ON true
+      LEFT JOIN attraction_rating ar ON ar.attraction_id = a.id
+      AND ar.status = 'ACCEPTED'
+      AND ($1::timestamp IS NULL OR ar.at >= $1)
+      LEFT JOIN LATERAL (
+        SELECT POWER(
+          0.5::float8,
+          GREATEST(EXTRACT(EPOCH FROM ($2 - ar.at))::float8, 0)
+          / 86400.0 / $3::float8
+        ) as value
+      ) weight ON ar.id IS NOT NULL
+      WHERE a.deleted_at IS NULL
+      GROUP BY a.id, visits.count, visits.weighted
+      ORDER BY a.id
+      "#,
+      since,
+      now,
+      half_life_days
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn create_attraction(
+    &self,
+    attraction: NewAttraction,
+    actor: &Actor,
+  ) -> sqlx::Result<Attraction> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let created = sqlx::query_as!(
+      Attraction,
+      r#"
+      INSERT INTO attraction
+        (description, city_id, attraction_type_id, latitude, longitude)
+      VALUES ($1, $2, $3, $4, $5)
+      returning *
+      "#,
+      attraction.description,
+      attraction.city_id,
+      attraction.attraction_type_id,
+      attraction.latitude,
+      attraction.longitude
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(created)
+  }
+
+  /// It fails with a row not found if the attraction doesn't exist or is
+  /// deleted.
+  async fn update_attraction(
+    &self,
+    id: i32,
+    attraction: NewAttraction,
+    actor: &Actor,
+  ) -> sqlx::Result<Attraction> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let updated = sqlx::query_as!(
+      Attraction,
+      r#"
+      UPDATE attraction
+      SET description = $2, city_id = $3, attraction_type_id = $4,
+        latitude = $5, longitude = $6
+      WHERE id = $1 AND deleted_at IS NULL
+      returning *
+      "#,
+      id,
+      attraction.description,
+      attraction.city_id,
+      attraction.attraction_type_id,
+      attraction.latitude,
+      attraction.longitude
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(updated)
+  }
+
+  /// Returns the number of deleted attractions. The attraction is deleted
+  /// softly, its ratings are kept so it can be restored.
+  async fn delete_attraction(
+    &self,
+    id: i32,
+    actor: &Actor,
+  ) -> sqlx::Result<u64> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let deleted = sqlx::query!(
+      r#"
+      UPDATE attraction SET deleted_at = now()
+      WHERE id = $1 AND deleted_at IS NULL
+      "#,
+      id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(deleted.rows_affected())
+  }
+
+  /// Returns the ratings of the user, the latest first.
+  async fn ratings_of_user(
+    &self,
+    user_id: i32,
+    attraction_id: Option<i32>,
+  ) -> sqlx::Result<Vec<AttractionRating>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      AttractionRating,
+      r#"
+      SELECT * FROM attraction_rating
+      WHERE user_id = $1 AND ($2::integer IS NULL OR attraction_id = $2)
+      ORDER BY at DESC, id DESC
+      "#,
+      user_id,
+      attraction_id
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// Change the rate of a rating of the user. The moment of the update moves
+  /// the watermark of its period, so its aggregates and sketch are rebuilt.
+  async fn update_user_rating(
+    &self,
+    id: i32,
+    user_id: i32,
+    rate: BigDecimal,
+    now: NaiveDateTime,
+  ) -> sqlx::Result<Option<AttractionRating>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      AttractionRating,
+      r#"
+      UPDATE attraction_rating SET rate = $3, updated_at = $4
+      WHERE id = $1 AND user_id = $2
+      returning *
+      "#,
+      id,
+      user_id,
+      rate,
+      now
+    )
+    .fetch_optional(conn)
+    .await
+  }
+
+  /// Returns the number of deleted ratings. The count of the ratings of its
+  /// period changes, so its aggregates and sketch are rebuilt.
+  async fn delete_user_rating(
+    &self,
+    id: i32,
+    user_id: i32,
+  ) -> sqlx::Result<u64> {
+    let conn = self.connection.get();
+    sqlx::query!(
+      r#"
+      DELETE FROM attraction_rating WHERE id = $1 AND user_id = $2
+      "#,
+      id,
+      user_id
+    )
+    .execute(conn)
+    .await
+    .map(|result| result.rows_affected())
+  }
+
+  async fn attractions_with_location(&self) -> sqlx::Result<Vec<Attraction>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Attraction,
+      r#"
+      SELECT * FROM attraction
+      WHERE latitude IS NOT NULL AND longitude IS NOT NULL
+      AND deleted_at IS NULL
+      ORDER BY id
+      "#
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn attractions_with_opening_hours(
+    &self,
+  ) -> sqlx::Result<Vec<Attraction>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Attraction,
+      r#"
+      SELECT a.* FROM attraction a
+      INNER JOIN attraction_opening_hours aoh ON aoh.attraction_id = a.id
+      WHERE a.deleted_at IS NULL
+      ORDER BY a.id
+      "#
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn opening_hours_of(
+    &self,
+    attraction_ids: Vec<i32>,
+  ) -> sqlx::Result<Vec<AttractionOpeningHours>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      AttractionOpeningHours,
+      r#"
+      SELECT * FROM attraction_opening_hours WHERE attraction_id = ANY($1)
+      "#,
+      &attraction_ids
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// Set the opening hours of the attraction, replacing the previous ones.
+  async fn save_opening_hours(
+    &self,
+    attraction_id: i32,
+    opening_hours: String,
+    now: NaiveDateTime,
+    actor: &Actor,
+  ) -> sqlx::Result<AttractionOpeningHours> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let saved = sqlx::query_as!(
+      AttractionOpeningHours,
+      r#"
+      INSERT INTO attraction_opening_hours
+        (attraction_id, opening_hours, updated_at)
+      VALUES ($1, $2, $3)
+      ON CONFLICT (attraction_id)
+      DO UPDATE SET opening_hours = $2, updated_at = $3
+      returning *
+      "#,
+      attraction_id,
+      opening_hours,
+      now
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(saved)
+  }
+
+  async fn delete_opening_hours(
+    &self,
+    attraction_id: i32,
+    actor: &Actor,
+  ) -> sqlx::Result<u64> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let deleted = sqlx::query!(
+      r#"
+      DELETE FROM attraction_opening_hours WHERE attraction_id = $1
+      "#,
+      attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(deleted.rows_affected())
+  }
+
+  /// Returns the holidays between both days, inclusive, of the countries of
+  /// the attractions.
+  async fn holidays_of(
+    &self,
+    attraction_ids: Vec<i32>,
+    from: NaiveDate,
+    to: NaiveDate,
+  ) -> sqlx::Result<Vec<AttractionHoliday>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      AttractionHoliday,
+      r#"
+      SELECT a.id as attraction_id, h.day
+      FROM attraction a
+      INNER JOIN city c ON a.city_id = c.id
+      INNER JOIN holiday h ON h.country_id = c.country_id
+      WHERE a.id = ANY($1) AND h.day BETWEEN $2 AND $3
+      "#,
+      &attraction_ids,
+      from,
+      to
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn tags_of(&self, attraction_id: i32) -> sqlx::Result<Vec<Tag>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Tag,
+      r#"
+      SELECT t.* FROM tag t
+      INNER JOIN attraction_tag at ON at.tag_id = t.id
+      WHERE at.attraction_id = $1 AND t.deleted_at IS NULL
+      ORDER BY t.code
+      "#,
+      attraction_id
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn tags_by_codes(&self, codes: Vec<String>) -> sqlx::Result<Vec<Tag>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Tag,
+      r#"
+      SELECT * FROM tag WHERE code = ANY($1) AND deleted_at IS NULL
+      ORDER BY code
+      "#,
+      &codes
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// Replace the tags of the attraction, it fails if the attraction doesn't
+  /// exist.
+  async fn replace_tags(
+    &self,
+    attraction_id: i32,
+    tag_ids: Vec<i32>,
+    actor: &Actor,
+  ) -> sqlx::Result<()> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    sqlx::query!(
+      r#"
+      DELETE FROM attraction_tag WHERE attraction_id = $1
+      "#,
+      attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    for tag_id in tag_ids {
+      sqlx::query!(
+        r#"
+        INSERT INTO attraction_tag (attraction_id, tag_id) VALUES ($1, $2)
+        "#,
+        attraction_id,
+        tag_id
+      )
+      .execute(&mut *transaction)
+      .await?;
+    }
+    transaction.commit().await
+  }
+
+  /// Returns the attractions that have all the tags.
+  async fn attractions_tagged(
+    &self,
+    codes: Vec<String>,
+  ) -> sqlx::Result<Vec<Attraction>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Attraction,
+      r#"
+      SELECT a.* FROM attraction a
+      WHERE a.deleted_at IS NULL AND a.id IN (
+        SELECT at.attraction_id FROM attraction_tag at
+        INNER JOIN tag t ON at.tag_id = t.id
+        WHERE t.code = ANY($1) AND t.deleted_at IS NULL
+        GROUP BY at.attraction_id
+        HAVING count(*) = cardinality($1)
+      )
+      ORDER BY a.id
+      "#,
+      &codes
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn attributes_of(
+    &self,
+    attraction_id: i32,
+  ) -> sqlx::Result<Option<AttractionAttributes>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      AttractionAttributes,
+      r#"
+      SELECT * FROM attraction_attribute WHERE attraction_id = $1
+      "#,
+      attraction_id
+    )
+    .fetch_optional(conn)
+    .await
+  }
+
+  /// Set the attributes of the attraction, replacing the previous ones.
+  async fn save_attributes(
+    &self,
+    attributes: AttractionAttributes,
+    actor: &Actor,
+  ) -> sqlx::Result<AttractionAttributes> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let saved = sqlx::query_as!(
+      AttractionAttributes,
+      r#"
+      INSERT INTO attraction_attribute
+        (attraction_id, price_range, visit_minutes, updated_at)
+      VALUES ($1, $2, $3, $4)
+      ON CONFLICT (attraction_id)
+      DO UPDATE SET price_range = $2, visit_minutes = $3, updated_at = $4
+      returning *
+      "#,
+      attributes.attraction_id,
+      attributes.price_range,
+      attributes.visit_minutes,
+      attributes.updated_at
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(saved)
+  }
+
+  /// Count the attractions of the city, or of every city, that have all the
+  /// tags by every tag, type, price range and duration of the visit.
+  async fn facets(
+    &self,
+    city_id: Option<i32>,
+    codes: Vec<String>,
+  ) -> sqlx::Result<Vec<FacetCount>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      FacetCount,
+      r#"
+      WITH filtered AS (
+        SELECT a.id, a.attraction_type_id FROM attraction a
+        WHERE ($1::integer IS NULL OR a.city_id = $1)
+        AND a.deleted_at IS NULL
+        AND (cardinality($2::varchar[]) = 0 OR a.id IN (
+          SELECT at.attraction_id FROM attraction_tag at
+          INNER JOIN tag t ON at.tag_id = t.id
+          WHERE t.code = ANY($2) AND t.deleted_at IS NULL
+          GROUP BY at.attraction_id
+          HAVING count(*) = cardinality($2)
+        ))
+      )
+      SELECT 'TAG' as "facet!", t.code as "value!", t.description,
+        count(*) as "attraction_count!"
+      FROM filtered f
+      INNER JOIN attraction_tag at ON at.attraction_id = f.id
+      INNER JOIN tag t ON at.tag_id = t.id
+      WHERE t.deleted_at IS NULL
+      GROUP BY t.code, t.description
+      UNION ALL
+      SELECT 'ATTRACTION_TYPE', aty.code, aty.description, count(*)
+      FROM filtered f
+      INNER JOIN attraction_type aty ON f.attraction_type_id = aty.id
+      GROUP BY aty.code, aty.description
+      UNION ALL
+      SELECT 'PRICE_RANGE', aa.price_range, NULL, count(*)
+      FROM filtered f
+      INNER JOIN attraction_attribute aa ON aa.attraction_id = f.id
+      WHERE aa.price_range IS NOT NULL
+      GROUP BY aa.price_range
+      UNION ALL
+      SELECT 'VISIT_DURATION', CASE
+          WHEN aa.visit_minutes <= 60 THEN 'UP_TO_1H'
+          WHEN aa.visit_minutes <= 120 THEN 'UP_TO_2H'
+          WHEN aa.visit_minutes <= 240 THEN 'UP_TO_4H'
+          ELSE 'MORE_THAN_4H'
+        END, NULL, count(*)
+      FROM filtered f
+      INNER JOIN attraction_attribute aa ON aa.attraction_id = f.id
+      WHERE aa.visit_minutes IS NOT NULL
+      GROUP BY 2
+      ORDER BY 1, 4 DESC, 2
+      "#,
+      city_id,
+      &codes
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// Returns the attractions with a description close to a word of the
+  /// query, or with a word that starts with the last one, the closest
+  /// first. The accents and the case are ignored.
+  async fn search_candidates(
+    &self,
+    words: Vec<String>,
+    city_id: Option<i32>,
+    attraction_type_id: Option<i32>,
+    limit: i64,
+  ) -> sqlx::Result<Vec<SearchCandidate>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      SearchCandidate,
+      r#"
+      WITH query AS (
+        SELECT array_agg(search_text(w)) as words FROM unnest($1::text[]) w
+      ), matched AS (
+        SELECT a.*,
+          (
+            SELECT avg(word_similarity(w, search_text(a.description)))
+            FROM unnest(q.words) w
+          ) as similarity,
+          search_text(a.description)
+            ~ ('(^|[^[:alnum:]])' || q.words[cardinality(q.words)])
+            as prefix_match
+        FROM attraction a CROSS JOIN query q
+        WHERE ($2::integer IS NULL OR a.city_id = $2)
+        AND a.deleted_at IS NULL
+        AND ($3::integer IS NULL OR a.attraction_type_id = $3)
+        AND (
+          EXISTS (
+            SELECT 1 FROM unnest(q.words) w
+            WHERE w <% search_text(a.description)
+          )
+          OR search_text(a.description)
+            ~ ('(^|[^[:alnum:]])' || q.words[cardinality(q.words)])
+        )
+        ORDER BY similarity DESC, a.id
+        LIMIT $4
+      )
+      SELECT m.id as attraction_id, m.description, m.city_id,
+        m.attraction_type_id, m.similarity::float8 as "similarity!",
+        m.prefix_match as "prefix_match!", r.rating_count as "rating_count!",
+        r.average_rate
+      FROM matched m
+      CROSS JOIN LATERAL (
+        SELECT count(*) as rating_count, avg(ar.rate)::float8 as average_rate
+        FROM attraction_rating ar
+        WHERE ar.attraction_id = m.id AND ar.status = 'ACCEPTED'
+      ) r
+      ORDER BY m.similarity DESC, m.id
+      "#,
+      &words,
+      city_id,
+      attraction_type_id,
+      limit
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// The translations of the attractions in any of the languages of the
+  /// locales, whatever their region.
+  async fn attraction_translations(
+    &self,
+    attraction_ids: Vec<i32>,
+    locales: Vec<String>,
+  ) -> sqlx::Result<Vec<Translation>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Translation,
+      r#"
+      SELECT attraction_id as entity_id, locale, description, updated_at
+      FROM attraction_translation
+      WHERE attraction_id = any($1) AND split_part(locale, '-', 1) = any($2)
+      "#,
+      &attraction_ids,
+      &languages_of(&locales)
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// Returns every attraction of the city, or of every city if there isn't
+  /// one.
+  async fn attractions_in(
+    &self,
+    city_id: Option<i32>,
+  ) -> sqlx::Result<Vec<Attraction>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Attraction,
+      r#"
+      SELECT * FROM attraction
+      WHERE ($1::integer IS NULL OR city_id = $1) AND deleted_at IS NULL
+      ORDER BY id
+      "#,
+      city_id
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn dismissed_duplicates(
+    &self,
+  ) -> sqlx::Result<Vec<DuplicateDismissal>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      DuplicateDismissal,
+      r#"
+      SELECT attraction_id, other_attraction_id
+      FROM attraction_duplicate_dismissal
+      "#
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// Returns the number of new dismissals, the lowest id must go first. It
+  /// fails if any of the attractions doesn't exist.
+  async fn dismiss_duplicate(
+    &self,
+    attraction_id: i32,
+    other_attraction_id: i32,
+  ) -> sqlx::Result<u64> {
+    let conn = self.connection.get();
+    sqlx::query!(
+      r#"
+      INSERT INTO attraction_duplicate_dismissal
+      (attraction_id, other_attraction_id) VALUES ($1, $2)
+      ON CONFLICT DO NOTHING
+      "#,
+      attraction_id,
+      other_attraction_id
+    )
+    .execute(conn)
+    .await
+    .map(|result| result.rows_affected())
+  }
+
+  /// Move everything of the merged attraction to the surviving one and
+  /// delete it, leaving a redirect from its id. The ratings of a user that
+  /// already rated the surviving attraction the same day are dropped, and
+  /// so are the aggregates, sketches and anomalies of the periods that the
+  /// surviving attraction already has. Moving the ratings touches them, so
+  /// the aggregates and sketches of their periods become stale and are
+  /// recalculated. The check-ins move too, and the surviving attraction takes
+  /// the place of the merged one in the wishlists that don't have it.
+  /// The tags, translations, opening hours and attributes that the surviving
+  /// attraction doesn't have are copied. It fails with RowNotFound if any of
+  /// the attractions doesn't exist.
+  async fn merge_attractions(
+    &self,
+    attraction_id: i32,
+    merged_attraction_id: i32,
+    actor: &Actor,
+  ) -> sqlx::Result<AttractionMerge> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let locked = sqlx::query!(
+      r#"
+      SELECT id FROM attraction
+      WHERE (id = $1 OR id = $2) AND deleted_at IS NULL
+      FOR UPDATE
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .fetch_all(&mut *transaction)
+    .await?;
+    if locked.len() != 2 {
+      return Err(sqlx::Error::RowNotFound);
+    }
+
+    let dropped_ratings = sqlx::query!(
+      r#"
+      DELETE FROM attraction_rating merged USING attraction_rating surviving
+      WHERE merged.attraction_id = $2 AND surviving.attraction_id = $1
+      AND merged.user_id = surviving.user_id
+      AND date_trunc('day', merged.at) = date_trunc('day', surviving.at)
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?
+    .rows_affected();
+    let moved_ratings = sqlx::query!(
+      r#"
+      UPDATE attraction_rating SET attraction_id = $1
+      WHERE attraction_id = $2
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?
+    .rows_affected();
+
+    sqlx::query!(
+      r#"
+      UPDATE check_in SET attraction_id = $1 WHERE attraction_id = $2
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+      r#"
+      DELETE FROM wishlist_attraction merged
+      USING wishlist_attraction surviving
+      WHERE merged.attraction_id = $2 AND surviving.attraction_id = $1
+      AND merged.wishlist_id = surviving.wishlist_id
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+      r#"
+      UPDATE wishlist_attraction SET attraction_id = $1
+      WHERE attraction_id = $2
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+      r#"
+      DELETE FROM attraction_rating_aggregate merged
+      USING attraction_rating_aggregate surviving
+      WHERE merged.attraction_id = $2 AND surviving.attraction_id = $1
+      AND merged.granularity = surviving.granularity
+      AND merged.at = surviving.at
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    let moved_aggregates = sqlx::query!(
+      r#"
+      UPDATE attraction_rating_aggregate SET attraction_id = $1
+      WHERE attraction_id = $2
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?
+    .rows_affected();
+
+    sqlx::query!(
+      r#"
+      DELETE FROM attraction_rating_sketch merged
+      USING attraction_rating_sketch surviving
+      WHERE merged.attraction_id = $2 AND surviving.attraction_id = $1
+      AND merged.at = surviving.at
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+      r#"
+      UPDATE attraction_rating_sketch SET attraction_id = $1
+      WHERE attraction_id = $2
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+      r#"
+      DELETE FROM attraction_rating_anomaly merged
+      USING attraction_rating_anomaly surviving
+      WHERE merged.attraction_id = $2 AND surviving.attraction_id = $1
+      AND merged.at = surviving.at AND merged.metric = surviving.metric
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+      r#"
+      UPDATE attraction_rating_anomaly SET attraction_id = $1
+      WHERE attraction_id = $2
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    let moved_similarities = sqlx::query!(
+      r#"
+      UPDATE attraction_similarity SET
+      attraction_id = CASE WHEN attraction_id = $2 THEN $1
+        ELSE attraction_id END,
+      to_attraction_id = CASE WHEN to_attraction_id = $2 THEN $1
+        ELSE to_attraction_id END
+      WHERE attraction_id = $2 OR to_attraction_id = $2
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?
+    .rows_affected();
+    let own_similarities = sqlx::query!(
+      r#"
+      DELETE FROM attraction_similarity
+      WHERE attraction_id = $1 AND to_attraction_id = $1
+      "#,
+      attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?
+    .rows_affected();
+
+    sqlx::query!(
+      r#"
+      INSERT INTO attraction_tag (attraction_id, tag_id)
+      SELECT $1, tag_id FROM attraction_tag WHERE attraction_id = $2
+      ON CONFLICT DO NOTHING
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+      r#"
+      INSERT INTO attraction_translation
+      (attraction_id, locale, description, updated_at)
+      SELECT $1, locale, description, updated_at
+      FROM attraction_translation WHERE attraction_id = $2
+      ON CONFLICT DO NOTHING
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+      r#"
+      INSERT INTO attraction_opening_hours
+      (attraction_id, opening_hours, updated_at)
+      SELECT $1, opening_hours, updated_at
+      FROM attraction_opening_hours WHERE attraction_id = $2
+      ON CONFLICT DO NOTHING
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+      r#"
+      INSERT INTO attraction_attribute
+      (attraction_id, price_range, visit_minutes, updated_at)
+      SELECT $1, price_range, visit_minutes, updated_at
+      FROM attraction_attribute WHERE attraction_id = $2
+      ON CONFLICT DO NOTHING
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    // The attractions merged before into the merged one now redirect to the
+    // surviving one.
+    sqlx::query!(
+      r#"
+      UPDATE attraction_redirect SET to_attraction_id = $1
+      WHERE to_attraction_id = $2
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+      r#"
+      INSERT INTO attraction_redirect (attraction_id, to_attraction_id)
+      VALUES ($2, $1)
+      "#,
+      attraction_id,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+      r#"
+      DELETE FROM attraction WHERE id = $1
+      "#,
+      merged_attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+
+    Ok(AttractionMerge {
+      attraction_id,
+      merged_attraction_id,
+      moved_ratings,
+      dropped_ratings,
+      moved_aggregates,
+      moved_similarities: moved_similarities.saturating_sub(own_similarities),
+    })
+  }
+
+  /// The attraction that the merged one redirects to, none if it wasn't
+  /// merged.
+  async fn redirect_of(
+    &self,
+    attraction_id: i32,
+  ) -> sqlx::Result<Option<EntityId>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      EntityId,
+      r#"
+      SELECT to_attraction_id as id FROM attraction_redirect
+      WHERE attraction_id = $1
+      "#,
+      attraction_id
+    )
+    .fetch_optional(conn)
+    .await
   }
 }
@@ -1,18 +1,23 @@
 use crate::model::{
-  attraction::{AttractionByDate, AttractionRatingAggregate},
+  aggregation_settings::AggregationSettings,
+  anomaly::{self, DailyRating},
+  attraction::{
+    AttractionByDate, AttractionRating, AttractionRatingAggregate,
+    AttractionRatingAnomaly, AttractionRatingSketch,
+  },
   attraction_repository::{AttractionRepository, EntityId},
+  granularity::Granularity,
+  rating_sketch::RatingSketch,
+  rating_statistics::{decimal_of, RatingStatistics, MIN_RATE},
   similarity_generator::Similarity,
   similarity_repository::SimilarityRepository,
 };
-use bigdecimal::{BigDecimal, Zero};
-use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::{NaiveDateTime, Utc};
 use sqlx::FromRow;
-use std::{
-  collections::HashSet,
-  ops::{AddAssign, Div},
-  sync::Arc,
-};
+use std::{collections::HashSet, sync::Arc};
 
+#[allow(dead_code)]
 #[derive(FromRow, Debug)]
 pub struct SimilarityBetweenAttraction {
   pub id: i32,
@@ -26,6 +31,7 @@ pub struct SimilarityBetweenAttraction {
 pub struct AttractionSimilarity<AttractionRepo, SimilarityRepo> {
   attraction_repo: AttractionRepo,
   similarity_repo: SimilarityRepo,
+  settings: AggregationSettings,
 }
 
 impl<AttractionRepo, SimilarityRepo>
@@ -37,45 +43,137 @@ where
   pub fn new(
     attraction_repo: AttractionRepo,
     similarity_repo: SimilarityRepo,
+    settings: AggregationSettings,
   ) -> Self {
     AttractionSimilarity {
       attraction_repo,
       similarity_repo,
+      settings,
     }
   }
 
+  /// Generate the missing or stale aggregates of an attraction, for every
+  /// configured granularity.
+  ///
+  /// An aggregate is stale when the number of ratings of its period or the
+  /// latest moment when one of them was written (the watermark) changed since
+  /// it was generated, that happens with late, corrected or deleted ratings.
+  /// The aggregates of periods that no longer have ratings are removed.
+  ///
+  /// When the sketches are enabled, the daily sketches are brought up to date
+  /// first and the day based aggregates are derived by merging them, unless
+  /// the period has few enough ratings to aggregate them exactly.
+  ///
+  /// Once aggregated, the anomalies are detected over the daily aggregates.
   pub async fn aggregate_for(&self, attraction_id: i32) -> Result<(), String> {
+    if self.settings.use_sketches {
+      self.refresh_sketches(attraction_id).await?;
+    }
+    for granularity in self.settings.granularities.iter() {
+      self
+        .aggregate_for_granularity(attraction_id, *granularity)
+        .await?;
+    }
+    if self.settings.anomaly.enabled
+      && self.settings.granularities.contains(&Granularity::Day)
+    {
+      self.detect_anomalies(attraction_id).await?;
+    }
+    Ok(())
+  }
+
+  /// Detect the anomalies of the whole history of daily aggregates of an
+  /// attraction, replacing the ones detected before.
+  async fn detect_anomalies(&self, attraction_id: i32) -> Result<(), String> {
+    let aggregates = self
+      .similarity_repo
+      .list_aggregates(attraction_id, Granularity::Day, None, None)
+      .await
+      .map_err(|e| e.to_string())?;
+    let days = aggregates
+      .iter()
+      .map(|an_aggregate| DailyRating {
+        at: an_aggregate.get_at(),
+        average: an_aggregate.get_average().to_f64().unwrap_or(MIN_RATE),
+        count: an_aggregate.get_rating_count() as f64,
+      })
+      .collect::<Vec<DailyRating>>();
+    let detected_at = Utc::now().naive_utc();
+    let anomalies = anomaly::detect(&days, &self.settings.anomaly)
+      .into_iter()
+      .filter_map(|an_anomaly| {
+        Some(AttractionRatingAnomaly {
+          id: 0,
+          attraction_id,
+          at: an_anomaly.at,
+          metric: an_anomaly.metric.to_string(),
+          method: an_anomaly.method.to_string(),
+          value: decimal_of(an_anomaly.value)?,
+          baseline: decimal_of(an_anomaly.baseline)?,
+          score: decimal_of(an_anomaly.score)?,
+          detected_at,
+        })
+      })
+      .collect::<Vec<AttractionRatingAnomaly>>();
+    self
+      .similarity_repo
+      .replace_anomalies(attraction_id, anomalies)
+      .await
+      .map_err(|e| e.to_string())
+  }
+
+  async fn aggregate_for_granularity(
+    &self,
+    attraction_id: i32,
+    granularity: Granularity,
+  ) -> Result<(), String> {
     let ratings_by_date = self
       .attraction_repo
-      .group_ratings_by_date(attraction_id)
+      .group_ratings_by(attraction_id, granularity)
       .await
       .map_err(|e| e.to_string())?;
     let aggregate_by_date = self
       .similarity_repo
-      .group_aggregate_by_date(attraction_id)
+      .group_aggregate_by(attraction_id, granularity)
       .await
       .map_err(|e| e.to_string())?;
+    let rated_dates: HashSet<Option<NaiveDateTime>> =
+      ratings_by_date.iter().map(|item| item.at).collect();
+
+    // A period without a date can't be located, so it is skipped.
+    let orphan_aggregates = aggregate_by_date
+      .iter()
+      .filter(|item| !rated_dates.contains(&item.at))
+      .filter_map(|item| item.at)
+      .collect::<Vec<NaiveDateTime>>();
+    for an_orphan in orphan_aggregates {
+      self
+        .similarity_repo
+        .delete_aggregate(attraction_id, granularity, an_orphan)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
     let aggregate_by_date: HashSet<AttractionByDate> =
       HashSet::from_iter(aggregate_by_date);
-
-    let missing_aggregates = ratings_by_date
+    let stale_aggregates = ratings_by_date
       .into_iter()
       .filter(|item| !aggregate_by_date.contains(item))
       .collect::<Vec<AttractionByDate>>();
 
-    for an_attraction_date in missing_aggregates {
+    for an_attraction_date in stale_aggregates {
+      let Some(at) = an_attraction_date.at else {
+        continue;
+      };
       match self
-        .aggregate_for_at(
-          an_attraction_date.attraction_id,
-          an_attraction_date.at.unwrap().date(),
-        )
+        .aggregate_for_at(an_attraction_date.attraction_id, granularity, at)
         .await
       {
         Ok(_) => {},
         Err(e) => {
           println!(
-            "Error generating the aggregation for attraction: {} \n {}",
-            an_attraction_date.attraction_id, e
+            "Error generating the {} aggregation for attraction: {} \n {}",
+            granularity, an_attraction_date.attraction_id, e
           );
         },
       }
@@ -84,38 +182,235 @@ where
     Ok(())
   }
 
-  async fn aggregate_for_at(
+  /// Bring the daily sketches of an attraction up to date with its ratings,
+  /// using the same staleness rule as the aggregates.
+  async fn refresh_sketches(&self, attraction_id: i32) -> Result<(), String> {
+    let ratings_by_day = self
+      .attraction_repo
+      .group_ratings_by(attraction_id, Granularity::Day)
+      .await
+      .map_err(|e| e.to_string())?;
+    let sketch_by_day = self
+      .similarity_repo
+      .group_sketch_by_date(attraction_id)
+      .await
+      .map_err(|e| e.to_string())?;
+    let rated_days: HashSet<Option<NaiveDateTime>> =
+      ratings_by_day.iter().map(|item| item.at).collect();
+
+    for an_orphan in sketch_by_day
+      .iter()
+      .filter(|item| !rated_days.contains(&item.at))
+      .filter_map(|item| item.at)
+    {
+      self
+        .similarity_repo
+        .delete_sketch(attraction_id, an_orphan)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    let sketch_by_day: HashSet<AttractionByDate> =
+      HashSet::from_iter(sketch_by_day);
+    for a_day in ratings_by_day
+      .into_iter()
+      .filter(|item| item.at.is_some() && !sketch_by_day.contains(item))
+    {
+      self.refresh_sketch_for(&a_day).await?;
+    }
+    Ok(())
+  }
+
+  /// Update the sketch of a day. When the only change since the sketch was
+  /// saved are new ratings, they are added to it. Otherwise, a rating was
+  /// corrected or deleted, and the sketch is rebuilt from every rating of the
+  /// day.
+  async fn refresh_sketch_for(
     &self,
-    attraction_id: i32,
-    at: NaiveDate,
+    ratings_by_day: &AttractionByDate,
   ) -> Result<(), String> {
-    let ratings = self
-      .attraction_repo
-      .sorted_ratings_for(attraction_id, at)
+    let attraction_id = ratings_by_day.attraction_id;
+    let Some(at) = ratings_by_day.at else {
+      return Err(format!(
+        "The ratings of the attraction {} have no day",
+        attraction_id
+      ));
+    };
+    let (from, to) = Granularity::Day.window(at);
+    let stored = self
+      .similarity_repo
+      .get_sketch(attraction_id, at)
       .await
       .map_err(|e| e.to_string())?;
 
-    let total = ratings.len() as i32;
-    let index: usize = (0.95 * (total as f32)) as usize;
-    let percentile_95 = ratings.get(index).unwrap();
-    let percentile_95 = percentile_95.get_rate();
-    let index: usize = (0.99 * (total as f32)) as usize;
-    let percentile_99 = ratings.get(index).unwrap().get_rate();
+    let mut incremental = None;
+    if let Some(stored) = stored {
+      let changed = self
+        .attraction_repo
+        .ratings_changed_since(
+          attraction_id,
+          from,
+          to,
+          stored.get_last_rating_id(),
+          stored.get_watermark(),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+      let only_new_ratings = changed
+        .iter()
+        .all(|a_rating| a_rating.get_id() > stored.get_last_rating_id());
+      let expected_count = stored.get_rating_count() as usize + changed.len();
+      if only_new_ratings
+        && Some(expected_count as i64) == ratings_by_day.rating_count
+      {
+        let sketch = RatingSketch::from_bytes(&stored.sketch)?;
+        incremental = Some((sketch, stored, changed));
+      }
+    }
+
+    let (mut sketch, mut last_rating_id, mut watermark, ratings) =
+      match incremental {
+        Some((sketch, stored, changed)) => (
+          sketch,
+          stored.get_last_rating_id(),
+          stored.get_watermark(),
+          changed,
+        ),
+        None => {
+          let ratings = self
+            .attraction_repo
+            .ratings_changed_since(attraction_id, from, to, 0, None)
+            .await
+            .map_err(|e| e.to_string())?;
+          (RatingSketch::default(), 0, None, ratings)
+        },
+      };
+    for a_rating in ratings.iter() {
+      sketch.add(rate_of(a_rating));
+      last_rating_id = last_rating_id.max(a_rating.get_id());
+      watermark = watermark.max(Some(a_rating.get_updated_at()));
+    }
 
-    let mut average = BigDecimal::zero();
+    self
+      .similarity_repo
+      .save_sketch(AttractionRatingSketch {
+        id: 0,
+        attraction_id,
+        at,
+        sketch: sketch.to_bytes(),
+        rating_count: sketch.count() as i32,
+        last_rating_id,
+        watermark,
+      })
+      .await
+      .map_err(|e| e.to_string())?;
+    Ok(())
+  }
 
-    for a_rating in ratings {
-      average.add_assign(a_rating.rate);
+  /// The statistics of the period merging the daily sketches it covers,
+  /// along with the watermark of the ratings included.
+  async fn statistics_from_sketches(
+    &self,
+    attraction_id: i32,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+  ) -> Result<(Option<RatingStatistics>, Option<NaiveDateTime>), String> {
+    let sketches = self
+      .similarity_repo
+      .list_sketches(attraction_id, from, to)
+      .await
+      .map_err(|e| e.to_string())?;
+    let mut merged = RatingSketch::default();
+    for a_sketch in sketches.iter() {
+      merged.merge(&RatingSketch::from_bytes(&a_sketch.sketch)?);
     }
-    average = average.div(BigDecimal::from(total));
+    let watermark = sketches
+      .iter()
+      .filter_map(|a_sketch| a_sketch.get_watermark())
+      .max();
+    Ok((merged.statistics(), watermark))
+  }
+
+  /// The exact statistics of the period, sorting all of its ratings.
+  async fn statistics_from_ratings(
+    &self,
+    attraction_id: i32,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+  ) -> Result<(Option<RatingStatistics>, Option<NaiveDateTime>), String> {
+    let ratings = self
+      .attraction_repo
+      .sorted_ratings_between(attraction_id, from, to)
+      .await
+      .map_err(|e| e.to_string())?;
+
+    let rates = ratings
+      .iter()
+      .map(|a_rating| a_rating.get_rate())
+      .collect::<Vec<BigDecimal>>();
+    let statistics =
+      RatingStatistics::from_sorted(&rates, self.settings.percentile_method);
+    let watermark = ratings
+      .iter()
+      .map(|a_rating| a_rating.get_updated_at())
+      .max();
+    Ok((statistics, watermark))
+  }
+
+  async fn aggregate_for_at(
+    &self,
+    attraction_id: i32,
+    granularity: Granularity,
+    at: NaiveDateTime,
+  ) -> Result<(), String> {
+    let (from, to) = granularity.window(at);
+    // The sketches are daily, so they can't be used for the hourly buckets.
+    // A period with few ratings is cheap to sort, so its percentiles are
+    // exact and follow the percentile method instead of the t-digest.
+    let (statistics, watermark) =
+      if self.settings.use_sketches && granularity != Granularity::Hour {
+        match self
+          .statistics_from_sketches(attraction_id, from, to)
+          .await?
+        {
+          (Some(statistics), _)
+            if statistics.count as u64 <= self.settings.exact_up_to =>
+          {
+            self
+              .statistics_from_ratings(attraction_id, from, to)
+              .await?
+          },
+          from_sketches => from_sketches,
+        }
+      } else {
+        self
+          .statistics_from_ratings(attraction_id, from, to)
+          .await?
+      };
+    // A period without ratings has nothing to aggregate.
+    let Some(statistics) = statistics else {
+      return self
+        .similarity_repo
+        .delete_aggregate(attraction_id, granularity, at)
+        .await
+        .map_err(|e| e.to_string());
+    };
 
     let attraction_aggregate = AttractionRatingAggregate {
       id: 0,
       attraction_id,
-      at: at.and_time(NaiveTime::default()),
-      average,
-      ninety_five_percentile: percentile_95,
-      ninety_nine_percentile: percentile_99,
+      at,
+      average: statistics.average,
+      ninety_five_percentile: statistics.percentile_95,
+      ninety_nine_percentile: statistics.percentile_99,
+      rating_count: statistics.count,
+      min_rate: statistics.min_rate,
+      max_rate: statistics.max_rate,
+      median: statistics.median,
+      standard_deviation: statistics.standard_deviation,
+      histogram: statistics.histogram,
+      granularity: granularity.to_string(),
+      watermark,
     };
 
     self
@@ -132,6 +427,8 @@ where
   /// fetch the information in every loop, justo to keep it simple. But it is
   /// going to be optimized because it doesn't scale well if the number of
   /// attractions grows.
+  /// The attractions without a daily aggregate, e.g. because all of their
+  /// ratings were quarantined, are skipped.
   /// # Return:
   /// * Nothing if everything is ok.
   /// * Err a string that represents the error.
@@ -148,20 +445,36 @@ where
     let mut seen_attraction: HashSet<i32> = HashSet::new();
     for an_attraction in attractions.clone().iter() {
       seen_attraction.insert(an_attraction.id);
-      let one_attraction_info = self
+      let Some(one_attraction_info) = self
         .similarity_repo
-        .get_info(an_attraction.id)
+        .get_info(
+          an_attraction.id,
+          self.settings.anomaly.exclude_from_similarity,
+        )
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())?
+      else {
+        println!(
+          "Skipping the similarity of attraction {}, it has no daily aggregate",
+          an_attraction.id
+        );
+        continue;
+      };
       for other_attraction in attractions.clone().iter() {
-        if seen_attraction.get(&other_attraction.id).is_some() {
+        if seen_attraction.contains(&other_attraction.id) {
           continue;
         }
-        let other_attraction_info = self
+        let Some(other_attraction_info) = self
           .similarity_repo
-          .get_info(other_attraction.id)
+          .get_info(
+            other_attraction.id,
+            self.settings.anomaly.exclude_from_similarity,
+          )
           .await
-          .map_err(|e| e.to_string())?;
+          .map_err(|e| e.to_string())?
+        else {
+          continue;
+        };
         let similarity = similarity_calculator
           .similarity_between(&one_attraction_info, &other_attraction_info);
         let similarity_between_attraction = SimilarityBetweenAttraction {
@@ -181,3 +494,962 @@ where
     Ok(())
   }
 }
+
+fn rate_of(a_rating: &AttractionRating) -> f64 {
+  a_rating.get_rate().to_f64().unwrap_or(MIN_RATE)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::{
+    aggregation_settings::AnomalySettings,
+    attraction::{
+      Attraction, AttractionAttributes, AttractionHoliday,
+      AttractionOpeningHours, AttractionRatingSummary, FacetCount,
+      FullAttraction, NewAttraction, RecentRating, Tag,
+    },
+    audit::Actor,
+    duplicate::{AttractionMerge, DuplicateDismissal},
+    percentile::PercentileMethod,
+    rating_rules::RatingStatus,
+    search::SearchCandidate,
+    similarity_generator::{AttractionInfo, SimilarityCalculator},
+    translation::Translation,
+  };
+  use async_trait::async_trait;
+  use chrono::{Duration, NaiveDate};
+  use std::{
+    collections::BTreeMap,
+    str::FromStr,
+    sync::{Arc, Mutex},
+  };
+
+  /// The ratings in memory. It records the last rating id asked for in every
+  /// query of the changed ratings, to tell an incremental update of a sketch
+  /// (asked from the last rating of the sketch) from a rebuild (asked from 0).
+  #[derive(Clone, Default)]
+  struct InMemoryRatings {
+    ratings: Arc<Mutex<Vec<AttractionRating>>>,
+    asked_since: Arc<Mutex<Vec<i32>>>,
+  }
+
+  impl InMemoryRatings {
+    fn accepted(&self, attraction_id: i32) -> Vec<AttractionRating> {
+      let ratings = self.ratings.lock().unwrap();
+      ratings
+        .iter()
+        .filter(|a_rating| {
+          a_rating.attraction_id == attraction_id
+            && a_rating.status == RatingStatus::Accepted.as_str()
+        })
+        .cloned()
+        .collect()
+    }
+
+    fn rate(&self, a_rating: AttractionRating) {
+      self.ratings.lock().unwrap().push(a_rating);
+    }
+
+    fn edit(&self, id: i32, rate: &str, updated_at: NaiveDateTime) {
+      let mut ratings = self.ratings.lock().unwrap();
+      let a_rating = ratings.iter_mut().find(|a_rating| a_rating.id == id);
+      let a_rating = a_rating.unwrap();
+      a_rating.rate = decimal(rate);
+      a_rating.updated_at = updated_at;
+    }
+
+    fn delete(&self, id: i32) {
+      self
+        .ratings
+        .lock()
+        .unwrap()
+        .retain(|a_rating| a_rating.id != id);
+    }
+  }
+
+  #[async_trait]
+  impl AttractionRepository for InMemoryRatings {
+    async fn list(&self) -> sqlx::Result<Vec<Attraction>> {
+      todo!()
+    }
+
+    async fn get_attraction(
+      &self,
+      _: i32,
+      _: Vec<String>,
+    ) -> sqlx::Result<FullAttraction> {
+      todo!()
+    }
+
+    async fn attraction_by_id(&self, _: i32) -> sqlx::Result<Attraction> {
+      todo!()
+    }
+
+    async fn attractions_by_ids(
+      &self,
+      _: Vec<i32>,
+    ) -> sqlx::Result<Vec<Attraction>> {
+      todo!()
+    }
+
+    async fn ratings_for(&self, _: i32) -> sqlx::Result<Vec<AttractionRating>> {
+      todo!()
+    }
+
+    async fn all_attractions_ids(&self) -> sqlx::Result<Vec<EntityId>> {
+      let ratings = self.ratings.lock().unwrap();
+      let mut ids = ratings
+        .iter()
+        .map(|a_rating| a_rating.attraction_id)
+        .collect::<Vec<i32>>();
+      ids.sort();
+      ids.dedup();
+      Ok(
+        ids
+          .into_iter()
+          .map(|id| EntityId {
+            id,
+          })
+          .collect(),
+      )
+    }
+
+    async fn sorted_ratings_between(
+      &self,
+      attraction_id: i32,
+      from: NaiveDateTime,
+      to: NaiveDateTime,
+    ) -> sqlx::Result<Vec<AttractionRating>> {
+      let mut ratings = self
+        .accepted(attraction_id)
+        .into_iter()
+        .filter(|a_rating| a_rating.at >= from && a_rating.at < to)
+        .collect::<Vec<AttractionRating>>();
+      ratings.sort_by(|one, other| one.rate.cmp(&other.rate));
+      Ok(ratings)
+    }
+
+    async fn ratings_changed_since(
+      &self,
+      attraction_id: i32,
+      from: NaiveDateTime,
+      to: NaiveDateTime,
+      last_rating_id: i32,
+      watermark: Option<NaiveDateTime>,
+    ) -> sqlx::Result<Vec<AttractionRating>> {
+      self.asked_since.lock().unwrap().push(last_rating_id);
+      let mut ratings = self
+        .accepted(attraction_id)
+        .into_iter()
+        .filter(|a_rating| a_rating.at >= from && a_rating.at < to)
+        .filter(|a_rating| {
+          a_rating.id > last_rating_id || Some(a_rating.updated_at) > watermark
+        })
+        .collect::<Vec<AttractionRating>>();
+      ratings.sort_by_key(|a_rating| a_rating.id);
+      Ok(ratings)
+    }
+
+    async fn group_ratings_by(
+      &self,
+      attraction_id: i32,
+      granularity: Granularity,
+    ) -> sqlx::Result<Vec<AttractionByDate>> {
+      let ratings = self.accepted(attraction_id);
+      let state_of =
+        |at: NaiveDateTime, ratings: Vec<&AttractionRating>| AttractionByDate {
+          attraction_id,
+          at: Some(at),
+          rating_count: Some(ratings.len() as i64),
+          watermark: ratings.iter().map(|a_rating| a_rating.updated_at).max(),
+        };
+      if granularity.rolling_days().is_some() {
+        let days = ratings
+          .iter()
+          .map(|a_rating| granularity.truncate(a_rating.at))
+          .collect::<Vec<NaiveDateTime>>();
+        let (Some(first), Some(last)) =
+          (days.iter().min().copied(), days.iter().max().copied())
+        else {
+          return Ok(vec![]);
+        };
+        let mut by_date = vec![];
+        let mut a_day = first;
+        while a_day <= last {
+          let (from, to) = granularity.window(a_day);
+          let in_window = ratings
+            .iter()
+            .filter(|a_rating| a_rating.at >= from && a_rating.at < to)
+            .collect::<Vec<&AttractionRating>>();
+          if !in_window.is_empty() {
+            by_date.push(state_of(a_day, in_window));
+          }
+          a_day = granularity.next(a_day);
+        }
+        return Ok(by_date);
+      }
+      let mut buckets: BTreeMap<NaiveDateTime, Vec<&AttractionRating>> =
+        BTreeMap::new();
+      for a_rating in ratings.iter() {
+        buckets
+          .entry(granularity.truncate(a_rating.at))
+          .or_default()
+          .push(a_rating);
+      }
+      Ok(
+        buckets
+          .into_iter()
+          .map(|(at, ratings)| state_of(at, ratings))
+          .collect(),
+      )
+    }
+
+    async fn recent_ratings(
+      &self,
+      _: i32,
+      _: Option<String>,
+      _: Option<String>,
+      _: NaiveDateTime,
+      _: NaiveDateTime,
+    ) -> sqlx::Result<Vec<RecentRating>> {
+      todo!()
+    }
+
+    async fn save_rating(&self, _: AttractionRating) -> sqlx::Result<EntityId> {
+      todo!()
+    }
+
+    async fn ratings_with_status(
+      &self,
+      _: RatingStatus,
+    ) -> sqlx::Result<Vec<AttractionRating>> {
+      todo!()
+    }
+
+    async fn update_rating_status(
+      &self,
+      _: i32,
+      _: RatingStatus,
+    ) -> sqlx::Result<AttractionRating> {
+      todo!()
+    }
+
+    async fn rating_summaries(
+      &self,
+      _: Option<NaiveDateTime>,
+      _: NaiveDateTime,
+      _: f64,
+    ) -> sqlx::Result<Vec<AttractionRatingSummary>> {
+      todo!()
+    }
+
+    async fn create_attraction(
+      &self,
+      _: NewAttraction,
+      _: &Actor,
+    ) -> sqlx::Result<Attraction> {
+      todo!()
+    }
+
+    async fn update_attraction(
+      &self,
+      _: i32,
+      _: NewAttraction,
+      _: &Actor,
+    ) -> sqlx::Result<Attraction> {
+      todo!()
+    }
+
+    async fn delete_attraction(&self, _: i32, _: &Actor) -> sqlx::Result<u64> {
+      todo!()
+    }
+
+    async fn ratings_of_user(
+      &self,
+      _: i32,
+      _: Option<i32>,
+    ) -> sqlx::Result<Vec<AttractionRating>> {
+      todo!()
+    }
+
+    async fn update_user_rating(
+      &self,
+      _: i32,
+      _: i32,
+      _: BigDecimal,
+      _: NaiveDateTime,
+    ) -> sqlx::Result<Option<AttractionRating>> {
+      todo!()
+    }
+
+    async fn delete_user_rating(&self, _: i32, _: i32) -> sqlx::Result<u64> {
+      todo!()
+    }
+
+    async fn attractions_with_location(&self) -> sqlx::Result<Vec<Attraction>> {
+      todo!()
+    }
+
+    async fn attractions_with_opening_hours(
+      &self,
+    ) -> sqlx::Result<Vec<Attraction>> {
+      todo!()
+    }
+
+    async fn opening_hours_of(
+      &self,
+      _: Vec<i32>,
+    ) -> sqlx::Result<Vec<AttractionOpeningHours>> {
+      todo!()
+    }
+
+    async fn save_opening_hours(
+      &self,
+      _: i32,
+      _: String,
+      _: NaiveDateTime,
+      _: &Actor,
+    ) -> sqlx::Result<AttractionOpeningHours> {
+      todo!()
+    }
+
+    async fn delete_opening_hours(
+      &self,
+      _: i32,
+      _: &Actor,
+    ) -> sqlx::Result<u64> {
+      todo!()
+    }
+
+    async fn holidays_of(
+      &self,
+      _: Vec<i32>,
+      _: NaiveDate,
+      _: NaiveDate,
+    ) -> sqlx::Result<Vec<AttractionHoliday>> {
+      todo!()
+    }
+
+    async fn tags_of(&self, _: i32) -> sqlx::Result<Vec<Tag>> {
+      todo!()
+    }
+
+    async fn tags_by_codes(&self, _: Vec<String>) -> sqlx::Result<Vec<Tag>> {
+      todo!()
+    }
+
+    async fn replace_tags(
+      &self,
+      _: i32,
+      _: Vec<i32>,
+      _: &Actor,
+    ) -> sqlx::Result<()> {
+      todo!()
+    }
+
+    async fn attractions_tagged(
+      &self,
+      _: Vec<String>,
+    ) -> sqlx::Result<Vec<Attraction>> {
+      todo!()
+    }
+
+    async fn attributes_of(
+      &self,
+      _: i32,
+    ) -> sqlx::Result<Option<AttractionAttributes>> {
+      todo!()
+    }
+
+    async fn save_attributes(
+      &self,
+      _: AttractionAttributes,
+      _: &Actor,
+    ) -> sqlx::Result<AttractionAttributes> {
+      todo!()
+    }
+
+    async fn facets(
+      &self,
+      _: Option<i32>,
+      _: Vec<String>,
+    ) -> sqlx::Result<Vec<FacetCount>> {
+      todo!()
+    }
+
+    async fn search_candidates(
+      &self,
+      _: Vec<String>,
+      _: Option<i32>,
+      _: Option<i32>,
+      _: i64,
+    ) -> sqlx::Result<Vec<SearchCandidate>> {
+      todo!()
+    }
+
+    async fn attraction_translations(
+      &self,
+      _: Vec<i32>,
+      _: Vec<String>,
+    ) -> sqlx::Result<Vec<Translation>> {
+      todo!()
+    }
+
+    async fn attractions_in(
+      &self,
+      _: Option<i32>,
+    ) -> sqlx::Result<Vec<Attraction>> {
+      todo!()
+    }
+
+    async fn dismissed_duplicates(
+      &self,
+    ) -> sqlx::Result<Vec<DuplicateDismissal>> {
+      todo!()
+    }
+
+    async fn dismiss_duplicate(&self, _: i32, _: i32) -> sqlx::Result<u64> {
+      todo!()
+    }
+
+    async fn merge_attractions(
+      &self,
+      _: i32,
+      _: i32,
+      _: &Actor,
+    ) -> sqlx::Result<AttractionMerge> {
+      todo!()
+    }
+
+    async fn redirect_of(&self, _: i32) -> sqlx::Result<Option<EntityId>> {
+      todo!()
+    }
+  }
+
+  /// The aggregates, the daily sketches and the pairs of attractions whose
+  /// similarity was saved, in memory.
+  #[derive(Clone, Default)]
+  struct InMemoryAggregates {
+    aggregates: Arc<Mutex<Vec<AttractionRatingAggregate>>>,
+    sketches: Arc<Mutex<Vec<AttractionRatingSketch>>>,
+    similar_pairs: Arc<Mutex<Vec<(i32, i32)>>>,
+  }
+
+  impl InMemoryAggregates {
+    fn aggregate_at(
+      &self,
+      granularity: Granularity,
+      at: NaiveDateTime,
+    ) -> Option<AttractionRatingAggregate> {
+      let aggregates = self.aggregates.lock().unwrap();
+      aggregates
+        .iter()
+        .find(|an_aggregate| {
+          an_aggregate.granularity == granularity.as_str()
+            && an_aggregate.at == at
+        })
+        .cloned()
+    }
+
+    fn sketch_at(&self, at: NaiveDateTime) -> Option<AttractionRatingSketch> {
+      let sketches = self.sketches.lock().unwrap();
+      sketches.iter().find(|a_sketch| a_sketch.at == at).cloned()
+    }
+  }
+
+  #[async_trait]
+  impl SimilarityRepository for InMemoryAggregates {
+    async fn list_aggregates(
+      &self,
+      attraction_id: i32,
+      granularity: Granularity,
+      from: Option<NaiveDateTime>,
+      to: Option<NaiveDateTime>,
+    ) -> sqlx::Result<Vec<AttractionRatingAggregate>> {
+      let aggregates = self.aggregates.lock().unwrap();
+      let mut listed = aggregates
+        .iter()
+        .filter(|an_aggregate| {
+          an_aggregate.attraction_id == attraction_id
+            && an_aggregate.granularity == granularity.as_str()
+            && from.is_none_or(|from| an_aggregate.at >= from)
+            && to.is_none_or(|to| an_aggregate.at < to)
+        })
+        .cloned()
+        .collect::<Vec<AttractionRatingAggregate>>();
+      listed.sort_by_key(|an_aggregate| an_aggregate.at);
+      Ok(listed)
+    }
+
+    async fn group_aggregate_by(
+      &self,
+      attraction_id: i32,
+      granularity: Granularity,
+    ) -> sqlx::Result<Vec<AttractionByDate>> {
+      let aggregates = self.aggregates.lock().unwrap();
+      Ok(
+        aggregates
+          .iter()
+          .filter(|an_aggregate| {
+            an_aggregate.attraction_id == attraction_id
+              && an_aggregate.granularity == granularity.as_str()
+          })
+          .map(|an_aggregate| AttractionByDate {
+            attraction_id,
+            at: Some(an_aggregate.at),
+            rating_count: Some(an_aggregate.rating_count as i64),
+            watermark: an_aggregate.watermark,
+          })
+          .collect(),
+      )
+    }
+
+    async fn save_attraction_rating_aggregate(
+      &self,
+      att_rating_aggregate: AttractionRatingAggregate,
+    ) -> sqlx::Result<EntityId> {
+      let mut aggregates = self.aggregates.lock().unwrap();
+      aggregates.retain(|an_aggregate| {
+        an_aggregate.attraction_id != att_rating_aggregate.attraction_id
+          || an_aggregate.granularity != att_rating_aggregate.granularity
+          || an_aggregate.at != att_rating_aggregate.at
+      });
+      aggregates.push(att_rating_aggregate);
+      Ok(EntityId {
+        id: aggregates.len() as i32,
+      })
+    }
+
+    async fn delete_aggregate(
+      &self,
+      attraction_id: i32,
+      granularity: Granularity,
+      at: NaiveDateTime,
+    ) -> sqlx::Result<()> {
+      self.aggregates.lock().unwrap().retain(|an_aggregate| {
+        an_aggregate.attraction_id != attraction_id
+          || an_aggregate.granularity != granularity.as_str()
+          || an_aggregate.at != at
+      });
+      Ok(())
+    }
+
+    async fn group_sketch_by_date(
+      &self,
+      attraction_id: i32,
+    ) -> sqlx::Result<Vec<AttractionByDate>> {
+      let sketches = self.sketches.lock().unwrap();
+      Ok(
+        sketches
+          .iter()
+          .filter(|a_sketch| a_sketch.attraction_id == attraction_id)
+          .map(|a_sketch| AttractionByDate {
+            attraction_id,
+            at: Some(a_sketch.at),
+            rating_count: Some(a_sketch.rating_count as i64),
+            watermark: a_sketch.watermark,
+          })
+          .collect(),
+      )
+    }
+
+    async fn get_sketch(
+      &self,
+      attraction_id: i32,
+      at: NaiveDateTime,
+    ) -> sqlx::Result<Option<AttractionRatingSketch>> {
+      let sketches = self.sketches.lock().unwrap();
+      Ok(
+        sketches
+          .iter()
+          .find(|a_sketch| {
+            a_sketch.attraction_id == attraction_id && a_sketch.at == at
+          })
+          .cloned(),
+      )
+    }
+
+    async fn list_sketches(
+      &self,
+      attraction_id: i32,
+      from: NaiveDateTime,
+      to: NaiveDateTime,
+    ) -> sqlx::Result<Vec<AttractionRatingSketch>> {
+      let sketches = self.sketches.lock().unwrap();
+      let mut listed = sketches
+        .iter()
+        .filter(|a_sketch| {
+          a_sketch.attraction_id == attraction_id
+            && a_sketch.at >= from
+            && a_sketch.at < to
+        })
+        .cloned()
+        .collect::<Vec<AttractionRatingSketch>>();
+      listed.sort_by_key(|a_sketch| a_sketch.at);
+      Ok(listed)
+    }
+
+    async fn save_sketch(
+      &self,
+      sketch: AttractionRatingSketch,
+    ) -> sqlx::Result<EntityId> {
+      let mut sketches = self.sketches.lock().unwrap();
+      sketches.retain(|a_sketch| {
+        a_sketch.attraction_id != sketch.attraction_id
+          || a_sketch.at != sketch.at
+      });
+      sketches.push(sketch);
+      Ok(EntityId {
+        id: sketches.len() as i32,
+      })
+    }
+
+    async fn delete_sketch(
+      &self,
+      attraction_id: i32,
+      at: NaiveDateTime,
+    ) -> sqlx::Result<()> {
+      self.sketches.lock().unwrap().retain(|a_sketch| {
+        a_sketch.attraction_id != attraction_id || a_sketch.at != at
+      });
+      Ok(())
+    }
+
+    async fn list_anomalies(
+      &self,
+      _: Option<i32>,
+      _: Option<NaiveDateTime>,
+      _: Option<NaiveDateTime>,
+    ) -> sqlx::Result<Vec<AttractionRatingAnomaly>> {
+      todo!()
+    }
+
+    async fn replace_anomalies(
+      &self,
+      _: i32,
+      _: Vec<AttractionRatingAnomaly>,
+    ) -> sqlx::Result<()> {
+      todo!()
+    }
+
+    async fn get_info(
+      &self,
+      attraction_id: i32,
+      _: bool,
+    ) -> sqlx::Result<Option<AttractionInfo>> {
+      let latest = self
+        .list_aggregates(attraction_id, Granularity::Day, None, None)
+        .await?
+        .pop();
+      Ok(latest.map(|an_aggregate| AttractionInfo {
+        attraction_id,
+        attraction_type_id: 1,
+        avg_rating: an_aggregate.average,
+        latitude: None,
+        longitude: None,
+        tags: vec![],
+      }))
+    }
+
+    async fn save_similarity(
+      &self,
+      similarity: SimilarityBetweenAttraction,
+    ) -> sqlx::Result<EntityId> {
+      let mut similar_pairs = self.similar_pairs.lock().unwrap();
+      similar_pairs
+        .push((similarity.attraction_id, similarity.to_attraction_id));
+      Ok(EntityId {
+        id: similar_pairs.len() as i32,
+      })
+    }
+  }
+
+  const ATTRACTION_ID: i32 = 1;
+
+  fn decimal(value: &str) -> BigDecimal {
+    BigDecimal::from_str(value).unwrap()
+  }
+
+  fn day(a_day: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 3, a_day)
+      .unwrap()
+      .and_hms_opt(0, 0, 0)
+      .unwrap()
+  }
+
+  /// A rating made at noon of the day and written at the given moment.
+  fn rating(
+    id: i32,
+    a_day: u32,
+    rate: &str,
+    updated_at: NaiveDateTime,
+  ) -> AttractionRating {
+    AttractionRating {
+      id,
+      at: day(a_day) + Duration::hours(12),
+      attraction_id: ATTRACTION_ID,
+      rate: decimal(rate),
+      updated_at,
+      source: String::from("WEB"),
+      submitter: None,
+      fingerprint: None,
+      status: RatingStatus::Accepted.to_string(),
+      fraud_score: decimal("0"),
+      fraud_reasons: vec![],
+      user_id: None,
+    }
+  }
+
+  fn aggregation_of(
+    ratings: &InMemoryRatings,
+    aggregates: &InMemoryAggregates,
+    granularity: Granularity,
+    use_sketches: bool,
+  ) -> AttractionSimilarity<InMemoryRatings, InMemoryAggregates> {
+    AttractionSimilarity::new(
+      ratings.clone(),
+      aggregates.clone(),
+      AggregationSettings {
+        granularities: vec![granularity],
+        use_sketches,
+        anomaly: AnomalySettings {
+          enabled: false,
+          ..AnomalySettings::default()
+        },
+        ..AggregationSettings::default()
+      },
+    )
+  }
+
+  #[tokio::test]
+  async fn a_late_rating_refreshes_the_aggregate_of_its_day() {
+    for use_sketches in [false, true] {
+      let ratings = InMemoryRatings::default();
+      let aggregates = InMemoryAggregates::default();
+      let aggregation =
+        aggregation_of(&ratings, &aggregates, Granularity::Day, use_sketches);
+      ratings.rate(rating(1, 1, "0.25", day(1)));
+      ratings.rate(rating(2, 1, "0.75", day(1)));
+      aggregation.aggregate_for(ATTRACTION_ID).await.unwrap();
+
+      let late = day(5);
+      ratings.rate(rating(3, 1, "1", late));
+      aggregation.aggregate_for(ATTRACTION_ID).await.unwrap();
+
+      let refreshed =
+        aggregates.aggregate_at(Granularity::Day, day(1)).unwrap();
+      assert_eq!(3, refreshed.rating_count);
+      assert_eq!(decimal("1"), refreshed.max_rate);
+      assert_eq!(Some(late), refreshed.watermark);
+    }
+  }
+
+  #[tokio::test]
+  async fn an_edited_rating_refreshes_the_aggregate_of_its_day() {
+    for use_sketches in [false, true] {
+      let ratings = InMemoryRatings::default();
+      let aggregates = InMemoryAggregates::default();
+      let aggregation =
+        aggregation_of(&ratings, &aggregates, Granularity::Day, use_sketches);
+      ratings.rate(rating(1, 1, "0.25", day(1)));
+      ratings.rate(rating(2, 1, "0.75", day(1)));
+      aggregation.aggregate_for(ATTRACTION_ID).await.unwrap();
+
+      // The same number of ratings, only the watermark moves.
+      ratings.edit(2, "0.5", day(5));
+      aggregation.aggregate_for(ATTRACTION_ID).await.unwrap();
+
+      let refreshed =
+        aggregates.aggregate_at(Granularity::Day, day(1)).unwrap();
+      assert_eq!(2, refreshed.rating_count);
+      assert_eq!(decimal("0.5"), refreshed.max_rate);
+      assert_eq!(decimal("0.375"), refreshed.average);
+    }
+  }
+
+  #[tokio::test]
+  async fn a_deleted_rating_refreshes_or_removes_the_aggregate_of_its_day() {
+    for use_sketches in [false, true] {
+      let ratings = InMemoryRatings::default();
+      let aggregates = InMemoryAggregates::default();
+      let aggregation =
+        aggregation_of(&ratings, &aggregates, Granularity::Day, use_sketches);
+      ratings.rate(rating(1, 1, "0.25", day(1)));
+      ratings.rate(rating(2, 1, "0.75", day(1)));
+      ratings.rate(rating(3, 2, "0.5", day(2)));
+      aggregation.aggregate_for(ATTRACTION_ID).await.unwrap();
+
+      ratings.delete(2);
+      ratings.delete(3);
+      aggregation.aggregate_for(ATTRACTION_ID).await.unwrap();
+
+      let refreshed =
+        aggregates.aggregate_at(Granularity::Day, day(1)).unwrap();
+      assert_eq!(1, refreshed.rating_count);
+      assert_eq!(decimal("0.25"), refreshed.max_rate);
+      assert!(aggregates.aggregate_at(Granularity::Day, day(2)).is_none());
+      assert!(aggregates.sketch_at(day(2)).is_none());
+    }
+  }
+
+  #[tokio::test]
+  async fn a_rolling_window_is_aggregated_every_day_it_covers() {
+    for use_sketches in [false, true] {
+      let ratings = InMemoryRatings::default();
+      let aggregates = InMemoryAggregates::default();
+      let aggregation = aggregation_of(
+        &ratings,
+        &aggregates,
+        Granularity::Rolling7Days,
+        use_sketches,
+      );
+      ratings.rate(rating(1, 1, "0.25", day(1)));
+      ratings.rate(rating(2, 3, "0.75", day(3)));
+      aggregation.aggregate_for(ATTRACTION_ID).await.unwrap();
+
+      let count_at = |a_day: u32| {
+        aggregates
+          .aggregate_at(Granularity::Rolling7Days, day(a_day))
+          .map(|an_aggregate| an_aggregate.rating_count)
+      };
+      assert_eq!(
+        vec![Some(1), Some(1), Some(2)],
+        vec![count_at(1), count_at(2), count_at(3)]
+      );
+
+      // A late rating changes every window that covers its day.
+      ratings.rate(rating(3, 2, "0.5", day(5)));
+      aggregation.aggregate_for(ATTRACTION_ID).await.unwrap();
+      assert_eq!(
+        vec![Some(1), Some(2), Some(3)],
+        vec![count_at(1), count_at(2), count_at(3)]
+      );
+    }
+  }
+
+  #[tokio::test]
+  async fn the_new_ratings_are_added_to_the_sketch_of_the_day() {
+    let ratings = InMemoryRatings::default();
+    let aggregates = InMemoryAggregates::default();
+    let aggregation =
+      aggregation_of(&ratings, &aggregates, Granularity::Day, true);
+    ratings.rate(rating(1, 1, "0.25", day(1)));
+    ratings.rate(rating(2, 1, "0.75", day(1)));
+    aggregation.aggregate_for(ATTRACTION_ID).await.unwrap();
+    ratings.asked_since.lock().unwrap().clear();
+
+    ratings.rate(rating(3, 1, "0.5", day(5)));
+    ratings.rate(rating(4, 1, "1", day(5)));
+    aggregation.aggregate_for(ATTRACTION_ID).await.unwrap();
+    assert_eq!(vec![2], *ratings.asked_since.lock().unwrap());
+    let updated = aggregates.sketch_at(day(1)).unwrap();
+
+    // The same day sketched from scratch.
+    let rebuilt_aggregates = InMemoryAggregates::default();
+    aggregation_of(&ratings, &rebuilt_aggregates, Granularity::Day, true)
+      .aggregate_for(ATTRACTION_ID)
+      .await
+      .unwrap();
+    let rebuilt = rebuilt_aggregates.sketch_at(day(1)).unwrap();
+
+    assert_eq!(rebuilt.rating_count, updated.rating_count);
+    assert_eq!(rebuilt.last_rating_id, updated.last_rating_id);
+    assert_eq!(rebuilt.watermark, updated.watermark);
+    let statistics_of = |a_sketch: &AttractionRatingSketch| {
+      let statistics = RatingSketch::from_bytes(&a_sketch.sketch)
+        .unwrap()
+        .statistics()
+        .unwrap();
+      (
+        statistics.count,
+        statistics.average,
+        statistics.median,
+        statistics.histogram,
+      )
+    };
+    assert_eq!(statistics_of(&rebuilt), statistics_of(&updated));
+  }
+
+  #[tokio::test]
+  async fn an_edited_rating_rebuilds_the_sketch_of_the_day() {
+    let ratings = InMemoryRatings::default();
+    let aggregates = InMemoryAggregates::default();
+    let aggregation =
+      aggregation_of(&ratings, &aggregates, Granularity::Day, true);
+    ratings.rate(rating(1, 1, "0.25", day(1)));
+    ratings.rate(rating(2, 1, "0.75", day(1)));
+    aggregation.aggregate_for(ATTRACTION_ID).await.unwrap();
+    ratings.asked_since.lock().unwrap().clear();
+
+    ratings.edit(1, "0.5", day(5));
+    aggregation.aggregate_for(ATTRACTION_ID).await.unwrap();
+
+    assert_eq!(vec![2, 0], *ratings.asked_since.lock().unwrap());
+    let rebuilt = aggregates.sketch_at(day(1)).unwrap();
+    assert_eq!(2, rebuilt.rating_count);
+    assert_eq!(Some(day(5)), rebuilt.watermark);
+  }
+
+  #[tokio::test]
+  async fn the_periods_with_few_ratings_follow_the_percentile_method() {
+    let percentile_95_with = |exact_up_to: u64| async move {
+      let ratings = InMemoryRatings::default();
+      let aggregates = InMemoryAggregates::default();
+      ratings.rate(rating(1, 1, "0.2", day(1)));
+      ratings.rate(rating(2, 1, "0.8", day(1)));
+      AttractionSimilarity::new(
+        ratings.clone(),
+        aggregates.clone(),
+        AggregationSettings {
+          percentile_method: PercentileMethod::HyndmanFan7,
+          granularities: vec![Granularity::Day],
+          use_sketches: true,
+          exact_up_to,
+          anomaly: AnomalySettings {
+            enabled: false,
+            ..AnomalySettings::default()
+          },
+        },
+      )
+      .aggregate_for(ATTRACTION_ID)
+      .await
+      .unwrap();
+      aggregates
+        .aggregate_at(Granularity::Day, day(1))
+        .unwrap()
+        .ninety_five_percentile
+    };
+
+    assert_eq!(decimal("0.77"), percentile_95_with(2).await);
+    // Above the threshold, the t-digest estimate ignores the method.
+    assert_eq!(decimal("0.8"), percentile_95_with(1).await);
+  }
+
+  #[tokio::test]
+  async fn the_attractions_without_a_daily_aggregate_have_no_similarity() {
+    let ratings = InMemoryRatings::default();
+    let aggregates = InMemoryAggregates::default();
+    let aggregation =
+      aggregation_of(&ratings, &aggregates, Granularity::Day, false);
+    for id in 1..=3 {
+      ratings.rate(AttractionRating {
+        attraction_id: id,
+        ..rating(id, 1, "0.5", day(1))
+      });
+    }
+    // Every rating of the second attraction is waiting for a review.
+    ratings.ratings.lock().unwrap()[1].status =
+      RatingStatus::Quarantined.to_string();
+    for id in 1..=3 {
+      aggregation.aggregate_for(id).await.unwrap();
+    }
+
+    aggregation
+      .generate_similarity(SimilarityCalculator)
+      .await
+      .unwrap();
+
+    assert_eq!(vec![(1, 3)], *aggregates.similar_pairs.lock().unwrap());
+  }
+}
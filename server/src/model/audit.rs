@@ -0,0 +1,255 @@
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::FromRow;
+use std::{fmt, str::FromStr};
+
+/// The user that writes the catalogue, recorded in the audit log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Actor {
+  pub id: i32,
+  pub username: String,
+}
+
+/// The tables of the catalogue whose writes are audited. The rows of the
+/// tables that belong to another one, like the tags of an attraction, are
+/// logged under the id of their owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum AuditedEntity {
+  Attraction,
+  AttractionType,
+  Country,
+  City,
+  Tag,
+  AttractionTag,
+  AttractionAttribute,
+  AttractionOpeningHours,
+  AttractionTranslation,
+  AttractionTypeTranslation,
+  CountryTranslation,
+  CityTranslation,
+  Holiday,
+}
+
+impl AuditedEntity {
+  /// The name of the table.
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      AuditedEntity::Attraction => "attraction",
+      AuditedEntity::AttractionType => "attraction_type",
+      AuditedEntity::Country => "country",
+      AuditedEntity::City => "city",
+      AuditedEntity::Tag => "tag",
+      AuditedEntity::AttractionTag => "attraction_tag",
+      AuditedEntity::AttractionAttribute => "attraction_attribute",
+      AuditedEntity::AttractionOpeningHours => "attraction_opening_hours",
+      AuditedEntity::AttractionTranslation => "attraction_translation",
+      AuditedEntity::AttractionTypeTranslation => "attraction_type_translation",
+      AuditedEntity::CountryTranslation => "country_translation",
+      AuditedEntity::CityTranslation => "city_translation",
+      AuditedEntity::Holiday => "holiday",
+    }
+  }
+
+  /// Whether the rows are deleted softly, only those can be restored.
+  pub fn deletes_softly(&self) -> bool {
+    matches!(
+      self,
+      AuditedEntity::Attraction
+        | AuditedEntity::AttractionType
+        | AuditedEntity::Country
+        | AuditedEntity::City
+        | AuditedEntity::Tag
+    )
+  }
+}
+
+impl fmt::Display for AuditedEntity {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+/// The entities are named by their table, or as in the paths of the api,
+/// like "attraction-type".
+impl FromStr for AuditedEntity {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_lowercase().replace('-', "_").as_str() {
+      "attraction" => Ok(AuditedEntity::Attraction),
+      "attraction_type" => Ok(AuditedEntity::AttractionType),
+      "country" => Ok(AuditedEntity::Country),
+      "city" => Ok(AuditedEntity::City),
+      "tag" => Ok(AuditedEntity::Tag),
+      "attraction_tag" => Ok(AuditedEntity::AttractionTag),
+      "attraction_attribute" => Ok(AuditedEntity::AttractionAttribute),
+      "attraction_opening_hours" => Ok(AuditedEntity::AttractionOpeningHours),
+      "attraction_translation" => Ok(AuditedEntity::AttractionTranslation),
+      "attraction_type_translation" => {
+        Ok(AuditedEntity::AttractionTypeTranslation)
+      },
+      "country_translation" => Ok(AuditedEntity::CountryTranslation),
+      "city_translation" => Ok(AuditedEntity::CityTranslation),
+      "holiday" => Ok(AuditedEntity::Holiday),
+      _ => Err(format!("Unknown audited entity: {}", s)),
+    }
+  }
+}
+
+impl TryFrom<String> for AuditedEntity {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+/// What a write did to a row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum AuditAction {
+  Create,
+  Update,
+  /// Deleted softly, it can be restored.
+  Delete,
+  Restore,
+  /// Removed for good, like the attractions merged into another one.
+  Purge,
+}
+
+impl AuditAction {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      AuditAction::Create => "CREATE",
+      AuditAction::Update => "UPDATE",
+      AuditAction::Delete => "DELETE",
+      AuditAction::Restore => "RESTORE",
+      AuditAction::Purge => "PURGE",
+    }
+  }
+}
+
+impl fmt::Display for AuditAction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+impl FromStr for AuditAction {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_uppercase().as_str() {
+      "CREATE" => Ok(AuditAction::Create),
+      "UPDATE" => Ok(AuditAction::Update),
+      "DELETE" => Ok(AuditAction::Delete),
+      "RESTORE" => Ok(AuditAction::Restore),
+      "PURGE" => Ok(AuditAction::Purge),
+      _ => Err(format!("Unknown audit action: {}", s)),
+    }
+  }
+}
+
+impl TryFrom<String> for AuditAction {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+/// A write of a row of the catalogue. The rows are the whole rows before and
+/// after the write, none when it didn't exist, and the changes are the
+/// columns with a different value, like
+/// `{"description": {"before": "Louvre", "after": "Musée du Louvre"}}`.
+#[derive(FromRow, Debug, Clone)]
+pub struct AuditEntry {
+  pub id: i32,
+  pub entity: String,
+  pub entity_id: i32,
+  pub action: String,
+  /// The user that made the write, none if it was made outside the
+  /// application.
+  pub actor_id: Option<i32>,
+  pub actor: Option<String>,
+  pub before: Option<Value>,
+  pub after: Option<Value>,
+  pub changes: Value,
+  pub recorded_at: NaiveDateTime,
+}
+
+/// Which entries of the audit log to browse, the latest first.
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+  pub entity: Option<AuditedEntity>,
+  pub entity_id: Option<i32>,
+  pub actor_id: Option<i32>,
+  pub action: Option<AuditAction>,
+  /// Only the entries older than this one, to page through the log.
+  pub before_id: Option<i32>,
+  pub limit: usize,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const ENTITIES: [AuditedEntity; 13] = [
+    AuditedEntity::Attraction,
+    AuditedEntity::AttractionType,
+    AuditedEntity::Country,
+    AuditedEntity::City,
+    AuditedEntity::Tag,
+    AuditedEntity::AttractionTag,
+    AuditedEntity::AttractionAttribute,
+    AuditedEntity::AttractionOpeningHours,
+    AuditedEntity::AttractionTranslation,
+    AuditedEntity::AttractionTypeTranslation,
+    AuditedEntity::CountryTranslation,
+    AuditedEntity::CityTranslation,
+    AuditedEntity::Holiday,
+  ];
+
+  #[test]
+  fn the_entities_are_named_by_their_table_or_their_path() {
+    assert_eq!(
+      "attraction-type".parse::<AuditedEntity>(),
+      Ok(AuditedEntity::AttractionType)
+    );
+    assert_eq!(" City ".parse::<AuditedEntity>(), Ok(AuditedEntity::City));
+    for an_entity in ENTITIES {
+      assert_eq!(an_entity.as_str().parse::<AuditedEntity>(), Ok(an_entity));
+    }
+  }
+
+  #[test]
+  fn the_unknown_entities_are_rejected() {
+    assert_eq!(
+      "opening-hours".parse::<AuditedEntity>(),
+      Err(String::from("Unknown audited entity: opening-hours"))
+    );
+  }
+
+  #[test]
+  fn only_the_entities_deleted_softly_can_be_restored() {
+    let restorable = ENTITIES
+      .into_iter()
+      .filter(AuditedEntity::deletes_softly)
+      .map(|an_entity| an_entity.as_str())
+      .collect::<Vec<&str>>();
+    assert_eq!(
+      restorable,
+      vec!["attraction", "attraction_type", "country", "city", "tag"]
+    );
+  }
+
+  #[test]
+  fn the_actions_are_parsed_in_any_case() {
+    assert_eq!("restore".parse::<AuditAction>(), Ok(AuditAction::Restore));
+    assert_eq!(" Purge ".parse::<AuditAction>(), Ok(AuditAction::Purge));
+    assert!("drop".parse::<AuditAction>().is_err());
+    assert_eq!(AuditAction::Purge.to_string(), "PURGE");
+  }
+}
@@ -0,0 +1,220 @@
+use crate::{
+  db::database::{is_foreign_key_violation, is_unique_violation},
+  model::{
+    audit::{Actor, AuditEntry, AuditFilter, AuditedEntity},
+    audit_repository::AuditRepository,
+  },
+};
+use async_trait::async_trait;
+
+/// The entries of the audit log browsed when the filter doesn't limit them.
+pub const DEFAULT_AUDIT_LIMIT: usize = 50;
+/// The most entries of the audit log browsed at once.
+pub const MAX_AUDIT_LIMIT: usize = 200;
+
+/// The history of the writes of the catalogue and the restore of the
+/// deleted rows.
+#[async_trait]
+pub trait AuditController: Send + Sync + 'static {
+  async fn history(
+    &self,
+    filter: AuditFilter,
+  ) -> Result<Vec<AuditEntry>, String>;
+  async fn restore(
+    &self,
+    entity: AuditedEntity,
+    id: i32,
+    actor: Actor,
+  ) -> Result<bool, String>;
+}
+
+#[derive(Clone)]
+pub struct AuditControllerImpl<AuditRepo> {
+  audit_repository: AuditRepo,
+}
+
+impl<AuditRepo> AuditControllerImpl<AuditRepo>
+where
+  AuditRepo: AuditRepository,
+{
+  pub fn new(audit_repository: AuditRepo) -> Self {
+    AuditControllerImpl {
+      audit_repository,
+    }
+  }
+}
+
+#[async_trait]
+impl<AuditRepo> AuditController for AuditControllerImpl<AuditRepo>
+where
+  AuditRepo: AuditRepository + Send + Sync + 'static,
+{
+  /// Returns an error if the limit is out of range.
+  async fn history(
+    &self,
+    filter: AuditFilter,
+  ) -> Result<Vec<AuditEntry>, String> {
+    if !(1..=MAX_AUDIT_LIMIT).contains(&filter.limit) {
+      return Err(format!(
+        "The limit must be between 1 and {MAX_AUDIT_LIMIT}, got {}",
+        filter.limit
+      ));
+    }
+    self
+      .audit_repository
+      .audit_entries(filter)
+      .await
+      .map_err(|e| e.to_string())
+  }
+
+  /// Returns false if the row doesn't exist or isn't deleted, and an error
+  /// if it can't be restored.
+  async fn restore(
+    &self,
+    entity: AuditedEntity,
+    id: i32,
+    actor: Actor,
+  ) -> Result<bool, String> {
+    match self.audit_repository.restore(entity, id, &actor).await {
+      Ok(restored) => Ok(restored > 0),
+      Err(e) if is_foreign_key_violation(&e) => {
+        Err(format!("The {entity} {id} references a deleted row"))
+      },
+      Err(e) if is_unique_violation(&e) => Err(format!(
+        "A {entity} with the same code as {id} was created after deleting it"
+      )),
+      Err(e) => Err(e.to_string()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::NaiveDateTime;
+  use serde_json::json;
+  use std::sync::Mutex;
+
+  /// The log and the deleted rows in memory.
+  #[derive(Default)]
+  struct InMemoryAuditRepo {
+    entries: Vec<AuditEntry>,
+    deleted: Mutex<Vec<(AuditedEntity, i32)>>,
+    restored_by: Mutex<Vec<Actor>>,
+  }
+
+  #[async_trait]
+  impl AuditRepository for InMemoryAuditRepo {
+    async fn audit_entries(
+      &self,
+      filter: AuditFilter,
+    ) -> sqlx::Result<Vec<AuditEntry>> {
+      Ok(
+        self
+          .entries
+          .iter()
+          .filter(|an_entry| {
+            filter
+              .entity
+              .is_none_or(|entity| an_entry.entity == entity.as_str())
+          })
+          .take(filter.limit)
+          .cloned()
+          .collect(),
+      )
+    }
+
+    async fn restore(
+      &self,
+      entity: AuditedEntity,
+      id: i32,
+      actor: &Actor,
+    ) -> sqlx::Result<u64> {
+      let mut deleted = self.deleted.lock().unwrap();
+      let before = deleted.len();
+      deleted.retain(|a_row| *a_row != (entity, id));
+      let restored = (before - deleted.len()) as u64;
+      if restored > 0 {
+        self.restored_by.lock().unwrap().push(actor.clone());
+      }
+      Ok(restored)
+    }
+  }
+
+  fn entry(id: i32, entity: &str) -> AuditEntry {
+    AuditEntry {
+      id,
+      entity: String::from(entity),
+      entity_id: 1,
+      action: String::from("CREATE"),
+      actor_id: Some(1),
+      actor: Some(String::from("admin")),
+      before: None,
+      after: Some(json!({"id": 1})),
+      changes: json!({}),
+      recorded_at: NaiveDateTime::default(),
+    }
+  }
+
+  fn admin() -> Actor {
+    Actor {
+      id: 1,
+      username: String::from("admin"),
+    }
+  }
+
+  #[tokio::test]
+  async fn the_history_is_filtered_and_limited() {
+    let controller = AuditControllerImpl::new(InMemoryAuditRepo {
+      entries: vec![entry(3, "tag"), entry(2, "city"), entry(1, "tag")],
+      ..Default::default()
+    });
+    let history = controller
+      .history(AuditFilter {
+        entity: Some(AuditedEntity::Tag),
+        limit: 1,
+        ..Default::default()
+      })
+      .await
+      .unwrap();
+    assert_eq!(
+      history
+        .iter()
+        .map(|an_entry| an_entry.id)
+        .collect::<Vec<i32>>(),
+      vec![3]
+    );
+  }
+
+  #[tokio::test]
+  async fn a_limit_out_of_range_is_rejected() {
+    let controller = AuditControllerImpl::new(InMemoryAuditRepo::default());
+    for limit in [0, MAX_AUDIT_LIMIT + 1] {
+      let filter = AuditFilter {
+        limit,
+        ..Default::default()
+      };
+      assert!(controller.history(filter).await.is_err());
+    }
+  }
+
+  #[tokio::test]
+  async fn a_deleted_row_is_restored_once_by_the_actor() {
+    let controller = AuditControllerImpl::new(InMemoryAuditRepo {
+      deleted: Mutex::new(vec![(AuditedEntity::Tag, 7)]),
+      ..Default::default()
+    });
+    assert_eq!(
+      controller.restore(AuditedEntity::Tag, 7, admin()).await,
+      Ok(true)
+    );
+    assert_eq!(
+      controller.restore(AuditedEntity::Tag, 7, admin()).await,
+      Ok(false)
+    );
+    assert_eq!(
+      *controller.audit_repository.restored_by.lock().unwrap(),
+      vec![admin()]
+    );
+  }
+}
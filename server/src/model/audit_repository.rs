@@ -0,0 +1,180 @@
+use crate::{
+  db::database::DbConnection,
+  model::audit::{Actor, AuditEntry, AuditFilter, AuditedEntity},
+};
+use async_trait::async_trait;
+use sqlx::PgConnection;
+
+/// Make the writes of the transaction on behalf of the actor, the audit log
+/// records them with it.
+pub async fn act_as(
+  transaction: &mut PgConnection,
+  actor: &Actor,
+) -> sqlx::Result<()> {
+  sqlx::query!(
+    r#"
+    SELECT set_config('cala.actor_id', $1, true) AS actor_id,
+      set_config('cala.actor', $2, true) AS actor
+    "#,
+    actor.id.to_string(),
+    actor.username
+  )
+  .fetch_one(transaction)
+  .await
+  .map(|_| ())
+}
+
+#[async_trait]
+pub trait AuditRepository {
+  async fn audit_entries(
+    &self,
+    filter: AuditFilter,
+  ) -> sqlx::Result<Vec<AuditEntry>>;
+  async fn restore(
+    &self,
+    entity: AuditedEntity,
+    id: i32,
+    actor: &Actor,
+  ) -> sqlx::Result<u64>;
+}
+
+#[derive(Clone, Default)]
+pub struct DummyAuditRepo;
+
+#[async_trait]
+impl AuditRepository for DummyAuditRepo {
+  async fn audit_entries(
+    &self,
+    _: AuditFilter,
+  ) -> sqlx::Result<Vec<AuditEntry>> {
+    todo!()
+  }
+
+  async fn restore(
+    &self,
+    _: AuditedEntity,
+    _: i32,
+    _: &Actor,
+  ) -> sqlx::Result<u64> {
+    todo!()
+  }
+}
+
+#[derive(Clone)]
+pub struct PgAuditRepository {
+  connection: DbConnection,
+}
+
+impl PgAuditRepository {
+  pub fn new(connection: DbConnection) -> Self {
+    PgAuditRepository {
+      connection,
+    }
+  }
+}
+
+#[async_trait]
+impl AuditRepository for PgAuditRepository {
+  /// Returns the entries that match the filter, the latest first.
+  async fn audit_entries(
+    &self,
+    filter: AuditFilter,
+  ) -> sqlx::Result<Vec<AuditEntry>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      AuditEntry,
+      r#"
+      SELECT * FROM audit_log
+      WHERE ($1::varchar IS NULL OR entity = $1)
+      AND ($2::integer IS NULL OR entity_id = $2)
+      AND ($3::integer IS NULL OR actor_id = $3)
+      AND ($4::varchar IS NULL OR action = $4)
+      AND ($5::integer IS NULL OR id < $5)
+      ORDER BY id DESC
+      LIMIT $6
+      "#,
+      filter.entity.map(|an_entity| an_entity.as_str()),
+      filter.entity_id,
+      filter.actor_id,
+      filter.action.map(|an_action| an_action.as_str()),
+      filter.before_id,
+      filter.limit as i64
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// Returns the number of restored rows, none if the row doesn't exist, it
+  /// isn't deleted or the rows of the entity aren't deleted softly. It fails if the row
+  /// references a deleted one, or if a row with the same code was created
+  /// after deleting it.
+  async fn restore(
+    &self,
+    entity: AuditedEntity,
+    id: i32,
+    actor: &Actor,
+  ) -> sqlx::Result<u64> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let restored = match entity {
+      AuditedEntity::Attraction => {
+        sqlx::query!(
+          r#"
+          UPDATE attraction SET deleted_at = NULL
+          WHERE id = $1 AND deleted_at IS NOT NULL
+          "#,
+          id
+        )
+        .execute(&mut *transaction)
+        .await?
+      },
+      AuditedEntity::AttractionType => {
+        sqlx::query!(
+          r#"
+          UPDATE attraction_type SET deleted_at = NULL
+          WHERE id = $1 AND deleted_at IS NOT NULL
+          "#,
+          id
+        )
+        .execute(&mut *transaction)
+        .await?
+      },
+      AuditedEntity::Country => {
+        sqlx::query!(
+          r#"
+          UPDATE country SET deleted_at = NULL
+          WHERE id = $1 AND deleted_at IS NOT NULL
+          "#,
+          id
+        )
+        .execute(&mut *transaction)
+        .await?
+      },
+      AuditedEntity::City => {
+        sqlx::query!(
+          r#"
+          UPDATE city SET deleted_at = NULL
+          WHERE id = $1 AND deleted_at IS NOT NULL
+          "#,
+          id
+        )
+        .execute(&mut *transaction)
+        .await?
+      },
+      AuditedEntity::Tag => {
+        sqlx::query!(
+          r#"
+          UPDATE tag SET deleted_at = NULL
+          WHERE id = $1 AND deleted_at IS NOT NULL
+          "#,
+          id
+        )
+        .execute(&mut *transaction)
+        .await?
+      },
+      _ => return Ok(0),
+    };
+    transaction.commit().await?;
+    Ok(restored.rows_affected())
+  }
+}
@@ -0,0 +1,229 @@
+use crate::model::aggregation_settings::from_env_var;
+use argon2::{
+  password_hash::{
+    rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier,
+    SaltString,
+  },
+  Argon2,
+};
+use chrono::{Duration, NaiveDateTime};
+use jsonwebtoken::{
+  decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use serde::{Deserialize, Serialize};
+
+pub const MIN_USERNAME_LENGTH: usize = 3;
+pub const MAX_USERNAME_LENGTH: usize = 50;
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// How the sessions and the tokens are issued.
+#[derive(Clone)]
+pub struct AuthSettings {
+  /// The secret that signs the JWT.
+  pub jwt_secret: String,
+  pub jwt_ttl: Duration,
+  pub session_ttl: Duration,
+  /// Send the session cookie only over https.
+  pub secure_cookie: bool,
+}
+
+impl AuthSettings {
+  /// Read the settings from the environment, falling back into the defaults
+  /// for the missing or invalid values.
+  /// * JWT_SECRET: the secret of the tokens. Without it a random one is used,
+  ///   so the tokens don't survive a restart.
+  /// * JWT_TTL_MINUTES: 60 by default.
+  /// * SESSION_TTL_HOURS: 24 by default.
+  /// * SECURE_COOKIE: true or false, false by default.
+  pub fn from_env() -> Self {
+    let jwt_secret = match std::env::var("JWT_SECRET") {
+      Ok(secret) if !secret.trim().is_empty() => secret,
+      _ => {
+        println!("xx->> JWT_SECRET NOT DEFINED, USING A RANDOM ONE\n");
+        random_token()
+      },
+    };
+    AuthSettings {
+      jwt_secret,
+      jwt_ttl: Duration::minutes(from_env_var("JWT_TTL_MINUTES").unwrap_or(60)),
+      session_ttl: Duration::hours(
+        from_env_var("SESSION_TTL_HOURS").unwrap_or(24),
+      ),
+      secure_cookie: from_env_var("SECURE_COOKIE").unwrap_or(false),
+    }
+  }
+}
+
+/// The claims of the JWT issued by the login.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Claims {
+  /// The id of the user.
+  pub sub: i32,
+  pub username: String,
+  /// Issued at, in seconds since the epoch.
+  pub iat: i64,
+  /// Expiration, in seconds since the epoch.
+  pub exp: i64,
+}
+
+/// A random and unguessable token, used for the sessions.
+pub fn random_token() -> String {
+  format!(
+    "{}{}",
+    uuid::Uuid::new_v4().simple(),
+    uuid::Uuid::new_v4().simple()
+  )
+}
+
+/// Check the username and the password of a new user.
+///
+/// # Return:
+/// * Ok if they are valid.
+/// * Err with a string with the reason if any of them is invalid.
+pub fn validate_credentials(
+  username: &str,
+  password: &str,
+) -> Result<(), String> {
+  let length = username.chars().count();
+  if !(MIN_USERNAME_LENGTH..=MAX_USERNAME_LENGTH).contains(&length) {
+    return Err(format!(
+      "The username must have between {MIN_USERNAME_LENGTH} and \
+       {MAX_USERNAME_LENGTH} characters"
+    ));
+  }
+  if !username
+    .chars()
+    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+  {
+    return Err(String::from(
+      "The username can only have letters, digits, '_', '-' and '.'",
+    ));
+  }
+  if password.chars().count() < MIN_PASSWORD_LENGTH {
+    return Err(format!(
+      "The password must have at least {MIN_PASSWORD_LENGTH} characters"
+    ));
+  }
+  Ok(())
+}
+
+/// The argon2id hash of the password in the PHC string format, with a random
+/// salt.
+pub fn hash_password(password: &str) -> Result<String, String> {
+  let salt = SaltString::generate(&mut OsRng);
+  Argon2::default()
+    .hash_password(password.as_bytes(), &salt)
+    .map(|hash| hash.to_string())
+    .map_err(|e| e.to_string())
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+  PasswordHash::new(password_hash)
+    .map(|hash| {
+      Argon2::default()
+        .verify_password(password.as_bytes(), &hash)
+        .is_ok()
+    })
+    .unwrap_or(false)
+}
+
+/// Sign a token for the user that expires after the ttl of the settings.
+///
+/// # Return:
+/// * Ok with the token and its expiration.
+/// * Err with a string that represents the error.
+pub fn encode_jwt(
+  user_id: i32,
+  username: &str,
+  now: NaiveDateTime,
+  settings: &AuthSettings,
+) -> Result<(String, NaiveDateTime), String> {
+  let expires_at = now + settings.jwt_ttl;
+  let claims = Claims {
+    sub: user_id,
+    username: username.to_string(),
+    iat: now.and_utc().timestamp(),
+    exp: expires_at.and_utc().timestamp(),
+  };
+  let token = encode(
+    &Header::new(Algorithm::HS256),
+    &claims,
+    &EncodingKey::from_secret(settings.jwt_secret.as_bytes()),
+  )
+  .map_err(|e| e.to_string())?;
+  Ok((token, expires_at))
+}
+
+/// Read the claims of a token, checking its signature and expiration.
+///
+/// # Return:
+/// * Ok with the claims.
+/// * Err with a string if the token is malformed, forged or expired.
+pub fn decode_jwt(
+  token: &str,
+  settings: &AuthSettings,
+) -> Result<Claims, String> {
+  let mut validation = Validation::new(Algorithm::HS256);
+  validation.leeway = 0;
+  decode::<Claims>(
+    token,
+    &DecodingKey::from_secret(settings.jwt_secret.as_bytes()),
+    &validation,
+  )
+  .map(|data| data.claims)
+  .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::Utc;
+
+  fn settings(secret: &str) -> AuthSettings {
+    AuthSettings {
+      jwt_secret: secret.to_string(),
+      jwt_ttl: Duration::minutes(5),
+      session_ttl: Duration::hours(1),
+      secure_cookie: false,
+    }
+  }
+
+  #[test]
+  fn verifies_only_the_hashed_password() {
+    let hash = hash_password("a long password").unwrap();
+    assert!(hash.starts_with("$argon2id$"));
+    assert!(verify_password("a long password", &hash));
+    assert!(!verify_password("another password", &hash));
+    assert!(!verify_password("a long password", "not a hash"));
+  }
+
+  #[test]
+  fn decodes_the_tokens_it_signs() {
+    let now = Utc::now().naive_utc();
+    let (token, expires_at) =
+      encode_jwt(7, "traveller", now, &settings("secret")).unwrap();
+    assert_eq!(expires_at, now + Duration::minutes(5));
+    let claims = decode_jwt(&token, &settings("secret")).unwrap();
+    assert_eq!(claims.sub, 7);
+    assert_eq!(claims.username, "traveller");
+
+    assert!(decode_jwt(&token, &settings("another secret")).is_err());
+    assert!(decode_jwt("not.a.token", &settings("secret")).is_err());
+    let (expired, _) = encode_jwt(
+      7,
+      "traveller",
+      now - Duration::hours(1),
+      &settings("secret"),
+    )
+    .unwrap();
+    assert!(decode_jwt(&expired, &settings("secret")).is_err());
+  }
+
+  #[test]
+  fn validates_the_credentials() {
+    assert!(validate_credentials("traveller", "12345678").is_ok());
+    assert!(validate_credentials("ab", "12345678").is_err());
+    assert!(validate_credentials("with space", "12345678").is_err());
+    assert!(validate_credentials("traveller", "1234567").is_err());
+  }
+}
@@ -0,0 +1,484 @@
+use crate::model::{
+  auth::{self, AuthSettings, Claims},
+  role::Role,
+  user::{User, UserSession},
+  user_repository::UserRepository,
+};
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use std::sync::{Arc, OnceLock};
+
+/// The result of a successful login, the cookie session and the bearer
+/// token.
+#[derive(Debug, Clone)]
+pub struct LoginSession {
+  pub user: User,
+  pub session: UserSession,
+  pub jwt: String,
+  pub jwt_expires_at: NaiveDateTime,
+}
+
+#[async_trait]
+pub trait AuthController: Send + Sync + 'static {
+  async fn register(
+    &self,
+    username: String,
+    password: String,
+  ) -> Result<User, String>;
+  async fn login(
+    &self,
+    username: String,
+    password: String,
+  ) -> Option<LoginSession>;
+  async fn logout(&self, session_token: String) -> Option<()>;
+  async fn user_of_session(&self, session_token: String) -> Option<User>;
+  async fn user_by_id(&self, id: i32) -> Option<User>;
+  async fn list_users(&self) -> Option<Vec<User>>;
+  async fn assign_role(
+    &self,
+    user_id: i32,
+    role: Role,
+  ) -> Result<Option<User>, String>;
+  async fn create_admin(
+    &self,
+    username: String,
+    password: Option<String>,
+  ) -> Result<User, String>;
+  fn claims_of(&self, jwt: &str) -> Result<Claims, String>;
+  fn settings(&self) -> Arc<AuthSettings>;
+}
+
+#[derive(Clone)]
+pub struct AuthControllerImpl<UserRepo> {
+  user_repository: UserRepo,
+  settings: Arc<AuthSettings>,
+}
+
+impl<UserRepo> AuthControllerImpl<UserRepo>
+where
+  UserRepo: UserRepository,
+{
+  pub fn new(user_repository: UserRepo, settings: AuthSettings) -> Self {
+    AuthControllerImpl {
+      user_repository,
+      settings: Arc::new(settings),
+    }
+  }
+}
+
+/// A hash verified when the user doesn't exist, so the login takes the same
+/// time whether the username exists or not.
+fn dummy_hash() -> &'static str {
+  static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+  DUMMY_HASH.get_or_init(|| {
+    auth::hash_password(&auth::random_token()).unwrap_or_default()
+  })
+}
+
+#[async_trait]
+impl<UserRepo> AuthController for AuthControllerImpl<UserRepo>
+where
+  UserRepo: UserRepository + Send + Sync + 'static,
+{
+  /// # Return:
+  /// * Ok with the new user.
+  /// * Err with a string with the reason if the credentials are invalid or
+  ///   the username is taken.
+  async fn register(
+    &self,
+    username: String,
+    password: String,
+  ) -> Result<User, String> {
+    let username = username.trim().to_string();
+    auth::validate_credentials(&username, &password)?;
+    let existing = self
+      .user_repository
+      .user_by_username(username.clone())
+      .await
+      .map_err(|e| e.to_string())?;
+    if existing.is_some() {
+      return Err(format!("The username {username} is already taken"));
+    }
+    let password_hash = auth::hash_password(&password)?;
+    self
+      .user_repository
+      .create_user(username, password_hash)
+      .await
+      .map_err(|e| e.to_string())
+  }
+
+  /// Open a new session and sign a token for the user.
+  ///
+  /// # Return:
+  /// * Some with the session and the token.
+  /// * None if the username or the password are wrong.
+  async fn login(
+    &self,
+    username: String,
+    password: String,
+  ) -> Option<LoginSession> {
+    let user = self
+      .user_repository
+      .user_by_username(username.trim().to_string())
+      .await
+      .ok()?;
+    let Some(user) = user else {
+      auth::verify_password(&password, dummy_hash());
+      return None;
+    };
+    if !auth::verify_password(&password, &user.get_password_hash()) {
+      return None;
+    }
+
+    let now = Utc::now().naive_utc();
+    let session = self
+      .user_repository
+      .create_session(
+        auth::random_token(),
+        user.get_id(),
+        now + self.settings.session_ttl,
+      )
+      .await
+      .ok()?;
+    let (jwt, jwt_expires_at) = auth::encode_jwt(
+      user.get_id(),
+      &user.get_username(),
+      now,
+      &self.settings,
+    )
+    .ok()?;
+    Some(LoginSession {
+      user,
+      session,
+      jwt,
+      jwt_expires_at,
+    })
+  }
+
+  async fn logout(&self, session_token: String) -> Option<()> {
+    self
+      .user_repository
+      .delete_session(session_token)
+      .await
+      .ok()
+  }
+
+  /// Returns the user if the session exists and didn't expire.
+  async fn user_of_session(&self, session_token: String) -> Option<User> {
+    self
+      .user_repository
+      .user_of_session(session_token, Utc::now().naive_utc())
+      .await
+      .ok()?
+  }
+
+  async fn user_by_id(&self, id: i32) -> Option<User> {
+    self.user_repository.user_by_id(id).await.ok()?
+  }
+
+  async fn list_users(&self) -> Option<Vec<User>> {
+    self.user_repository.list_users().await.ok()
+  }
+
+  /// # Return:
+  /// * Ok with Some with the updated user.
+  /// * Ok with None if the user doesn't exist.
+  /// * Err with a string if the user is the last admin and the role would
+  ///   leave the application without admins.
+  async fn assign_role(
+    &self,
+    user_id: i32,
+    role: Role,
+  ) -> Result<Option<User>, String> {
+    let Some(a_user) = self.user_by_id(user_id).await else {
+      return Ok(None);
+    };
+    if a_user.get_role() == Role::Admin && role != Role::Admin {
+      let admins = self
+        .user_repository
+        .count_with_role(Role::Admin)
+        .await
+        .map_err(|e| e.to_string())?;
+      if admins <= 1 {
+        return Err(format!(
+          "The user {} is the last admin",
+          a_user.get_username()
+        ));
+      }
+    }
+    self
+      .user_repository
+      .update_role(user_id, role)
+      .await
+      .map_err(|e| e.to_string())
+  }
+
+  /// Make the user an admin, registering it with the password if it doesn't
+  /// exist. It's how the first admin is created.
+  ///
+  /// # Return:
+  /// * Ok with the admin.
+  /// * Err with a string with the reason if the user doesn't exist and the
+  ///   password is missing or the credentials are invalid.
+  async fn create_admin(
+    &self,
+    username: String,
+    password: Option<String>,
+  ) -> Result<User, String> {
+    let username = username.trim().to_string();
+    let existing = self
+      .user_repository
+      .user_by_username(username.clone())
+      .await
+      .map_err(|e| e.to_string())?;
+    let a_user = match (existing, password) {
+      (Some(a_user), _) => a_user,
+      (None, Some(password)) => {
+        self.register(username.clone(), password).await?
+      },
+      (None, None) => {
+        return Err(format!(
+          "The user {username} doesn't exist and there is no password to \
+           register it"
+        ))
+      },
+    };
+    self
+      .user_repository
+      .update_role(a_user.get_id(), Role::Admin)
+      .await
+      .map_err(|e| e.to_string())?
+      .ok_or(format!("The user {username} doesn't exist"))
+  }
+
+  fn claims_of(&self, jwt: &str) -> Result<Claims, String> {
+    auth::decode_jwt(jwt, &self.settings)
+  }
+
+  fn settings(&self) -> Arc<AuthSettings> {
+    self.settings.clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::Duration;
+  use std::sync::Mutex;
+
+  /// The users and their sessions in memory. New users are viewers, as in
+  /// the database.
+  #[derive(Default)]
+  struct InMemoryUserRepo {
+    users: Mutex<Vec<User>>,
+    sessions: Mutex<Vec<UserSession>>,
+  }
+
+  #[async_trait]
+  impl UserRepository for InMemoryUserRepo {
+    async fn create_user(
+      &self,
+      username: String,
+      password_hash: String,
+    ) -> sqlx::Result<User> {
+      let mut users = self.users.lock().unwrap();
+      let created = User {
+        id: users.len() as i32 + 1,
+        username,
+        password_hash,
+        created_at: NaiveDateTime::default(),
+        role: Role::Viewer.to_string(),
+      };
+      users.push(created.clone());
+      Ok(created)
+    }
+
+    async fn user_by_username(
+      &self,
+      username: String,
+    ) -> sqlx::Result<Option<User>> {
+      let users = self.users.lock().unwrap();
+      Ok(
+        users
+          .iter()
+          .find(|a_user| a_user.username == username)
+          .cloned(),
+      )
+    }
+
+    async fn user_by_id(&self, id: i32) -> sqlx::Result<Option<User>> {
+      let users = self.users.lock().unwrap();
+      Ok(users.iter().find(|a_user| a_user.id == id).cloned())
+    }
+
+    async fn list_users(&self) -> sqlx::Result<Vec<User>> {
+      Ok(self.users.lock().unwrap().clone())
+    }
+
+    async fn count_with_role(&self, role: Role) -> sqlx::Result<i64> {
+      let users = self.users.lock().unwrap();
+      Ok(
+        users
+          .iter()
+          .filter(|a_user| a_user.get_role() == role)
+          .count() as i64,
+      )
+    }
+
+    async fn update_role(
+      &self,
+      id: i32,
+      role: Role,
+    ) -> sqlx::Result<Option<User>> {
+      let mut users = self.users.lock().unwrap();
+      let a_user = users.iter_mut().find(|a_user| a_user.id == id);
+      Ok(a_user.map(|a_user| {
+        a_user.role = role.to_string();
+        a_user.clone()
+      }))
+    }
+
+    async fn create_session(
+      &self,
+      token: String,
+      user_id: i32,
+      expires_at: NaiveDateTime,
+    ) -> sqlx::Result<UserSession> {
+      let session = UserSession {
+        token,
+        user_id,
+        created_at: NaiveDateTime::default(),
+        expires_at,
+      };
+      self.sessions.lock().unwrap().push(session.clone());
+      Ok(session)
+    }
+
+    async fn user_of_session(
+      &self,
+      token: String,
+      now: NaiveDateTime,
+    ) -> sqlx::Result<Option<User>> {
+      let user_id = self
+        .sessions
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|a_session| {
+          a_session.token == token && a_session.expires_at > now
+        })
+        .map(|a_session| a_session.user_id);
+      match user_id {
+        Some(user_id) => self.user_by_id(user_id).await,
+        None => Ok(None),
+      }
+    }
+
+    async fn delete_session(&self, token: String) -> sqlx::Result<()> {
+      self
+        .sessions
+        .lock()
+        .unwrap()
+        .retain(|a_session| a_session.token != token);
+      Ok(())
+    }
+  }
+
+  fn controller() -> AuthControllerImpl<InMemoryUserRepo> {
+    AuthControllerImpl::new(
+      InMemoryUserRepo::default(),
+      AuthSettings {
+        jwt_secret: String::from("a-secret"),
+        jwt_ttl: Duration::minutes(60),
+        session_ttl: Duration::hours(24),
+        secure_cookie: false,
+      },
+    )
+  }
+
+  #[tokio::test]
+  async fn a_registered_user_is_a_viewer() {
+    let controller = controller();
+    let a_user = controller
+      .register(String::from(" traveller "), String::from("a-password"))
+      .await
+      .unwrap();
+    assert_eq!("traveller", a_user.get_username());
+    assert_eq!(Role::Viewer, a_user.get_role());
+    assert_ne!("a-password", a_user.get_password_hash());
+  }
+
+  #[tokio::test]
+  async fn a_username_cant_be_registered_twice() {
+    let controller = controller();
+    let register = |username: &str| {
+      controller.register(String::from(username), String::from("a-password"))
+    };
+    assert!(register("traveller").await.is_ok());
+    assert!(register("traveller").await.is_err());
+  }
+
+  #[tokio::test]
+  async fn the_login_needs_the_password_of_the_user() {
+    let controller = controller();
+    controller
+      .register(String::from("traveller"), String::from("a-password"))
+      .await
+      .unwrap();
+
+    let login = |username: &str, password: &str| {
+      controller.login(String::from(username), String::from(password))
+    };
+    assert!(login("traveller", "another-password").await.is_none());
+    assert!(login("stranger", "a-password").await.is_none());
+
+    let a_session = login("traveller", "a-password").await.unwrap();
+    let claims = controller.claims_of(&a_session.jwt).unwrap();
+    assert_eq!(a_session.user.get_id(), claims.sub);
+    let of_session = controller
+      .user_of_session(a_session.session.get_token())
+      .await
+      .unwrap();
+    assert_eq!(a_session.user.get_id(), of_session.get_id());
+  }
+
+  #[tokio::test]
+  async fn an_existing_user_is_promoted_to_admin() {
+    let controller = controller();
+    let a_user = controller
+      .register(String::from("traveller"), String::from("a-password"))
+      .await
+      .unwrap();
+
+    let an_admin = controller
+      .create_admin(String::from("traveller"), None)
+      .await
+      .unwrap();
+    assert_eq!(a_user.get_id(), an_admin.get_id());
+    assert_eq!(Role::Admin, an_admin.get_role());
+    // The password is only used to register a new user.
+    assert!(controller
+      .login(String::from("traveller"), String::from("a-password"))
+      .await
+      .is_some());
+  }
+
+  #[tokio::test]
+  async fn a_new_admin_is_registered_with_the_password() {
+    let controller = controller();
+    assert!(controller
+      .create_admin(String::from("root"), None)
+      .await
+      .is_err());
+
+    let an_admin = controller
+      .create_admin(String::from("root"), Some(String::from("a-password")))
+      .await
+      .unwrap();
+    assert_eq!(Role::Admin, an_admin.get_role());
+    assert!(controller
+      .login(String::from("root"), String::from("a-password"))
+      .await
+      .is_some());
+  }
+}
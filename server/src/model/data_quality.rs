@@ -0,0 +1,385 @@
+use crate::model::{
+  aggregation_settings::from_env_var,
+  attraction::Attraction,
+  percentile::{percentile, PercentileMethod},
+};
+use chrono::NaiveDateTime;
+use geoutils::Location;
+use sqlx::FromRow;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DataQualitySettings {
+  /// The most offending ids reported by every check.
+  pub sample_size: usize,
+  /// The farthest an attraction can be from the center of its city.
+  pub max_city_distance_km: f64,
+  /// The least attractions with coordinates a city needs to tell where its
+  /// center is.
+  pub min_city_attractions: usize,
+}
+
+impl Default for DataQualitySettings {
+  fn default() -> Self {
+    DataQualitySettings {
+      sample_size: 10,
+      max_city_distance_km: 50.0,
+      min_city_attractions: 3,
+    }
+  }
+}
+
+impl DataQualitySettings {
+  /// Read the settings from the environment, falling back into the defaults
+  /// for the missing or invalid values.
+  /// * DATA_QUALITY_SAMPLE_SIZE and DATA_QUALITY_MIN_CITY_ATTRACTIONS: positive
+  ///   integers.
+  /// * DATA_QUALITY_MAX_CITY_DISTANCE_KM: a number.
+  pub fn from_env() -> Self {
+    let defaults = DataQualitySettings::default();
+    DataQualitySettings {
+      sample_size: from_env_var("DATA_QUALITY_SAMPLE_SIZE")
+        .unwrap_or(defaults.sample_size),
+      max_city_distance_km: from_env_var("DATA_QUALITY_MAX_CITY_DISTANCE_KM")
+        .unwrap_or(defaults.max_city_distance_km),
+      min_city_attractions: from_env_var("DATA_QUALITY_MIN_CITY_ATTRACTIONS")
+        .unwrap_or(defaults.min_city_attractions),
+    }
+  }
+}
+
+/// How bad it is to find offending rows in a check. Only the errors make
+/// the report fail.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+  Warning,
+  Error,
+}
+
+impl Severity {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Severity::Warning => "WARNING",
+      Severity::Error => "ERROR",
+    }
+  }
+}
+
+/// The rows found by a check, counted in the database.
+#[derive(FromRow, Debug, Clone, Default, PartialEq)]
+pub struct CheckCount {
+  pub count: i64,
+  /// The lowest ids of the offending rows.
+  pub sample_ids: Vec<i32>,
+}
+
+/// The outcome of a check of the catalogue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataQualityCheck {
+  pub code: &'static str,
+  /// The table of the offending ids.
+  pub entity: &'static str,
+  pub severity: Severity,
+  pub description: &'static str,
+  pub count: i64,
+  pub sample_ids: Vec<i32>,
+}
+
+impl DataQualityCheck {
+  pub fn new(
+    code: &'static str,
+    entity: &'static str,
+    severity: Severity,
+    description: &'static str,
+    found: CheckCount,
+  ) -> Self {
+    DataQualityCheck {
+      code,
+      entity,
+      severity,
+      description,
+      count: found.count,
+      sample_ids: found.sample_ids,
+    }
+  }
+
+  pub fn failed(&self) -> bool {
+    self.severity == Severity::Error && self.count > 0
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct DataQualityReport {
+  pub checked_at: NaiveDateTime,
+  pub checks: Vec<DataQualityCheck>,
+}
+
+impl DataQualityReport {
+  /// Whether any check with error severity found offending rows.
+  pub fn failed(&self) -> bool {
+    self.checks.iter().any(DataQualityCheck::failed)
+  }
+}
+
+/// The coordinates of an attraction, as far as they can be read.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Coordinates {
+  Missing,
+  Invalid,
+  Valid(f64, f64),
+}
+
+/// Read the coordinates stored as text. They are invalid when only one of
+/// them is stored, when they aren't finite numbers or when they are out of
+/// the range of the latitudes and longitudes.
+fn coordinates_of(an_attraction: &Attraction) -> Coordinates {
+  let parse = |value: &String| {
+    value
+      .trim()
+      .parse::<f64>()
+      .ok()
+      .filter(|a_number| a_number.is_finite())
+  };
+  match (&an_attraction.latitude, &an_attraction.longitude) {
+    (None, None) => Coordinates::Missing,
+    (Some(latitude), Some(longitude)) => {
+      match (parse(latitude), parse(longitude)) {
+        (Some(latitude), Some(longitude))
+          if (-90.0..=90.0).contains(&latitude)
+            && (-180.0..=180.0).contains(&longitude) =>
+        {
+          Coordinates::Valid(latitude, longitude)
+        },
+        _ => Coordinates::Invalid,
+      }
+    },
+    _ => Coordinates::Invalid,
+  }
+}
+
+fn found(mut ids: Vec<i32>, sample_size: usize) -> CheckCount {
+  ids.sort();
+  let count = ids.len() as i64;
+  ids.truncate(sample_size);
+  CheckCount {
+    count,
+    sample_ids: ids,
+  }
+}
+
+/// Check the coordinates of the attractions. Some attractions don't have
+/// coordinates, but the ones that have them must be readable and close to
+/// the center of their city. The center of a city is the median of the
+/// coordinates of its attractions, so a few misplaced ones don't move it,
+/// and the cities with too few attractions to tell aren't checked.
+pub fn check_coordinates(
+  attractions: &[Attraction],
+  settings: &DataQualitySettings,
+) -> Vec<DataQualityCheck> {
+  let mut missing = vec![];
+  let mut invalid = vec![];
+  let mut by_city: HashMap<i32, Vec<(i32, f64, f64)>> = HashMap::new();
+  for an_attraction in attractions {
+    match coordinates_of(an_attraction) {
+      Coordinates::Missing => missing.push(an_attraction.get_id()),
+      Coordinates::Invalid => invalid.push(an_attraction.get_id()),
+      Coordinates::Valid(latitude, longitude) => by_city
+        .entry(an_attraction.get_city_id())
+        .or_default()
+        .push((an_attraction.get_id(), latitude, longitude)),
+    }
+  }
+  let median = |mut values: Vec<f64>| {
+    values.sort_by(f64::total_cmp);
+    percentile(&values, 0.5, PercentileMethod::HyndmanFan7)
+  };
+  let mut far = vec![];
+  for located in by_city.values() {
+    if located.len() < settings.min_city_attractions {
+      continue;
+    }
+    let (Some(latitude), Some(longitude)) = (
+      median(located.iter().map(|(_, latitude, _)| *latitude).collect()),
+      median(located.iter().map(|(_, _, longitude)| *longitude).collect()),
+    ) else {
+      continue;
+    };
+    let center = Location::new(latitude, longitude);
+    for (id, latitude, longitude) in located {
+      let kilometers = Location::new(*latitude, *longitude)
+        .haversine_distance_to(&center)
+        .meters()
+        / 1000.0;
+      if kilometers > settings.max_city_distance_km {
+        far.push(*id);
+      }
+    }
+  }
+  vec![
+    DataQualityCheck::new(
+      "ATTRACTION_WITHOUT_COORDINATES",
+      "attraction",
+      Severity::Warning,
+      "Attractions without latitude and longitude",
+      found(missing, settings.sample_size),
+    ),
+    DataQualityCheck::new(
+      "ATTRACTION_INVALID_COORDINATES",
+      "attraction",
+      Severity::Error,
+      "Attractions with a latitude or a longitude that can't be read or is \
+       out of range",
+      found(invalid, settings.sample_size),
+    ),
+    DataQualityCheck::new(
+      "ATTRACTION_FAR_FROM_CITY",
+      "attraction",
+      Severity::Error,
+      "Attractions too far from the center of their city",
+      found(far, settings.sample_size),
+    ),
+  ]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn attraction(
+    id: i32,
+    city_id: i32,
+    latitude: Option<&str>,
+    longitude: Option<&str>,
+  ) -> Attraction {
+    Attraction {
+      id,
+      description: format!("Attraction {id}"),
+      city_id,
+      latitude: latitude.map(str::to_string),
+      longitude: longitude.map(str::to_string),
+      attraction_type_id: 1,
+      deleted_at: None,
+    }
+  }
+
+  /// Three attractions in the center of Buenos Aires.
+  fn in_buenos_aires() -> Vec<Attraction> {
+    vec![
+      attraction(1, 1, Some("-34.6037"), Some("-58.3816")),
+      attraction(2, 1, Some("-34.5823"), Some("-58.4097")),
+      attraction(3, 1, Some("-34.6158"), Some("-58.3734")),
+    ]
+  }
+
+  fn outcome_of(
+    attractions: Vec<Attraction>,
+    code: &str,
+    settings: &DataQualitySettings,
+  ) -> (i64, Vec<i32>) {
+    check_coordinates(&attractions, settings)
+      .into_iter()
+      .find(|a_check| a_check.code == code)
+      .map(|a_check| (a_check.count, a_check.sample_ids))
+      .unwrap()
+  }
+
+  #[test]
+  fn the_attractions_without_coordinates_are_a_warning() {
+    let mut attractions = in_buenos_aires();
+    attractions.push(attraction(5, 1, None, None));
+    let checks =
+      check_coordinates(&attractions, &DataQualitySettings::default());
+    assert_eq!(checks[0].code, "ATTRACTION_WITHOUT_COORDINATES");
+    assert_eq!(
+      (checks[0].count, checks[0].sample_ids.clone()),
+      (1, vec![5])
+    );
+    assert!(!checks[0].failed());
+  }
+
+  #[test]
+  fn the_coordinates_unreadable_or_out_of_range_are_invalid() {
+    let mut attractions = in_buenos_aires();
+    attractions.extend([
+      attraction(6, 1, Some("abc"), Some("-58.3816")),
+      attraction(7, 1, Some("-34.6037"), None),
+      attraction(8, 1, Some("-134.6"), Some("-58.3816")),
+      attraction(9, 1, Some("NaN"), Some("-58.3816")),
+    ]);
+    assert_eq!(
+      outcome_of(
+        attractions,
+        "ATTRACTION_INVALID_COORDINATES",
+        &DataQualitySettings::default()
+      ),
+      (4, vec![6, 7, 8, 9])
+    );
+  }
+
+  #[test]
+  fn the_attractions_far_from_the_center_of_their_city_are_misplaced() {
+    let mut attractions = in_buenos_aires();
+    // In Paris, but stored in Buenos Aires.
+    attractions.push(attraction(4, 1, Some("48.8606"), Some("2.3376")));
+    assert_eq!(
+      outcome_of(
+        attractions,
+        "ATTRACTION_FAR_FROM_CITY",
+        &DataQualitySettings::default()
+      ),
+      (1, vec![4])
+    );
+  }
+
+  #[test]
+  fn the_cities_with_too_few_attractions_have_no_center() {
+    let attractions = vec![
+      attraction(1, 2, Some("-34.6037"), Some("-58.3816")),
+      attraction(2, 2, Some("48.8606"), Some("2.3376")),
+    ];
+    assert_eq!(
+      outcome_of(
+        attractions,
+        "ATTRACTION_FAR_FROM_CITY",
+        &DataQualitySettings::default()
+      ),
+      (0, vec![])
+    );
+  }
+
+  #[test]
+  fn the_sample_has_the_lowest_offending_ids() {
+    let attractions = (1..=5)
+      .rev()
+      .map(|id| attraction(id, 1, Some("abc"), Some("def")))
+      .collect();
+    let settings = DataQualitySettings {
+      sample_size: 2,
+      ..DataQualitySettings::default()
+    };
+    assert_eq!(
+      outcome_of(attractions, "ATTRACTION_INVALID_COORDINATES", &settings),
+      (5, vec![1, 2])
+    );
+  }
+
+  #[test]
+  fn the_report_fails_only_when_an_error_check_finds_rows() {
+    let mut attractions = in_buenos_aires();
+    attractions.push(attraction(5, 1, None, None));
+    let checks =
+      check_coordinates(&attractions, &DataQualitySettings::default());
+    let report = DataQualityReport {
+      checked_at: NaiveDateTime::default(),
+      checks,
+    };
+    assert!(!report.failed());
+
+    attractions.push(attraction(6, 1, Some("abc"), Some("-58.3816")));
+    let report = DataQualityReport {
+      checked_at: NaiveDateTime::default(),
+      checks: check_coordinates(&attractions, &DataQualitySettings::default()),
+    };
+    assert!(report.failed());
+  }
+}
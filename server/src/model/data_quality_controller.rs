@@ -0,0 +1,119 @@
+use crate::model::{
+  attraction_repository::AttractionRepository,
+  data_quality::{
+    check_coordinates, DataQualityCheck, DataQualityReport,
+    DataQualitySettings, Severity,
+  },
+  data_quality_repository::DataQualityRepository,
+  rating_statistics::{MAX_RATE, MIN_RATE},
+};
+use async_trait::async_trait;
+use chrono::Utc;
+
+#[async_trait]
+pub trait DataQualityController: Send + Sync + 'static {
+  async fn report(&self) -> Result<DataQualityReport, String>;
+}
+
+#[derive(Clone)]
+pub struct DataQualityControllerImpl<AttractionRepo, DataQualityRepo> {
+  attraction_repository: AttractionRepo,
+  data_quality_repository: DataQualityRepo,
+  settings: DataQualitySettings,
+}
+
+impl<AttractionRepo, DataQualityRepo>
+  DataQualityControllerImpl<AttractionRepo, DataQualityRepo>
+where
+  AttractionRepo: AttractionRepository,
+  DataQualityRepo: DataQualityRepository,
+{
+  pub fn new(
+    attraction_repository: AttractionRepo,
+    data_quality_repository: DataQualityRepo,
+    settings: DataQualitySettings,
+  ) -> Self {
+    DataQualityControllerImpl {
+      attraction_repository,
+      data_quality_repository,
+      settings,
+    }
+  }
+}
+
+#[async_trait]
+impl<AttractionRepo, DataQualityRepo> DataQualityController
+  for DataQualityControllerImpl<AttractionRepo, DataQualityRepo>
+where
+  AttractionRepo: AttractionRepository + Send + Sync + 'static,
+  DataQualityRepo: DataQualityRepository + Send + Sync + 'static,
+{
+  /// Run every check of the catalogue. The coordinates are checked here,
+  /// because they are stored as text, and the rest in the database.
+  ///
+  /// # Return:
+  /// * Ok with the outcome of every check, even the ones without offending
+  ///   rows.
+  /// * Err if any check can't be run.
+  async fn report(&self) -> Result<DataQualityReport, String> {
+    let sample_size = self.settings.sample_size as i32;
+    let attractions = self
+      .attraction_repository
+      .attractions_in(None)
+      .await
+      .map_err(|e| e.to_string())?;
+    let mut checks = check_coordinates(&attractions, &self.settings);
+
+    let repository = &self.data_quality_repository;
+    let without_ratings = repository
+      .attractions_without_ratings(sample_size)
+      .await
+      .map_err(|e| e.to_string())?;
+    let without_attractions = repository
+      .cities_without_attractions(sample_size)
+      .await
+      .map_err(|e| e.to_string())?;
+    let out_of_range = repository
+      .ratings_out_of_range(MIN_RATE, MAX_RATE, sample_size)
+      .await
+      .map_err(|e| e.to_string())?;
+    let orphan_aggregates = repository
+      .aggregates_without_ratings(sample_size)
+      .await
+      .map_err(|e| e.to_string())?;
+    checks.extend([
+      DataQualityCheck::new(
+        "ATTRACTION_WITHOUT_RATINGS",
+        "attraction",
+        Severity::Warning,
+        "Attractions without any accepted rating",
+        without_ratings,
+      ),
+      DataQualityCheck::new(
+        "CITY_WITHOUT_ATTRACTIONS",
+        "city",
+        Severity::Warning,
+        "Cities without any attraction",
+        without_attractions,
+      ),
+      DataQualityCheck::new(
+        "RATING_OUT_OF_RANGE",
+        "attraction_rating",
+        Severity::Error,
+        "Ratings with a rate out of the range from 0 to 1",
+        out_of_range,
+      ),
+      DataQualityCheck::new(
+        "AGGREGATE_WITHOUT_RATINGS",
+        "attraction_rating_aggregate",
+        Severity::Error,
+        "Aggregates without any accepted rating in the period they aggregate",
+        orphan_aggregates,
+      ),
+    ]);
+    Ok(DataQualityReport {
+      checked_at: Utc::now().naive_utc(),
+      checks,
+    })
+  }
+}
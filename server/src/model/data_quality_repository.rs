@@ -0,0 +1,193 @@
+use crate::{db::database::DbConnection, model::data_quality::CheckCount};
+use async_trait::async_trait;
+
+/// The checks of the catalogue that are counted in the database. Every
+/// check returns how many rows are offending and the lowest ids of them.
+#[async_trait]
+pub trait DataQualityRepository {
+  async fn attractions_without_ratings(
+    &self,
+    sample_size: i32,
+  ) -> sqlx::Result<CheckCount>;
+  async fn cities_without_attractions(
+    &self,
+    sample_size: i32,
+  ) -> sqlx::Result<CheckCount>;
+  async fn ratings_out_of_range(
+    &self,
+    min_rate: f64,
+    max_rate: f64,
+    sample_size: i32,
+  ) -> sqlx::Result<CheckCount>;
+  async fn aggregates_without_ratings(
+    &self,
+    sample_size: i32,
+  ) -> sqlx::Result<CheckCount>;
+}
+
+#[derive(Clone, Default)]
+pub struct DummyDataQualityRepo;
+
+#[async_trait]
+impl DataQualityRepository for DummyDataQualityRepo {
+  async fn attractions_without_ratings(
+    &self,
+    _: i32,
+  ) -> sqlx::Result<CheckCount> {
+    todo!()
+  }
+
+  async fn cities_without_attractions(
+    &self,
+    _: i32,
+  ) -> sqlx::Result<CheckCount> {
+    todo!()
+  }
+
+  async fn ratings_out_of_range(
+    &self,
+    _: f64,
+    _: f64,
+    _: i32,
+  ) -> sqlx::Result<CheckCount> {
+    todo!()
+  }
+
+  async fn aggregates_without_ratings(
+    &self,
+    _: i32,
+  ) -> sqlx::Result<CheckCount> {
+    todo!()
+  }
+}
+
+#[derive(Clone)]
+pub struct PgDataQualityRepository {
+  connection: DbConnection,
+}
+
+impl PgDataQualityRepository {
+  pub fn new(connection: DbConnection) -> Self {
+    PgDataQualityRepository {
+      connection,
+    }
+  }
+}
+
+#[async_trait]
+impl DataQualityRepository for PgDataQualityRepository {
+  /// The attractions without any accepted rating.
+  async fn attractions_without_ratings(
+    &self,
+    sample_size: i32,
+  ) -> sqlx::Result<CheckCount> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      CheckCount,
+      r#"
+      SELECT count(*) AS "count!",
+        coalesce((array_agg(a.id ORDER BY a.id))[1:$1], '{}')
+          AS "sample_ids!"
+      FROM attraction a
+      WHERE a.deleted_at IS NULL AND NOT EXISTS (
+        SELECT 1 FROM attraction_rating ar
+        WHERE ar.attraction_id = a.id AND ar.status = 'ACCEPTED'
+      )
+      "#,
+      sample_size
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  async fn cities_without_attractions(
+    &self,
+    sample_size: i32,
+  ) -> sqlx::Result<CheckCount> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      CheckCount,
+      r#"
+      SELECT count(*) AS "count!",
+        coalesce((array_agg(c.id ORDER BY c.id))[1:$1], '{}')
+          AS "sample_ids!"
+      FROM city c
+      WHERE c.deleted_at IS NULL AND NOT EXISTS (
+        SELECT 1 FROM attraction a
+        WHERE a.city_id = c.id AND a.deleted_at IS NULL
+      )
+      "#,
+      sample_size
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  /// The ratings, whatever their status, with a rate out of the range.
+  async fn ratings_out_of_range(
+    &self,
+    min_rate: f64,
+    max_rate: f64,
+    sample_size: i32,
+  ) -> sqlx::Result<CheckCount> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      CheckCount,
+      r#"
+      SELECT count(*) AS "count!",
+        coalesce((array_agg(id ORDER BY id))[1:$3], '{}') AS "sample_ids!"
+      FROM attraction_rating
+      WHERE rate::float8 < $1 OR rate::float8 > $2
+      "#,
+      min_rate,
+      max_rate,
+      sample_size
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  /// The aggregates without any accepted rating in the period they
+  /// aggregate, the same periods of `Granularity::window`.
+  async fn aggregates_without_ratings(
+    &self,
+    sample_size: i32,
+  ) -> sqlx::Result<CheckCount> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      CheckCount,
+      r#"
+      WITH aggregate_window AS (
+        SELECT id, attraction_id,
+          CASE granularity
+            WHEN 'ROLLING_7_DAYS' THEN at - interval '6 days'
+            WHEN 'ROLLING_30_DAYS' THEN at - interval '29 days'
+            WHEN 'ROLLING_90_DAYS' THEN at - interval '89 days'
+            ELSE at
+          END AS from_at,
+          CASE granularity
+            WHEN 'HOUR' THEN at + interval '1 hour'
+            WHEN 'WEEK' THEN at + interval '1 week'
+            WHEN 'MONTH' THEN at + interval '1 month'
+            ELSE at + interval '1 day'
+          END AS to_at
+        FROM attraction_rating_aggregate
+      )
+      SELECT count(*) AS "count!",
+        coalesce((array_agg(aw.id ORDER BY aw.id))[1:$1], '{}')
+          AS "sample_ids!"
+      FROM aggregate_window aw
+      WHERE NOT EXISTS (
+        SELECT 1 FROM attraction_rating ar
+        WHERE ar.attraction_id = aw.attraction_id
+          AND ar.status = 'ACCEPTED'
+          AND ar.at >= aw.from_at
+          AND ar.at < aw.to_at
+      )
+      "#,
+      sample_size
+    )
+    .fetch_one(conn)
+    .await
+  }
+}
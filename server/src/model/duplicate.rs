@@ -0,0 +1,378 @@
+use crate::model::{
+  aggregation_settings::from_env_var, attraction::Attraction,
+};
+use sqlx::FromRow;
+use std::collections::{HashMap, HashSet};
+
+/// The scaling factor of the common prefix in the Jaro-Winkler similarity.
+const PREFIX_SCALE: f64 = 0.1;
+/// The longest common prefix rewarded by the Jaro-Winkler similarity.
+const MAX_PREFIX: usize = 4;
+/// The words of this length or shorter, like "de", "du" or "la", don't tell
+/// the attractions apart.
+const MAX_IGNORED_WORD: usize = 2;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DuplicateSettings {
+  /// The farthest two attractions can be to be duplicates, when both have
+  /// coordinates.
+  pub max_distance_meters: f64,
+  /// The least score of two attractions to be duplicates, from 0 to 1.
+  pub min_score: f64,
+  /// The weight of the similarity of the names in the score, the rest is
+  /// the weight of their proximity.
+  pub name_weight: f64,
+}
+
+impl Default for DuplicateSettings {
+  fn default() -> Self {
+    DuplicateSettings {
+      max_distance_meters: 200.0,
+      min_score: 0.85,
+      name_weight: 0.8,
+    }
+  }
+}
+
+impl DuplicateSettings {
+  /// Read the settings from the environment, falling back into the defaults
+  /// for the missing or invalid values.
+  /// * DUPLICATE_MAX_DISTANCE_METERS, DUPLICATE_MIN_SCORE and
+  ///   DUPLICATE_NAME_WEIGHT: numbers.
+  pub fn from_env() -> Self {
+    let defaults = DuplicateSettings::default();
+    DuplicateSettings {
+      max_distance_meters: from_env_var("DUPLICATE_MAX_DISTANCE_METERS")
+        .unwrap_or(defaults.max_distance_meters),
+      min_score: from_env_var("DUPLICATE_MIN_SCORE")
+        .unwrap_or(defaults.min_score),
+      name_weight: from_env_var("DUPLICATE_NAME_WEIGHT")
+        .unwrap_or(defaults.name_weight),
+    }
+  }
+}
+
+/// Two attractions that seem to be the same one, the lowest id first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateCandidate {
+  pub attraction_id: i32,
+  pub description: String,
+  pub other_attraction_id: i32,
+  pub other_description: String,
+  pub name_similarity: f64,
+  /// The distance between both, none if any of them doesn't have
+  /// coordinates.
+  pub distance_meters: Option<f64>,
+  pub score: f64,
+}
+
+/// A pair of attractions reviewed as not being duplicates.
+#[derive(FromRow, Debug, Clone)]
+pub struct DuplicateDismissal {
+  pub attraction_id: i32,
+  pub other_attraction_id: i32,
+}
+
+/// What was done to merge an attraction into another one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AttractionMerge {
+  pub attraction_id: i32,
+  pub merged_attraction_id: i32,
+  pub moved_ratings: u64,
+  /// The ratings of the users that had already rated the surviving
+  /// attraction the same day, dropped to keep one rating per day.
+  pub dropped_ratings: u64,
+  pub moved_aggregates: u64,
+  pub moved_similarities: u64,
+}
+
+/// The Jaro-Winkler similarity of both texts, from 0 to 1.
+pub fn jaro_winkler(one: &str, other: &str) -> f64 {
+  let one = one.chars().collect::<Vec<char>>();
+  let other = other.chars().collect::<Vec<char>>();
+  if one.is_empty() && other.is_empty() {
+    return 1.0;
+  }
+  if one.is_empty() || other.is_empty() {
+    return 0.0;
+  }
+  let window = (one.len().max(other.len()) / 2).saturating_sub(1);
+  let mut one_matched = vec![false; one.len()];
+  let mut other_matched = vec![false; other.len()];
+  let mut matches = 0;
+  for (i, a_char) in one.iter().enumerate() {
+    let from = i.saturating_sub(window);
+    let to = (i + window + 1).min(other.len());
+    for j in from..to {
+      if !other_matched[j] && other[j] == *a_char {
+        one_matched[i] = true;
+        other_matched[j] = true;
+        matches += 1;
+        break;
+      }
+    }
+  }
+  if matches == 0 {
+    return 0.0;
+  }
+  let one_in_order = one
+    .iter()
+    .zip(one_matched)
+    .filter_map(|(a_char, matched)| matched.then_some(a_char));
+  let other_in_order = other
+    .iter()
+    .zip(other_matched)
+    .filter_map(|(a_char, matched)| matched.then_some(a_char));
+  let transpositions = one_in_order
+    .zip(other_in_order)
+    .filter(|(a_char, other_char)| a_char != other_char)
+    .count()
+    / 2;
+  let matches = matches as f64;
+  let jaro = (matches / one.len() as f64
+    + matches / other.len() as f64
+    + (matches - transpositions as f64) / matches)
+    / 3.0;
+  let prefix = one
+    .iter()
+    .zip(other.iter())
+    .take(MAX_PREFIX)
+    .take_while(|(a_char, other_char)| a_char == other_char)
+    .count();
+  jaro + prefix as f64 * PREFIX_SCALE * (1.0 - jaro)
+}
+
+/// The words of a name in lower case and without accents, leaving out the
+/// short ones.
+pub fn tokens_of(name: &str) -> Vec<String> {
+  name
+    .to_lowercase()
+    .chars()
+    .map(without_accent)
+    .collect::<String>()
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|a_word| a_word.chars().count() > MAX_IGNORED_WORD)
+    .map(str::to_string)
+    .collect()
+}
+
+fn without_accent(c: char) -> char {
+  match c {
+    'á' | 'à' | 'â' | 'ä' | 'ã' | 'å' => 'a',
+    'é' | 'è' | 'ê' | 'ë' => 'e',
+    'í' | 'ì' | 'î' | 'ï' => 'i',
+    'ó' | 'ò' | 'ô' | 'ö' | 'õ' | 'ø' => 'o',
+    'ú' | 'ù' | 'û' | 'ü' => 'u',
+    'ý' | 'ÿ' => 'y',
+    'ñ' => 'n',
+    'ç' => 'c',
+    _ => c,
+  }
+}
+
+/// The similarity of two names, from 0 to 1. It's the best of the
+/// similarity of the whole names and of how well the words of the name with
+/// fewer words are found in the other one, so "Louvre" is as similar to
+/// "Musée du Louvre" as to itself.
+pub fn name_similarity(one: &str, other: &str) -> f64 {
+  let one = tokens_of(one);
+  let other = tokens_of(other);
+  let whole = jaro_winkler(&one.join(" "), &other.join(" "));
+  let (fewer, more) = if one.len() <= other.len() {
+    (&one, &other)
+  } else {
+    (&other, &one)
+  };
+  if fewer.is_empty() {
+    return whole;
+  }
+  let contained = fewer
+    .iter()
+    .map(|a_word| {
+      more
+        .iter()
+        .map(|other_word| jaro_winkler(a_word, other_word))
+        .fold(0.0, f64::max)
+    })
+    .sum::<f64>()
+    / fewer.len() as f64;
+  whole.max(contained)
+}
+
+/// The pairs of attractions of the same city and type that seem to be the
+/// same one, the most likely first. The score weights the similarity of the
+/// names with the proximity, and it's only the similarity of the names when
+/// any of them doesn't have coordinates. The attractions farther than the
+/// max distance and the dismissed pairs are never duplicates.
+pub fn find_duplicates(
+  attractions: &[Attraction],
+  dismissed: &HashSet<(i32, i32)>,
+  settings: &DuplicateSettings,
+) -> Vec<DuplicateCandidate> {
+  let mut groups: HashMap<(i32, i32), Vec<&Attraction>> = HashMap::new();
+  for an_attraction in attractions {
+    groups
+      .entry((
+        an_attraction.get_city_id(),
+        an_attraction.get_attraction_type_id(),
+      ))
+      .or_default()
+      .push(an_attraction);
+  }
+  let mut candidates = vec![];
+  for a_group in groups.values() {
+    for (i, one) in a_group.iter().enumerate() {
+      for other in a_group.iter().skip(i + 1) {
+        let (one, other) = if one.get_id() < other.get_id() {
+          (one, other)
+        } else {
+          (other, one)
+        };
+        if dismissed.contains(&(one.get_id(), other.get_id())) {
+          continue;
+        }
+        let distance_meters = one.location().zip(other.location()).map(
+          |(one_location, other_location)| {
+            one_location.haversine_distance_to(&other_location).meters()
+          },
+        );
+        if distance_meters
+          .is_some_and(|meters| meters > settings.max_distance_meters)
+        {
+          continue;
+        }
+        let name_similarity =
+          name_similarity(&one.get_description(), &other.get_description());
+        let score = match distance_meters {
+          Some(meters) => {
+            let proximity =
+              1.0 - meters / settings.max_distance_meters.max(f64::EPSILON);
+            settings.name_weight * name_similarity
+              + (1.0 - settings.name_weight) * proximity
+          },
+          None => name_similarity,
+        };
+        if score >= settings.min_score {
+          candidates.push(DuplicateCandidate {
+            attraction_id: one.get_id(),
+            description: one.get_description(),
+            other_attraction_id: other.get_id(),
+            other_description: other.get_description(),
+            name_similarity,
+            distance_meters,
+            score,
+          });
+        }
+      }
+    }
+  }
+  candidates.sort_by(|one, other| {
+    other.score.total_cmp(&one.score).then_with(|| {
+      (one.attraction_id, one.other_attraction_id)
+        .cmp(&(other.attraction_id, other.other_attraction_id))
+    })
+  });
+  candidates
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn attraction(
+    id: i32,
+    description: &str,
+    attraction_type_id: i32,
+    latitude: &str,
+    longitude: &str,
+  ) -> Attraction {
+    Attraction {
+      id,
+      description: description.to_string(),
+      city_id: 1,
+      latitude: Some(latitude.to_string()),
+      longitude: Some(longitude.to_string()),
+      attraction_type_id,
+      deleted_at: None,
+    }
+  }
+
+  /// The Louvre twice 30 m apart, once far away and once of another type,
+  /// and the Orsay nearby.
+  fn attractions() -> Vec<Attraction> {
+    vec![
+      attraction(1, "Louvre", 1, "48.8606", "2.3376"),
+      attraction(2, "Musée du Louvre", 1, "48.8608", "2.3378"),
+      attraction(3, "Louvre", 1, "48.8700", "2.3376"),
+      attraction(4, "Louvre", 2, "48.8606", "2.3376"),
+      attraction(5, "Musée d'Orsay", 1, "48.8600", "2.3266"),
+    ]
+  }
+
+  #[test]
+  fn the_jaro_winkler_similarity_rewards_the_common_prefix() {
+    assert!((jaro_winkler("martha", "marhta") - 0.9611).abs() < 1e-4);
+    assert!((jaro_winkler("dixon", "dicksonx") - 0.8133).abs() < 1e-4);
+    assert_eq!(jaro_winkler("", ""), 1.0);
+    assert_eq!(jaro_winkler("louvre", ""), 0.0);
+  }
+
+  #[test]
+  fn the_names_are_compared_by_their_words_without_accents() {
+    assert_eq!(tokens_of("Musée du Louvre"), vec!["musee", "louvre"]);
+    assert_eq!(name_similarity("Louvre", "Musée du Louvre"), 1.0);
+    assert_eq!(name_similarity("MUSÉE DU LOUVRE", "musee louvre"), 1.0);
+    assert!(name_similarity("Louvre", "Musée d'Orsay") < 0.85);
+  }
+
+  #[test]
+  fn finds_the_close_attractions_of_the_same_type_with_similar_names() {
+    let candidates = find_duplicates(
+      &attractions(),
+      &HashSet::new(),
+      &DuplicateSettings::default(),
+    );
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(
+      (
+        candidates[0].attraction_id,
+        candidates[0].other_attraction_id
+      ),
+      (1, 2)
+    );
+    assert!(candidates[0].distance_meters.unwrap() < 30.0);
+  }
+
+  #[test]
+  fn the_attractions_without_coordinates_are_compared_by_their_names() {
+    let mut without_location = attraction(6, "Louvre", 1, "", "");
+    without_location.latitude = None;
+    without_location.longitude = None;
+    let mut attractions = attractions();
+    attractions.push(without_location);
+    let candidates = find_duplicates(
+      &attractions,
+      &HashSet::new(),
+      &DuplicateSettings::default(),
+    );
+    let pairs = candidates
+      .iter()
+      .map(|a_candidate| {
+        (a_candidate.attraction_id, a_candidate.other_attraction_id)
+      })
+      .collect::<Vec<(i32, i32)>>();
+    assert_eq!(pairs, vec![(1, 6), (2, 6), (3, 6), (1, 2)]);
+    assert_eq!(candidates[0].distance_meters, None);
+  }
+
+  #[test]
+  fn the_dismissed_pairs_are_never_duplicates() {
+    let dismissed = HashSet::from([(1, 2)]);
+    assert!(find_duplicates(
+      &attractions(),
+      &dismissed,
+      &DuplicateSettings::default()
+    )
+    .is_empty());
+  }
+}
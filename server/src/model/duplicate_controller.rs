@@ -0,0 +1,169 @@
+use crate::{
+  db::database::is_foreign_key_violation,
+  model::{
+    attraction_repository::AttractionRepository,
+    audit::Actor,
+    duplicate::{
+      find_duplicates, AttractionMerge, DuplicateCandidate, DuplicateSettings,
+    },
+  },
+};
+use async_trait::async_trait;
+use std::collections::HashSet;
+
+/// The review of the attractions that seem to be duplicates and their merge.
+#[async_trait]
+pub trait DuplicateController: Send + Sync + 'static {
+  async fn duplicates(
+    &self,
+    city_id: Option<i32>,
+    limit: usize,
+  ) -> Option<Vec<DuplicateCandidate>>;
+  async fn dismiss(
+    &self,
+    attraction_id: i32,
+    other_attraction_id: i32,
+  ) -> Result<bool, String>;
+  async fn merge(
+    &self,
+    attraction_id: i32,
+    merged_attraction_id: i32,
+    actor: Actor,
+  ) -> Result<Option<AttractionMerge>, String>;
+}
+
+#[derive(Clone)]
+pub struct DuplicateControllerImpl<AttractionRepo> {
+  attraction_repository: AttractionRepo,
+  settings: DuplicateSettings,
+}
+
+impl<AttractionRepo> DuplicateControllerImpl<AttractionRepo>
+where
+  AttractionRepo: AttractionRepository,
+{
+  pub fn new(
+    attraction_repository: AttractionRepo,
+    settings: DuplicateSettings,
+  ) -> Self {
+    DuplicateControllerImpl {
+      attraction_repository,
+      settings,
+    }
+  }
+}
+
+#[async_trait]
+impl<AttractionRepo> DuplicateController
+  for DuplicateControllerImpl<AttractionRepo>
+where
+  AttractionRepo: AttractionRepository + Send + Sync + 'static,
+{
+  /// The pairs of attractions of the city, or of every city, that seem to be
+  /// the same one and weren't dismissed, the most likely first.
+  async fn duplicates(
+    &self,
+    city_id: Option<i32>,
+    limit: usize,
+  ) -> Option<Vec<DuplicateCandidate>> {
+    let attractions = self
+      .attraction_repository
+      .attractions_in(city_id)
+      .await
+      .ok()?;
+    let dismissed = self
+      .attraction_repository
+      .dismissed_duplicates()
+      .await
+      .ok()?
+      .into_iter()
+      .map(|a_dismissal| {
+        (a_dismissal.attraction_id, a_dismissal.other_attraction_id)
+      })
+      .collect::<HashSet<(i32, i32)>>();
+    let mut candidates =
+      find_duplicates(&attractions, &dismissed, &self.settings);
+    candidates.truncate(limit);
+    Some(candidates)
+  }
+
+  /// Record that both attractions aren't the same one. Returns false if any
+  /// of them doesn't exist.
+  async fn dismiss(
+    &self,
+    attraction_id: i32,
+    other_attraction_id: i32,
+  ) -> Result<bool, String> {
+    if attraction_id == other_attraction_id {
+      return Err(String::from("An attraction can't be its own duplicate"));
+    }
+    match self
+      .attraction_repository
+      .dismiss_duplicate(
+        attraction_id.min(other_attraction_id),
+        attraction_id.max(other_attraction_id),
+      )
+      .await
+    {
+      Ok(_) => Ok(true),
+      Err(e) if is_foreign_key_violation(&e) => Ok(false),
+      Err(e) => Err(e.to_string()),
+    }
+  }
+
+  /// Merge the duplicate into the attraction, that survives. Returns None if
+  /// any of them doesn't exist.
+  async fn merge(
+    &self,
+    attraction_id: i32,
+    merged_attraction_id: i32,
+    actor: Actor,
+  ) -> Result<Option<AttractionMerge>, String> {
+    if attraction_id == merged_attraction_id {
+      return Err(String::from("An attraction can't be merged into itself"));
+    }
+    match self
+      .attraction_repository
+      .merge_attractions(attraction_id, merged_attraction_id, &actor)
+      .await
+    {
+      Ok(a_merge) => Ok(Some(a_merge)),
+      Err(sqlx::Error::RowNotFound) => Ok(None),
+      Err(e) => Err(e.to_string()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::attraction_repository::DummyAttractionRepo;
+
+  /// The repository isn't reached, the requests are rejected before.
+  fn controller() -> DuplicateControllerImpl<DummyAttractionRepo> {
+    DuplicateControllerImpl::new(
+      DummyAttractionRepo,
+      DuplicateSettings::default(),
+    )
+  }
+
+  #[tokio::test]
+  async fn an_attraction_cant_be_dismissed_as_its_own_duplicate() {
+    assert_eq!(
+      controller().dismiss(1, 1).await,
+      Err(String::from("An attraction can't be its own duplicate"))
+    );
+  }
+
+  #[tokio::test]
+  async fn an_attraction_cant_be_merged_into_itself() {
+    let actor = Actor {
+      id: 1,
+      username: String::from("editor"),
+    };
+    assert_eq!(
+      controller().merge(1, 1, actor).await,
+      Err(String::from("An attraction can't be merged into itself"))
+    );
+  }
+}
@@ -0,0 +1,478 @@
+use crate::model::rating_statistics::{MAX_RATE, MIN_RATE};
+use chrono::{Duration, NaiveDateTime};
+use serde::Deserialize;
+use std::{fmt, str::FromStr};
+
+/// The smoothing values tried when the model is fitted.
+const ALPHAS: [f64; 9] = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9];
+const BETAS: [f64; 5] = [0.01, 0.05, 0.1, 0.2, 0.3];
+const GAMMAS: [f64; 5] = [0.05, 0.1, 0.2, 0.3, 0.5];
+
+/// The exponential smoothing model fitted on the daily average ratings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum ForecastMethod {
+  /// Only a level, the forecast is flat.
+  SimpleExponential,
+  /// A level and an additive trend (Holt's linear method).
+  Holt,
+  /// A level, an additive trend and an additive seasonality (Holt-Winters).
+  #[default]
+  HoltWinters,
+}
+
+impl ForecastMethod {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ForecastMethod::SimpleExponential => "SIMPLE_EXPONENTIAL",
+      ForecastMethod::Holt => "HOLT",
+      ForecastMethod::HoltWinters => "HOLT_WINTERS",
+    }
+  }
+
+  fn has_trend(&self) -> bool {
+    !matches!(self, ForecastMethod::SimpleExponential)
+  }
+
+  fn has_season(&self) -> bool {
+    matches!(self, ForecastMethod::HoltWinters)
+  }
+}
+
+impl fmt::Display for ForecastMethod {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+impl FromStr for ForecastMethod {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_uppercase().as_str() {
+      "SIMPLE_EXPONENTIAL" => Ok(ForecastMethod::SimpleExponential),
+      "HOLT" => Ok(ForecastMethod::Holt),
+      "HOLT_WINTERS" => Ok(ForecastMethod::HoltWinters),
+      _ => Err(format!("Unknown forecast method: {}", s)),
+    }
+  }
+}
+
+impl TryFrom<String> for ForecastMethod {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ForecastSettings {
+  pub method: ForecastMethod,
+  /// The days forecasted after the last observed day.
+  pub horizon_days: usize,
+  /// The days of a season, a week by default.
+  pub season_length: usize,
+  /// The last days held out to measure the error of the model, no backtest
+  /// when it is 0.
+  pub backtest_days: usize,
+  /// The days of history used to fit the model.
+  pub history_days: i64,
+  /// The z-score of the confidence intervals, 1.96 for 95%.
+  pub confidence_z: f64,
+}
+
+impl Default for ForecastSettings {
+  fn default() -> Self {
+    ForecastSettings {
+      method: ForecastMethod::default(),
+      horizon_days: 30,
+      season_length: 7,
+      backtest_days: 28,
+      history_days: 365,
+      confidence_z: 1.96,
+    }
+  }
+}
+
+/// The average rating of a day of the series.
+#[derive(Debug, Clone, Copy)]
+pub struct Observation {
+  pub at: NaiveDateTime,
+  pub value: f64,
+  /// False when the day had no ratings and the value was filled.
+  pub observed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForecastPoint {
+  pub at: NaiveDateTime,
+  pub value: f64,
+  pub lower: f64,
+  pub upper: f64,
+}
+
+/// The error of the model when it forecasts the held out days.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backtest {
+  pub days: usize,
+  /// The held out days that had ratings, the only ones evaluated.
+  pub evaluated_days: usize,
+  pub mae: f64,
+  /// The mean absolute percentage error, None if every actual value is 0.
+  pub mape: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Smoothing {
+  pub alpha: f64,
+  pub beta: Option<f64>,
+  pub gamma: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Forecast {
+  pub method: ForecastMethod,
+  pub smoothing: Smoothing,
+  /// The days used to fit the model.
+  pub history_days: usize,
+  /// The standard deviation of the one step ahead errors.
+  pub residual_sd: f64,
+  /// The change of the level per day, None without trend.
+  pub trend_per_day: Option<f64>,
+  pub points: Vec<ForecastPoint>,
+  pub backtest: Option<Backtest>,
+}
+
+/// The state of a fitted model after the last observation.
+#[derive(Debug, Clone)]
+struct Fitted {
+  smoothing: Smoothing,
+  level: f64,
+  trend: f64,
+  /// The seasonal components of the last season, the first one is the
+  /// component of the day after the last observation.
+  seasonals: Vec<f64>,
+  sse: f64,
+  residuals: usize,
+}
+
+impl Fitted {
+  fn residual_sd(&self) -> f64 {
+    if self.residuals == 0 {
+      return 0.0;
+    }
+    (self.sse / self.residuals as f64).sqrt()
+  }
+
+  fn predict(&self, h: usize) -> f64 {
+    let seasonal = if self.seasonals.is_empty() {
+      0.0
+    } else {
+      self.seasonals[(h - 1) % self.seasonals.len()]
+    };
+    self.level + h as f64 * self.trend + seasonal
+  }
+
+  /// The variance of the error h days ahead relative to the one step ahead,
+  /// the additive error models of Hyndman et al. (2008), table 6.1.
+  fn variance_factor(&self, h: usize) -> f64 {
+    let Smoothing {
+      alpha,
+      beta,
+      gamma,
+    } = self.smoothing;
+    let (beta, gamma) = (beta.unwrap_or(0.0), gamma.unwrap_or(0.0));
+    let h_f = h as f64;
+    let m = self.seasonals.len().max(1);
+    let k = ((h - 1) / m) as f64;
+    1.0
+      + (h_f - 1.0)
+        * (alpha.powi(2)
+          + alpha * beta * h_f
+          + beta.powi(2) * h_f * (2.0 * h_f - 1.0) / 6.0)
+      + gamma * k * (2.0 * alpha + gamma + beta * m as f64 * (k + 1.0))
+  }
+}
+
+/// The minimum number of days to fit a model.
+fn min_history(method: ForecastMethod, season_length: usize) -> usize {
+  match method {
+    ForecastMethod::SimpleExponential => 2,
+    ForecastMethod::Holt => 3,
+    ForecastMethod::HoltWinters => 2 * season_length + 1,
+  }
+}
+
+/// Run the model with the given smoothing over the series.
+fn smooth(
+  values: &[f64],
+  smoothing: Smoothing,
+  method: ForecastMethod,
+  season_length: usize,
+) -> Fitted {
+  let alpha = smoothing.alpha;
+  let beta = smoothing.beta.unwrap_or(0.0);
+  let gamma = smoothing.gamma.unwrap_or(0.0);
+  let m = if method.has_season() {
+    season_length
+  } else {
+    0
+  };
+
+  let (mut level, mut trend, mut seasonals, start) = if m > 0 {
+    let first = values[..m].iter().sum::<f64>() / m as f64;
+    let second = values[m..2 * m].iter().sum::<f64>() / m as f64;
+    let seasonals = values[..m].iter().map(|value| value - first).collect();
+    (first, (second - first) / m as f64, seasonals, m)
+  } else if method.has_trend() {
+    (values[0], values[1] - values[0], Vec::new(), 1)
+  } else {
+    (values[0], 0.0, Vec::new(), 1)
+  };
+
+  let mut sse = 0.0;
+  for (t, value) in values.iter().enumerate().skip(start) {
+    let seasonal = if m > 0 { seasonals[t % m] } else { 0.0 };
+    let error = value - (level + trend + seasonal);
+    sse += error.powi(2);
+    let previous_level = level;
+    level = alpha * (value - seasonal) + (1.0 - alpha) * (level + trend);
+    if method.has_trend() {
+      trend = beta * (level - previous_level) + (1.0 - beta) * trend;
+    }
+    if m > 0 {
+      seasonals[t % m] = gamma * (value - level) + (1.0 - gamma) * seasonal;
+    }
+  }
+  if m > 0 {
+    // The component of the day after the last one goes first.
+    seasonals.rotate_left(values.len() % m);
+  }
+
+  Fitted {
+    smoothing,
+    level,
+    trend,
+    seasonals,
+    sse,
+    residuals: values.len() - start,
+  }
+}
+
+/// Fit the model choosing the smoothing with the least squared one step ahead
+/// errors from a grid of values.
+fn fit(
+  values: &[f64],
+  method: ForecastMethod,
+  season_length: usize,
+) -> Result<Fitted, String> {
+  let needed = min_history(method, season_length);
+  if values.len() < needed {
+    return Err(format!(
+      "{method} needs at least {needed} days of history but there are {}",
+      values.len()
+    ));
+  }
+  let betas: &[f64] = if method.has_trend() { &BETAS } else { &[0.0] };
+  let gammas: &[f64] = if method.has_season() { &GAMMAS } else { &[0.0] };
+  let mut best: Option<Fitted> = None;
+  for alpha in ALPHAS {
+    for beta in betas {
+      for gamma in gammas {
+        let smoothing = Smoothing {
+          alpha,
+          beta: method.has_trend().then_some(*beta),
+          gamma: method.has_season().then_some(*gamma),
+        };
+        let fitted = smooth(values, smoothing, method, season_length);
+        if best.as_ref().is_none_or(|best| fitted.sse < best.sse) {
+          best = Some(fitted);
+        }
+      }
+    }
+  }
+  best.ok_or(String::from("The model couldn't be fitted"))
+}
+
+/// Measure the error of the model forecasting the last days of the series
+/// with a model fitted on the days before them.
+fn backtest(
+  series: &[Observation],
+  settings: &ForecastSettings,
+) -> Option<Backtest> {
+  let days = settings.backtest_days;
+  if days == 0
+    || series.len()
+      < days + min_history(settings.method, settings.season_length)
+  {
+    return None;
+  }
+  let (train, test) = series.split_at(series.len() - days);
+  let values = train.iter().map(|day| day.value).collect::<Vec<f64>>();
+  let fitted = fit(&values, settings.method, settings.season_length).ok()?;
+
+  let errors = test
+    .iter()
+    .enumerate()
+    .filter(|(_, day)| day.observed)
+    .map(|(index, day)| {
+      let predicted = fitted.predict(index + 1).clamp(MIN_RATE, MAX_RATE);
+      (day.value, (day.value - predicted).abs())
+    })
+    .collect::<Vec<(f64, f64)>>();
+  if errors.is_empty() {
+    return None;
+  }
+  let percentages = errors
+    .iter()
+    .filter(|(actual, _)| *actual != 0.0)
+    .map(|(actual, error)| error / actual.abs())
+    .collect::<Vec<f64>>();
+  Some(Backtest {
+    days,
+    evaluated_days: errors.len(),
+    mae: errors.iter().map(|(_, error)| error).sum::<f64>()
+      / errors.len() as f64,
+    mape: (!percentages.is_empty())
+      .then(|| percentages.iter().sum::<f64>() / percentages.len() as f64),
+  })
+}
+
+/// Forecast the average rating of the days after the series.
+///
+/// # Arguments:
+/// * series: one observation per consecutive day, the oldest first.
+/// * settings: the model, the horizon and the backtest.
+///
+/// # Return:
+/// * Ok with the forecast, the values and the intervals are clamped to the
+///   range of the rates.
+/// * Err with a string if the series is too short for the method.
+pub fn forecast(
+  series: &[Observation],
+  settings: &ForecastSettings,
+) -> Result<Forecast, String> {
+  if settings.season_length == 0 {
+    return Err(String::from("The season length must be positive"));
+  }
+  let values = series.iter().map(|day| day.value).collect::<Vec<f64>>();
+  let fitted = fit(&values, settings.method, settings.season_length)?;
+  let residual_sd = fitted.residual_sd();
+  let last = series.last().map(|day| day.at).ok_or("Empty series")?;
+
+  let points = (1..=settings.horizon_days)
+    .map(|h| {
+      let value = fitted.predict(h);
+      let margin =
+        settings.confidence_z * residual_sd * fitted.variance_factor(h).sqrt();
+      ForecastPoint {
+        at: last + Duration::days(h as i64),
+        value: value.clamp(MIN_RATE, MAX_RATE),
+        lower: (value - margin).clamp(MIN_RATE, MAX_RATE),
+        upper: (value + margin).clamp(MIN_RATE, MAX_RATE),
+      }
+    })
+    .collect();
+
+  Ok(Forecast {
+    method: settings.method,
+    smoothing: fitted.smoothing,
+    history_days: series.len(),
+    residual_sd,
+    trend_per_day: settings.method.has_trend().then_some(fitted.trend),
+    points,
+    backtest: backtest(series, settings),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::NaiveDate;
+
+  fn series_of(values: impl Iterator<Item = f64>) -> Vec<Observation> {
+    let start = NaiveDate::from_ymd_opt(2023, 1, 2)
+      .unwrap()
+      .and_hms_opt(0, 0, 0)
+      .unwrap();
+    values
+      .enumerate()
+      .map(|(day, value)| Observation {
+        at: start + Duration::days(day as i64),
+        value,
+        observed: true,
+      })
+      .collect()
+  }
+
+  fn weekly(day: usize) -> f64 {
+    // Better rated on the weekends.
+    if day % 7 >= 5 {
+      0.8
+    } else {
+      0.5
+    }
+  }
+
+  #[test]
+  fn holt_winters_follows_the_weekly_season() {
+    let series = series_of((0..8 * 7).map(weekly));
+    let forecast = forecast(&series, &ForecastSettings::default()).unwrap();
+    assert_eq!(forecast.points.len(), 30);
+    for (h, point) in forecast.points.iter().enumerate() {
+      let expected = weekly(series.len() + h);
+      assert!((point.value - expected).abs() < 0.02, "day {h} {point:?}");
+      assert!(point.lower <= point.value && point.value <= point.upper);
+    }
+    let backtest = forecast.backtest.unwrap();
+    assert_eq!(backtest.evaluated_days, 28);
+    assert!(backtest.mae < 0.02);
+    assert!(backtest.mape.unwrap() < 0.05);
+  }
+
+  #[test]
+  fn holt_extrapolates_the_trend_and_widens_the_interval() {
+    let series = series_of(
+      (0..40).map(|day| 0.3 + 0.005 * day as f64 + 0.01 * (day % 2) as f64),
+    );
+    let settings = ForecastSettings {
+      method: ForecastMethod::Holt,
+      horizon_days: 10,
+      ..Default::default()
+    };
+    let forecast = forecast(&series, &settings).unwrap();
+    let trend = forecast.trend_per_day.unwrap();
+    assert!((trend - 0.005).abs() < 0.002, "trend {trend}");
+    let first = forecast.points.first().unwrap();
+    let last = forecast.points.last().unwrap();
+    assert!(last.value > first.value);
+    assert!(last.upper - last.lower > first.upper - first.lower);
+  }
+
+  #[test]
+  fn simple_exponential_is_flat() {
+    let series = series_of([0.4, 0.6, 0.5, 0.5, 0.55].into_iter());
+    let settings = ForecastSettings {
+      method: ForecastMethod::SimpleExponential,
+      ..Default::default()
+    };
+    let forecast = forecast(&series, &settings).unwrap();
+    assert_eq!(forecast.trend_per_day, None);
+    assert!(forecast.backtest.is_none());
+    assert!(forecast
+      .points
+      .windows(2)
+      .all(|pair| pair[0].value == pair[1].value));
+  }
+
+  #[test]
+  fn needs_two_seasons_for_holt_winters() {
+    let series = series_of((0..10).map(weekly));
+    assert!(forecast(&series, &ForecastSettings::default()).is_err());
+    assert_eq!(
+      "holt_winters".parse::<ForecastMethod>(),
+      Ok(ForecastMethod::HoltWinters)
+    );
+  }
+}
@@ -0,0 +1,269 @@
+use chrono::{Datelike, Duration, Months, NaiveDateTime, NaiveTime, Timelike};
+use serde::Deserialize;
+use std::{fmt, str::FromStr};
+
+/// The period covered by a rating aggregate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Granularity {
+  Hour,
+  #[default]
+  Day,
+  /// An ISO week, starting on Monday.
+  Week,
+  Month,
+  /// The 7 days that end in the day of the aggregate.
+  Rolling7Days,
+  /// The 30 days that end in the day of the aggregate.
+  Rolling30Days,
+  /// The 90 days that end in the day of the aggregate.
+  Rolling90Days,
+}
+
+impl Granularity {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Granularity::Hour => "HOUR",
+      Granularity::Day => "DAY",
+      Granularity::Week => "WEEK",
+      Granularity::Month => "MONTH",
+      Granularity::Rolling7Days => "ROLLING_7_DAYS",
+      Granularity::Rolling30Days => "ROLLING_30_DAYS",
+      Granularity::Rolling90Days => "ROLLING_90_DAYS",
+    }
+  }
+
+  /// The unit used to truncate the timestamps of the ratings into the
+  /// buckets of this granularity, as expected by DATE_TRUNC.
+  pub fn trunc_unit(&self) -> &'static str {
+    match self {
+      Granularity::Hour => "hour",
+      Granularity::Week => "week",
+      Granularity::Month => "month",
+      Granularity::Day
+      | Granularity::Rolling7Days
+      | Granularity::Rolling30Days
+      | Granularity::Rolling90Days => "day",
+    }
+  }
+
+  /// The length in days of the rolling window, None if the granularity is
+  /// not a rolling window.
+  pub fn rolling_days(&self) -> Option<i64> {
+    match self {
+      Granularity::Rolling7Days => Some(7),
+      Granularity::Rolling30Days => Some(30),
+      Granularity::Rolling90Days => Some(90),
+      _ => None,
+    }
+  }
+
+  /// The start of the bucket where the moment falls, the same truncation
+  /// done by DATE_TRUNC with the trunc_unit.
+  pub fn truncate(&self, at: NaiveDateTime) -> NaiveDateTime {
+    let start_of_day = at.date().and_time(NaiveTime::default());
+    match self {
+      Granularity::Hour => start_of_day + Duration::hours(at.hour() as i64),
+      Granularity::Week => {
+        start_of_day
+          - Duration::days(at.weekday().num_days_from_monday() as i64)
+      },
+      Granularity::Month => start_of_day - Duration::days(at.day0() as i64),
+      Granularity::Day
+      | Granularity::Rolling7Days
+      | Granularity::Rolling30Days
+      | Granularity::Rolling90Days => start_of_day,
+    }
+  }
+
+  /// The start of the bucket that follows the one starting at the given
+  /// moment. There is a rolling window ending in every day.
+  pub fn next(&self, at: NaiveDateTime) -> NaiveDateTime {
+    match self {
+      Granularity::Hour | Granularity::Week | Granularity::Month => {
+        self.window(at).1
+      },
+      Granularity::Day
+      | Granularity::Rolling7Days
+      | Granularity::Rolling30Days
+      | Granularity::Rolling90Days => at + Duration::days(1),
+    }
+  }
+
+  /// The period of the ratings aggregated in the bucket that starts at the
+  /// given moment.
+  ///
+  /// # Arguments:
+  /// * at: the start of the bucket, already truncated to the granularity.
+  ///
+  /// # Return:
+  /// * The inclusive start and the exclusive end of the period.
+  pub fn window(&self, at: NaiveDateTime) -> (NaiveDateTime, NaiveDateTime) {
+    match self {
+      Granularity::Hour => (at, at + Duration::hours(1)),
+      Granularity::Day => (at, at + Duration::days(1)),
+      Granularity::Week => (at, at + Duration::weeks(1)),
+      Granularity::Month => (
+        at,
+        at.checked_add_months(Months::new(1))
+          .unwrap_or(at + Duration::days(31)),
+      ),
+      Granularity::Rolling7Days
+      | Granularity::Rolling30Days
+      | Granularity::Rolling90Days => {
+        let days = self.rolling_days().unwrap_or(1);
+        (at - Duration::days(days - 1), at + Duration::days(1))
+      },
+    }
+  }
+}
+
+impl fmt::Display for Granularity {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+impl FromStr for Granularity {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_uppercase().as_str() {
+      "HOUR" => Ok(Granularity::Hour),
+      "DAY" => Ok(Granularity::Day),
+      "WEEK" => Ok(Granularity::Week),
+      "MONTH" => Ok(Granularity::Month),
+      "ROLLING_7_DAYS" => Ok(Granularity::Rolling7Days),
+      "ROLLING_30_DAYS" => Ok(Granularity::Rolling30Days),
+      "ROLLING_90_DAYS" => Ok(Granularity::Rolling90Days),
+      _ => Err(format!("Unknown granularity: {}", s)),
+    }
+  }
+}
+
+impl TryFrom<String> for Granularity {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::NaiveDate;
+
+  fn at(year: i32, month: u32, day: u32, hour: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(year, month, day)
+      .unwrap()
+      .and_hms_opt(hour, 0, 0)
+      .unwrap()
+  }
+
+  #[test]
+  fn truncates_to_the_start_of_the_bucket() {
+    let moment = at(2024, 1, 3, 15) + Duration::minutes(42);
+    assert_eq!(Granularity::Hour.truncate(moment), at(2024, 1, 3, 15));
+    assert_eq!(Granularity::Day.truncate(moment), at(2024, 1, 3, 0));
+    assert_eq!(
+      Granularity::Rolling30Days.truncate(moment),
+      at(2024, 1, 3, 0)
+    );
+    assert_eq!(Granularity::Month.truncate(moment), at(2024, 1, 1, 0));
+    assert_eq!(
+      Granularity::Month.truncate(at(2024, 2, 29, 23)),
+      at(2024, 2, 1, 0)
+    );
+  }
+
+  #[test]
+  fn the_weeks_start_on_monday() {
+    // A Wednesday, a Monday and a Sunday.
+    assert_eq!(
+      Granularity::Week.truncate(at(2024, 1, 3, 9)),
+      at(2024, 1, 1, 0)
+    );
+    assert_eq!(
+      Granularity::Week.truncate(at(2024, 1, 1, 0)),
+      at(2024, 1, 1, 0)
+    );
+    assert_eq!(
+      Granularity::Week.truncate(at(2024, 1, 7, 23)),
+      at(2024, 1, 1, 0)
+    );
+    // The ISO week of Friday January 1st started the year before.
+    assert_eq!(
+      Granularity::Week.truncate(at(2021, 1, 1, 12)),
+      at(2020, 12, 28, 0)
+    );
+  }
+
+  #[test]
+  fn the_months_end_at_their_last_day() {
+    let end_of =
+      |year, month| Granularity::Month.window(at(year, month, 1, 0)).1;
+    assert_eq!(end_of(2024, 1), at(2024, 2, 1, 0));
+    assert_eq!(end_of(2024, 2), at(2024, 3, 1, 0));
+    assert_eq!(end_of(2023, 2), at(2023, 3, 1, 0));
+    assert_eq!(end_of(2024, 4), at(2024, 5, 1, 0));
+    assert_eq!(end_of(2023, 12), at(2024, 1, 1, 0));
+  }
+
+  #[test]
+  fn the_next_bucket_rolls_over_the_year() {
+    assert_eq!(
+      Granularity::Hour.next(at(2023, 12, 31, 23)),
+      at(2024, 1, 1, 0)
+    );
+    assert_eq!(
+      Granularity::Day.next(at(2023, 12, 31, 0)),
+      at(2024, 1, 1, 0)
+    );
+    assert_eq!(
+      Granularity::Week.next(at(2024, 12, 30, 0)),
+      at(2025, 1, 6, 0)
+    );
+    assert_eq!(
+      Granularity::Month.next(at(2024, 12, 1, 0)),
+      at(2025, 1, 1, 0)
+    );
+    // Every day ends a rolling window.
+    assert_eq!(
+      Granularity::Rolling90Days.next(at(2024, 2, 28, 0)),
+      at(2024, 2, 29, 0)
+    );
+  }
+
+  #[test]
+  fn the_rolling_windows_end_in_their_day() {
+    assert_eq!(
+      Granularity::Rolling7Days.window(at(2024, 3, 1, 0)),
+      (at(2024, 2, 24, 0), at(2024, 3, 2, 0))
+    );
+    assert_eq!(
+      Granularity::Rolling7Days.window(at(2023, 3, 1, 0)),
+      (at(2023, 2, 23, 0), at(2023, 3, 2, 0))
+    );
+    let (start, end) = Granularity::Rolling30Days.window(at(2024, 1, 15, 0));
+    assert_eq!((end - start).num_days(), 30);
+    assert_eq!(start, at(2023, 12, 17, 0));
+  }
+
+  #[test]
+  fn parses_the_names() {
+    for a_granularity in [
+      Granularity::Hour,
+      Granularity::Day,
+      Granularity::Week,
+      Granularity::Month,
+      Granularity::Rolling7Days,
+      Granularity::Rolling30Days,
+      Granularity::Rolling90Days,
+    ] {
+      assert_eq!(a_granularity.to_string().parse(), Ok(a_granularity));
+    }
+    assert_eq!(" week ".parse(), Ok(Granularity::Week));
+    assert!("FORTNIGHT".parse::<Granularity>().is_err());
+  }
+}
@@ -0,0 +1,417 @@
+use crate::model::aggregation_settings::from_env_var;
+use geoutils::Location;
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ItinerarySettings {
+  pub walking_speed_kmh: f64,
+  /// The minutes spent in every attraction.
+  pub visit_minutes: f64,
+  /// The most attractions that can be planned at once.
+  pub max_attractions: usize,
+  pub max_days: usize,
+}
+
+impl Default for ItinerarySettings {
+  fn default() -> Self {
+    ItinerarySettings {
+      walking_speed_kmh: 4.5,
+      visit_minutes: 60.0,
+      max_attractions: 50,
+      max_days: 14,
+    }
+  }
+}
+
+impl ItinerarySettings {
+  /// Read the settings from the environment, falling back into the defaults
+  /// for the missing or invalid values.
+  /// * ITINERARY_WALKING_SPEED_KMH, ITINERARY_VISIT_MINUTES,
+  ///   ITINERARY_MAX_ATTRACTIONS and ITINERARY_MAX_DAYS: numbers.
+  pub fn from_env() -> Self {
+    let defaults = ItinerarySettings::default();
+    ItinerarySettings {
+      walking_speed_kmh: from_env_var("ITINERARY_WALKING_SPEED_KMH")
+        .unwrap_or(defaults.walking_speed_kmh),
+      visit_minutes: from_env_var("ITINERARY_VISIT_MINUTES")
+        .unwrap_or(defaults.visit_minutes),
+      max_attractions: from_env_var("ITINERARY_MAX_ATTRACTIONS")
+        .unwrap_or(defaults.max_attractions),
+      max_days: from_env_var("ITINERARY_MAX_DAYS").unwrap_or(defaults.max_days),
+    }
+  }
+
+  fn walking_minutes(&self, meters: f64) -> f64 {
+    meters / 1000.0 / self.walking_speed_kmh.max(f64::EPSILON) * 60.0
+  }
+}
+
+/// An attraction to be visited.
+#[derive(Debug, Clone, Copy)]
+pub struct Stop {
+  pub attraction_id: i32,
+  pub location: Location,
+}
+
+/// A stop of a day, in the order of the visit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteStop {
+  pub attraction_id: i32,
+  /// The walk from the previous stop, or from the start for the first one.
+  pub walking_meters: f64,
+  /// The minutes since the start of the day when the stop is reached.
+  pub arrival_minutes: f64,
+  /// Whether the attraction is open when the stop is reached, None when the
+  /// start of the days or the opening hours aren't known.
+  pub open_on_arrival: Option<bool>,
+}
+
+/// The route of a day, from the start point through the stops and back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DayRoute {
+  pub day: usize,
+  pub stops: Vec<RouteStop>,
+  /// The walk of the whole day, the return to the start included.
+  pub walking_meters: f64,
+  pub walking_minutes: f64,
+  pub visit_minutes: f64,
+}
+
+impl DayRoute {
+  pub fn total_minutes(&self) -> f64 {
+    self.walking_minutes + self.visit_minutes
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Itinerary {
+  pub days: Vec<DayRoute>,
+  /// The attractions that don't fit in the hours of the days.
+  pub unscheduled: Vec<i32>,
+  /// The attractions without coordinates, they can't be routed.
+  pub without_location: Vec<i32>,
+}
+
+fn meters_between(one: &Location, other: &Location) -> f64 {
+  one.haversine_distance_to(other).meters()
+}
+
+/// The distances between the start, at index 0, and the stops.
+fn distances(start: &Location, stops: &[Stop]) -> Vec<Vec<f64>> {
+  let points = std::iter::once(*start)
+    .chain(stops.iter().map(|a_stop| a_stop.location))
+    .collect::<Vec<Location>>();
+  points
+    .iter()
+    .map(|one| {
+      points
+        .iter()
+        .map(|other| meters_between(one, other))
+        .collect()
+    })
+    .collect()
+}
+
+/// The length of a tour that leaves from the start and returns to it.
+fn tour_length(tour: &[usize], distances: &[Vec<f64>]) -> f64 {
+  let mut previous = 0;
+  let mut length = 0.0;
+  for point in tour.iter().chain(std::iter::once(&0)) {
+    length += distances[previous][*point];
+    previous = *point;
+  }
+  length
+}
+
+/// Visit the points from the start always going to the closest one left.
+fn nearest_neighbour(points: &[usize], distances: &[Vec<f64>]) -> Vec<usize> {
+  let mut left = points.to_vec();
+  let mut tour = Vec::with_capacity(points.len());
+  let mut current = 0;
+  while !left.is_empty() {
+    let (index, _) = left
+      .iter()
+      .enumerate()
+      .min_by(|(_, one), (_, other)| {
+        distances[current][**one].total_cmp(&distances[current][**other])
+      })
+      .unwrap();
+    current = left.swap_remove(index);
+    tour.push(current);
+  }
+  tour
+}
+
+/// Reverse the segments of the tour while that shortens it. The start stays
+/// at both ends of the tour.
+fn two_opt(tour: &mut [usize], distances: &[Vec<f64>]) {
+  let point_at = |tour: &[usize], index: usize| {
+    if index == 0 || index > tour.len() {
+      0
+    } else {
+      tour[index - 1]
+    }
+  };
+  let mut improved = true;
+  while improved {
+    improved = false;
+    // The edges go from the position i to i + 1, where 0 and len + 1 are the
+    // start.
+    for i in 0..tour.len() {
+      for j in i + 1..tour.len() {
+        let (a, b) = (point_at(tour, i), point_at(tour, i + 1));
+        let (c, d) = (point_at(tour, j + 1), point_at(tour, j + 2));
+        let delta =
+          distances[a][c] + distances[b][d] - distances[a][b] - distances[c][d];
+        if delta < -1e-9 {
+          tour[i..=j].reverse();
+          improved = true;
+        }
+      }
+    }
+  }
+}
+
+/// The shortest tour found through the points, from and back to the start.
+fn route(points: &[usize], distances: &[Vec<f64>]) -> Vec<usize> {
+  let mut tour = nearest_neighbour(points, distances);
+  two_opt(&mut tour, distances);
+  tour
+}
+
+/// Split the stops into groups of the same size, sweeping around the start.
+/// The sweep begins after the widest angle without stops, so no group spans
+/// the stops at both sides of it.
+fn sweep(start: &Location, stops: &[Stop], days: usize) -> Vec<Vec<usize>> {
+  let mut by_angle = stops
+    .iter()
+    .enumerate()
+    .map(|(index, a_stop)| {
+      let angle = (a_stop.location.latitude() - start.latitude())
+        .atan2(a_stop.location.longitude() - start.longitude());
+      (index + 1, angle)
+    })
+    .collect::<Vec<(usize, f64)>>();
+  by_angle.sort_by(|one, other| one.1.total_cmp(&other.1));
+  if let Some(widest) = (0..by_angle.len()).max_by(|one, other| {
+    let gap = |index: usize| {
+      let previous = by_angle[(index + by_angle.len() - 1) % by_angle.len()];
+      (by_angle[index].1 - previous.1).rem_euclid(2.0 * PI)
+    };
+    gap(*one).total_cmp(&gap(*other))
+  }) {
+    by_angle.rotate_left(widest);
+  }
+
+  let days = days.clamp(1, stops.len().max(1));
+  (0..days)
+    .map(|day| {
+      by_angle[day * by_angle.len() / days..(day + 1) * by_angle.len() / days]
+        .iter()
+        .map(|(point, _)| *point)
+        .collect()
+    })
+    .collect()
+}
+
+/// Plan the visit of the stops in days. The stops are grouped by their
+/// direction from the start, and every day is a round trip from the start
+/// ordered by nearest neighbour and improved by 2-opt over the haversine
+/// distances. When a day takes longer than the hours, the stop whose removal
+/// saves the most time is left unscheduled until it fits.
+pub fn plan(
+  start: &Location,
+  stops: &[Stop],
+  days: usize,
+  max_hours_per_day: f64,
+  settings: &ItinerarySettings,
+) -> Itinerary {
+  let distances = distances(start, stops);
+  let max_minutes = max_hours_per_day * 60.0;
+  let duration = |tour: &[usize]| {
+    settings.walking_minutes(tour_length(tour, &distances))
+      + tour.len() as f64 * settings.visit_minutes
+  };
+
+  let mut unscheduled = Vec::new();
+  let mut routes = Vec::new();
+  for group in sweep(start, stops, days) {
+    let mut tour = route(&group, &distances);
+    while !tour.is_empty() && duration(&tour) > max_minutes {
+      let (index, _) = (0..tour.len())
+        .map(|index| {
+          let mut without = tour.clone();
+          without.remove(index);
+          (index, duration(&without))
+        })
+        .min_by(|one, other| one.1.total_cmp(&other.1))
+        .unwrap();
+      unscheduled.push(stops[tour.remove(index) - 1].attraction_id);
+      tour = route(&tour, &distances);
+    }
+    routes.push(tour);
+  }
+
+  let days = routes
+    .into_iter()
+    .filter(|tour| !tour.is_empty())
+    .enumerate()
+    .map(|(index, tour)| {
+      let mut previous = 0;
+      let mut minutes = 0.0;
+      let stops = tour
+        .iter()
+        .map(|point| {
+          let walking_meters = distances[previous][*point];
+          minutes += settings.walking_minutes(walking_meters);
+          let a_stop = RouteStop {
+            attraction_id: stops[point - 1].attraction_id,
+            walking_meters,
+            arrival_minutes: minutes,
+            open_on_arrival: None,
+          };
+          minutes += settings.visit_minutes;
+          previous = *point;
+          a_stop
+        })
+        .collect::<Vec<RouteStop>>();
+      let walking_meters = tour_length(&tour, &distances);
+      DayRoute {
+        day: index + 1,
+        stops,
+        walking_meters,
+        walking_minutes: settings.walking_minutes(walking_meters),
+        visit_minutes: tour.len() as f64 * settings.visit_minutes,
+      }
+    })
+    .collect();
+  unscheduled.sort();
+  Itinerary {
+    days,
+    unscheduled,
+    without_location: Vec::new(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn stop(attraction_id: i32, latitude: f64, longitude: f64) -> Stop {
+    Stop {
+      attraction_id,
+      location: Location::new(latitude, longitude),
+    }
+  }
+
+  fn settings() -> ItinerarySettings {
+    ItinerarySettings {
+      visit_minutes: 30.0,
+      ..Default::default()
+    }
+  }
+
+  fn start() -> Location {
+    Location::new(-34.60, -58.40)
+  }
+
+  /// Two stops to the north and two to the south, given shuffled.
+  fn stops() -> Vec<Stop> {
+    vec![
+      stop(1, -34.59, -58.40),
+      stop(2, -34.61, -58.40),
+      stop(3, -34.58, -58.40),
+      stop(4, -34.62, -58.40),
+    ]
+  }
+
+  fn ids_by_day(itinerary: &Itinerary) -> Vec<Vec<i32>> {
+    itinerary
+      .days
+      .iter()
+      .map(|a_day| {
+        a_day
+          .stops
+          .iter()
+          .map(|a_stop| a_stop.attraction_id)
+          .collect::<Vec<i32>>()
+      })
+      .collect()
+  }
+
+  #[test]
+  fn every_day_visits_the_stops_in_the_same_direction_closest_first() {
+    let itinerary = plan(&start(), &stops(), 2, 8.0, &settings());
+    assert!(itinerary.unscheduled.is_empty());
+    let mut days = ids_by_day(&itinerary);
+    days.sort();
+    assert_eq!(days, vec![vec![1, 3], vec![2, 4]]);
+  }
+
+  #[test]
+  fn every_day_is_a_round_trip_from_the_start() {
+    let itinerary = plan(&start(), &stops(), 2, 8.0, &settings());
+    // Around 2.2 km to the farthest stop and back.
+    let a_day = &itinerary.days[0];
+    assert!((a_day.walking_meters - 4448.0).abs() < 50.0);
+    let walked = a_day
+      .stops
+      .iter()
+      .map(|a_stop| a_stop.walking_meters)
+      .sum::<f64>();
+    assert!(a_day.walking_meters > walked);
+  }
+
+  #[test]
+  fn the_arrivals_add_the_walks_and_the_visits() {
+    let settings = settings();
+    let itinerary = plan(&start(), &stops(), 2, 8.0, &settings);
+    let a_day = &itinerary.days[0];
+    assert_eq!(a_day.visit_minutes, 60.0);
+    let (first, second) = (&a_day.stops[0], &a_day.stops[1]);
+    assert_eq!(
+      first.arrival_minutes,
+      settings.walking_minutes(first.walking_meters)
+    );
+    assert!(
+      (second.arrival_minutes
+        - first.arrival_minutes
+        - settings.visit_minutes
+        - settings.walking_minutes(second.walking_meters))
+      .abs()
+        < 1e-9
+    );
+    assert_eq!(first.open_on_arrival, None);
+  }
+
+  #[test]
+  fn the_two_opt_removes_the_crossings_of_the_route() {
+    let start = start();
+    let stops = stops();
+    let distances = distances(&start, &stops);
+    // North, south, north, south crosses the start twice.
+    let mut tour = vec![1, 2, 3, 4];
+    let crossed = tour_length(&tour, &distances);
+    two_opt(&mut tour, &distances);
+    assert!(tour_length(&tour, &distances) < crossed);
+    let one_day = plan(&start, &stops, 1, 8.0, &settings());
+    assert!((one_day.days[0].walking_meters - 8896.0).abs() < 100.0);
+  }
+
+  #[test]
+  fn the_stops_that_dont_fit_in_the_hours_are_left_unscheduled() {
+    let short = plan(&start(), &stops(), 1, 2.5, &settings());
+    assert_eq!(short.unscheduled.len(), 2);
+    assert_eq!(short.days[0].stops.len(), 2);
+    assert!(short.days[0].total_minutes() <= 150.0);
+  }
+
+  #[test]
+  fn the_days_without_stops_arent_planned() {
+    let itinerary = plan(&start(), &stops(), 6, 8.0, &settings());
+    assert_eq!(itinerary.days.len(), 4);
+    let no_time = plan(&start(), &stops(), 1, 0.1, &settings());
+    assert!(no_time.days.is_empty());
+    assert_eq!(no_time.unscheduled, vec![1, 2, 3, 4]);
+  }
+}
@@ -0,0 +1,245 @@
+use crate::model::{
+  attraction_controller::schedules_of,
+  attraction_repository::AttractionRepository,
+  itinerary::{self, Itinerary, ItinerarySettings, Stop},
+};
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDateTime};
+use geoutils::Location;
+
+/// What the user wants to visit and how.
+#[derive(Debug, Clone)]
+pub struct ItineraryRequest {
+  pub attraction_ids: Vec<i32>,
+  pub start_latitude: f64,
+  pub start_longitude: f64,
+  pub days: usize,
+  pub max_hours_per_day: f64,
+  /// The moment when the first day starts, in the local time of the
+  /// attractions. The next days start at the same time.
+  pub start_at: Option<NaiveDateTime>,
+}
+
+#[async_trait]
+pub trait ItineraryController: Send + Sync + 'static {
+  async fn plan(&self, request: ItineraryRequest) -> Result<Itinerary, String>;
+}
+
+#[derive(Clone)]
+pub struct ItineraryControllerImpl<AttractionRepo> {
+  attraction_repository: AttractionRepo,
+  settings: ItinerarySettings,
+}
+
+impl<AttractionRepo> ItineraryControllerImpl<AttractionRepo>
+where
+  AttractionRepo: AttractionRepository,
+{
+  pub fn new(
+    attraction_repository: AttractionRepo,
+    settings: ItinerarySettings,
+  ) -> Self {
+    ItineraryControllerImpl {
+      attraction_repository,
+      settings,
+    }
+  }
+
+  fn validate(&self, request: &ItineraryRequest) -> Result<(), String> {
+    if request.attraction_ids.is_empty() {
+      return Err(String::from("The itinerary needs at least one attraction"));
+    }
+    if request.attraction_ids.len() > self.settings.max_attractions {
+      return Err(format!(
+        "The itinerary can have {} attractions at most",
+        self.settings.max_attractions
+      ));
+    }
+    if !(1..=self.settings.max_days).contains(&request.days) {
+      return Err(format!(
+        "The days must be between 1 and {}",
+        self.settings.max_days
+      ));
+    }
+    if !(request.max_hours_per_day > 0.0 && request.max_hours_per_day <= 24.0) {
+      return Err(String::from("The hours per day must be between 0 and 24"));
+    }
+    if !(-90.0..=90.0).contains(&request.start_latitude)
+      || !(-180.0..=180.0).contains(&request.start_longitude)
+    {
+      return Err(String::from("The start point is out of range"));
+    }
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl<AttractionRepo> ItineraryController
+  for ItineraryControllerImpl<AttractionRepo>
+where
+  AttractionRepo: AttractionRepository + Send + Sync + 'static,
+{
+  /// Plan the visit of the attractions, the repeated ones are visited once.
+  ///
+  /// # Return:
+  /// * Err if the request is invalid or an attraction doesn't exist.
+  async fn plan(&self, request: ItineraryRequest) -> Result<Itinerary, String> {
+    let mut request = request;
+    request.attraction_ids.sort();
+    request.attraction_ids.dedup();
+    self.validate(&request)?;
+
+    let attractions = self
+      .attraction_repository
+      .attractions_by_ids(request.attraction_ids.clone())
+      .await
+      .map_err(|e| e.to_string())?;
+    let missing = request
+      .attraction_ids
+      .iter()
+      .filter(|id| {
+        !attractions
+          .iter()
+          .any(|an_attraction| an_attraction.get_id() == **id)
+      })
+      .map(|id| id.to_string())
+      .collect::<Vec<String>>();
+    if !missing.is_empty() {
+      return Err(format!(
+        "The attractions {} don't exist",
+        missing.join(", ")
+      ));
+    }
+
+    let mut stops = Vec::new();
+    let mut without_location = Vec::new();
+    for an_attraction in attractions.iter() {
+      match an_attraction.location() {
+        Some(location) => stops.push(Stop {
+          attraction_id: an_attraction.get_id(),
+          location,
+        }),
+        None => without_location.push(an_attraction.get_id()),
+      }
+    }
+    let start = Location::new(request.start_latitude, request.start_longitude);
+    let mut planned = itinerary::plan(
+      &start,
+      &stops,
+      request.days,
+      request.max_hours_per_day,
+      &self.settings,
+    );
+    planned.without_location = without_location;
+    if let Some(start_at) = request.start_at {
+      let days = Duration::days(request.days as i64);
+      let schedules = schedules_of(
+        &self.attraction_repository,
+        request.attraction_ids.clone(),
+        start_at.date(),
+        (start_at + days).date(),
+      )
+      .await
+      .map_err(|e| e.to_string())?;
+      for a_day in planned.days.iter_mut() {
+        let day_start = start_at + Duration::days(a_day.day as i64 - 1);
+        for a_stop in a_day.stops.iter_mut() {
+          let arrival = day_start
+            + Duration::seconds((a_stop.arrival_minutes * 60.0) as i64);
+          a_stop.open_on_arrival =
+            schedules.is_open(a_stop.attraction_id, arrival);
+        }
+      }
+    }
+    Ok(planned)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::attraction_repository::DummyAttractionRepo;
+
+  /// The repository isn't reached, the requests are rejected before.
+  fn controller() -> ItineraryControllerImpl<DummyAttractionRepo> {
+    ItineraryControllerImpl::new(
+      DummyAttractionRepo,
+      ItinerarySettings {
+        max_attractions: 3,
+        max_days: 2,
+        ..Default::default()
+      },
+    )
+  }
+
+  fn request() -> ItineraryRequest {
+    ItineraryRequest {
+      attraction_ids: vec![1, 2, 3],
+      start_latitude: -34.60,
+      start_longitude: -58.40,
+      days: 1,
+      max_hours_per_day: 8.0,
+      start_at: None,
+    }
+  }
+
+  #[tokio::test]
+  async fn an_itinerary_without_attractions_is_rejected() {
+    let result = controller()
+      .plan(ItineraryRequest {
+        attraction_ids: Vec::new(),
+        ..request()
+      })
+      .await;
+    assert!(result.is_err());
+  }
+
+  #[tokio::test]
+  async fn an_itinerary_with_too_many_attractions_is_rejected() {
+    let result = controller()
+      .plan(ItineraryRequest {
+        attraction_ids: vec![1, 2, 3, 4],
+        ..request()
+      })
+      .await;
+    assert_eq!(
+      result,
+      Err(String::from("The itinerary can have 3 attractions at most"))
+    );
+  }
+
+  #[tokio::test]
+  async fn the_days_and_the_hours_must_be_in_range() {
+    for invalid in [
+      ItineraryRequest {
+        days: 0,
+        ..request()
+      },
+      ItineraryRequest {
+        days: 3,
+        ..request()
+      },
+      ItineraryRequest {
+        max_hours_per_day: 0.0,
+        ..request()
+      },
+      ItineraryRequest {
+        max_hours_per_day: 25.0,
+        ..request()
+      },
+    ] {
+      assert!(controller().plan(invalid).await.is_err());
+    }
+  }
+
+  #[tokio::test]
+  async fn a_start_point_out_of_range_is_rejected() {
+    let result = controller()
+      .plan(ItineraryRequest {
+        start_latitude: 91.0,
+        ..request()
+      })
+      .await;
+    assert_eq!(result, Err(String::from("The start point is out of range")));
+  }
+}
@@ -0,0 +1,595 @@
+use crate::model::attraction::{AttractionHoliday, AttractionOpeningHours};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+use std::{
+  collections::{HashMap, HashSet},
+  fmt,
+  str::FromStr,
+};
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+const MONTHS: [&str; 12] = [
+  "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov",
+  "dec",
+];
+const WEEKDAYS: [&str; 7] = ["mo", "tu", "we", "th", "fr", "sa", "su"];
+
+/// A period of a day when the attraction is open, in minutes since the
+/// midnight. It ends after the midnight when it goes into the next day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeSpan {
+  pub from: u32,
+  pub to: u32,
+}
+
+impl TimeSpan {
+  const FULL_DAY: TimeSpan = TimeSpan {
+    from: 0,
+    to: MINUTES_PER_DAY,
+  };
+
+  fn contains(&self, minute: u32) -> bool {
+    self.from <= minute && minute < self.to
+  }
+}
+
+impl fmt::Display for TimeSpan {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let time = |minutes: u32| {
+      let minutes = if minutes > MINUTES_PER_DAY {
+        minutes - MINUTES_PER_DAY
+      } else {
+        minutes
+      };
+      format!("{:02}:{:02}", minutes / 60, minutes % 60)
+    };
+    write!(f, "{}-{}", time(self.from), time(self.to))
+  }
+}
+
+/// The days of the year between two dates, both included. It goes over the
+/// end of the year when the first date is after the last one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DateRange {
+  from: (u32, u32),
+  to: (u32, u32),
+}
+
+impl DateRange {
+  fn matches(&self, date: NaiveDate) -> bool {
+    let day = (date.month(), date.day());
+    if self.from <= self.to {
+      self.from <= day && day <= self.to
+    } else {
+      day >= self.from || day <= self.to
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DaySelector {
+  Weekdays { from: u32, to: u32 },
+  PublicHoliday,
+}
+
+impl DaySelector {
+  fn matches(&self, date: NaiveDate, holiday: bool) -> bool {
+    match self {
+      DaySelector::Weekdays {
+        from,
+        to,
+      } => {
+        let day = date.weekday().num_days_from_monday();
+        if from <= to {
+          *from <= day && day <= *to
+        } else {
+          day >= *from || day <= *to
+        }
+      },
+      DaySelector::PublicHoliday => holiday,
+    }
+  }
+}
+
+/// A rule of the opening hours: the days it applies to and the hours of
+/// them, none when it closes them.
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+  dates: Vec<DateRange>,
+  days: Vec<DaySelector>,
+  spans: Vec<TimeSpan>,
+  closed: bool,
+}
+
+impl Rule {
+  fn matches(&self, date: NaiveDate, holiday: bool) -> bool {
+    (self.dates.is_empty()
+      || self.dates.iter().any(|a_range| a_range.matches(date)))
+      && (self.days.is_empty()
+        || self.days.iter().any(|a_day| a_day.matches(date, holiday)))
+  }
+}
+
+/// The opening hours of an attraction in the OSM opening_hours syntax, like
+/// "Mo-Fr 09:00-18:00; Sa 10:00-14:00; Dec 24-Dec 26 off; PH off".
+///
+/// The rules are separated by semicolons and the later ones replace the
+/// earlier ones in the days they apply to. A rule has optional month or date
+/// ranges (Jun-Aug, Dec 24-26), optional weekdays and public holidays
+/// (Mo-Fr,Su,PH), and the hours (09:00-12:00,13:00-17:00, 24/7) or off. The
+/// hours that end before they start go past the midnight, and a rule without
+/// hours is open the whole day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpeningHours {
+  rules: Vec<Rule>,
+}
+
+impl OpeningHours {
+  /// The periods when the attraction is open in the date.
+  pub fn spans_on(&self, date: NaiveDate, holiday: bool) -> Vec<TimeSpan> {
+    let mut spans = Vec::new();
+    for a_rule in self
+      .rules
+      .iter()
+      .filter(|a_rule| a_rule.matches(date, holiday))
+    {
+      spans = match (a_rule.closed, a_rule.spans.is_empty()) {
+        (true, _) => Vec::new(),
+        (false, true) => vec![TimeSpan::FULL_DAY],
+        (false, false) => a_rule.spans.clone(),
+      };
+    }
+    spans
+  }
+
+  /// Whether the attraction is open at the moment, in its local time. The
+  /// hours of the previous day that go past the midnight are considered.
+  pub fn is_open(
+    &self,
+    at: NaiveDateTime,
+    holidays: &HashSet<NaiveDate>,
+  ) -> bool {
+    let minute = at.hour() * 60 + at.minute();
+    let date = at.date();
+    if self
+      .spans_on(date, holidays.contains(&date))
+      .iter()
+      .any(|a_span| a_span.contains(minute))
+    {
+      return true;
+    }
+    date.pred_opt().is_some_and(|previous| {
+      self
+        .spans_on(previous, holidays.contains(&previous))
+        .iter()
+        .any(|a_span| a_span.contains(minute + MINUTES_PER_DAY))
+    })
+  }
+}
+
+impl FromStr for OpeningHours {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let rules = s
+      .split(';')
+      .map(str::trim)
+      .filter(|a_rule| !a_rule.is_empty())
+      .map(|a_rule| {
+        Parser::new(a_rule)
+          .rule()
+          .map_err(|e| format!("Invalid opening hours \"{a_rule}\": {e}"))
+      })
+      .collect::<Result<Vec<Rule>, String>>()?;
+    if rules.is_empty() {
+      return Err(String::from("The opening hours can't be empty"));
+    }
+    Ok(OpeningHours {
+      rules,
+    })
+  }
+}
+
+/// The opening hours of some attractions with the holidays of their
+/// countries.
+#[derive(Debug, Clone, Default)]
+pub struct Schedules {
+  hours: HashMap<i32, OpeningHours>,
+  holidays: HashMap<i32, HashSet<NaiveDate>>,
+}
+
+impl Schedules {
+  /// The opening hours that can't be parsed are left out, they are checked
+  /// before being saved.
+  pub fn new(
+    opening_hours: &[AttractionOpeningHours],
+    holidays: &[AttractionHoliday],
+  ) -> Self {
+    let hours = opening_hours
+      .iter()
+      .filter_map(|some_hours| {
+        some_hours
+          .get_opening_hours()
+          .parse::<OpeningHours>()
+          .ok()
+          .map(|parsed| (some_hours.get_attraction_id(), parsed))
+      })
+      .collect();
+    let mut by_attraction: HashMap<i32, HashSet<NaiveDate>> = HashMap::new();
+    for a_holiday in holidays {
+      by_attraction
+        .entry(a_holiday.attraction_id)
+        .or_default()
+        .insert(a_holiday.day);
+    }
+    Schedules {
+      hours,
+      holidays: by_attraction,
+    }
+  }
+
+  /// Whether the attraction is open at the moment, None when it has no
+  /// opening hours.
+  pub fn is_open(&self, attraction_id: i32, at: NaiveDateTime) -> Option<bool> {
+    let no_holidays = HashSet::new();
+    self.hours.get(&attraction_id).map(|hours| {
+      hours.is_open(
+        at,
+        self.holidays.get(&attraction_id).unwrap_or(&no_holidays),
+      )
+    })
+  }
+}
+
+/// Reads a rule from the start to the end.
+struct Parser {
+  chars: Vec<char>,
+  position: usize,
+}
+
+impl Parser {
+  fn new(rule: &str) -> Self {
+    Parser {
+      chars: rule.chars().collect(),
+      position: 0,
+    }
+  }
+
+  fn skip_spaces(&mut self) {
+    while self
+      .chars
+      .get(self.position)
+      .is_some_and(|c| c.is_whitespace())
+    {
+      self.position += 1;
+    }
+  }
+
+  fn peek(&self) -> Option<char> {
+    self.chars.get(self.position).copied()
+  }
+
+  fn eat(&mut self, expected: char) -> bool {
+    self.skip_spaces();
+    if self.peek() == Some(expected) {
+      self.position += 1;
+      true
+    } else {
+      false
+    }
+  }
+
+  fn at_end(&mut self) -> bool {
+    self.skip_spaces();
+    self.position >= self.chars.len()
+  }
+
+  /// The next letters in lower case, without consuming them.
+  fn word(&mut self) -> String {
+    self.skip_spaces();
+    self.chars[self.position..]
+      .iter()
+      .take_while(|c| c.is_ascii_alphabetic())
+      .collect::<String>()
+      .to_lowercase()
+  }
+
+  fn consume(&mut self, word: &str) {
+    self.skip_spaces();
+    self.position += word.len();
+  }
+
+  fn number(&mut self) -> Option<u32> {
+    self.skip_spaces();
+    let digits = self.chars[self.position..]
+      .iter()
+      .take_while(|c| c.is_ascii_digit())
+      .collect::<String>();
+    if digits.is_empty() {
+      return None;
+    }
+    self.position += digits.len();
+    digits.parse().ok()
+  }
+
+  fn rule(&mut self) -> Result<Rule, String> {
+    let dates = self.dates()?;
+    let days = self.days()?;
+    let mut spans = Vec::new();
+    let mut closed = false;
+    let word = self.word();
+    if word == "off" || word == "closed" {
+      self.consume(&word);
+      closed = true;
+    } else if self.eat_text("24/7") {
+      spans.push(TimeSpan::FULL_DAY);
+    } else if !word.is_empty() {
+      return Err(format!("unexpected \"{word}\""));
+    } else if !self.at_end() {
+      spans = self.spans()?;
+    }
+    if !self.at_end() {
+      return Err(format!(
+        "unexpected \"{}\"",
+        self.chars[self.position..].iter().collect::<String>()
+      ));
+    }
+    Ok(Rule {
+      dates,
+      days,
+      spans,
+      closed,
+    })
+  }
+
+  fn eat_text(&mut self, text: &str) -> bool {
+    self.skip_spaces();
+    let end = self.position + text.chars().count();
+    if end <= self.chars.len()
+      && self.chars[self.position..end]
+        .iter()
+        .copied()
+        .eq(text.chars())
+    {
+      self.position = end;
+      true
+    } else {
+      false
+    }
+  }
+
+  fn month(&mut self) -> Option<u32> {
+    let word = self.word();
+    let month = MONTHS.iter().position(|a_month| *a_month == word)?;
+    self.consume(&word);
+    Some(month as u32 + 1)
+  }
+
+  /// The month and the optional day of a date.
+  fn date(&mut self) -> Option<(u32, Option<u32>)> {
+    let month = self.month()?;
+    Some((month, self.number()))
+  }
+
+  fn dates(&mut self) -> Result<Vec<DateRange>, String> {
+    let mut dates = Vec::new();
+    while let Some((month, day)) = self.date() {
+      let from = (month, day.unwrap_or(1));
+      let to = if self.eat('-') {
+        match (self.date(), day) {
+          (Some((to_month, to_day)), _) => (to_month, to_day.unwrap_or(31)),
+          (None, Some(_)) => {
+            (month, self.number().ok_or("a day is missing in the range")?)
+          },
+          (None, None) => return Err(String::from("a month is missing")),
+        }
+      } else {
+        (month, day.unwrap_or(31))
+      };
+      if !(1..=31).contains(&from.1) || !(1..=31).contains(&to.1) {
+        return Err(String::from("the day of the month is out of range"));
+      }
+      dates.push(DateRange {
+        from,
+        to,
+      });
+      if !self.eat(',') {
+        break;
+      }
+    }
+    Ok(dates)
+  }
+
+  fn weekday(&mut self) -> Option<u32> {
+    let word = self.word();
+    let weekday = WEEKDAYS.iter().position(|a_day| *a_day == word)?;
+    self.consume(&word);
+    Some(weekday as u32)
+  }
+
+  fn days(&mut self) -> Result<Vec<DaySelector>, String> {
+    let mut days = Vec::new();
+    loop {
+      if self.word() == "ph" {
+        self.consume("ph");
+        days.push(DaySelector::PublicHoliday);
+      } else if let Some(from) = self.weekday() {
+        let to = if self.eat('-') {
+          self.weekday().ok_or("a weekday is missing in the range")?
+        } else {
+          from
+        };
+        days.push(DaySelector::Weekdays {
+          from,
+          to,
+        });
+      } else {
+        break;
+      }
+      let position = self.position;
+      if !self.eat(',') {
+        break;
+      }
+      let word = self.word();
+      if word != "ph" && !WEEKDAYS.contains(&word.as_str()) {
+        self.position = position;
+        break;
+      }
+    }
+    Ok(days)
+  }
+
+  fn time(&mut self) -> Result<u32, String> {
+    let hours = self.number().ok_or("an hour is missing")?;
+    if self.peek() != Some(':') {
+      return Err(String::from("the time must be HH:MM"));
+    }
+    self.position += 1;
+    let minutes = self.number().ok_or("the minutes are missing")?;
+    if hours > 24 || minutes > 59 || (hours == 24 && minutes > 0) {
+      return Err(format!("the time {hours:02}:{minutes:02} is out of range"));
+    }
+    Ok(hours * 60 + minutes)
+  }
+
+  fn spans(&mut self) -> Result<Vec<TimeSpan>, String> {
+    let mut spans = Vec::new();
+    loop {
+      let from = self.time()?;
+      if !self.eat('-') {
+        return Err(String::from("the end of the hours is missing"));
+      }
+      let to = self.time()?;
+      if from >= MINUTES_PER_DAY {
+        return Err(String::from("the hours can't start at 24:00"));
+      }
+      let to = if to <= from { to + MINUTES_PER_DAY } else { to };
+      spans.push(TimeSpan {
+        from,
+        to,
+      });
+      if !self.eat(',') {
+        break;
+      }
+    }
+    Ok(spans)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn at(date: &str, time: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(&format!("{date} {time}"), "%Y-%m-%d %H:%M")
+      .unwrap()
+  }
+
+  fn day(date: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+  }
+
+  fn hours() -> OpeningHours {
+    "Mo-Fr 09:00-12:00,13:00-18:00; Sa 22:00-02:00; Dec 24-26 off; Jul-Aug \
+     Mo-Su 10:00-20:00; PH off"
+      .parse::<OpeningHours>()
+      .unwrap()
+  }
+
+  fn no_holidays() -> HashSet<NaiveDate> {
+    HashSet::new()
+  }
+
+  #[test]
+  fn is_open_only_within_the_hours_of_the_weekday() {
+    let hours = hours();
+    // 2023-03-06 is a Monday.
+    assert!(hours.is_open(at("2023-03-06", "09:00"), &no_holidays()));
+    assert!(!hours.is_open(at("2023-03-06", "12:30"), &no_holidays()));
+    assert!(!hours.is_open(at("2023-03-06", "18:00"), &no_holidays()));
+    assert!(!hours.is_open(at("2023-03-12", "10:00"), &no_holidays()));
+    assert_eq!(
+      hours
+        .spans_on(day("2023-03-06"), false)
+        .iter()
+        .map(|a_span| a_span.to_string())
+        .collect::<Vec<String>>(),
+      vec!["09:00-12:00", "13:00-18:00"]
+    );
+  }
+
+  #[test]
+  fn the_hours_past_the_midnight_open_the_next_day() {
+    let hours = hours();
+    assert!(hours.is_open(at("2023-03-11", "23:00"), &no_holidays()));
+    assert!(hours.is_open(at("2023-03-12", "01:30"), &no_holidays()));
+    assert!(!hours.is_open(at("2023-03-12", "02:00"), &no_holidays()));
+    assert_eq!(
+      hours.spans_on(day("2023-03-11"), false)[0].to_string(),
+      "22:00-02:00"
+    );
+  }
+
+  #[test]
+  fn the_later_rules_replace_the_earlier_ones() {
+    let hours = hours();
+    // The season opens the Sunday, and Christmas is closed.
+    assert!(hours.is_open(at("2023-07-09", "19:00"), &no_holidays()));
+    assert!(!hours.is_open(at("2023-12-25", "10:00"), &no_holidays()));
+  }
+
+  #[test]
+  fn the_public_holidays_are_closed() {
+    let holidays = HashSet::from([day("2023-05-01")]);
+    // A Monday.
+    assert!(!hours().is_open(at("2023-05-01", "10:00"), &holidays));
+    assert!(hours().is_open(at("2023-05-01", "10:00"), &no_holidays()));
+  }
+
+  #[test]
+  fn the_seasons_and_the_weekdays_go_over_the_end_of_the_year_and_the_week() {
+    let winter = "Nov-Feb Fr-Mo 10:00-16:00".parse::<OpeningHours>().unwrap();
+    assert!(winter.is_open(at("2024-01-07", "10:00"), &no_holidays()));
+    assert!(!winter.is_open(at("2024-01-09", "10:00"), &no_holidays()));
+    assert!(!winter.is_open(at("2024-03-08", "10:00"), &no_holidays()));
+  }
+
+  #[test]
+  fn the_rules_without_hours_open_the_whole_day() {
+    let always = "24/7".parse::<OpeningHours>().unwrap();
+    assert!(always.is_open(at("2024-01-09", "03:00"), &no_holidays()));
+    let weekends = "Sa,Su".parse::<OpeningHours>().unwrap();
+    assert!(weekends.is_open(at("2024-01-07", "03:00"), &no_holidays()));
+    assert!(!weekends.is_open(at("2024-01-08", "03:00"), &no_holidays()));
+  }
+
+  #[test]
+  fn the_invalid_opening_hours_are_rejected() {
+    for invalid in ["", "Mo-Xy 10:00-12:00", "25:00-26:00", "10-12", "SH off"] {
+      assert!(invalid.parse::<OpeningHours>().is_err(), "{invalid}");
+    }
+  }
+
+  #[test]
+  fn the_schedules_use_the_holidays_of_every_attraction() {
+    let opening_hours = [1, 2, 3].map(|attraction_id| AttractionOpeningHours {
+      attraction_id,
+      opening_hours: String::from(if attraction_id == 3 {
+        "Mo-Xy 10:00-12:00"
+      } else {
+        "Mo-Fr 09:00-18:00; PH off"
+      }),
+      updated_at: at("2023-01-01", "00:00"),
+    });
+    let holidays = [AttractionHoliday {
+      attraction_id: 1,
+      day: day("2023-05-01"),
+    }];
+    let schedules = Schedules::new(&opening_hours, &holidays);
+    let may_day = at("2023-05-01", "10:00");
+    assert_eq!(schedules.is_open(1, may_day), Some(false));
+    assert_eq!(schedules.is_open(2, may_day), Some(true));
+    // The invalid hours and the attractions without them aren't known.
+    assert_eq!(schedules.is_open(3, may_day), None);
+    assert_eq!(schedules.is_open(4, may_day), None);
+  }
+}
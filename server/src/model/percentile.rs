@@ -0,0 +1,254 @@
+use std::{fmt, str::FromStr};
+
+/// The method used to estimate a percentile from a sample.
+///
+/// The interpolated methods follow the definitions of Hyndman & Fan (1996),
+/// "Sample Quantiles in Statistical Packages". All of them estimate the
+/// percentile as `x[j] + g * (x[j + 1] - x[j])` where `x` is the sorted sample
+/// (1-based), `j` is the integer part and `g` the fractional part of the
+/// position `h`, that is what changes between methods.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PercentileMethod {
+  /// The smallest value such that at least p of the sample is less or equal
+  /// to it, without interpolation (Hyndman–Fan type 1).
+  NearestRank,
+  /// Linear interpolation of the empirical distribution function, h = n * p
+  /// (Hyndman–Fan type 4).
+  LinearInterpolation,
+  /// Piecewise linear function where the knots are the midpoints of the
+  /// steps of the empirical distribution function, h = n * p + 1/2
+  /// (Hyndman–Fan type 5).
+  Hazen,
+  /// h = (n + 1) * p (Hyndman–Fan type 6), used by Minitab and SPSS.
+  Weibull,
+  /// h = (n - 1) * p + 1 (Hyndman–Fan type 7), used by default in R, NumPy
+  /// and spreadsheets.
+  #[default]
+  HyndmanFan7,
+}
+
+impl PercentileMethod {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      PercentileMethod::NearestRank => "NEAREST_RANK",
+      PercentileMethod::LinearInterpolation => "LINEAR_INTERPOLATION",
+      PercentileMethod::Hazen => "HAZEN",
+      PercentileMethod::Weibull => "WEIBULL",
+      PercentileMethod::HyndmanFan7 => "HYNDMAN_FAN_7",
+    }
+  }
+}
+
+impl fmt::Display for PercentileMethod {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+impl FromStr for PercentileMethod {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_uppercase().as_str() {
+      "NEAREST_RANK" => Ok(PercentileMethod::NearestRank),
+      "LINEAR_INTERPOLATION" => Ok(PercentileMethod::LinearInterpolation),
+      "HAZEN" => Ok(PercentileMethod::Hazen),
+      "WEIBULL" => Ok(PercentileMethod::Weibull),
+      "HYNDMAN_FAN_7" => Ok(PercentileMethod::HyndmanFan7),
+      _ => Err(format!("Unknown percentile method: {}", s)),
+    }
+  }
+}
+
+/// Estimate a percentile of a sample.
+///
+/// # Arguments:
+/// * sorted: the sample sorted in ascending order.
+/// * p: the percentile to estimate, between 0 and 1. Values outside the range
+///   are clamped.
+/// * method: the estimation method.
+///
+/// # Return:
+/// * Some with the estimated percentile.
+/// * None if the sample is empty.
+pub fn percentile(
+  sorted: &[f64],
+  p: f64,
+  method: PercentileMethod,
+) -> Option<f64> {
+  let first = *sorted.first()?;
+  let last = *sorted.last()?;
+  let p = p.clamp(0.0, 1.0);
+  let n = sorted.len() as f64;
+
+  // The position in the sorted sample, 1-based.
+  let h = match method {
+    PercentileMethod::NearestRank => {
+      let rank = (n * p).ceil().max(1.0);
+      return Some(sorted[rank as usize - 1]);
+    },
+    PercentileMethod::LinearInterpolation => n * p,
+    PercentileMethod::Hazen => n * p + 0.5,
+    PercentileMethod::Weibull => (n + 1.0) * p,
+    PercentileMethod::HyndmanFan7 => (n - 1.0) * p + 1.0,
+  };
+
+  if h < 1.0 {
+    return Some(first);
+  }
+  if h >= n {
+    return Some(last);
+  }
+  let j = h.floor();
+  let g = h - j;
+  let lower = sorted[j as usize - 1];
+  let upper = sorted[j as usize];
+  Some(lower + g * (upper - lower))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use proptest::prelude::*;
+
+  const SAMPLE: [f64; 5] = [15.0, 20.0, 35.0, 40.0, 50.0];
+
+  fn assert_close(actual: Option<f64>, expected: f64) {
+    let actual = actual.unwrap();
+    assert!(
+      (actual - expected).abs() < 1e-9,
+      "expected {expected} but was {actual}"
+    );
+  }
+
+  #[test]
+  fn nearest_rank_matches_reference_values() {
+    let method = PercentileMethod::NearestRank;
+    assert_close(percentile(&SAMPLE, 0.05, method), 15.0);
+    assert_close(percentile(&SAMPLE, 0.30, method), 20.0);
+    assert_close(percentile(&SAMPLE, 0.40, method), 20.0);
+    assert_close(percentile(&SAMPLE, 0.50, method), 35.0);
+    assert_close(percentile(&SAMPLE, 1.00, method), 50.0);
+  }
+
+  #[test]
+  fn hyndman_fan_7_matches_reference_values() {
+    // Same values returned by R quantile(type = 7) and numpy.percentile.
+    let method = PercentileMethod::HyndmanFan7;
+    assert_close(percentile(&SAMPLE, 0.40, method), 29.0);
+    assert_close(percentile(&SAMPLE, 0.50, method), 35.0);
+    assert_close(percentile(&SAMPLE, 0.95, method), 48.0);
+    assert_close(percentile(&[1.0, 2.0, 3.0, 4.0], 0.25, method), 1.75);
+  }
+
+  #[test]
+  fn interpolated_methods_match_reference_values() {
+    // Values returned by R quantile(c(1, 2, 3, 4), 0.25, type = 4, 5 and 6).
+    let sample = [1.0, 2.0, 3.0, 4.0];
+    assert_close(
+      percentile(&sample, 0.25, PercentileMethod::LinearInterpolation),
+      1.0,
+    );
+    assert_close(percentile(&sample, 0.25, PercentileMethod::Hazen), 1.5);
+    assert_close(percentile(&sample, 0.25, PercentileMethod::Weibull), 1.25);
+    assert_close(
+      percentile(&SAMPLE, 0.40, PercentileMethod::LinearInterpolation),
+      20.0,
+    );
+    assert_close(percentile(&SAMPLE, 0.40, PercentileMethod::Weibull), 26.0);
+  }
+
+  #[test]
+  fn empty_sample_has_no_percentile() {
+    assert_eq!(percentile(&[], 0.5, PercentileMethod::HyndmanFan7), None);
+    assert_eq!(percentile(&[], 0.5, PercentileMethod::NearestRank), None);
+  }
+
+  #[test]
+  fn parses_the_configured_method() {
+    assert_eq!(
+      "hyndman_fan_7".parse::<PercentileMethod>(),
+      Ok(PercentileMethod::HyndmanFan7)
+    );
+    assert_eq!(
+      " NEAREST_RANK ".parse::<PercentileMethod>(),
+      Ok(PercentileMethod::NearestRank)
+    );
+    assert!("median".parse::<PercentileMethod>().is_err());
+  }
+
+  fn any_method() -> impl Strategy<Value = PercentileMethod> {
+    prop_oneof![
+      Just(PercentileMethod::NearestRank),
+      Just(PercentileMethod::LinearInterpolation),
+      Just(PercentileMethod::Hazen),
+      Just(PercentileMethod::Weibull),
+      Just(PercentileMethod::HyndmanFan7),
+    ]
+  }
+
+  fn sorted_sample() -> impl Strategy<Value = Vec<f64>> {
+    prop::collection::vec(0.0..=1.0f64, 1..200).prop_map(|mut sample| {
+      sample.sort_by(|a, b| a.partial_cmp(b).unwrap());
+      sample
+    })
+  }
+
+  proptest! {
+    #[test]
+    fn percentile_is_within_the_sample_range(
+      sample in sorted_sample(),
+      p in 0.0..=1.0f64,
+      method in any_method(),
+    ) {
+      let value = percentile(&sample, p, method).unwrap();
+      prop_assert!(value >= sample[0]);
+      prop_assert!(value <= sample[sample.len() - 1]);
+    }
+
+    #[test]
+    fn percentile_is_monotonic(
+      sample in sorted_sample(),
+      p in 0.0..=1.0f64,
+      q in 0.0..=1.0f64,
+      method in any_method(),
+    ) {
+      let (low, high) = if p <= q { (p, q) } else { (q, p) };
+      let low_value = percentile(&sample, low, method).unwrap();
+      let high_value = percentile(&sample, high, method).unwrap();
+      prop_assert!(low_value <= high_value);
+    }
+
+    #[test]
+    fn extremes_are_min_and_max(
+      sample in sorted_sample(),
+      method in any_method(),
+    ) {
+      prop_assert_eq!(percentile(&sample, 0.0, method), Some(sample[0]));
+      prop_assert_eq!(
+        percentile(&sample, 1.0, method),
+        Some(sample[sample.len() - 1])
+      );
+    }
+
+    #[test]
+    fn single_rating_is_every_percentile(
+      rate in 0.0..=1.0f64,
+      p in 0.0..=1.0f64,
+      method in any_method(),
+    ) {
+      prop_assert_eq!(percentile(&[rate], p, method), Some(rate));
+    }
+
+    #[test]
+    fn constant_sample_is_every_percentile(
+      rate in 0.0..=1.0f64,
+      size in 1..50usize,
+      p in 0.0..=1.0f64,
+      method in any_method(),
+    ) {
+      let sample = vec![rate; size];
+      prop_assert_eq!(percentile(&sample, p, method), Some(rate));
+    }
+  }
+}
@@ -0,0 +1,421 @@
+use crate::model::attraction::AttractionRatingSummary;
+use bigdecimal::ToPrimitive;
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+use std::{collections::HashMap, fmt, hash::Hash, str::FromStr};
+
+/// The mean rate assumed when there are no ratings at all, the middle of the
+/// rating scale.
+const DEFAULT_PRIOR_MEAN: f64 = 0.5;
+
+/// The group of attractions whose mean rate is the prior of the Bayesian
+/// average of an attraction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum PriorGroup {
+  #[default]
+  City,
+  Type,
+}
+
+/// The score used to sort the ranking.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum RankingOrder {
+  #[default]
+  Bayesian,
+  Recency,
+  Wilson,
+  Popularity,
+}
+
+impl PriorGroup {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      PriorGroup::City => "CITY",
+      PriorGroup::Type => "TYPE",
+    }
+  }
+}
+
+impl RankingOrder {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      RankingOrder::Bayesian => "BAYESIAN",
+      RankingOrder::Recency => "RECENCY",
+      RankingOrder::Wilson => "WILSON",
+      RankingOrder::Popularity => "POPULARITY",
+    }
+  }
+}
+
+impl fmt::Display for PriorGroup {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+impl fmt::Display for RankingOrder {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+impl FromStr for PriorGroup {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_uppercase().as_str() {
+      "CITY" => Ok(PriorGroup::City),
+      "TYPE" => Ok(PriorGroup::Type),
+      _ => Err(format!("Unknown prior group: {}", s)),
+    }
+  }
+}
+
+impl FromStr for RankingOrder {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_uppercase().as_str() {
+      "BAYESIAN" => Ok(RankingOrder::Bayesian),
+      "RECENCY" => Ok(RankingOrder::Recency),
+      "WILSON" => Ok(RankingOrder::Wilson),
+      "POPULARITY" => Ok(RankingOrder::Popularity),
+      _ => Err(format!("Unknown ranking order: {}", s)),
+    }
+  }
+}
+
+impl TryFrom<String> for PriorGroup {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+impl TryFrom<String> for RankingOrder {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RankingSettings {
+  pub prior: PriorGroup,
+  /// How many ratings the prior is worth, the more ratings an attraction
+  /// has, the less its score is pulled towards the prior.
+  pub prior_weight: f64,
+  /// The age in days at which a rating weights half in the recency score.
+  pub half_life_days: f64,
+  /// The z of the confidence of the Wilson score, 1.96 for 95%.
+  pub confidence_z: f64,
+  pub order: RankingOrder,
+}
+
+impl Default for RankingSettings {
+  fn default() -> Self {
+    RankingSettings {
+      prior: PriorGroup::default(),
+      prior_weight: 10.0,
+      half_life_days: 30.0,
+      confidence_z: 1.96,
+      order: RankingOrder::default(),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct RankedAttraction {
+  pub rank: usize,
+  pub attraction_id: i32,
+  pub description: String,
+  pub city_id: i32,
+  pub attraction_type_id: i32,
+  pub rating_count: i64,
+  /// The raw average, None without ratings.
+  pub average: Option<f64>,
+  pub prior_mean: f64,
+  /// The average pulled towards the mean of its group.
+  pub bayesian_average: f64,
+  /// The Bayesian average where every rating weights less as it gets older.
+  pub recency_score: f64,
+  /// The lower bound of the confidence interval of the average.
+  pub wilson_score: f64,
+  pub last_rated_at: Option<NaiveDateTime>,
+  pub check_in_count: i64,
+  /// The ratings and the check-ins without a rating, weighted by recency.
+  pub popularity_score: f64,
+}
+
+impl RankedAttraction {
+  fn score(&self, order: RankingOrder) -> f64 {
+    match order {
+      RankingOrder::Bayesian => self.bayesian_average,
+      RankingOrder::Recency => self.recency_score,
+      RankingOrder::Wilson => self.wilson_score,
+      RankingOrder::Popularity => self.popularity_score,
+    }
+  }
+}
+
+/// Rank the attractions of a city or a type, or all of them.
+///
+/// # Arguments:
+/// * summaries: the ratings of every attraction, the priors are computed
+///   from all of them.
+/// * city_id, attraction_type_id: the optional filters of the ranking.
+/// * settings: the parameters of the scores and the order.
+///
+/// # Return:
+/// * The attractions that pass the filters, the best first.
+pub fn rank(
+  summaries: &[AttractionRatingSummary],
+  city_id: Option<i32>,
+  attraction_type_id: Option<i32>,
+  settings: &RankingSettings,
+) -> Vec<RankedAttraction> {
+  let global_mean = mean_of(summaries.iter()).unwrap_or(DEFAULT_PRIOR_MEAN);
+  let group_means = match settings.prior {
+    PriorGroup::City => group_means(summaries, |a_summary| a_summary.city_id),
+    PriorGroup::Type => {
+      group_means(summaries, |a_summary| a_summary.attraction_type_id)
+    },
+  };
+
+  let mut ranked = summaries
+    .iter()
+    .filter(|a_summary| city_id.is_none_or(|id| a_summary.city_id == id))
+    .filter(|a_summary| {
+      attraction_type_id.is_none_or(|id| a_summary.attraction_type_id == id)
+    })
+    .map(|a_summary| {
+      let group = match settings.prior {
+        PriorGroup::City => a_summary.city_id,
+        PriorGroup::Type => a_summary.attraction_type_id,
+      };
+      let prior_mean = *group_means.get(&group).unwrap_or(&global_mean);
+      let count = a_summary.rating_count as f64;
+      let sum = a_summary.rate_sum.to_f64().unwrap_or_default();
+      RankedAttraction {
+        rank: 0,
+        attraction_id: a_summary.attraction_id,
+        description: a_summary.description.clone(),
+        city_id: a_summary.city_id,
+        attraction_type_id: a_summary.attraction_type_id,
+        rating_count: a_summary.rating_count,
+        average: (count > 0.0).then(|| sum / count),
+        prior_mean,
+        bayesian_average: bayesian_average(
+          sum,
+          count,
+          prior_mean,
+          settings.prior_weight,
+        ),
+        recency_score: bayesian_average(
+          a_summary.weighted_sum,
+          a_summary.weighted_count,
+          prior_mean,
+          settings.prior_weight,
+        ),
+        wilson_score: wilson_lower_bound(sum, count, settings.confidence_z),
+        last_rated_at: a_summary.last_rated_at,
+        check_in_count: a_summary.check_in_count,
+        popularity_score: a_summary.weighted_count
+          + a_summary.weighted_check_ins,
+      }
+    })
+    .collect::<Vec<RankedAttraction>>();
+
+  ranked.sort_by(|one, other| {
+    other
+      .score(settings.order)
+      .total_cmp(&one.score(settings.order))
+      .then(other.rating_count.cmp(&one.rating_count))
+      .then(one.attraction_id.cmp(&other.attraction_id))
+  });
+  for (index, a_ranked) in ranked.iter_mut().enumerate() {
+    a_ranked.rank = index + 1;
+  }
+  ranked
+}
+
+fn mean_of<'a>(
+  summaries: impl Iterator<Item = &'a AttractionRatingSummary>,
+) -> Option<f64> {
+  let (sum, count) = summaries.fold((0.0, 0.0), |(sum, count), a_summary| {
+    (
+      sum + a_summary.rate_sum.to_f64().unwrap_or_default(),
+      count + a_summary.rating_count as f64,
+    )
+  });
+  (count > 0.0).then(|| sum / count)
+}
+
+fn group_means<K: Eq + Hash + Copy>(
+  summaries: &[AttractionRatingSummary],
+  key_of: impl Fn(&AttractionRatingSummary) -> K,
+) -> HashMap<K, f64> {
+  let mut groups: HashMap<K, Vec<&AttractionRatingSummary>> = HashMap::new();
+  for a_summary in summaries {
+    groups.entry(key_of(a_summary)).or_default().push(a_summary);
+  }
+  groups
+    .into_iter()
+    .filter_map(|(key, members)| Some((key, mean_of(members.into_iter())?)))
+    .collect()
+}
+
+/// The average of the ratings as if the attraction had prior_weight more
+/// ratings with the prior mean.
+pub fn bayesian_average(
+  sum: f64,
+  count: f64,
+  prior_mean: f64,
+  prior_weight: f64,
+) -> f64 {
+  let weight = prior_weight.max(0.0);
+  if weight + count <= 0.0 {
+    return prior_mean;
+  }
+  (weight * prior_mean + sum) / (weight + count)
+}
+
+/// The lower bound of the Wilson score interval, the rates are taken as the
+/// fraction of a positive vote of every rating.
+pub fn wilson_lower_bound(sum: f64, count: f64, z: f64) -> f64 {
+  if count <= 0.0 {
+    return 0.0;
+  }
+  let p = (sum / count).clamp(0.0, 1.0);
+  let z2 = z * z;
+  let center = p + z2 / (2.0 * count);
+  let margin = z * (p * (1.0 - p) / count + z2 / (4.0 * count * count)).sqrt();
+  ((center - margin) / (1.0 + z2 / count)).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use bigdecimal::{BigDecimal, FromPrimitive};
+
+  fn summary(
+    attraction_id: i32,
+    city_id: i32,
+    average: f64,
+    count: i64,
+  ) -> AttractionRatingSummary {
+    AttractionRatingSummary {
+      attraction_id,
+      description: format!("Attraction {attraction_id}"),
+      city_id,
+      attraction_type_id: 1,
+      rating_count: count,
+      rate_sum: BigDecimal::from_f64(average * count as f64).unwrap(),
+      weighted_count: count as f64,
+      weighted_sum: average * count as f64,
+      last_rated_at: None,
+      check_in_count: 0,
+      weighted_check_ins: 0.0,
+    }
+  }
+
+  #[test]
+  fn many_ratings_beat_a_few_higher_ones() {
+    let summaries = [
+      summary(1, 1, 0.95, 3),
+      summary(2, 1, 0.85, 3000),
+      summary(3, 1, 0.5, 300),
+    ];
+    let ranked = rank(&summaries, None, None, &RankingSettings::default());
+    let ids = ranked
+      .iter()
+      .map(|a_ranked| a_ranked.attraction_id)
+      .collect::<Vec<i32>>();
+    assert_eq!(ids, vec![2, 1, 3]);
+    assert_eq!(ranked[0].rank, 1);
+
+    let wilson = rank(
+      &summaries,
+      None,
+      None,
+      &RankingSettings {
+        order: RankingOrder::Wilson,
+        ..Default::default()
+      },
+    );
+    assert_eq!(wilson[0].attraction_id, 2);
+  }
+
+  #[test]
+  fn the_prior_is_the_mean_of_the_group() {
+    let summaries = [
+      summary(1, 1, 0.9, 100),
+      summary(2, 2, 0.3, 100),
+      summary(3, 2, 0.0, 0),
+    ];
+    let ranked = rank(&summaries, Some(2), None, &RankingSettings::default());
+    assert_eq!(ranked.len(), 2);
+    let unrated = ranked
+      .iter()
+      .find(|a_ranked| a_ranked.attraction_id == 3)
+      .unwrap();
+    assert_eq!(unrated.average, None);
+    assert!((unrated.bayesian_average - 0.3).abs() < 1e-9);
+  }
+
+  #[test]
+  fn recency_score_follows_the_recent_ratings() {
+    // 100 old ratings of 0.9, that barely weight now, and 50 recent of 0.3.
+    let mut declining = summary(1, 1, 0.7, 150);
+    declining.weighted_count = 50.0;
+    declining.weighted_sum = 15.0;
+    let steady = summary(2, 1, 0.6, 150);
+    let summaries = [declining, steady];
+
+    let ranked = rank(&summaries, None, None, &RankingSettings::default());
+    assert_eq!(ranked[0].attraction_id, 1);
+    let ranked = rank(
+      &summaries,
+      None,
+      None,
+      &RankingSettings {
+        order: RankingOrder::Recency,
+        ..Default::default()
+      },
+    );
+    assert_eq!(ranked[0].attraction_id, 2);
+  }
+
+  #[test]
+  fn popularity_counts_the_unrated_visits() {
+    let mut visited = summary(1, 1, 0.4, 10);
+    visited.check_in_count = 40;
+    visited.weighted_check_ins = 30.0;
+    let summaries = [visited, summary(2, 1, 0.9, 20)];
+
+    let ranked = rank(&summaries, None, None, &RankingSettings::default());
+    assert_eq!(ranked[0].attraction_id, 2);
+    let ranked = rank(
+      &summaries,
+      None,
+      None,
+      &RankingSettings {
+        order: RankingOrder::Popularity,
+        ..Default::default()
+      },
+    );
+    assert_eq!(ranked[0].attraction_id, 1);
+    assert!((ranked[0].popularity_score - 40.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn wilson_lower_bound_matches_reference_values() {
+    assert_eq!(wilson_lower_bound(0.0, 0.0, 1.96), 0.0);
+    // 8 positive out of 10 at 95%.
+    assert!((wilson_lower_bound(8.0, 10.0, 1.96) - 0.4902).abs() < 1e-4);
+  }
+}
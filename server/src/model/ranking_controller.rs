@@ -0,0 +1,63 @@
+use crate::model::{
+  attraction_repository::AttractionRepository,
+  ranking::{self, RankedAttraction, RankingSettings},
+};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+
+#[async_trait]
+pub trait RankingController: Send + Sync + 'static {
+  async fn ranking(
+    &self,
+    city_id: Option<i32>,
+    attraction_type_id: Option<i32>,
+    window_days: Option<i64>,
+    settings: RankingSettings,
+  ) -> Option<Vec<RankedAttraction>>;
+}
+
+#[derive(Clone)]
+pub struct RankingControllerImpl<AttractionRepo> {
+  attraction_repository: AttractionRepo,
+}
+
+impl<AttractionRepo> RankingControllerImpl<AttractionRepo>
+where
+  AttractionRepo: AttractionRepository,
+{
+  pub fn new(attraction_repository: AttractionRepo) -> Self {
+    RankingControllerImpl {
+      attraction_repository,
+    }
+  }
+}
+
+#[async_trait]
+impl<AttractionRepo> RankingController for RankingControllerImpl<AttractionRepo>
+where
+  AttractionRepo: AttractionRepository + Send + Sync + 'static,
+{
+  /// Only the ratings of the last window days are considered, all of them if
+  /// there is no window.
+  async fn ranking(
+    &self,
+    city_id: Option<i32>,
+    attraction_type_id: Option<i32>,
+    window_days: Option<i64>,
+    settings: RankingSettings,
+  ) -> Option<Vec<RankedAttraction>> {
+    let now = Utc::now().naive_utc();
+    let since = window_days.map(|days| now - Duration::days(days.max(0)));
+    let summaries = self
+      .attraction_repository
+      .rating_summaries(since, now, settings.half_life_days)
+      .await
+      .ok()?;
+    Some(ranking::rank(
+      &summaries,
+      city_id,
+      attraction_type_id,
+      &settings,
+    ))
+  }
+}
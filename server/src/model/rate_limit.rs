@@ -0,0 +1,173 @@
+use std::{collections::HashMap, sync::Mutex, time::Instant};
+
+/// The limit of an api key: its bucket holds up to burst tokens, a request
+/// takes one and rate_per_minute of them are refilled every minute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+  pub rate_per_minute: u32,
+  pub burst: u32,
+}
+
+/// Whether a request is allowed and the state of the bucket after it, sent
+/// in the RateLimit headers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateDecision {
+  pub allowed: bool,
+  pub limit: u32,
+  pub remaining: u32,
+  /// Seconds until the bucket is full again.
+  pub reset_seconds: u64,
+  /// Seconds until the next request is allowed, when this one isn't.
+  pub retry_after_seconds: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+  tokens: f64,
+  updated_at: Instant,
+}
+
+impl TokenBucket {
+  fn full(limit: RateLimit, now: Instant) -> Self {
+    TokenBucket {
+      tokens: limit.burst as f64,
+      updated_at: now,
+    }
+  }
+
+  fn take(&mut self, limit: RateLimit, now: Instant) -> RateDecision {
+    let burst = limit.burst.max(1) as f64;
+    let per_second = limit.rate_per_minute.max(1) as f64 / 60.0;
+    let elapsed = now.saturating_duration_since(self.updated_at);
+    self.tokens = (self.tokens + elapsed.as_secs_f64() * per_second).min(burst);
+    self.updated_at = now;
+
+    let allowed = self.tokens >= 1.0;
+    if allowed {
+      self.tokens -= 1.0;
+    }
+    let seconds_until = |tokens: f64| {
+      ((tokens - self.tokens).max(0.0) / per_second).ceil() as u64
+    };
+    RateDecision {
+      allowed,
+      limit: burst as u32,
+      remaining: self.tokens.floor() as u32,
+      reset_seconds: seconds_until(burst),
+      retry_after_seconds: (!allowed).then(|| seconds_until(1.0)),
+    }
+  }
+}
+
+/// The token buckets of the api keys, kept in memory so they are per
+/// instance of the server and start full after a restart.
+#[derive(Default)]
+pub struct RateLimiter {
+  buckets: Mutex<HashMap<i32, TokenBucket>>,
+}
+
+impl RateLimiter {
+  pub fn new() -> Self {
+    RateLimiter::default()
+  }
+
+  /// Take a token from the bucket of the key, if there is one.
+  pub fn check(
+    &self,
+    key_id: i32,
+    limit: RateLimit,
+    now: Instant,
+  ) -> RateDecision {
+    let mut buckets = self
+      .buckets
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner());
+    buckets
+      .entry(key_id)
+      .or_insert_with(|| TokenBucket::full(limit, now))
+      .take(limit, now)
+  }
+
+  /// Forget the bucket of a revoked key.
+  pub fn forget(&self, key_id: i32) {
+    let mut buckets = self
+      .buckets
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner());
+    buckets.remove(&key_id);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::time::Duration;
+
+  const LIMIT: RateLimit = RateLimit {
+    rate_per_minute: 60,
+    burst: 3,
+  };
+
+  #[test]
+  fn allows_the_burst_and_then_throttles() {
+    let limiter = RateLimiter::new();
+    let start = Instant::now();
+    let decisions = (0..4)
+      .map(|_| limiter.check(1, LIMIT, start))
+      .collect::<Vec<RateDecision>>();
+    assert!(decisions[..3].iter().all(|decision| decision.allowed));
+    assert_eq!(decisions[2].remaining, 0);
+    assert_eq!(decisions[2].reset_seconds, 3);
+    assert!(!decisions[3].allowed);
+    assert_eq!(decisions[3].retry_after_seconds, Some(1));
+  }
+
+  #[test]
+  fn every_key_has_its_own_bucket() {
+    let limiter = RateLimiter::new();
+    let start = Instant::now();
+    (0..3).for_each(|_| {
+      limiter.check(1, LIMIT, start);
+    });
+    assert!(!limiter.check(1, LIMIT, start).allowed);
+    assert!(limiter.check(2, LIMIT, start).allowed);
+  }
+
+  #[test]
+  fn refills_the_tokens_with_the_time() {
+    let limiter = RateLimiter::new();
+    let start = Instant::now();
+    (0..3).for_each(|_| {
+      limiter.check(1, LIMIT, start);
+    });
+    // A token per second is refilled.
+    let later = limiter.check(1, LIMIT, start + Duration::from_millis(1500));
+    assert!(later.allowed);
+    assert_eq!(later.remaining, 0);
+    let too_soon = limiter.check(1, LIMIT, start + Duration::from_millis(1600));
+    assert!(!too_soon.allowed);
+    assert_eq!(too_soon.retry_after_seconds, Some(1));
+  }
+
+  #[test]
+  fn the_bucket_never_holds_more_than_the_burst() {
+    let limiter = RateLimiter::new();
+    let start = Instant::now();
+    limiter.check(1, LIMIT, start);
+    let much_later = limiter.check(1, LIMIT, start + Duration::from_secs(60));
+    assert_eq!(much_later.remaining, 2);
+    assert_eq!(much_later.limit, 3);
+    assert_eq!(much_later.reset_seconds, 1);
+  }
+
+  #[test]
+  fn a_forgotten_key_starts_with_a_full_bucket() {
+    let limiter = RateLimiter::new();
+    let start = Instant::now();
+    (0..3).for_each(|_| {
+      limiter.check(1, LIMIT, start);
+    });
+    limiter.forget(1);
+    assert_eq!(limiter.check(1, LIMIT, start).remaining, 2);
+  }
+}
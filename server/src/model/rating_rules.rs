@@ -0,0 +1,442 @@
+use crate::model::{
+  aggregation_settings::from_env_var, attraction::RecentRating,
+};
+use bigdecimal::BigDecimal;
+use chrono::{Duration, NaiveDateTime};
+use geoutils::Location;
+use serde::Deserialize;
+use std::{fmt, str::FromStr};
+
+/// The state of a rating after its review, only the accepted ratings are
+/// aggregated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum RatingStatus {
+  #[default]
+  Accepted,
+  /// Suspicious, waiting for a manual review.
+  Quarantined,
+  /// Confirmed as fraudulent.
+  Rejected,
+}
+
+impl RatingStatus {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      RatingStatus::Accepted => "ACCEPTED",
+      RatingStatus::Quarantined => "QUARANTINED",
+      RatingStatus::Rejected => "REJECTED",
+    }
+  }
+}
+
+impl fmt::Display for RatingStatus {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+impl FromStr for RatingStatus {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_uppercase().as_str() {
+      "ACCEPTED" => Ok(RatingStatus::Accepted),
+      "QUARANTINED" => Ok(RatingStatus::Quarantined),
+      "REJECTED" => Ok(RatingStatus::Rejected),
+      _ => Err(format!("Unknown rating status: {}", s)),
+    }
+  }
+}
+
+impl TryFrom<String> for RatingStatus {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+/// A rating being submitted, before it is registered.
+#[derive(Debug, Clone)]
+pub struct IncomingRating {
+  pub attraction_id: i32,
+  pub rate: BigDecimal,
+  pub at: NaiveDateTime,
+  pub source: String,
+  pub submitter: Option<String>,
+  pub fingerprint: Option<String>,
+  /// The user that rates, none for the anonymous ratings.
+  pub user_id: Option<i32>,
+}
+
+impl IncomingRating {
+  /// True if both ratings were submitted by the same submitter or from the
+  /// same device.
+  fn same_origin(&self, other: &RecentRating) -> bool {
+    let same = |mine: &Option<String>, theirs: &Option<String>| {
+      mine.is_some() && mine == theirs
+    };
+    same(&self.submitter, &other.submitter)
+      || same(&self.fingerprint, &other.fingerprint)
+  }
+}
+
+/// What is known when a rating is reviewed.
+pub struct RatingContext<'a> {
+  pub rating: &'a IncomingRating,
+  /// The location of the rated attraction.
+  pub location: Option<Location>,
+  /// The ratings registered in the lookback of the rules, of the same
+  /// attraction, submitter or fingerprint.
+  pub recent: &'a [RecentRating],
+}
+
+/// A check over an incoming rating.
+pub trait RatingRule: Send + Sync {
+  fn name(&self) -> &'static str;
+  /// How far before the rating the rule looks at.
+  fn lookback(&self) -> Duration;
+  /// The suspicion score of the rating, 0 if the rule doesn't apply.
+  fn score(&self, context: &RatingContext) -> f64;
+}
+
+/// The same submitter, or device, rates too often.
+pub struct SubmitterRateLimit {
+  pub max_ratings: usize,
+  pub window: Duration,
+}
+
+impl RatingRule for SubmitterRateLimit {
+  fn name(&self) -> &'static str {
+    "SUBMITTER_RATE_LIMIT"
+  }
+
+  fn lookback(&self) -> Duration {
+    self.window
+  }
+
+  fn score(&self, context: &RatingContext) -> f64 {
+    let since = context.rating.at - self.window;
+    let submitted = context
+      .recent
+      .iter()
+      .filter(|a_rating| {
+        a_rating.at > since && a_rating.at <= context.rating.at
+      })
+      .filter(|a_rating| context.rating.same_origin(a_rating))
+      .count();
+    if submitted >= self.max_ratings {
+      1.0
+    } else {
+      0.0
+    }
+  }
+}
+
+/// Many identical rates of the same attraction in the same second.
+pub struct IdenticalBurst {
+  pub max_identical: usize,
+}
+
+impl RatingRule for IdenticalBurst {
+  fn name(&self) -> &'static str {
+    "IDENTICAL_BURST"
+  }
+
+  fn lookback(&self) -> Duration {
+    Duration::seconds(1)
+  }
+
+  fn score(&self, context: &RatingContext) -> f64 {
+    let second = context.rating.at.timestamp();
+    let identical = context
+      .recent
+      .iter()
+      .filter(|a_rating| a_rating.attraction_id == context.rating.attraction_id)
+      .filter(|a_rating| a_rating.rate == context.rating.rate)
+      .filter(|a_rating| a_rating.at.timestamp() == second)
+      .count();
+    if identical >= self.max_identical {
+      1.0
+    } else {
+      0.0
+    }
+  }
+}
+
+/// The submitter rated another attraction so recently that they couldn't have
+/// travelled between both.
+pub struct ImpossibleVelocity {
+  pub max_speed_kmh: f64,
+  pub window: Duration,
+}
+
+impl RatingRule for ImpossibleVelocity {
+  fn name(&self) -> &'static str {
+    "IMPOSSIBLE_VELOCITY"
+  }
+
+  fn lookback(&self) -> Duration {
+    self.window
+  }
+
+  fn score(&self, context: &RatingContext) -> f64 {
+    let Some(location) = context.location.as_ref() else {
+      return 0.0;
+    };
+    let impossible = context
+      .recent
+      .iter()
+      .filter(|a_rating| a_rating.attraction_id != context.rating.attraction_id)
+      .filter(|a_rating| context.rating.same_origin(a_rating))
+      .any(|a_rating| {
+        let Some(other_location) =
+          location_of(&a_rating.latitude, &a_rating.longitude)
+        else {
+          return false;
+        };
+        let Ok(distance) = location.distance_to(&other_location) else {
+          return false;
+        };
+        let kilometers = distance.meters() / 1000.0;
+        let hours =
+          (context.rating.at - a_rating.at).num_seconds().abs() as f64 / 3600.0;
+        kilometers > self.max_speed_kmh * hours
+      });
+    if impossible {
+      1.0
+    } else {
+      0.0
+    }
+  }
+}
+
+/// The result of the review of a rating.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RatingReview {
+  pub status: RatingStatus,
+  pub score: f64,
+  /// The names of the rules that consider the rating suspicious.
+  pub reasons: Vec<String>,
+}
+
+/// How the incoming ratings are reviewed.
+#[derive(Debug, Clone)]
+pub struct RatingRulesSettings {
+  pub max_ratings_per_hour: usize,
+  pub max_identical_per_second: usize,
+  pub max_speed_kmh: f64,
+  /// The score from which a rating is quarantined.
+  pub quarantine_score: f64,
+}
+
+impl Default for RatingRulesSettings {
+  fn default() -> Self {
+    RatingRulesSettings {
+      max_ratings_per_hour: 20,
+      max_identical_per_second: 2,
+      max_speed_kmh: 900.0,
+      quarantine_score: 1.0,
+    }
+  }
+}
+
+impl RatingRulesSettings {
+  /// Read the settings from the environment, falling back into the defaults
+  /// for the missing or invalid values.
+  /// * RATING_MAX_PER_HOUR, RATING_MAX_IDENTICAL_PER_SECOND,
+  ///   RATING_MAX_SPEED_KMH and RATING_QUARANTINE_SCORE: numbers.
+  pub fn from_env() -> Self {
+    let defaults = RatingRulesSettings::default();
+    RatingRulesSettings {
+      max_ratings_per_hour: from_env_var("RATING_MAX_PER_HOUR")
+        .unwrap_or(defaults.max_ratings_per_hour),
+      max_identical_per_second: from_env_var("RATING_MAX_IDENTICAL_PER_SECOND")
+        .unwrap_or(defaults.max_identical_per_second),
+      max_speed_kmh: from_env_var("RATING_MAX_SPEED_KMH")
+        .unwrap_or(defaults.max_speed_kmh),
+      quarantine_score: from_env_var("RATING_QUARANTINE_SCORE")
+        .unwrap_or(defaults.quarantine_score),
+    }
+  }
+}
+
+/// Scores the incoming ratings with a set of rules, the ratings whose total
+/// score reaches the threshold are quarantined.
+pub struct RatingRulesEngine {
+  rules: Vec<Box<dyn RatingRule>>,
+  quarantine_score: f64,
+}
+
+impl RatingRulesEngine {
+  pub fn new(rules: Vec<Box<dyn RatingRule>>, quarantine_score: f64) -> Self {
+    RatingRulesEngine {
+      rules,
+      quarantine_score,
+    }
+  }
+
+  pub fn from_settings(settings: &RatingRulesSettings) -> Self {
+    RatingRulesEngine::new(
+      vec![
+        Box::new(SubmitterRateLimit {
+          max_ratings: settings.max_ratings_per_hour,
+          window: Duration::hours(1),
+        }),
+        Box::new(IdenticalBurst {
+          max_identical: settings.max_identical_per_second,
+        }),
+        Box::new(ImpossibleVelocity {
+          max_speed_kmh: settings.max_speed_kmh,
+          window: Duration::hours(24),
+        }),
+      ],
+      settings.quarantine_score,
+    )
+  }
+
+  /// The period before a rating that the rules need to review it.
+  pub fn lookback(&self) -> Duration {
+    self
+      .rules
+      .iter()
+      .map(|a_rule| a_rule.lookback())
+      .max()
+      .unwrap_or(Duration::zero())
+  }
+
+  pub fn review(&self, context: &RatingContext) -> RatingReview {
+    let mut score = 0.0;
+    let mut reasons = Vec::new();
+    for a_rule in self.rules.iter() {
+      let rule_score = a_rule.score(context);
+      if rule_score > 0.0 {
+        score += rule_score;
+        reasons.push(a_rule.name().to_string());
+      }
+    }
+    let status = if score >= self.quarantine_score {
+      RatingStatus::Quarantined
+    } else {
+      RatingStatus::Accepted
+    };
+    RatingReview {
+      status,
+      score,
+      reasons,
+    }
+  }
+}
+
+/// The location from the coordinates stored as text.
+pub fn location_of(
+  latitude: &Option<String>,
+  longitude: &Option<String>,
+) -> Option<Location> {
+  let latitude = latitude.as_ref()?.trim().parse::<f64>().ok()?;
+  let longitude = longitude.as_ref()?.trim().parse::<f64>().ok()?;
+  Some(Location::new(latitude, longitude))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::NaiveDate;
+
+  fn moment(seconds: i64) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2023, 12, 1)
+      .unwrap()
+      .and_hms_opt(12, 0, 0)
+      .unwrap()
+      + Duration::seconds(seconds)
+  }
+
+  fn incoming(attraction_id: i32, rate: &str, seconds: i64) -> IncomingRating {
+    IncomingRating {
+      attraction_id,
+      rate: rate.parse().unwrap(),
+      at: moment(seconds),
+      source: String::from("WEB"),
+      submitter: Some(String::from("someone")),
+      fingerprint: None,
+      user_id: None,
+    }
+  }
+
+  fn recent_rating(
+    attraction_id: i32,
+    rate: &str,
+    seconds: i64,
+    submitter: &str,
+    location: (&str, &str),
+  ) -> RecentRating {
+    RecentRating {
+      id: 0,
+      attraction_id,
+      rate: rate.parse().unwrap(),
+      at: moment(seconds),
+      submitter: Some(submitter.to_string()),
+      fingerprint: None,
+      latitude: Some(location.0.to_string()),
+      longitude: Some(location.1.to_string()),
+    }
+  }
+
+  const PARIS: (&str, &str) = ("48.8606", "2.3376");
+  const BUENOS_AIRES: (&str, &str) = ("-34.6037", "-58.3816");
+
+  fn review(rating: &IncomingRating, recent: &[RecentRating]) -> RatingReview {
+    let engine =
+      RatingRulesEngine::from_settings(&RatingRulesSettings::default());
+    engine.review(&RatingContext {
+      rating,
+      location: location_of(
+        &Some(PARIS.0.to_string()),
+        &Some(PARIS.1.to_string()),
+      ),
+      recent,
+    })
+  }
+
+  #[test]
+  fn accepts_a_regular_rating() {
+    let recent = [recent_rating(1, "0.8", -7200, "someone", PARIS)];
+    let a_review = review(&incoming(1, "0.8", 0), &recent);
+    assert_eq!(a_review.status, RatingStatus::Accepted);
+    assert!(a_review.reasons.is_empty());
+  }
+
+  #[test]
+  fn quarantines_a_submitter_over_the_rate_limit() {
+    let recent = (0..20)
+      .map(|i| recent_rating(1, "0.5", -60 * i, "someone", PARIS))
+      .collect::<Vec<RecentRating>>();
+    let a_review = review(&incoming(1, "0.9", 0), &recent);
+    assert_eq!(a_review.status, RatingStatus::Quarantined);
+    assert_eq!(a_review.reasons, vec!["SUBMITTER_RATE_LIMIT"]);
+  }
+
+  #[test]
+  fn quarantines_a_burst_of_identical_rates() {
+    let recent = [
+      recent_rating(1, "0.10", 0, "a", PARIS),
+      recent_rating(1, "0.1", 0, "b", PARIS),
+      recent_rating(1, "0.9", 0, "c", PARIS),
+    ];
+    let a_review = review(&incoming(1, "0.1", 0), &recent);
+    assert_eq!(a_review.reasons, vec!["IDENTICAL_BURST"]);
+    assert_eq!(review(&incoming(1, "0.9", 0), &recent).reasons.len(), 0);
+  }
+
+  #[test]
+  fn quarantines_an_impossible_trip() {
+    let recent = [recent_rating(2, "0.7", -3600, "someone", BUENOS_AIRES)];
+    let a_review = review(&incoming(1, "0.7", 0), &recent);
+    assert_eq!(a_review.reasons, vec!["IMPOSSIBLE_VELOCITY"]);
+
+    let recent = [recent_rating(2, "0.7", -20 * 3600, "someone", BUENOS_AIRES)];
+    let a_review = review(&incoming(1, "0.7", 0), &recent);
+    assert_eq!(a_review.status, RatingStatus::Accepted);
+  }
+}
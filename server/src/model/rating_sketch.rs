@@ -0,0 +1,202 @@
+use super::{
+  rating_statistics::{
+    bucket_of, decimal_of, RatingStatistics, HISTOGRAM_BUCKETS,
+  },
+  tdigest::{ByteReader, TDigest},
+};
+
+/// The version of the binary representation of the sketch.
+const ENCODING_VERSION: u8 = 1;
+
+/// A mergeable summary of a set of rates. Besides the t-digest used to
+/// estimate the quantiles, it keeps the moments and the histogram of the
+/// rates, so every statistic of an aggregate can be derived by merging the
+/// sketches of the days it covers.
+#[derive(Debug, Clone)]
+pub struct RatingSketch {
+  digest: TDigest,
+  count: u64,
+  sum: f64,
+  sum_of_squares: f64,
+  histogram: Vec<i32>,
+}
+
+impl Default for RatingSketch {
+  fn default() -> Self {
+    RatingSketch {
+      digest: TDigest::default(),
+      count: 0,
+      sum: 0.0,
+      sum_of_squares: 0.0,
+      histogram: vec![0; HISTOGRAM_BUCKETS],
+    }
+  }
+}
+
+impl RatingSketch {
+  pub fn count(&self) -> u64 {
+    self.count
+  }
+
+  pub fn add(&mut self, rate: f64) {
+    self.digest.add(rate);
+    self.count += 1;
+    self.sum += rate;
+    self.sum_of_squares += rate * rate;
+    self.histogram[bucket_of(rate)] += 1;
+  }
+
+  pub fn merge(&mut self, other: &RatingSketch) {
+    self.digest.merge(&other.digest);
+    self.count += other.count;
+    self.sum += other.sum;
+    self.sum_of_squares += other.sum_of_squares;
+    for (bucket, other_bucket) in
+      self.histogram.iter_mut().zip(other.histogram.iter())
+    {
+      *bucket += other_bucket;
+    }
+  }
+
+  /// The statistics of the summarized rates, the median and the percentiles
+  /// are estimated by the t-digest.
+  ///
+  /// # Return:
+  /// * Some with the statistics.
+  /// * None if the sketch is empty.
+  pub fn statistics(&self) -> Option<RatingStatistics> {
+    if self.count == 0 {
+      return None;
+    }
+    let count = self.count as f64;
+    let average = self.sum / count;
+    let variance = (self.sum_of_squares / count - average * average).max(0.0);
+
+    Some(RatingStatistics {
+      count: self.count as i32,
+      min_rate: decimal_of(self.digest.min()?)?,
+      max_rate: decimal_of(self.digest.max()?)?,
+      average: decimal_of(average)?,
+      median: decimal_of(self.digest.quantile(0.5)?)?,
+      percentile_95: decimal_of(self.digest.quantile(0.95)?)?,
+      percentile_99: decimal_of(self.digest.quantile(0.99)?)?,
+      standard_deviation: decimal_of(variance.sqrt())?,
+      histogram: self.histogram.clone(),
+    })
+  }
+
+  /// The compact binary representation of the sketch.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = vec![ENCODING_VERSION];
+    bytes.extend_from_slice(&self.count.to_le_bytes());
+    bytes.extend_from_slice(&self.sum.to_le_bytes());
+    bytes.extend_from_slice(&self.sum_of_squares.to_le_bytes());
+    bytes.push(self.histogram.len() as u8);
+    for a_bucket in self.histogram.iter() {
+      bytes.extend_from_slice(&(*a_bucket as u32).to_le_bytes());
+    }
+    bytes.extend_from_slice(&self.digest.to_bytes());
+    bytes
+  }
+
+  /// Read a sketch from its binary representation.
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+    let mut reader = ByteReader::new(bytes);
+    let version = reader.u8()?;
+    if version != ENCODING_VERSION {
+      return Err(format!("Unknown sketch encoding version: {}", version));
+    }
+    let count = reader.u64()?;
+    let sum = reader.f64()?;
+    let sum_of_squares = reader.f64()?;
+    let buckets = reader.u8()? as usize;
+    if buckets != HISTOGRAM_BUCKETS {
+      return Err(format!("Unexpected histogram of {} buckets", buckets));
+    }
+    let mut histogram = Vec::with_capacity(buckets);
+    for _ in 0..buckets {
+      histogram.push(reader.u32()? as i32);
+    }
+    let (digest, _) = TDigest::from_bytes(reader.remaining())?;
+    if digest.count() as u64 != count {
+      return Err(String::from("The digest doesn't match the sketch count"));
+    }
+
+    Ok(RatingSketch {
+      digest,
+      count,
+      sum,
+      sum_of_squares,
+      histogram,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use bigdecimal::BigDecimal;
+  use std::str::FromStr;
+
+  fn sketch_of(rates: &[f64]) -> RatingSketch {
+    let mut sketch = RatingSketch::default();
+    rates.iter().for_each(|rate| sketch.add(*rate));
+    sketch
+  }
+
+  fn decimal(value: &str) -> BigDecimal {
+    BigDecimal::from_str(value).unwrap()
+  }
+
+  #[test]
+  fn empty_sketch_has_no_statistics() {
+    assert!(RatingSketch::default().statistics().is_none());
+  }
+
+  #[test]
+  fn summarizes_the_rates() {
+    let statistics = sketch_of(&[0.2, 0.4, 0.4, 0.8]).statistics().unwrap();
+    assert_eq!(4, statistics.count);
+    assert_eq!(decimal("0.2"), statistics.min_rate);
+    assert_eq!(decimal("0.8"), statistics.max_rate);
+    assert_eq!(decimal("0.45"), statistics.average);
+    assert_eq!(decimal("0.2179449472"), statistics.standard_deviation);
+    assert_eq!(vec![0, 0, 1, 0, 2, 0, 0, 0, 1, 0], statistics.histogram);
+    assert_eq!(decimal("0.4"), statistics.median);
+  }
+
+  #[test]
+  fn merged_sketches_summarize_like_a_single_one() {
+    let mut merged = sketch_of(&[0.2, 0.4]);
+    merged.merge(&sketch_of(&[0.4, 0.8]));
+    let merged = merged.statistics().unwrap();
+    let single = sketch_of(&[0.2, 0.4, 0.4, 0.8]).statistics().unwrap();
+    assert_eq!(single.count, merged.count);
+    assert_eq!(single.min_rate, merged.min_rate);
+    assert_eq!(single.max_rate, merged.max_rate);
+    assert_eq!(single.average, merged.average);
+    assert_eq!(single.standard_deviation, merged.standard_deviation);
+    assert_eq!(single.histogram, merged.histogram);
+    assert_eq!(single.median, merged.median);
+  }
+
+  #[test]
+  fn round_trips_through_its_binary_representation() {
+    let sketch = sketch_of(&[0.1, 0.5, 0.9, 1.0]);
+    let decoded = RatingSketch::from_bytes(&sketch.to_bytes()).unwrap();
+    assert_eq!(sketch.count(), decoded.count());
+    let (original, decoded) =
+      (sketch.statistics().unwrap(), decoded.statistics().unwrap());
+    assert_eq!(original.average, decoded.average);
+    assert_eq!(original.histogram, decoded.histogram);
+    assert_eq!(original.percentile_95, decoded.percentile_95);
+  }
+
+  #[test]
+  fn rejects_an_unknown_or_truncated_encoding() {
+    let mut bytes = sketch_of(&[0.5]).to_bytes();
+    assert!(RatingSketch::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    bytes[0] = ENCODING_VERSION + 1;
+    assert!(RatingSketch::from_bytes(&bytes).is_err());
+  }
+}
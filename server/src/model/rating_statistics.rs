@@ -0,0 +1,221 @@
+use super::percentile::{percentile, PercentileMethod};
+use bigdecimal::{BigDecimal, FromPrimitive, ToPrimitive, Zero};
+use std::ops::{AddAssign, Div, Mul, Sub};
+
+/// The number of fixed buckets used to build the histogram of rates.
+pub const HISTOGRAM_BUCKETS: usize = 10;
+/// The lowest rate that an attraction could receive.
+pub const MIN_RATE: f64 = 0.0;
+/// The highest rate that an attraction could receive.
+pub const MAX_RATE: f64 = 1.0;
+/// The decimal digits kept in the estimated statistics.
+const STATISTICS_SCALE: i64 = 10;
+
+/// The descriptive statistics of the ratings of an attraction in a period.
+#[derive(Debug, Clone)]
+pub struct RatingStatistics {
+  pub count: i32,
+  pub min_rate: BigDecimal,
+  pub max_rate: BigDecimal,
+  pub average: BigDecimal,
+  pub median: BigDecimal,
+  pub percentile_95: BigDecimal,
+  pub percentile_99: BigDecimal,
+  pub standard_deviation: BigDecimal,
+  pub histogram: Vec<i32>,
+}
+
+impl RatingStatistics {
+  /// Calculate the statistics from a set of rates.
+  ///
+  /// # Arguments:
+  /// * sorted_rates: the rates sorted in ascending order.
+  /// * method: the method used to estimate the median and the percentiles.
+  ///
+  /// # Return:
+  /// * Some with the statistics.
+  /// * None if there are no rates.
+  pub fn from_sorted(
+    sorted_rates: &[BigDecimal],
+    method: PercentileMethod,
+  ) -> Option<Self> {
+    let min_rate = sorted_rates.first()?.clone();
+    let max_rate = sorted_rates.last()?.clone();
+    let total = sorted_rates.len();
+
+    let sample = sorted_rates
+      .iter()
+      .map(|a_rate| a_rate.to_f64().unwrap_or(MIN_RATE))
+      .collect::<Vec<f64>>();
+    let percentile_of = |p: f64| -> Option<BigDecimal> {
+      decimal_of(percentile(&sample, p, method)?)
+    };
+    let median = percentile_of(0.5)?;
+    let percentile_95 = percentile_of(0.95)?;
+    let percentile_99 = percentile_of(0.99)?;
+
+    let mut average = BigDecimal::zero();
+    for a_rate in sorted_rates {
+      average.add_assign(a_rate);
+    }
+    average = average.div(BigDecimal::from(total as i32));
+
+    let mut variance = BigDecimal::zero();
+    for a_rate in sorted_rates {
+      let deviation = a_rate.sub(&average);
+      variance.add_assign(deviation.clone().mul(deviation));
+    }
+    variance = variance.div(BigDecimal::from(total as i32));
+    let standard_deviation = variance
+      .sqrt()
+      .unwrap_or_default()
+      .round(STATISTICS_SCALE)
+      .normalized();
+
+    Some(RatingStatistics {
+      count: total as i32,
+      min_rate,
+      max_rate,
+      average,
+      median,
+      percentile_95,
+      percentile_99,
+      standard_deviation,
+      histogram: histogram_of(&sample),
+    })
+  }
+}
+
+/// The decimal representation of an estimated statistic.
+pub fn decimal_of(value: f64) -> Option<BigDecimal> {
+  BigDecimal::from_f64(value)
+    .map(|value| value.round(STATISTICS_SCALE).normalized())
+}
+
+/// The bucket of the histogram where a rate falls. Rates outside the
+/// [MIN_RATE, MAX_RATE] range are counted in the first or last bucket.
+pub fn bucket_of(rate: f64) -> usize {
+  // Scaled instead of divided by the width of a bucket, 0.6 / 0.1 falls
+  // just below 6 in floating point.
+  let bucket = ((rate - MIN_RATE) * HISTOGRAM_BUCKETS as f64
+    / (MAX_RATE - MIN_RATE))
+    .floor();
+  bucket.clamp(0.0, (HISTOGRAM_BUCKETS - 1) as f64) as usize
+}
+
+fn histogram_of(rates: &[f64]) -> Vec<i32> {
+  let mut histogram = vec![0; HISTOGRAM_BUCKETS];
+  for a_rate in rates {
+    histogram[bucket_of(*a_rate)] += 1;
+  }
+  histogram
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::str::FromStr;
+
+  fn rates(values: &[&str]) -> Vec<BigDecimal> {
+    values
+      .iter()
+      .map(|a_value| BigDecimal::from_str(a_value).unwrap())
+      .collect()
+  }
+
+  fn assert_close(actual: &BigDecimal, expected: f64) {
+    let actual = actual.to_f64().unwrap();
+    assert!(
+      (actual - expected).abs() < 1e-9,
+      "expected {expected} but was {actual}"
+    );
+  }
+
+  #[test]
+  fn describes_the_rates() {
+    let sample = rates(&["0.1", "0.2", "0.4", "0.6", "0.8"]);
+    let statistics =
+      RatingStatistics::from_sorted(&sample, PercentileMethod::default())
+        .unwrap();
+    assert_eq!(statistics.count, 5);
+    assert_close(&statistics.min_rate, 0.1);
+    assert_close(&statistics.max_rate, 0.8);
+    assert_close(&statistics.average, 0.42);
+    // The population deviation, sqrt(0.328 / 5).
+    assert_close(&statistics.standard_deviation, 0.0656_f64.sqrt());
+    assert_eq!(statistics.histogram, vec![0, 1, 1, 0, 1, 0, 1, 0, 1, 0]);
+  }
+
+  #[test]
+  fn the_percentiles_follow_the_method() {
+    let sample = rates(&["0.1", "0.2", "0.4", "0.6", "0.8"]);
+    // (method, median, percentile 95, percentile 99), the values of R
+    // quantile(type = 1, 4, 5, 6 and 7).
+    let expected = [
+      (PercentileMethod::NearestRank, 0.4, 0.8, 0.8),
+      (PercentileMethod::LinearInterpolation, 0.3, 0.75, 0.79),
+      (PercentileMethod::Hazen, 0.4, 0.8, 0.8),
+      (PercentileMethod::Weibull, 0.4, 0.8, 0.8),
+      (PercentileMethod::HyndmanFan7, 0.4, 0.76, 0.792),
+    ];
+    for (method, median, percentile_95, percentile_99) in expected {
+      let statistics = RatingStatistics::from_sorted(&sample, method).unwrap();
+      assert_close(&statistics.median, median);
+      assert_close(&statistics.percentile_95, percentile_95);
+      assert_close(&statistics.percentile_99, percentile_99);
+    }
+  }
+
+  #[test]
+  fn there_are_no_statistics_without_rates() {
+    assert!(
+      RatingStatistics::from_sorted(&[], PercentileMethod::default()).is_none()
+    );
+  }
+
+  #[test]
+  fn a_single_rate_is_every_statistic() {
+    let statistics = RatingStatistics::from_sorted(
+      &rates(&["0.7"]),
+      PercentileMethod::LinearInterpolation,
+    )
+    .unwrap();
+    assert_eq!(statistics.count, 1);
+    for a_statistic in [
+      &statistics.min_rate,
+      &statistics.max_rate,
+      &statistics.average,
+      &statistics.median,
+      &statistics.percentile_95,
+      &statistics.percentile_99,
+    ] {
+      assert_close(a_statistic, 0.7);
+    }
+    assert_close(&statistics.standard_deviation, 0.0);
+    assert_eq!(statistics.histogram[7], 1);
+  }
+
+  #[test]
+  fn equal_rates_have_no_deviation() {
+    let statistics = RatingStatistics::from_sorted(
+      &rates(&["0.5", "0.5", "0.5", "0.5"]),
+      PercentileMethod::Weibull,
+    )
+    .unwrap();
+    assert_close(&statistics.median, 0.5);
+    assert_close(&statistics.percentile_99, 0.5);
+    assert_close(&statistics.standard_deviation, 0.0);
+    assert_eq!(statistics.histogram, vec![0, 0, 0, 0, 0, 4, 0, 0, 0, 0]);
+  }
+
+  #[test]
+  fn the_buckets_include_their_lower_bound() {
+    assert_eq!(bucket_of(0.0), 0);
+    assert_eq!(bucket_of(0.6), 6);
+    assert_eq!(bucket_of(0.7), 7);
+    assert_eq!(bucket_of(0.6999), 6);
+    assert_eq!(bucket_of(1.0), HISTOGRAM_BUCKETS - 1);
+    assert_eq!(bucket_of(-0.5), 0);
+    assert_eq!(bucket_of(1.5), HISTOGRAM_BUCKETS - 1);
+  }
+}
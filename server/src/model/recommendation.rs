@@ -0,0 +1,503 @@
+use crate::model::{
+  aggregation_settings::from_env_var,
+  rating_statistics::{MAX_RATE, MIN_RATE},
+};
+use sqlx::FromRow;
+use std::collections::HashMap;
+
+/// The rate a user is assumed to give before rating anything, the middle of
+/// the rating scale.
+const NEUTRAL_RATE: f64 = (MIN_RATE + MAX_RATE) / 2.0;
+/// The rated attractions given as the reason of a recommendation.
+const MAX_REASONS: usize = 3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RecommendationSettings {
+  /// The most similar attractions kept for every attraction.
+  pub neighbours: usize,
+  /// The users that must have rated both attractions to compare them.
+  pub min_co_ratings: usize,
+  /// How many co-ratings a similarity needs to weight half, the fewer users
+  /// rated both attractions, the more the similarity is shrunk towards 0.
+  pub shrinkage: f64,
+  /// How many ratings the neutral rate is worth in the mean of a user, so a
+  /// single rating can still tell what the user likes.
+  pub baseline_weight: f64,
+  /// The collaborative weight at which the collaborative and the content
+  /// scores are blended in halves. Below it the content similarity prevails,
+  /// which covers the attractions that few users rated.
+  pub blend_weight: f64,
+  /// The rate assumed for the attractions that a user visited without
+  /// rating them, a visit tells the user was interested in it.
+  pub check_in_rate: f64,
+}
+
+impl Default for RecommendationSettings {
+  fn default() -> Self {
+    RecommendationSettings {
+      neighbours: 20,
+      min_co_ratings: 2,
+      shrinkage: 10.0,
+      baseline_weight: 2.0,
+      blend_weight: 1.0,
+      check_in_rate: 0.7,
+    }
+  }
+}
+
+impl RecommendationSettings {
+  /// Read the settings from the environment, falling back into the defaults
+  /// for the missing or invalid values.
+  /// * RECOMMENDATION_NEIGHBOURS, RECOMMENDATION_MIN_CO_RATINGS,
+  ///   RECOMMENDATION_SHRINKAGE, RECOMMENDATION_BASELINE_WEIGHT,
+  ///   RECOMMENDATION_BLEND_WEIGHT and RECOMMENDATION_CHECK_IN_RATE: numbers.
+  ///   The check-in rate is clamped to the rating scale.
+  pub fn from_env() -> Self {
+    let defaults = RecommendationSettings::default();
+    RecommendationSettings {
+      neighbours: from_env_var("RECOMMENDATION_NEIGHBOURS")
+        .unwrap_or(defaults.neighbours),
+      min_co_ratings: from_env_var("RECOMMENDATION_MIN_CO_RATINGS")
+        .unwrap_or(defaults.min_co_ratings),
+      shrinkage: from_env_var("RECOMMENDATION_SHRINKAGE")
+        .unwrap_or(defaults.shrinkage),
+      baseline_weight: from_env_var("RECOMMENDATION_BASELINE_WEIGHT")
+        .unwrap_or(defaults.baseline_weight),
+      blend_weight: from_env_var("RECOMMENDATION_BLEND_WEIGHT")
+        .unwrap_or(defaults.blend_weight),
+      check_in_rate: from_env_var::<f64>("RECOMMENDATION_CHECK_IN_RATE")
+        .unwrap_or(defaults.check_in_rate)
+        .clamp(MIN_RATE, MAX_RATE),
+    }
+  }
+}
+
+/// The average rate a user gave to an attraction.
+#[derive(FromRow, Debug, Clone, Copy, PartialEq)]
+pub struct UserRate {
+  pub user_id: i32,
+  pub attraction_id: i32,
+  pub rate: f64,
+}
+
+/// How similar an attraction is to another one. For the collaborative model
+/// the count is the number of users that rated both, for the content
+/// similarity it is 0.
+#[derive(FromRow, Debug, Clone, Copy, PartialEq)]
+pub struct Neighbour {
+  pub attraction_id: i32,
+  pub to_attraction_id: i32,
+  pub similarity: f64,
+  pub co_rating_count: i32,
+}
+
+/// An attraction recommended to a user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recommendation {
+  pub attraction_id: i32,
+  /// How much more than usual the user is expected to like the attraction.
+  pub score: f64,
+  pub predicted_rate: f64,
+  /// The score from the ratings of the users that rated the same
+  /// attractions, None when none of them rated this one.
+  pub collaborative_score: Option<f64>,
+  /// The score from the similarity with the rated attractions.
+  pub content_score: Option<f64>,
+  /// The rated attractions that contributed the most, the first the most.
+  pub because_of: Vec<i32>,
+}
+
+/// The usual rate of a user, the mean of the rates pulled towards the
+/// neutral rate.
+fn baseline(rates: &[f64], settings: &RecommendationSettings) -> f64 {
+  let weight = settings.baseline_weight.max(0.0);
+  (rates.iter().sum::<f64>() + weight * NEUTRAL_RATE)
+    / (rates.len() as f64 + weight)
+}
+
+#[derive(Default)]
+struct CoRatings {
+  product: f64,
+  one_squares: f64,
+  other_squares: f64,
+  count: usize,
+}
+
+/// Train the item-item model: the adjusted cosine similarity between the
+/// attractions rated by the same users, using how much each user liked them
+/// over their baseline. The negative similarities are kept too, they tell
+/// that the users who liked one disliked the other. Every attraction keeps
+/// the neighbours with the strongest similarities.
+pub fn train(
+  rates: &[UserRate],
+  settings: &RecommendationSettings,
+) -> Vec<Neighbour> {
+  let mut by_user: HashMap<i32, Vec<&UserRate>> = HashMap::new();
+  for a_rate in rates {
+    by_user.entry(a_rate.user_id).or_default().push(a_rate);
+  }
+
+  let mut pairs: HashMap<(i32, i32), CoRatings> = HashMap::new();
+  for user_rates in by_user.values() {
+    let rates_of_user = user_rates
+      .iter()
+      .map(|a_rate| a_rate.rate)
+      .collect::<Vec<f64>>();
+    let user_baseline = baseline(&rates_of_user, settings);
+    let mut deviations = user_rates
+      .iter()
+      .map(|a_rate| (a_rate.attraction_id, a_rate.rate - user_baseline))
+      .collect::<Vec<(i32, f64)>>();
+    deviations.sort_by_key(|(attraction_id, _)| *attraction_id);
+    for (index, (one, one_deviation)) in deviations.iter().enumerate() {
+      for (other, other_deviation) in deviations[index + 1..].iter() {
+        let co_ratings = pairs.entry((*one, *other)).or_default();
+        co_ratings.product += one_deviation * other_deviation;
+        co_ratings.one_squares += one_deviation * one_deviation;
+        co_ratings.other_squares += other_deviation * other_deviation;
+        co_ratings.count += 1;
+      }
+    }
+  }
+
+  let mut by_attraction: HashMap<i32, Vec<Neighbour>> = HashMap::new();
+  for ((one, other), co_ratings) in pairs {
+    let norm = (co_ratings.one_squares * co_ratings.other_squares).sqrt();
+    if co_ratings.count < settings.min_co_ratings.max(1) || norm <= 0.0 {
+      continue;
+    }
+    let count = co_ratings.count as f64;
+    let similarity =
+      co_ratings.product / norm * count / (count + settings.shrinkage.max(0.0));
+    if similarity == 0.0 {
+      continue;
+    }
+    for (from, to) in [(one, other), (other, one)] {
+      by_attraction.entry(from).or_default().push(Neighbour {
+        attraction_id: from,
+        to_attraction_id: to,
+        similarity,
+        co_rating_count: co_ratings.count as i32,
+      });
+    }
+  }
+
+  let mut model = by_attraction
+    .into_values()
+    .flat_map(|mut neighbours| {
+      neighbours.sort_by(|one, other| {
+        other
+          .similarity
+          .abs()
+          .total_cmp(&one.similarity.abs())
+          .then(one.to_attraction_id.cmp(&other.to_attraction_id))
+      });
+      neighbours.truncate(settings.neighbours);
+      neighbours
+    })
+    .collect::<Vec<Neighbour>>();
+  model.sort_by(|one, other| {
+    one
+      .attraction_id
+      .cmp(&other.attraction_id)
+      .then(other.similarity.total_cmp(&one.similarity))
+  });
+  model
+}
+
+#[derive(Default)]
+struct Evidence {
+  weighted: f64,
+  weight: f64,
+}
+
+impl Evidence {
+  fn add(&mut self, similarity: f64, deviation: f64) {
+    self.weighted += similarity * deviation;
+    self.weight += similarity.abs();
+  }
+
+  fn score(&self) -> Option<f64> {
+    (self.weight > 0.0).then(|| self.weighted / self.weight)
+  }
+}
+
+/// Recommend to a user the candidates that they didn't rate and are expected
+/// to like more than usual, the best first.
+///
+/// The collaborative score of a candidate is the average of how much the user
+/// liked the rated attractions, weighted by their similarity in the model.
+/// The content score does the same with the content similarity. Both are
+/// blended by the collaborative weight, so the candidates that few users
+/// rated (the cold start) rely on the content similarity.
+///
+/// # Arguments:
+/// * user_rates: the rates of the user.
+/// * neighbours: the neighbours in the model of the rated attractions.
+/// * content: the content similarity of the rated attractions to the rest.
+/// * candidates: the attractions that can be recommended.
+pub fn recommend(
+  user_rates: &[UserRate],
+  neighbours: &[Neighbour],
+  content: &[Neighbour],
+  candidates: &[i32],
+  settings: &RecommendationSettings,
+) -> Vec<Recommendation> {
+  let rates_of_user = user_rates
+    .iter()
+    .map(|a_rate| a_rate.rate)
+    .collect::<Vec<f64>>();
+  let user_baseline = baseline(&rates_of_user, settings);
+  let deviations = user_rates
+    .iter()
+    .map(|a_rate| (a_rate.attraction_id, a_rate.rate - user_baseline))
+    .collect::<HashMap<i32, f64>>();
+
+  let mut collaborative: HashMap<i32, Evidence> = HashMap::new();
+  let mut content_based: HashMap<i32, Evidence> = HashMap::new();
+  let mut contributions: HashMap<i32, Vec<(i32, f64)>> = HashMap::new();
+  for (similarities, evidence) in [
+    (neighbours, &mut collaborative),
+    (content, &mut content_based),
+  ] {
+    for a_neighbour in similarities.iter().filter(|a_neighbour| {
+      a_neighbour.similarity != 0.0
+        && !deviations.contains_key(&a_neighbour.to_attraction_id)
+    }) {
+      let Some(deviation) = deviations.get(&a_neighbour.attraction_id) else {
+        continue;
+      };
+      evidence
+        .entry(a_neighbour.to_attraction_id)
+        .or_default()
+        .add(a_neighbour.similarity, *deviation);
+      contributions
+        .entry(a_neighbour.to_attraction_id)
+        .or_default()
+        .push((
+          a_neighbour.attraction_id,
+          a_neighbour.similarity * deviation,
+        ));
+    }
+  }
+
+  let mut recommendations = candidates
+    .iter()
+    .filter(|candidate| !deviations.contains_key(candidate))
+    .filter_map(|candidate| {
+      let collaborative = collaborative.get(candidate);
+      let collaborative_score = collaborative.and_then(Evidence::score);
+      let content_score =
+        content_based.get(candidate).and_then(Evidence::score);
+      let collaborative_weight =
+        collaborative.map_or(0.0, |evidence| evidence.weight);
+      let confidence = collaborative_weight
+        / (collaborative_weight + settings.blend_weight.max(f64::EPSILON));
+      let score = match (collaborative_score, content_score) {
+        (Some(collaborative), Some(content)) => {
+          confidence * collaborative + (1.0 - confidence) * content
+        },
+        (Some(collaborative), None) => collaborative,
+        (None, Some(content)) => content,
+        (None, None) => return None,
+      };
+      if score <= 0.0 {
+        return None;
+      }
+      let mut reasons =
+        contributions.get(candidate).cloned().unwrap_or_default();
+      reasons.sort_by(|one, other| other.1.total_cmp(&one.1));
+      let mut because_of = Vec::new();
+      for (attraction_id, contribution) in reasons {
+        if contribution > 0.0 && !because_of.contains(&attraction_id) {
+          because_of.push(attraction_id);
+        }
+      }
+      because_of.truncate(MAX_REASONS);
+      Some(Recommendation {
+        attraction_id: *candidate,
+        score,
+        predicted_rate: (user_baseline + score).clamp(MIN_RATE, MAX_RATE),
+        collaborative_score,
+        content_score,
+        because_of,
+      })
+    })
+    .collect::<Vec<Recommendation>>();
+  recommendations.sort_by(|one, other| {
+    other
+      .score
+      .total_cmp(&one.score)
+      .then(one.attraction_id.cmp(&other.attraction_id))
+  });
+  recommendations
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn rate(user_id: i32, attraction_id: i32, rate: f64) -> UserRate {
+    UserRate {
+      user_id,
+      attraction_id,
+      rate,
+    }
+  }
+
+  fn unshrunk() -> RecommendationSettings {
+    RecommendationSettings {
+      shrinkage: 0.0,
+      ..Default::default()
+    }
+  }
+
+  /// The users that like 1 like 2 and dislike 3.
+  fn rates() -> Vec<UserRate> {
+    vec![
+      rate(1, 1, 0.9),
+      rate(1, 2, 0.9),
+      rate(1, 3, 0.1),
+      rate(2, 1, 1.0),
+      rate(2, 2, 0.8),
+      rate(2, 3, 0.2),
+      rate(3, 1, 0.2),
+      rate(3, 2, 0.1),
+      rate(3, 3, 0.9),
+    ]
+  }
+
+  fn similarity_of(model: &[Neighbour], one: i32, other: i32) -> Option<f64> {
+    model
+      .iter()
+      .find(|a_neighbour| {
+        a_neighbour.attraction_id == one
+          && a_neighbour.to_attraction_id == other
+      })
+      .map(|a_neighbour| a_neighbour.similarity)
+  }
+
+  fn content(attraction_id: i32, to_attraction_id: i32) -> Neighbour {
+    Neighbour {
+      attraction_id,
+      to_attraction_id,
+      similarity: 0.5,
+      co_rating_count: 0,
+    }
+  }
+
+  fn ids_of(recommendations: &[Recommendation]) -> Vec<i32> {
+    recommendations
+      .iter()
+      .map(|a_recommendation| a_recommendation.attraction_id)
+      .collect()
+  }
+
+  #[test]
+  fn the_attractions_liked_by_the_same_users_are_similar() {
+    let model = train(&rates(), &unshrunk());
+    assert!(
+      similarity_of(&model, 1, 2).is_some_and(|similarity| similarity > 0.9)
+    );
+    assert_eq!(similarity_of(&model, 1, 2), similarity_of(&model, 2, 1));
+    assert!(model
+      .iter()
+      .all(|a_neighbour| a_neighbour.co_rating_count == 3));
+  }
+
+  #[test]
+  fn the_attractions_liked_by_opposite_users_are_dissimilar() {
+    let model = train(&rates(), &unshrunk());
+    assert!(
+      similarity_of(&model, 1, 3).is_some_and(|similarity| similarity < 0.0)
+    );
+    assert!(
+      similarity_of(&model, 2, 3).is_some_and(|similarity| similarity < 0.0)
+    );
+  }
+
+  #[test]
+  fn the_attractions_rated_by_too_few_users_arent_compared() {
+    let settings = RecommendationSettings {
+      min_co_ratings: 4,
+      ..unshrunk()
+    };
+    assert!(train(&rates(), &settings).is_empty());
+  }
+
+  #[test]
+  fn the_similarities_with_few_co_ratings_are_shrunk() {
+    let unshrunk_model = train(&rates(), &unshrunk());
+    let shrunk_model = train(&rates(), &RecommendationSettings::default());
+    let unshrunk_similarity = similarity_of(&unshrunk_model, 1, 2).unwrap();
+    let shrunk_similarity = similarity_of(&shrunk_model, 1, 2).unwrap();
+    // Three co-ratings with a shrinkage of 10 weight 3 / 13.
+    assert!(
+      (shrunk_similarity - unshrunk_similarity * 3.0 / 13.0).abs() < 1e-9
+    );
+  }
+
+  #[test]
+  fn every_attraction_keeps_only_its_strongest_neighbours() {
+    let settings = RecommendationSettings {
+      neighbours: 1,
+      ..unshrunk()
+    };
+    let model = train(&rates(), &settings);
+    assert_eq!(model.len(), 3);
+    assert!(model
+      .iter()
+      .all(|a_neighbour| a_neighbour.similarity.abs() > 0.9));
+  }
+
+  #[test]
+  fn recommends_what_similar_users_liked_and_not_what_they_disliked() {
+    let settings = unshrunk();
+    let model = train(&rates(), &settings);
+    let recommendations =
+      recommend(&[rate(4, 1, 0.9)], &model, &[], &[1, 2, 3], &settings);
+    assert_eq!(ids_of(&recommendations), vec![2]);
+    assert!(recommendations[0].collaborative_score.is_some());
+    assert_eq!(recommendations[0].content_score, None);
+    assert_eq!(recommendations[0].because_of, vec![1]);
+    assert!(recommendations[0].predicted_rate > 0.5);
+    assert!(recommendations[0].predicted_rate <= MAX_RATE);
+  }
+
+  #[test]
+  fn the_attractions_without_ratings_are_recommended_by_their_content() {
+    let settings = unshrunk();
+    let model = train(&rates(), &settings);
+    let recommendations = recommend(
+      &[rate(4, 1, 0.9)],
+      &model,
+      &[content(1, 4)],
+      &[1, 2, 3, 4],
+      &settings,
+    );
+    assert_eq!(ids_of(&recommendations), vec![2, 4]);
+    assert_eq!(recommendations[1].collaborative_score, None);
+    assert!(recommendations[1].content_score.is_some());
+    assert_eq!(recommendations[1].because_of, vec![1]);
+  }
+
+  #[test]
+  fn only_the_candidates_that_the_user_didnt_rate_are_recommended() {
+    let settings = unshrunk();
+    let model = train(&rates(), &settings);
+    let user_rates = [rate(4, 1, 0.9), rate(4, 4, 0.9)];
+    let recommendations =
+      recommend(&user_rates, &model, &[content(1, 4)], &[1, 2, 4], &settings);
+    assert_eq!(ids_of(&recommendations), vec![2]);
+  }
+
+  #[test]
+  fn nothing_is_recommended_without_ratings() {
+    let settings = unshrunk();
+    let model = train(&rates(), &settings);
+    assert!(
+      recommend(&[], &model, &[content(1, 4)], &[1, 2, 4], &settings)
+        .is_empty()
+    );
+  }
+}
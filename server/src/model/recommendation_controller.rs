@@ -0,0 +1,316 @@
+use crate::model::{
+  recommendation::{self, Recommendation, RecommendationSettings},
+  recommendation_repository::RecommendationRepository,
+};
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use std::collections::HashSet;
+
+/// What the training of the model found.
+#[derive(Debug, Clone)]
+pub struct TrainedModel {
+  pub users: usize,
+  pub rated_attractions: usize,
+  pub neighbours: usize,
+  pub trained_at: NaiveDateTime,
+}
+
+#[async_trait]
+pub trait RecommendationController: Send + Sync + 'static {
+  async fn train(&self) -> Result<TrainedModel, String>;
+  async fn recommendations(
+    &self,
+    user_id: i32,
+    city_id: Option<i32>,
+  ) -> Option<Vec<Recommendation>>;
+}
+
+#[derive(Clone)]
+pub struct RecommendationControllerImpl<RecommendationRepo> {
+  recommendation_repository: RecommendationRepo,
+  settings: RecommendationSettings,
+}
+
+impl<RecommendationRepo> RecommendationControllerImpl<RecommendationRepo>
+where
+  RecommendationRepo: RecommendationRepository,
+{
+  pub fn new(
+    recommendation_repository: RecommendationRepo,
+    settings: RecommendationSettings,
+  ) -> Self {
+    RecommendationControllerImpl {
+      recommendation_repository,
+      settings,
+    }
+  }
+}
+
+#[async_trait]
+impl<RecommendationRepo> RecommendationController
+  for RecommendationControllerImpl<RecommendationRepo>
+where
+  RecommendationRepo: RecommendationRepository + Send + Sync + 'static,
+{
+  /// Train the model from the accepted ratings and the check-ins of the
+  /// users and replace the stored one.
+  async fn train(&self) -> Result<TrainedModel, String> {
+    let rates = self
+      .recommendation_repository
+      .user_rates(None, self.settings.check_in_rate)
+      .await
+      .map_err(|e| e.to_string())?;
+    let neighbours = recommendation::train(&rates, &self.settings);
+    let trained_at = Utc::now().naive_utc();
+    let trained = TrainedModel {
+      users: rates
+        .iter()
+        .map(|a_rate| a_rate.user_id)
+        .collect::<HashSet<i32>>()
+        .len(),
+      rated_attractions: rates
+        .iter()
+        .map(|a_rate| a_rate.attraction_id)
+        .collect::<HashSet<i32>>()
+        .len(),
+      neighbours: neighbours.len(),
+      trained_at,
+    };
+    self
+      .recommendation_repository
+      .replace_neighbours(neighbours, trained_at)
+      .await
+      .map_err(|e| e.to_string())?;
+    Ok(trained)
+  }
+
+  /// The attractions of the city, or of every city, that the user didn't
+  /// rate yet. The attractions rated after the last training are compared by
+  /// content until the model is trained again.
+  async fn recommendations(
+    &self,
+    user_id: i32,
+    city_id: Option<i32>,
+  ) -> Option<Vec<Recommendation>> {
+    let user_rates = self
+      .recommendation_repository
+      .user_rates(Some(user_id), self.settings.check_in_rate)
+      .await
+      .ok()?;
+    if user_rates.is_empty() {
+      return Some(Vec::new());
+    }
+    let rated = user_rates
+      .iter()
+      .map(|a_rate| a_rate.attraction_id)
+      .collect::<Vec<i32>>();
+    let neighbours = self
+      .recommendation_repository
+      .neighbours_of(rated.clone())
+      .await
+      .ok()?;
+    let content = self
+      .recommendation_repository
+      .content_similarities_of(rated)
+      .await
+      .ok()?;
+    let candidates = self
+      .recommendation_repository
+      .candidate_ids(city_id)
+      .await
+      .ok()?
+      .iter()
+      .map(|an_id| an_id.id)
+      .collect::<Vec<i32>>();
+    Some(recommendation::recommend(
+      &user_rates,
+      &neighbours,
+      &content,
+      &candidates,
+      &self.settings,
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::{
+    attraction_repository::EntityId,
+    recommendation::{Neighbour, UserRate},
+  };
+  use std::sync::Mutex;
+
+  /// The rates, the stored model and the candidates in memory.
+  #[derive(Default)]
+  struct InMemoryRecommendationRepo {
+    rates: Vec<UserRate>,
+    content: Vec<Neighbour>,
+    candidates: Vec<i32>,
+    model: Mutex<Vec<Neighbour>>,
+  }
+
+  #[async_trait]
+  impl RecommendationRepository for InMemoryRecommendationRepo {
+    async fn user_rates(
+      &self,
+      user_id: Option<i32>,
+      _: f64,
+    ) -> sqlx::Result<Vec<UserRate>> {
+      Ok(
+        self
+          .rates
+          .iter()
+          .filter(|a_rate| user_id.is_none_or(|id| a_rate.user_id == id))
+          .copied()
+          .collect(),
+      )
+    }
+
+    async fn replace_neighbours(
+      &self,
+      neighbours: Vec<Neighbour>,
+      _: NaiveDateTime,
+    ) -> sqlx::Result<()> {
+      *self.model.lock().unwrap() = neighbours;
+      Ok(())
+    }
+
+    async fn neighbours_of(
+      &self,
+      attraction_ids: Vec<i32>,
+    ) -> sqlx::Result<Vec<Neighbour>> {
+      let model = self.model.lock().unwrap();
+      Ok(
+        model
+          .iter()
+          .filter(|a_neighbour| {
+            attraction_ids.contains(&a_neighbour.attraction_id)
+          })
+          .copied()
+          .collect(),
+      )
+    }
+
+    async fn content_similarities_of(
+      &self,
+      attraction_ids: Vec<i32>,
+    ) -> sqlx::Result<Vec<Neighbour>> {
+      Ok(
+        self
+          .content
+          .iter()
+          .filter(|a_neighbour| {
+            attraction_ids.contains(&a_neighbour.attraction_id)
+          })
+          .copied()
+          .collect(),
+      )
+    }
+
+    async fn candidate_ids(
+      &self,
+      _: Option<i32>,
+    ) -> sqlx::Result<Vec<EntityId>> {
+      Ok(
+        self
+          .candidates
+          .iter()
+          .map(|id| EntityId {
+            id: *id,
+          })
+          .collect(),
+      )
+    }
+  }
+
+  fn rate(user_id: i32, attraction_id: i32, rate: f64) -> UserRate {
+    UserRate {
+      user_id,
+      attraction_id,
+      rate,
+    }
+  }
+
+  /// The users 1 and 2 like 1 and 2 and dislike 3, the user 3 the opposite.
+  /// The user 4 only rated 1, and 4 is similar in content to 1.
+  fn controller() -> RecommendationControllerImpl<InMemoryRecommendationRepo> {
+    let repository = InMemoryRecommendationRepo {
+      rates: vec![
+        rate(1, 1, 0.9),
+        rate(1, 2, 0.9),
+        rate(1, 3, 0.1),
+        rate(2, 1, 1.0),
+        rate(2, 2, 0.8),
+        rate(2, 3, 0.2),
+        rate(3, 1, 0.2),
+        rate(3, 2, 0.1),
+        rate(3, 3, 0.9),
+        rate(4, 1, 0.9),
+      ],
+      content: vec![Neighbour {
+        attraction_id: 1,
+        to_attraction_id: 4,
+        similarity: 0.5,
+        co_rating_count: 0,
+      }],
+      candidates: vec![1, 2, 3, 4],
+      ..Default::default()
+    };
+    RecommendationControllerImpl::new(
+      repository,
+      RecommendationSettings {
+        shrinkage: 0.0,
+        ..Default::default()
+      },
+    )
+  }
+
+  #[tokio::test]
+  async fn the_training_counts_the_users_and_stores_the_model() {
+    let controller = controller();
+    let trained = controller.train().await.unwrap();
+    assert_eq!(trained.users, 4);
+    assert_eq!(trained.rated_attractions, 3);
+    assert_eq!(trained.neighbours, 6);
+    assert_eq!(
+      controller
+        .recommendation_repository
+        .model
+        .lock()
+        .unwrap()
+        .len(),
+      6
+    );
+  }
+
+  #[tokio::test]
+  async fn the_trained_model_recommends_to_the_users() {
+    let controller = controller();
+    controller.train().await.unwrap();
+    let recommendations = controller.recommendations(4, None).await.unwrap();
+    let ids = recommendations
+      .iter()
+      .map(|a_recommendation| a_recommendation.attraction_id)
+      .collect::<Vec<i32>>();
+    assert_eq!(ids, vec![2, 4]);
+  }
+
+  #[tokio::test]
+  async fn before_the_training_only_the_content_is_compared() {
+    let controller = controller();
+    let recommendations = controller.recommendations(4, None).await.unwrap();
+    let ids = recommendations
+      .iter()
+      .map(|a_recommendation| a_recommendation.attraction_id)
+      .collect::<Vec<i32>>();
+    assert_eq!(ids, vec![4]);
+  }
+
+  #[tokio::test]
+  async fn a_user_without_ratings_gets_no_recommendations() {
+    let controller = controller();
+    controller.train().await.unwrap();
+    assert_eq!(controller.recommendations(5, None).await, Some(Vec::new()));
+  }
+}
@@ -0,0 +1,225 @@
+use crate::{
+  db::database::DbConnection,
+  model::{
+    attraction_repository::EntityId,
+    recommendation::{Neighbour, UserRate},
+  },
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+
+#[async_trait]
+pub trait RecommendationRepository {
+  async fn user_rates(
+    &self,
+    user_id: Option<i32>,
+    check_in_rate: f64,
+  ) -> sqlx::Result<Vec<UserRate>>;
+  async fn replace_neighbours(
+    &self,
+    neighbours: Vec<Neighbour>,
+    trained_at: NaiveDateTime,
+  ) -> sqlx::Result<()>;
+  async fn neighbours_of(
+    &self,
+    attraction_ids: Vec<i32>,
+  ) -> sqlx::Result<Vec<Neighbour>>;
+  async fn content_similarities_of(
+    &self,
+    attraction_ids: Vec<i32>,
+  ) -> sqlx::Result<Vec<Neighbour>>;
+  async fn candidate_ids(
+    &self,
+    city_id: Option<i32>,
+  ) -> sqlx::Result<Vec<EntityId>>;
+}
+
+#[derive(Clone, Default)]
+pub struct DummyRecommendationRepo;
+
+#[async_trait]
+impl RecommendationRepository for DummyRecommendationRepo {
+  async fn user_rates(
+    &self,
+    _: Option<i32>,
+    _: f64,
+  ) -> sqlx::Result<Vec<UserRate>> {
+    todo!()
+  }
+
+  async fn replace_neighbours(
+    &self,
+    _: Vec<Neighbour>,
+    _: NaiveDateTime,
+  ) -> sqlx::Result<()> {
+    todo!()
+  }
+
+  async fn neighbours_of(&self, _: Vec<i32>) -> sqlx::Result<Vec<Neighbour>> {
+    todo!()
+  }
+
+  async fn content_similarities_of(
+    &self,
+    _: Vec<i32>,
+  ) -> sqlx::Result<Vec<Neighbour>> {
+    todo!()
+  }
+
+  async fn candidate_ids(&self, _: Option<i32>) -> sqlx::Result<Vec<EntityId>> {
+    todo!()
+  }
+}
+
+#[derive(Clone)]
+pub struct PgRecommendationRepository {
+  connection: DbConnection,
+}
+
+impl PgRecommendationRepository {
+  pub fn new(connection: DbConnection) -> Self {
+    PgRecommendationRepository {
+      connection,
+    }
+  }
+}
+
+#[async_trait]
+impl RecommendationRepository for PgRecommendationRepository {
+  /// The average of the accepted ratings of every user to every attraction,
+  /// only of one user if given. The anonymous ratings are left out. The
+  /// attractions that the user visited but never rated count as rated with
+  /// the check-in rate.
+  async fn user_rates(
+    &self,
+    user_id: Option<i32>,
+    check_in_rate: f64,
+  ) -> sqlx::Result<Vec<UserRate>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      UserRate,
+      r#"
+      SELECT user_id as "user_id!", attraction_id as "attraction_id!",
+      avg(rate)::float8 as "rate!"
+      FROM (
+        SELECT user_id, attraction_id, rate::float8
+        FROM attraction_rating
+        WHERE user_id IS NOT NULL AND status = 'ACCEPTED'
+        UNION ALL
+        SELECT DISTINCT ci.user_id, ci.attraction_id, $2::float8
+        FROM check_in ci
+        WHERE NOT EXISTS (
+          SELECT 1 FROM attraction_rating ar
+          WHERE ar.user_id = ci.user_id AND ar.attraction_id = ci.attraction_id
+          AND ar.status = 'ACCEPTED'
+        )
+      ) rates
+      WHERE ($1::integer IS NULL OR user_id = $1)
+      GROUP BY user_id, attraction_id
+      ORDER BY user_id, attraction_id
+      "#,
+      user_id,
+      check_in_rate
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// Replaces the whole model with the one just trained, in a single
+  /// transaction.
+  async fn replace_neighbours(
+    &self,
+    neighbours: Vec<Neighbour>,
+    trained_at: NaiveDateTime,
+  ) -> sqlx::Result<()> {
+    let mut transaction = self.connection.get().begin().await?;
+    sqlx::query!(
+      r#"
+      DELETE FROM attraction_neighbour
+      "#
+    )
+    .execute(&mut *transaction)
+    .await?;
+    for a_neighbour in neighbours {
+      sqlx::query!(
+        r#"
+        INSERT INTO attraction_neighbour
+        (attraction_id, to_attraction_id, similarity, co_rating_count,
+        trained_at)
+        VALUES ($1, $2, $3::float8, $4, $5)
+        "#,
+        a_neighbour.attraction_id,
+        a_neighbour.to_attraction_id,
+        a_neighbour.similarity,
+        a_neighbour.co_rating_count,
+        trained_at
+      )
+      .execute(&mut *transaction)
+      .await?;
+    }
+    transaction.commit().await
+  }
+
+  async fn neighbours_of(
+    &self,
+    attraction_ids: Vec<i32>,
+  ) -> sqlx::Result<Vec<Neighbour>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Neighbour,
+      r#"
+      SELECT attraction_id, to_attraction_id,
+      similarity::float8 as "similarity!", co_rating_count
+      FROM attraction_neighbour
+      WHERE attraction_id = ANY($1)
+      "#,
+      &attraction_ids
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// The latest content similarity between the attractions and the rest,
+  /// seen from the given attractions.
+  async fn content_similarities_of(
+    &self,
+    attraction_ids: Vec<i32>,
+  ) -> sqlx::Result<Vec<Neighbour>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Neighbour,
+      r#"
+      SELECT DISTINCT ON (1, 2)
+      CASE WHEN attraction_id = ANY($1) THEN attraction_id
+        ELSE to_attraction_id END as "attraction_id!",
+      CASE WHEN attraction_id = ANY($1) THEN to_attraction_id
+        ELSE attraction_id END as "to_attraction_id!",
+      similarity::float8 as "similarity!", 0 as "co_rating_count!"
+      FROM attraction_similarity
+      WHERE attraction_id = ANY($1) OR to_attraction_id = ANY($1)
+      ORDER BY 1, 2, at DESC
+      "#,
+      &attraction_ids
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn candidate_ids(
+    &self,
+    city_id: Option<i32>,
+  ) -> sqlx::Result<Vec<EntityId>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      EntityId,
+      r#"
+      SELECT id FROM attraction
+      WHERE ($1::integer IS NULL OR city_id = $1) AND deleted_at IS NULL
+      ORDER BY id
+      "#,
+      city_id
+    )
+    .fetch_all(conn)
+    .await
+  }
+}
@@ -0,0 +1,714 @@
+use crate::{
+  db::database::{is_foreign_key_violation, is_unique_violation},
+  model::{
+    attraction::{AttractionType, City, Country, Holiday, Tag},
+    audit::Actor,
+    reference_repository::ReferenceRepository,
+  },
+};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+
+/// The reference data of the attractions: their types and tags, the
+/// countries with their holidays and the cities.
+#[async_trait]
+pub trait ReferenceController: Send + Sync + 'static {
+  async fn attraction_types(&self) -> Option<Vec<AttractionType>>;
+  async fn create_attraction_type(
+    &self,
+    code: String,
+    description: String,
+    actor: Actor,
+  ) -> Result<AttractionType, String>;
+  async fn update_attraction_type(
+    &self,
+    id: i32,
+    code: String,
+    description: String,
+    actor: Actor,
+  ) -> Result<Option<AttractionType>, String>;
+  async fn delete_attraction_type(
+    &self,
+    id: i32,
+    actor: Actor,
+  ) -> Result<bool, String>;
+  async fn countries(&self) -> Option<Vec<Country>>;
+  async fn create_country(
+    &self,
+    iso_code: String,
+    description: String,
+    actor: Actor,
+  ) -> Result<Country, String>;
+  async fn update_country(
+    &self,
+    iso_code: String,
+    description: String,
+    actor: Actor,
+  ) -> Result<Option<Country>, String>;
+  async fn delete_country(
+    &self,
+    iso_code: String,
+    actor: Actor,
+  ) -> Result<bool, String>;
+  async fn cities(&self, country_id: Option<i32>) -> Option<Vec<City>>;
+  async fn create_city(
+    &self,
+    description: String,
+    country_id: i32,
+    actor: Actor,
+  ) -> Result<City, String>;
+  async fn update_city(
+    &self,
+    id: i32,
+    description: String,
+    country_id: i32,
+    actor: Actor,
+  ) -> Result<Option<City>, String>;
+  async fn delete_city(&self, id: i32, actor: Actor) -> Result<bool, String>;
+  async fn holidays(
+    &self,
+    iso_code: String,
+    year: Option<i32>,
+  ) -> Option<Vec<Holiday>>;
+  async fn create_holiday(
+    &self,
+    iso_code: String,
+    day: NaiveDate,
+    description: String,
+    actor: Actor,
+  ) -> Result<Option<Holiday>, String>;
+  async fn delete_holiday(
+    &self,
+    iso_code: String,
+    day: NaiveDate,
+    actor: Actor,
+  ) -> Result<bool, String>;
+  async fn tags(&self) -> Option<Vec<Tag>>;
+  async fn create_tag(
+    &self,
+    code: String,
+    description: String,
+    actor: Actor,
+  ) -> Result<Tag, String>;
+  async fn update_tag(
+    &self,
+    id: i32,
+    code: String,
+    description: String,
+    actor: Actor,
+  ) -> Result<Option<Tag>, String>;
+  async fn delete_tag(&self, id: i32, actor: Actor) -> Result<bool, String>;
+}
+
+#[derive(Clone)]
+pub struct ReferenceControllerImpl<ReferenceRepo> {
+  reference_repository: ReferenceRepo,
+}
+
+impl<ReferenceRepo> ReferenceControllerImpl<ReferenceRepo>
+where
+  ReferenceRepo: ReferenceRepository,
+{
+  pub fn new(reference_repository: ReferenceRepo) -> Self {
+    ReferenceControllerImpl {
+      reference_repository,
+    }
+  }
+}
+
+#[async_trait]
+impl<ReferenceRepo> ReferenceController
+  for ReferenceControllerImpl<ReferenceRepo>
+where
+  ReferenceRepo: ReferenceRepository + Send + Sync + 'static,
+{
+  async fn attraction_types(&self) -> Option<Vec<AttractionType>> {
+    self.reference_repository.list_attraction_types().await.ok()
+  }
+
+  async fn create_attraction_type(
+    &self,
+    code: String,
+    description: String,
+    actor: Actor,
+  ) -> Result<AttractionType, String> {
+    let code = required(code, "code")?;
+    let description = required(description, "description")?;
+    self
+      .reference_repository
+      .create_attraction_type(code, description, &actor)
+      .await
+      .map_err(|e| e.to_string())
+  }
+
+  /// Returns None if the type doesn't exist.
+  async fn update_attraction_type(
+    &self,
+    id: i32,
+    code: String,
+    description: String,
+    actor: Actor,
+  ) -> Result<Option<AttractionType>, String> {
+    let code = required(code, "code")?;
+    let description = required(description, "description")?;
+    found(
+      self
+        .reference_repository
+        .update_attraction_type(id, code, description, &actor)
+        .await,
+    )
+    .map_err(|e| e.to_string())
+  }
+
+  /// Returns false if the type doesn't exist, and an error if an attraction
+  /// still has it.
+  async fn delete_attraction_type(
+    &self,
+    id: i32,
+    actor: Actor,
+  ) -> Result<bool, String> {
+    deleted(
+      self
+        .reference_repository
+        .delete_attraction_type(id, &actor)
+        .await,
+      "The type has attractions",
+    )
+  }
+
+  async fn countries(&self) -> Option<Vec<Country>> {
+    self.reference_repository.list_countries().await.ok()
+  }
+
+  async fn create_country(
+    &self,
+    iso_code: String,
+    description: String,
+    actor: Actor,
+  ) -> Result<Country, String> {
+    let iso_code = iso_code_of(iso_code)?;
+    let description = required(description, "description")?;
+    self
+      .reference_repository
+      .create_country(iso_code.clone(), description, &actor)
+      .await
+      .map_err(|e| {
+        if is_unique_violation(&e) {
+          format!("The country {iso_code} already exists")
+        } else {
+          e.to_string()
+        }
+      })
+  }
+
+  /// Returns None if the country doesn't exist.
+  async fn update_country(
+    &self,
+    iso_code: String,
+    description: String,
+    actor: Actor,
+  ) -> Result<Option<Country>, String> {
+    let iso_code = iso_code_of(iso_code)?;
+    let description = required(description, "description")?;
+    found(
+      self
+        .reference_repository
+        .update_country(iso_code, description, &actor)
+        .await,
+    )
+    .map_err(|e| e.to_string())
+  }
+
+  /// Returns false if the country doesn't exist, and an error if it still
+  /// has cities.
+  async fn delete_country(
+    &self,
+    iso_code: String,
+    actor: Actor,
+  ) -> Result<bool, String> {
+    deleted(
+      self
+        .reference_repository
+        .delete_country(iso_code.trim().to_uppercase(), &actor)
+        .await,
+      "The country has cities",
+    )
+  }
+
+  async fn cities(&self, country_id: Option<i32>) -> Option<Vec<City>> {
+    self.reference_repository.list_cities(country_id).await.ok()
+  }
+
+  async fn create_city(
+    &self,
+    description: String,
+    country_id: i32,
+    actor: Actor,
+  ) -> Result<City, String> {
+    let description = required(description, "description")?;
+    self
+      .reference_repository
+      .create_city(description, country_id, &actor)
+      .await
+      .map_err(|e| missing_country(&e))
+  }
+
+  /// Returns None if the city doesn't exist.
+  async fn update_city(
+    &self,
+    id: i32,
+    description: String,
+    country_id: i32,
+    actor: Actor,
+  ) -> Result<Option<City>, String> {
+    let description = required(description, "description")?;
+    found(
+      self
+        .reference_repository
+        .update_city(id, description, country_id, &actor)
+        .await,
+    )
+    .map_err(|e| missing_country(&e))
+  }
+
+  /// Returns false if the city doesn't exist, and an error if it still has
+  /// attractions.
+  async fn delete_city(&self, id: i32, actor: Actor) -> Result<bool, String> {
+    deleted(
+      self.reference_repository.delete_city(id, &actor).await,
+      "The city has attractions",
+    )
+  }
+
+  async fn holidays(
+    &self,
+    iso_code: String,
+    year: Option<i32>,
+  ) -> Option<Vec<Holiday>> {
+    self
+      .reference_repository
+      .list_holidays(iso_code.trim().to_uppercase(), year)
+      .await
+      .ok()
+  }
+
+  /// Returns None if the country doesn't exist.
+  async fn create_holiday(
+    &self,
+    iso_code: String,
+    day: NaiveDate,
+    description: String,
+    actor: Actor,
+  ) -> Result<Option<Holiday>, String> {
+    let iso_code = iso_code_of(iso_code)?;
+    let description = required(description, "description")?;
+    found(
+      self
+        .reference_repository
+        .create_holiday(iso_code.clone(), day, description, &actor)
+        .await,
+    )
+    .map_err(|e| {
+      if is_unique_violation(&e) {
+        format!("The country {iso_code} already has a holiday on {day}")
+      } else {
+        e.to_string()
+      }
+    })
+  }
+
+  /// Returns false if the country doesn't have the holiday.
+  async fn delete_holiday(
+    &self,
+    iso_code: String,
+    day: NaiveDate,
+    actor: Actor,
+  ) -> Result<bool, String> {
+    self
+      .reference_repository
+      .delete_holiday(iso_code.trim().to_uppercase(), day, &actor)
+      .await
+      .map(|deleted| deleted > 0)
+      .map_err(|e| e.to_string())
+  }
+
+  async fn tags(&self) -> Option<Vec<Tag>> {
+    self.reference_repository.list_tags().await.ok()
+  }
+
+  async fn create_tag(
+    &self,
+    code: String,
+    description: String,
+    actor: Actor,
+  ) -> Result<Tag, String> {
+    let code = tag_code_of(code)?;
+    let description = required(description, "description")?;
+    self
+      .reference_repository
+      .create_tag(code.clone(), description, &actor)
+      .await
+      .map_err(|e| taken_tag(&e, &code))
+  }
+
+  /// Returns None if the tag doesn't exist.
+  async fn update_tag(
+    &self,
+    id: i32,
+    code: String,
+    description: String,
+    actor: Actor,
+  ) -> Result<Option<Tag>, String> {
+    let code = tag_code_of(code)?;
+    let description = required(description, "description")?;
+    found(
+      self
+        .reference_repository
+        .update_tag(id, code.clone(), description, &actor)
+        .await,
+    )
+    .map_err(|e| taken_tag(&e, &code))
+  }
+
+  /// Returns false if the tag doesn't exist, the attractions lose it until
+  /// it's restored.
+  async fn delete_tag(&self, id: i32, actor: Actor) -> Result<bool, String> {
+    self
+      .reference_repository
+      .delete_tag(id, &actor)
+      .await
+      .map(|deleted| deleted > 0)
+      .map_err(|e| e.to_string())
+  }
+}
+
+/// The trimmed value, or an error if it is blank.
+pub fn required(value: String, name: &str) -> Result<String, String> {
+  let value = value.trim().to_string();
+  if value.is_empty() {
+    return Err(format!("The {name} can't be empty"));
+  }
+  Ok(value)
+}
+
+/// The upper case code, or an error if it doesn't have 2 or 3 letters.
+fn iso_code_of(iso_code: String) -> Result<String, String> {
+  let iso_code = iso_code.trim().to_uppercase();
+  if !(2..=3).contains(&iso_code.len())
+    || !iso_code.chars().all(|c| c.is_ascii_alphabetic())
+  {
+    return Err(String::from("The iso code must have 2 or 3 letters"));
+  }
+  Ok(iso_code)
+}
+
+/// The lower case code, or an error if it has something else than letters,
+/// digits and dashes, like "family-friendly".
+pub fn tag_code_of(code: String) -> Result<String, String> {
+  let code = code.trim().to_lowercase();
+  if code.is_empty()
+    || !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+  {
+    return Err(format!(
+      "The tag \"{code}\" must have only letters, digits and dashes"
+    ));
+  }
+  Ok(code)
+}
+
+fn found<T>(result: sqlx::Result<T>) -> sqlx::Result<Option<T>> {
+  match result {
+    Ok(value) => Ok(Some(value)),
+    Err(sqlx::Error::RowNotFound) => Ok(None),
+    Err(e) => Err(e),
+  }
+}
+
+fn deleted(result: sqlx::Result<u64>, in_use: &str) -> Result<bool, String> {
+  match result {
+    Ok(count) => Ok(count > 0),
+    Err(e) if is_foreign_key_violation(&e) => Err(in_use.to_string()),
+    Err(e) => Err(e.to_string()),
+  }
+}
+
+fn taken_tag(e: &sqlx::Error, code: &str) -> String {
+  if is_unique_violation(e) {
+    format!("The tag {code} already exists")
+  } else {
+    e.to_string()
+  }
+}
+
+fn missing_country(e: &sqlx::Error) -> String {
+  if is_foreign_key_violation(e) {
+    String::from("The country of the city doesn't exist")
+  } else {
+    e.to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Mutex;
+
+  /// The tags in memory, the rest of the reference data isn't needed.
+  #[derive(Default)]
+  struct InMemoryTagRepo {
+    tags: Mutex<Vec<Tag>>,
+  }
+
+  #[async_trait]
+  impl ReferenceRepository for InMemoryTagRepo {
+    async fn list_attraction_types(&self) -> sqlx::Result<Vec<AttractionType>> {
+      todo!()
+    }
+
+    async fn create_attraction_type(
+      &self,
+      _: String,
+      _: String,
+      _: &Actor,
+    ) -> sqlx::Result<AttractionType> {
+      todo!()
+    }
+
+    async fn update_attraction_type(
+      &self,
+      _: i32,
+      _: String,
+      _: String,
+      _: &Actor,
+    ) -> sqlx::Result<AttractionType> {
+      todo!()
+    }
+
+    async fn delete_attraction_type(
+      &self,
+      _: i32,
+      _: &Actor,
+    ) -> sqlx::Result<u64> {
+      todo!()
+    }
+
+    async fn list_countries(&self) -> sqlx::Result<Vec<Country>> {
+      todo!()
+    }
+
+    async fn create_country(
+      &self,
+      _: String,
+      _: String,
+      _: &Actor,
+    ) -> sqlx::Result<Country> {
+      todo!()
+    }
+
+    async fn update_country(
+      &self,
+      _: String,
+      _: String,
+      _: &Actor,
+    ) -> sqlx::Result<Country> {
+      todo!()
+    }
+
+    async fn delete_country(&self, _: String, _: &Actor) -> sqlx::Result<u64> {
+      todo!()
+    }
+
+    async fn list_cities(&self, _: Option<i32>) -> sqlx::Result<Vec<City>> {
+      todo!()
+    }
+
+    async fn create_city(
+      &self,
+      _: String,
+      _: i32,
+      _: &Actor,
+    ) -> sqlx::Result<City> {
+      todo!()
+    }
+
+    async fn update_city(
+      &self,
+      _: i32,
+      _: String,
+      _: i32,
+      _: &Actor,
+    ) -> sqlx::Result<City> {
+      todo!()
+    }
+
+    async fn delete_city(&self, _: i32, _: &Actor) -> sqlx::Result<u64> {
+      todo!()
+    }
+
+    async fn list_holidays(
+      &self,
+      _: String,
+      _: Option<i32>,
+    ) -> sqlx::Result<Vec<Holiday>> {
+      todo!()
+    }
+
+    async fn create_holiday(
+      &self,
+      _: String,
+      _: NaiveDate,
+      _: String,
+      _: &Actor,
+    ) -> sqlx::Result<Holiday> {
+      todo!()
+    }
+
+    async fn delete_holiday(
+      &self,
+      _: String,
+      _: NaiveDate,
+      _: &Actor,
+    ) -> sqlx::Result<u64> {
+      todo!()
+    }
+
+    async fn list_tags(&self) -> sqlx::Result<Vec<Tag>> {
+      Ok(self.tags.lock().unwrap().clone())
+    }
+
+    async fn create_tag(
+      &self,
+      code: String,
+      description: String,
+      _: &Actor,
+    ) -> sqlx::Result<Tag> {
+      let mut tags = self.tags.lock().unwrap();
+      let created = Tag {
+        id: tags.len() as i32 + 1,
+        code,
+        description,
+        deleted_at: None,
+      };
+      tags.push(created.clone());
+      Ok(created)
+    }
+
+    async fn update_tag(
+      &self,
+      id: i32,
+      code: String,
+      description: String,
+      _: &Actor,
+    ) -> sqlx::Result<Tag> {
+      let mut tags = self.tags.lock().unwrap();
+      let a_tag = tags
+        .iter_mut()
+        .find(|a_tag| a_tag.id == id)
+        .ok_or(sqlx::Error::RowNotFound)?;
+      a_tag.code = code;
+      a_tag.description = description;
+      Ok(a_tag.clone())
+    }
+
+    async fn delete_tag(&self, id: i32, _: &Actor) -> sqlx::Result<u64> {
+      let mut tags = self.tags.lock().unwrap();
+      let before = tags.len();
+      tags.retain(|a_tag| a_tag.id != id);
+      Ok((before - tags.len()) as u64)
+    }
+  }
+
+  fn editor() -> Actor {
+    Actor {
+      id: 1,
+      username: String::from("editor"),
+    }
+  }
+
+  #[test]
+  fn the_iso_codes_have_two_or_three_letters() {
+    assert_eq!(iso_code_of(String::from(" ar ")), Ok(String::from("AR")));
+    assert_eq!(iso_code_of(String::from("arg")), Ok(String::from("ARG")));
+    assert!(iso_code_of(String::from("a")).is_err());
+    assert!(iso_code_of(String::from("a1")).is_err());
+  }
+
+  #[test]
+  fn the_required_values_cant_be_blank() {
+    assert_eq!(
+      required(String::from(" Museum "), "description"),
+      Ok(String::from("Museum"))
+    );
+    assert_eq!(
+      required(String::from("  "), "description"),
+      Err(String::from("The description can't be empty"))
+    );
+  }
+
+  #[test]
+  fn the_tag_codes_are_lower_case_words_joined_by_dashes() {
+    assert_eq!(
+      tag_code_of(String::from(" Family-Friendly ")),
+      Ok(String::from("family-friendly"))
+    );
+    assert!(tag_code_of(String::from("free entry")).is_err());
+    assert!(tag_code_of(String::from(" ")).is_err());
+  }
+
+  #[tokio::test]
+  async fn a_tag_is_created_with_its_code_and_description_cleaned() {
+    let controller = ReferenceControllerImpl::new(InMemoryTagRepo::default());
+    let created = controller
+      .create_tag(String::from(" Indoor "), String::from(" Indoor "), editor())
+      .await
+      .unwrap();
+    assert_eq!(created.code, "indoor");
+    assert_eq!(created.description, "Indoor");
+    assert_eq!(controller.tags().await.map(|tags| tags.len()), Some(1));
+  }
+
+  #[tokio::test]
+  async fn an_invalid_tag_isnt_stored() {
+    let controller = ReferenceControllerImpl::new(InMemoryTagRepo::default());
+    assert!(controller
+      .create_tag(String::from("free entry"), String::from("Free"), editor())
+      .await
+      .is_err());
+    assert!(controller
+      .create_tag(String::from("free"), String::from(" "), editor())
+      .await
+      .is_err());
+    assert_eq!(controller.tags().await.map(|tags| tags.len()), Some(0));
+  }
+
+  #[tokio::test]
+  async fn a_missing_tag_is_neither_updated_nor_deleted() {
+    let controller = ReferenceControllerImpl::new(InMemoryTagRepo::default());
+    let created = controller
+      .create_tag(String::from("free"), String::from("Free"), editor())
+      .await
+      .unwrap();
+    let updated = controller
+      .update_tag(
+        created.id,
+        String::from("Free"),
+        String::from("Gratis"),
+        editor(),
+      )
+      .await
+      .unwrap();
+    assert_eq!(
+      updated.map(|a_tag| a_tag.description),
+      Some(String::from("Gratis"))
+    );
+    assert_eq!(
+      controller
+        .update_tag(2, String::from("free"), String::from("Free"), editor())
+        .await
+        .map(|a_tag| a_tag.is_none()),
+      Ok(true)
+    );
+    assert_eq!(controller.delete_tag(created.id, editor()).await, Ok(true));
+    assert_eq!(controller.delete_tag(created.id, editor()).await, Ok(false));
+  }
+}
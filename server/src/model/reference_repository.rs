@@ -0,0 +1,675 @@
+use crate::{
+  db::database::DbConnection,
+  model::{
+    attraction::{AttractionType, City, Country, Holiday, Tag},
+    audit::Actor,
+    audit_repository::act_as,
+  },
+};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+
+#[async_trait]
+pub trait ReferenceRepository {
+  async fn list_attraction_types(&self) -> sqlx::Result<Vec<AttractionType>>;
+  async fn create_attraction_type(
+    &self,
+    code: String,
+    description: String,
+    actor: &Actor,
+  ) -> sqlx::Result<AttractionType>;
+  async fn update_attraction_type(
+    &self,
+    id: i32,
+    code: String,
+    description: String,
+    actor: &Actor,
+  ) -> sqlx::Result<AttractionType>;
+  async fn delete_attraction_type(
+    &self,
+    id: i32,
+    actor: &Actor,
+  ) -> sqlx::Result<u64>;
+  async fn list_countries(&self) -> sqlx::Result<Vec<Country>>;
+  async fn create_country(
+    &self,
+    iso_code: String,
+    description: String,
+    actor: &Actor,
+  ) -> sqlx::Result<Country>;
+  async fn update_country(
+    &self,
+    iso_code: String,
+    description: String,
+    actor: &Actor,
+  ) -> sqlx::Result<Country>;
+  async fn delete_country(
+    &self,
+    iso_code: String,
+    actor: &Actor,
+  ) -> sqlx::Result<u64>;
+  async fn list_cities(
+    &self,
+    country_id: Option<i32>,
+  ) -> sqlx::Result<Vec<City>>;
+  async fn create_city(
+    &self,
+    description: String,
+    country_id: i32,
+    actor: &Actor,
+  ) -> sqlx::Result<City>;
+  async fn update_city(
+    &self,
+    id: i32,
+    description: String,
+    country_id: i32,
+    actor: &Actor,
+  ) -> sqlx::Result<City>;
+  async fn delete_city(&self, id: i32, actor: &Actor) -> sqlx::Result<u64>;
+  async fn list_holidays(
+    &self,
+    iso_code: String,
+    year: Option<i32>,
+  ) -> sqlx::Result<Vec<Holiday>>;
+  async fn create_holiday(
+    &self,
+    iso_code: String,
+    day: NaiveDate,
+    description: String,
+    actor: &Actor,
+  ) -> sqlx::Result<Holiday>;
+  async fn delete_holiday(
+    &self,
+    iso_code: String,
+    day: NaiveDate,
+    actor: &Actor,
+  ) -> sqlx::Result<u64>;
+  async fn list_tags(&self) -> sqlx::Result<Vec<Tag>>;
+  async fn create_tag(
+    &self,
+    code: String,
+    description: String,
+    actor: &Actor,
+  ) -> sqlx::Result<Tag>;
+  async fn update_tag(
+    &self,
+    id: i32,
+    code: String,
+    description: String,
+    actor: &Actor,
+  ) -> sqlx::Result<Tag>;
+  async fn delete_tag(&self, id: i32, actor: &Actor) -> sqlx::Result<u64>;
+}
+
+#[derive(Clone, Default)]
+pub struct DummyReferenceRepo;
+
+#[async_trait]
+impl ReferenceRepository for DummyReferenceRepo {
+  async fn list_attraction_types(&self) -> sqlx::Result<Vec<AttractionType>> {
+    todo!()
+  }
+
+  async fn create_attraction_type(
+    &self,
+    _: String,
+    _: String,
+    _: &Actor,
+  ) -> sqlx::Result<AttractionType> {
+    todo!()
+  }
+
+  async fn update_attraction_type(
+    &self,
+    _: i32,
+    _: String,
+    _: String,
+    _: &Actor,
+  ) -> sqlx::Result<AttractionType> {
+    todo!()
+  }
+
+  async fn delete_attraction_type(
+    &self,
+    _: i32,
+    _: &Actor,
+  ) -> sqlx::Result<u64> {
+    todo!()
+  }
+
+  async fn list_countries(&self) -> sqlx::Result<Vec<Country>> {
+    todo!()
+  }
+
+  async fn create_country(
+    &self,
+    _: String,
+    _: String,
+    _: &Actor,
+  ) -> sqlx::Result<Country> {
+    todo!()
+  }
+
+  async fn update_country(
+    &self,
+    _: String,
+    _: String,
+    _: &Actor,
+  ) -> sqlx::Result<Country> {
+    todo!()
+  }
+
+  async fn delete_country(&self, _: String, _: &Actor) -> sqlx::Result<u64> {
+    todo!()
+  }
+
+  async fn list_cities(&self, _: Option<i32>) -> sqlx::Result<Vec<City>> {
+    todo!()
+  }
+
+  async fn create_city(
+    &self,
+    _: String,
+    _: i32,
+    _: &Actor,
+  ) -> sqlx::Result<City> {
+    todo!()
+  }
+
+  async fn update_city(
+    &self,
+    _: i32,
+    _: String,
+    _: i32,
+    _: &Actor,
+  ) -> sqlx::Result<City> {
+    todo!()
+  }
+
+  async fn delete_city(&self, _: i32, _: &Actor) -> sqlx::Result<u64> {
+    todo!()
+  }
+
+  async fn list_holidays(
+    &self,
+    _: String,
+    _: Option<i32>,
+  ) -> sqlx::Result<Vec<Holiday>> {
+    todo!()
+  }
+
+  async fn create_holiday(
+    &self,
+    _: String,
+    _: NaiveDate,
+    _: String,
+    _: &Actor,
+  ) -> sqlx::Result<Holiday> {
+    todo!()
+  }
+
+  async fn delete_holiday(
+    &self,
+    _: String,
+    _: NaiveDate,
+    _: &Actor,
+  ) -> sqlx::Result<u64> {
+    todo!()
+  }
+
+  async fn list_tags(&self) -> sqlx::Result<Vec<Tag>> {
+    todo!()
+  }
+
+  async fn create_tag(
+    &self,
+    _: String,
+    _: String,
+    _: &Actor,
+  ) -> sqlx::Result<Tag> {
+    todo!()
+  }
+
+  async fn update_tag(
+    &self,
+    _: i32,
+    _: String,
+    _: String,
+    _: &Actor,
+  ) -> sqlx::Result<Tag> {
+    todo!()
+  }
+
+  async fn delete_tag(&self, _: i32, _: &Actor) -> sqlx::Result<u64> {
+    todo!()
+  }
+}
+
+#[derive(Clone)]
+pub struct PgReferenceRepository {
+  connection: DbConnection,
+}
+
+impl PgReferenceRepository {
+  pub fn new(connection: DbConnection) -> Self {
+    PgReferenceRepository {
+      connection,
+    }
+  }
+}
+
+#[async_trait]
+impl ReferenceRepository for PgReferenceRepository {
+  async fn list_attraction_types(&self) -> sqlx::Result<Vec<AttractionType>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      AttractionType,
+      r#"
+      SELECT * FROM attraction_type
+      WHERE deleted_at IS NULL
+      ORDER BY code
+      "#
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn create_attraction_type(
+    &self,
+    code: String,
+    description: String,
+    actor: &Actor,
+  ) -> sqlx::Result<AttractionType> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let created = sqlx::query_as!(
+      AttractionType,
+      r#"
+      INSERT INTO attraction_type (code, description)
+      VALUES ($1, $2)
+      returning *
+      "#,
+      code,
+      description
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(created)
+  }
+
+  async fn update_attraction_type(
+    &self,
+    id: i32,
+    code: String,
+    description: String,
+    actor: &Actor,
+  ) -> sqlx::Result<AttractionType> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let updated = sqlx::query_as!(
+      AttractionType,
+      r#"
+      UPDATE attraction_type SET code = $2, description = $3
+      WHERE id = $1 AND deleted_at IS NULL
+      returning *
+      "#,
+      id,
+      code,
+      description
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(updated)
+  }
+
+  /// Returns the number of deleted types, it fails if an attraction still has
+  /// the type. The type is deleted softly, it can be restored.
+  async fn delete_attraction_type(
+    &self,
+    id: i32,
+    actor: &Actor,
+  ) -> sqlx::Result<u64> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let deleted = sqlx::query!(
+      r#"
+      UPDATE attraction_type SET deleted_at = now()
+      WHERE id = $1 AND deleted_at IS NULL
+      "#,
+      id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(deleted.rows_affected())
+  }
+
+  async fn list_countries(&self) -> sqlx::Result<Vec<Country>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Country,
+      r#"
+      SELECT * FROM country
+      WHERE deleted_at IS NULL
+      ORDER BY iso_code
+      "#
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn create_country(
+    &self,
+    iso_code: String,
+    description: String,
+    actor: &Actor,
+  ) -> sqlx::Result<Country> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let created = sqlx::query_as!(
+      Country,
+      r#"
+      INSERT INTO country (iso_code, description)
+      VALUES ($1, $2)
+      returning *
+      "#,
+      iso_code,
+      description
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(created)
+  }
+
+  async fn update_country(
+    &self,
+    iso_code: String,
+    description: String,
+    actor: &Actor,
+  ) -> sqlx::Result<Country> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let updated = sqlx::query_as!(
+      Country,
+      r#"
+      UPDATE country SET description = $2
+      WHERE iso_code = $1 AND deleted_at IS NULL
+      returning *
+      "#,
+      iso_code,
+      description
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(updated)
+  }
+
+  /// Returns the number of deleted countries, it fails if the country still
+  /// has cities. The country is deleted softly, it can be restored.
+  async fn delete_country(
+    &self,
+    iso_code: String,
+    actor: &Actor,
+  ) -> sqlx::Result<u64> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let deleted = sqlx::query!(
+      r#"
+      UPDATE country SET deleted_at = now()
+      WHERE iso_code = $1 AND deleted_at IS NULL
+      "#,
+      iso_code
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(deleted.rows_affected())
+  }
+
+  /// Returns the cities of the country, or all of them if there isn't one.
+  async fn list_cities(
+    &self,
+    country_id: Option<i32>,
+  ) -> sqlx::Result<Vec<City>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      City,
+      r#"
+      SELECT * FROM city
+      WHERE ($1::integer IS NULL OR country_id = $1) AND deleted_at IS NULL
+      ORDER BY description
+      "#,
+      country_id
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn create_city(
+    &self,
+    description: String,
+    country_id: i32,
+    actor: &Actor,
+  ) -> sqlx::Result<City> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let created = sqlx::query_as!(
+      City,
+      r#"
+      INSERT INTO city (description, country_id)
+      VALUES ($1, $2)
+      returning *
+      "#,
+      description,
+      country_id
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(created)
+  }
+
+  async fn update_city(
+    &self,
+    id: i32,
+    description: String,
+    country_id: i32,
+    actor: &Actor,
+  ) -> sqlx::Result<City> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let updated = sqlx::query_as!(
+      City,
+      r#"
+      UPDATE city SET description = $2, country_id = $3
+      WHERE id = $1 AND deleted_at IS NULL
+      returning *
+      "#,
+      id,
+      description,
+      country_id
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(updated)
+  }
+
+  /// Returns the number of deleted cities, it fails if the city still has
+  /// attractions. The city is deleted softly, it can be restored.
+  async fn delete_city(&self, id: i32, actor: &Actor) -> sqlx::Result<u64> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let deleted = sqlx::query!(
+      r#"
+      UPDATE city SET deleted_at = now()
+      WHERE id = $1 AND deleted_at IS NULL
+      "#,
+      id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(deleted.rows_affected())
+  }
+
+  /// Returns the holidays of the country, of the year if there's one.
+  async fn list_holidays(
+    &self,
+    iso_code: String,
+    year: Option<i32>,
+  ) -> sqlx::Result<Vec<Holiday>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Holiday,
+      r#"
+      SELECT h.* FROM holiday h
+      INNER JOIN country c ON h.country_id = c.id
+      WHERE c.iso_code = $1 AND c.deleted_at IS NULL
+        AND ($2::integer IS NULL OR extract(year FROM h.day) = $2)
+      ORDER BY h.day
+      "#,
+      iso_code,
+      year
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// It fails with RowNotFound if the country doesn't exist.
+  async fn create_holiday(
+    &self,
+    iso_code: String,
+    day: NaiveDate,
+    description: String,
+    actor: &Actor,
+  ) -> sqlx::Result<Holiday> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let created = sqlx::query_as!(
+      Holiday,
+      r#"
+      INSERT INTO holiday (country_id, day, description)
+      SELECT id, $2, $3 FROM country
+      WHERE iso_code = $1 AND deleted_at IS NULL
+      returning *
+      "#,
+      iso_code,
+      day,
+      description
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(created)
+  }
+
+  async fn delete_holiday(
+    &self,
+    iso_code: String,
+    day: NaiveDate,
+    actor: &Actor,
+  ) -> sqlx::Result<u64> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let deleted = sqlx::query!(
+      r#"
+      DELETE FROM holiday h USING country c
+      WHERE h.country_id = c.id AND c.iso_code = $1 AND c.deleted_at IS NULL
+      AND h.day = $2
+      "#,
+      iso_code,
+      day
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(deleted.rows_affected())
+  }
+
+  async fn list_tags(&self) -> sqlx::Result<Vec<Tag>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Tag,
+      r#"
+      SELECT * FROM tag
+      WHERE deleted_at IS NULL
+      ORDER BY code
+      "#
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn create_tag(
+    &self,
+    code: String,
+    description: String,
+    actor: &Actor,
+  ) -> sqlx::Result<Tag> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let created = sqlx::query_as!(
+      Tag,
+      r#"
+      INSERT INTO tag (code, description)
+      VALUES ($1, $2)
+      returning *
+      "#,
+      code,
+      description
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(created)
+  }
+
+  async fn update_tag(
+    &self,
+    id: i32,
+    code: String,
+    description: String,
+    actor: &Actor,
+  ) -> sqlx::Result<Tag> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let updated = sqlx::query_as!(
+      Tag,
+      r#"
+      UPDATE tag SET code = $2, description = $3
+      WHERE id = $1 AND deleted_at IS NULL
+      returning *
+      "#,
+      id,
+      code,
+      description
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(updated)
+  }
+
+  /// Returns the number of deleted tags, the attractions lose them until the
+  /// tag is restored.
+  async fn delete_tag(&self, id: i32, actor: &Actor) -> sqlx::Result<u64> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let deleted = sqlx::query!(
+      r#"
+      UPDATE tag SET deleted_at = now()
+      WHERE id = $1 AND deleted_at IS NULL
+      "#,
+      id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(deleted.rows_affected())
+  }
+}
@@ -0,0 +1,163 @@
+use crate::model::{
+  attraction::{RegionInfo, RegionRatingBucket},
+  attraction_repository::AttractionRepository,
+  granularity::Granularity,
+  ranking::{self, RankedAttraction, RankingSettings},
+  region_repository::RegionRepository,
+  region_stats::{self, Geography, Improvement, Region, TypeCount},
+};
+use async_trait::async_trait;
+use bigdecimal::ToPrimitive;
+use chrono::{NaiveDateTime, Utc};
+use std::collections::HashSet;
+
+/// The minimum number of ratings in both periods to consider an attraction
+/// for the most improved.
+const MIN_IMPROVEMENT_COUNT: i64 = 5;
+
+/// The statistics of the attractions of a city or a country.
+#[derive(Debug, Clone)]
+pub struct RegionStats {
+  pub region: Region,
+  pub info: RegionInfo,
+  pub attraction_count: usize,
+  pub attractions_by_type: Vec<TypeCount>,
+  /// The accepted ratings of all the attractions.
+  pub rating_count: i64,
+  /// The average of all the accepted ratings, None without ratings.
+  pub average: Option<f64>,
+  pub over_time: Vec<RegionRatingBucket>,
+  pub top_rated: Vec<RankedAttraction>,
+  pub most_improved: Vec<Improvement>,
+  /// The days of each period compared for the most improved.
+  pub improvement_days: i32,
+  pub geography: Option<Geography>,
+}
+
+#[async_trait]
+pub trait RegionController: Send + Sync + 'static {
+  async fn stats(
+    &self,
+    region: Region,
+    granularity: Granularity,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+    top: usize,
+    improvement_days: i32,
+  ) -> Option<RegionStats>;
+}
+
+#[derive(Clone)]
+pub struct RegionControllerImpl<AttractionRepo, RegionRepo> {
+  attraction_repository: AttractionRepo,
+  region_repository: RegionRepo,
+}
+
+impl<AttractionRepo, RegionRepo>
+  RegionControllerImpl<AttractionRepo, RegionRepo>
+where
+  AttractionRepo: AttractionRepository,
+  RegionRepo: RegionRepository,
+{
+  pub fn new(
+    attraction_repository: AttractionRepo,
+    region_repository: RegionRepo,
+  ) -> Self {
+    RegionControllerImpl {
+      attraction_repository,
+      region_repository,
+    }
+  }
+}
+
+#[async_trait]
+impl<AttractionRepo, RegionRepo> RegionController
+  for RegionControllerImpl<AttractionRepo, RegionRepo>
+where
+  AttractionRepo: AttractionRepository + Send + Sync + 'static,
+  RegionRepo: RegionRepository + Send + Sync + 'static,
+{
+  /// The top rated are ranked by the Bayesian average among every
+  /// attraction, so the prior of each city is the same used by the ranking.
+  ///
+  /// # Return:
+  /// * Some with the statistics.
+  /// * None if the region doesn't exist or the statistics couldn't be read.
+  async fn stats(
+    &self,
+    region: Region,
+    granularity: Granularity,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+    top: usize,
+    improvement_days: i32,
+  ) -> Option<RegionStats> {
+    let info = self.region_repository.region_info(&region).await.ok()??;
+    let attractions =
+      self.region_repository.attractions_in(&region).await.ok()?;
+    let over_time = self
+      .region_repository
+      .ratings_over_time(&region, granularity, from, to)
+      .await
+      .ok()?;
+    let changes = self
+      .region_repository
+      .rating_changes(&region, improvement_days)
+      .await
+      .ok()?;
+
+    let settings = RankingSettings::default();
+    let summaries = self
+      .attraction_repository
+      .rating_summaries(None, Utc::now().naive_utc(), settings.half_life_days)
+      .await
+      .ok()?;
+    let ids = attractions
+      .iter()
+      .map(|an_attraction| an_attraction.attraction_id)
+      .collect::<HashSet<i32>>();
+    let in_region = summaries
+      .iter()
+      .filter(|a_summary| ids.contains(&a_summary.attraction_id))
+      .collect::<Vec<_>>();
+    let rating_count = in_region
+      .iter()
+      .map(|a_summary| a_summary.rating_count)
+      .sum();
+    let rate_sum = in_region
+      .iter()
+      .filter_map(|a_summary| a_summary.rate_sum.to_f64())
+      .sum::<f64>();
+
+    let mut top_rated =
+      ranking::rank(&summaries, region.city_id(), None, &settings)
+        .into_iter()
+        .filter(|a_ranked| {
+          a_ranked.rating_count > 0 && ids.contains(&a_ranked.attraction_id)
+        })
+        .take(top)
+        .collect::<Vec<RankedAttraction>>();
+    top_rated
+      .iter_mut()
+      .enumerate()
+      .for_each(|(index, a_ranked)| a_ranked.rank = index + 1);
+
+    Some(RegionStats {
+      attraction_count: attractions.len(),
+      attractions_by_type: region_stats::count_by_type(&attractions),
+      rating_count,
+      average: (rating_count > 0).then(|| rate_sum / rating_count as f64),
+      over_time,
+      top_rated,
+      most_improved: region_stats::most_improved(
+        changes,
+        MIN_IMPROVEMENT_COUNT,
+        top,
+      ),
+      improvement_days,
+      geography: region_stats::geography_of(&attractions),
+      region,
+      info,
+    })
+  }
+}
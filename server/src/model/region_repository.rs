@@ -0,0 +1,236 @@
+use crate::{
+  db::database::DbConnection,
+  model::{
+    attraction::{
+      AttractionRatingChange, RegionAttraction, RegionInfo, RegionRatingBucket,
+    },
+    granularity::Granularity,
+    region_stats::Region,
+  },
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+
+#[async_trait]
+pub trait RegionRepository {
+  async fn region_info(
+    &self,
+    region: &Region,
+  ) -> sqlx::Result<Option<RegionInfo>>;
+  async fn attractions_in(
+    &self,
+    region: &Region,
+  ) -> sqlx::Result<Vec<RegionAttraction>>;
+  async fn ratings_over_time(
+    &self,
+    region: &Region,
+    granularity: Granularity,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+  ) -> sqlx::Result<Vec<RegionRatingBucket>>;
+  async fn rating_changes(
+    &self,
+    region: &Region,
+    days: i32,
+  ) -> sqlx::Result<Vec<AttractionRatingChange>>;
+}
+
+#[derive(Clone, Default)]
+pub struct DummyRegionRepo;
+
+#[async_trait]
+impl RegionRepository for DummyRegionRepo {
+  async fn region_info(&self, _: &Region) -> sqlx::Result<Option<RegionInfo>> {
+    todo!()
+  }
+
+  async fn attractions_in(
+    &self,
+    _: &Region,
+  ) -> sqlx::Result<Vec<RegionAttraction>> {
+    todo!()
+  }
+
+  async fn ratings_over_time(
+    &self,
+    _: &Region,
+    _: Granularity,
+    _: Option<NaiveDateTime>,
+    _: Option<NaiveDateTime>,
+  ) -> sqlx::Result<Vec<RegionRatingBucket>> {
+    todo!()
+  }
+
+  async fn rating_changes(
+    &self,
+    _: &Region,
+    _: i32,
+  ) -> sqlx::Result<Vec<AttractionRatingChange>> {
+    todo!()
+  }
+}
+
+#[derive(Clone)]
+pub struct PgRegionRepository {
+  connection: DbConnection,
+}
+
+impl PgRegionRepository {
+  pub fn new(connection: DbConnection) -> Self {
+    PgRegionRepository {
+      connection,
+    }
+  }
+}
+
+#[async_trait]
+impl RegionRepository for PgRegionRepository {
+  /// Returns the name of the city or the country, and the country.
+  async fn region_info(
+    &self,
+    region: &Region,
+  ) -> sqlx::Result<Option<RegionInfo>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      RegionInfo,
+      r#"
+      SELECT
+      CASE WHEN $1::integer IS NULL THEN co.description
+      ELSE MAX(c.description) END as "name!",
+      co.iso_code, co.description as country,
+      COUNT(c.id) as "city_count!"
+      FROM country co
+      INNER JOIN city c ON c.country_id = co.id
+      WHERE ($1::integer IS NULL OR c.id = $1)
+      AND ($2::varchar IS NULL OR co.iso_code = $2)
+      AND co.deleted_at IS NULL AND c.deleted_at IS NULL
+      GROUP BY co.id
+      "#,
+      region.city_id(),
+      region.iso_code()
+    )
+    .fetch_optional(conn)
+    .await
+  }
+
+  async fn attractions_in(
+    &self,
+    region: &Region,
+  ) -> sqlx::Result<Vec<RegionAttraction>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      RegionAttraction,
+      r#"
+      SELECT a.id as attraction_id, a.latitude, a.longitude,
+      at.id as attraction_type_id, at.code as attraction_type_code,
+      at.description as attraction_type
+      FROM attraction a
+      INNER JOIN attraction_type at ON at.id = a.attraction_type_id
+      INNER JOIN city c ON c.id = a.city_id
+      INNER JOIN country co ON co.id = c.country_id
+      WHERE ($1::integer IS NULL OR c.id = $1)
+      AND ($2::varchar IS NULL OR co.iso_code = $2)
+      AND a.deleted_at IS NULL
+      ORDER BY a.id
+      "#,
+      region.city_id(),
+      region.iso_code()
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// Returns the volume and the average of the ratings of all the
+  /// attractions of the region for every bucket in the [from, to) period.
+  async fn ratings_over_time(
+    &self,
+    region: &Region,
+    granularity: Granularity,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+  ) -> sqlx::Result<Vec<RegionRatingBucket>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      RegionRatingBucket,
+      r#"
+      SELECT ara.at, SUM(ara.rating_count)::bigint as "rating_count!",
+      SUM(ara.average * ara.rating_count) / SUM(ara.rating_count)
+      as "average!"
+      FROM attraction_rating_aggregate ara
+      INNER JOIN attraction a ON a.id = ara.attraction_id
+      INNER JOIN city c ON c.id = a.city_id
+      INNER JOIN country co ON co.id = c.country_id
+      WHERE ($1::integer IS NULL OR c.id = $1)
+      AND ($2::varchar IS NULL OR co.iso_code = $2)
+      AND a.deleted_at IS NULL
+      AND ara.granularity = $3
+      AND ($4::timestamp IS NULL OR ara.at >= $4)
+      AND ($5::timestamp IS NULL OR ara.at < $5)
+      GROUP BY ara.at
+      HAVING SUM(ara.rating_count) > 0
+      ORDER BY ara.at asc
+      "#,
+      region.city_id(),
+      region.iso_code(),
+      granularity.as_str(),
+      from,
+      to
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// Returns the average rating of every attraction of the region in the
+  /// last days and in the days before them, from the daily aggregates. The
+  /// last days end in the latest daily aggregate of the region.
+  async fn rating_changes(
+    &self,
+    region: &Region,
+    days: i32,
+  ) -> sqlx::Result<Vec<AttractionRatingChange>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      AttractionRatingChange,
+      r#"
+      WITH region_attraction AS (
+        SELECT a.id, a.description
+        FROM attraction a
+        INNER JOIN city c ON c.id = a.city_id
+        INNER JOIN country co ON co.id = c.country_id
+        WHERE ($1::integer IS NULL OR c.id = $1)
+        AND ($2::varchar IS NULL OR co.iso_code = $2)
+        AND a.deleted_at IS NULL
+      ), daily AS (
+        SELECT ara.attraction_id, ara.at, ara.average, ara.rating_count
+        FROM attraction_rating_aggregate ara
+        INNER JOIN region_attraction ra ON ra.id = ara.attraction_id
+        WHERE ara.granularity = 'DAY'
+      ), period AS (
+        SELECT MAX(at) + INTERVAL '1 day' - MAKE_INTERVAL(days => $3) as split,
+        MAX(at) + INTERVAL '1 day' - MAKE_INTERVAL(days => 2 * $3) as start
+        FROM daily
+      )
+      SELECT ra.id as "attraction_id!", ra.description as "description!",
+      COALESCE(SUM(d.rating_count) FILTER (WHERE d.at < p.split), 0)::bigint
+      as "previous_count!",
+      SUM(d.average * d.rating_count) FILTER (WHERE d.at < p.split)
+      / NULLIF(SUM(d.rating_count) FILTER (WHERE d.at < p.split), 0)
+      as previous_average,
+      COALESCE(SUM(d.rating_count) FILTER (WHERE d.at >= p.split), 0)::bigint
+      as "recent_count!",
+      SUM(d.average * d.rating_count) FILTER (WHERE d.at >= p.split)
+      / NULLIF(SUM(d.rating_count) FILTER (WHERE d.at >= p.split), 0)
+      as recent_average
+      FROM region_attraction ra
+      CROSS JOIN period p
+      LEFT JOIN daily d ON d.attraction_id = ra.id AND d.at >= p.start
+      GROUP BY ra.id, ra.description
+      "#,
+      region.city_id(),
+      region.iso_code(),
+      days
+    )
+    .fetch_all(conn)
+    .await
+  }
+}
@@ -0,0 +1,214 @@
+use crate::model::{
+  attraction::{AttractionRatingChange, RegionAttraction},
+  rating_rules::location_of,
+};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use std::{cmp::Reverse, collections::BTreeMap};
+
+/// The area whose attractions are summarized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Region {
+  City(i32),
+  /// A country by its ISO code.
+  Country(String),
+}
+
+impl Region {
+  pub fn city_id(&self) -> Option<i32> {
+    match self {
+      Region::City(id) => Some(*id),
+      Region::Country(_) => None,
+    }
+  }
+
+  pub fn iso_code(&self) -> Option<String> {
+    match self {
+      Region::City(_) => None,
+      Region::Country(iso_code) => Some(iso_code.to_uppercase()),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct TypeCount {
+  pub attraction_type_id: i32,
+  pub code: String,
+  pub description: String,
+  pub count: usize,
+}
+
+/// The number of attractions of every type, the most common first.
+pub fn count_by_type(attractions: &[RegionAttraction]) -> Vec<TypeCount> {
+  let mut counts: BTreeMap<i32, TypeCount> = BTreeMap::new();
+  for an_attraction in attractions {
+    counts
+      .entry(an_attraction.attraction_type_id)
+      .or_insert_with(|| TypeCount {
+        attraction_type_id: an_attraction.attraction_type_id,
+        code: an_attraction.attraction_type_code.clone(),
+        description: an_attraction.attraction_type.clone(),
+        count: 0,
+      })
+      .count += 1;
+  }
+  let mut counts = counts.into_values().collect::<Vec<TypeCount>>();
+  counts.sort_by_key(|a_count| Reverse(a_count.count));
+  counts
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Geography {
+  pub centroid_latitude: f64,
+  pub centroid_longitude: f64,
+  pub min_latitude: f64,
+  pub min_longitude: f64,
+  pub max_latitude: f64,
+  pub max_longitude: f64,
+  /// The attractions with valid coordinates.
+  pub located_attractions: usize,
+}
+
+/// The geographic centroid and the bounding box of the attractions.
+///
+/// The centroid is the mean of the positions over the sphere, so it is right
+/// even when the attractions are at both sides of the antimeridian. The
+/// bounding box doesn't wrap around it.
+///
+/// # Return:
+/// * Some with the geography.
+/// * None if no attraction has valid coordinates.
+pub fn geography_of(attractions: &[RegionAttraction]) -> Option<Geography> {
+  let coordinates = attractions
+    .iter()
+    .filter_map(|an_attraction| {
+      location_of(&an_attraction.latitude, &an_attraction.longitude)
+    })
+    .map(|a_location| (a_location.latitude(), a_location.longitude()))
+    .collect::<Vec<(f64, f64)>>();
+  if coordinates.is_empty() {
+    return None;
+  }
+
+  let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+  for (latitude, longitude) in coordinates.iter() {
+    let (latitude, longitude) = (latitude.to_radians(), longitude.to_radians());
+    x += latitude.cos() * longitude.cos();
+    y += latitude.cos() * longitude.sin();
+    z += latitude.sin();
+  }
+  let n = coordinates.len() as f64;
+  let (x, y, z) = (x / n, y / n, z / n);
+  let fold =
+    |pick: fn(&(f64, f64)) -> f64, init: f64, op: fn(f64, f64) -> f64| {
+      coordinates.iter().map(pick).fold(init, op)
+    };
+
+  Some(Geography {
+    centroid_latitude: z.atan2((x * x + y * y).sqrt()).to_degrees(),
+    centroid_longitude: y.atan2(x).to_degrees(),
+    min_latitude: fold(|c| c.0, f64::INFINITY, f64::min),
+    min_longitude: fold(|c| c.1, f64::INFINITY, f64::min),
+    max_latitude: fold(|c| c.0, f64::NEG_INFINITY, f64::max),
+    max_longitude: fold(|c| c.1, f64::NEG_INFINITY, f64::max),
+    located_attractions: coordinates.len(),
+  })
+}
+
+#[derive(Debug, Clone)]
+pub struct Improvement {
+  pub change: AttractionRatingChange,
+  /// The recent average minus the previous one.
+  pub difference: f64,
+}
+
+/// The attractions whose average rating grew the most, considering only the
+/// ones with at least min_count ratings in both periods.
+pub fn most_improved(
+  changes: Vec<AttractionRatingChange>,
+  min_count: i64,
+  top: usize,
+) -> Vec<Improvement> {
+  let average = |value: &Option<BigDecimal>| value.as_ref()?.to_f64();
+  let mut improvements = changes
+    .into_iter()
+    .filter(|a_change| {
+      a_change.previous_count >= min_count && a_change.recent_count >= min_count
+    })
+    .filter_map(|a_change| {
+      let difference = average(&a_change.recent_average)?
+        - average(&a_change.previous_average)?;
+      Some(Improvement {
+        change: a_change,
+        difference,
+      })
+    })
+    .filter(|an_improvement| an_improvement.difference > 0.0)
+    .collect::<Vec<Improvement>>();
+  improvements
+    .sort_by(|one, other| other.difference.total_cmp(&one.difference));
+  improvements.truncate(top);
+  improvements
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn attraction(latitude: &str, longitude: &str) -> RegionAttraction {
+    RegionAttraction {
+      attraction_id: 1,
+      latitude: Some(latitude.to_string()),
+      longitude: Some(longitude.to_string()),
+      attraction_type_id: 1,
+      attraction_type_code: String::from("MUS"),
+      attraction_type: String::from("Museum"),
+    }
+  }
+
+  #[test]
+  fn centroid_of_the_attractions() {
+    let geography =
+      geography_of(&[attraction("10", "20"), attraction("-10", "40")]).unwrap();
+    assert!(geography.centroid_latitude.abs() < 1e-9);
+    assert!((geography.centroid_longitude - 30.0).abs() < 0.5);
+    assert_eq!(geography.min_latitude, -10.0);
+    assert_eq!(geography.max_longitude, 40.0);
+
+    let across_the_antimeridian =
+      geography_of(&[attraction("0", "179"), attraction("0", "-179")]).unwrap();
+    assert!(
+      (across_the_antimeridian.centroid_longitude.abs() - 180.0).abs() < 1e-6
+    );
+    assert!(geography_of(&[attraction("north", "")]).is_none());
+  }
+
+  #[test]
+  fn most_improved_needs_ratings_in_both_periods() {
+    let change = |id: i32, previous: &str, recent: &str, count: i64| {
+      AttractionRatingChange {
+        attraction_id: id,
+        description: format!("Attraction {id}"),
+        previous_count: count,
+        previous_average: previous.parse().ok(),
+        recent_count: count,
+        recent_average: recent.parse().ok(),
+      }
+    };
+    let improvements = most_improved(
+      vec![
+        change(1, "0.5", "0.6", 10),
+        change(2, "0.2", "0.8", 1),
+        change(3, "0.4", "0.7", 10),
+        change(4, "0.7", "0.6", 10),
+        change(5, "", "0.9", 10),
+      ],
+      5,
+      10,
+    );
+    let ids = improvements
+      .iter()
+      .map(|an_improvement| an_improvement.change.attraction_id)
+      .collect::<Vec<i32>>();
+    assert_eq!(ids, vec![3, 1]);
+  }
+}
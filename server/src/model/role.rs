@@ -0,0 +1,102 @@
+use serde::Deserialize;
+use std::{fmt, str::FromStr};
+
+/// The role of a user. Every role has the permissions of the previous ones.
+#[derive(
+  Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize,
+)]
+#[serde(try_from = "String")]
+pub enum Role {
+  /// Read only access, and rating the attractions.
+  #[default]
+  Viewer,
+  /// Edit the attractions and the reference data, and review the ratings.
+  Editor,
+  /// Calculate the similarities, delete and manage the roles of the users.
+  Admin,
+}
+
+impl Role {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Role::Viewer => "VIEWER",
+      Role::Editor => "EDITOR",
+      Role::Admin => "ADMIN",
+    }
+  }
+
+  /// True if the role has the permissions of the required one.
+  pub fn grants(&self, required: Role) -> bool {
+    *self >= required
+  }
+}
+
+impl fmt::Display for Role {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+impl FromStr for Role {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_uppercase().as_str() {
+      "VIEWER" => Ok(Role::Viewer),
+      "EDITOR" => Ok(Role::Editor),
+      "ADMIN" => Ok(Role::Admin),
+      _ => Err(format!("Unknown role: {}", s)),
+    }
+  }
+}
+
+impl TryFrom<String> for Role {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn every_role_has_the_permissions_of_the_previous_ones() {
+    assert!(Role::Admin.grants(Role::Editor));
+    assert!(Role::Admin.grants(Role::Viewer));
+    assert!(Role::Editor.grants(Role::Viewer));
+    assert!(Role::Editor.grants(Role::Editor));
+  }
+
+  #[test]
+  fn no_role_has_the_permissions_of_the_next_ones() {
+    assert!(!Role::Editor.grants(Role::Admin));
+    assert!(!Role::Viewer.grants(Role::Editor));
+    assert!(!Role::Viewer.grants(Role::Admin));
+  }
+
+  #[test]
+  fn the_users_are_viewers_by_default() {
+    assert_eq!(Role::default(), Role::Viewer);
+  }
+
+  #[test]
+  fn the_roles_are_parsed_in_any_case() {
+    assert_eq!("editor".parse::<Role>(), Ok(Role::Editor));
+    assert_eq!(" Admin ".parse::<Role>(), Ok(Role::Admin));
+    for a_role in [Role::Viewer, Role::Editor, Role::Admin] {
+      assert_eq!(a_role.to_string().parse::<Role>(), Ok(a_role));
+    }
+  }
+
+  #[test]
+  fn the_unknown_roles_are_rejected() {
+    assert_eq!(
+      "root".parse::<Role>(),
+      Err(String::from("Unknown role: root"))
+    );
+    assert!("".parse::<Role>().is_err());
+  }
+}
@@ -0,0 +1,235 @@
+use crate::model::{
+  aggregation_settings::from_env_var, rating_statistics::MIN_RATE,
+};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SearchSettings {
+  /// The least similarity of the words of the query to the description of
+  /// an attraction to match it, from 0 to 1.
+  pub min_similarity: f64,
+  /// The score added when the last word of the query starts a word of the
+  /// description, the autocomplete of what is being typed.
+  pub prefix_boost: f64,
+  /// The most score added by the ratings of the attraction, the most rated
+  /// and best rated attraction among the matches gets all of it.
+  pub popularity_boost: f64,
+  /// The matches ranked, the best ones by their description.
+  pub candidates: usize,
+  pub max_limit: usize,
+}
+
+impl Default for SearchSettings {
+  fn default() -> Self {
+    SearchSettings {
+      min_similarity: 0.3,
+      prefix_boost: 0.3,
+      popularity_boost: 0.2,
+      candidates: 200,
+      max_limit: 50,
+    }
+  }
+}
+
+impl SearchSettings {
+  /// Read the settings from the environment, falling back into the defaults
+  /// for the missing or invalid values.
+  /// * SEARCH_MIN_SIMILARITY, SEARCH_PREFIX_BOOST, SEARCH_POPULARITY_BOOST,
+  ///   SEARCH_CANDIDATES and SEARCH_MAX_LIMIT: numbers.
+  pub fn from_env() -> Self {
+    let defaults = SearchSettings::default();
+    SearchSettings {
+      min_similarity: from_env_var("SEARCH_MIN_SIMILARITY")
+        .unwrap_or(defaults.min_similarity),
+      prefix_boost: from_env_var("SEARCH_PREFIX_BOOST")
+        .unwrap_or(defaults.prefix_boost),
+      popularity_boost: from_env_var("SEARCH_POPULARITY_BOOST")
+        .unwrap_or(defaults.popularity_boost),
+      candidates: from_env_var("SEARCH_CANDIDATES")
+        .unwrap_or(defaults.candidates),
+      max_limit: from_env_var("SEARCH_MAX_LIMIT").unwrap_or(defaults.max_limit),
+    }
+  }
+}
+
+/// An attraction whose description matches the query, with how well it does
+/// and its accepted ratings.
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct SearchCandidate {
+  pub attraction_id: i32,
+  pub description: String,
+  pub city_id: i32,
+  pub attraction_type_id: i32,
+  /// The mean of the similarity of every word of the query to the closest
+  /// words of the description.
+  pub similarity: f64,
+  /// Whether the last word of the query starts a word of the description.
+  pub prefix_match: bool,
+  pub rating_count: i64,
+  pub average_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+  pub candidate: SearchCandidate,
+  pub score: f64,
+}
+
+/// The words of the query, without punctuation.
+pub fn words_of(query: &str) -> Vec<String> {
+  query
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|a_word| !a_word.is_empty())
+    .map(str::to_lowercase)
+    .collect()
+}
+
+/// Rank the candidates by the similarity of their description, boosted by
+/// the prefix match and by their popularity: the log of their ratings
+/// relative to the most rated candidate, times their average rate.
+pub fn rank(
+  candidates: Vec<SearchCandidate>,
+  settings: &SearchSettings,
+  limit: usize,
+) -> Vec<SearchHit> {
+  let max_count = candidates
+    .iter()
+    .map(|a_candidate| a_candidate.rating_count)
+    .max()
+    .unwrap_or_default();
+  let mut hits = candidates
+    .into_iter()
+    .filter(|a_candidate| {
+      a_candidate.prefix_match
+        || a_candidate.similarity >= settings.min_similarity
+    })
+    .map(|a_candidate| {
+      let popularity = if max_count > 0 {
+        (a_candidate.rating_count as f64).ln_1p() / (max_count as f64).ln_1p()
+          * a_candidate.average_rate.unwrap_or(MIN_RATE)
+      } else {
+        0.0
+      };
+      let prefix = if a_candidate.prefix_match {
+        settings.prefix_boost
+      } else {
+        0.0
+      };
+      SearchHit {
+        score: a_candidate.similarity
+          + prefix
+          + settings.popularity_boost * popularity,
+        candidate: a_candidate,
+      }
+    })
+    .collect::<Vec<SearchHit>>();
+  hits.sort_by(|one, other| {
+    other.score.total_cmp(&one.score).then_with(|| {
+      one
+        .candidate
+        .attraction_id
+        .cmp(&other.candidate.attraction_id)
+    })
+  });
+  hits.truncate(limit);
+  hits
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn candidate(
+    attraction_id: i32,
+    similarity: f64,
+    prefix_match: bool,
+    rating_count: i64,
+    average_rate: Option<f64>,
+  ) -> SearchCandidate {
+    SearchCandidate {
+      attraction_id,
+      description: format!("Attraction {attraction_id}"),
+      city_id: 1,
+      attraction_type_id: 1,
+      similarity,
+      prefix_match,
+      rating_count,
+      average_rate,
+    }
+  }
+
+  fn ids_of(hits: &[SearchHit]) -> Vec<i32> {
+    hits
+      .iter()
+      .map(|a_hit| a_hit.candidate.attraction_id)
+      .collect()
+  }
+
+  #[test]
+  fn the_words_of_the_query_are_lower_case_without_punctuation() {
+    assert_eq!(words_of(" Eifel, tower! "), vec!["eifel", "tower"]);
+    assert!(words_of(" ?! ").is_empty());
+  }
+
+  #[test]
+  fn the_candidates_too_different_from_the_query_are_left_out() {
+    let ranked = rank(
+      vec![
+        candidate(1, 0.3, false, 0, None),
+        candidate(2, 0.2, false, 100, Some(1.0)),
+      ],
+      &SearchSettings::default(),
+      10,
+    );
+    assert_eq!(ids_of(&ranked), vec![1]);
+  }
+
+  #[test]
+  fn the_candidates_being_typed_are_kept_and_boosted() {
+    let ranked = rank(
+      vec![
+        candidate(1, 0.4, false, 0, None),
+        candidate(2, 0.2, true, 0, None),
+      ],
+      &SearchSettings::default(),
+      10,
+    );
+    assert_eq!(ids_of(&ranked), vec![2, 1]);
+    assert!((ranked[0].score - 0.5).abs() < 1e-9);
+  }
+
+  #[test]
+  fn the_popular_candidates_go_first() {
+    let ranked = rank(
+      vec![
+        // Close enough, but nobody rated it.
+        candidate(1, 0.5, false, 0, None),
+        // As close and popular.
+        candidate(2, 0.5, false, 100, Some(0.9)),
+        // As close, rated by fewer users.
+        candidate(3, 0.5, false, 10, Some(0.9)),
+      ],
+      &SearchSettings::default(),
+      10,
+    );
+    assert_eq!(ids_of(&ranked), vec![2, 3, 1]);
+    assert!((ranked[0].score - (0.5 + 0.2 * 0.9)).abs() < 1e-9);
+    assert_eq!(ranked[2].score, 0.5);
+  }
+
+  #[test]
+  fn the_ties_go_by_attraction_and_the_limit_keeps_the_best() {
+    let candidates = vec![
+      candidate(3, 0.5, false, 0, None),
+      candidate(1, 0.5, false, 0, None),
+      candidate(2, 0.9, false, 0, None),
+    ];
+    let settings = SearchSettings::default();
+    assert_eq!(
+      ids_of(&rank(candidates.clone(), &settings, 10)),
+      vec![2, 1, 3]
+    );
+    assert_eq!(ids_of(&rank(candidates.clone(), &settings, 2)), vec![2, 1]);
+    assert!(rank(candidates, &settings, 0).is_empty());
+  }
+}
@@ -0,0 +1,114 @@
+use crate::model::{
+  attraction_repository::AttractionRepository,
+  search::{self, SearchHit, SearchSettings},
+};
+use async_trait::async_trait;
+
+/// What the user is looking for.
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+  pub text: String,
+  pub city_id: Option<i32>,
+  pub attraction_type_id: Option<i32>,
+  pub limit: usize,
+}
+
+#[async_trait]
+pub trait SearchController: Send + Sync + 'static {
+  async fn search(&self, query: SearchQuery) -> Result<Vec<SearchHit>, String>;
+}
+
+#[derive(Clone)]
+pub struct SearchControllerImpl<AttractionRepo> {
+  attraction_repository: AttractionRepo,
+  settings: SearchSettings,
+}
+
+impl<AttractionRepo> SearchControllerImpl<AttractionRepo>
+where
+  AttractionRepo: AttractionRepository,
+{
+  pub fn new(
+    attraction_repository: AttractionRepo,
+    settings: SearchSettings,
+  ) -> Self {
+    SearchControllerImpl {
+      attraction_repository,
+      settings,
+    }
+  }
+}
+
+#[async_trait]
+impl<AttractionRepo> SearchController for SearchControllerImpl<AttractionRepo>
+where
+  AttractionRepo: AttractionRepository + Send + Sync + 'static,
+{
+  /// Search the attractions by their description. The search reads the
+  /// attractions as they are stored, so it's always in sync with them.
+  ///
+  /// # Return:
+  /// * Ok with the best matches first.
+  /// * Err if the query has no words or the limit is out of range.
+  async fn search(&self, query: SearchQuery) -> Result<Vec<SearchHit>, String> {
+    let words = search::words_of(&query.text);
+    if words.is_empty() {
+      return Err(String::from("The query must have at least one word"));
+    }
+    if !(1..=self.settings.max_limit).contains(&query.limit) {
+      return Err(format!(
+        "The limit must be between 1 and {}",
+        self.settings.max_limit
+      ));
+    }
+    let candidates = self
+      .attraction_repository
+      .search_candidates(
+        words,
+        query.city_id,
+        query.attraction_type_id,
+        self.settings.candidates as i64,
+      )
+      .await
+      .map_err(|e| e.to_string())?;
+    Ok(search::rank(candidates, &self.settings, query.limit))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::attraction_repository::DummyAttractionRepo;
+
+  /// The repository isn't reached, the queries are rejected before.
+  fn controller() -> SearchControllerImpl<DummyAttractionRepo> {
+    SearchControllerImpl::new(DummyAttractionRepo, SearchSettings::default())
+  }
+
+  fn query(text: &str, limit: usize) -> SearchQuery {
+    SearchQuery {
+      text: String::from(text),
+      city_id: None,
+      attraction_type_id: None,
+      limit,
+    }
+  }
+
+  #[tokio::test]
+  async fn a_query_without_words_is_rejected() {
+    assert_eq!(
+      controller().search(query(" ?! ", 10)).await,
+      Err(String::from("The query must have at least one word"))
+    );
+  }
+
+  #[tokio::test]
+  async fn a_limit_out_of_range_is_rejected() {
+    for limit in [0, 51] {
+      assert_eq!(
+        controller().search(query("eifel tower", limit)).await,
+        Err(String::from("The limit must be between 1 and 50"))
+      );
+    }
+  }
+}
@@ -1,20 +1,48 @@
 use super::{
-  attraction::AttractionRatingAggregate,
+  aggregation_settings::AggregationSettings,
+  attraction::{AttractionRatingAggregate, AttractionRatingAnomaly},
   attraction_repository::AttractionRepository,
 };
 use crate::model::{
   attraction_similarity::AttractionSimilarity,
+  forecast::{self, Forecast, ForecastSettings, Observation},
+  granularity::Granularity,
   similarity_generator::SimilarityCalculator,
   similarity_repository::SimilarityRepository,
+  time_series::{GapFill, TimeSeries, TimeSeriesOptions},
 };
 use async_trait::async_trait;
+use bigdecimal::ToPrimitive;
+use chrono::{Duration, NaiveDate, NaiveTime};
 
 #[async_trait]
 pub trait SimilarityController: Send + Sync + 'static {
   async fn list_rating_aggregate(
     &self,
     attraction_id: i32,
+    granularity: Granularity,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
   ) -> Option<Vec<AttractionRatingAggregate>>;
+  async fn rating_time_series(
+    &self,
+    attraction_id: i32,
+    granularity: Granularity,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    options: TimeSeriesOptions,
+  ) -> Result<TimeSeries, String>;
+  async fn forecast(
+    &self,
+    attraction_id: i32,
+    settings: ForecastSettings,
+  ) -> Result<Forecast, String>;
+  async fn list_anomalies(
+    &self,
+    attraction_id: Option<i32>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+  ) -> Option<Vec<AttractionRatingAnomaly>>;
   async fn calculate_similarity_between_attractions(
     &self,
   ) -> Result<(), String>;
@@ -36,6 +64,7 @@ where
   pub fn new(
     attraction_repo: AttractionRepo,
     similarity_repo: SimilarityRepo,
+    settings: AggregationSettings,
   ) -> Self {
     let att_repo_clone = attraction_repo.clone();
     let sim_repo_clone = similarity_repo.clone();
@@ -45,6 +74,7 @@ where
       attraction_similarity: AttractionSimilarity::new(
         attraction_repo,
         similarity_repo,
+        settings,
       ),
     }
   }
@@ -75,21 +105,133 @@ where
   AttractionRepo: AttractionRepository + Send + Sync + 'static + Clone,
   SimilarityRepo: SimilarityRepository + Send + Sync + 'static + Clone,
 {
+  /// The period is inclusive on both dates.
   async fn list_rating_aggregate(
     &self,
     attraction_id: i32,
+    granularity: Granularity,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
   ) -> Option<Vec<AttractionRatingAggregate>> {
-    match self.similarity_repo.list_aggregates(attraction_id).await {
-      Ok(aggregates) => Some(aggregates),
-      Err(_) => None,
-    }
+    let from = from.map(|a_date| a_date.and_time(NaiveTime::default()));
+    let to = to
+      .and_then(|a_date| a_date.succ_opt())
+      .map(|a_date| a_date.and_time(NaiveTime::default()));
+    self
+      .similarity_repo
+      .list_aggregates(attraction_id, granularity, from, to)
+      .await
+      .ok()
+  }
+
+  /// The series goes from the bucket of the from date to the bucket of the
+  /// to date.
+  async fn rating_time_series(
+    &self,
+    attraction_id: i32,
+    granularity: Granularity,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    options: TimeSeriesOptions,
+  ) -> Result<TimeSeries, String> {
+    let from = from.map(|a_date| {
+      granularity.truncate(a_date.and_time(NaiveTime::default()))
+    });
+    let to = to.map(|a_date| {
+      granularity.truncate(a_date.and_time(NaiveTime::default()))
+    });
+    let aggregates = self
+      .similarity_repo
+      .list_aggregates(
+        attraction_id,
+        granularity,
+        from,
+        to.map(|at| granularity.next(at)),
+      )
+      .await
+      .map_err(|e| e.to_string())?;
+    TimeSeries::build(
+      attraction_id,
+      granularity,
+      &aggregates,
+      from,
+      to,
+      options,
+    )
+  }
+
+  /// The model is fitted on the daily averages of the last history days that
+  /// end in the latest daily aggregate, the days without ratings repeat the
+  /// previous average.
+  async fn forecast(
+    &self,
+    attraction_id: i32,
+    settings: ForecastSettings,
+  ) -> Result<Forecast, String> {
+    let aggregates = self
+      .similarity_repo
+      .list_aggregates(attraction_id, Granularity::Day, None, None)
+      .await
+      .map_err(|e| e.to_string())?;
+    let last = aggregates
+      .last()
+      .map(|an_aggregate| an_aggregate.get_at())
+      .ok_or(format!("There are no daily aggregates of {attraction_id}"))?;
+    let since = last - Duration::days(settings.history_days.max(1) - 1);
+    let aggregates = aggregates
+      .into_iter()
+      .filter(|an_aggregate| an_aggregate.get_at() >= since)
+      .collect::<Vec<AttractionRatingAggregate>>();
+    let options = TimeSeriesOptions {
+      fill: GapFill::Previous,
+      moving_average_window: 1,
+      ..Default::default()
+    };
+    let series = TimeSeries::build(
+      attraction_id,
+      Granularity::Day,
+      &aggregates,
+      None,
+      None,
+      options,
+    )?;
+    let observations = series
+      .points
+      .iter()
+      .filter_map(|point| {
+        Some(Observation {
+          at: point.at,
+          value: point.average.as_ref()?.to_f64()?,
+          observed: !point.filled,
+        })
+      })
+      .collect::<Vec<Observation>>();
+    forecast::forecast(&observations, &settings)
+  }
+
+  /// The period is inclusive on both dates.
+  async fn list_anomalies(
+    &self,
+    attraction_id: Option<i32>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+  ) -> Option<Vec<AttractionRatingAnomaly>> {
+    let from = from.map(|a_date| a_date.and_time(NaiveTime::default()));
+    let to = to
+      .and_then(|a_date| a_date.succ_opt())
+      .map(|a_date| a_date.and_time(NaiveTime::default()));
+    self
+      .similarity_repo
+      .list_anomalies(attraction_id, from, to)
+      .await
+      .ok()
   }
 
   async fn calculate_similarity_between_attractions(
     &self,
   ) -> Result<(), String> {
     self.aggregate().await?;
-    let similarity_calculator = SimilarityCalculator::default();
+    let similarity_calculator = SimilarityCalculator;
     self
       .attraction_similarity
       .generate_similarity(similarity_calculator)
@@ -1,32 +1,24 @@
+use crate::model::rating_rules::location_of;
 use bigdecimal::{BigDecimal, FromPrimitive, ToPrimitive};
 use geoutils::{self, Distance, Location};
-use std::ops::Sub;
+use std::{collections::HashSet, ops::Sub};
 
 /// The information that the implementation of the trait needs to accomplish
 /// the task of calculate the similarity.
+#[allow(dead_code)]
 pub struct AttractionInfo {
   pub attraction_id: i32,
   pub attraction_type_id: i32,
   pub avg_rating: BigDecimal,
   pub latitude: Option<String>,
   pub longitude: Option<String>,
+  /// The codes of the tags of the attraction.
+  pub tags: Vec<String>,
 }
 
 impl AttractionInfo {
-  fn location(&self) -> Option<Location> {
-    if self.longitude.is_none() || self.longitude.is_none() {
-      return None;
-    }
-
-    let Ok(lat) = self.latitude.as_ref().unwrap().parse::<f64>() else {
-      return None;
-    };
-
-    let Ok(long) = self.longitude.as_ref().unwrap().parse::<f64>() else {
-      return None;
-    };
-
-    Some(Location::new(lat, long))
+  pub fn location(&self) -> Option<Location> {
+    location_of(&self.latitude, &self.longitude)
   }
 
   fn distance_from(
@@ -40,6 +32,29 @@ impl AttractionInfo {
     }
     location.unwrap().distance_to(&other_location.unwrap()).ok()
   }
+
+  /// The share of the tags of both attractions that they have in common,
+  /// None when any of them has no tags, there is nothing to compare.
+  pub fn tag_similarity(
+    &self,
+    other_attraction: &AttractionInfo,
+  ) -> Option<f64> {
+    if self.tags.is_empty() || other_attraction.tags.is_empty() {
+      return None;
+    }
+    let union = self
+      .tags
+      .iter()
+      .chain(other_attraction.tags.iter())
+      .collect::<HashSet<&String>>()
+      .len();
+    let common = self
+      .tags
+      .iter()
+      .filter(|a_tag| other_attraction.tags.contains(a_tag))
+      .count();
+    Some(common as f64 / union as f64)
+  }
 }
 
 /// An trait to define the similarity between every attraction.
@@ -52,7 +67,7 @@ pub trait Similarity {
   /// # Arguments:
   /// * one_attraction: all the information necessary about an attraction.
   /// * another_attraction: all the the information necessary about another
-  /// attraction.
+  ///   attraction.
   /// # Return:
   /// * The similarity between the two attractions.
   fn similarity_between(
@@ -66,11 +81,16 @@ pub trait Similarity {
 /// In the future with more data this must be replaced, but for this POC is
 /// good enough.
 /// The similarity is going to be calculated based on the type of the
-/// attraction, the distance between two attractions and the difference
-/// between their average_ratings.
+/// attraction, the distance between two attractions, the difference
+/// between their average_ratings and the tags they share. The tags only
+/// count when both attractions have them, and every pair is scaled by the
+/// same maximum, so a missing tag is never less similar than different tags.
 #[derive(Default, Clone)]
 pub struct SimilarityCalculator;
 
+/// The score of a pair of attractions where every component matches.
+const MAX_SIMILARITY: f64 = 40.0;
+
 impl Similarity for SimilarityCalculator {
   fn similarity_between(
     &self,
@@ -80,13 +100,13 @@ impl Similarity for SimilarityCalculator {
     let mut similarity: f64 = 0.0;
 
     // distance component
-    let distance_result = one_attraction.distance_from(&another_attraction);
+    let distance_result = one_attraction.distance_from(another_attraction);
     match distance_result {
       None => {},
       Some(distance) => match distance.meters().to_i128().unwrap() * 1000 {
         0..=5 => similarity += 10.0,
-        5..=10 => similarity += 5.0,
-        10..=20 => similarity += 2.0,
+        6..=10 => similarity += 5.0,
+        11..=20 => similarity += 2.0,
         _ => {},
       },
     }
@@ -107,8 +127,72 @@ impl Similarity for SimilarityCalculator {
       similarity += 10.0;
     }
 
-    similarity /= 30.0;
+    // tags component
+    if let Some(shared) = one_attraction.tag_similarity(another_attraction) {
+      similarity += 10.0 * shared;
+    }
+
+    BigDecimal::from_f64(similarity / MAX_SIMILARITY).unwrap()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
 
-    BigDecimal::from_f64(similarity).unwrap()
+  fn attraction(attraction_id: i32, tags: &[&str]) -> AttractionInfo {
+    AttractionInfo {
+      attraction_id,
+      attraction_type_id: 1,
+      avg_rating: BigDecimal::from(0),
+      latitude: None,
+      longitude: None,
+      tags: tags.iter().map(|a_tag| a_tag.to_string()).collect(),
+    }
+  }
+
+  #[test]
+  fn the_tag_similarity_is_the_share_of_the_tags_in_common() {
+    let museum = attraction(1, &["indoor", "free"]);
+    let park = attraction(2, &["free", "family-friendly"]);
+    assert_eq!(museum.tag_similarity(&park), Some(1.0 / 3.0));
+    assert_eq!(museum.tag_similarity(&museum), Some(1.0));
+    assert_eq!(
+      museum.tag_similarity(&attraction(3, &["outdoor"])),
+      Some(0.0)
+    );
+    assert_eq!(museum.tag_similarity(&attraction(3, &[])), None);
+    assert_eq!(attraction(3, &[]).tag_similarity(&attraction(4, &[])), None);
+  }
+
+  #[test]
+  fn the_attractions_that_share_tags_are_more_similar() {
+    let calculator = SimilarityCalculator;
+    let museum = attraction(1, &["indoor", "free"]);
+    let similarity_to = |tags: &[&str]| {
+      calculator
+        .similarity_between(&museum, &attraction(2, tags))
+        .to_f64()
+        .unwrap()
+    };
+    assert!(similarity_to(&["indoor", "free"]) > similarity_to(&["free"]));
+    assert!(similarity_to(&["free"]) > similarity_to(&["outdoor"]));
+  }
+
+  #[test]
+  fn the_attractions_without_tags_are_on_the_same_scale() {
+    let calculator = SimilarityCalculator;
+    let similarity = |one: &[&str], other: &[&str]| {
+      calculator
+        .similarity_between(&attraction(1, one), &attraction(2, other))
+        .to_f64()
+        .unwrap()
+    };
+    // The same type and rating, without locations.
+    let untagged = similarity(&[], &[]);
+    assert!((untagged - 20.0 / 40.0).abs() < 1e-9);
+    assert_eq!(untagged, similarity(&["indoor"], &[]));
+    assert_eq!(untagged, similarity(&["indoor"], &["outdoor"]));
+    assert!(similarity(&["indoor"], &["indoor"]) > untagged);
   }
 }
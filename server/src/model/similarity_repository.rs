@@ -1,29 +1,83 @@
 use crate::{
   db::database::DbConnection,
   model::{
-    attraction::{AttractionByDate, AttractionRatingAggregate},
+    attraction::{
+      AttractionByDate, AttractionRatingAggregate, AttractionRatingAnomaly,
+      AttractionRatingSketch,
+    },
     attraction_repository::EntityId,
     attraction_similarity::SimilarityBetweenAttraction,
+    granularity::Granularity,
     similarity_generator::AttractionInfo,
   },
 };
 use async_trait::async_trait;
+use chrono::NaiveDateTime;
 
 #[async_trait]
 pub trait SimilarityRepository {
   async fn list_aggregates(
     &self,
     attraction_id: i32,
+    granularity: Granularity,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
   ) -> sqlx::Result<Vec<AttractionRatingAggregate>>;
-  async fn group_aggregate_by_date(
+  async fn group_aggregate_by(
     &self,
     attraction_id: i32,
+    granularity: Granularity,
   ) -> sqlx::Result<Vec<AttractionByDate>>;
   async fn save_attraction_rating_aggregate(
     &self,
     att_rating_aggregate: AttractionRatingAggregate,
   ) -> sqlx::Result<EntityId>;
-  async fn get_info(&self, attraction_id: i32) -> sqlx::Result<AttractionInfo>;
+  async fn delete_aggregate(
+    &self,
+    attraction_id: i32,
+    granularity: Granularity,
+    at: NaiveDateTime,
+  ) -> sqlx::Result<()>;
+  async fn group_sketch_by_date(
+    &self,
+    attraction_id: i32,
+  ) -> sqlx::Result<Vec<AttractionByDate>>;
+  async fn get_sketch(
+    &self,
+    attraction_id: i32,
+    at: NaiveDateTime,
+  ) -> sqlx::Result<Option<AttractionRatingSketch>>;
+  async fn list_sketches(
+    &self,
+    attraction_id: i32,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+  ) -> sqlx::Result<Vec<AttractionRatingSketch>>;
+  async fn save_sketch(
+    &self,
+    sketch: AttractionRatingSketch,
+  ) -> sqlx::Result<EntityId>;
+  async fn delete_sketch(
+    &self,
+    attraction_id: i32,
+    at: NaiveDateTime,
+  ) -> sqlx::Result<()>;
+  async fn list_anomalies(
+    &self,
+    attraction_id: Option<i32>,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+  ) -> sqlx::Result<Vec<AttractionRatingAnomaly>>;
+  async fn replace_anomalies(
+    &self,
+    attraction_id: i32,
+    anomalies: Vec<AttractionRatingAnomaly>,
+  ) -> sqlx::Result<()>;
+  async fn get_info(
+    &self,
+    attraction_id: i32,
+    exclude_anomalies: bool,
+  ) -> sqlx::Result<Option<AttractionInfo>>;
   async fn save_similarity(
     &self,
     similarity: SimilarityBetweenAttraction,
@@ -38,13 +92,17 @@ impl SimilarityRepository for DummySimilarityRepo {
   async fn list_aggregates(
     &self,
     _: i32,
+    _: Granularity,
+    _: Option<NaiveDateTime>,
+    _: Option<NaiveDateTime>,
   ) -> sqlx::Result<Vec<AttractionRatingAggregate>> {
     todo!()
   }
 
-  async fn group_aggregate_by_date(
+  async fn group_aggregate_by(
     &self,
     _: i32,
+    _: Granularity,
   ) -> sqlx::Result<Vec<AttractionByDate>> {
     todo!()
   }
@@ -56,7 +114,72 @@ impl SimilarityRepository for DummySimilarityRepo {
     todo!()
   }
 
-  async fn get_info(&self, _: i32) -> sqlx::Result<AttractionInfo> {
+  async fn delete_aggregate(
+    &self,
+    _: i32,
+    _: Granularity,
+    _: NaiveDateTime,
+  ) -> sqlx::Result<()> {
+    todo!()
+  }
+
+  async fn group_sketch_by_date(
+    &self,
+    _: i32,
+  ) -> sqlx::Result<Vec<AttractionByDate>> {
+    todo!()
+  }
+
+  async fn get_sketch(
+    &self,
+    _: i32,
+    _: NaiveDateTime,
+  ) -> sqlx::Result<Option<AttractionRatingSketch>> {
+    todo!()
+  }
+
+  async fn list_sketches(
+    &self,
+    _: i32,
+    _: NaiveDateTime,
+    _: NaiveDateTime,
+  ) -> sqlx::Result<Vec<AttractionRatingSketch>> {
+    todo!()
+  }
+
+  async fn save_sketch(
+    &self,
+    _: AttractionRatingSketch,
+  ) -> sqlx::Result<EntityId> {
+    todo!()
+  }
+
+  async fn delete_sketch(&self, _: i32, _: NaiveDateTime) -> sqlx::Result<()> {
+    todo!()
+  }
+
+  async fn list_anomalies(
+    &self,
+    _: Option<i32>,
+    _: Option<NaiveDateTime>,
+    _: Option<NaiveDateTime>,
+  ) -> sqlx::Result<Vec<AttractionRatingAnomaly>> {
+    todo!()
+  }
+
+  async fn replace_anomalies(
+    &self,
+    _: i32,
+    _: Vec<AttractionRatingAnomaly>,
+  ) -> sqlx::Result<()> {
+    todo!()
+  }
+
+  async fn get_info(
+    &self,
+    _: i32,
+    _: bool,
+  ) -> sqlx::Result<Option<AttractionInfo>> {
     todo!()
   }
 
@@ -83,45 +206,60 @@ impl PgSimilarityRepository {
 
 #[async_trait]
 impl SimilarityRepository for PgSimilarityRepository {
+  /// Returns the aggregates of the granularity in the [from, to) period,
+  /// sorted by date. A missing bound leaves the period open on that side.
   async fn list_aggregates(
     &self,
     attraction_id: i32,
+    granularity: Granularity,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
   ) -> sqlx::Result<Vec<AttractionRatingAggregate>> {
     let conn = self.connection.get();
     sqlx::query_as!(
       AttractionRatingAggregate,
       r#"
       SELECT * FROM attraction_rating_aggregate
-      WHERE attraction_id = $1
+      WHERE attraction_id = $1 AND granularity = $2
+      AND ($3::timestamp IS NULL OR at >= $3)
+      AND ($4::timestamp IS NULL OR at < $4)
+      ORDER BY at asc
       "#,
-      attraction_id
+      attraction_id,
+      granularity.as_str(),
+      from,
+      to
     )
     .fetch_all(conn)
     .await
   }
 
-  async fn group_aggregate_by_date(
+  /// Returns the state of the ratings recorded when every aggregate of the
+  /// granularity was generated.
+  async fn group_aggregate_by(
     &self,
     attraction_id: i32,
+    granularity: Granularity,
   ) -> sqlx::Result<Vec<AttractionByDate>> {
     let conn = self.connection.get();
     let rows = sqlx::query_as!(
       AttractionByDate,
       r#"
-      SELECT attraction_id, DATE_TRUNC('day', at) as at
-      FROM attraction_rating_aggregate 
-      WHERE attraction_id = $1
-      GROUP BY attraction_id, DATE_TRUNC('day', at)
+      SELECT attraction_id, at as "at?",
+      rating_count::bigint as rating_count, watermark
+      FROM attraction_rating_aggregate
+      WHERE attraction_id = $1 AND granularity = $2
       "#,
-      attraction_id
+      attraction_id,
+      granularity.as_str()
     )
-    //.bind(attraction_id)
     .fetch_all(conn)
     .await?;
-    // let res = AttractionByDate::from_database(rows);
     Ok(rows)
   }
 
+  /// Inserts the aggregate, or replaces the existing one for the same
+  /// attraction, granularity and date.
   async fn save_attraction_rating_aggregate(
     &self,
     att_rating_aggregate: AttractionRatingAggregate,
@@ -131,32 +269,270 @@ impl SimilarityRepository for PgSimilarityRepository {
       EntityId,
       r#"
       INSERT INTO attraction_rating_aggregate
-      (attraction_id, at, average, ninety_five_percentile, ninety_nine_percentile)
-      VALUES ($1, $2, $3, $4, $5) returning id
+      (attraction_id, at, average, ninety_five_percentile, ninety_nine_percentile,
+      rating_count, min_rate, max_rate, median, standard_deviation, histogram,
+      granularity, watermark)
+      VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+      ON CONFLICT ON CONSTRAINT attraction_rating_aggregate_uk DO UPDATE SET
+      average = EXCLUDED.average,
+      ninety_five_percentile = EXCLUDED.ninety_five_percentile,
+      ninety_nine_percentile = EXCLUDED.ninety_nine_percentile,
+      rating_count = EXCLUDED.rating_count,
+      min_rate = EXCLUDED.min_rate,
+      max_rate = EXCLUDED.max_rate,
+      median = EXCLUDED.median,
+      standard_deviation = EXCLUDED.standard_deviation,
+      histogram = EXCLUDED.histogram,
+      watermark = EXCLUDED.watermark
+      returning id
       "#,
       att_rating_aggregate.get_attraction_id(),
       att_rating_aggregate.get_at(),
       att_rating_aggregate.get_average(),
       att_rating_aggregate.get_95_percentile(),
       att_rating_aggregate.get_99_percentile(),
+      att_rating_aggregate.get_rating_count(),
+      att_rating_aggregate.get_min_rate(),
+      att_rating_aggregate.get_max_rate(),
+      att_rating_aggregate.get_median(),
+      att_rating_aggregate.get_standard_deviation(),
+      &att_rating_aggregate.get_histogram(),
+      att_rating_aggregate.get_granularity(),
+      att_rating_aggregate.get_watermark(),
     ).fetch_one(conn).await
   }
 
-  async fn get_info(&self, attraction_id: i32) -> sqlx::Result<AttractionInfo> {
+  async fn delete_aggregate(
+    &self,
+    attraction_id: i32,
+    granularity: Granularity,
+    at: NaiveDateTime,
+  ) -> sqlx::Result<()> {
+    let conn = self.connection.get();
+    sqlx::query!(
+      r#"
+      DELETE FROM attraction_rating_aggregate
+      WHERE attraction_id = $1 AND granularity = $2 AND at = $3
+      "#,
+      attraction_id,
+      granularity.as_str(),
+      at
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+  }
+
+  /// Returns the state of the ratings included in every daily sketch.
+  async fn group_sketch_by_date(
+    &self,
+    attraction_id: i32,
+  ) -> sqlx::Result<Vec<AttractionByDate>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      AttractionByDate,
+      r#"
+      SELECT attraction_id, at as "at?",
+      rating_count::bigint as rating_count, watermark
+      FROM attraction_rating_sketch
+      WHERE attraction_id = $1
+      "#,
+      attraction_id
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn get_sketch(
+    &self,
+    attraction_id: i32,
+    at: NaiveDateTime,
+  ) -> sqlx::Result<Option<AttractionRatingSketch>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      AttractionRatingSketch,
+      r#"
+      SELECT * FROM attraction_rating_sketch
+      WHERE attraction_id = $1 AND at = $2
+      "#,
+      attraction_id,
+      at
+    )
+    .fetch_optional(conn)
+    .await
+  }
+
+  /// Returns the daily sketches in the [from, to) period.
+  async fn list_sketches(
+    &self,
+    attraction_id: i32,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+  ) -> sqlx::Result<Vec<AttractionRatingSketch>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      AttractionRatingSketch,
+      r#"
+      SELECT * FROM attraction_rating_sketch
+      WHERE attraction_id = $1 AND at >= $2 AND at < $3
+      ORDER BY at asc
+      "#,
+      attraction_id,
+      from,
+      to
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// Inserts the sketch, or replaces the existing one for the same
+  /// attraction and day.
+  async fn save_sketch(
+    &self,
+    sketch: AttractionRatingSketch,
+  ) -> sqlx::Result<EntityId> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      EntityId,
+      r#"
+      INSERT INTO attraction_rating_sketch
+      (attraction_id, at, sketch, rating_count, last_rating_id, watermark)
+      VALUES ($1, $2, $3, $4, $5, $6)
+      ON CONFLICT ON CONSTRAINT attraction_rating_sketch_uk DO UPDATE SET
+      sketch = EXCLUDED.sketch,
+      rating_count = EXCLUDED.rating_count,
+      last_rating_id = EXCLUDED.last_rating_id,
+      watermark = EXCLUDED.watermark
+      returning id
+      "#,
+      sketch.get_attraction_id(),
+      sketch.get_at(),
+      sketch.sketch,
+      sketch.get_rating_count(),
+      sketch.get_last_rating_id(),
+      sketch.get_watermark()
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  async fn delete_sketch(
+    &self,
+    attraction_id: i32,
+    at: NaiveDateTime,
+  ) -> sqlx::Result<()> {
+    let conn = self.connection.get();
+    sqlx::query!(
+      r#"
+      DELETE FROM attraction_rating_sketch
+      WHERE attraction_id = $1 AND at = $2
+      "#,
+      attraction_id,
+      at
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+  }
+
+  /// Returns the anomalies detected in the [from, to) period, of every
+  /// attraction if it is not specified, the latest first.
+  async fn list_anomalies(
+    &self,
+    attraction_id: Option<i32>,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+  ) -> sqlx::Result<Vec<AttractionRatingAnomaly>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      AttractionRatingAnomaly,
+      r#"
+      SELECT * FROM attraction_rating_anomaly
+      WHERE ($1::integer IS NULL OR attraction_id = $1)
+      AND ($2::timestamp IS NULL OR at >= $2)
+      AND ($3::timestamp IS NULL OR at < $3)
+      ORDER BY at desc, attraction_id asc, metric asc
+      "#,
+      attraction_id,
+      from,
+      to
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// Replaces the anomalies of the attraction with the ones of the last
+  /// detection, in a single transaction.
+  async fn replace_anomalies(
+    &self,
+    attraction_id: i32,
+    anomalies: Vec<AttractionRatingAnomaly>,
+  ) -> sqlx::Result<()> {
+    let mut transaction = self.connection.get().begin().await?;
+    sqlx::query!(
+      r#"
+      DELETE FROM attraction_rating_anomaly WHERE attraction_id = $1
+      "#,
+      attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    for an_anomaly in anomalies {
+      sqlx::query!(
+        r#"
+        INSERT INTO attraction_rating_anomaly
+        (attraction_id, at, metric, method, value, baseline, score)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+        attraction_id,
+        an_anomaly.get_at(),
+        an_anomaly.get_metric(),
+        an_anomaly.get_method(),
+        an_anomaly.get_value(),
+        an_anomaly.get_baseline(),
+        an_anomaly.get_score()
+      )
+      .execute(&mut *transaction)
+      .await?;
+    }
+    transaction.commit().await
+  }
+
+  /// The average rating is the one of the latest daily aggregate, skipping
+  /// the days with anomalies if requested. None when the attraction has no
+  /// such aggregate, e.g. all of its ratings were quarantined.
+  async fn get_info(
+    &self,
+    attraction_id: i32,
+    exclude_anomalies: bool,
+  ) -> sqlx::Result<Option<AttractionInfo>> {
     let conn = self.connection.get();
     sqlx::query_as!(
       AttractionInfo,
       r#"
       SELECT a.id as attraction_id, a.attraction_type_id as attraction_type_id,
-      ara.average as avg_rating, a.latitude as latitude, a.longitude as longitude
+      ara.average as avg_rating, a.latitude as latitude, a.longitude as longitude,
+      array(
+        SELECT t.code FROM attraction_tag at
+        INNER JOIN tag t ON at.tag_id = t.id
+        WHERE at.attraction_id = a.id AND t.deleted_at IS NULL
+        ORDER BY t.code
+      ) as "tags!"
       FROM attraction a
       INNER JOIN attraction_rating_aggregate ara ON a.id = ara.attraction_id
-      WHERE a.id = $1 ORDER BY ara.at DESC
+      AND ara.granularity = 'DAY'
+      WHERE a.id = $1 AND a.deleted_at IS NULL
+      AND NOT ($2 AND EXISTS (
+        SELECT 1 FROM attraction_rating_anomaly an
+        WHERE an.attraction_id = ara.attraction_id AND an.at = ara.at
+      ))
+      ORDER BY ara.at DESC
       LIMIT 1
       "#,
-      attraction_id
+      attraction_id,
+      exclude_anomalies
     )
-        .fetch_one(conn)
+        .fetch_optional(conn)
         .await
   }
 
@@ -0,0 +1,331 @@
+use std::f64::consts::PI;
+
+/// The version of the binary representation of the digest.
+const ENCODING_VERSION: u8 = 1;
+/// The default compression, the digest keeps roughly this many centroids.
+pub const DEFAULT_COMPRESSION: f64 = 100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Centroid {
+  pub mean: f64,
+  pub weight: f64,
+}
+
+/// A mergeable sketch that estimates quantiles of a stream of values with
+/// bounded memory, the merging t-digest described by Dunning & Ertl in
+/// "Computing Extremely Accurate Quantiles Using t-Digests".
+///
+/// The values are summarized in centroids whose maximum size depends on the
+/// quantile they represent (k1 scale function), so the tails are kept with
+/// more precision than the middle of the distribution. Two digests can be
+/// merged without access to the original values.
+#[derive(Debug, Clone)]
+pub struct TDigest {
+  compression: f64,
+  centroids: Vec<Centroid>,
+  unmerged: Vec<Centroid>,
+  total_weight: f64,
+  min: f64,
+  max: f64,
+}
+
+impl Default for TDigest {
+  fn default() -> Self {
+    TDigest::new(DEFAULT_COMPRESSION)
+  }
+}
+
+impl TDigest {
+  pub fn new(compression: f64) -> Self {
+    TDigest {
+      compression,
+      centroids: Vec::new(),
+      unmerged: Vec::new(),
+      total_weight: 0.0,
+      min: f64::INFINITY,
+      max: f64::NEG_INFINITY,
+    }
+  }
+
+  pub fn count(&self) -> f64 {
+    self.total_weight
+  }
+
+  pub fn min(&self) -> Option<f64> {
+    (self.total_weight > 0.0).then_some(self.min)
+  }
+
+  pub fn max(&self) -> Option<f64> {
+    (self.total_weight > 0.0).then_some(self.max)
+  }
+
+  pub fn add(&mut self, value: f64) {
+    self.add_centroid(Centroid {
+      mean: value,
+      weight: 1.0,
+    });
+  }
+
+  /// Add all the values summarized by another digest.
+  pub fn merge(&mut self, other: &TDigest) {
+    for a_centroid in other.centroids.iter().chain(other.unmerged.iter()) {
+      self.add_centroid(*a_centroid);
+    }
+  }
+
+  fn add_centroid(&mut self, centroid: Centroid) {
+    if centroid.weight <= 0.0 || centroid.mean.is_nan() {
+      return;
+    }
+    self.min = self.min.min(centroid.mean);
+    self.max = self.max.max(centroid.mean);
+    self.total_weight += centroid.weight;
+    self.unmerged.push(centroid);
+    if self.unmerged.len() as f64 > 5.0 * self.compression {
+      self.compress();
+    }
+  }
+
+  /// Merge the buffered values into the centroids.
+  pub fn compress(&mut self) {
+    if self.unmerged.is_empty() {
+      return;
+    }
+    let mut all = std::mem::take(&mut self.centroids);
+    all.append(&mut self.unmerged);
+    all.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+
+    let total = self.total_weight;
+    let mut merged: Vec<Centroid> = Vec::with_capacity(all.len());
+    let mut current = all[0];
+    let mut weight_so_far = 0.0;
+    let mut q_limit = self.q_limit(0.0);
+    for next in all.into_iter().skip(1) {
+      let q = (weight_so_far + current.weight + next.weight) / total;
+      if q <= q_limit {
+        let weight = current.weight + next.weight;
+        current.mean += (next.mean - current.mean) * next.weight / weight;
+        current.weight = weight;
+      } else {
+        weight_so_far += current.weight;
+        merged.push(current);
+        q_limit = self.q_limit(weight_so_far / total);
+        current = next;
+      }
+    }
+    merged.push(current);
+    self.centroids = merged;
+  }
+
+  /// The highest quantile that a centroid starting at q0 could reach.
+  fn q_limit(&self, q0: f64) -> f64 {
+    let k = self.compression / (2.0 * PI) * (2.0 * q0 - 1.0).asin();
+    let k = k + 1.0;
+    let limit = ((2.0 * PI * k / self.compression).sin() + 1.0) / 2.0;
+    if k >= self.compression / 4.0 {
+      1.0
+    } else {
+      limit
+    }
+  }
+
+  /// Estimate the value at the quantile q, between 0 and 1.
+  ///
+  /// # Return:
+  /// * Some with the estimated value.
+  /// * None if the digest is empty.
+  pub fn quantile(&self, q: f64) -> Option<f64> {
+    if !self.unmerged.is_empty() {
+      let mut digest = self.clone();
+      digest.compress();
+      return digest.quantile(q);
+    }
+    let first = self.centroids.first()?;
+    let last = self.centroids.last()?;
+    let q = q.clamp(0.0, 1.0);
+    if q == 0.0 {
+      return Some(self.min);
+    }
+    if q == 1.0 {
+      return Some(self.max);
+    }
+    if self.centroids.len() == 1 {
+      return Some(self.min + q * (self.max - self.min));
+    }
+
+    let index = q * self.total_weight;
+    // The first half of the first centroid, between the min and its mean.
+    if index < first.weight / 2.0 {
+      let z = index / (first.weight / 2.0);
+      return Some(self.min + z * (first.mean - self.min));
+    }
+    let mut cumulative = first.weight / 2.0;
+    for pair in self.centroids.windows(2) {
+      let delta = (pair[0].weight + pair[1].weight) / 2.0;
+      if cumulative + delta > index {
+        let z = (index - cumulative) / delta;
+        return Some(pair[0].mean + z * (pair[1].mean - pair[0].mean));
+      }
+      cumulative += delta;
+    }
+    // The second half of the last centroid, between its mean and the max.
+    let z = ((index - cumulative) / (last.weight / 2.0)).min(1.0);
+    Some(last.mean + z * (self.max - last.mean))
+  }
+
+  /// The compact binary representation of the digest.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut digest = self.clone();
+    digest.compress();
+    let mut bytes =
+      Vec::with_capacity(1 + 8 * 3 + 4 + 16 * digest.centroids.len());
+    bytes.push(ENCODING_VERSION);
+    bytes.extend_from_slice(&digest.compression.to_le_bytes());
+    bytes.extend_from_slice(&digest.min.to_le_bytes());
+    bytes.extend_from_slice(&digest.max.to_le_bytes());
+    bytes.extend_from_slice(&(digest.centroids.len() as u32).to_le_bytes());
+    for a_centroid in digest.centroids.iter() {
+      bytes.extend_from_slice(&a_centroid.mean.to_le_bytes());
+      bytes.extend_from_slice(&a_centroid.weight.to_le_bytes());
+    }
+    bytes
+  }
+
+  /// Read a digest from its binary representation.
+  ///
+  /// # Return:
+  /// * Ok with the digest and the remaining bytes.
+  /// * Err with a string that represents the error.
+  pub fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), String> {
+    let mut reader = ByteReader::new(bytes);
+    let version = reader.u8()?;
+    if version != ENCODING_VERSION {
+      return Err(format!("Unknown t-digest encoding version: {}", version));
+    }
+    let mut digest = TDigest::new(reader.f64()?);
+    digest.min = reader.f64()?;
+    digest.max = reader.f64()?;
+    let size = reader.u32()?;
+    for _ in 0..size {
+      let a_centroid = Centroid {
+        mean: reader.f64()?,
+        weight: reader.f64()?,
+      };
+      digest.total_weight += a_centroid.weight;
+      digest.centroids.push(a_centroid);
+    }
+    Ok((digest, reader.remaining()))
+  }
+}
+
+/// Reads little endian values from a slice of bytes.
+pub struct ByteReader<'a> {
+  bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+  pub fn new(bytes: &'a [u8]) -> Self {
+    ByteReader {
+      bytes,
+    }
+  }
+
+  fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+    if self.bytes.len() < N {
+      return Err(String::from("Unexpected end of the encoded sketch"));
+    }
+    let (value, rest) = self.bytes.split_at(N);
+    self.bytes = rest;
+    Ok(value.try_into().unwrap())
+  }
+
+  pub fn u8(&mut self) -> Result<u8, String> {
+    Ok(self.take::<1>()?[0])
+  }
+
+  pub fn u32(&mut self) -> Result<u32, String> {
+    Ok(u32::from_le_bytes(self.take()?))
+  }
+
+  pub fn u64(&mut self) -> Result<u64, String> {
+    Ok(u64::from_le_bytes(self.take()?))
+  }
+
+  pub fn f64(&mut self) -> Result<f64, String> {
+    Ok(f64::from_le_bytes(self.take()?))
+  }
+
+  pub fn remaining(self) -> &'a [u8] {
+    self.bytes
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn digest_of(values: impl Iterator<Item = f64>) -> TDigest {
+    let mut digest = TDigest::default();
+    values.for_each(|value| digest.add(value));
+    digest
+  }
+
+  #[test]
+  fn empty_digest_has_no_quantiles() {
+    assert_eq!(TDigest::default().quantile(0.5), None);
+  }
+
+  #[test]
+  fn estimates_the_quantiles_of_a_uniform_distribution() {
+    let digest = digest_of((0..=10_000).map(|i| i as f64 / 10_000.0));
+    for q in [0.01, 0.25, 0.5, 0.75, 0.95, 0.99] {
+      let estimated = digest.quantile(q).unwrap();
+      assert!((estimated - q).abs() < 0.01, "q {q} estimated {estimated}");
+    }
+    assert_eq!(digest.quantile(0.0), Some(0.0));
+    assert_eq!(digest.quantile(1.0), Some(1.0));
+  }
+
+  #[test]
+  fn merged_digests_estimate_like_a_single_one() {
+    let day_values =
+      |day: usize| (0..500).map(move |i| ((i * 7 + day * 13) % 1000) as f64);
+    let mut merged = TDigest::default();
+    for day in 0..30 {
+      merged.merge(&digest_of(day_values(day)));
+    }
+    let single = digest_of((0..30).flat_map(day_values));
+    let mut sorted = (0..30).flat_map(day_values).collect::<Vec<f64>>();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    assert_eq!(merged.count(), 15_000.0);
+    for q in [0.05, 0.5, 0.95] {
+      let exact = sorted[(q * sorted.len() as f64) as usize];
+      let estimated = merged.quantile(q).unwrap();
+      assert!(
+        (estimated - exact).abs() < 10.0,
+        "q {q} estimated {estimated}"
+      );
+      let from_single = single.quantile(q).unwrap();
+      assert!((estimated - from_single).abs() < 10.0);
+    }
+  }
+
+  #[test]
+  fn keeps_its_size_bounded() {
+    let digest = digest_of((0..100_000).map(|i| (i % 997) as f64));
+    let (decoded, _) = TDigest::from_bytes(&digest.to_bytes()).unwrap();
+    assert!(decoded.centroids.len() < 2 * DEFAULT_COMPRESSION as usize);
+  }
+
+  #[test]
+  fn round_trips_through_its_binary_representation() {
+    let digest = digest_of([0.2, 0.4, 0.4, 0.9].into_iter());
+    let mut bytes = digest.to_bytes();
+    bytes.push(42);
+    let (decoded, rest) = TDigest::from_bytes(&bytes).unwrap();
+    assert_eq!(rest, &[42]);
+    assert_eq!(decoded.count(), 4.0);
+    assert_eq!(decoded.quantile(0.5), digest.quantile(0.5));
+    assert!(TDigest::from_bytes(&bytes[..10]).is_err());
+  }
+}
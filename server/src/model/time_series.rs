@@ -0,0 +1,465 @@
+use crate::model::{
+  attraction::AttractionRatingAggregate, granularity::Granularity,
+  rating_statistics::decimal_of,
+};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::{Duration, NaiveDateTime};
+use serde::Deserialize;
+use std::{collections::HashMap, fmt, str::FromStr};
+
+/// The maximum number of buckets of a series, to avoid generating huge
+/// responses, e.g. by the hour for several years.
+pub const MAX_POINTS: usize = 5_000;
+
+/// How the buckets without an aggregate are returned in the series.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum GapFill {
+  /// The missing buckets are not returned.
+  None,
+  /// The missing buckets are returned without values.
+  #[default]
+  Null,
+  /// The missing buckets repeat the values of the previous bucket.
+  Previous,
+}
+
+impl GapFill {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      GapFill::None => "NONE",
+      GapFill::Null => "NULL",
+      GapFill::Previous => "PREVIOUS",
+    }
+  }
+}
+
+impl fmt::Display for GapFill {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+impl FromStr for GapFill {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_uppercase().as_str() {
+      "NONE" => Ok(GapFill::None),
+      "NULL" => Ok(GapFill::Null),
+      "PREVIOUS" => Ok(GapFill::Previous),
+      _ => Err(format!("Unknown gap fill: {}", s)),
+    }
+  }
+}
+
+impl TryFrom<String> for GapFill {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TimeSeriesOptions {
+  pub fill: GapFill,
+  /// The number of buckets averaged by the moving average.
+  pub moving_average_window: usize,
+  /// The number of days, ending in the last bucket, used for the slope.
+  pub trend_days: i64,
+}
+
+impl Default for TimeSeriesOptions {
+  fn default() -> Self {
+    TimeSeriesOptions {
+      fill: GapFill::default(),
+      moving_average_window: 7,
+      trend_days: 30,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct TimeSeriesPoint {
+  pub at: NaiveDateTime,
+  pub count: i32,
+  pub average: Option<BigDecimal>,
+  pub median: Option<BigDecimal>,
+  pub percentile_95: Option<BigDecimal>,
+  pub percentile_99: Option<BigDecimal>,
+  /// The average of the ratings in the trailing window of buckets.
+  pub moving_average: Option<BigDecimal>,
+  /// True when the bucket has no aggregate and its values were filled.
+  pub filled: bool,
+}
+
+impl TimeSeriesPoint {
+  fn from_aggregate(an_aggregate: &AttractionRatingAggregate) -> Self {
+    TimeSeriesPoint {
+      at: an_aggregate.get_at(),
+      count: an_aggregate.get_rating_count(),
+      average: Some(an_aggregate.get_average()),
+      median: Some(an_aggregate.get_median()),
+      percentile_95: Some(an_aggregate.get_95_percentile()),
+      percentile_99: Some(an_aggregate.get_99_percentile()),
+      moving_average: None,
+      filled: false,
+    }
+  }
+
+  fn gap(at: NaiveDateTime, previous: Option<&TimeSeriesPoint>) -> Self {
+    match previous {
+      Some(previous) => TimeSeriesPoint {
+        at,
+        count: 0,
+        filled: true,
+        ..previous.clone()
+      },
+      None => TimeSeriesPoint {
+        at,
+        count: 0,
+        average: None,
+        median: None,
+        percentile_95: None,
+        percentile_99: None,
+        moving_average: None,
+        filled: true,
+      },
+    }
+  }
+
+  fn average_f64(&self) -> Option<f64> {
+    self.average.as_ref()?.to_f64()
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Trend {
+  pub days: i64,
+  /// The change of the average rating per day, by least squares over the
+  /// buckets of the last days.
+  pub slope_per_day: Option<BigDecimal>,
+  pub last_week_average: Option<BigDecimal>,
+  pub previous_week_average: Option<BigDecimal>,
+  /// The relative change between the average of the last 7 days and the 7
+  /// days before them.
+  pub week_over_week_change: Option<BigDecimal>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TimeSeries {
+  pub attraction_id: i32,
+  pub granularity: Granularity,
+  pub points: Vec<TimeSeriesPoint>,
+  pub trend: Trend,
+}
+
+impl TimeSeries {
+  /// Build the series of an attraction from its aggregates.
+  ///
+  /// # Arguments:
+  /// * aggregates: the aggregates of the granularity, sorted by date.
+  /// * from, to: the first and the last bucket, truncated to the granularity.
+  ///   By default the first and last aggregates.
+  ///
+  /// # Return:
+  /// * Ok with the series.
+  /// * Err with a string if the series is too long or the range is invalid.
+  pub fn build(
+    attraction_id: i32,
+    granularity: Granularity,
+    aggregates: &[AttractionRatingAggregate],
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+    options: TimeSeriesOptions,
+  ) -> Result<Self, String> {
+    let first = from
+      .or(aggregates.first().map(|an_aggregate| an_aggregate.get_at()))
+      .map(|at| granularity.truncate(at));
+    let last = to
+      .or(aggregates.last().map(|an_aggregate| an_aggregate.get_at()))
+      .map(|at| granularity.truncate(at));
+    let (Some(first), Some(last)) = (first, last) else {
+      return Ok(TimeSeries {
+        attraction_id,
+        granularity,
+        points: Vec::new(),
+        trend: Trend {
+          days: options.trend_days,
+          ..Default::default()
+        },
+      });
+    };
+    if first > last {
+      return Err(format!("The series starts after its end: {first} {last}"));
+    }
+
+    let aggregate_by_date: HashMap<NaiveDateTime, &AttractionRatingAggregate> =
+      aggregates
+        .iter()
+        .map(|an_aggregate| (an_aggregate.get_at(), an_aggregate))
+        .collect();
+    let mut points: Vec<TimeSeriesPoint> = Vec::new();
+    let mut at = first;
+    while at <= last {
+      if points.len() >= MAX_POINTS {
+        return Err(format!("The series exceeds {MAX_POINTS} buckets"));
+      }
+      match aggregate_by_date.get(&at) {
+        Some(an_aggregate) => {
+          points.push(TimeSeriesPoint::from_aggregate(an_aggregate))
+        },
+        None => match options.fill {
+          GapFill::None => {},
+          GapFill::Null => points.push(TimeSeriesPoint::gap(at, None)),
+          GapFill::Previous => {
+            let previous = points.last().cloned();
+            points.push(TimeSeriesPoint::gap(at, previous.as_ref()));
+          },
+        },
+      }
+      at = granularity.next(at);
+    }
+
+    fill_moving_average(&mut points, options.moving_average_window);
+    let end = granularity.window(last).1;
+    let trend = trend_of(&points, end, options.trend_days);
+    Ok(TimeSeries {
+      attraction_id,
+      granularity,
+      points,
+      trend,
+    })
+  }
+}
+
+/// The rated (not filled) points, as their average weighted by the count.
+fn rated<'a>(
+  points: impl Iterator<Item = &'a TimeSeriesPoint> + 'a,
+) -> impl Iterator<Item = (f64, f64)> + 'a {
+  points
+    .filter(|point| !point.filled && point.count > 0)
+    .filter_map(|point| Some((point.average_f64()?, point.count as f64)))
+}
+
+fn weighted_average(values: impl Iterator<Item = (f64, f64)>) -> Option<f64> {
+  let (sum, weight) = values.fold((0.0, 0.0), |(sum, weight), (value, w)| {
+    (sum + value * w, weight + w)
+  });
+  (weight > 0.0).then(|| sum / weight)
+}
+
+fn fill_moving_average(points: &mut [TimeSeriesPoint], window: usize) {
+  let window = window.max(1);
+  for index in 0..points.len() {
+    let start = (index + 1).saturating_sub(window);
+    let average = weighted_average(rated(points[start..=index].iter()));
+    points[index].moving_average = average.and_then(decimal_of);
+  }
+}
+
+/// The trend indicators of the points in the days before the end.
+fn trend_of(
+  points: &[TimeSeriesPoint],
+  end: NaiveDateTime,
+  days: i64,
+) -> Trend {
+  let between = |from: NaiveDateTime, to: NaiveDateTime| {
+    weighted_average(rated(
+      points
+        .iter()
+        .filter(move |point| point.at >= from && point.at < to),
+    ))
+  };
+  let last_week = between(end - Duration::days(7), end);
+  let previous_week =
+    between(end - Duration::days(14), end - Duration::days(7));
+  let week_over_week_change = match (last_week, previous_week) {
+    (Some(last), Some(previous)) if previous > 0.0 => {
+      Some((last - previous) / previous)
+    },
+    _ => None,
+  };
+
+  let since = end - Duration::days(days.max(1));
+  let samples = points
+    .iter()
+    .filter(|point| !point.filled && point.at >= since)
+    .filter_map(|point| {
+      let x = (point.at - since).num_seconds() as f64 / 86_400.0;
+      Some((x, point.average_f64()?))
+    })
+    .collect::<Vec<(f64, f64)>>();
+
+  Trend {
+    days,
+    slope_per_day: slope_of(&samples).and_then(decimal_of),
+    last_week_average: last_week.and_then(decimal_of),
+    previous_week_average: previous_week.and_then(decimal_of),
+    week_over_week_change: week_over_week_change.and_then(decimal_of),
+  }
+}
+
+/// The slope of the least squares line through the samples, None if there
+/// are less than two distinct x.
+fn slope_of(samples: &[(f64, f64)]) -> Option<f64> {
+  let n = samples.len() as f64;
+  if samples.len() < 2 {
+    return None;
+  }
+  let mean_x = samples.iter().map(|(x, _)| x).sum::<f64>() / n;
+  let mean_y = samples.iter().map(|(_, y)| y).sum::<f64>() / n;
+  let covariance = samples
+    .iter()
+    .map(|(x, y)| (x - mean_x) * (y - mean_y))
+    .sum::<f64>();
+  let variance = samples
+    .iter()
+    .map(|(x, _)| (x - mean_x).powi(2))
+    .sum::<f64>();
+  (variance > 0.0).then(|| covariance / variance)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::NaiveDate;
+  use std::str::FromStr;
+
+  fn day(day: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2023, 10, day)
+      .unwrap()
+      .and_hms_opt(0, 0, 0)
+      .unwrap()
+  }
+
+  fn aggregate(at: NaiveDateTime, average: &str) -> AttractionRatingAggregate {
+    let average = BigDecimal::from_str(average).unwrap();
+    AttractionRatingAggregate {
+      id: 0,
+      attraction_id: 1,
+      at,
+      average: average.clone(),
+      ninety_five_percentile: average.clone(),
+      ninety_nine_percentile: average.clone(),
+      rating_count: 1,
+      min_rate: average.clone(),
+      max_rate: average.clone(),
+      median: average.clone(),
+      standard_deviation: BigDecimal::from(0),
+      histogram: vec![],
+      granularity: Granularity::Day.to_string(),
+      watermark: None,
+    }
+  }
+
+  fn build(
+    aggregates: &[AttractionRatingAggregate],
+    options: TimeSeriesOptions,
+  ) -> TimeSeries {
+    TimeSeries::build(1, Granularity::Day, aggregates, None, None, options)
+      .unwrap()
+  }
+
+  fn decimal(value: &str) -> Option<BigDecimal> {
+    Some(BigDecimal::from_str(value).unwrap())
+  }
+
+  #[test]
+  fn fills_the_gaps_between_the_aggregates() {
+    let aggregates = [aggregate(day(1), "0.5"), aggregate(day(4), "0.8")];
+    let series = build(&aggregates, TimeSeriesOptions::default());
+    assert_eq!(series.points.len(), 4);
+    assert!(series.points[1].filled);
+    assert_eq!(series.points[1].average, None);
+
+    let series = build(
+      &aggregates,
+      TimeSeriesOptions {
+        fill: GapFill::Previous,
+        ..Default::default()
+      },
+    );
+    assert_eq!(series.points[2].average, decimal("0.5"));
+    assert_eq!(series.points[2].count, 0);
+
+    let series = build(
+      &aggregates,
+      TimeSeriesOptions {
+        fill: GapFill::None,
+        ..Default::default()
+      },
+    );
+    assert_eq!(series.points.len(), 2);
+  }
+
+  #[test]
+  fn moving_average_ignores_the_filled_buckets() {
+    let aggregates = [
+      aggregate(day(1), "0.2"),
+      aggregate(day(2), "0.4"),
+      aggregate(day(4), "0.9"),
+    ];
+    let series = build(
+      &aggregates,
+      TimeSeriesOptions {
+        fill: GapFill::Previous,
+        moving_average_window: 2,
+        ..Default::default()
+      },
+    );
+    let moving_averages = series
+      .points
+      .iter()
+      .map(|point| point.moving_average.clone())
+      .collect::<Vec<Option<BigDecimal>>>();
+    assert_eq!(
+      moving_averages,
+      vec![
+        decimal("0.2"),
+        decimal("0.3"),
+        decimal("0.4"),
+        decimal("0.9")
+      ]
+    );
+  }
+
+  #[test]
+  fn computes_the_slope_and_the_week_over_week_change() {
+    let aggregates = (1..=14)
+      .map(|a_day| aggregate(day(a_day), &format!("0.{:02}", 10 + a_day)))
+      .collect::<Vec<AttractionRatingAggregate>>();
+    let series = build(&aggregates, TimeSeriesOptions::default());
+    assert_eq!(series.trend.slope_per_day, decimal("0.01"));
+    assert_eq!(series.trend.previous_week_average, decimal("0.14"));
+    assert_eq!(series.trend.last_week_average, decimal("0.21"));
+    assert_eq!(series.trend.week_over_week_change, decimal("0.5"));
+  }
+
+  #[test]
+  fn rejects_invalid_ranges() {
+    let options = TimeSeriesOptions::default();
+    let series =
+      TimeSeries::build(1, Granularity::Day, &[], None, None, options).unwrap();
+    assert!(series.points.is_empty());
+    assert!(TimeSeries::build(
+      1,
+      Granularity::Day,
+      &[],
+      Some(day(2)),
+      Some(day(1)),
+      options
+    )
+    .is_err());
+    assert!(TimeSeries::build(
+      1,
+      Granularity::Hour,
+      &[],
+      Some(day(1)),
+      Some(day(1) + Duration::days(365)),
+      options
+    )
+    .is_err());
+  }
+}
@@ -0,0 +1,245 @@
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+use std::cmp::Ordering;
+
+/// The most locales taken from the Accept-Language header.
+const MAX_PREFERRED_LOCALES: usize = 10;
+
+/// The description of an entity in a locale.
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct Translation {
+  pub entity_id: i32,
+  pub locale: String,
+  pub description: String,
+  pub updated_at: NaiveDateTime,
+}
+
+/// The entity whose description is translated: an attraction, an attraction
+/// type or a city by their id, or a country by its iso code.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TranslationKey {
+  Attraction(i32),
+  AttractionType(i32),
+  Country(String),
+  City(i32),
+}
+
+impl TranslationKey {
+  /// The kind of the entity, to tell which one wasn't found.
+  pub fn kind(&self) -> &str {
+    match self {
+      TranslationKey::Attraction(_) => "ATTRACTION",
+      TranslationKey::AttractionType(_) => "ATTRACTION_TYPE",
+      TranslationKey::Country(_) => "COUNTRY",
+      TranslationKey::City(_) => "CITY",
+    }
+  }
+
+  pub fn key(&self) -> String {
+    match self {
+      TranslationKey::Attraction(id)
+      | TranslationKey::AttractionType(id)
+      | TranslationKey::City(id) => id.to_string(),
+      TranslationKey::Country(iso_code) => iso_code.to_string(),
+    }
+  }
+}
+
+/// The canonical form of a locale, a language of 2 or 3 letters followed by
+/// its subtags, like "es", "pt-BR" or "zh-Hant-TW". The language is in lower
+/// case, the regions in upper case and the scripts capitalized.
+pub fn locale_of(locale: &str) -> Result<String, String> {
+  let invalid = || format!("The locale \"{}\" isn't valid", locale.trim());
+  let mut subtags = locale.trim().split(['-', '_']);
+  let language = subtags.next().unwrap_or_default();
+  if !(2..=3).contains(&language.len())
+    || !language.chars().all(|c| c.is_ascii_alphabetic())
+  {
+    return Err(invalid());
+  }
+  let mut canonical = vec![language.to_ascii_lowercase()];
+  for a_subtag in subtags {
+    if !(2..=8).contains(&a_subtag.len())
+      || !a_subtag.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+      return Err(invalid());
+    }
+    let a_subtag = match a_subtag.len() {
+      2 => a_subtag.to_ascii_uppercase(),
+      4 if a_subtag.chars().all(|c| c.is_ascii_alphabetic()) => {
+        a_subtag[..1].to_ascii_uppercase() + &a_subtag[1..].to_ascii_lowercase()
+      },
+      _ => a_subtag.to_ascii_lowercase(),
+    };
+    canonical.push(a_subtag);
+  }
+  Ok(canonical.join("-"))
+}
+
+/// The locales to look the translations up, the preferred first. Every
+/// locale of the Accept-Language header is followed by its fallbacks, the
+/// same locale without its last subtags, like "es-AR" by "es". The locales
+/// are ordered by their quality, the ones that aren't valid, the wildcard
+/// and the ones with quality 0 are left out.
+pub fn locales_of(accept_language: &str) -> Vec<String> {
+  let mut preferred = accept_language
+    .split(',')
+    .filter_map(|a_range| {
+      let mut parts = a_range.split(';');
+      let locale = locale_of(parts.next()?).ok()?;
+      let quality = parts
+        .find_map(|a_param| a_param.trim().strip_prefix("q="))
+        .map_or(Some(1.0), |quality| quality.trim().parse::<f64>().ok())?;
+      (quality > 0.0).then_some((locale, quality))
+    })
+    .take(MAX_PREFERRED_LOCALES)
+    .collect::<Vec<(String, f64)>>();
+  // The sort is stable, so the locales with the same quality keep the order.
+  preferred.sort_by(|one, other| {
+    other.1.partial_cmp(&one.1).unwrap_or(Ordering::Equal)
+  });
+  let mut chain: Vec<String> = vec![];
+  for (locale, _) in preferred {
+    let subtags = locale.split('-').collect::<Vec<&str>>();
+    for length in (1..=subtags.len()).rev() {
+      let fallback = subtags[..length].join("-");
+      if !chain.contains(&fallback) {
+        chain.push(fallback);
+      }
+    }
+  }
+  chain
+}
+
+/// The language of a locale, its first subtag, like "es" of "es-AR".
+pub fn language_of(locale: &str) -> &str {
+  locale.split('-').next().unwrap_or(locale)
+}
+
+/// The distinct languages of the locales of the chain, in the same order.
+pub fn languages_of(chain: &[String]) -> Vec<String> {
+  let mut languages: Vec<String> = vec![];
+  for a_locale in chain {
+    let language = language_of(a_locale).to_string();
+    if !languages.contains(&language) {
+      languages.push(language);
+    }
+  }
+  languages
+}
+
+/// The translation in the preferred language of the chain that has one. In
+/// that language, the one in the first locale of the chain, or without any,
+/// the one of another region, like "es-AR" for "es" or "es-MX" for "es-AR".
+pub fn best_of<'a>(
+  translations: &'a [Translation],
+  chain: &[String],
+) -> Option<&'a Translation> {
+  let languages = languages_of(chain);
+  translations
+    .iter()
+    .filter_map(|a_translation| {
+      let language = languages.iter().position(|a_language| {
+        a_language == language_of(&a_translation.locale)
+      })?;
+      let exact = chain
+        .iter()
+        .position(|a_locale| *a_locale == a_translation.locale)
+        .unwrap_or(chain.len());
+      Some(((language, exact, &a_translation.locale), a_translation))
+    })
+    .min_by(|(one, _), (other, _)| one.cmp(other))
+    .map(|(_, a_translation)| a_translation)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn translation(locale: &str, description: &str) -> Translation {
+    Translation {
+      entity_id: 1,
+      locale: String::from(locale),
+      description: String::from(description),
+      updated_at: NaiveDateTime::default(),
+    }
+  }
+
+  #[test]
+  fn the_locales_are_written_in_their_canonical_form() {
+    assert_eq!(locale_of(" pt_br "), Ok(String::from("pt-BR")));
+    assert_eq!(locale_of("ZH-hant-tw"), Ok(String::from("zh-Hant-TW")));
+    assert_eq!(locale_of("ES"), Ok(String::from("es")));
+  }
+
+  #[test]
+  fn the_invalid_locales_are_rejected() {
+    for invalid in ["spanish", "es-", "e", "es-AR-toolongsubtag", "1s"] {
+      assert!(locale_of(invalid).is_err(), "{invalid}");
+    }
+  }
+
+  #[test]
+  fn the_locales_are_ordered_by_quality_followed_by_their_fallbacks() {
+    assert_eq!(
+      locales_of("fr;q=0.5, es-AR, en;q=0.8, es;q=0.9"),
+      vec!["es-AR", "es", "en", "fr"]
+    );
+    assert_eq!(locales_of("en, pt-BR"), vec!["en", "pt-BR", "pt"]);
+  }
+
+  #[test]
+  fn the_wildcard_and_the_rejected_locales_are_left_out() {
+    assert_eq!(locales_of("*;q=0.1, de;q=0, es, x"), vec!["es"]);
+    assert!(locales_of("").is_empty());
+  }
+
+  #[test]
+  fn the_best_translation_is_in_the_first_locale_of_the_chain() {
+    let translations = vec![
+      translation("en", "Japanese Garden"),
+      translation("es", "Jardín Japonés"),
+    ];
+    let chain = locales_of("es-AR, en;q=0.5");
+    assert_eq!(
+      best_of(&translations, &chain)
+        .map(|a_translation| a_translation.description.as_str()),
+      Some("Jardín Japonés")
+    );
+    assert_eq!(best_of(&translations, &locales_of("de")), None);
+  }
+
+  #[test]
+  fn a_translation_of_another_region_of_the_language_is_the_fallback() {
+    let description_in = |translations: &[Translation], accept: &str| {
+      best_of(translations, &locales_of(accept))
+        .map(|a_translation| a_translation.locale.clone())
+    };
+    let regional = vec![
+      translation("es-MX", "Jardín Japonés"),
+      translation("es-AR", "Jardín Japonés"),
+    ];
+    assert_eq!(description_in(&regional, "es"), Some(String::from("es-AR")));
+    assert_eq!(
+      description_in(&regional, "es-CL, en;q=0.5"),
+      Some(String::from("es-AR"))
+    );
+    assert_eq!(
+      description_in(&regional, "es-MX"),
+      Some(String::from("es-MX"))
+    );
+
+    // The preferred language comes first, even in another region.
+    let mut with_english = regional.clone();
+    with_english.push(translation("en", "Japanese Garden"));
+    assert_eq!(
+      description_in(&with_english, "es, en;q=0.5"),
+      Some(String::from("es-AR"))
+    );
+    assert_eq!(
+      description_in(&with_english, "en, es;q=0.5"),
+      Some(String::from("en"))
+    );
+    assert_eq!(description_in(&regional, "pt-BR"), None);
+  }
+}
@@ -0,0 +1,298 @@
+use crate::model::{
+  audit::Actor,
+  reference_controller::required,
+  translation::{locale_of, Translation, TranslationKey},
+  translation_repository::TranslationRepository,
+};
+use async_trait::async_trait;
+
+/// The translations of the descriptions of the attractions, their types, the
+/// countries and the cities.
+#[async_trait]
+pub trait TranslationController: Send + Sync + 'static {
+  async fn translations(&self, key: TranslationKey)
+    -> Option<Vec<Translation>>;
+  async fn set_translation(
+    &self,
+    key: TranslationKey,
+    locale: String,
+    description: String,
+    actor: Actor,
+  ) -> Result<Option<Translation>, String>;
+  async fn delete_translation(
+    &self,
+    key: TranslationKey,
+    locale: String,
+    actor: Actor,
+  ) -> Result<bool, String>;
+}
+
+#[derive(Clone)]
+pub struct TranslationControllerImpl<TranslationRepo> {
+  translation_repository: TranslationRepo,
+}
+
+impl<TranslationRepo> TranslationControllerImpl<TranslationRepo>
+where
+  TranslationRepo: TranslationRepository,
+{
+  pub fn new(translation_repository: TranslationRepo) -> Self {
+    TranslationControllerImpl {
+      translation_repository,
+    }
+  }
+}
+
+#[async_trait]
+impl<TranslationRepo> TranslationController
+  for TranslationControllerImpl<TranslationRepo>
+where
+  TranslationRepo: TranslationRepository + Send + Sync + 'static,
+{
+  async fn translations(
+    &self,
+    key: TranslationKey,
+  ) -> Option<Vec<Translation>> {
+    self
+      .translation_repository
+      .list_translations(key_of(key))
+      .await
+      .ok()
+  }
+
+  /// Create or replace the translation in the locale, written in its
+  /// canonical form. Returns None if the entity doesn't exist.
+  async fn set_translation(
+    &self,
+    key: TranslationKey,
+    locale: String,
+    description: String,
+    actor: Actor,
+  ) -> Result<Option<Translation>, String> {
+    let locale = locale_of(&locale)?;
+    let description = required(description, "description")?;
+    match self
+      .translation_repository
+      .save_translation(key_of(key), locale, description, &actor)
+      .await
+    {
+      Ok(a_translation) => Ok(Some(a_translation)),
+      Err(sqlx::Error::RowNotFound) => Ok(None),
+      Err(e) => Err(e.to_string()),
+    }
+  }
+
+  /// Returns false if the entity doesn't have a translation in the locale.
+  async fn delete_translation(
+    &self,
+    key: TranslationKey,
+    locale: String,
+    actor: Actor,
+  ) -> Result<bool, String> {
+    let locale = locale_of(&locale)?;
+    self
+      .translation_repository
+      .delete_translation(key_of(key), locale, &actor)
+      .await
+      .map(|deleted| deleted > 0)
+      .map_err(|e| e.to_string())
+  }
+}
+
+/// The key with the iso code of the countries in upper case, as they are
+/// stored.
+fn key_of(key: TranslationKey) -> TranslationKey {
+  match key {
+    TranslationKey::Country(iso_code) => {
+      TranslationKey::Country(iso_code.trim().to_uppercase())
+    },
+    _ => key,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::NaiveDateTime;
+  use std::sync::Mutex;
+
+  /// The translations in memory of the attraction 1 and of Argentina, the
+  /// country 10.
+  #[derive(Default)]
+  struct InMemoryTranslationRepo {
+    translations: Mutex<Vec<(TranslationKey, Translation)>>,
+  }
+
+  impl InMemoryTranslationRepo {
+    fn entity_id_of(key: &TranslationKey) -> sqlx::Result<i32> {
+      match key {
+        TranslationKey::Attraction(1) => Ok(1),
+        TranslationKey::Country(iso_code) if iso_code == "AR" => Ok(10),
+        _ => Err(sqlx::Error::RowNotFound),
+      }
+    }
+  }
+
+  #[async_trait]
+  impl TranslationRepository for InMemoryTranslationRepo {
+    async fn list_translations(
+      &self,
+      key: TranslationKey,
+    ) -> sqlx::Result<Vec<Translation>> {
+      let translations = self.translations.lock().unwrap();
+      Ok(
+        translations
+          .iter()
+          .filter(|(a_key, _)| *a_key == key)
+          .map(|(_, a_translation)| a_translation.clone())
+          .collect(),
+      )
+    }
+
+    async fn save_translation(
+      &self,
+      key: TranslationKey,
+      locale: String,
+      description: String,
+      _: &Actor,
+    ) -> sqlx::Result<Translation> {
+      let saved = Translation {
+        entity_id: Self::entity_id_of(&key)?,
+        locale,
+        description,
+        updated_at: NaiveDateTime::default(),
+      };
+      let mut translations = self.translations.lock().unwrap();
+      translations.retain(|(a_key, a_translation)| {
+        *a_key != key || a_translation.locale != saved.locale
+      });
+      translations.push((key, saved.clone()));
+      Ok(saved)
+    }
+
+    async fn delete_translation(
+      &self,
+      key: TranslationKey,
+      locale: String,
+      _: &Actor,
+    ) -> sqlx::Result<u64> {
+      let mut translations = self.translations.lock().unwrap();
+      let before = translations.len();
+      translations.retain(|(a_key, a_translation)| {
+        *a_key != key || a_translation.locale != locale
+      });
+      Ok((before - translations.len()) as u64)
+    }
+  }
+
+  fn controller() -> TranslationControllerImpl<InMemoryTranslationRepo> {
+    TranslationControllerImpl::new(InMemoryTranslationRepo::default())
+  }
+
+  fn translator() -> Actor {
+    Actor {
+      id: 1,
+      username: String::from("translator"),
+    }
+  }
+
+  async fn set(
+    controller: &TranslationControllerImpl<InMemoryTranslationRepo>,
+    key: TranslationKey,
+    locale: &str,
+    description: &str,
+  ) -> Result<Option<Translation>, String> {
+    controller
+      .set_translation(
+        key,
+        String::from(locale),
+        String::from(description),
+        translator(),
+      )
+      .await
+  }
+
+  #[tokio::test]
+  async fn a_translation_is_saved_in_its_canonical_locale_and_replaced() {
+    let controller = controller();
+    let attraction = TranslationKey::Attraction(1);
+    set(&controller, attraction.clone(), "es_ar", "Jardin")
+      .await
+      .unwrap();
+    let replaced = set(&controller, attraction.clone(), "ES-ar", " Jardín ")
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(replaced.locale, "es-AR");
+    assert_eq!(replaced.description, "Jardín");
+    assert_eq!(
+      controller.translations(attraction).await,
+      Some(vec![replaced])
+    );
+  }
+
+  #[tokio::test]
+  async fn the_countries_are_translated_by_their_iso_code_in_any_case() {
+    let controller = controller();
+    let saved = set(
+      &controller,
+      TranslationKey::Country(String::from(" ar ")),
+      "en",
+      "Argentina",
+    )
+    .await
+    .unwrap();
+    assert_eq!(saved.map(|a_translation| a_translation.entity_id), Some(10));
+    assert_eq!(
+      controller
+        .translations(TranslationKey::Country(String::from("Ar")))
+        .await
+        .map(|translations| translations.len()),
+      Some(1)
+    );
+  }
+
+  #[tokio::test]
+  async fn the_entities_that_dont_exist_arent_translated() {
+    let controller = controller();
+    assert_eq!(
+      set(&controller, TranslationKey::City(1), "en", "Buenos Aires").await,
+      Ok(None)
+    );
+    assert_eq!(
+      set(&controller, TranslationKey::Attraction(2), "en", "Garden").await,
+      Ok(None)
+    );
+  }
+
+  #[tokio::test]
+  async fn an_invalid_translation_isnt_saved() {
+    let controller = controller();
+    let attraction = TranslationKey::Attraction(1);
+    assert!(set(&controller, attraction.clone(), "spanish", "Jardín")
+      .await
+      .is_err());
+    assert!(set(&controller, attraction.clone(), "es", "  ")
+      .await
+      .is_err());
+    assert_eq!(controller.translations(attraction).await, Some(Vec::new()));
+  }
+
+  #[tokio::test]
+  async fn a_translation_is_deleted_by_its_locale_in_any_form() {
+    let controller = controller();
+    let attraction = TranslationKey::Attraction(1);
+    set(&controller, attraction.clone(), "pt-BR", "Jardim")
+      .await
+      .unwrap();
+    let delete = || {
+      controller.delete_translation(
+        attraction.clone(),
+        String::from("pt_br"),
+        translator(),
+      )
+    };
+    assert_eq!(delete().await, Ok(true));
+    assert_eq!(delete().await, Ok(false));
+  }
+}
@@ -0,0 +1,297 @@
+use crate::{
+  db::database::DbConnection,
+  model::{
+    audit::Actor,
+    audit_repository::act_as,
+    translation::{Translation, TranslationKey},
+  },
+};
+use async_trait::async_trait;
+
+/// The translations of the descriptions of the attractions and of their
+/// reference data.
+#[async_trait]
+pub trait TranslationRepository {
+  async fn list_translations(
+    &self,
+    key: TranslationKey,
+  ) -> sqlx::Result<Vec<Translation>>;
+  async fn save_translation(
+    &self,
+    key: TranslationKey,
+    locale: String,
+    description: String,
+    actor: &Actor,
+  ) -> sqlx::Result<Translation>;
+  async fn delete_translation(
+    &self,
+    key: TranslationKey,
+    locale: String,
+    actor: &Actor,
+  ) -> sqlx::Result<u64>;
+}
+
+#[derive(Clone, Default)]
+pub struct DummyTranslationRepo;
+
+#[async_trait]
+impl TranslationRepository for DummyTranslationRepo {
+  async fn list_translations(
+    &self,
+    _: TranslationKey,
+  ) -> sqlx::Result<Vec<Translation>> {
+    todo!()
+  }
+
+  async fn save_translation(
+    &self,
+    _: TranslationKey,
+    _: String,
+    _: String,
+    _: &Actor,
+  ) -> sqlx::Result<Translation> {
+    todo!()
+  }
+
+  async fn delete_translation(
+    &self,
+    _: TranslationKey,
+    _: String,
+    _: &Actor,
+  ) -> sqlx::Result<u64> {
+    todo!()
+  }
+}
+
+#[derive(Clone)]
+pub struct PgTranslationRepository {
+  connection: DbConnection,
+}
+
+impl PgTranslationRepository {
+  pub fn new(connection: DbConnection) -> Self {
+    PgTranslationRepository {
+      connection,
+    }
+  }
+}
+
+#[async_trait]
+impl TranslationRepository for PgTranslationRepository {
+  /// Returns the translations of the entity, none if it doesn't exist.
+  async fn list_translations(
+    &self,
+    key: TranslationKey,
+  ) -> sqlx::Result<Vec<Translation>> {
+    let conn = self.connection.get();
+    match key {
+      TranslationKey::Attraction(id) => {
+        sqlx::query_as!(
+          Translation,
+          r#"
+        SELECT attraction_id as entity_id, locale, description, updated_at
+        FROM attraction_translation
+        WHERE attraction_id = $1
+        ORDER BY locale
+          "#,
+          id
+        )
+        .fetch_all(conn)
+        .await
+      },
+      TranslationKey::AttractionType(id) => {
+        sqlx::query_as!(
+          Translation,
+          r#"
+        SELECT attraction_type_id as entity_id, locale, description, updated_at
+        FROM attraction_type_translation
+        WHERE attraction_type_id = $1
+        ORDER BY locale
+          "#,
+          id
+        )
+        .fetch_all(conn)
+        .await
+      },
+      TranslationKey::Country(iso_code) => {
+        sqlx::query_as!(
+          Translation,
+          r#"
+        SELECT t.country_id as entity_id, t.locale, t.description,
+        t.updated_at
+        FROM country_translation t
+        INNER JOIN country c ON t.country_id = c.id
+        WHERE c.iso_code = $1 AND c.deleted_at IS NULL
+        ORDER BY t.locale
+          "#,
+          iso_code
+        )
+        .fetch_all(conn)
+        .await
+      },
+      TranslationKey::City(id) => {
+        sqlx::query_as!(
+          Translation,
+          r#"
+        SELECT city_id as entity_id, locale, description, updated_at
+        FROM city_translation
+        WHERE city_id = $1
+        ORDER BY locale
+          "#,
+          id
+        )
+        .fetch_all(conn)
+        .await
+      },
+    }
+  }
+
+  /// Create or replace the translation in the locale. It fails with
+  /// RowNotFound if the entity doesn't exist.
+  async fn save_translation(
+    &self,
+    key: TranslationKey,
+    locale: String,
+    description: String,
+    actor: &Actor,
+  ) -> sqlx::Result<Translation> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let saved = match key {
+      TranslationKey::Attraction(id) => {
+        sqlx::query_as!(
+          Translation,
+          r#"
+          INSERT INTO attraction_translation (attraction_id, locale, description)
+          SELECT id, $2, $3 FROM attraction WHERE id = $1 AND deleted_at IS NULL
+          ON CONFLICT (attraction_id, locale)
+          DO UPDATE SET description = excluded.description, updated_at = now()
+          returning attraction_id as entity_id, locale, description, updated_at
+          "#,
+          id,
+          locale,
+          description
+        )
+        .fetch_one(&mut *transaction)
+        .await
+      },
+      TranslationKey::AttractionType(id) => {
+        sqlx::query_as!(
+          Translation,
+          r#"
+          INSERT INTO attraction_type_translation (attraction_type_id, locale, description)
+          SELECT id, $2, $3 FROM attraction_type WHERE id = $1 AND deleted_at IS NULL
+          ON CONFLICT (attraction_type_id, locale)
+          DO UPDATE SET description = excluded.description, updated_at = now()
+          returning attraction_type_id as entity_id, locale, description, updated_at
+          "#,
+          id,
+          locale,
+          description
+        )
+        .fetch_one(&mut *transaction)
+        .await
+      },
+      TranslationKey::Country(iso_code) => {
+        sqlx::query_as!(
+          Translation,
+          r#"
+          INSERT INTO country_translation (country_id, locale, description)
+          SELECT id, $2, $3 FROM country
+          WHERE iso_code = $1 AND deleted_at IS NULL
+          ON CONFLICT (country_id, locale)
+          DO UPDATE SET description = excluded.description, updated_at = now()
+          returning country_id as entity_id, locale, description, updated_at
+          "#,
+          iso_code,
+          locale,
+          description
+        )
+        .fetch_one(&mut *transaction)
+        .await
+      },
+      TranslationKey::City(id) => {
+        sqlx::query_as!(
+          Translation,
+          r#"
+          INSERT INTO city_translation (city_id, locale, description)
+          SELECT id, $2, $3 FROM city WHERE id = $1 AND deleted_at IS NULL
+          ON CONFLICT (city_id, locale)
+          DO UPDATE SET description = excluded.description, updated_at = now()
+          returning city_id as entity_id, locale, description, updated_at
+          "#,
+          id,
+          locale,
+          description
+        )
+        .fetch_one(&mut *transaction)
+        .await
+      },
+    }?;
+    transaction.commit().await?;
+    Ok(saved)
+  }
+
+  async fn delete_translation(
+    &self,
+    key: TranslationKey,
+    locale: String,
+    actor: &Actor,
+  ) -> sqlx::Result<u64> {
+    let mut transaction = self.connection.get().begin().await?;
+    act_as(&mut transaction, actor).await?;
+    let deleted = match key {
+      TranslationKey::Attraction(id) => {
+        sqlx::query!(
+          r#"
+          DELETE FROM attraction_translation
+          WHERE attraction_id = $1 AND locale = $2
+          "#,
+          id,
+          locale
+        )
+        .execute(&mut *transaction)
+        .await
+      },
+      TranslationKey::AttractionType(id) => {
+        sqlx::query!(
+          r#"
+          DELETE FROM attraction_type_translation
+          WHERE attraction_type_id = $1 AND locale = $2
+          "#,
+          id,
+          locale
+        )
+        .execute(&mut *transaction)
+        .await
+      },
+      TranslationKey::Country(iso_code) => {
+        sqlx::query!(
+          r#"
+          DELETE FROM country_translation t USING country c
+          WHERE t.country_id = c.id AND c.iso_code = $1 AND c.deleted_at IS NULL
+          AND t.locale = $2
+          "#,
+          iso_code,
+          locale
+        )
+        .execute(&mut *transaction)
+        .await
+      },
+      TranslationKey::City(id) => {
+        sqlx::query!(
+          r#"
+          DELETE FROM city_translation
+          WHERE city_id = $1 AND locale = $2
+          "#,
+          id,
+          locale
+        )
+        .execute(&mut *transaction)
+        .await
+      },
+    }?;
+    transaction.commit().await?;
+    Ok(deleted.rows_affected())
+  }
+}
@@ -0,0 +1,55 @@
+use crate::model::role::Role;
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+
+#[derive(FromRow, Debug, Clone)]
+pub struct User {
+  pub id: i32,
+  pub username: String,
+  pub password_hash: String,
+  pub created_at: NaiveDateTime,
+  pub role: String,
+}
+
+impl User {
+  pub fn get_id(&self) -> i32 {
+    self.id
+  }
+
+  pub fn get_username(&self) -> String {
+    self.username.to_string()
+  }
+
+  pub fn get_password_hash(&self) -> String {
+    self.password_hash.to_string()
+  }
+
+  pub fn get_created_at(&self) -> NaiveDateTime {
+    self.created_at
+  }
+
+  /// The role of the user, the least privileged if it is unknown.
+  pub fn get_role(&self) -> Role {
+    self.role.parse().unwrap_or_default()
+  }
+}
+
+/// A session opened by the login, its token is the value of the cookie.
+#[allow(dead_code)]
+#[derive(FromRow, Debug, Clone)]
+pub struct UserSession {
+  pub token: String,
+  pub user_id: i32,
+  pub created_at: NaiveDateTime,
+  pub expires_at: NaiveDateTime,
+}
+
+impl UserSession {
+  pub fn get_token(&self) -> String {
+    self.token.to_string()
+  }
+
+  pub fn get_expires_at(&self) -> NaiveDateTime {
+    self.expires_at
+  }
+}
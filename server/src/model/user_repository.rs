@@ -0,0 +1,260 @@
+use crate::{
+  db::database::DbConnection,
+  model::{
+    role::Role,
+    user::{User, UserSession},
+  },
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+
+#[async_trait]
+pub trait UserRepository {
+  async fn create_user(
+    &self,
+    username: String,
+    password_hash: String,
+  ) -> sqlx::Result<User>;
+  async fn user_by_username(
+    &self,
+    username: String,
+  ) -> sqlx::Result<Option<User>>;
+  async fn user_by_id(&self, id: i32) -> sqlx::Result<Option<User>>;
+  async fn list_users(&self) -> sqlx::Result<Vec<User>>;
+  async fn count_with_role(&self, role: Role) -> sqlx::Result<i64>;
+  async fn update_role(
+    &self,
+    id: i32,
+    role: Role,
+  ) -> sqlx::Result<Option<User>>;
+  async fn create_session(
+    &self,
+    token: String,
+    user_id: i32,
+    expires_at: NaiveDateTime,
+  ) -> sqlx::Result<UserSession>;
+  async fn user_of_session(
+    &self,
+    token: String,
+    now: NaiveDateTime,
+  ) -> sqlx::Result<Option<User>>;
+  async fn delete_session(&self, token: String) -> sqlx::Result<()>;
+}
+
+#[derive(Clone, Default)]
+pub struct DummyUserRepo;
+
+#[async_trait]
+impl UserRepository for DummyUserRepo {
+  async fn create_user(&self, _: String, _: String) -> sqlx::Result<User> {
+    todo!()
+  }
+
+  async fn user_by_username(&self, _: String) -> sqlx::Result<Option<User>> {
+    todo!()
+  }
+
+  async fn user_by_id(&self, _: i32) -> sqlx::Result<Option<User>> {
+    todo!()
+  }
+
+  async fn list_users(&self) -> sqlx::Result<Vec<User>> {
+    todo!()
+  }
+
+  async fn count_with_role(&self, _: Role) -> sqlx::Result<i64> {
+    todo!()
+  }
+
+  async fn update_role(&self, _: i32, _: Role) -> sqlx::Result<Option<User>> {
+    todo!()
+  }
+
+  async fn create_session(
+    &self,
+    _: String,
+    _: i32,
+    _: NaiveDateTime,
+  ) -> sqlx::Result<UserSession> {
+    todo!()
+  }
+
+  async fn user_of_session(
+    &self,
+    _: String,
+    _: NaiveDateTime,
+  ) -> sqlx::Result<Option<User>> {
+    todo!()
+  }
+
+  async fn delete_session(&self, _: String) -> sqlx::Result<()> {
+    todo!()
+  }
+}
+
+#[derive(Clone)]
+pub struct PgUserRepository {
+  connection: DbConnection,
+}
+
+impl PgUserRepository {
+  pub fn new(connection: DbConnection) -> Self {
+    PgUserRepository {
+      connection,
+    }
+  }
+}
+
+#[async_trait]
+impl UserRepository for PgUserRepository {
+  /// Every new user is a viewer.
+  async fn create_user(
+    &self,
+    username: String,
+    password_hash: String,
+  ) -> sqlx::Result<User> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      User,
+      r#"
+      INSERT INTO app_user (username, password_hash, role)
+      VALUES ($1, $2, $3)
+      RETURNING *
+      "#,
+      username,
+      password_hash,
+      Role::Viewer.as_str()
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  async fn user_by_username(
+    &self,
+    username: String,
+  ) -> sqlx::Result<Option<User>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      User,
+      r#"
+      SELECT * FROM app_user WHERE username = $1
+      "#,
+      username
+    )
+    .fetch_optional(conn)
+    .await
+  }
+
+  async fn user_by_id(&self, id: i32) -> sqlx::Result<Option<User>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      User,
+      r#"
+      SELECT * FROM app_user WHERE id = $1
+      "#,
+      id
+    )
+    .fetch_optional(conn)
+    .await
+  }
+
+  async fn list_users(&self) -> sqlx::Result<Vec<User>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      User,
+      r#"
+      SELECT * FROM app_user ORDER BY id
+      "#
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  async fn count_with_role(&self, role: Role) -> sqlx::Result<i64> {
+    let conn = self.connection.get();
+    let count = sqlx::query_scalar!(
+      r#"
+      SELECT COUNT(*) as "count!" FROM app_user WHERE role = $1
+      "#,
+      role.as_str()
+    )
+    .fetch_one(conn)
+    .await?;
+    Ok(count)
+  }
+
+  async fn update_role(
+    &self,
+    id: i32,
+    role: Role,
+  ) -> sqlx::Result<Option<User>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      User,
+      r#"
+      UPDATE app_user SET role = $2 WHERE id = $1
+      RETURNING *
+      "#,
+      id,
+      role.as_str()
+    )
+    .fetch_optional(conn)
+    .await
+  }
+
+  async fn create_session(
+    &self,
+    token: String,
+    user_id: i32,
+    expires_at: NaiveDateTime,
+  ) -> sqlx::Result<UserSession> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      UserSession,
+      r#"
+      INSERT INTO user_session (token, user_id, expires_at)
+      VALUES ($1, $2, $3)
+      RETURNING *
+      "#,
+      token,
+      user_id,
+      expires_at
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  /// Returns the user of the session if it didn't expire at the given moment.
+  async fn user_of_session(
+    &self,
+    token: String,
+    now: NaiveDateTime,
+  ) -> sqlx::Result<Option<User>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      User,
+      r#"
+      SELECT u.* FROM app_user u
+      INNER JOIN user_session s ON s.user_id = u.id
+      WHERE s.token = $1 AND s.expires_at > $2
+      "#,
+      token,
+      now
+    )
+    .fetch_optional(conn)
+    .await
+  }
+
+  async fn delete_session(&self, token: String) -> sqlx::Result<()> {
+    let conn = self.connection.get();
+    sqlx::query!(
+      r#"
+      DELETE FROM user_session WHERE token = $1
+      "#,
+      token
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+  }
+}
@@ -0,0 +1,130 @@
+use bigdecimal::BigDecimal;
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+use std::collections::HashSet;
+
+/// The source of the ratings given when checking in.
+pub const CHECK_IN_SOURCE: &str = "CHECK_IN";
+/// The longest name of a wishlist.
+pub const MAX_WISHLIST_NAME: usize = 100;
+/// The most attractions a wishlist can have.
+pub const MAX_WISHLIST_ATTRACTIONS: usize = 500;
+
+/// A named list of attractions of a user. It can be read by anyone with its
+/// share token, none if it isn't shared.
+#[derive(FromRow, Debug, Clone)]
+pub struct Wishlist {
+  pub id: i32,
+  pub user_id: i32,
+  pub name: String,
+  pub share_token: Option<String>,
+  pub created_at: NaiveDateTime,
+  pub updated_at: NaiveDateTime,
+}
+
+/// An attraction of a wishlist, the lists are sorted by the position.
+#[derive(FromRow, Debug, Clone)]
+pub struct WishlistAttraction {
+  pub attraction_id: i32,
+  pub description: String,
+  pub city_id: i32,
+  pub position: i32,
+  pub added_at: NaiveDateTime,
+}
+
+/// A visit of a user to an attraction, with the rating given in it.
+#[derive(FromRow, Debug, Clone)]
+pub struct CheckIn {
+  pub id: i32,
+  pub user_id: i32,
+  pub attraction_id: i32,
+  pub at: NaiveDateTime,
+  /// None if no rate was given, or if the rating was deleted.
+  pub rating_id: Option<i32>,
+  pub rate: Option<BigDecimal>,
+  pub rating_status: Option<String>,
+  pub created_at: NaiveDateTime,
+}
+
+/// A visit being registered, the rate is optional.
+#[derive(Debug, Clone)]
+pub struct NewCheckIn {
+  pub attraction_id: i32,
+  pub at: NaiveDateTime,
+  pub rate: Option<BigDecimal>,
+}
+
+/// The trimmed name, or an error if it's empty or too long.
+pub fn wishlist_name_of(name: String) -> Result<String, String> {
+  let name = name.trim().to_string();
+  if name.is_empty() || name.chars().count() > MAX_WISHLIST_NAME {
+    return Err(format!(
+      "The name of the wishlist must have between 1 and {MAX_WISHLIST_NAME} \
+       characters"
+    ));
+  }
+  Ok(name)
+}
+
+/// The attractions in the order given, without the repeated ones, or an
+/// error if there are too many.
+pub fn ordered_attraction_ids(
+  attraction_ids: Vec<i32>,
+) -> Result<Vec<i32>, String> {
+  let mut seen = HashSet::new();
+  let ordered = attraction_ids
+    .into_iter()
+    .filter(|an_id| seen.insert(*an_id))
+    .collect::<Vec<i32>>();
+  if ordered.len() > MAX_WISHLIST_ATTRACTIONS {
+    return Err(format!(
+      "A wishlist can't have more than {MAX_WISHLIST_ATTRACTIONS} attractions"
+    ));
+  }
+  Ok(ordered)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn the_names_of_the_wishlists_are_trimmed() {
+    assert_eq!(
+      wishlist_name_of(String::from("  Rome in May ")),
+      Ok(String::from("Rome in May"))
+    );
+    assert_eq!(
+      wishlist_name_of("á".repeat(MAX_WISHLIST_NAME))
+        .map(|a_name| a_name.len()),
+      Ok(2 * MAX_WISHLIST_NAME)
+    );
+  }
+
+  #[test]
+  fn the_blank_or_too_long_names_are_rejected() {
+    assert!(wishlist_name_of(String::from("   ")).is_err());
+    assert!(wishlist_name_of("a".repeat(MAX_WISHLIST_NAME + 1)).is_err());
+  }
+
+  #[test]
+  fn the_attractions_keep_their_order_without_the_repeated_ones() {
+    assert_eq!(
+      ordered_attraction_ids(vec![3, 1, 3, 2, 1]),
+      Ok(vec![3, 1, 2])
+    );
+    assert_eq!(ordered_attraction_ids(Vec::new()), Ok(Vec::new()));
+  }
+
+  #[test]
+  fn a_wishlist_cant_have_too_many_attractions() {
+    let most = (1..=MAX_WISHLIST_ATTRACTIONS as i32).collect::<Vec<i32>>();
+    assert!(ordered_attraction_ids(most.clone()).is_ok());
+    let mut repeated = most.clone();
+    repeated.push(1);
+    assert!(ordered_attraction_ids(repeated).is_ok());
+    let mut too_many = most;
+    too_many.push(0);
+    assert!(ordered_attraction_ids(too_many).is_err());
+  }
+}
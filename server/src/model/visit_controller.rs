@@ -0,0 +1,790 @@
+use crate::{
+  db::database::{is_foreign_key_violation, is_unique_violation},
+  model::{
+    attraction_controller::{rating_fail, reviewed_rating, RatingFail},
+    attraction_repository::AttractionRepository,
+    auth::random_token,
+    rating_rules::{IncomingRating, RatingRulesEngine, RatingRulesSettings},
+    visit::{
+      ordered_attraction_ids, wishlist_name_of, CheckIn, NewCheckIn, Wishlist,
+      WishlistAttraction, CHECK_IN_SOURCE, MAX_WISHLIST_ATTRACTIONS,
+    },
+    visit_repository::VisitRepository,
+  },
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use std::sync::Arc;
+
+/// The wishlists of the users and their visits to the attractions.
+#[async_trait]
+pub trait VisitController: Send + Sync + 'static {
+  async fn wishlists(&self, user_id: i32) -> Option<Vec<Wishlist>>;
+  async fn wishlist(
+    &self,
+    id: i32,
+    user_id: i32,
+  ) -> Option<(Wishlist, Vec<WishlistAttraction>)>;
+  async fn shared_wishlist(
+    &self,
+    share_token: String,
+  ) -> Option<(Wishlist, Vec<WishlistAttraction>)>;
+  async fn create_wishlist(
+    &self,
+    user_id: i32,
+    name: String,
+  ) -> Result<Wishlist, String>;
+  async fn rename_wishlist(
+    &self,
+    id: i32,
+    user_id: i32,
+    name: String,
+  ) -> Result<Option<Wishlist>, String>;
+  async fn delete_wishlist(&self, id: i32, user_id: i32) -> Option<bool>;
+  async fn share_wishlist(
+    &self,
+    id: i32,
+    user_id: i32,
+    shared: bool,
+  ) -> Option<Wishlist>;
+  async fn set_wishlist_attractions(
+    &self,
+    id: i32,
+    user_id: i32,
+    attraction_ids: Vec<i32>,
+  ) -> Result<Option<Vec<WishlistAttraction>>, String>;
+  async fn add_wishlist_attraction(
+    &self,
+    id: i32,
+    user_id: i32,
+    attraction_id: i32,
+  ) -> Result<Option<Vec<WishlistAttraction>>, String>;
+  async fn remove_wishlist_attraction(
+    &self,
+    id: i32,
+    user_id: i32,
+    attraction_id: i32,
+  ) -> Option<bool>;
+  async fn check_ins(
+    &self,
+    user_id: i32,
+    attraction_id: Option<i32>,
+  ) -> Option<Vec<CheckIn>>;
+  async fn check_in(&self, id: i32, user_id: i32) -> Option<CheckIn>;
+  async fn create_check_in(
+    &self,
+    user_id: i32,
+    username: String,
+    check_in: NewCheckIn,
+  ) -> Result<Option<CheckIn>, RatingFail>;
+  async fn delete_check_in(&self, id: i32, user_id: i32) -> Option<bool>;
+}
+
+#[derive(Clone)]
+pub struct VisitControllerImpl<VisitRepo, AttractionRepo> {
+  visit_repository: VisitRepo,
+  attraction_repository: AttractionRepo,
+  rules_engine: Arc<RatingRulesEngine>,
+}
+
+impl<VisitRepo, AttractionRepo> VisitControllerImpl<VisitRepo, AttractionRepo>
+where
+  VisitRepo: VisitRepository,
+  AttractionRepo: AttractionRepository,
+{
+  pub fn new(
+    visit_repository: VisitRepo,
+    attraction_repository: AttractionRepo,
+    rules_settings: RatingRulesSettings,
+  ) -> Self {
+    VisitControllerImpl {
+      visit_repository,
+      attraction_repository,
+      rules_engine: Arc::new(RatingRulesEngine::from_settings(&rules_settings)),
+    }
+  }
+
+  /// The wishlist with its attractions.
+  async fn with_attractions(
+    &self,
+    a_wishlist: Wishlist,
+  ) -> Option<(Wishlist, Vec<WishlistAttraction>)> {
+    let attractions = self
+      .visit_repository
+      .wishlist_attractions(a_wishlist.id)
+      .await
+      .ok()?;
+    Some((a_wishlist, attractions))
+  }
+
+  /// Whether the wishlist belongs to the user.
+  async fn owns(&self, id: i32, user_id: i32) -> Result<bool, String> {
+    match self.visit_repository.wishlist(id, user_id).await {
+      Ok(_) => Ok(true),
+      Err(sqlx::Error::RowNotFound) => Ok(false),
+      Err(e) => Err(e.to_string()),
+    }
+  }
+}
+
+#[async_trait]
+impl<VisitRepo, AttractionRepo> VisitController
+  for VisitControllerImpl<VisitRepo, AttractionRepo>
+where
+  VisitRepo: VisitRepository + Send + Sync + 'static,
+  AttractionRepo: AttractionRepository + Send + Sync + 'static,
+{
+  async fn wishlists(&self, user_id: i32) -> Option<Vec<Wishlist>> {
+    self.visit_repository.wishlists_of(user_id).await.ok()
+  }
+
+  /// Returns None if the wishlist doesn't exist or belongs to another user.
+  async fn wishlist(
+    &self,
+    id: i32,
+    user_id: i32,
+  ) -> Option<(Wishlist, Vec<WishlistAttraction>)> {
+    let a_wishlist = self.visit_repository.wishlist(id, user_id).await.ok()?;
+    self.with_attractions(a_wishlist).await
+  }
+
+  /// Returns None if no wishlist is shared with the token.
+  async fn shared_wishlist(
+    &self,
+    share_token: String,
+  ) -> Option<(Wishlist, Vec<WishlistAttraction>)> {
+    let a_wishlist = self
+      .visit_repository
+      .shared_wishlist(share_token)
+      .await
+      .ok()?;
+    self.with_attractions(a_wishlist).await
+  }
+
+  async fn create_wishlist(
+    &self,
+    user_id: i32,
+    name: String,
+  ) -> Result<Wishlist, String> {
+    let name = wishlist_name_of(name)?;
+    self
+      .visit_repository
+      .create_wishlist(user_id, name.clone())
+      .await
+      .map_err(|e| taken_name(&e, &name))
+  }
+
+  /// Returns None if the wishlist doesn't exist or belongs to another user.
+  async fn rename_wishlist(
+    &self,
+    id: i32,
+    user_id: i32,
+    name: String,
+  ) -> Result<Option<Wishlist>, String> {
+    let name = wishlist_name_of(name)?;
+    match self
+      .visit_repository
+      .rename_wishlist(id, user_id, name.clone())
+      .await
+    {
+      Ok(a_wishlist) => Ok(Some(a_wishlist)),
+      Err(sqlx::Error::RowNotFound) => Ok(None),
+      Err(e) => Err(taken_name(&e, &name)),
+    }
+  }
+
+  /// Returns false if the wishlist doesn't exist or belongs to another user.
+  async fn delete_wishlist(&self, id: i32, user_id: i32) -> Option<bool> {
+    self
+      .visit_repository
+      .delete_wishlist(id, user_id)
+      .await
+      .ok()
+      .map(|deleted| deleted > 0)
+  }
+
+  /// Share the wishlist with a new token, or keep the one it has. Not
+  /// sharing it any more drops the token, so the links given stop working.
+  /// Returns None if the wishlist doesn't exist or belongs to another user.
+  async fn share_wishlist(
+    &self,
+    id: i32,
+    user_id: i32,
+    shared: bool,
+  ) -> Option<Wishlist> {
+    if shared {
+      self
+        .visit_repository
+        .share_wishlist(id, user_id, random_token())
+        .await
+        .ok()
+    } else {
+      self
+        .visit_repository
+        .unshare_wishlist(id, user_id)
+        .await
+        .ok()
+    }
+  }
+
+  /// Replace the attractions of the wishlist, in the order given. Returns
+  /// None if the wishlist doesn't exist or belongs to another user, and an
+  /// error if any attraction doesn't exist.
+  async fn set_wishlist_attractions(
+    &self,
+    id: i32,
+    user_id: i32,
+    attraction_ids: Vec<i32>,
+  ) -> Result<Option<Vec<WishlistAttraction>>, String> {
+    let attraction_ids = ordered_attraction_ids(attraction_ids)?;
+    if !self.owns(id, user_id).await? {
+      return Ok(None);
+    }
+    let existing = self
+      .attraction_repository
+      .attractions_by_ids(attraction_ids.clone())
+      .await
+      .map_err(|e| e.to_string())?;
+    if existing.len() != attraction_ids.len() {
+      let missing = attraction_ids
+        .iter()
+        .filter(|an_id| {
+          !existing
+            .iter()
+            .any(|an_attraction| an_attraction.get_id() == **an_id)
+        })
+        .map(|an_id| an_id.to_string())
+        .collect::<Vec<String>>();
+      return Err(format!(
+        "The attractions {} don't exist",
+        missing.join(", ")
+      ));
+    }
+    self
+      .visit_repository
+      .replace_wishlist_attractions(id, attraction_ids)
+      .await
+      .map_err(|e| e.to_string())?;
+    self
+      .visit_repository
+      .wishlist_attractions(id)
+      .await
+      .map(Some)
+      .map_err(|e| e.to_string())
+  }
+
+  /// Add the attraction at the end of the wishlist, nothing changes if it
+  /// already has it. Returns None if the wishlist doesn't exist or belongs
+  /// to another user, and an error if the attraction doesn't exist.
+  async fn add_wishlist_attraction(
+    &self,
+    id: i32,
+    user_id: i32,
+    attraction_id: i32,
+  ) -> Result<Option<Vec<WishlistAttraction>>, String> {
+    if !self.owns(id, user_id).await? {
+      return Ok(None);
+    }
+    if self
+      .attraction_repository
+      .attraction_by_id(attraction_id)
+      .await
+      .is_err()
+    {
+      return Err(format!("The attraction {attraction_id} doesn't exist"));
+    }
+    let attractions = self
+      .visit_repository
+      .wishlist_attractions(id)
+      .await
+      .map_err(|e| e.to_string())?;
+    if attractions.len() >= MAX_WISHLIST_ATTRACTIONS
+      && attractions
+        .iter()
+        .all(|an_attraction| an_attraction.attraction_id != attraction_id)
+    {
+      return Err(format!(
+        "A wishlist can't have more than {MAX_WISHLIST_ATTRACTIONS} attractions"
+      ));
+    }
+    self
+      .visit_repository
+      .add_wishlist_attraction(id, attraction_id)
+      .await
+      .map_err(|e| {
+        if is_foreign_key_violation(&e) {
+          format!("The attraction {attraction_id} doesn't exist")
+        } else {
+          e.to_string()
+        }
+      })?;
+    self
+      .visit_repository
+      .wishlist_attractions(id)
+      .await
+      .map(Some)
+      .map_err(|e| e.to_string())
+  }
+
+  /// Returns false if the wishlist doesn't exist, belongs to another user or
+  /// doesn't have the attraction.
+  async fn remove_wishlist_attraction(
+    &self,
+    id: i32,
+    user_id: i32,
+    attraction_id: i32,
+  ) -> Option<bool> {
+    if !self.owns(id, user_id).await.ok()? {
+      return Some(false);
+    }
+    self
+      .visit_repository
+      .remove_wishlist_attraction(id, attraction_id)
+      .await
+      .ok()
+      .map(|removed| removed > 0)
+  }
+
+  async fn check_ins(
+    &self,
+    user_id: i32,
+    attraction_id: Option<i32>,
+  ) -> Option<Vec<CheckIn>> {
+    self
+      .visit_repository
+      .check_ins_of(user_id, attraction_id)
+      .await
+      .ok()
+  }
+
+  /// Returns None if the check-in doesn't exist or belongs to another user.
+  async fn check_in(&self, id: i32, user_id: i32) -> Option<CheckIn> {
+    self.visit_repository.check_in(id, user_id).await.ok()
+  }
+
+  /// Register the visit of the user. The rate given is registered as a
+  /// rating of the user at the moment, reviewed by the fraud rules like any
+  /// other.
+  ///
+  /// # Return:
+  /// * Ok with None if the attraction doesn't exist.
+  /// * Err with a duplicate if the visit has a rate and the user already
+  ///   rated the attraction that day, or the failure if it can't be saved.
+  async fn create_check_in(
+    &self,
+    user_id: i32,
+    username: String,
+    check_in: NewCheckIn,
+  ) -> Result<Option<CheckIn>, RatingFail> {
+    match self
+      .attraction_repository
+      .attraction_by_id(check_in.attraction_id)
+      .await
+    {
+      Ok(_) => {},
+      Err(sqlx::Error::RowNotFound) => return Ok(None),
+      Err(e) => return Err(RatingFail::Service(e.to_string())),
+    }
+    let rating = match check_in.rate {
+      Some(rate) => {
+        let incoming_rating = IncomingRating {
+          attraction_id: check_in.attraction_id,
+          rate,
+          at: Utc::now().naive_utc(),
+          source: String::from(CHECK_IN_SOURCE),
+          submitter: Some(username),
+          fingerprint: None,
+          user_id: Some(user_id),
+        };
+        let Some(a_rating) = reviewed_rating(
+          &self.attraction_repository,
+          &self.rules_engine,
+          incoming_rating,
+        )
+        .await?
+        else {
+          return Ok(None);
+        };
+        Some(a_rating)
+      },
+      None => None,
+    };
+    match self
+      .visit_repository
+      .save_check_in(
+        user_id,
+        check_in.attraction_id,
+        check_in.at,
+        rating.clone(),
+      )
+      .await
+    {
+      Ok(a_check_in) => Ok(Some(a_check_in)),
+      Err(e) if is_foreign_key_violation(&e) => Ok(None),
+      Err(e) => Err(match &rating {
+        Some(a_rating) => rating_fail(&e, a_rating),
+        None => RatingFail::Service(e.to_string()),
+      }),
+    }
+  }
+
+  /// Delete the check-in and the rating given in it, so the statistics of
+  /// its period are calculated again. Returns false if the check-in doesn't
+  /// exist or belongs to another user.
+  async fn delete_check_in(&self, id: i32, user_id: i32) -> Option<bool> {
+    self
+      .visit_repository
+      .delete_check_in(id, user_id)
+      .await
+      .ok()
+      .map(|deleted| deleted > 0)
+  }
+}
+
+fn taken_name(e: &sqlx::Error, name: &str) -> String {
+  if is_unique_violation(e) {
+    format!("The user already has a wishlist named {name}")
+  } else {
+    e.to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::model::{
+    attraction::AttractionRating, attraction_repository::DummyAttractionRepo,
+  };
+  use chrono::NaiveDateTime;
+  use std::sync::Mutex;
+
+  /// The wishlists and the check-ins in memory, the attractions of the
+  /// wishlists are only their ids.
+  #[derive(Default)]
+  struct InMemoryVisitRepo {
+    wishlists: Mutex<Vec<Wishlist>>,
+    wishlist_attractions: Mutex<Vec<(i32, i32)>>,
+    check_ins: Mutex<Vec<CheckIn>>,
+  }
+
+  impl InMemoryVisitRepo {
+    fn update_wishlist(
+      &self,
+      id: i32,
+      user_id: i32,
+      update: impl FnOnce(&mut Wishlist),
+    ) -> sqlx::Result<Wishlist> {
+      let mut wishlists = self.wishlists.lock().unwrap();
+      let a_wishlist = wishlists
+        .iter_mut()
+        .find(|a_wishlist| a_wishlist.id == id && a_wishlist.user_id == user_id)
+        .ok_or(sqlx::Error::RowNotFound)?;
+      update(a_wishlist);
+      Ok(a_wishlist.clone())
+    }
+  }
+
+  #[async_trait]
+  impl VisitRepository for InMemoryVisitRepo {
+    async fn wishlists_of(&self, user_id: i32) -> sqlx::Result<Vec<Wishlist>> {
+      let wishlists = self.wishlists.lock().unwrap();
+      Ok(
+        wishlists
+          .iter()
+          .filter(|a_wishlist| a_wishlist.user_id == user_id)
+          .cloned()
+          .collect(),
+      )
+    }
+
+    async fn wishlist(&self, id: i32, user_id: i32) -> sqlx::Result<Wishlist> {
+      self.update_wishlist(id, user_id, |_| {})
+    }
+
+    async fn shared_wishlist(
+      &self,
+      share_token: String,
+    ) -> sqlx::Result<Wishlist> {
+      let wishlists = self.wishlists.lock().unwrap();
+      wishlists
+        .iter()
+        .find(|a_wishlist| {
+          a_wishlist.share_token.as_ref() == Some(&share_token)
+        })
+        .cloned()
+        .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn create_wishlist(
+      &self,
+      user_id: i32,
+      name: String,
+    ) -> sqlx::Result<Wishlist> {
+      let mut wishlists = self.wishlists.lock().unwrap();
+      let created = Wishlist {
+        id: wishlists.len() as i32 + 1,
+        user_id,
+        name,
+        share_token: None,
+        created_at: NaiveDateTime::default(),
+        updated_at: NaiveDateTime::default(),
+      };
+      wishlists.push(created.clone());
+      Ok(created)
+    }
+
+    async fn rename_wishlist(
+      &self,
+      id: i32,
+      user_id: i32,
+      name: String,
+    ) -> sqlx::Result<Wishlist> {
+      self.update_wishlist(id, user_id, |a_wishlist| a_wishlist.name = name)
+    }
+
+    async fn delete_wishlist(
+      &self,
+      id: i32,
+      user_id: i32,
+    ) -> sqlx::Result<u64> {
+      let mut wishlists = self.wishlists.lock().unwrap();
+      let before = wishlists.len();
+      wishlists.retain(|a_wishlist| {
+        a_wishlist.id != id || a_wishlist.user_id != user_id
+      });
+      Ok((before - wishlists.len()) as u64)
+    }
+
+    async fn share_wishlist(
+      &self,
+      id: i32,
+      user_id: i32,
+      share_token: String,
+    ) -> sqlx::Result<Wishlist> {
+      self.update_wishlist(id, user_id, |a_wishlist| {
+        a_wishlist.share_token.get_or_insert(share_token);
+      })
+    }
+
+    async fn unshare_wishlist(
+      &self,
+      id: i32,
+      user_id: i32,
+    ) -> sqlx::Result<Wishlist> {
+      self.update_wishlist(id, user_id, |a_wishlist| {
+        a_wishlist.share_token = None
+      })
+    }
+
+    async fn wishlist_attractions(
+      &self,
+      wishlist_id: i32,
+    ) -> sqlx::Result<Vec<WishlistAttraction>> {
+      let wishlist_attractions = self.wishlist_attractions.lock().unwrap();
+      Ok(
+        wishlist_attractions
+          .iter()
+          .filter(|(an_id, _)| *an_id == wishlist_id)
+          .enumerate()
+          .map(|(position, (_, attraction_id))| WishlistAttraction {
+            attraction_id: *attraction_id,
+            description: format!("Attraction {attraction_id}"),
+            city_id: 1,
+            position: position as i32,
+            added_at: NaiveDateTime::default(),
+          })
+          .collect(),
+      )
+    }
+
+    async fn replace_wishlist_attractions(
+      &self,
+      _: i32,
+      _: Vec<i32>,
+    ) -> sqlx::Result<()> {
+      todo!()
+    }
+
+    async fn add_wishlist_attraction(
+      &self,
+      _: i32,
+      _: i32,
+    ) -> sqlx::Result<u64> {
+      todo!()
+    }
+
+    async fn remove_wishlist_attraction(
+      &self,
+      wishlist_id: i32,
+      attraction_id: i32,
+    ) -> sqlx::Result<u64> {
+      let mut wishlist_attractions = self.wishlist_attractions.lock().unwrap();
+      let before = wishlist_attractions.len();
+      wishlist_attractions
+        .retain(|a_pair| *a_pair != (wishlist_id, attraction_id));
+      Ok((before - wishlist_attractions.len()) as u64)
+    }
+
+    async fn check_ins_of(
+      &self,
+      _: i32,
+      _: Option<i32>,
+    ) -> sqlx::Result<Vec<CheckIn>> {
+      todo!()
+    }
+
+    async fn check_in(&self, id: i32, user_id: i32) -> sqlx::Result<CheckIn> {
+      let check_ins = self.check_ins.lock().unwrap();
+      check_ins
+        .iter()
+        .find(|a_check_in| a_check_in.id == id && a_check_in.user_id == user_id)
+        .cloned()
+        .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn save_check_in(
+      &self,
+      _: i32,
+      _: i32,
+      _: NaiveDateTime,
+      _: Option<AttractionRating>,
+    ) -> sqlx::Result<CheckIn> {
+      todo!()
+    }
+
+    async fn delete_check_in(
+      &self,
+      id: i32,
+      user_id: i32,
+    ) -> sqlx::Result<u64> {
+      let mut check_ins = self.check_ins.lock().unwrap();
+      let before = check_ins.len();
+      check_ins.retain(|a_check_in| {
+        a_check_in.id != id || a_check_in.user_id != user_id
+      });
+      Ok((before - check_ins.len()) as u64)
+    }
+  }
+
+  /// The attractions aren't read by the wishlists nor by the check-ins
+  /// already made.
+  fn controller(
+    visit_repository: InMemoryVisitRepo,
+  ) -> VisitControllerImpl<InMemoryVisitRepo, DummyAttractionRepo> {
+    VisitControllerImpl::new(
+      visit_repository,
+      DummyAttractionRepo,
+      RatingRulesSettings::default(),
+    )
+  }
+
+  #[tokio::test]
+  async fn a_wishlist_is_created_with_its_name_trimmed() {
+    let controller = controller(InMemoryVisitRepo::default());
+    let created = controller
+      .create_wishlist(1, String::from(" Rome in May "))
+      .await
+      .unwrap();
+    assert_eq!(created.name, "Rome in May");
+    assert!(controller
+      .create_wishlist(1, String::from("  "))
+      .await
+      .is_err());
+    assert_eq!(controller.wishlists(1).await.map(|all| all.len()), Some(1));
+  }
+
+  #[tokio::test]
+  async fn another_user_cant_change_the_wishlist() {
+    let controller = controller(InMemoryVisitRepo::default());
+    let created = controller
+      .create_wishlist(1, String::from("Rome"))
+      .await
+      .unwrap();
+    let renamed = controller
+      .rename_wishlist(created.id, 2, String::from("Paris"))
+      .await;
+    assert!(matches!(renamed, Ok(None)));
+    assert!(controller.wishlist(created.id, 2).await.is_none());
+    assert_eq!(controller.delete_wishlist(created.id, 2).await, Some(false));
+    assert_eq!(controller.delete_wishlist(created.id, 1).await, Some(true));
+  }
+
+  #[tokio::test]
+  async fn a_shared_wishlist_keeps_its_token_until_it_isnt_shared() {
+    let controller = controller(InMemoryVisitRepo::default());
+    let created = controller
+      .create_wishlist(1, String::from("Rome"))
+      .await
+      .unwrap();
+    let shared = controller
+      .share_wishlist(created.id, 1, true)
+      .await
+      .unwrap();
+    let token = shared.share_token.clone().unwrap();
+    let shared_again = controller.share_wishlist(created.id, 1, true).await;
+    assert_eq!(
+      shared_again.and_then(|it| it.share_token),
+      Some(token.clone())
+    );
+    assert!(controller.shared_wishlist(token.clone()).await.is_some());
+
+    controller
+      .share_wishlist(created.id, 1, false)
+      .await
+      .unwrap();
+    assert!(controller.shared_wishlist(token).await.is_none());
+  }
+
+  #[tokio::test]
+  async fn only_the_owner_removes_the_attractions_of_the_wishlist() {
+    let visit_repository = InMemoryVisitRepo::default();
+    *visit_repository.wishlist_attractions.lock().unwrap() =
+      vec![(1, 10), (1, 20)];
+    let controller = controller(visit_repository);
+    controller
+      .create_wishlist(1, String::from("Rome"))
+      .await
+      .unwrap();
+    assert_eq!(
+      controller.remove_wishlist_attraction(1, 2, 10).await,
+      Some(false)
+    );
+    assert_eq!(
+      controller.remove_wishlist_attraction(1, 1, 10).await,
+      Some(true)
+    );
+    assert_eq!(
+      controller.remove_wishlist_attraction(1, 1, 10).await,
+      Some(false)
+    );
+    let (_, attractions) = controller.wishlist(1, 1).await.unwrap();
+    assert_eq!(
+      attractions
+        .iter()
+        .map(|an_attraction| an_attraction.attraction_id)
+        .collect::<Vec<i32>>(),
+      vec![20]
+    );
+  }
+
+  #[tokio::test]
+  async fn only_the_visitor_reads_and_deletes_the_check_in() {
+    let visit_repository = InMemoryVisitRepo::default();
+    *visit_repository.check_ins.lock().unwrap() = vec![CheckIn {
+      id: 1,
+      user_id: 1,
+      attraction_id: 10,
+      at: NaiveDateTime::default(),
+      rating_id: None,
+      rate: None,
+      rating_status: None,
+      created_at: NaiveDateTime::default(),
+    }];
+    let controller = controller(visit_repository);
+    assert!(controller.check_in(1, 2).await.is_none());
+    assert_eq!(controller.delete_check_in(1, 2).await, Some(false));
+    assert!(controller.check_in(1, 1).await.is_some());
+    assert_eq!(controller.delete_check_in(1, 1).await, Some(true));
+    assert!(controller.check_in(1, 1).await.is_none());
+  }
+}
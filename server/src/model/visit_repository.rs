@@ -0,0 +1,605 @@
+use crate::{
+  db::database::DbConnection,
+  model::{
+    attraction::AttractionRating,
+    attraction_repository::EntityId,
+    visit::{CheckIn, Wishlist, WishlistAttraction},
+  },
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+
+#[async_trait]
+pub trait VisitRepository {
+  async fn wishlists_of(&self, user_id: i32) -> sqlx::Result<Vec<Wishlist>>;
+  async fn wishlist(&self, id: i32, user_id: i32) -> sqlx::Result<Wishlist>;
+  async fn shared_wishlist(
+    &self,
+    share_token: String,
+  ) -> sqlx::Result<Wishlist>;
+  async fn create_wishlist(
+    &self,
+    user_id: i32,
+    name: String,
+  ) -> sqlx::Result<Wishlist>;
+  async fn rename_wishlist(
+    &self,
+    id: i32,
+    user_id: i32,
+    name: String,
+  ) -> sqlx::Result<Wishlist>;
+  async fn delete_wishlist(&self, id: i32, user_id: i32) -> sqlx::Result<u64>;
+  async fn share_wishlist(
+    &self,
+    id: i32,
+    user_id: i32,
+    share_token: String,
+  ) -> sqlx::Result<Wishlist>;
+  async fn unshare_wishlist(
+    &self,
+    id: i32,
+    user_id: i32,
+  ) -> sqlx::Result<Wishlist>;
+  async fn wishlist_attractions(
+    &self,
+    wishlist_id: i32,
+  ) -> sqlx::Result<Vec<WishlistAttraction>>;
+  async fn replace_wishlist_attractions(
+    &self,
+    wishlist_id: i32,
+    attraction_ids: Vec<i32>,
+  ) -> sqlx::Result<()>;
+  async fn add_wishlist_attraction(
+    &self,
+    wishlist_id: i32,
+    attraction_id: i32,
+  ) -> sqlx::Result<u64>;
+  async fn remove_wishlist_attraction(
+    &self,
+    wishlist_id: i32,
+    attraction_id: i32,
+  ) -> sqlx::Result<u64>;
+  async fn check_ins_of(
+    &self,
+    user_id: i32,
+    attraction_id: Option<i32>,
+  ) -> sqlx::Result<Vec<CheckIn>>;
+  async fn check_in(&self, id: i32, user_id: i32) -> sqlx::Result<CheckIn>;
+  async fn save_check_in(
+    &self,
+    user_id: i32,
+    attraction_id: i32,
+    at: NaiveDateTime,
+    rating: Option<AttractionRating>,
+  ) -> sqlx::Result<CheckIn>;
+  async fn delete_check_in(&self, id: i32, user_id: i32) -> sqlx::Result<u64>;
+}
+
+#[derive(Clone, Default)]
+pub struct DummyVisitRepo;
+
+#[async_trait]
+impl VisitRepository for DummyVisitRepo {
+  async fn wishlists_of(&self, _: i32) -> sqlx::Result<Vec<Wishlist>> {
+    todo!()
+  }
+
+  async fn wishlist(&self, _: i32, _: i32) -> sqlx::Result<Wishlist> {
+    todo!()
+  }
+
+  async fn shared_wishlist(&self, _: String) -> sqlx::Result<Wishlist> {
+    todo!()
+  }
+
+  async fn create_wishlist(&self, _: i32, _: String) -> sqlx::Result<Wishlist> {
+    todo!()
+  }
+
+  async fn rename_wishlist(
+    &self,
+    _: i32,
+    _: i32,
+    _: String,
+  ) -> sqlx::Result<Wishlist> {
+    todo!()
+  }
+
+  async fn delete_wishlist(&self, _: i32, _: i32) -> sqlx::Result<u64> {
+    todo!()
+  }
+
+  async fn share_wishlist(
+    &self,
+    _: i32,
+    _: i32,
+    _: String,
+  ) -> sqlx::Result<Wishlist> {
+    todo!()
+  }
+
+  async fn unshare_wishlist(&self, _: i32, _: i32) -> sqlx::Result<Wishlist> {
+    todo!()
+  }
+
+  async fn wishlist_attractions(
+    &self,
+    _: i32,
+  ) -> sqlx::Result<Vec<WishlistAttraction>> {
+    todo!()
+  }
+
+  async fn replace_wishlist_attractions(
+    &self,
+    _: i32,
+    _: Vec<i32>,
+  ) -> sqlx::Result<()> {
+    todo!()
+  }
+
+  async fn add_wishlist_attraction(&self, _: i32, _: i32) -> sqlx::Result<u64> {
+    todo!()
+  }
+
+  async fn remove_wishlist_attraction(
+    &self,
+    _: i32,
+    _: i32,
+  ) -> sqlx::Result<u64> {
+    todo!()
+  }
+
+  async fn check_ins_of(
+    &self,
+    _: i32,
+    _: Option<i32>,
+  ) -> sqlx::Result<Vec<CheckIn>> {
+    todo!()
+  }
+
+  async fn check_in(&self, _: i32, _: i32) -> sqlx::Result<CheckIn> {
+    todo!()
+  }
+
+  async fn save_check_in(
+    &self,
+    _: i32,
+    _: i32,
+    _: NaiveDateTime,
+    _: Option<AttractionRating>,
+  ) -> sqlx::Result<CheckIn> {
+    todo!()
+  }
+
+  async fn delete_check_in(&self, _: i32, _: i32) -> sqlx::Result<u64> {
+    todo!()
+  }
+}
+
+#[derive(Clone)]
+pub struct PgVisitRepository {
+  connection: DbConnection,
+}
+
+impl PgVisitRepository {
+  pub fn new(connection: DbConnection) -> Self {
+    PgVisitRepository {
+      connection,
+    }
+  }
+}
+
+#[async_trait]
+impl VisitRepository for PgVisitRepository {
+  /// Returns the wishlists of the user sorted by name.
+  async fn wishlists_of(&self, user_id: i32) -> sqlx::Result<Vec<Wishlist>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Wishlist,
+      r#"
+      SELECT * FROM wishlist WHERE user_id = $1
+      ORDER BY name
+      "#,
+      user_id
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// It fails with a row not found if the wishlist doesn't exist or belongs
+  /// to another user.
+  async fn wishlist(&self, id: i32, user_id: i32) -> sqlx::Result<Wishlist> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Wishlist,
+      r#"
+      SELECT * FROM wishlist WHERE id = $1 AND user_id = $2
+      "#,
+      id,
+      user_id
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  async fn shared_wishlist(
+    &self,
+    share_token: String,
+  ) -> sqlx::Result<Wishlist> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Wishlist,
+      r#"
+      SELECT * FROM wishlist WHERE share_token = $1
+      "#,
+      share_token
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  /// It fails with a unique violation if the user has a wishlist with the
+  /// same name.
+  async fn create_wishlist(
+    &self,
+    user_id: i32,
+    name: String,
+  ) -> sqlx::Result<Wishlist> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Wishlist,
+      r#"
+      INSERT INTO wishlist (user_id, name) VALUES ($1, $2)
+      returning *
+      "#,
+      user_id,
+      name
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  async fn rename_wishlist(
+    &self,
+    id: i32,
+    user_id: i32,
+    name: String,
+  ) -> sqlx::Result<Wishlist> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Wishlist,
+      r#"
+      UPDATE wishlist SET name = $3, updated_at = now()
+      WHERE id = $1 AND user_id = $2
+      returning *
+      "#,
+      id,
+      user_id,
+      name
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  /// Returns the number of deleted wishlists, their attractions go with
+  /// them.
+  async fn delete_wishlist(&self, id: i32, user_id: i32) -> sqlx::Result<u64> {
+    let conn = self.connection.get();
+    sqlx::query!(
+      r#"
+      DELETE FROM wishlist WHERE id = $1 AND user_id = $2
+      "#,
+      id,
+      user_id
+    )
+    .execute(conn)
+    .await
+    .map(|result| result.rows_affected())
+  }
+
+  /// A wishlist already shared keeps its token, so the links given before
+  /// still work.
+  async fn share_wishlist(
+    &self,
+    id: i32,
+    user_id: i32,
+    share_token: String,
+  ) -> sqlx::Result<Wishlist> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Wishlist,
+      r#"
+      UPDATE wishlist SET share_token = coalesce(share_token, $3)
+      WHERE id = $1 AND user_id = $2
+      returning *
+      "#,
+      id,
+      user_id,
+      share_token
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  async fn unshare_wishlist(
+    &self,
+    id: i32,
+    user_id: i32,
+  ) -> sqlx::Result<Wishlist> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      Wishlist,
+      r#"
+      UPDATE wishlist SET share_token = NULL
+      WHERE id = $1 AND user_id = $2
+      returning *
+      "#,
+      id,
+      user_id
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  /// Returns the attractions of the wishlist in their order, the deleted
+  /// ones are left out.
+  async fn wishlist_attractions(
+    &self,
+    wishlist_id: i32,
+  ) -> sqlx::Result<Vec<WishlistAttraction>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      WishlistAttraction,
+      r#"
+      SELECT a.id as attraction_id, a.description, a.city_id, wa.position,
+      wa.added_at
+      FROM wishlist_attraction wa
+      INNER JOIN attraction a ON a.id = wa.attraction_id
+      WHERE wa.wishlist_id = $1 AND a.deleted_at IS NULL
+      ORDER BY wa.position, a.id
+      "#,
+      wishlist_id
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// Replace the attractions of the wishlist, in a single transaction. The
+  /// attractions it already had keep when they were added.
+  async fn replace_wishlist_attractions(
+    &self,
+    wishlist_id: i32,
+    attraction_ids: Vec<i32>,
+  ) -> sqlx::Result<()> {
+    let mut transaction = self.connection.get().begin().await?;
+    sqlx::query!(
+      r#"
+      DELETE FROM wishlist_attraction
+      WHERE wishlist_id = $1 AND attraction_id <> ALL($2)
+      "#,
+      wishlist_id,
+      &attraction_ids
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+      r#"
+      INSERT INTO wishlist_attraction (wishlist_id, attraction_id, position)
+      SELECT $1, ids.attraction_id, ids.position
+      FROM unnest($2::integer[]) WITH ORDINALITY ids(attraction_id, position)
+      ON CONFLICT (wishlist_id, attraction_id)
+      DO UPDATE SET position = excluded.position
+      "#,
+      wishlist_id,
+      &attraction_ids
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+      r#"
+      UPDATE wishlist SET updated_at = now() WHERE id = $1
+      "#,
+      wishlist_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await
+  }
+
+  /// Add the attraction at the end of the wishlist. Returns the number of
+  /// added attractions, none if the wishlist already had it.
+  async fn add_wishlist_attraction(
+    &self,
+    wishlist_id: i32,
+    attraction_id: i32,
+  ) -> sqlx::Result<u64> {
+    let mut transaction = self.connection.get().begin().await?;
+    let added = sqlx::query!(
+      r#"
+      INSERT INTO wishlist_attraction (wishlist_id, attraction_id, position)
+      SELECT $1, $2, coalesce(max(position), 0) + 1
+      FROM wishlist_attraction WHERE wishlist_id = $1
+      ON CONFLICT DO NOTHING
+      "#,
+      wishlist_id,
+      attraction_id
+    )
+    .execute(&mut *transaction)
+    .await?
+    .rows_affected();
+    sqlx::query!(
+      r#"
+      UPDATE wishlist SET updated_at = now() WHERE id = $1
+      "#,
+      wishlist_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(added)
+  }
+
+  async fn remove_wishlist_attraction(
+    &self,
+    wishlist_id: i32,
+    attraction_id: i32,
+  ) -> sqlx::Result<u64> {
+    let conn = self.connection.get();
+    sqlx::query!(
+      r#"
+      DELETE FROM wishlist_attraction
+      WHERE wishlist_id = $1 AND attraction_id = $2
+      "#,
+      wishlist_id,
+      attraction_id
+    )
+    .execute(conn)
+    .await
+    .map(|result| result.rows_affected())
+  }
+
+  /// Returns the check-ins of the user, the latest first. The ones of the
+  /// deleted attractions are left out.
+  async fn check_ins_of(
+    &self,
+    user_id: i32,
+    attraction_id: Option<i32>,
+  ) -> sqlx::Result<Vec<CheckIn>> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      CheckIn,
+      r#"
+      SELECT ci.id, ci.user_id, ci.attraction_id, ci.at, ci.rating_id,
+      ar.rate as "rate?", ar.status as "rating_status?", ci.created_at
+      FROM check_in ci
+      INNER JOIN attraction a ON a.id = ci.attraction_id
+      LEFT JOIN attraction_rating ar ON ar.id = ci.rating_id
+      WHERE ci.user_id = $1 AND a.deleted_at IS NULL
+      AND ($2::integer IS NULL OR ci.attraction_id = $2)
+      ORDER BY ci.at DESC, ci.id DESC
+      "#,
+      user_id,
+      attraction_id
+    )
+    .fetch_all(conn)
+    .await
+  }
+
+  /// It fails with a row not found if the check-in doesn't exist, belongs to
+  /// another user or its attraction is deleted.
+  async fn check_in(&self, id: i32, user_id: i32) -> sqlx::Result<CheckIn> {
+    let conn = self.connection.get();
+    sqlx::query_as!(
+      CheckIn,
+      r#"
+      SELECT ci.id, ci.user_id, ci.attraction_id, ci.at, ci.rating_id,
+      ar.rate as "rate?", ar.status as "rating_status?", ci.created_at
+      FROM check_in ci
+      INNER JOIN attraction a ON a.id = ci.attraction_id
+      LEFT JOIN attraction_rating ar ON ar.id = ci.rating_id
+      WHERE ci.id = $1 AND ci.user_id = $2 AND a.deleted_at IS NULL
+      "#,
+      id,
+      user_id
+    )
+    .fetch_one(conn)
+    .await
+  }
+
+  /// Save the check-in and the rating given in it, both or none.
+  async fn save_check_in(
+    &self,
+    user_id: i32,
+    attraction_id: i32,
+    at: NaiveDateTime,
+    rating: Option<AttractionRating>,
+  ) -> sqlx::Result<CheckIn> {
+    let mut transaction = self.connection.get().begin().await?;
+    let rating_id = match rating {
+      Some(a_rating) => Some(
+        sqlx::query_as!(
+          EntityId,
+          r#"
+          INSERT INTO attraction_rating
+          (at, attraction_id, rate, source, submitter, fingerprint, status,
+          fraud_score, fraud_reasons, user_id)
+          VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+          returning id
+          "#,
+          a_rating.get_at(),
+          a_rating.get_attraction_id(),
+          a_rating.get_rate(),
+          a_rating.get_source(),
+          a_rating.get_submitter(),
+          a_rating.get_fingerprint(),
+          a_rating.get_status(),
+          a_rating.get_fraud_score(),
+          &a_rating.get_fraud_reasons(),
+          a_rating.get_user_id()
+        )
+        .fetch_one(&mut *transaction)
+        .await?
+        .id,
+      ),
+      None => None,
+    };
+    let a_check_in = sqlx::query_as!(
+      CheckIn,
+      r#"
+      WITH saved AS (
+        INSERT INTO check_in (user_id, attraction_id, at, rating_id)
+        VALUES ($1, $2, $3, $4)
+        returning *
+      )
+      SELECT s.id as "id!", s.user_id as "user_id!",
+      s.attraction_id as "attraction_id!", s.at as "at!", s.rating_id,
+      ar.rate as "rate?", ar.status as "rating_status?",
+      s.created_at as "created_at!"
+      FROM saved s
+      LEFT JOIN attraction_rating ar ON ar.id = s.rating_id
+      "#,
+      user_id,
+      attraction_id,
+      at,
+      rating_id
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(a_check_in)
+  }
+
+  /// Delete the check-in and the rating given in it, both or none. Returns
+  /// the number of deleted check-ins.
+  async fn delete_check_in(&self, id: i32, user_id: i32) -> sqlx::Result<u64> {
+    let mut transaction = self.connection.get().begin().await?;
+    let deleted = sqlx::query!(
+      r#"
+      DELETE FROM check_in ci USING attraction a
+      WHERE ci.id = $1 AND ci.user_id = $2
+      AND a.id = ci.attraction_id AND a.deleted_at IS NULL
+      returning ci.rating_id
+      "#,
+      id,
+      user_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+    let Some(a_check_in) = deleted else {
+      return Ok(0);
+    };
+    if let Some(rating_id) = a_check_in.rating_id {
+      sqlx::query!(
+        r#"
+        DELETE FROM attraction_rating WHERE id = $1 AND user_id = $2
+        "#,
+        rating_id,
+        user_id
+      )
+      .execute(&mut *transaction)
+      .await?;
+    }
+    transaction.commit().await?;
+    Ok(1)
+  }
+}